@@ -79,6 +79,8 @@ pub const PAGE_SHIFT: usize = 12;
 
 pub use platform::init_basics;
 pub use platform::Cpu;
+#[cfg(target_arch = "x86_64")]
+pub use platform::{cpu_features, CpuFeatures};
 pub use traits::cpu::CpuTrait;
 
 // =============================================================================
@@ -0,0 +1,80 @@
+//! Arquivo: x86_64/acpi/hpet.rs
+//!
+//! Propósito: Parsing da High Precision Event Timer Table (HPET).
+//! A tabela informa onde está o bloco de registradores do HPET; o driver
+//! (`drivers::timer::hpet`) lê o resto (período, comparadores) do próprio
+//! bloco.
+//!
+//! Layout após o header SDT (36 bytes):
+//! - +0: Event Timer Block ID.
+//! - +4: Generic Address Structure (12 bytes) com o endereço do bloco.
+//! - +16: Número do HPET.
+//! - +17: Tick mínimo em modo periódico.
+//! - +19: Proteção de página.
+
+/// Espaço de endereçamento "memória" da Generic Address Structure
+const GAS_SYSTEM_MEMORY: u8 = 0;
+
+/// Dados da tabela HPET
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HpetInfo {
+    /// Endereço físico do bloco de registradores
+    pub address: u64,
+    /// Número do bloco (mais de um HPET no sistema)
+    pub number: u8,
+    /// Menor período aceito em modo periódico, em ticks do contador
+    pub min_tick: u16,
+}
+
+/// Lê uma tabela HPET completa; `None` se truncada ou se o bloco não está
+/// no espaço de memória
+pub fn parse(table: &[u8]) -> Option<HpetInfo> {
+    let body = table.get(super::SDT_HEADER_LEN..super::SDT_HEADER_LEN + 20)?;
+    let gas = &body[4..16];
+    if gas[0] != GAS_SYSTEM_MEMORY {
+        return None;
+    }
+    let address = u64::from_le_bytes(gas[4..12].try_into().ok()?);
+    if address == 0 {
+        return None;
+    }
+    Some(HpetInfo {
+        address,
+        number: body[16],
+        min_tick: u16::from_le_bytes([body[17], body[18]]),
+    })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse() {
+        let mut table = vec![0u8; super::super::SDT_HEADER_LEN + 20];
+        let body = &mut table[super::super::SDT_HEADER_LEN..];
+        body[8..16].copy_from_slice(&0xFED0_0000u64.to_le_bytes());
+        body[17..19].copy_from_slice(&128u16.to_le_bytes());
+        assert_eq!(
+            parse(&table),
+            Some(HpetInfo {
+                address: 0xFED0_0000,
+                number: 0,
+                min_tick: 128,
+            })
+        );
+
+        // Bloco em espaço de I/O ou sem endereço
+        let mut io = table.clone();
+        io[super::super::SDT_HEADER_LEN + 4] = 1;
+        assert_eq!(parse(&io), None);
+        assert_eq!(parse(&[0u8; 56]), None);
+        // Truncada
+        assert_eq!(parse(&table[..50]), None);
+    }
+}
@@ -11,6 +11,8 @@
 ///   - Tipo 0: Processor Local APIC.
 ///   - Tipo 1: I/O APIC.
 ///   - Tipo 2: Interrupt Source Override (ISO) - Crucial para teclados e timers legacy.
+use alloc::vec::Vec;
+use core::mem::size_of;
 
 /// ACPI MADT (Multiple APIC Description Table)
 
@@ -83,5 +85,225 @@ pub struct MadtNmi {
     pub lint: u8, // LINT# input (0 ou 1)
 }
 
-// TODO: Implementar iterador seguro sobre as entradas da MADT
-// (Requer apenas ler bytes raw a partir de &MadtHeader + sizeof(MadtHeader))
+/// Tipo 9: Processor Local x2APIC (APIC IDs acima de 255)
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct MadtLocalX2Apic {
+    pub header: MadtEntryHeader,
+    pub reserved: u16,
+    pub x2apic_id: u32,
+    pub flags: u32, // Bit 0 = Processor Enabled
+    pub acpi_processor_uid: u32,
+}
+
+pub const ENTRY_LOCAL_APIC: u8 = 0;
+pub const ENTRY_IO_APIC: u8 = 1;
+pub const ENTRY_ISO: u8 = 2;
+pub const ENTRY_NMI: u8 = 4;
+pub const ENTRY_LOCAL_X2APIC: u8 = 9;
+
+/// Flag de `MadtLocalApic`/`MadtLocalX2Apic`: processador utilizável.
+///
+/// O bit 1 (Online Capable) só indica hot-plug futuro; esses não sobem.
+const LAPIC_ENABLED: u32 = 1 << 0;
+
+/// Iterador sobre as entradas da MADT: `(tipo, bytes da entrada)`.
+///
+/// Para na primeira entrada truncada ou com tamanho inválido.
+pub struct MadtEntries<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for MadtEntries<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.bytes.get(self.offset..)?;
+        if rest.len() < size_of::<MadtEntryHeader>() {
+            return None;
+        }
+        let len = rest[1] as usize;
+        if len < size_of::<MadtEntryHeader>() || len > rest.len() {
+            return None;
+        }
+        self.offset += len;
+        Some((rest[0], &rest[..len]))
+    }
+}
+
+/// Entradas de uma MADT completa (header incluído).
+///
+/// Respeita o `length` do header se ele for menor que `table`.
+pub fn entries(table: &[u8]) -> MadtEntries<'_> {
+    let len = match table.get(4..8) {
+        Some(b) => (u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize).min(table.len()),
+        None => 0,
+    };
+    MadtEntries {
+        bytes: &table[..len],
+        offset: size_of::<MadtHeader>(),
+    }
+}
+
+/// Um processador da MADT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalApic {
+    pub acpi_id: u32,
+    pub apic_id: u32,
+    /// Pode ser iniciado
+    pub enabled: bool,
+}
+
+/// Processadores (entradas Local APIC e Local x2APIC), na ordem da tabela
+pub fn local_apics(table: &[u8]) -> Vec<LocalApic> {
+    let mut cpus = Vec::new();
+    for (kind, entry) in entries(table) {
+        match kind {
+            ENTRY_LOCAL_APIC if entry.len() >= size_of::<MadtLocalApic>() => {
+                cpus.push(LocalApic {
+                    acpi_id: entry[2] as u32,
+                    apic_id: entry[3] as u32,
+                    enabled: read_u32(entry, 4) & LAPIC_ENABLED != 0,
+                });
+            }
+            ENTRY_LOCAL_X2APIC if entry.len() >= size_of::<MadtLocalX2Apic>() => {
+                cpus.push(LocalApic {
+                    acpi_id: read_u32(entry, 12),
+                    apic_id: read_u32(entry, 4),
+                    enabled: read_u32(entry, 8) & LAPIC_ENABLED != 0,
+                });
+            }
+            _ => {}
+        }
+    }
+    cpus
+}
+
+/// Um I/O APIC da MADT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoApic {
+    pub id: u8,
+    /// Endereço físico dos registradores
+    pub address: u32,
+    /// Primeira GSI atendida por este I/O APIC
+    pub gsi_base: u32,
+}
+
+/// I/O APICs (entradas tipo 1), na ordem da tabela
+pub fn io_apics(table: &[u8]) -> Vec<IoApic> {
+    entries(table)
+        .filter(|(kind, entry)| *kind == ENTRY_IO_APIC && entry.len() >= size_of::<MadtIoApic>())
+        .map(|(_, entry)| IoApic {
+            id: entry[2],
+            address: read_u32(entry, 4),
+            gsi_base: read_u32(entry, 8),
+        })
+        .collect()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// MADT com header zerado (exceto o tamanho) e as entradas dadas
+    fn madt(entries: &[&[u8]]) -> Vec<u8> {
+        let mut table = vec![0u8; size_of::<MadtHeader>()];
+        table[..4].copy_from_slice(b"APIC");
+        for entry in entries {
+            table.extend_from_slice(entry);
+        }
+        let len = table.len() as u32;
+        table[4..8].copy_from_slice(&len.to_le_bytes());
+        table
+    }
+
+    #[test]
+    fn test_local_apics() {
+        let table = madt(&[
+            // BSP e um AP habilitados, um desabilitado, um só "online capable"
+            &[0, 8, 0, 0, 1, 0, 0, 0],
+            &[0, 8, 1, 2, 1, 0, 0, 0],
+            &[0, 8, 2, 4, 0, 0, 0, 0],
+            &[0, 8, 3, 6, 2, 0, 0, 0],
+            // I/O APIC no meio é ignorado
+            &[1, 12, 0, 0, 0, 0, 0xC0, 0xFE, 0, 0, 0, 0],
+            // x2APIC id 300, uid 9
+            &[9, 16, 0, 0, 0x2C, 1, 0, 0, 1, 0, 0, 0, 9, 0, 0, 0],
+        ]);
+
+        let cpus = local_apics(&table);
+        let ids: Vec<(u32, u32, bool)> = cpus
+            .iter()
+            .map(|c| (c.acpi_id, c.apic_id, c.enabled))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                (0, 0, true),
+                (1, 2, true),
+                (2, 4, false),
+                (3, 6, false),
+                (9, 300, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_io_apics() {
+        let table = madt(&[
+            &[0, 8, 0, 0, 1, 0, 0, 0],
+            &[1, 12, 2, 0, 0x00, 0x00, 0xC0, 0xFE, 0, 0, 0, 0],
+            // ISO entre os dois
+            &[2, 10, 0, 0, 2, 0, 0, 0, 0, 0],
+            &[1, 12, 3, 0, 0x00, 0x10, 0xC0, 0xFE, 24, 0, 0, 0],
+            // Curta demais: ignorada
+            &[1, 8, 4, 0, 0, 0, 0, 0],
+        ]);
+
+        assert_eq!(
+            io_apics(&table),
+            [
+                IoApic {
+                    id: 2,
+                    address: 0xFEC0_0000,
+                    gsi_base: 0
+                },
+                IoApic {
+                    id: 3,
+                    address: 0xFEC0_1000,
+                    gsi_base: 24
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_entries() {
+        // Entrada com tamanho zero encerra a iteração
+        let table = madt(&[&[0, 8, 0, 0, 1, 0, 0, 0], &[0, 0, 1, 1]]);
+        assert_eq!(local_apics(&table).len(), 1);
+
+        // `length` do header menor que o buffer corta a última entrada
+        let mut table = madt(&[&[0, 8, 0, 0, 1, 0, 0, 0], &[0, 8, 1, 1, 1, 0, 0, 0]]);
+        let short = (table.len() - 8) as u32;
+        table[4..8].copy_from_slice(&short.to_le_bytes());
+        assert_eq!(local_apics(&table).len(), 1);
+
+        assert!(local_apics(&[]).is_empty());
+    }
+}
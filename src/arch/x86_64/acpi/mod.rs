@@ -4,18 +4,461 @@ pub mod fadt;
 ///
 /// Propósito: Módulo de suporte a ACPI (Advanced Configuration and Power Interface).
 /// O ACPI fornece tabelas de descrição de hardware essenciais para descobrir:
-/// - Topologia de CPUs (MADT).
+/// - Topologia de CPUs e I/O APICs (MADT).
+/// - Timer de alta precisão (HPET).
 /// - Configuração de Energia (FADT).
 /// - Dispositivos de Sistema (DSDT).
 ///
+/// Descoberta:
+/// 1. RSDP: o endereço do bootloader (BootInfo) ou, sem ele, uma varredura
+///    da EBDA (primeiro 1 KiB) e da área da BIOS (0xE0000-0xFFFFF).
+/// 2. Raiz: XSDT (ACPI 2.0+, ponteiros de 64 bits); sem XSDT, ou com ela
+///    inválida, a RSDT (ponteiros de 32 bits).
+/// 3. Tabelas: cada uma com o checksum conferido; inválidas são ignoradas.
+///
 /// Módulos contidos:
 /// - `madt`: Multiple APIC Description Table.
+/// - `hpet`: High Precision Event Timer Table.
 /// - `fadt`: Fixed ACPI Description Table.
 /// - `dsdt`: Differentiated System Description Table.
+pub mod hpet;
 pub mod madt;
 
-/// Inicializa o subsistema ACPI
+use crate::sync::Spinlock;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Assinatura do RSDP
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+/// Tamanho do RSDP 1.0 (coberto pelo primeiro checksum)
+const RSDP_V1_LEN: usize = 20;
+/// Tamanho do RSDP 2.0 (com o ponteiro da XSDT)
+const RSDP_LEN: usize = 36;
+/// Cabeçalho comum das tabelas (SDT)
+const SDT_HEADER_LEN: usize = 36;
+/// Tamanho máximo aceito de uma tabela (sanidade do campo `length`)
+const MAX_TABLE_LEN: usize = 1 << 20;
+
+/// Ponteiro (segmento real-mode) para a EBDA, na BIOS Data Area
+const EBDA_POINTER: u64 = 0x40E;
+/// Trecho da EBDA varrido
+const EBDA_SCAN_LEN: u64 = 1024;
+/// Área da BIOS varrida
+const BIOS_AREA: core::ops::Range<u64> = 0xE0000..0x100000;
+/// O RSDP fica alinhado a 16 bytes
+const RSDP_ALIGN: u64 = 16;
+
+/// Processadores da MADT (vazio sem ACPI ou sem MADT)
+static CPUS: Spinlock<Vec<madt::LocalApic>> = Spinlock::new(Vec::new());
+
+/// I/O APICs da MADT
+static IOAPICS: Spinlock<Vec<madt::IoApic>> = Spinlock::new(Vec::new());
+
+/// Endereço físico do bloco HPET; 0 sem tabela HPET
+static HPET_BASE: AtomicU64 = AtomicU64::new(0);
+
+/// Memória física onde estão as tabelas
+pub trait PhysMemory {
+    /// `len` bytes a partir de `phys`, se o trecho for legível
+    fn read(&self, phys: u64, len: usize) -> Option<&[u8]>;
+}
+
+/// Memória física pelo HHDM
+struct Hhdm;
+
+impl PhysMemory for Hhdm {
+    fn read(&self, phys: u64, len: usize) -> Option<&[u8]> {
+        // SAFETY: tabelas de firmware (ou a área da BIOS), só leitura
+        Some(unsafe { core::slice::from_raw_parts(crate::mm::addr::phys_to_virt::<u8>(phys), len) })
+    }
+}
+
+/// RSDP validado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rsdp {
+    pub revision: u8,
+    pub rsdt: u64,
+    /// Só com revisão 2+ e o checksum estendido correto
+    pub xsdt: Option<u64>,
+}
+
+/// Inicializa o subsistema ACPI. `rsdp` é o endereço do BootInfo; 0 faz
+/// procurar o RSDP na memória baixa.
 pub fn init(rsdp: u64) {
     crate::kinfo!("(ACPI) Init with RSDP: ", rsdp);
-    // TODO: Parse RSDP, XSDT, FADT, MADT
+    // TODO: Parse FADT
+
+    let rsdp = match rsdp {
+        0 => find_rsdp(&Hhdm),
+        addr => Some(addr),
+    };
+    let Some(rsdp) = rsdp.and_then(|addr| read_rsdp(&Hhdm, addr)) else {
+        crate::kwarn!("(ACPI) RSDP não encontrado ou inválido");
+        return;
+    };
+    let Some(tables) = root_tables(&Hhdm, &rsdp) else {
+        crate::kwarn!("(ACPI) Nem XSDT nem RSDT válidas");
+        return;
+    };
+
+    if let Some(info) = find_table(&Hhdm, &tables, b"HPET").and_then(hpet::parse) {
+        crate::kinfo!("(ACPI) HPET em", info.address);
+        HPET_BASE.store(info.address, Ordering::Relaxed);
+    }
+
+    let Some(table) = find_table(&Hhdm, &tables, b"APIC") else {
+        crate::kwarn!("(ACPI) MADT não encontrada");
+        return;
+    };
+    let cpus = madt::local_apics(table);
+    let ioapics = madt::io_apics(table);
+    crate::kinfo!("(ACPI) Processadores na MADT:", cpus.len() as u64);
+    crate::kinfo!("(ACPI) I/O APICs na MADT:", ioapics.len() as u64);
+    *CPUS.lock() = cpus;
+    *IOAPICS.lock() = ioapics;
+}
+
+/// Processadores descritos pela MADT, na ordem da tabela
+pub fn cpus() -> Vec<madt::LocalApic> {
+    CPUS.lock().clone()
+}
+
+/// I/O APICs descritos pela MADT, na ordem da tabela
+pub fn ioapics() -> Vec<madt::IoApic> {
+    IOAPICS.lock().clone()
+}
+
+/// Endereço físico do bloco HPET, se o firmware descreveu um
+pub fn hpet_base() -> Option<u64> {
+    match HPET_BASE.load(Ordering::Relaxed) {
+        0 => None,
+        base => Some(base),
+    }
+}
+
+/// Soma dos bytes módulo 256 é zero?
+pub fn checksum_ok(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Lê e valida o RSDP em `phys` (assinatura e checksums)
+pub fn read_rsdp(mem: &impl PhysMemory, phys: u64) -> Option<Rsdp> {
+    let v1 = mem.read(phys, RSDP_V1_LEN)?;
+    if &v1[..8] != RSDP_SIGNATURE || !checksum_ok(v1) {
+        return None;
+    }
+    let revision = v1[15];
+    let rsdt = read_u32(v1, 16)? as u64;
+    if revision < 2 {
+        return Some(Rsdp {
+            revision,
+            rsdt,
+            xsdt: None,
+        });
+    }
+
+    // ACPI 2.0+: o checksum estendido cobre `length` bytes; se ele falhar,
+    // a XSDT não é confiável e fica a RSDT
+    let xsdt = mem.read(phys, RSDP_LEN).and_then(|v2| {
+        let len = read_u32(v2, 20)? as usize;
+        let full = mem.read(phys, len.clamp(RSDP_LEN, 64))?;
+        if !checksum_ok(full) {
+            crate::kwarn!("(ACPI) Checksum estendido do RSDP inválido");
+            return None;
+        }
+        read_u64(full, 24).filter(|&xsdt| xsdt != 0)
+    });
+    Some(Rsdp {
+        revision,
+        rsdt,
+        xsdt,
+    })
+}
+
+/// Procura o RSDP na EBDA e na área da BIOS
+pub fn find_rsdp(mem: &impl PhysMemory) -> Option<u64> {
+    let ebda = mem
+        .read(EBDA_POINTER, 2)
+        .map(|b| (u16::from_le_bytes([b[0], b[1]]) as u64) << 4)
+        .filter(|&ebda| ebda != 0);
+    let ebda_area = ebda.map(|start| start..start + EBDA_SCAN_LEN);
+
+    ebda_area
+        .into_iter()
+        .chain(core::iter::once(BIOS_AREA))
+        .flat_map(|area| area.step_by(RSDP_ALIGN as usize))
+        .find(|&addr| read_rsdp(mem, addr).is_some())
+}
+
+/// Tabela completa em `phys`, com `length` plausível e checksum correto
+fn load_table(mem: &impl PhysMemory, phys: u64) -> Option<&[u8]> {
+    if phys == 0 {
+        return None;
+    }
+    let header = mem.read(phys, SDT_HEADER_LEN)?;
+    let len = read_u32(header, 4)? as usize;
+    if !(SDT_HEADER_LEN..=MAX_TABLE_LEN).contains(&len) {
+        return None;
+    }
+    let table = mem.read(phys, len)?;
+    if !checksum_ok(table) {
+        crate::kwarn!("(ACPI) Checksum inválido na tabela em", phys);
+        return None;
+    }
+    Some(table)
+}
+
+/// Endereços das tabelas listadas na XSDT ou, sem ela, na RSDT
+pub fn root_tables(mem: &impl PhysMemory, rsdp: &Rsdp) -> Option<Vec<u64>> {
+    let xsdt = rsdp
+        .xsdt
+        .and_then(|phys| load_table(mem, phys))
+        .filter(|table| &table[..4] == b"XSDT");
+    // XSDT: ponteiros de 64 bits; RSDT: 32
+    let (root, entry_size) = match xsdt {
+        Some(table) => (table, 8),
+        None => {
+            if rsdp.xsdt.is_some() {
+                crate::kwarn!("(ACPI) XSDT inválida; usando a RSDT");
+            }
+            let table = load_table(mem, rsdp.rsdt).filter(|table| &table[..4] == b"RSDT")?;
+            (table, 4)
+        }
+    };
+
+    Some(
+        root[SDT_HEADER_LEN..]
+            .chunks_exact(entry_size)
+            .map(|entry| {
+                let mut addr = [0u8; 8];
+                addr[..entry_size].copy_from_slice(entry);
+                u64::from_le_bytes(addr)
+            })
+            .collect(),
+    )
+}
+
+/// Primeira tabela válida com a assinatura `signature`
+pub fn find_table<'m>(
+    mem: &'m impl PhysMemory,
+    tables: &[u64],
+    signature: &[u8; 4],
+) -> Option<&'m [u8]> {
+    tables.iter().find_map(|&phys| {
+        // Assinatura primeiro: não conferir o checksum das outras tabelas
+        let header = mem.read(phys, 4)?;
+        if header != signature {
+            return None;
+        }
+        load_table(mem, phys)
+    })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Memória física de mentira: trechos soltos
+    struct MockMemory {
+        regions: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl MockMemory {
+        fn new() -> Self {
+            Self {
+                regions: Vec::new(),
+            }
+        }
+
+        fn place(&mut self, phys: u64, bytes: Vec<u8>) {
+            self.regions.push((phys, bytes));
+        }
+    }
+
+    impl PhysMemory for MockMemory {
+        fn read(&self, phys: u64, len: usize) -> Option<&[u8]> {
+            self.regions.iter().find_map(|(base, bytes)| {
+                let start = phys.checked_sub(*base)? as usize;
+                bytes.get(start..start + len)
+            })
+        }
+    }
+
+    /// Acerta o byte de checksum em `offset` para a soma de `bytes` dar zero
+    fn fix_checksum(bytes: &mut [u8], offset: usize) {
+        bytes[offset] = 0;
+        let sum = bytes.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+        bytes[offset] = 0u8.wrapping_sub(sum);
+    }
+
+    /// Tabela com cabeçalho SDT e o corpo dado
+    fn sdt(signature: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut table = vec![0u8; SDT_HEADER_LEN];
+        table[..4].copy_from_slice(signature);
+        table.extend_from_slice(body);
+        let len = table.len() as u32;
+        table[4..8].copy_from_slice(&len.to_le_bytes());
+        fix_checksum(&mut table, 9);
+        table
+    }
+
+    fn rsdp_bytes(revision: u8, rsdt: u32, xsdt: u64) -> Vec<u8> {
+        let mut bytes = vec![0u8; RSDP_LEN];
+        bytes[..8].copy_from_slice(RSDP_SIGNATURE);
+        bytes[15] = revision;
+        bytes[16..20].copy_from_slice(&rsdt.to_le_bytes());
+        bytes[20..24].copy_from_slice(&(RSDP_LEN as u32).to_le_bytes());
+        bytes[24..32].copy_from_slice(&xsdt.to_le_bytes());
+        fix_checksum(&mut bytes[..RSDP_V1_LEN], 8);
+        fix_checksum(&mut bytes, 32);
+        bytes
+    }
+
+    const MADT_AT: u64 = 0x7FE_1000;
+    const HPET_AT: u64 = 0x7FE_2000;
+    const RSDT_AT: u64 = 0x7FE_3000;
+    const XSDT_AT: u64 = 0x7FE_4000;
+
+    /// Firmware com MADT (2 CPUs, 1 I/O APIC), HPET, RSDT e XSDT
+    fn firmware() -> MockMemory {
+        let mut mem = MockMemory::new();
+
+        let mut madt = vec![0u8; 8];
+        madt.extend_from_slice(&[0, 8, 0, 0, 1, 0, 0, 0]);
+        madt.extend_from_slice(&[0, 8, 1, 1, 1, 0, 0, 0]);
+        madt.extend_from_slice(&[1, 12, 2, 0, 0x00, 0x00, 0xC0, 0xFE, 0, 0, 0, 0]);
+        mem.place(MADT_AT, sdt(b"APIC", &madt));
+
+        let mut hpet = vec![0u8; 20];
+        hpet[8..16].copy_from_slice(&0xFED0_0000u64.to_le_bytes());
+        mem.place(HPET_AT, sdt(b"HPET", &hpet));
+
+        let rsdt: Vec<u8> = [MADT_AT as u32, HPET_AT as u32]
+            .iter()
+            .flat_map(|a| a.to_le_bytes())
+            .collect();
+        mem.place(RSDT_AT, sdt(b"RSDT", &rsdt));
+        let xsdt: Vec<u8> = [HPET_AT, MADT_AT]
+            .iter()
+            .flat_map(|a| a.to_le_bytes())
+            .collect();
+        mem.place(XSDT_AT, sdt(b"XSDT", &xsdt));
+        mem
+    }
+
+    fn check_tables(mem: &MockMemory, tables: &[u64]) {
+        let madt = find_table(mem, tables, b"APIC").expect("MADT");
+        assert_eq!(madt::local_apics(madt).len(), 2);
+        let ioapics = madt::io_apics(madt);
+        assert_eq!(ioapics.len(), 1);
+        assert_eq!(ioapics[0].address, 0xFEC0_0000);
+
+        let hpet = find_table(mem, tables, b"HPET").and_then(hpet::parse);
+        assert_eq!(hpet.map(|h| h.address), Some(0xFED0_0000));
+        assert!(find_table(mem, tables, b"FACP").is_none());
+    }
+
+    #[test]
+    fn test_xsdt_preferred() {
+        let mut mem = firmware();
+        mem.place(0x1000, rsdp_bytes(2, RSDT_AT as u32, XSDT_AT));
+
+        let rsdp = read_rsdp(&mem, 0x1000).unwrap();
+        assert_eq!(rsdp.xsdt, Some(XSDT_AT));
+        let tables = root_tables(&mem, &rsdp).unwrap();
+        // Ordem da XSDT (HPET, MADT), não da RSDT
+        assert_eq!(tables, [HPET_AT, MADT_AT]);
+        check_tables(&mem, &tables);
+    }
+
+    #[test]
+    fn test_rsdt_fallback() {
+        // ACPI 1.0: sem XSDT
+        let mut mem = firmware();
+        mem.place(0x1000, rsdp_bytes(0, RSDT_AT as u32, 0));
+        let rsdp = read_rsdp(&mem, 0x1000).unwrap();
+        assert_eq!(rsdp.xsdt, None);
+        let tables = root_tables(&mem, &rsdp).unwrap();
+        assert_eq!(tables, [MADT_AT, HPET_AT]);
+        check_tables(&mem, &tables);
+
+        // XSDT com checksum errado: cai para a RSDT
+        let mut mem = firmware();
+        mem.place(0x1000, rsdp_bytes(2, RSDT_AT as u32, XSDT_AT));
+        mem.regions
+            .iter_mut()
+            .find(|(base, _)| *base == XSDT_AT)
+            .unwrap()
+            .1[40] ^= 1;
+        let rsdp = read_rsdp(&mem, 0x1000).unwrap();
+        assert_eq!(root_tables(&mem, &rsdp).unwrap(), [MADT_AT, HPET_AT]);
+    }
+
+    #[test]
+    fn test_checksums() {
+        let mut mem = firmware();
+        mem.place(0x1000, rsdp_bytes(2, RSDT_AT as u32, XSDT_AT));
+
+        // RSDP com checksum errado é rejeitado
+        let mut bad = rsdp_bytes(2, RSDT_AT as u32, XSDT_AT);
+        bad[16] ^= 1;
+        mem.place(0x2000, bad);
+        assert!(read_rsdp(&mem, 0x2000).is_none());
+
+        // Checksum estendido errado: só a XSDT é descartada
+        let mut bad = rsdp_bytes(2, RSDT_AT as u32, XSDT_AT);
+        bad[33] ^= 1;
+        mem.place(0x3000, bad);
+        assert_eq!(read_rsdp(&mem, 0x3000).unwrap().xsdt, None);
+
+        // Tabela corrompida some da busca
+        mem.regions
+            .iter_mut()
+            .find(|(base, _)| *base == HPET_AT)
+            .unwrap()
+            .1[50] ^= 1;
+        let tables = root_tables(&mem, &read_rsdp(&mem, 0x1000).unwrap()).unwrap();
+        assert!(find_table(&mem, &tables, b"HPET").is_none());
+        assert!(find_table(&mem, &tables, b"APIC").is_some());
+    }
+
+    #[test]
+    fn test_find_rsdp() {
+        // Na EBDA (segmento 0x9FC0 = 0x9FC00)
+        let mut mem = firmware();
+        mem.place(EBDA_POINTER, 0x9FC0u16.to_le_bytes().to_vec());
+        let mut ebda = vec![0u8; EBDA_SCAN_LEN as usize];
+        ebda[0x40..0x40 + RSDP_LEN].copy_from_slice(&rsdp_bytes(0, RSDT_AT as u32, 0));
+        mem.place(0x9FC00, ebda);
+        assert_eq!(find_rsdp(&mem), Some(0x9FC40));
+
+        // Na área da BIOS, depois de uma assinatura com checksum errado
+        let mut mem = firmware();
+        let mut bios = vec![0u8; (BIOS_AREA.end - BIOS_AREA.start) as usize];
+        let mut decoy = rsdp_bytes(0, RSDT_AT as u32, 0);
+        decoy[8] ^= 1;
+        bios[0x100..0x100 + RSDP_LEN].copy_from_slice(&decoy);
+        bios[0x1F0..0x1F0 + RSDP_LEN].copy_from_slice(&rsdp_bytes(2, RSDT_AT as u32, XSDT_AT));
+        mem.place(BIOS_AREA.start, bios);
+        assert_eq!(find_rsdp(&mem), Some(0xE01F0));
+
+        assert_eq!(find_rsdp(&MockMemory::new()), None);
+    }
 }
@@ -13,6 +13,8 @@
 /// - Usa MSR `IA32_APIC_BASE` para habilitar globalmente.
 /// - Usa MMIO (padrão 0xFEE00000) para acesso aos registradores de controle.
 /// - Configura Spurious Interrupt Vector (SVR) para habilitar recepção de interrupções.
+/// - Timer local calibrado contra o PIT e usado como tick periódico (ver
+///   `core::time::tick`).
 
 /// Controlador Local APIC
 use crate::arch::x86_64::cpu::Cpu;
@@ -27,6 +29,8 @@ const REG_VER: usize = 0x030;
 const REG_EOI: usize = 0x0B0;
 const REG_SVR: usize = 0x0F0; // Spurious Interrupt Vector
 const REG_ESR: usize = 0x280; // Error Status Register
+const REG_ICR_LOW: usize = 0x300; // Interrupt Command
+const REG_ICR_HIGH: usize = 0x310; // Destino da IPI (bits 24-31)
 const REG_LVT_TIMER: usize = 0x320;
 const REG_TICR: usize = 0x380; // Timer Initial Count
 const REG_TCCR: usize = 0x390; // Timer Current Count
@@ -35,6 +39,25 @@ const REG_TDCR: usize = 0x3E0; // Timer Divide Config
 // Bits e Flags
 const APIC_ENABLE_BIT: u64 = 1 << 11; // MSR Enable
 const SVR_SOFT_ENABLE: u32 = 1 << 8; // Software Enable no registro SVR
+const LVT_MASKED: u32 = 1 << 16;
+const LVT_TIMER_PERIODIC: u32 = 1 << 17;
+const TDCR_DIVIDE_BY_16: u32 = 0x3;
+const ICR_DELIVERY_INIT: u32 = 0b101 << 8;
+const ICR_DELIVERY_STARTUP: u32 = 0b110 << 8;
+const ICR_SEND_PENDING: u32 = 1 << 12; // Delivery Status
+const ICR_LEVEL_ASSERT: u32 = 1 << 14;
+
+/// Iterações máximas esperando o ICR aceitar uma IPI
+const ICR_POLL_LIMIT: u32 = 1_000_000;
+
+/// Vetor do spurious interrupt (programado no SVR)
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+/// Vetor do timer local
+pub const TIMER_VECTOR: u8 = 0xF0;
+
+/// Duração da janela de calibração do timer
+const CALIBRATION_MS: u32 = 10;
 
 /// Inicializa o Local APIC do core atual.
 ///
@@ -52,11 +75,11 @@ pub unsafe fn init() {
 
     // 2. Definir Spurious Interrupt Vector e Habilitar Software (Bit 8)
     // Vetor 0xFF (255) geralmente usado para Spurious
-    write(REG_SVR, SVR_SOFT_ENABLE | 0xFF);
+    write(REG_SVR, SVR_SOFT_ENABLE | SPURIOUS_VECTOR as u32);
 
     // 3. Mascarar LVT Timer inicialmente (até configurarmos o timer)
     // Bit 16 = Masked
-    write(REG_LVT_TIMER, 1 << 16);
+    write(REG_LVT_TIMER, LVT_MASKED);
 
     // 4. Limpar Error Status Register (precisa escrever 2x em hardware antigo, 1x em novos)
     write(REG_ESR, 0);
@@ -82,6 +105,90 @@ pub fn id() -> u32 {
     unsafe { read(REG_ID) >> 24 }
 }
 
+/// Mede a frequência do timer local (ticks por segundo, divisor 16).
+///
+/// Conta por [`CALIBRATION_MS`] no canal 2 do PIT com o timer mascarado em
+/// one-shot; o timer fica parado no fim.
+///
+/// # Safety
+/// LAPIC inicializado ([`init`]); bloqueia a CPU durante a medição.
+pub unsafe fn calibrate_timer() -> u64 {
+    write(REG_TDCR, TDCR_DIVIDE_BY_16);
+    write(REG_LVT_TIMER, LVT_MASKED);
+    write(REG_TICR, u32::MAX);
+
+    crate::drivers::timer::pit::busy_wait_ms(CALIBRATION_MS);
+
+    let remaining = read(REG_TCCR);
+    write(REG_TICR, 0);
+
+    (u32::MAX - remaining) as u64 * 1000 / CALIBRATION_MS as u64
+}
+
+/// Liga o timer local em modo periódico: uma interrupção em `vector` a cada
+/// `initial_count` ticks (divisor 16).
+///
+/// # Safety
+/// `vector` precisa de handler na IDT que envie [`eoi`].
+pub unsafe fn start_periodic_timer(vector: u8, initial_count: u32) {
+    write(REG_TDCR, TDCR_DIVIDE_BY_16);
+    write(REG_LVT_TIMER, LVT_TIMER_PERIODIC | vector as u32);
+    write(REG_TICR, initial_count);
+}
+
+/// Liga o timer local em modo one-shot: uma interrupção em `vector` daqui
+/// a `count` ticks (divisor 16).
+///
+/// # Safety
+/// `vector` precisa de handler na IDT que envie [`eoi`].
+pub unsafe fn start_oneshot_timer(vector: u8, count: u32) {
+    write(REG_TDCR, TDCR_DIVIDE_BY_16);
+    write(REG_LVT_TIMER, vector as u32);
+    write(REG_TICR, count);
+}
+
+/// Para e mascara o timer local.
+///
+/// # Safety
+/// LAPIC inicializado ([`init`]).
+pub unsafe fn stop_timer() {
+    write(REG_LVT_TIMER, LVT_MASKED);
+    write(REG_TICR, 0);
+}
+
+/// Envia INIT para o core `apic_id` (primeiro passo do INIT-SIPI-SIPI).
+///
+/// # Safety
+/// LAPIC inicializado ([`init`]). O core alvo é resetado.
+pub unsafe fn send_init(apic_id: u32) -> bool {
+    send_ipi_raw(apic_id, ICR_DELIVERY_INIT | ICR_LEVEL_ASSERT)
+}
+
+/// Envia STARTUP (SIPI): o core alvo começa em modo real no endereço
+/// físico `vector << 12`.
+///
+/// # Safety
+/// LAPIC inicializado ([`init`]) e código válido na página do vetor.
+pub unsafe fn send_startup(apic_id: u32, vector: u8) -> bool {
+    send_ipi_raw(apic_id, ICR_DELIVERY_STARTUP | vector as u32)
+}
+
+/// Escreve o ICR e espera a entrega. `false` se o LAPIC não aceitou a IPI.
+///
+/// Só xAPIC: o destino tem 8 bits.
+unsafe fn send_ipi_raw(apic_id: u32, command: u32) -> bool {
+    write(REG_ICR_HIGH, (apic_id & 0xFF) << 24);
+    write(REG_ICR_LOW, command);
+
+    for _ in 0..ICR_POLL_LIMIT {
+        if read(REG_ICR_LOW) & ICR_SEND_PENDING == 0 {
+            return true;
+        }
+        core::hint::spin_loop();
+    }
+    false
+}
+
 // --- Helpers de Acesso MMIO (Privados) ---
 
 #[inline]
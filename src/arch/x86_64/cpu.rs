@@ -18,6 +18,8 @@ pub struct Cpu;
 impl CpuTrait for Cpu {
     #[inline(always)]
     fn disable_interrupts() {
+        // Privilegiada: nos testes de host (user mode) daria #GP
+        #[cfg(not(test))]
         unsafe {
             core::arch::asm!("cli", options(nomem, nostack));
         }
@@ -25,6 +27,8 @@ impl CpuTrait for Cpu {
 
     #[inline(always)]
     fn enable_interrupts() {
+        // Privilegiada: nos testes de host (user mode) daria #GP
+        #[cfg(not(test))]
         unsafe {
             core::arch::asm!("sti", options(nomem, nostack));
         }
@@ -32,6 +36,8 @@ impl CpuTrait for Cpu {
 
     #[inline(always)]
     fn halt() {
+        // Privilegiada: nos testes de host (user mode) daria #GP
+        #[cfg(not(test))]
         unsafe {
             core::arch::asm!("hlt", options(nomem, nostack));
         }
@@ -39,8 +45,7 @@ impl CpuTrait for Cpu {
 
     #[inline(always)]
     fn current_core_id() -> u32 {
-        // TODO: Ler APIC ID real
-        0
+        crate::core::smp::current_cpu_id()
     }
 
     #[inline(always)]
@@ -113,6 +118,117 @@ impl Cpu {
         }
     }
 
+    /// Lê o Time Stamp Counter
+    #[inline(always)]
+    pub fn rdtsc() -> u64 {
+        // SAFETY: rdtsc não tem efeitos colaterais
+        unsafe { core::arch::x86_64::_rdtsc() }
+    }
+
+    /// Executa CPUID. Retorna `[eax, ebx, ecx, edx]`.
+    pub fn cpuid(leaf: u32, subleaf: u32) -> [u32; 4] {
+        // SAFETY: cpuid é sempre disponível em x86_64
+        let r = unsafe { core::arch::x86_64::__cpuid_count(leaf, subleaf) };
+        [r.eax, r.ebx, r.ecx, r.edx]
+    }
+
+    /// CPU tem Local APIC (CPUID.01H:EDX[9])
+    pub fn has_apic() -> bool {
+        super::cpuid::features().apic
+    }
+
+    /// APIC ID inicial do core atual (CPUID.01H:EBX[31:24]).
+    ///
+    /// Não toca no MMIO do LAPIC, então vale com qualquer CR3.
+    pub fn apic_id() -> u32 {
+        Self::cpuid(1, 0)[1] >> 24
+    }
+
+    /// Lê 64 bits do gerador de hardware (`rdrand`), se a CPU tiver um.
+    ///
+    /// Retorna `None` sem suporte (CPUID.01H:ECX[30]) ou se o gerador não
+    /// entregar um valor após algumas tentativas.
+    pub fn rdrand() -> Option<u64> {
+        if !super::cpuid::features().rdrand {
+            return None;
+        }
+
+        // A Intel recomenda até 10 tentativas antes de desistir
+        for _ in 0..10 {
+            let value: u64;
+            let ok: u8;
+            // SAFETY: suporte a rdrand verificado acima
+            unsafe {
+                core::arch::asm!(
+                    "rdrand {value}",
+                    "setc {ok}",
+                    value = out(reg) value,
+                    ok = out(reg_byte) ok,
+                    options(nomem, nostack)
+                );
+            }
+            if ok != 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Lê o registrador de controle CR0
+    #[inline]
+    pub fn read_cr0() -> u64 {
+        let value: u64;
+        // SAFETY: leitura de registrador de controle em Ring 0
+        unsafe {
+            core::arch::asm!("mov {}, cr0", out(reg) value, options(nomem, nostack));
+        }
+        value
+    }
+
+    /// Escreve no registrador de controle CR0
+    ///
+    /// # Safety
+    ///
+    /// Bits como PG e PE mudam o modo de execução: só ligue ou desligue o
+    /// que o kernel sabe tratar.
+    #[inline]
+    pub unsafe fn write_cr0(value: u64) {
+        core::arch::asm!("mov cr0, {}", in(reg) value, options(nomem, nostack));
+    }
+
+    /// Lê o registrador de controle CR4
+    #[inline]
+    pub fn read_cr4() -> u64 {
+        let value: u64;
+        // SAFETY: leitura de registrador de controle em Ring 0
+        unsafe {
+            core::arch::asm!("mov {}, cr4", out(reg) value, options(nomem, nostack));
+        }
+        value
+    }
+
+    /// Escreve no registrador de controle CR4
+    ///
+    /// # Safety
+    ///
+    /// Ligar um bit que a CPU não suporta causa #GP; bits como SMEP/SMAP
+    /// passam a valer imediatamente para o código em execução.
+    #[inline]
+    pub unsafe fn write_cr4(value: u64) {
+        core::arch::asm!("mov cr4, {}", in(reg) value, options(nomem, nostack));
+    }
+
+    /// Lê o RFLAGS
+    #[inline(always)]
+    pub fn read_rflags() -> u64 {
+        let rflags: u64;
+        // SAFETY: pushfq/pop só tocam a stack
+        unsafe {
+            core::arch::asm!("pushfq; pop {}", out(reg) rflags, options(nomem, preserves_flags));
+        }
+        rflags
+    }
+
     /// Lê o registrador de controle CR3 (Page Table Base)
     #[inline]
     pub fn read_cr3() -> u64 {
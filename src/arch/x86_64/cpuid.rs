@@ -0,0 +1,202 @@
+//! Recursos da CPU (CPUID)
+//!
+//! O CPUID é executado uma vez, no boot ([`init`]), e o resultado fica em
+//! [`CpuFeatures`]. Quem precisa saber se a CPU tem PCID, SMEP, RDRAND etc.
+//! consulta [`features`] (ou `arch::cpu_features()`) em vez de repetir a
+//! instrução, que é serializante e, em VMs, causa uma saída para o
+//! hipervisor.
+//!
+//! | Recurso        | Folha              | Bit     |
+//! |----------------|--------------------|---------|
+//! | APIC           | 01H EDX            | 9       |
+//! | SSE / SSE2     | 01H EDX            | 25 / 26 |
+//! | PCID           | 01H ECX            | 17      |
+//! | RDRAND         | 01H ECX            | 30      |
+//! | SMEP           | 07H.0 EBX          | 7       |
+//! | RDSEED         | 07H.0 EBX          | 18      |
+//! | SMAP           | 07H.0 EBX          | 20      |
+//! | NX             | 80000001H EDX      | 20      |
+//! | Páginas de 1GiB| 80000001H EDX      | 26      |
+//! | TSC invariante | 80000007H EDX      | 8       |
+
+use super::cpu::Cpu;
+use crate::sync::Spinlock;
+
+/// Resultado do CPUID, feito uma vez
+static FEATURES: Spinlock<Option<CpuFeatures>> = Spinlock::new(None);
+
+/// Recursos da CPU que o kernel usa
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFeatures {
+    /// "GenuineIntel", "AuthenticAMD", ...
+    pub vendor: [u8; 12],
+    /// Família, já somada à família estendida
+    pub family: u32,
+    /// Modelo, já combinado com o modelo estendido
+    pub model: u32,
+    pub stepping: u32,
+    pub apic: bool,
+    pub sse: bool,
+    pub sse2: bool,
+    pub pcid: bool,
+    pub rdrand: bool,
+    pub rdseed: bool,
+    pub smep: bool,
+    pub smap: bool,
+    pub nx: bool,
+    /// Páginas de 1GiB (PDPE1GB)
+    pub huge_1g: bool,
+    pub invariant_tsc: bool,
+}
+
+fn bit(reg: u32, n: u32) -> bool {
+    reg & (1 << n) != 0
+}
+
+impl CpuFeatures {
+    /// Monta a partir de uma função `cpuid(folha, subfolha) -> [eax, ebx,
+    /// ecx, edx]`. Folhas além do máximo informado pela CPU não são lidas.
+    pub fn from_cpuid(mut cpuid: impl FnMut(u32, u32) -> [u32; 4]) -> Self {
+        let [max_basic, ebx, ecx, edx] = cpuid(0, 0);
+        let mut vendor = [0u8; 12];
+        vendor[0..4].copy_from_slice(&ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&edx.to_le_bytes());
+        vendor[8..12].copy_from_slice(&ecx.to_le_bytes());
+
+        let [eax1, _, ecx1, edx1] = if max_basic >= 1 { cpuid(1, 0) } else { [0; 4] };
+        let [_, ebx7, _, _] = if max_basic >= 7 { cpuid(7, 0) } else { [0; 4] };
+
+        let max_extended = cpuid(0x8000_0000, 0)[0];
+        let [_, _, _, edx_ext1] = if max_extended >= 0x8000_0001 {
+            cpuid(0x8000_0001, 0)
+        } else {
+            [0; 4]
+        };
+        let [_, _, _, edx_ext7] = if max_extended >= 0x8000_0007 {
+            cpuid(0x8000_0007, 0)
+        } else {
+            [0; 4]
+        };
+
+        // Família 0xF soma a estendida; 6 e 0xF combinam o modelo estendido
+        let base_family = (eax1 >> 8) & 0xF;
+        let mut family = base_family;
+        let mut model = (eax1 >> 4) & 0xF;
+        if base_family == 0xF {
+            family += (eax1 >> 20) & 0xFF;
+        }
+        if base_family == 0x6 || base_family == 0xF {
+            model |= ((eax1 >> 16) & 0xF) << 4;
+        }
+
+        Self {
+            vendor,
+            family,
+            model,
+            stepping: eax1 & 0xF,
+            apic: bit(edx1, 9),
+            sse: bit(edx1, 25),
+            sse2: bit(edx1, 26),
+            pcid: bit(ecx1, 17),
+            rdrand: bit(ecx1, 30),
+            rdseed: bit(ebx7, 18),
+            smep: bit(ebx7, 7),
+            smap: bit(ebx7, 20),
+            nx: bit(edx_ext1, 20),
+            huge_1g: bit(edx_ext1, 26),
+            invariant_tsc: bit(edx_ext7, 8),
+        }
+    }
+
+    /// Vendor como texto ("?" se não for ASCII)
+    pub fn vendor_str(&self) -> &str {
+        core::str::from_utf8(&self.vendor).unwrap_or("?")
+    }
+}
+
+/// Executa o CPUID e guarda o resultado. Chamado no início do boot; as
+/// consultas antes disso fazem a leitura na hora.
+pub fn init() {
+    let features = features();
+    crate::kinfo!("(CPU) Vendor:", features.vendor_str());
+    crate::kinfo!("(CPU) Família:", features.family as u64);
+    crate::kinfo!("(CPU) Modelo:", features.model as u64);
+}
+
+/// Recursos da CPU
+pub fn features() -> CpuFeatures {
+    *FEATURES
+        .lock()
+        .get_or_insert_with(|| CpuFeatures::from_cpuid(Cpu::cpuid))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// CPUID de mentira: folhas fixas, zero no resto
+    fn mock(leaves: &[(u32, [u32; 4])]) -> impl FnMut(u32, u32) -> [u32; 4] + '_ {
+        move |leaf, _| {
+            leaves
+                .iter()
+                .find(|(l, _)| *l == leaf)
+                .map(|(_, regs)| *regs)
+                .unwrap_or([0; 4])
+        }
+    }
+
+    #[test]
+    fn test_from_cpuid() {
+        // "GenuineIntel": EBX, EDX, ECX
+        let vendor = [0x756E_6547, 0x4965_6E69, 0x6C65_746E];
+        let leaves = [
+            (0, [0xD, vendor[0], vendor[2], vendor[1]]),
+            // Família 6, modelo 0x3A (estendido 3), stepping 9
+            (
+                1,
+                [
+                    0x0003_06A9,
+                    0,
+                    1 << 17 | 1 << 30,
+                    1 << 9 | 1 << 25 | 1 << 26,
+                ],
+            ),
+            (7, [0, 1 << 7 | 1 << 18 | 1 << 20, 0, 0]),
+            (0x8000_0000, [0x8000_0008, 0, 0, 0]),
+            (0x8000_0001, [0, 0, 0, 1 << 20 | 1 << 26]),
+            (0x8000_0007, [0, 0, 0, 1 << 8]),
+        ];
+
+        let f = CpuFeatures::from_cpuid(mock(&leaves));
+        assert_eq!(f.vendor_str(), "GenuineIntel");
+        assert_eq!((f.family, f.model, f.stepping), (6, 0x3A, 9));
+        assert!(f.apic && f.sse && f.sse2 && f.pcid && f.rdrand);
+        assert!(f.smep && f.rdseed && f.smap);
+        assert!(f.nx && f.huge_1g && f.invariant_tsc);
+    }
+
+    #[test]
+    fn test_missing_leaves() {
+        // Folha 7 e 80000007H além do máximo: não são lidas, mesmo que o
+        // mock responda
+        let leaves = [
+            (0, [1, 0, 0, 0]),
+            // Família 0xF + 8 = 0x17 (Zen), modelo estendido 1
+            (1, [0x0081_0F21, 0, 0, 1 << 25]),
+            (7, [0, u32::MAX, 0, 0]),
+            (0x8000_0000, [0x8000_0001, 0, 0, 0]),
+            (0x8000_0001, [0, 0, 0, 1 << 20]),
+            (0x8000_0007, [0, 0, 0, u32::MAX]),
+        ];
+
+        let f = CpuFeatures::from_cpuid(mock(&leaves));
+        assert_eq!((f.family, f.model, f.stepping), (0x17, 0x12, 1));
+        assert!(f.sse && !f.sse2 && !f.apic);
+        assert!(!f.smep && !f.smap && !f.rdseed);
+        assert!(f.nx && !f.huge_1g && !f.invariant_tsc);
+    }
+}
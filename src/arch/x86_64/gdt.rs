@@ -173,9 +173,8 @@ impl Tss {
     }
 }
 
-// GDT global estática
 // 7 Entradas: Null, KCode, KData, UCode, UData, TSS-Low, TSS-High
-static mut GDT: [GdtEntry; 7] = [
+const GDT_TEMPLATE: [GdtEntry; 7] = [
     GdtEntry::null(),
     GdtEntry::kernel_code(),
     GdtEntry::kernel_data(),
@@ -185,6 +184,9 @@ static mut GDT: [GdtEntry; 7] = [
     GdtEntry::null(),      // TSS high (será preenchido no init)
 ];
 
+// GDT global estática (BSP)
+static mut GDT: [GdtEntry; 7] = GDT_TEMPLATE;
+
 // TSS global estática
 static mut TSS: Tss = Tss::new();
 
@@ -217,10 +219,50 @@ pub unsafe fn init() {
     GDT[5] = GdtEntry::tss_low(tss_base, tss_limit);
     GDT[6] = GdtEntry::tss_high(tss_base);
 
+    load(&raw const GDT);
+}
+
+/// GDT, TSS e stack de double fault de um AP
+#[repr(C, align(16))]
+struct ApTables {
+    gdt: [GdtEntry; 7],
+    tss: Tss,
+    double_fault_stack: [u8; 4096],
+}
+
+/// Inicializa a GDT e o TSS de um AP (Application Processor).
+///
+/// Cada AP tem as suas tabelas, alocadas no heap e nunca liberadas: o
+/// descritor do TSS fica marcado como ocupado após o `ltr`, e cada core
+/// precisa do seu IST de double fault.
+///
+/// # Safety
+///
+/// Uma vez por AP, no próprio AP, com o heap pronto.
+pub unsafe fn init_ap() {
+    let tables = alloc::boxed::Box::leak(alloc::boxed::Box::new(ApTables {
+        gdt: GDT_TEMPLATE,
+        tss: Tss::new(),
+        double_fault_stack: [0; 4096],
+    }));
+
+    let tss_base = (&raw const tables.tss) as u64;
+    let tss_limit = (size_of::<Tss>() - 1) as u32;
+    // Topo alinhado a 16 (a stack começa em offset múltiplo de 16)
+    tables.tss.ist1 = (tables.double_fault_stack.as_ptr() as u64 + 4096) & !0xF;
+
+    tables.gdt[5] = GdtEntry::tss_low(tss_base, tss_limit);
+    tables.gdt[6] = GdtEntry::tss_high(tss_base);
+
+    load(&raw const tables.gdt);
+}
+
+/// Carrega `gdt`, recarrega os segmentos e o Task Register
+unsafe fn load(gdt: *const [GdtEntry; 7]) {
     // 2. Carregar GDT
     let gdtr = GdtDescriptor {
         limit: (size_of::<[GdtEntry; 7]>() - 1) as u16,
-        base: (&raw const GDT) as u64,
+        base: gdt as u64,
     };
 
     core::arch::asm!("lgdt [{}]", in(reg) &gdtr, options(readonly, nostack, preserves_flags));
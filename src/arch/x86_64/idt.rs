@@ -9,8 +9,11 @@
 /// - Mantém uma tabela estática de 256 entradas.
 /// - Fornece métodos para registrar handlers.
 /// - Implementa `load` para configurar o registrador IDTR.
+/// - Aloca vetores dinâmicos (0x50..=0xEF) para MSI/MSI-X.
 // Interrupt Descriptor Table
 use crate::arch::x86_64::gdt::KERNEL_CODE_SEL;
+use crate::sync::Spinlock;
+use alloc::vec::Vec;
 use core::mem::size_of;
 
 // Alias ContextFrame to TrapFrame for syscall handling compatibility
@@ -112,3 +115,74 @@ struct IdtDescriptor {
 
 // Global IDT (estática e mutável apenas na init)
 pub static mut IDT: Idt = Idt::new();
+
+// =============================================================================
+// ALOCAÇÃO DE VETORES
+// =============================================================================
+
+/// Primeiro vetor alocável. 0-31 são exceções e 32-47 as IRQs do PIC.
+pub const DYNAMIC_VECTOR_START: u8 = 0x50;
+
+/// Último vetor alocável. Acima ficam IPIs e o spurious (0xFF).
+pub const DYNAMIC_VECTOR_END: u8 = 0xEF;
+
+/// Vetores dinâmicos em uso (um bit por vetor)
+static ALLOCATED_VECTORS: Spinlock<[u64; 4]> = Spinlock::new([0; 4]);
+
+fn vector_in_use(bitmap: &[u64; 4], vector: u8) -> bool {
+    bitmap[vector as usize / 64] & (1 << (vector % 64)) != 0
+}
+
+/// Reserva `count` vetores livres (não necessariamente contíguos).
+///
+/// Tudo ou nada: `None` se não houver vetores suficientes. Os vetores saem
+/// com um handler padrão que só envia EOI ao LAPIC; o driver instala o seu
+/// com [`install_handler`].
+pub fn alloc_vectors(count: usize) -> Option<Vec<u8>> {
+    let mut bitmap = ALLOCATED_VECTORS.lock();
+    let vectors: Vec<u8> = (DYNAMIC_VECTOR_START..=DYNAMIC_VECTOR_END)
+        .filter(|&v| !vector_in_use(&bitmap, v))
+        .take(count)
+        .collect();
+    if vectors.len() < count {
+        return None;
+    }
+
+    let default = crate::arch::x86_64::interrupts::unhandled_vector_handler as *const () as u64;
+    for &vector in &vectors {
+        bitmap[vector as usize / 64] |= 1 << (vector % 64);
+        install_entry(vector, default);
+    }
+    Some(vectors)
+}
+
+/// Devolve um vetor reservado por [`alloc_vectors`]
+pub fn free_vector(vector: u8) {
+    let mut bitmap = ALLOCATED_VECTORS.lock();
+    if !vector_in_use(&bitmap, vector) {
+        return;
+    }
+    bitmap[vector as usize / 64] &= !(1 << (vector % 64));
+    let default = crate::arch::x86_64::interrupts::unhandled_vector_handler as *const () as u64;
+    install_entry(vector, default);
+}
+
+/// Instala o handler de um vetor reservado por [`alloc_vectors`].
+///
+/// O handler deve terminar com `apic::lapic::eoi()` (MSIs chegam pelo LAPIC,
+/// não pelo PIC).
+pub fn install_handler(vector: u8, handler: HandlerFunc) -> bool {
+    let bitmap = ALLOCATED_VECTORS.lock();
+    if !vector_in_use(&bitmap, vector) {
+        return false;
+    }
+    install_entry(vector, handler);
+    true
+}
+
+fn install_entry(vector: u8, handler: HandlerFunc) {
+    // Chamado com o lock dos vetores (IRQs desligadas); a IDT já carregada
+    // enxerga a alteração sem novo `lidt`
+    let idt = unsafe { &mut *core::ptr::addr_of_mut!(IDT) };
+    idt.set_handler(vector, handler);
+}
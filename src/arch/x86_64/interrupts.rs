@@ -11,6 +11,7 @@
 //! - **Preempção:** O Timer (IRQ 0) é o gatilho que permite ao kernel retomar o
 //!   controle da CPU em intervalos regulares.
 use crate::arch::x86_64::idt::IDT;
+use crate::sched::signal::{SIGFPE, SIGILL, SIGSEGV};
 
 /// Stack Frame pushed by CPU on exception
 #[repr(C)]
@@ -63,13 +64,32 @@ pub fn init_idt() {
     // Agora usamos o handler asm 'timer_handler' para permitir preempção
     idt.set_handler(32, timer_handler as *const () as u64);
     idt.set_handler(33, keyboard_interrupt_handler as *const () as u64);
+    idt.set_handler(36, serial_interrupt_handler as *const () as u64);
     idt.set_handler(44, mouse_interrupt_handler as *const () as u64);
 
+    // Timer do LAPIC: mesmo handler do PIT (ver core::time::tick)
+    idt.set_handler(
+        crate::arch::x86_64::apic::lapic::TIMER_VECTOR,
+        timer_handler as *const () as u64,
+    );
+    idt.set_handler(
+        crate::arch::x86_64::apic::lapic::SPURIOUS_VECTOR,
+        spurious_interrupt_handler as *const () as u64,
+    );
+
     unsafe {
         idt.load();
     }
 }
 
+/// Carrega a IDT já montada por [`init_idt`] no core atual (APs).
+///
+/// # Safety
+/// `init_idt` já executado no BSP.
+pub unsafe fn load_idt() {
+    (*core::ptr::addr_of!(IDT)).load();
+}
+
 // =============================================================================
 // HANDLERS ASM (IRQs Simples)
 // =============================================================================
@@ -81,6 +101,11 @@ extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: ExceptionStac
     crate::arch::x86_64::ports::outb(0x20, 0x20); // EOI Master
 }
 
+extern "x86-interrupt" fn serial_interrupt_handler(_stack_frame: ExceptionStackFrame) {
+    crate::drivers::serial::handle_irq();
+    crate::arch::x86_64::ports::outb(0x20, 0x20); // EOI Master
+}
+
 extern "x86-interrupt" fn mouse_interrupt_handler(_stack_frame: ExceptionStackFrame) {
     crate::kdebug!("(Arch) Mouse Interrupt fired");
     crate::drivers::input::mouse::handle_irq();
@@ -88,6 +113,15 @@ extern "x86-interrupt" fn mouse_interrupt_handler(_stack_frame: ExceptionStackFr
     crate::arch::x86_64::ports::outb(0x20, 0x20); // EOI Master
 }
 
+/// Spurious do LAPIC: não há ISR a limpar, então sem EOI
+extern "x86-interrupt" fn spurious_interrupt_handler(_stack_frame: ExceptionStackFrame) {}
+
+/// Handler padrão dos vetores dinâmicos (MSI/MSI-X) ainda sem dono
+pub(crate) extern "x86-interrupt" fn unhandled_vector_handler(_stack_frame: ExceptionStackFrame) {
+    crate::kwarn!("(Arch) Interrupção em vetor dinâmico sem handler");
+    unsafe { crate::arch::x86_64::apic::lapic::eoi() };
+}
+
 // =============================================================================
 // HANDLERS RUST (INNER)
 // =============================================================================
@@ -96,7 +130,11 @@ extern "x86-interrupt" fn mouse_interrupt_handler(_stack_frame: ExceptionStackFr
 ///
 /// Este handler é responsável por:
 /// 1. Incrementar contador de ticks do sistema (jiffies).
-/// 2. Enviar EOI para o PIC.
+/// 2. Notificar o scheduler (quantum e "need resched").
+/// 3. Acordar tasks da SleepQueue e liberar trabalho com atraso vencido.
+/// 4. Enviar EOI para a fonte do tick (PIT ou timer do LAPIC).
+///
+/// A troca de contexto em si acontece no ASM, ao retornar para user mode.
 #[no_mangle]
 pub extern "C" fn timer_handler_inner() {
     // 1. Incrementar contador de jiffies (usado para sleep, timeouts, etc)
@@ -105,11 +143,13 @@ pub extern "C" fn timer_handler_inner() {
     // 2. Notificar o scheduler sobre a passagem de tempo (Time-Slicing)
     crate::sched::core::scheduler::timer_tick();
 
-    // 3. Verificar se há tasks para acordar na SleepQueue
+    // 3. Verificar se há tasks para acordar na SleepQueue e trabalho
+    // com atraso vencido (vai para a fila do worker)
     crate::sched::core::sleep_queue::check_sleep_queue();
+    crate::core::work::delayed::run_expired(crate::core::time::now_ns());
 
-    // 4. Enviar EOI para o PIC (Master = 0x20)
-    crate::arch::x86_64::ports::outb(0x20, 0x20);
+    // 4. Enviar EOI (PIC ou LAPIC, conforme a fonte do tick)
+    crate::core::time::tick::eoi();
 }
 
 /// Inicializa e remapeia o PIC (Programmable Interrupt Controller) 8259
@@ -198,13 +238,13 @@ pub fn pic_disable_irq(irq: u8) {
 pub extern "C" fn divide_error_handler_inner(stack_frame: *const ExceptionStackFrame) {
     // Reconstruímos a referência a partir do ponteiro
     let frame = unsafe { &*stack_frame };
-    handle_fault("Divide Error (#DE)", frame, None, None);
+    handle_fault("Divide Error (#DE)", frame, None, None, SIGFPE);
 }
 
 #[no_mangle]
 pub extern "C" fn invalid_opcode_handler_inner(stack_frame: *const ExceptionStackFrame) {
     let frame = unsafe { &*stack_frame };
-    handle_fault("Invalid Opcode (#UD)", frame, None, None);
+    handle_fault("Invalid Opcode (#UD)", frame, None, None, SIGILL);
 }
 
 #[no_mangle]
@@ -224,18 +264,40 @@ pub extern "C" fn page_fault_handler_inner(
         core::arch::asm!("mov {}, cr2", out(reg) cr2, options(nomem, nostack, preserves_flags));
     }
 
-    // 1. Tentar resolver a falta de página via subsistema de memória
     use crate::mm::fault::{handle_page_fault, FaultResult, PageFaultInfo};
     let info = PageFaultInfo::from_error_code(cr2, frame.instruction_pointer, error_code);
 
+    // 0. Kernel tocando memória de usuário fora de um UserAccessGuard (SMAP)
+    // ou executando-a (SMEP): bug do kernel, nada a popular
+    if super::smap::is_violation(error_code, cr2, frame.cpu_flags) {
+        crate::kerror!("(SMAP) Acesso do kernel a página de usuário:", cr2);
+        info.dump();
+        handle_fault(
+            "Page Fault (#PF, SMEP/SMAP)",
+            frame,
+            Some(error_code),
+            Some(cr2),
+            SIGSEGV,
+        );
+        return;
+    }
+
+    // 1. Tentar resolver a falta de página pelas VMAs da task atual
+
     match handle_page_fault(info) {
         FaultResult::Success => {
-            // Falta resolvida (ex: lazy allocation ou COW), podemos retornar e repetir a instrução
-            return;
+            // Falta resolvida (populada, COW ou stack estendida): a instrução é repetida
         }
         _ => {
-            // Falha não pôde ser resolvida rotineiramente, chama o tratador genérico (panic/kill)
-            handle_fault("Page Fault (#PF)", frame, Some(error_code), Some(cr2));
+            // Sem VMA ou acesso inválido: SIGSEGV na task (user) ou panic (kernel)
+            info.dump();
+            handle_fault(
+                "Page Fault (#PF)",
+                frame,
+                Some(error_code),
+                Some(cr2),
+                SIGSEGV,
+            );
         }
     }
 }
@@ -251,6 +313,7 @@ pub extern "C" fn general_protection_handler_inner(
         frame,
         Some(error_code),
         None,
+        SIGSEGV,
     );
 }
 
@@ -266,12 +329,17 @@ pub extern "C" fn double_fault_handler_inner(
 }
 
 /// Trata falhas de CPU decidindo se deve matar o processo ou dar Panic no kernel.
-#[allow(dead_code)]
+///
+/// Em user mode o processo recebe `signal`, entregue pelo wrapper no retorno
+/// para user mode (`signal_return_to_user`): um handler registrado roda, a
+/// ação padrão termina a task com `128 + signal`. Em kernel mode o estado da
+/// CPU é registrado antes do panic.
 fn handle_fault(
     name: &str,
     stack_frame: &ExceptionStackFrame,
     error_code: Option<u64>,
     extra_info: Option<u64>,
+    signal: i32,
 ) {
     let is_user = (stack_frame.code_segment & 3) == 3;
 
@@ -286,17 +354,29 @@ fn handle_fault(
         if let Some(info) = extra_info {
             crate::kerror!("Extra Info (ex: CR2):", info);
         }
-        crate::kerror!("Ação: Encerrando processo infrator.");
+        crate::kerror!("Ação: Sinal para o processo infrator:", signal);
 
-        // Encerra a task atual e pula para a próxima via scheduler
-        crate::sched::core::scheduler::exit_current(-1);
+        // Entregue na saída do wrapper, antes do iretq
+        crate::sched::signal::raise_current(signal);
     } else {
         crate::kerror!("!!! KERNEL PANIC !!!");
         crate::kerror!("Exceção crítica no modo kernel:", name);
         crate::kerror!("RIP:", stack_frame.instruction_pointer);
+        crate::kerror!("CS:", stack_frame.code_segment);
+        crate::kerror!("RFLAGS:", stack_frame.cpu_flags);
+        crate::kerror!("RSP:", stack_frame.stack_pointer);
+        crate::kerror!("CR3:", crate::arch::x86_64::cpu::Cpu::read_cr3());
         if let Some(err) = error_code {
             crate::kerror!("Error Code:", err);
         }
+        if let Some(info) = extra_info {
+            crate::kerror!("CR2:", info);
+        }
+        if let Some(current) = crate::sched::core::CURRENT.try_lock() {
+            if let Some(task) = current.as_ref() {
+                crate::kerror!("Task atual (TID):", task.tid.as_u32());
+            }
+        }
         panic!("Falha Crítica no Kernel!");
     }
 }
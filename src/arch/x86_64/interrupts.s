@@ -17,6 +17,7 @@
 .extern should_reschedule
 .extern clear_need_resched
 .extern schedule
+.extern signal_return_to_user
 
 # =============================================================================
 # MACROS
@@ -46,6 +47,92 @@
     pop rax
 .endm
 
+# TrapFrame completo (mesma ordem de syscall.s / syscall::TrapFrame), logo
+# abaixo do frame de hardware. Usado pelos caminhos que podem voltar para
+# user mode com um sinal a entregar.
+# Com o TrapFrame (15 * 8 = 120 bytes): RIP em [rsp + 120], CS em [rsp + 128].
+
+# Todos menos RAX (ver TRAP_ENTRY_ERR)
+.macro PUSH_TRAP_REST
+    push rbx
+    push rcx
+    push rdx
+    push rbp
+    push rdi
+    push rsi
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+.endm
+
+.macro POP_TRAP_REGS
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rsi
+    pop rdi
+    pop rbp
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+.endm
+
+# Vindo de ring 3: limpa RFLAGS.AC, que o usuário controla (popf) e que
+# suspenderia o SMAP no kernel. O AC do usuário volta no iretq.
+.macro CLEAR_AC
+    pushfq
+    btr qword ptr [rsp], 18
+    popfq
+.endm
+
+# Entrada sem error code
+.macro TRAP_ENTRY
+    push rax
+    PUSH_TRAP_REST
+    test byte ptr [rsp + 128], 3
+    jz .L_trap_entry_\@
+    swapgs
+    CLEAR_AC
+.L_trap_entry_\@:
+.endm
+
+# Entrada com error code: o RAX ocupa o slot do error code, que vai em RSI
+# (segundo argumento). O TrapFrame fica contíguo ao frame de hardware.
+.macro TRAP_ENTRY_ERR
+    xchg rax, [rsp]
+    PUSH_TRAP_REST
+    mov rsi, rax
+    test byte ptr [rsp + 128], 3
+    jz .L_trap_entry_\@
+    swapgs
+    CLEAR_AC
+.L_trap_entry_\@:
+.endm
+
+# Saída: voltando para user mode, entrega sinais pendentes (podem
+# redirecionar o TrapFrame para um handler) e restaura o GS do usuário
+.macro TRAP_RETURN
+    test byte ptr [rsp + 128], 3
+    jz .L_trap_return_\@
+    mov rdi, rsp
+    call signal_return_to_user
+    swapgs
+.L_trap_return_\@:
+    POP_TRAP_REGS
+    iretq
+.endm
+
 # =============================================================================
 # EXCEPTION HANDLERS (WRAPPERS)
 # =============================================================================
@@ -54,40 +141,19 @@
 # DIVIDE ERROR (#DE) - NO ERROR CODE
 # -----------------------------------------------------------------------------
 divide_error_wrapper:
-    PUSH_SCRATCH_REGS
-    # Checar se veio de User Mode (CS & 3 == 3)
-    # CS esta em [rsp + 72 + 8] = [rsp + 80]
-    test byte ptr [rsp + 80], 3
-    jz .L_div_no_swap_in
-    swapgs
-.L_div_no_swap_in:
-    lea rdi, [rsp + 72]      # RDI = Ponteiro para stack frame
+    TRAP_ENTRY
+    lea rdi, [rsp + 120]     # RDI = Ponteiro para stack frame
     call divide_error_handler_inner
-    
-    test byte ptr [rsp + 80], 3
-    jz .L_div_no_swap_out
-    swapgs
-.L_div_no_swap_out:
-    POP_SCRATCH_REGS
-    iretq
+    TRAP_RETURN
 
 # -----------------------------------------------------------------------------
 # INVALID OPCODE (#UD) - NO ERROR CODE
 # -----------------------------------------------------------------------------
 invalid_opcode_wrapper:
-    PUSH_SCRATCH_REGS
-    test byte ptr [rsp + 80], 3
-    jz .L_ud_no_swap_in
-    swapgs
-.L_ud_no_swap_in:
-    lea rdi, [rsp + 72]
+    TRAP_ENTRY
+    lea rdi, [rsp + 120]
     call invalid_opcode_handler_inner
-    test byte ptr [rsp + 80], 3
-    jz .L_ud_no_swap_out
-    swapgs
-.L_ud_no_swap_out:
-    POP_SCRATCH_REGS
-    iretq
+    TRAP_RETURN
 
 # -----------------------------------------------------------------------------
 # BREAKPOINT (#BP) - NO ERROR CODE
@@ -109,47 +175,22 @@ breakpoint_wrapper:
 
 # -----------------------------------------------------------------------------
 # PAGE FAULT (#PF) - WITH ERROR CODE
-# Stack: [Regs(72), ERR(8), RIP(8), CS(8)]
+# Stack: [TrapFrame(120), RIP(8), CS(8)]; o error code vai em RSI
 # -----------------------------------------------------------------------------
 page_fault_wrapper:
-    PUSH_SCRATCH_REGS
-    # CS esta em 72 + 8 + 8 = 88
-    test byte ptr [rsp + 88], 3
-    jz .L_pf_no_swap_in
-    swapgs
-.L_pf_no_swap_in:
-    lea rdi, [rsp + 80]      # RDI = Stack frame (pula erro)
-    mov rsi, [rsp + 72]      # RSI = Error Code
+    TRAP_ENTRY_ERR
+    lea rdi, [rsp + 120]     # RDI = Stack frame
     call page_fault_handler_inner
-    
-    test byte ptr [rsp + 88], 3
-    jz .L_pf_no_swap_out
-    swapgs
-.L_pf_no_swap_out:
-    POP_SCRATCH_REGS
-    add rsp, 8            # Pop error code
-    iretq
+    TRAP_RETURN
 
 # -----------------------------------------------------------------------------
 # GENERAL PROTECTION (#GP) - WITH ERROR CODE
 # -----------------------------------------------------------------------------
 general_protection_wrapper:
-    PUSH_SCRATCH_REGS
-    test byte ptr [rsp + 88], 3
-    jz .L_gp_no_swap_in
-    swapgs
-.L_gp_no_swap_in:
-    lea rdi, [rsp + 80]
-    mov rsi, [rsp + 72]
+    TRAP_ENTRY_ERR
+    lea rdi, [rsp + 120]
     call general_protection_handler_inner
-    
-    test byte ptr [rsp + 88], 3
-    jz .L_gp_no_swap_out
-    swapgs
-.L_gp_no_swap_out:
-    POP_SCRATCH_REGS
-    add rsp, 8
-    iretq
+    TRAP_RETURN
 
 # -----------------------------------------------------------------------------
 # DOUBLE FAULT (#DF) - WITH ERROR CODE
@@ -176,50 +217,28 @@ double_fault_wrapper:
 # TIMER HANDLER (IRQ 0)
 # =============================================================================
 timer_handler:
-    PUSH_SCRATCH_REGS
-    
-    # 1. Checar se viemos de User Mode (CS & 3 == 3)
-    # CS salvo em [rsp + 72 (scratch) + 8 (RIP) + 8 (CS)] = [rsp + 80]
-    test byte ptr [rsp + 80], 3
-    jz .L_timer_kernel
-    
-    # Se User Mode: precisamos de SWAPGS para ter acesso ao Kernel GS (se usado)
-    swapgs
-    
-.L_timer_kernel:
-    # 2. Chamar handler Rust (inc jiffies, etc)
+    TRAP_ENTRY
+
+    # 1. Chamar handler Rust (inc jiffies, etc)
     call timer_handler_inner
-    
-    # 3. Verificar Preempção
+
+    # 2. Verificar Preempção
     # Se estávamos em Kernel Mode (CS & 3 == 0), NÃO fazemos preempção agora
     # (Simples kernel não-preemptivo por enquanto, ou lógica complexa de irq_count)
-    mov rax, [rsp + 80]
-    and rax, 3
-    cmp rax, 3
-    jne .L_timer_exit # Retorna se veio do Kernel
-    
+    test byte ptr [rsp + 128], 3
+    jz .L_timer_exit # Retorna se veio do Kernel
+
     # Se chegamos aqui, viemos de User Mode. Podemos agendar.
     call should_reschedule
     test al, al
     jz .L_timer_exit
-    
+
     call clear_need_resched
-    
-    # Preempção:
-    # Salvar callee-saved e chamar schedule
-    push r12
-    mov r12, rsp
-    and rsp, -16
+
+    # Preempção: os registradores do usuário estão todos no TrapFrame e a
+    # stack está alinhada a 16
     call schedule
-    mov rsp, r12
-    pop r12
 
 .L_timer_exit:
-    # 4. Restaurar SwapGS se necessário
-    test byte ptr [rsp + 80], 3
-    jz .L_timer_iret
-    swapgs
-
-.L_timer_iret:
-    POP_SCRATCH_REGS
-    iretq
+    # 3. Sinais, SwapGS e retorno
+    TRAP_RETURN
@@ -0,0 +1,183 @@
+/// Arquivo: x86_64/iommu/domain.rs
+///
+/// Propósito: Domínios de remapeamento de DMA.
+/// Cada domínio tem sua própria tabela de páginas de I/O (formato second-level
+/// do VT-d, 4 níveis / 48 bits). Um dispositivo anexado ao domínio só alcança
+/// os IOVAs mapeados aqui; qualquer outro endereço resulta em fault de DMA.
+///
+/// Detalhes de Implementação:
+/// - Tabelas alocadas no PMM e acessadas via HHDM.
+/// - PTE: bit 0 = Read, bit 1 = Write, bits 12-51 = endereço físico.
+/// - `root()` é o valor a gravar no context entry do dispositivo.
+use crate::mm::pmm::{FRAME_ALLOCATOR, FRAME_SIZE};
+use crate::mm::PhysAddr;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+const PTE_READ: u64 = 1 << 0;
+const PTE_WRITE: u64 = 1 << 1;
+const PTE_ADDR_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+/// Níveis da tabela (AGAW de 48 bits)
+const LEVELS: usize = 4;
+const ENTRIES: usize = 512;
+
+/// Domain ID 0 é reservado pelo VT-d
+static NEXT_DOMAIN_ID: AtomicU16 = AtomicU16::new(1);
+
+/// Erros de domínio IOMMU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainError {
+    /// Sem frames para tabelas
+    OutOfMemory,
+    /// IOVA ou endereço físico não alinhado a 4KB
+    Unaligned,
+    /// IOVA já mapeado
+    AlreadyMapped,
+}
+
+/// Domínio de isolamento de DMA
+pub struct IommuDomain {
+    id: u16,
+    root: PhysAddr,
+    /// Todas as tabelas do domínio (incluindo a raiz), liberadas no `Drop`
+    tables: Vec<PhysAddr>,
+}
+
+impl IommuDomain {
+    /// Cria um domínio vazio: nenhum IOVA é acessível.
+    pub fn new() -> Result<Self, DomainError> {
+        let root = alloc_table()?;
+        Ok(Self {
+            id: NEXT_DOMAIN_ID.fetch_add(1, Ordering::Relaxed),
+            root,
+            tables: alloc::vec![root],
+        })
+    }
+
+    /// Domain ID (para o context entry)
+    #[inline]
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Endereço físico da tabela raiz (second-level page table pointer)
+    #[inline]
+    pub fn root(&self) -> PhysAddr {
+        self.root
+    }
+
+    /// Mapeia a página `iova` para o frame `phys`.
+    pub fn map(&mut self, iova: u64, phys: PhysAddr, writable: bool) -> Result<(), DomainError> {
+        if iova % FRAME_SIZE != 0 || phys.as_u64() % FRAME_SIZE != 0 {
+            return Err(DomainError::Unaligned);
+        }
+
+        let mut table = self.root;
+        for level in (1..LEVELS).rev() {
+            let entry = entry_ptr(table, index(iova, level));
+            // SAFETY: `table` é uma tabela deste domínio, mapeada via HHDM
+            let value = unsafe { entry.read_volatile() };
+            table = if value & PTE_READ != 0 {
+                PhysAddr::new(value & PTE_ADDR_MASK)
+            } else {
+                let next = alloc_table()?;
+                self.tables.push(next);
+                // Tabelas intermediárias permitem tudo; a folha decide
+                unsafe { entry.write_volatile(next.as_u64() | PTE_READ | PTE_WRITE) };
+                next
+            };
+        }
+
+        let leaf = entry_ptr(table, index(iova, 0));
+        // SAFETY: idem
+        unsafe {
+            if leaf.read_volatile() & PTE_READ != 0 {
+                return Err(DomainError::AlreadyMapped);
+            }
+            let rights = if writable {
+                PTE_READ | PTE_WRITE
+            } else {
+                PTE_READ
+            };
+            leaf.write_volatile(phys.as_u64() | rights);
+        }
+        Ok(())
+    }
+
+    /// Remove o mapeamento de `iova`, retornando o frame que estava lá.
+    pub fn unmap(&mut self, iova: u64) -> Option<PhysAddr> {
+        let leaf = self.leaf(iova)?;
+        // SAFETY: folha de uma tabela deste domínio
+        unsafe {
+            let value = leaf.read_volatile();
+            leaf.write_volatile(0);
+            Some(PhysAddr::new(value & PTE_ADDR_MASK))
+        }
+    }
+
+    /// Traduz `iova` como o hardware faria. `None` = DMA bloqueado.
+    pub fn translate(&self, iova: u64) -> Option<PhysAddr> {
+        let leaf = self.leaf(iova)?;
+        // SAFETY: folha de uma tabela deste domínio
+        let value = unsafe { leaf.read_volatile() };
+        Some(PhysAddr::new(
+            (value & PTE_ADDR_MASK) | (iova & (FRAME_SIZE - 1)),
+        ))
+    }
+
+    /// PTE folha presente que cobre `iova`
+    fn leaf(&self, iova: u64) -> Option<*mut u64> {
+        let mut table = self.root;
+        for level in (1..LEVELS).rev() {
+            // SAFETY: tabelas deste domínio, mapeadas via HHDM
+            let value = unsafe { entry_ptr(table, index(iova, level)).read_volatile() };
+            if value & PTE_READ == 0 {
+                return None;
+            }
+            table = PhysAddr::new(value & PTE_ADDR_MASK);
+        }
+        let leaf = entry_ptr(table, index(iova, 0));
+        // SAFETY: idem
+        if unsafe { leaf.read_volatile() } & PTE_READ == 0 {
+            return None;
+        }
+        Some(leaf)
+    }
+}
+
+impl Drop for IommuDomain {
+    fn drop(&mut self) {
+        let pmm = FRAME_ALLOCATOR.lock();
+        for &table in &self.tables {
+            pmm.deallocate_frame(table);
+        }
+    }
+}
+
+// --- Funções internas ---
+
+fn index(iova: u64, level: usize) -> usize {
+    ((iova >> (12 + 9 * level)) as usize) & (ENTRIES - 1)
+}
+
+fn entry_ptr(table: PhysAddr, index: usize) -> *mut u64 {
+    // SAFETY (dos callers): index < 512, dentro do frame da tabela
+    unsafe { crate::mm::addr::phys_to_virt::<u64>(table.as_u64()).add(index) }
+}
+
+fn alloc_table() -> Result<PhysAddr, DomainError> {
+    let frame = FRAME_ALLOCATOR
+        .lock()
+        .allocate_frame()
+        .ok_or(DomainError::OutOfMemory)?;
+    // SAFETY: frame recém-alocado, exclusivo desta tabela
+    unsafe {
+        core::ptr::write_bytes(
+            crate::mm::addr::phys_to_virt::<u8>(frame.as_u64()),
+            0,
+            FRAME_SIZE as usize,
+        );
+    }
+    Ok(frame)
+}
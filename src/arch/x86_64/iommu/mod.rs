@@ -5,9 +5,13 @@
 /// e prover isolamento/proteção (DMA Remapping).
 ///
 /// Módulos contidos:
+/// - `domain`: Domínios de isolamento (tabelas de páginas de I/O).
 /// - `intel_vtd`: Implementação específica para Intel VT-d.
+pub mod domain;
 pub mod intel_vtd;
 
+pub use domain::{DomainError, IommuDomain};
+
 pub fn is_available() -> bool {
     false
 }
@@ -1,11 +1,13 @@
 //! Implementação x86_64
 
 pub mod cpu;
+pub mod cpuid;
 pub mod gdt;
 pub mod idt;
 pub mod interrupts;
 pub mod memory;
 pub mod ports;
+pub mod smap;
 pub mod syscall;
 
 pub mod acpi;
@@ -15,24 +17,34 @@ pub mod smp;
 pub mod vmm;
 
 pub use cpu::Cpu;
+pub use cpuid::{features as cpu_features, CpuFeatures};
 
-/// Inicializa o básico da arquitetura: GDT, IDT, PICS, Syscall.
+/// Inicializa o básico da arquitetura: GDT, área por CPU, IDT, PICS, Syscall.
 ///
 /// # Safety
 ///
 /// Deve ser chamado no início do boot, single-core.
 pub unsafe fn init_basics() {
+    cpuid::init();
     gdt::init();
+    // GS base → área do BSP (this_cpu, syscall.s)
+    crate::core::smp::percpu::init_bsp();
     interrupts::init_idt();
     interrupts::init_pics(); // Remapear PIC para 32-47
 
-    // Inicializar PIT (Timer) - 100 Hz
-    crate::drivers::timer::pit::init(100);
-
-    // Inicializar syscall MSRs
+    // Inicializar PIT (Timer) na frequência do tick; o IRQ 0 só é
+    // desmascarado se o timer do LAPIC não puder ser usado
+    crate::drivers::timer::pit::init(crate::core::time::HZ as u32);
 
     // Inicializar syscall MSRs
     syscall::init();
 
     crate::kinfo!("(Arch) Basics initialized (GDT, IDT, Syscall)");
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(feature = "smap_test")]
+pub mod test;
@@ -11,7 +11,13 @@
 
 // IO Ports (legado x86)
 
+// Testes de host rodam em user mode, onde `in`/`out` dão #GP: sem hardware,
+// as leituras devolvem 0xFF (barramento flutuante) e as escritas somem.
+#[cfg(test)]
+pub use host::*;
+
 /// Lê um byte de uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn inb(port: u16) -> u8 {
     let value: u8;
@@ -28,6 +34,7 @@ pub fn inb(port: u16) -> u8 {
 }
 
 /// Escreve um byte em uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn outb(port: u16, value: u8) {
     // SAFETY: IO ports são operações privilegiadas mas seguras do ponto de vista de memória
@@ -42,6 +49,7 @@ pub fn outb(port: u16, value: u8) {
 }
 
 /// Lê um word (16 bits) de uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn inw(port: u16) -> u16 {
     let value: u16;
@@ -58,6 +66,7 @@ pub fn inw(port: u16) -> u16 {
 }
 
 /// Escreve um word em uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn outw(port: u16, value: u16) {
     // SAFETY: IO ports são operações privilegiadas mas seguras do ponto de vista de memória
@@ -72,6 +81,7 @@ pub fn outw(port: u16, value: u16) {
 }
 
 /// Lê um dword (32 bits) de uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn inl(port: u16) -> u32 {
     let value: u32;
@@ -88,6 +98,7 @@ pub fn inl(port: u16) -> u32 {
 }
 
 /// Escreve um dword em uma porta IO
+#[cfg(not(test))]
 #[inline]
 pub fn outl(port: u16, value: u32) {
     // SAFETY: IO ports são operações privilegiadas mas seguras do ponto de vista de memória
@@ -110,3 +121,24 @@ pub fn io_wait() {
     // Porta 0x80 é usada para POST codes, escrever lá é seguro e causa um pequeno delay
     outb(0x80, 0);
 }
+
+#[cfg(test)]
+mod host {
+    pub fn inb(_port: u16) -> u8 {
+        0xFF
+    }
+
+    pub fn outb(_port: u16, _value: u8) {}
+
+    pub fn inw(_port: u16) -> u16 {
+        0xFFFF
+    }
+
+    pub fn outw(_port: u16, _value: u16) {}
+
+    pub fn inl(_port: u16) -> u32 {
+        0xFFFF_FFFF
+    }
+
+    pub fn outl(_port: u16, _value: u32) {}
+}
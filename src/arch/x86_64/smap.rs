@@ -0,0 +1,178 @@
+//! SMEP e SMAP
+//!
+//! Isolam o kernel das páginas de usuário (U/S = 1):
+//! - SMEP (CR4.20): o kernel não executa código de usuário;
+//! - SMAP (CR4.21): o kernel não lê nem escreve memória de usuário, a menos
+//!   que RFLAGS.AC esteja ligado.
+//!
+//! Acesso deliberado (cópias de/para userspace) é feito dentro de um
+//! [`UserAccessGuard`], que liga o AC (`stac`) e o desliga ao sair do escopo
+//! (`clac`). Qualquer outro acesso vira page fault no kernel.
+//!
+//! Os bits são ligados por CPU, só se o CPUID os anunciar: no BSP por
+//! [`init`], depois da memória (o identity map do bootloader não é mais
+//! usado), e nos APs por [`init_ap`]. O AC do usuário não entra no kernel:
+//! o FMASK limpa na syscall e os wrappers de trap, na entrada vinda de
+//! ring 3.
+
+use super::cpu::Cpu;
+use super::cpuid;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// CR4: Supervisor Mode Execution Prevention
+pub const CR4_SMEP: u64 = 1 << 20;
+/// CR4: Supervisor Mode Access Prevention
+pub const CR4_SMAP: u64 = 1 << 21;
+/// RFLAGS: Alignment Check / Access Control
+pub const RFLAGS_AC: u64 = 1 << 18;
+
+/// Fim (exclusivo) da metade baixa, onde ficam as páginas de usuário
+const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// Bits do error code do #PF
+const PF_PRESENT: u64 = 1 << 0;
+const PF_USER: u64 = 1 << 2;
+const PF_INSTRUCTION: u64 = 1 << 4;
+
+/// SMAP ligado no BSP (e, portanto, em todos os cores)
+static SMAP_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Bits de CR4 que o CPUID permite ligar
+fn cr4_bits() -> u64 {
+    let features = cpuid::features();
+    let mut bits = 0;
+    if features.smep {
+        bits |= CR4_SMEP;
+    }
+    if features.smap {
+        bits |= CR4_SMAP;
+    }
+    bits
+}
+
+/// Liga SMEP/SMAP no BSP, conforme o CPUID.
+pub fn init() {
+    let bits = cr4_bits();
+    if bits == 0 {
+        crate::kwarn!("(SMAP) CPU sem SMEP/SMAP; kernel sem isolamento de páginas de usuário");
+        return;
+    }
+    // SAFETY: só bits anunciados pelo CPUID; páginas do kernel não têm U/S
+    unsafe { Cpu::write_cr4(Cpu::read_cr4() | bits) };
+    SMAP_ENABLED.store(bits & CR4_SMAP != 0, Ordering::Relaxed);
+    crate::kinfo!("(SMAP) SMEP ligado:", (bits & CR4_SMEP != 0) as u64);
+    crate::kinfo!("(SMAP) SMAP ligado:", (bits & CR4_SMAP != 0) as u64);
+
+    #[cfg(feature = "smap_test")]
+    super::test::run_tests();
+}
+
+/// Liga no AP os mesmos bits do BSP (o trampolim não os herda)
+pub fn init_ap() {
+    let bits = cr4_bits();
+    if bits != 0 {
+        // SAFETY: idem a `init`
+        unsafe { Cpu::write_cr4(Cpu::read_cr4() | bits) };
+    }
+}
+
+/// SMAP está ligado?
+pub fn is_enabled() -> bool {
+    SMAP_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Um #PF com `error_code` em `addr`, com o RFLAGS `rflags` do momento da
+/// falta, é o kernel tocando memória de usuário (SMAP, fora de um
+/// [`UserAccessGuard`]) ou executando-a (SMEP)?
+pub fn is_violation(error_code: u64, addr: u64, rflags: u64) -> bool {
+    violates(is_enabled(), error_code, addr, rflags)
+}
+
+fn violates(smap: bool, error_code: u64, addr: u64, rflags: u64) -> bool {
+    // Página presente, acesso do kernel, endereço de usuário
+    if error_code & PF_PRESENT == 0 || error_code & PF_USER != 0 || addr >= USER_SPACE_END {
+        return false;
+    }
+    if error_code & PF_INSTRUCTION != 0 {
+        return true;
+    }
+    smap && rflags & RFLAGS_AC == 0
+}
+
+/// Janela de acesso deliberado à memória de usuário.
+///
+/// Liga o RFLAGS.AC na criação e o desliga no drop; sem SMAP não faz nada.
+/// Aninhar é permitido: só o guard externo desliga o AC.
+///
+/// A troca de contexto não salva o RFLAGS: o guard não pode atravessar nada
+/// que bloqueie ou ceda a CPU. Mantê-lo em volta da cópia, e só dela.
+#[must_use]
+pub struct UserAccessGuard {
+    /// Este guard ligou o AC (não estava ligado antes)
+    clear_on_drop: bool,
+    /// Preso ao core (e à task) que ligou o AC
+    _not_send: PhantomData<*const ()>,
+}
+
+impl UserAccessGuard {
+    #[inline]
+    pub fn new() -> Self {
+        let clear_on_drop = is_enabled() && Cpu::read_rflags() & RFLAGS_AC == 0;
+        if clear_on_drop {
+            // SAFETY: SMAP ligado, então a CPU tem stac
+            unsafe { core::arch::asm!("stac", options(nostack)) };
+        }
+        Self {
+            clear_on_drop,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Default for UserAccessGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for UserAccessGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if self.clear_on_drop {
+            // SAFETY: idem a `new`
+            unsafe { core::arch::asm!("clac", options(nostack)) };
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations() {
+        let user = 0x40_1000;
+        let kernel = 0xFFFF_8000_0010_0000;
+        let present = PF_PRESENT;
+
+        // Leitura/escrita do kernel em página de usuário presente, AC desligado
+        assert!(violates(true, present, user, 0));
+        assert!(violates(true, present | 0b10, user, 0x202));
+        // Dentro de um guard (AC ligado), ou sem SMAP: falta comum
+        assert!(!violates(true, present, user, RFLAGS_AC));
+        assert!(!violates(false, present, user, 0));
+        // Execução de página de usuário: SMEP, independe do AC
+        assert!(violates(false, present | PF_INSTRUCTION, user, RFLAGS_AC));
+
+        // Página ausente (demand paging), falta em user mode, ou endereço do
+        // kernel: não é violação
+        assert!(!violates(true, 0, user, 0));
+        assert!(!violates(true, present | PF_USER, user, 0));
+        assert!(!violates(true, present, kernel, 0));
+    }
+}
@@ -3,6 +3,7 @@
 //! Módulo principal de SMP.
 
 pub mod tlb;
+pub mod trampoline;
 
 // Re-exports
 pub use tlb::{flush_all, invalidate_page, invalidate_range};
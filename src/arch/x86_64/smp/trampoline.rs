@@ -0,0 +1,91 @@
+//! # Trampolim dos APs
+//!
+//! Código que leva um AP do SIPI (modo real) ao long mode, em trampoline.s.
+//! Ele roda de uma página fixa na memória baixa: [`install`] copia o código
+//! para [`TRAMPOLINE_BASE`] e grava os parâmetros do próximo AP
+//! ([`ApBootData`]). Um AP por vez: os parâmetros são reescritos a cada
+//! [`install`].
+
+use crate::arch::x86_64::cpu::Cpu;
+use crate::arch::x86_64::smap::{CR4_SMAP, CR4_SMEP};
+
+core::arch::global_asm!(include_str!("trampoline.s"));
+
+/// Endereço físico do trampolim. Tem que bater com `TRAMPOLINE_BASE` em
+/// trampoline.s.
+///
+/// Alinhado a 4 KiB e abaixo de 1 MiB (o vetor do SIPI é a página); o PMM
+/// nunca entrega essa região.
+pub const TRAMPOLINE_BASE: u64 = 0x8000;
+
+const MSR_EFER: u32 = 0xC000_0080;
+/// EFER.LMA é só leitura; o trampolim grava o EFER de uma vez
+const EFER_LMA: u64 = 1 << 10;
+
+extern "C" {
+    static ap_trampoline_start: u8;
+    static ap_trampoline_data: u8;
+    static ap_trampoline_end: u8;
+}
+
+/// Entrada em Rust do AP: long mode, page tables do kernel, GDT provisória,
+/// interrupções desligadas.
+pub type ApEntry = extern "C" fn(cpu_id: u64) -> !;
+
+/// Parâmetros lidos pelo trampolim. Layout fixo (offsets em trampoline.s).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ApBootData {
+    pub cr3: u64,
+    pub cr4: u64,
+    pub cr0: u64,
+    pub efer: u64,
+    /// Topo da stack do AP (alinhado a 16)
+    pub stack_top: u64,
+    pub entry: u64,
+    /// Id lógico, passado em RDI
+    pub cpu_id: u64,
+}
+
+impl ApBootData {
+    /// Parâmetros para o AP subir com o estado de paginação da CPU atual.
+    ///
+    /// Falha se a PML4 está acima de 4 GiB: o trampolim carrega o CR3 em
+    /// modo protegido, com 32 bits.
+    pub fn from_current(stack_top: u64, entry: ApEntry, cpu_id: u32) -> Result<Self, &'static str> {
+        let cr3 = Cpu::read_cr3() & crate::mm::config::PAGE_MASK;
+        if cr3 > u32::MAX as u64 {
+            return Err("PML4 do kernel acima de 4 GiB");
+        }
+        Ok(Self {
+            cr3,
+            // Sem SMEP/SMAP: o trampolim roda no identity map, de flags
+            // desconhecidas; o AP os liga em `smap::init_ap`
+            cr4: Cpu::read_cr4() & !(CR4_SMEP | CR4_SMAP),
+            cr0: Cpu::read_cr0(),
+            efer: Cpu::read_msr(MSR_EFER) & !EFER_LMA,
+            stack_top,
+            entry: entry as *const () as u64,
+            cpu_id: cpu_id as u64,
+        })
+    }
+}
+
+/// Copia o trampolim para [`TRAMPOLINE_BASE`] com os parâmetros `data`.
+///
+/// Retorna o vetor do SIPI.
+///
+/// # Safety
+/// A página do trampolim precisa estar mapeada (identity) e não pode haver
+/// AP executando o trampolim.
+pub unsafe fn install(data: &ApBootData) -> u8 {
+    let start = core::ptr::addr_of!(ap_trampoline_start);
+    let len = (core::ptr::addr_of!(ap_trampoline_end) as usize) - start as usize;
+    let data_offset = (core::ptr::addr_of!(ap_trampoline_data) as usize) - start as usize;
+
+    let dest = crate::mm::addr::phys_to_virt::<u8>(TRAMPOLINE_BASE);
+    core::ptr::copy_nonoverlapping(start, dest, len);
+    core::ptr::write_volatile(dest.add(data_offset).cast::<ApBootData>(), *data);
+
+    (TRAMPOLINE_BASE >> 12) as u8
+}
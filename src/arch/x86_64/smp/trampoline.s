@@ -0,0 +1,124 @@
+# Trampolim de boot dos APs (INIT-SIPI-SIPI)
+#
+# Copiado para TRAMPOLINE_BASE por core::smp::bringup. O SIPI faz o AP
+# começar em ap_trampoline_start, em modo real com CS:IP = (BASE >> 4):0.
+# Passa por modo protegido até o long mode com as page tables do kernel e
+# salta para a entrada em Rust com RDI = id lógico da CPU.
+#
+# O código roda fora do endereço de link: todo endereço absoluto é
+# TRAMPOLINE_BASE + deslocamento a partir de ap_trampoline_start. Os
+# parâmetros ficam em ap_trampoline_data (ver smp::trampoline::ApBootData).
+
+.pushsection .rodata.ap_trampoline, "a"
+
+.set TRAMPOLINE_BASE, 0x8000
+
+.set AP_PROTECTED,  TRAMPOLINE_BASE + (ap_protected - ap_trampoline_start)
+.set AP_LONG,       TRAMPOLINE_BASE + (ap_long - ap_trampoline_start)
+.set AP_GDT,        TRAMPOLINE_BASE + (ap_gdt - ap_trampoline_start)
+.set AP_GDT_PTR,    TRAMPOLINE_BASE + (ap_gdt_ptr - ap_trampoline_start)
+.set AP_DATA,       TRAMPOLINE_BASE + (ap_trampoline_data - ap_trampoline_start)
+
+# Layout de ApBootData
+.set AP_DATA_CR3,    AP_DATA + 0
+.set AP_DATA_CR4,    AP_DATA + 8
+.set AP_DATA_CR0,    AP_DATA + 16
+.set AP_DATA_EFER,   AP_DATA + 24
+.set AP_DATA_STACK,  AP_DATA + 32
+.set AP_DATA_ENTRY,  AP_DATA + 40
+.set AP_DATA_CPU_ID, AP_DATA + 48
+
+.set MSR_EFER, 0xC0000080
+
+.global ap_trampoline_start
+.global ap_trampoline_data
+.global ap_trampoline_end
+
+.code16
+ap_trampoline_start:
+    cli
+    cld
+    xor ax, ax
+    mov ds, ax
+    mov es, ax
+    mov ss, ax
+
+    lgdt [AP_GDT_PTR]
+    mov eax, cr0
+    or eax, 1
+    mov cr0, eax
+
+    # jmp far 0x08:AP_PROTECTED (offset de 32 bits)
+    .byte 0x66, 0xEA
+    .long AP_PROTECTED
+    .word 0x08
+
+.code32
+ap_protected:
+    mov ax, 0x10
+    mov ds, ax
+    mov es, ax
+    mov ss, ax
+
+    # CR4 do BSP (PAE etc.), sem PCIDE: só pode ser ligado em long mode
+    mov eax, [AP_DATA_CR4]
+    and eax, 0xFFFDFFFF
+    mov cr4, eax
+
+    mov eax, [AP_DATA_CR3]
+    mov cr3, eax
+
+    # EFER do BSP (LME, NXE, SCE)
+    mov ecx, MSR_EFER
+    mov eax, [AP_DATA_EFER]
+    mov edx, [AP_DATA_EFER + 4]
+    wrmsr
+
+    # Ligar a paginação ativa o long mode (compatibilidade até o salto)
+    mov eax, [AP_DATA_CR0]
+    mov cr0, eax
+
+    # jmp far 0x18:AP_LONG
+    .byte 0xEA
+    .long AP_LONG
+    .word 0x18
+
+.code64
+ap_long:
+    mov ax, 0x10
+    mov ds, ax
+    mov es, ax
+    mov ss, ax
+    xor ax, ax
+    mov fs, ax
+    mov gs, ax
+
+    # CR4 completo
+    mov rax, [AP_DATA_CR4]
+    mov cr4, rax
+
+    mov rsp, [AP_DATA_STACK]
+    mov rdi, [AP_DATA_CPU_ID]
+    mov rax, [AP_DATA_ENTRY]
+
+    # Endereço de retorno nulo: a entrada nunca retorna
+    push 0
+    jmp rax
+
+# GDT provisória: a entrada em Rust carrega a GDT da CPU
+.balign 8
+ap_gdt:
+    .quad 0
+    .quad 0x00CF9A000000FFFF  # 0x08: código 32 bits
+    .quad 0x00CF92000000FFFF  # 0x10: dados
+    .quad 0x00AF9A000000FFFF  # 0x18: código 64 bits
+ap_gdt_ptr:
+    .word ap_gdt_ptr - ap_gdt - 1
+    .long AP_GDT
+
+.balign 8
+ap_trampoline_data:
+    .space 56
+ap_trampoline_end:
+
+.popsection
@@ -11,7 +11,7 @@ context_switch:
     push r15
 
     mov [rdi], rsp
-    mov rsp, [rsi]
+    mov rsp, rsi
 
     pop r15
     pop r14
@@ -24,29 +24,11 @@ const MSR_EFER: u32 = 0xC0000080;
 const MSR_STAR: u32 = 0xC0000081;
 const MSR_LSTAR: u32 = 0xC0000082;
 const MSR_FMASK: u32 = 0xC0000084;
-const MSR_KERNEL_GS_BASE: u32 = 0xC0000102;
-const MSR_GS_BASE: u32 = 0xC0000101;
 
 // Flags
 const EFER_SCE: u64 = 1; // System Call Extensions
 const RFLAGS_IF: u64 = 1 << 9; // Interrupt Flag
-
-/// Estrutura per-CPU para syscall.
-/// syscall.s acessa via gs:[0] (user_rsp) e gs:[8] (kernel_rsp).
-#[repr(C)]
-pub struct SyscallStack {
-    /// RSP do usuário salvo pelo handler (gs:[0])
-    pub user_rsp: u64,
-    /// Kernel stack pointer para usar durante syscall (gs:[8])
-    pub kernel_rsp: u64,
-}
-
-/// Stack de syscall para BSP (CPU 0)
-/// TODO: Tornar per-CPU com array para SMP
-static mut SYSCALL_STACK: SyscallStack = SyscallStack {
-    user_rsp: 0,
-    kernel_rsp: 0,
-};
+const RFLAGS_AC: u64 = super::smap::RFLAGS_AC; // Access Control (SMAP)
 
 /// Estrutura que representa o estado salvo dos registradores na stack.
 /// Deve corresponder EXATAMENTE à ordem de push em `syscall.s`.
@@ -137,25 +119,15 @@ pub unsafe fn init() {
     // 4. Configurar FMASK (Mask RFLAGS)
     // Limpar Interrupt Flag (IF) ao entrar na syscall para evitar reentrância imediata
     // e permitir que o kernel decida quando habilitar.
-    Cpu::write_msr(MSR_FMASK, RFLAGS_IF);
-
-    // 5. Configurar GS Base para syscalls
-    // INICIALIZAÇÃO CRÍTICA:
-    // Estamos em Kernel Mode. O 'Active' GS Base deve apontar para as estruturas do Kernel (SYSCALL_STACK).
-    // O 'Shadow' (KERNEL_GS_BASE MSR) deve guardar o valor do GS do usuário (ou 0 inicialmente).
-    // Quando 'iretq_restore' executar 'swapgs', ele trocará Active(Kernel) <-> Shadow(User).
-    // Assim, o User Mode rodará com GS=User e MSR=Kernel.
-    // Quando 'syscall' ocorrer, 'swapgs' trocará Active(User) <-> Shadow(Kernel).
-    let syscall_stack_addr = core::ptr::addr_of!(SYSCALL_STACK) as u64;
-
-    // Configurar Active GS Base (para uso imediato no Kernel)
-    Cpu::write_msr(MSR_GS_BASE, syscall_stack_addr);
-
-    // Configurar Shadow GS Base (para ser carregado via swapgs ao ir para user)
-    Cpu::write_msr(MSR_KERNEL_GS_BASE, 0);
-
-    crate::kinfo!("(Syscall) GS_BASE inicializado:", syscall_stack_addr);
-    crate::kinfo!("(Syscall) KERNEL_GS_BASE (Shadow) inicializado com 0");
+    // Limpar AC: o usuário não pode entrar no kernel com o SMAP suspenso.
+    Cpu::write_msr(MSR_FMASK, RFLAGS_IF | RFLAGS_AC);
+
+    // 5. GS Base
+    // O 'Active' GS Base aponta para a área da CPU (core::smp::percpu), onde
+    // syscall.s guarda o RSP do usuário (gs:[0]) e lê a stack do kernel
+    // (gs:[8]). O 'Shadow' (KERNEL_GS_BASE) guarda o GS do usuário; 'swapgs'
+    // troca os dois na entrada e na saída do kernel. Ambos são configurados
+    // por percpu::init_bsp/init_ap.
 }
 
 /// Configura o kernel RSP para a task atual.
@@ -165,5 +137,7 @@ pub unsafe fn init() {
 ///
 /// O kernel_stack deve ser um endereço válido e mapeado.
 pub unsafe fn set_kernel_rsp(kernel_stack: u64) {
-    SYSCALL_STACK.kernel_rsp = kernel_stack;
+    crate::core::smp::percpu::this_cpu()
+        .kernel_rsp
+        .store(kernel_stack, core::sync::atomic::Ordering::Relaxed);
 }
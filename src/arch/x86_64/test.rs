@@ -0,0 +1,64 @@
+//! # Teste do SMAP
+//!
+//! Executado no boot com a feature `smap_test`, logo depois de
+//! [`super::smap::init`]. Mapeia uma página de usuário no CR3 atual e:
+//! 1. a acessa dentro de um [`UserAccessGuard`]: tem que funcionar;
+//! 2. a lê fora do guard: com SMAP ligado o kernel TEM que parar em panic,
+//!    com "(SMAP) Acesso do kernel a página de usuário" no log.
+//!
+//! O teste só passa pelo panic; chegar ao fim dele é falha. Sem SMAP na CPU
+//! (ex.: `-cpu qemu64`) ele é ignorado; no QEMU usar `-cpu max`.
+
+use super::smap::{self, UserAccessGuard};
+use crate::mm::{MapFlags, FRAME_ALLOCATOR};
+
+/// Página de usuário do teste, num slot da PML4 que o kernel não usa
+const TEST_PAGE: u64 = 0x0000_7F00_0000_0000;
+
+pub fn run_tests() {
+    crate::kinfo!("(SMAP) Iniciando teste de acesso a memória de usuário...");
+    if !smap::is_enabled() {
+        crate::kwarn!("(SMAP) CPU sem SMAP; teste ignorado");
+        return;
+    }
+    map_user_page();
+    test_guarded_access();
+    test_unguarded_access_faults();
+}
+
+fn map_user_page() {
+    let mut pmm = FRAME_ALLOCATOR.lock();
+    let frame = pmm
+        .allocate_frame()
+        .expect("(SMAP) Sem memória para a página de teste");
+    crate::mm::vmm::map_page_with_pmm(
+        TEST_PAGE,
+        frame.as_u64(),
+        MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::USER,
+        &mut pmm,
+    )
+    .expect("(SMAP) Falha ao mapear a página de teste");
+}
+
+/// Dentro do guard o kernel lê e escreve a página de usuário.
+fn test_guarded_access() {
+    let ptr = TEST_PAGE as *mut u64;
+    let _uaccess = UserAccessGuard::new();
+    // SAFETY: página mapeada acima; acesso permitido pelo guard
+    unsafe {
+        core::ptr::write_volatile(ptr, 0x5A5A_A5A5);
+        assert_eq!(core::ptr::read_volatile(ptr), 0x5A5A_A5A5);
+    }
+    crate::kinfo!("(SMAP) Acesso com guard: OK");
+}
+
+/// Sem o guard, a mesma leitura vira page fault (e panic).
+fn test_unguarded_access_faults() {
+    crate::kinfo!("(SMAP) Lendo sem guard; o esperado é um panic (SMAP)");
+    // SAFETY: página mapeada; a falta é o resultado esperado
+    let value = unsafe { core::ptr::read_volatile(TEST_PAGE as *const u64) };
+    panic!(
+        "(SMAP) Leitura sem guard não gerou page fault: {:#x}",
+        value
+    );
+}
@@ -18,21 +18,7 @@ pub fn init() {
 }
 
 pub fn is_supported() -> bool {
-    #[cfg(target_arch = "x86_64")]
-    unsafe {
-        let ecx: u32;
-        core::arch::asm!(
-            "mov eax, 1",
-            "cpuid",
-            out("ecx") ecx,
-            out("eax") _,
-            out("edx") _,
-            options(nostack, nomem)
-        );
-        ecx & (1 << 17) != 0
-    }
-    #[cfg(not(target_arch = "x86_64"))]
-    false
+    crate::arch::x86_64::cpuid::features().pcid
 }
 
 fn enable() {
@@ -27,6 +27,7 @@ pub extern "C" fn kernel_main(boot_info: &'static BootInfo) -> ! {
     }
 
     crate::kinfo!("Versão do Protocolo de Boot:", boot_info.version);
+    crate::core::debug::ksym::init();
 
     // 2. Inicialização da Arquitetura (CPU, GDT, IDT, Interrupções)
     crate::kinfo!("'Inicializando Arquitetura'");
@@ -40,6 +41,10 @@ pub extern "C" fn kernel_main(boot_info: &'static BootInfo) -> ! {
         crate::mm::init(boot_info);
     }
 
+    // SMEP/SMAP: depois da memória, quando o identity map do bootloader
+    // não é mais usado
+    crate::arch::platform::smap::init();
+
     // 2.5. Inicialização de Vídeo (Framebuffer)
     // Inicializamos agora que o HHDM está pronto para mapear o FB corretamente
     crate::drivers::display::init(boot_info.framebuffer);
@@ -50,10 +55,9 @@ pub extern "C" fn kernel_main(boot_info: &'static BootInfo) -> ! {
 
     // 5. ACPI e Descoberta de Hardware
     crate::kinfo!("'Inicializando ACPI'");
-    if boot_info.rsdp_addr != 0 {
-        // Inicializa ACPI via implementação da arquitetura (x86_64)
-        crate::arch::platform::acpi::init(boot_info.rsdp_addr);
-    }
+    // Inicializa ACPI via implementação da arquitetura (x86_64); sem RSDP
+    // no BootInfo (0) ele é procurado na memória baixa
+    crate::arch::platform::acpi::init(boot_info.rsdp_addr);
 
     // 6. SMP Bringup (Acordar outros cores)
     crate::kinfo!("'Inicializando SMP'");
@@ -92,23 +96,50 @@ pub extern "C" fn kernel_main(boot_info: &'static BootInfo) -> ! {
     // Primeiro inicializar drivers de input
     crate::kinfo!("'Inicializando Drivers de Input'");
     crate::drivers::input::init();
+    crate::drivers::serial::enable_rx_interrupts();
+
+    // 8.5. Inicializar Scheduler (cria a Idle Task)
+    // A idle task fica em IDLE_TASK (fallback permanente) e NÃO na RunQueue
+    crate::kinfo!("'Inicializando Scheduler'");
+    crate::sched::init();
+    crate::core::work::init();
+
+    // 9. Habilitar Timer IRQ (APÓS scheduler estar pronto)
+    crate::kinfo!("'Habilitando Timer Preemptivo'");
+    crate::core::time::tick::start();
 
-    // 8.5. Inicializar Idle Task
-    // A idle task fica em IDLE_TASK (fallback permanente) e NÃO em CURRENT
-    crate::kinfo!("'Inicializando Idle Task'");
-    crate::sched::core::idle::init_idle_task();
+    // Antes do init, que disputaria CPU e memória com os testes
+    #[cfg(feature = "self_test")]
+    run_self_tests();
 
     crate::kinfo!("'Iniciando Processo Init'");
     crate::core::process::spawn_init();
 
     crate::kinfo!("'Inicialização do Kernel Concluída'");
 
-    // 9. Habilitar Timer IRQ (APÓS scheduler estar pronto)
-    crate::kinfo!("'Habilitando Timer Preemptivo'");
-    crate::arch::x86_64::interrupts::pic_enable_irq(0);
-
     // 10. Entrar no loop do scheduler
-    // CURRENT está vazio, schedule() vai pegar a primeira task da RunQueue
-    // Se não houver tasks, vai para a idle task (fallback)
+    // O contexto de boot é descartado; a idle task assume a CPU e cede
+    // para as tasks da RunQueue
     crate::sched::core::scheduler::run();
 }
+
+/// Roda os testes de cada subsistema (`test.rs`) no fluxo de boot: com
+/// scheduler, worker, tick, SMP e InitRAMFS prontos, e o init ainda não.
+#[cfg(feature = "self_test")]
+fn run_self_tests() {
+    crate::kinfo!("'Executando Self-Tests'");
+    crate::drivers::serial::test::run_tests();
+    crate::core::debug::test::run_tests();
+    crate::core::time::test::run_tests();
+    crate::core::smp::test::run_tests();
+    crate::core::work::test::run_tests();
+    crate::mm::test::run_tests();
+    crate::sync::test::run_tests();
+    crate::sched::test::run_tests();
+    crate::sched::exec::test::run_tests();
+    crate::ipc::test::run_tests();
+    crate::fs::test::run_tests();
+    crate::module::test::run_tests();
+    crate::syscall::test::run_tests();
+    crate::kinfo!("'Self-Tests concluídos'");
+}
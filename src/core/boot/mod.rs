@@ -4,6 +4,8 @@ pub mod cmdline;
 pub mod entry;
 pub mod handoff;
 pub mod initcall;
+// Nos testes de host o pânico é o da std
+#[cfg(not(test))]
 pub mod panic;
 
 pub use entry::kernel_main;
@@ -5,12 +5,15 @@
 /// É o ponto final de falhas irrecuperáveis.
 ///
 /// Detalhes de Implementação:
-/// - Imprime mensagem de erro e localização.
+/// - Imprime mensagem de erro, localização e backtrace (ver `debug::backtrace`).
 /// - Desabilita interrupções.
 /// - Trava a CPU (loop infinito com HLT).
 /// - (Futuro) Parar outras CPUs via IPI.
-/// - (Futuro) Dump de stack trace.
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Já estamos no handler: um pânico durante o backtrace não tenta de novo
+static PANICKING: AtomicBool = AtomicBool::new(false);
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
@@ -38,6 +41,12 @@ fn panic(info: &PanicInfo) -> ! {
     crate::kerror!("(Panic payload indisponivel temporariamente)");
     // }
 
+    if !PANICKING.swap(true, Ordering::SeqCst) {
+        crate::core::debug::backtrace::print();
+    } else {
+        crate::kerror!("(Panic) Pânico aninhado: backtrace omitido");
+    }
+
     crate::kerror!("*****************************************************");
     crate::kerror!("*             SISTEMA HALTED FOREVER                *");
     crate::kerror!("*****************************************************");
@@ -0,0 +1,218 @@
+//! Backtrace por frame pointer
+//!
+//! O kernel é compilado com `force-frame-pointers`, então todo frame
+//! começa com `[rbp] = rbp do chamador` e `[rbp + 8] = endereço de
+//! retorno`. A stack de boot entra com `rbp = 0` (ver `_start`), o que
+//! encerra a cadeia.
+//!
+//! ## Cadeia corrompida
+//! O backtrace roda no pânico, então um RBP lixo não pode travar nem causar
+//! outra falta:
+//! - todo RBP tem de estar alinhado e dentro da stack atual ([`StackBounds`]);
+//! - a cadeia só sobe (o RBP do chamador é maior), então não há laço;
+//! - o endereço de retorno tem de estar no `.text` do kernel;
+//! - no máximo [`MAX_FRAMES`] frames.
+
+use super::klog::{self, Level, Subsystem};
+use core::ops::Range;
+
+/// Profundidade máxima impressa
+pub const MAX_FRAMES: usize = 32;
+
+extern "C" {
+    static __text_start: u8;
+    static __text_end: u8;
+}
+
+/// Faixa de endereços onde a cadeia de RBPs pode andar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackBounds {
+    pub low: u64,
+    pub high: u64,
+}
+
+impl StackBounds {
+    /// Se cabe um frame (`rbp` e o endereço de retorno) a partir de `rbp`
+    fn holds_frame(&self, rbp: u64) -> bool {
+        rbp % 8 == 0 && rbp >= self.low && rbp.checked_add(16).is_some_and(|end| end <= self.high)
+    }
+}
+
+/// Anda a cadeia de frames a partir de `rbp`, gravando os endereços de
+/// retorno em `out`; retorna quantos.
+///
+/// `read` lê um `u64` da memória e só é chamado dentro de `bounds`.
+pub fn walk(
+    mut rbp: u64,
+    bounds: StackBounds,
+    text: Range<u64>,
+    read: impl Fn(u64) -> u64,
+    out: &mut [u64],
+) -> usize {
+    let mut count = 0;
+    while count < out.len() && bounds.holds_frame(rbp) {
+        let ret = read(rbp + 8);
+        if !text.contains(&ret) {
+            break;
+        }
+        out[count] = ret;
+        count += 1;
+
+        let caller = read(rbp);
+        // Fim da cadeia (0) ou RBP que não sobe: corrompido
+        if caller <= rbp {
+            break;
+        }
+        rbp = caller;
+    }
+    count
+}
+
+/// `.text` do kernel
+fn kernel_text() -> Range<u64> {
+    // SAFETY: símbolos do linker script; só os endereços são usados
+    unsafe { (&raw const __text_start as u64)..(&raw const __text_end as u64) }
+}
+
+/// Stack em que estamos rodando, a partir de `rsp`.
+///
+/// O topo vem da task atual, se o RSP está na stack de kernel dela; senão
+/// (boot, ou `CURRENT` travado por quem entrou em pânico) assume a maior
+/// stack de kernel.
+fn current_bounds(rsp: u64) -> StackBounds {
+    let max = crate::sched::config::KERNEL_STACK_SIZE as u64;
+    let task_top = crate::sched::core::CURRENT
+        .try_lock()
+        .and_then(|current| current.as_ref().map(|task| task.kernel_stack.as_u64()));
+    let high = match task_top {
+        Some(top) if top > rsp && top - rsp <= max => top,
+        _ => rsp.saturating_add(max),
+    };
+    StackBounds { low: rsp, high }
+}
+
+/// Captura os endereços de retorno a partir de quem chamou `capture`;
+/// retorna quantos foram gravados em `out`
+#[inline(never)]
+pub fn capture(out: &mut [u64]) -> usize {
+    let rbp: u64;
+    let rsp: u64;
+    // SAFETY: só lê registradores
+    unsafe {
+        core::arch::asm!(
+            "mov {rbp}, rbp",
+            "mov {rsp}, rsp",
+            rbp = out(reg) rbp,
+            rsp = out(reg) rsp,
+            options(nomem, nostack, preserves_flags)
+        );
+    }
+    // SAFETY: `walk` só lê endereços dentro da stack atual
+    let read = |addr: u64| unsafe { core::ptr::read_volatile(addr as *const u64) };
+    walk(rbp, current_bounds(rsp), kernel_text(), read, out)
+}
+
+/// Imprime o backtrace de quem chamou (usado pelo handler de pânico)
+#[inline(never)]
+pub fn print() {
+    let mut frames = [0u64; MAX_FRAMES];
+    let count = capture(&mut frames);
+    print_frames(&frames[..count]);
+}
+
+/// Imprime endereços de retorno já capturados, um por linha, com
+/// `função+offset` quando há tabela de símbolos (ver `ksym`)
+pub fn print_frames(frames: &[u64]) {
+    crate::kerror!("Backtrace (frames):", frames.len() as u64);
+    if !klog::enabled(Subsystem::Kernel, Level::Error) {
+        return;
+    }
+    for &addr in frames {
+        klog::begin(Level::Error);
+        klog::write_str("  em");
+        klog::SerialDebug::serial_debug(&addr);
+        if let Some((name, offset)) = super::ksym::resolve(addr) {
+            let mut digits = [0u8; 16];
+            klog::write_str(" ");
+            klog::write_str(name);
+            klog::write_str("+0x");
+            klog::write_str(crate::klib::string::u64_to_hex(&mut digits, offset));
+        }
+        klog::write_str("\n");
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    const TEXT: Range<u64> = 0x1000..0x2000;
+    const BOUNDS: StackBounds = StackBounds {
+        low: 0x8000,
+        high: 0x9000,
+    };
+
+    /// Memória falsa: (rbp do chamador, retorno) em cada frame
+    fn stack(frames: &[(u64, u64, u64)]) -> BTreeMap<u64, u64> {
+        let mut mem = BTreeMap::new();
+        for &(rbp, caller, ret) in frames {
+            mem.insert(rbp, caller);
+            mem.insert(rbp + 8, ret);
+        }
+        mem
+    }
+
+    fn run(mem: &BTreeMap<u64, u64>, rbp: u64) -> alloc::vec::Vec<u64> {
+        let mut out = [0u64; MAX_FRAMES];
+        let count = walk(
+            rbp,
+            BOUNDS,
+            TEXT,
+            |addr| {
+                assert!((BOUNDS.low..BOUNDS.high).contains(&addr));
+                mem.get(&addr).copied().unwrap_or(0)
+            },
+            &mut out,
+        );
+        out[..count].to_vec()
+    }
+
+    #[test]
+    fn test_walk_until_chain_end() {
+        let mem = stack(&[
+            (0x8100, 0x8200, 0x1010),
+            (0x8200, 0x8300, 0x1020),
+            (0x8300, 0, 0x1030),
+        ]);
+        assert_eq!(run(&mem, 0x8100), [0x1010, 0x1020, 0x1030]);
+    }
+
+    #[test]
+    fn test_walk_stops_on_corrupt_chain() {
+        // Laço: o chamador aponta de volta
+        let looped = stack(&[(0x8100, 0x8200, 0x1010), (0x8200, 0x8100, 0x1020)]);
+        assert_eq!(run(&looped, 0x8100), [0x1010, 0x1020]);
+
+        // RBP fora da stack, desalinhado ou retorno fora do `.text`
+        let escaped = stack(&[(0x8100, 0xdead_0000, 0x1010)]);
+        assert_eq!(run(&escaped, 0x8100), [0x1010]);
+        assert!(run(&escaped, 0x8104).is_empty());
+        let bad_ret = stack(&[(0x8100, 0x8200, 0x1010), (0x8200, 0x8300, 0x5000)]);
+        assert_eq!(run(&bad_ret, 0x8100), [0x1010]);
+        // Frame encostado no topo: o retorno ficaria fora
+        assert!(run(&BTreeMap::new(), BOUNDS.high - 8).is_empty());
+    }
+
+    #[test]
+    fn test_walk_depth_limit() {
+        let frames: alloc::vec::Vec<_> = (0..MAX_FRAMES as u64 + 8)
+            .map(|i| (0x8000 + i * 0x10, 0x8000 + (i + 1) * 0x10, 0x1000 + i))
+            .collect();
+        assert_eq!(run(&stack(&frames), 0x8000).len(), MAX_FRAMES);
+    }
+}
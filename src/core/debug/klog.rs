@@ -1,7 +1,177 @@
 //! Sistema de Logging Simplificado
 //!
-//! Macros diretas para saída serial.
+//! Macros diretas para saída serial (e console de vídeo, se ativo).
 //! Sem traits complexas, apenas texto e u64.
+//!
+//! Tudo que é escrito aqui também vai para o buffer circular do log
+//! ([`kmsg`](super::kmsg)), lido pelo userspace via `sys_klog_read`.
+//!
+//! ## Nível em runtime
+//! Cada macro só escreve se o nível da linha alcança o limite do seu
+//! [`Subsystem`] (uma carga atômica). O limite global vem de [`set_level`];
+//! [`set_subsystem_level`] sobrepõe o de um subsistema, para ligar o trace
+//! só onde se está depurando:
+//!
+//! ```ignore
+//! klog::set_subsystem_level(Subsystem::Syscall, Some(Level::Trace));
+//! ktrace!(Syscall: "(Syscall) num=", num);  // agora aparece
+//! ktrace!("(Slab) alloc");                  // `Kernel`: segue o global
+//! ```
+
+use super::kmsg;
+use crate::sync::Spinlock;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Nível de uma linha de log (em ordem crescente de gravidade)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Level {
+    Trace = 0,
+    Debug = 1,
+    Info = 2,
+    Warn = 3,
+    Error = 4,
+}
+
+impl Level {
+    /// Prefixo da linha na saída
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Level::Trace => "[TRACE] ",
+            Level::Debug => "[DEBUG] ",
+            Level::Info => "[INFO]  ",
+            Level::Warn => "[WARN]  ",
+            Level::Error => "[ERROR] ",
+        }
+    }
+
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Level::Trace),
+            1 => Some(Level::Debug),
+            2 => Some(Level::Info),
+            3 => Some(Level::Warn),
+            4 => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Subsistema de uma linha de log, para limites de nível próprios
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Subsystem {
+    /// Padrão das macros sem subsistema
+    Kernel = 0,
+    Arch = 1,
+    Mm = 2,
+    Sched = 3,
+    Syscall = 4,
+    Fs = 5,
+    Ipc = 6,
+    Drivers = 7,
+}
+
+impl Subsystem {
+    pub const COUNT: usize = 8;
+
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Subsystem::Kernel),
+            1 => Some(Subsystem::Arch),
+            2 => Some(Subsystem::Mm),
+            3 => Some(Subsystem::Sched),
+            4 => Some(Subsystem::Syscall),
+            5 => Some(Subsystem::Fs),
+            6 => Some(Subsystem::Ipc),
+            7 => Some(Subsystem::Drivers),
+            _ => None,
+        }
+    }
+}
+
+/// Limite inicial: `kdebug!` aparece em builds debug, `ktrace!` não
+pub const DEFAULT_LEVEL: Level = if cfg!(debug_assertions) {
+    Level::Debug
+} else {
+    Level::Info
+};
+
+#[allow(clippy::declare_interior_mutable_const)]
+const DEFAULT_THRESHOLD: AtomicU8 = AtomicU8::new(DEFAULT_LEVEL as u8);
+
+/// Limite efetivo de cada subsistema (override ou global), lido pelas macros
+static THRESHOLDS: [AtomicU8; Subsystem::COUNT] = [DEFAULT_THRESHOLD; Subsystem::COUNT];
+
+/// Limite global e overrides; só quem muda limites toma este lock
+static LEVELS: Spinlock<(Level, [Option<Level>; Subsystem::COUNT])> =
+    Spinlock::new((DEFAULT_LEVEL, [None; Subsystem::COUNT]));
+
+/// Se uma linha de nível `level` de `subsystem` deve ser escrita
+#[inline(always)]
+pub fn enabled(subsystem: Subsystem, level: Level) -> bool {
+    level as u8 >= THRESHOLDS[subsystem as usize].load(Ordering::Relaxed)
+}
+
+/// Muda o limite global (subsistemas com override não mudam)
+pub fn set_level(level: Level) {
+    let mut levels = LEVELS.lock();
+    levels.0 = level;
+    publish(&levels);
+}
+
+/// Limite global atual
+pub fn level() -> Level {
+    LEVELS.lock().0
+}
+
+/// Sobrepõe o limite de `subsystem`; `None` volta a seguir o global
+pub fn set_subsystem_level(subsystem: Subsystem, level: Option<Level>) {
+    let mut levels = LEVELS.lock();
+    levels.1[subsystem as usize] = level;
+    publish(&levels);
+}
+
+fn publish(levels: &(Level, [Option<Level>; Subsystem::COUNT])) {
+    let (global, overrides) = levels;
+    for (threshold, level) in THRESHOLDS.iter().zip(overrides) {
+        threshold.store(level.unwrap_or(*global) as u8, Ordering::Relaxed);
+    }
+}
+
+/// Começa uma linha de log: escreve o prefixo do nível e abre a linha no
+/// buffer do log
+pub fn begin(level: Level) {
+    kmsg::begin(level);
+    let tag = level.tag();
+    crate::drivers::serial::write_str(tag);
+    crate::drivers::display::console::write_str(tag);
+}
+
+/// Escreve texto do log na serial e no console de vídeo
+pub fn write_str(s: &str) {
+    crate::drivers::serial::write_str(s);
+    crate::drivers::display::console::write_str(s);
+    kmsg::append(s.as_bytes());
+}
+
+/// Escreve um byte do log na serial e no console de vídeo
+pub fn write_byte(byte: u8) {
+    crate::drivers::serial::write_byte(byte);
+    crate::drivers::display::console::write_bytes(&[byte]);
+    kmsg::append(&[byte]);
+}
+
+/// Escreve `0x` + 16 dígitos hexadecimais na serial e no console de vídeo
+pub fn write_hex(value: u64) {
+    crate::drivers::serial::write_hex(value);
+
+    let mut text = [b'0'; 18];
+    text[1] = b'x';
+    crate::klib::string::u64_to_hex(&mut text[2..], value);
+    crate::drivers::display::console::write_bytes(&text);
+    kmsg::append(&text);
+}
 
 /// Trait auxiliar para imprimir valores de tipos diferentes
 pub trait SerialDebug {
@@ -10,57 +180,57 @@ pub trait SerialDebug {
 
 impl SerialDebug for u64 {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self);
+        write_byte(b' ');
+        write_hex(*self);
     }
 }
 
 impl SerialDebug for usize {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
 impl SerialDebug for u32 {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
 impl SerialDebug for i32 {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
 impl SerialDebug for &str {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_str(self);
+        write_byte(b' ');
+        write_str(self);
     }
 }
 
 impl SerialDebug for u8 {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
 impl<T> SerialDebug for *const T {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
 impl<T> SerialDebug for *mut T {
     fn serial_debug(&self) {
-        crate::drivers::serial::write_byte(b' ');
-        crate::drivers::serial::write_hex(*self as u64);
+        write_byte(b' ');
+        write_hex(*self as u64);
     }
 }
 
@@ -72,72 +242,109 @@ macro_rules! kprint {
     };
 }
 
-/// Info Log
+/// Escreve uma linha de log se `enabled` (uso interno das macros `k*!`)
+#[macro_export]
+macro_rules! klog_line {
+    ($sub:ident, $level:ident, $msg:expr) => {
+        if $crate::core::debug::klog::enabled(
+            $crate::core::debug::klog::Subsystem::$sub,
+            $crate::core::debug::klog::Level::$level,
+        ) {
+            $crate::core::debug::klog::begin($crate::core::debug::klog::Level::$level);
+            $crate::core::debug::klog::write_str($msg);
+            $crate::core::debug::klog::write_str("\n");
+        }
+    };
+    ($sub:ident, $level:ident, $msg:expr, $val:expr) => {
+        if $crate::core::debug::klog::enabled(
+            $crate::core::debug::klog::Subsystem::$sub,
+            $crate::core::debug::klog::Level::$level,
+        ) {
+            $crate::core::debug::klog::begin($crate::core::debug::klog::Level::$level);
+            $crate::core::debug::klog::write_str($msg);
+            $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
+            $crate::core::debug::klog::write_str("\n");
+        }
+    };
+}
+
+/// Info Log (`kinfo!(Subsistema: "msg")` usa o limite do subsistema)
 #[macro_export]
 macro_rules! kinfo {
+    ($sub:ident: $msg:expr) => {
+        $crate::klog_line!($sub, Info, $msg)
+    };
+    ($sub:ident: $msg:expr, $val:expr) => {
+        $crate::klog_line!($sub, Info, $msg, $val)
+    };
     ($msg:expr) => {
-        $crate::drivers::serial::write_str("[INFO]  ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Info, $msg)
     };
     ($msg:expr, $val:expr) => {
-        $crate::drivers::serial::write_str("[INFO]  ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Info, $msg, $val)
     };
 }
 
 /// Warn Log
 #[macro_export]
 macro_rules! kwarn {
+    ($sub:ident: $msg:expr) => {
+        $crate::klog_line!($sub, Warn, $msg)
+    };
+    ($sub:ident: $msg:expr, $val:expr) => {
+        $crate::klog_line!($sub, Warn, $msg, $val)
+    };
     ($msg:expr) => {
-        $crate::drivers::serial::write_str("[WARN]  ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Warn, $msg)
     };
     ($msg:expr, $val:expr) => {
-        $crate::drivers::serial::write_str("[WARN]  ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Warn, $msg, $val)
     };
 }
 
 /// Error Log
 #[macro_export]
 macro_rules! kerror {
+    ($sub:ident: $msg:expr) => {
+        $crate::klog_line!($sub, Error, $msg)
+    };
+    ($sub:ident: $msg:expr, $val:expr) => {
+        $crate::klog_line!($sub, Error, $msg, $val)
+    };
     ($msg:expr) => {
-        $crate::drivers::serial::write_str("[ERROR] ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Error, $msg)
     };
     ($msg:expr, $val:expr) => {
-        $crate::drivers::serial::write_str("[ERROR] ");
-        $crate::drivers::serial::write_str($msg);
-        $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
-        $crate::drivers::serial::write_str("\n");
+        $crate::klog_line!(Kernel, Error, $msg, $val)
     };
 }
 
-/// Debug Log (Compilado apenas em debug/test ou se feature estiver ativa)
+/// Debug Log (Compilado apenas em debug/test; em runtime, só acima do
+/// limite do subsistema)
 #[macro_export]
 macro_rules! kdebug {
+    ($sub:ident: $msg:expr) => {
+        #[cfg(debug_assertions)]
+        {
+            $crate::klog_line!($sub, Debug, $msg)
+        }
+    };
+    ($sub:ident: $msg:expr, $val:expr) => {
+        #[cfg(debug_assertions)]
+        {
+            $crate::klog_line!($sub, Debug, $msg, $val)
+        }
+    };
     ($msg:expr) => {
         #[cfg(debug_assertions)]
         {
-            $crate::drivers::serial::write_str("[DEBUG] ");
-            $crate::drivers::serial::write_str($msg);
-            $crate::drivers::serial::write_str("\n");
+            $crate::klog_line!(Kernel, Debug, $msg)
         }
     };
     ($msg:expr, $val:expr) => {
         #[cfg(debug_assertions)]
         {
-            $crate::drivers::serial::write_str("[DEBUG] ");
-            $crate::drivers::serial::write_str($msg);
-            $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
-            $crate::drivers::serial::write_str("\n");
+            $crate::klog_line!(Kernel, Debug, $msg, $val)
         }
     };
 }
@@ -0,0 +1,315 @@
+//! Buffer circular do log do kernel (estilo `dmesg`)
+//!
+//! Tudo que passa por [`klog`](super::klog) (as macros `k*!`) também é
+//! guardado aqui, linha a linha, com nível e timestamp. Assim o userspace
+//! consegue ler depois (`sys_klog_read`, `/proc/kmsg`) mensagens de boot
+//! que já rolaram para fora do console.
+//!
+//! ## Armazenamento
+//! [`KMSG_RECORDS`] registros de tamanho fixo em memória estática: quando
+//! enche, a linha mais antiga é sobrescrita. Linhas maiores que
+//! [`KMSG_LINE`] bytes são truncadas. O append não aloca e roda sob um
+//! `Spinlock` (que desliga interrupções), então pode ser chamado de um
+//! handler de interrupção.
+//!
+//! ## Leitura
+//! Não consome: cada leitura devolve as linhas retidas com nível mínimo
+//! pedido, no formato `[    1.234567] [INFO]  texto\n`. Se não couberem
+//! todas, ficam as mais recentes.
+
+use super::klog::Level;
+use crate::sync::Spinlock;
+
+/// Linhas retidas
+pub const KMSG_RECORDS: usize = 256;
+
+/// Bytes de texto por linha (o resto é descartado)
+pub const KMSG_LINE: usize = 120;
+
+/// Maior `[segundos.uuuuuu] ` antes do nível (segundos com até 20 dígitos)
+const STAMP_MAX: usize = 1 + 20 + 1 + 6 + 2;
+
+/// Maior linha formatada: timestamp, nível, texto e `\n`
+const FORMATTED_MAX: usize = STAMP_MAX + 8 + KMSG_LINE + 1;
+
+#[derive(Clone, Copy)]
+struct Record {
+    ts_ns: u64,
+    level: Level,
+    len: u8,
+    text: [u8; KMSG_LINE],
+}
+
+impl Record {
+    const EMPTY: Self = Self {
+        ts_ns: 0,
+        level: Level::Info,
+        len: 0,
+        text: [0; KMSG_LINE],
+    };
+
+    fn start(level: Level, ts_ns: u64) -> Self {
+        Self {
+            ts_ns,
+            level,
+            ..Self::EMPTY
+        }
+    }
+
+    /// Escreve a linha formatada em `out`; retorna o tamanho
+    fn format(&self, out: &mut [u8; FORMATTED_MAX]) -> usize {
+        let secs = self.ts_ns / 1_000_000_000;
+        let micros = self.ts_ns % 1_000_000_000 / 1_000;
+
+        out[0] = b'[';
+        let mut pos = 1;
+        let mut digits = [b' '; 20];
+        let count = format_decimal(&mut digits, secs);
+        // Segundos alinhados à direita em 5 colunas, como o dmesg
+        for _ in count..5 {
+            out[pos] = b' ';
+            pos += 1;
+        }
+        out[pos..pos + count].copy_from_slice(&digits[..count]);
+        pos += count;
+        out[pos] = b'.';
+        pos += 1;
+        let mut frac = [b'0'; 6];
+        let mut rest = micros;
+        for digit in frac.iter_mut().rev() {
+            *digit = b'0' + (rest % 10) as u8;
+            rest /= 10;
+        }
+        out[pos..pos + 6].copy_from_slice(&frac);
+        pos += 6;
+        out[pos..pos + 2].copy_from_slice(b"] ");
+        pos += 2;
+
+        let tag = self.level.tag().as_bytes();
+        out[pos..pos + tag.len()].copy_from_slice(tag);
+        pos += tag.len();
+        let len = self.len as usize;
+        out[pos..pos + len].copy_from_slice(&self.text[..len]);
+        pos += len;
+        out[pos] = b'\n';
+        pos + 1
+    }
+}
+
+/// Dígitos decimais de `value` no começo de `out`; retorna quantos
+fn format_decimal(out: &mut [u8; 20], mut value: u64) -> usize {
+    let mut tmp = [0u8; 20];
+    let mut count = 0;
+    loop {
+        tmp[count] = b'0' + (value % 10) as u8;
+        count += 1;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    for i in 0..count {
+        out[i] = tmp[count - 1 - i];
+    }
+    count
+}
+
+/// Linhas do log e a linha em construção
+pub struct KmsgBuffer {
+    records: [Record; KMSG_RECORDS],
+    /// Linhas gravadas desde o boot (a próxima vai em `next % KMSG_RECORDS`)
+    next: u64,
+    /// Linha sendo escrita (até o `\n`)
+    pending: Option<Record>,
+}
+
+impl KmsgBuffer {
+    pub const fn new() -> Self {
+        Self {
+            records: [Record::EMPTY; KMSG_RECORDS],
+            next: 0,
+            pending: None,
+        }
+    }
+
+    /// Começa uma linha de nível `level` (fecha a anterior, se incompleta)
+    pub fn begin(&mut self, level: Level, ts_ns: u64) {
+        if self.pending.is_some() {
+            self.commit();
+        }
+        self.pending = Some(Record::start(level, ts_ns));
+    }
+
+    /// Acrescenta bytes à linha atual; cada `\n` fecha uma linha. Texto
+    /// escrito fora de [`begin`](Self::begin) vira uma linha `Info`.
+    pub fn push(&mut self, bytes: &[u8], now_ns: impl Fn() -> u64) {
+        for &byte in bytes {
+            let record = self
+                .pending
+                .get_or_insert_with(|| Record::start(Level::Info, now_ns()));
+            if byte == b'\n' {
+                self.commit();
+            } else if (record.len as usize) < KMSG_LINE {
+                record.text[record.len as usize] = byte;
+                record.len += 1;
+            }
+        }
+    }
+
+    fn commit(&mut self) {
+        if let Some(record) = self.pending.take() {
+            self.records[(self.next % KMSG_RECORDS as u64) as usize] = record;
+            self.next += 1;
+        }
+    }
+
+    /// Sequências das linhas ainda retidas
+    fn retained(&self) -> core::ops::Range<u64> {
+        self.next.saturating_sub(KMSG_RECORDS as u64)..self.next
+    }
+
+    fn record(&self, seq: u64) -> &Record {
+        &self.records[(seq % KMSG_RECORDS as u64) as usize]
+    }
+
+    /// Copia para `out` as linhas com nível `>= min`, formatadas.
+    ///
+    /// Só entram linhas inteiras; se não couberem todas, ficam as mais
+    /// recentes. Retorna os bytes escritos.
+    pub fn read(&self, out: &mut [u8], min: Level) -> usize {
+        let mut line = [0u8; FORMATTED_MAX];
+
+        // Do fim para o começo, até onde cabe
+        let mut first = self.next;
+        let mut total = 0;
+        for seq in self.retained().rev() {
+            let record = self.record(seq);
+            if record.level < min {
+                continue;
+            }
+            let len = record.format(&mut line);
+            if total + len > out.len() {
+                break;
+            }
+            total += len;
+            first = seq;
+        }
+
+        let mut pos = 0;
+        for seq in first..self.next {
+            let record = self.record(seq);
+            if record.level < min {
+                continue;
+            }
+            let len = record.format(&mut line);
+            out[pos..pos + len].copy_from_slice(&line[..len]);
+            pos += len;
+        }
+        pos
+    }
+}
+
+impl Default for KmsgBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// BUFFER GLOBAL
+// =============================================================================
+
+static KMSG: Spinlock<KmsgBuffer> = Spinlock::new(KmsgBuffer::new());
+
+/// Começa uma linha de log de nível `level`
+pub fn begin(level: Level) {
+    let now = crate::core::time::now_ns();
+    KMSG.lock().begin(level, now);
+}
+
+/// Acrescenta texto à linha atual do log
+pub fn append(bytes: &[u8]) {
+    KMSG.lock().push(bytes, crate::core::time::now_ns);
+}
+
+/// Copia o log retido (nível `>= min`) para `out`; retorna os bytes escritos
+pub fn read(out: &mut [u8], min: Level) -> usize {
+    KMSG.lock().read(out, min)
+}
+
+/// Tamanho máximo de uma leitura completa do log
+pub const fn capacity() -> usize {
+    KMSG_RECORDS * FORMATTED_MAX
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec;
+
+    fn read_all(kmsg: &KmsgBuffer, min: Level) -> String {
+        let mut out = vec![0u8; capacity()];
+        let len = kmsg.read(&mut out, min);
+        String::from_utf8(out[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_lines_with_level_and_timestamp() {
+        let mut kmsg = Box::new(KmsgBuffer::new());
+        kmsg.begin(Level::Info, 1_500_000_000);
+        kmsg.push(b"boot ok\n", || 0);
+        kmsg.begin(Level::Error, 12_000_001_000);
+        kmsg.push(b"disco", || 0);
+        kmsg.push(b" falhou 0x1\n", || 0);
+
+        assert_eq!(
+            read_all(&kmsg, Level::Trace),
+            "[    1.500000] [INFO]  boot ok\n[   12.000001] [ERROR] disco falhou 0x1\n"
+        );
+        assert_eq!(
+            read_all(&kmsg, Level::Warn),
+            "[   12.000001] [ERROR] disco falhou 0x1\n"
+        );
+    }
+
+    #[test]
+    fn test_oldest_dropped_and_truncation() {
+        let mut kmsg = Box::new(KmsgBuffer::new());
+        for i in 0..KMSG_RECORDS + 3 {
+            kmsg.begin(Level::Info, 0);
+            let mut digits = [0u8; 20];
+            let n = format_decimal(&mut digits, i as u64);
+            kmsg.push(&digits[..n], || 0);
+            kmsg.push(b"\n", || 0);
+        }
+        let text = read_all(&kmsg, Level::Trace);
+        assert_eq!(text.lines().count(), KMSG_RECORDS);
+        assert!(text.starts_with("[    0.000000] [INFO]  3\n"));
+
+        // Linha longa é truncada; texto sem `begin` vira Info
+        kmsg.push(&[b'x'; KMSG_LINE + 10], || 0);
+        kmsg.push(b"\n", || 0);
+        let text = read_all(&kmsg, Level::Trace);
+        let last = text.lines().last().unwrap();
+        assert_eq!(last.len(), "[    0.000000] [INFO]  ".len() + KMSG_LINE);
+    }
+
+    #[test]
+    fn test_short_buffer_keeps_newest() {
+        let mut kmsg = Box::new(KmsgBuffer::new());
+        kmsg.begin(Level::Warn, 0);
+        kmsg.push(b"velha\n", || 0);
+        kmsg.begin(Level::Warn, 0);
+        kmsg.push(b"nova\n", || 0);
+
+        let mut out = [0u8; 30];
+        let len = kmsg.read(&mut out, Level::Trace);
+        assert_eq!(&out[..len], b"[    0.000000] [WARN]  nova\n");
+    }
+}
@@ -0,0 +1,219 @@
+//! Tabela de símbolos do kernel
+//!
+//! Converte endereços em `função+offset` para o backtrace do pânico (e,
+//! no futuro, `/proc/kallsyms`).
+//!
+//! ## Origem
+//! O kernel reserva a seção `.ksymtab` ([`KSYMTAB_SIZE`] bytes zerados,
+//! carregada junto com o `.rodata`). Depois do link, `tools/ksymgen` lê o
+//! `.symtab` do ELF final e grava a tabela dentro dessa seção, no próprio
+//! arquivo. Um kernel sem esse passo tem a seção zerada: [`resolve`] só
+//! devolve `None` e o backtrace sai com endereços crus.
+//!
+//! ## Formato (little-endian)
+//! | Campo     | Tamanho          | Conteúdo                                  |
+//! |-----------|------------------|-------------------------------------------|
+//! | cabeçalho | 16               | `KSYM`, count: u32, names_len: u32, 0: u32|
+//! | entradas  | count × 16       | addr: u64, size: u32, name_off: u32       |
+//! | nomes     | names_len        | nomes demangled, terminados em NUL        |
+//!
+//! As entradas vêm ordenadas por endereço, então a busca é binária.
+
+/// Espaço reservado para a tabela
+pub const KSYMTAB_SIZE: usize = 1024 * 1024;
+
+const MAGIC: &[u8; 4] = b"KSYM";
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 16;
+
+/// Reserva a seção; o conteúdo é gravado no arquivo depois do link
+#[used]
+#[link_section = ".ksymtab"]
+static KSYMTAB: [u8; KSYMTAB_SIZE] = [0; KSYMTAB_SIZE];
+
+extern "C" {
+    static __ksymtab_start: u8;
+    static __ksymtab_end: u8;
+}
+
+/// Símbolo da tabela
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    pub name: &'a str,
+    pub addr: u64,
+    pub size: u32,
+}
+
+/// Visão sobre uma tabela no formato acima
+#[derive(Clone, Copy)]
+pub struct SymbolTable<'a> {
+    entries: &'a [u8],
+    names: &'a [u8],
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Valida o cabeçalho e os tamanhos; `None` se não houver tabela
+    pub fn parse(blob: &'a [u8]) -> Option<Self> {
+        if blob.len() < HEADER_LEN || &blob[..4] != MAGIC {
+            return None;
+        }
+        let count = read_u32(blob, 4) as usize;
+        let names_len = read_u32(blob, 8) as usize;
+        let entries_end = HEADER_LEN.checked_add(count.checked_mul(ENTRY_LEN)?)?;
+        let names_end = entries_end.checked_add(names_len)?;
+        if names_end > blob.len() {
+            return None;
+        }
+        Some(Self {
+            entries: &blob[HEADER_LEN..entries_end],
+            names: &blob[entries_end..names_end],
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() / ENTRY_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn addr(&self, index: usize) -> u64 {
+        read_u64(self.entries, index * ENTRY_LEN)
+    }
+
+    /// Símbolo na posição `index` (nome inválido vira `"?"`)
+    pub fn get(&self, index: usize) -> Symbol<'a> {
+        let at = index * ENTRY_LEN;
+        let name_off = read_u32(self.entries, at + 12) as usize;
+        let name = self
+            .names
+            .get(name_off..)
+            .and_then(|rest| rest.split(|&b| b == 0).next())
+            .and_then(|raw| core::str::from_utf8(raw).ok())
+            .unwrap_or("?");
+        Symbol {
+            name,
+            addr: self.addr(index),
+            size: read_u32(self.entries, at + 8),
+        }
+    }
+
+    /// Símbolo que contém `addr`: o de maior endereço `<= addr` (busca
+    /// binária). Endereços entre símbolos ficam com o anterior.
+    pub fn lookup(&self, addr: u64) -> Option<Symbol<'a>> {
+        let (mut low, mut high) = (0, self.len());
+        // Primeiro índice com endereço > addr
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.addr(mid) <= addr {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low.checked_sub(1).map(|index| self.get(index))
+    }
+}
+
+/// Tabela embutida no kernel, se `ksymgen` a gravou
+pub fn table() -> Option<SymbolTable<'static>> {
+    // SAFETY: símbolos do linker script em volta da seção `.ksymtab`, que
+    // fica mapeada só para leitura durante toda a vida do kernel. Ler pelos
+    // símbolos (e não por `KSYMTAB`) impede o compilador de assumir zeros.
+    let blob = unsafe {
+        let start = &raw const __ksymtab_start;
+        let end = &raw const __ksymtab_end;
+        core::slice::from_raw_parts(start, end as usize - start as usize)
+    };
+    SymbolTable::parse(blob)
+}
+
+/// `addr` como `(função, offset)`, se houver tabela
+pub fn resolve(addr: u64) -> Option<(&'static str, u64)> {
+    let symbol = table()?.lookup(addr)?;
+    Some((symbol.name, addr - symbol.addr))
+}
+
+/// Reporta no boot se a tabela está presente
+pub fn init() {
+    match table() {
+        Some(table) => crate::kinfo!("(Ksym) Símbolos do kernel:", table.len() as u64),
+        None => crate::kwarn!("(Ksym) Sem tabela de símbolos (rodar tools/ksymgen)"),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Monta uma tabela como o `ksymgen` faria
+    fn build(symbols: &[(u64, u32, &str)]) -> Vec<u8> {
+        let mut names = Vec::new();
+        let mut entries = Vec::new();
+        for &(addr, size, name) in symbols {
+            entries.extend_from_slice(&addr.to_le_bytes());
+            entries.extend_from_slice(&size.to_le_bytes());
+            entries.extend_from_slice(&(names.len() as u32).to_le_bytes());
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        let mut blob = Vec::new();
+        blob.extend_from_slice(MAGIC);
+        blob.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+        blob.extend_from_slice(&(names.len() as u32).to_le_bytes());
+        blob.extend_from_slice(&0u32.to_le_bytes());
+        blob.extend_from_slice(&entries);
+        blob.extend_from_slice(&names);
+        // Resto da seção reservada, zerado
+        blob.resize(blob.len() + 64, 0);
+        blob
+    }
+
+    #[test]
+    fn test_lookup_nearest_lower() {
+        let blob = build(&[
+            (0x1000, 0x20, "kernel_main"),
+            (0x1040, 0x10, "forge::sched::yield_now"),
+            (0x2000, 0x80, "rust_begin_unwind"),
+        ]);
+        let table = SymbolTable::parse(&blob).unwrap();
+        assert_eq!(table.len(), 3);
+
+        let hit = |addr| table.lookup(addr).map(|s| (s.name, addr - s.addr));
+        assert_eq!(hit(0x1000), Some(("kernel_main", 0)));
+        assert_eq!(hit(0x1013), Some(("kernel_main", 0x13)));
+        // Entre símbolos: fica com o anterior
+        assert_eq!(hit(0x1030), Some(("kernel_main", 0x30)));
+        assert_eq!(hit(0x1048), Some(("forge::sched::yield_now", 8)));
+        assert_eq!(hit(0x9000), Some(("rust_begin_unwind", 0x7000)));
+        assert_eq!(hit(0xfff), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_or_truncated() {
+        assert!(SymbolTable::parse(&[0; 64]).is_none());
+
+        let blob = build(&[(0x1000, 0, "a")]);
+        let header_and_entry = HEADER_LEN + ENTRY_LEN;
+        assert!(SymbolTable::parse(&blob[..header_and_entry]).is_none());
+
+        let empty = build(&[]);
+        let table = SymbolTable::parse(&empty).unwrap();
+        assert!(table.is_empty());
+        assert!(table.lookup(0x1000).is_none());
+    }
+}
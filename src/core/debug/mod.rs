@@ -4,14 +4,23 @@
 /// Fornece ferramentas para inspeção, logging, tracing e estatísticas do kernel.
 ///
 /// Módulos contidos:
+/// - `backtrace`: Backtrace por frame pointer (usado no pânico).
 /// - `klog`: Macros de logging (kinfo, kerror, etc).
+/// - `kmsg`: Buffer circular do log, lido pelo userspace.
+/// - `ksym`: Tabela de símbolos (endereço → função+offset).
 /// - `kdebug`: Utilitários de debug (breakpoints, assertions).
 /// - `oops`: Tratamento de erros recuperáveis.
 /// - `stats`: Contadores globais de performance/eventos.
 /// - `trace`: Sistema de tracing leve.
 
+pub mod backtrace;
 pub mod klog;
+pub mod kmsg;
 pub mod kdebug;
+pub mod ksym;
 pub mod oops;
 pub mod stats;
 pub mod trace;
+
+#[cfg(feature = "self_test")]
+pub mod test;
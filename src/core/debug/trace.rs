@@ -10,23 +10,32 @@
 // Sistema de Tracing Simplificado
 // Apenas macros diretas
 
+/// Trace Log (Compilado apenas em debug/test; desligado em runtime até o
+/// limite do subsistema chegar a `Level::Trace`)
 #[macro_export]
 macro_rules! ktrace {
+    ($sub:ident: $msg:expr) => {
+        #[cfg(debug_assertions)]
+        {
+            $crate::klog_line!($sub, Trace, $msg)
+        }
+    };
+    ($sub:ident: $msg:expr, $val:expr) => {
+        #[cfg(debug_assertions)]
+        {
+            $crate::klog_line!($sub, Trace, $msg, $val)
+        }
+    };
     ($name:expr) => {
         #[cfg(debug_assertions)]
         {
-            $crate::drivers::serial::write_str("[TRACE] ");
-            $crate::drivers::serial::write_str($name);
-            $crate::drivers::serial::write_str("\n");
+            $crate::klog_line!(Kernel, Trace, $name)
         }
     };
     ($msg:expr, $val:expr) => {
         #[cfg(debug_assertions)]
         {
-            $crate::drivers::serial::write_str("[TRACE] ");
-            $crate::drivers::serial::write_str($msg);
-            $crate::core::debug::klog::SerialDebug::serial_debug(&$val);
-            $crate::drivers::serial::write_str("\n");
+            $crate::klog_line!(Kernel, Trace, $msg, $val)
         }
     };
 }
@@ -8,6 +8,7 @@
 //! |----------|-----------------------------------------------|
 //! | `boot`   | Inicialização, kernel_main, panic handler     |
 //! | `object` | Gerenciamento de objetos kernel (handles)     |
+//! | `sched`  | Política CFS (vruntime, pesos por nice)       |
 //! | `smp`    | Multiprocessamento (per-cpu, IPI, topology)   |
 //! | `time`   | Relógios e timers                             |
 //! | `work`   | Trabalho diferido (workqueues, tasklets)      |
@@ -30,6 +31,12 @@ pub use boot::handoff::BootInfo;
 
 pub mod object;
 
+// =============================================================================
+// SCHED — Política de Escalonamento (CFS)
+// =============================================================================
+
+pub mod sched;
+
 // =============================================================================
 // SMP — Multiprocessamento Simétrico
 // =============================================================================
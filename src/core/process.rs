@@ -8,10 +8,12 @@ pub fn spawn_init() {
     let init_path = "/system/core/supervisor";
 
     // Caminho para a função spawn via sched -> exec -> spawn (mod) -> spawn (file) -> spawn (func)
-    match crate::sched::exec::spawn(init_path, None) {
+    match crate::sched::exec::spawn(init_path, &[init_path], &[], None) {
         Ok(pid) => {
             // Access public field .0 since Pid is tuple struct
             crate::kinfo!("Init process spawned. PID:", pid.0 as u64);
+            // Adota os processos órfãos
+            crate::sched::task::family::set_init(crate::sys::types::Tid::new(pid.0));
         }
         Err(crate::sched::ExecError::NotFound) => {
             core::panic!(
@@ -0,0 +1,279 @@
+//! # CFS — Completely Fair Scheduler (núcleo)
+//!
+//! Política de tempo justo: cada entidade acumula `vruntime`, o tempo de CPU
+//! consumido escalado pelo inverso do seu peso (derivado do `nice`). A próxima
+//! a executar é sempre a de menor `vruntime`.
+//!
+//! ## Estrutura
+//! - As entidades prontas ficam em uma árvore Red-Black (`klib::tree`) com
+//!   chave `(vruntime, tid)` — o TID desempata entidades com o mesmo `vruntime`.
+//! - A entidade em execução fica fora da árvore (`current`) e volta a ela
+//!   quando `pick_next` é chamado.
+//! - `min_vruntime` é monotônico e serve de piso para entidades que chegam,
+//!   impedindo que uma task recém-acordada monopolize a CPU.
+//!
+//! Este módulo só implementa a política; a troca de contexto continua em
+//! `sched::core`.
+
+use crate::klib::tree::RbTree;
+use crate::sys::types::Tid;
+
+// =============================================================================
+// CONSTANTES
+// =============================================================================
+
+/// Níveis de prioridade (0..100 realtime, 100..140 normais)
+pub const MAX_PRIO: usize = 140;
+
+/// Primeiro nível não-realtime
+pub const MAX_RT_PRIO: usize = 100;
+
+/// Faixa de nice: -20 (mais CPU) .. 19 (menos CPU)
+pub const NICE_MIN: i8 = -20;
+pub const NICE_MAX: i8 = 19;
+
+/// Peso de uma entidade com nice 0
+pub const NICE_0_LOAD: u64 = 1024;
+
+/// Tabela nice → peso do Linux (`sched_prio_to_weight`). Cada nível de nice
+/// altera a fatia de CPU em ~10% (razão ~1.25 entre pesos vizinhos).
+#[rustfmt::skip]
+const NICE_TO_WEIGHT: [u32; 40] = [
+    /* -20 */ 88761, 71755, 56483, 46273, 36291,
+    /* -15 */ 29154, 23254, 18705, 14949, 11916,
+    /* -10 */ 9548, 7620, 6100, 4904, 3906,
+    /*  -5 */ 3121, 2501, 1991, 1586, 1277,
+    /*   0 */ 1024, 820, 655, 526, 423,
+    /*   5 */ 335, 272, 215, 172, 137,
+    /*  10 */ 110, 87, 70, 56, 45,
+    /*  15 */ 36, 29, 23, 18, 15,
+];
+
+/// Peso correspondente a um valor de nice (saturado em `NICE_MIN..=NICE_MAX`)
+pub fn nice_to_weight(nice: i8) -> u32 {
+    let nice = nice.clamp(NICE_MIN, NICE_MAX);
+    NICE_TO_WEIGHT[(nice - NICE_MIN) as usize]
+}
+
+/// Nível de prioridade (100..140) correspondente a um nice
+pub fn nice_to_prio(nice: i8) -> usize {
+    (MAX_RT_PRIO as isize + 20 + nice.clamp(NICE_MIN, NICE_MAX) as isize) as usize
+}
+
+// =============================================================================
+// ENTIDADE
+// =============================================================================
+
+/// Entidade escalonável pelo CFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedEntity {
+    pub tid: Tid,
+    pub nice: i8,
+    pub weight: u32,
+    /// Tempo virtual (ns ponderados)
+    pub vruntime: u64,
+    /// Tempo real de CPU acumulado (ns)
+    pub sum_exec_runtime: u64,
+}
+
+impl SchedEntity {
+    pub fn new(tid: Tid, nice: i8) -> Self {
+        Self {
+            tid,
+            nice,
+            weight: nice_to_weight(nice),
+            vruntime: 0,
+            sum_exec_runtime: 0,
+        }
+    }
+
+    /// Converte tempo real em tempo virtual: `delta * NICE_0_LOAD / weight`
+    pub fn calc_delta_fair(&self, delta_ns: u64) -> u64 {
+        if self.weight as u64 == NICE_0_LOAD {
+            return delta_ns;
+        }
+        ((delta_ns as u128 * NICE_0_LOAD as u128) / self.weight as u128) as u64
+    }
+
+    fn key(&self) -> (u64, u32) {
+        (self.vruntime, self.tid.as_u32())
+    }
+}
+
+// =============================================================================
+// SCHEDULER
+// =============================================================================
+
+/// Runqueue CFS
+pub struct Scheduler {
+    /// Entidades prontas, ordenadas por `(vruntime, tid)`
+    tree: RbTree<(u64, u32), SchedEntity>,
+    /// Entidade em execução (fora da árvore)
+    current: Option<SchedEntity>,
+    /// Menor `vruntime` observado (monotônico)
+    min_vruntime: u64,
+}
+
+impl Scheduler {
+    /// Runqueue vazia
+    pub const fn new() -> Self {
+        Self {
+            tree: RbTree::new(),
+            current: None,
+            min_vruntime: 0,
+        }
+    }
+
+    /// Número de entidades (prontas + em execução)
+    pub fn nr_running(&self) -> usize {
+        self.tree.len() + self.current.is_some() as usize
+    }
+
+    pub fn min_vruntime(&self) -> u64 {
+        self.min_vruntime
+    }
+
+    /// Entidade em execução
+    pub fn current(&self) -> Option<&SchedEntity> {
+        self.current.as_ref()
+    }
+
+    /// Adiciona uma entidade pronta.
+    ///
+    /// O `vruntime` é elevado até `min_vruntime`, para que tasks novas ou
+    /// acordadas não acumulem "crédito" enquanto estavam fora da fila.
+    pub fn enqueue(&mut self, mut entity: SchedEntity) {
+        entity.vruntime = core::cmp::max(entity.vruntime, self.min_vruntime);
+        self.tree.insert(entity.key(), entity);
+    }
+
+    /// Retira a entidade em execução da runqueue (bloqueio ou saída)
+    pub fn dequeue_current(&mut self) -> Option<SchedEntity> {
+        self.current.take()
+    }
+
+    /// Seleciona a entidade de menor `vruntime`.
+    ///
+    /// A entidade em execução volta para a árvore antes da escolha, logo ela
+    /// continua se ainda for a mais "atrasada".
+    pub fn pick_next(&mut self) -> Option<Tid> {
+        if let Some(prev) = self.current.take() {
+            self.tree.insert(prev.key(), prev);
+        }
+
+        let (_, next) = self.tree.pop_first()?;
+        let tid = next.tid;
+        self.current = Some(next);
+        self.update_min_vruntime();
+        Some(tid)
+    }
+
+    /// Contabiliza `delta_ns` de CPU para a entidade em execução
+    pub fn tick(&mut self, delta_ns: u64) {
+        if let Some(ref mut curr) = self.current {
+            curr.sum_exec_runtime += delta_ns;
+            curr.vruntime += curr.calc_delta_fair(delta_ns);
+        }
+        self.update_min_vruntime();
+    }
+
+    fn update_min_vruntime(&mut self) {
+        let leftmost = self.tree.min().map(|(_, e)| e.vruntime);
+        let candidate = match (self.current.as_ref().map(|e| e.vruntime), leftmost) {
+            (Some(c), Some(l)) => core::cmp::min(c, l),
+            (Some(c), None) => c,
+            (None, Some(l)) => l,
+            (None, None) => return,
+        };
+        self.min_vruntime = core::cmp::max(self.min_vruntime, candidate);
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weight_table() {
+        assert_eq!(nice_to_weight(0), 1024);
+        assert_eq!(nice_to_weight(-20), 88761);
+        assert_eq!(nice_to_weight(19), 15);
+        assert_eq!(nice_to_weight(100), 15);
+        assert_eq!(nice_to_prio(0), 120);
+        assert_eq!(nice_to_prio(NICE_MAX), MAX_PRIO - 1);
+    }
+
+    #[test]
+    fn test_picks_smallest_vruntime() {
+        let mut sched = Scheduler::new();
+        assert!(sched.pick_next().is_none());
+
+        let mut a = SchedEntity::new(Tid::new(1), 0);
+        let mut b = SchedEntity::new(Tid::new(2), 0);
+        a.vruntime = 500;
+        b.vruntime = 100;
+        sched.enqueue(a);
+        sched.enqueue(b);
+
+        assert_eq!(sched.pick_next(), Some(Tid::new(2)));
+        sched.tick(1000);
+        // b agora tem vruntime 1100 > 500
+        assert_eq!(sched.pick_next(), Some(Tid::new(1)));
+        assert_eq!(sched.nr_running(), 2);
+    }
+
+    #[test]
+    fn test_cpu_time_follows_weights() {
+        const SLICE_NS: u64 = 1_000_000;
+        let mut sched = Scheduler::new();
+        sched.enqueue(SchedEntity::new(Tid::new(1), 0));
+        sched.enqueue(SchedEntity::new(Tid::new(2), 5));
+
+        let mut runtime = [0u64; 2];
+        for _ in 0..10_000 {
+            let tid = sched.pick_next().unwrap();
+            sched.tick(SLICE_NS);
+            runtime[tid.as_u32() as usize - 1] += SLICE_NS;
+        }
+
+        // Razão esperada: 1024 / 335 ~= 3.06
+        let ratio_x100 = runtime[0] * 100 / runtime[1];
+        let expected_x100 = 1024 * 100 / 335;
+        assert!(
+            ratio_x100.abs_diff(expected_x100) * 100 <= expected_x100 * 5,
+            "razão de CPU fora de 5%"
+        );
+    }
+
+    #[test]
+    fn test_new_entity_starts_at_min_vruntime() {
+        let mut sched = Scheduler::new();
+        sched.enqueue(SchedEntity::new(Tid::new(1), 0));
+        for _ in 0..10 {
+            sched.pick_next();
+            sched.tick(1000);
+        }
+        let floor = sched.min_vruntime();
+        assert!(floor > 0);
+
+        sched.enqueue(SchedEntity::new(Tid::new(2), 0));
+        // Sem o piso, a nova entidade (vruntime 0) rodaria as 10 fatias seguintes
+        let mut runs = [0u32; 2];
+        for _ in 0..10 {
+            let tid = sched.pick_next().unwrap();
+            sched.tick(1000);
+            runs[tid.as_u32() as usize - 1] += 1;
+        }
+        assert_eq!(runs, [5, 5]);
+    }
+}
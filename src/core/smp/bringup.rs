@@ -1,12 +1,120 @@
 //! Bringup de APs (Application Processors)
 //!
 //! Responsável por acordar outros núcleos da CPU.
+//!
+//! Os APs vêm da MADT (`acpi::cpus()`) e sobem com INIT-SIPI-SIPI a partir
+//! do trampolim em memória baixa (`arch::x86_64::smp::trampoline`). Um AP
+//! por vez: o trampolim tem um só bloco de parâmetros, então o BSP espera o
+//! check-in de cada AP antes de acordar o próximo.
+//!
+//! Cada AP carrega a sua GDT/TSS, a sua área por CPU (`GS` base, ver
+//! [`super::percpu`]) e a IDT compartilhada, liga o LAPIC, registra o seu
+//! APIC ID (o que faz [`super::current_cpu_id`] funcionar nele) e fica em
+//! `sched::core::idle::ap_idle_loop`.
+
+use super::topology::{CpuId, TOPOLOGY};
+use crate::arch::x86_64::apic::lapic;
+use crate::arch::x86_64::cpu::Cpu;
+use crate::arch::x86_64::smp::trampoline::{self, ApBootData};
+use crate::drivers::timer::pit;
+
+/// Stack de cada AP
+const AP_STACK_SIZE: usize = 16 * 1024;
+
+/// Esperas do INIT-SIPI-SIPI (Intel SDM: 10 ms após o INIT, 200 µs entre
+/// SIPIs; o PIT só mede milissegundos)
+const INIT_DELAY_MS: u32 = 10;
+const SIPI_DELAY_MS: u32 = 1;
 
+/// Tempo máximo para um AP se registrar depois do segundo SIPI
+const CHECKIN_TIMEOUT_MS: u32 = 200;
 
 /// Inicializa o subsistema de SMP
+///
+/// Registra o BSP como CPU 0 e acorda os APs habilitados na MADT. Sem MADT,
+/// sem APIC ou com um único processador, segue só com o BSP.
 pub fn init() {
     crate::kinfo!("(SMP) Init");
-    // TODO: Detectar CPUs e iniciar APs via wake_ap
+
+    let bsp_apic_id = Cpu::apic_id();
+    let cpus = crate::arch::x86_64::acpi::cpus();
+    let bsp_acpi_id = cpus
+        .iter()
+        .find(|cpu| cpu.apic_id == bsp_apic_id)
+        .map_or(0, |cpu| cpu.acpi_id);
+
+    let bsp = TOPOLOGY.lock().register_cpu(bsp_apic_id, bsp_acpi_id, true);
+    super::register_apic_id(bsp_apic_id, bsp);
+
+    let aps: alloc::vec::Vec<_> = cpus
+        .iter()
+        .filter(|cpu| cpu.enabled && cpu.apic_id != bsp_apic_id)
+        .collect();
+
+    if aps.is_empty() {
+        crate::kinfo!("(SMP) Apenas o BSP, APIC ID:", bsp_apic_id);
+        return;
+    }
+    if !Cpu::has_apic() {
+        crate::kwarn!("(SMP) CPU sem APIC; APs ignorados:", aps.len());
+        return;
+    }
+
+    // O BSP envia as IPIs pelo próprio LAPIC
+    unsafe { lapic::init() };
+
+    let mut expected: u32 = 0;
+    for ap in aps {
+        if ap.apic_id > 0xFF {
+            crate::kwarn!("(SMP) APIC ID fora do xAPIC, ignorado:", ap.apic_id);
+            continue;
+        }
+        if TOPOLOGY.lock().count() >= super::percpu::MAX_CPUS {
+            crate::kwarn!("(SMP) Limite de CPUs atingido:", super::percpu::MAX_CPUS);
+            break;
+        }
+
+        expected += 1;
+        let cpu_id = TOPOLOGY.lock().register_cpu(ap.apic_id, ap.acpi_id, false);
+        if let Err(e) = unsafe { start_ap(ap.apic_id, cpu_id) } {
+            crate::kerror!("(SMP) Falha ao iniciar AP:", e);
+        }
+    }
+
+    // Check-in dos APs: o esperado tem que bater com o que subiu
+    let online = super::cpu_count() as u32 - 1;
+    if online == expected {
+        crate::kinfo!("(SMP) APs online:", online);
+    } else {
+        crate::kerror!("(SMP) APs esperados:", expected);
+        crate::kerror!("(SMP) APs online:", online);
+    }
+}
+
+/// Acorda um AP e espera o check-in dele.
+///
+/// # Safety
+/// BSP com LAPIC inicializado; nenhum outro AP no trampolim.
+unsafe fn start_ap(apic_id: u32, cpu_id: CpuId) -> Result<(), &'static str> {
+    let layout = alloc::alloc::Layout::from_size_align(AP_STACK_SIZE, 16).unwrap();
+    let stack = alloc::alloc::alloc_zeroed(layout);
+    if stack.is_null() {
+        return Err("sem memória para a stack do AP");
+    }
+    let stack_top = stack as u64 + AP_STACK_SIZE as u64;
+
+    let data = ApBootData::from_current(stack_top, ap_entry, cpu_id)?;
+    trampoline::install(&data);
+    wake_ap(apic_id, trampoline::TRAMPOLINE_BASE)?;
+
+    for _ in 0..CHECKIN_TIMEOUT_MS {
+        if TOPOLOGY.lock().is_online(cpu_id) {
+            return Ok(());
+        }
+        pit::busy_wait_ms(1);
+    }
+    // A stack fica alocada: o AP pode ainda estar no trampolim
+    Err("AP não respondeu")
 }
 
 /// Tenta acordar uma CPU específica.
@@ -34,29 +142,52 @@ pub unsafe fn wake_ap(apic_id: u32, trampoline_addr: u64) -> Result<(), &'static
 
     let sipi_vector = (trampoline_addr >> 12) as u8;
 
-    // TODO: Usar funções de envio de IPI raw da arquitetura, pois send_ipi genérico usa vetor da IDT,
-    // e aqui precisamos enviar sinais INIT/STARTUP especiais que não são vetores normais.
-    // Como não expusemos isso ainda, deixaremos como TODO descritivo.
-
     // Sequência padrão x86 INIT-SIPI-SIPI:
 
     // 1. Enviar INIT IPI
-    // crate::arch::apic::send_init_ipi(apic_id);
     crate::kdebug!("Enviando INIT...");
+    if !lapic::send_init(apic_id) {
+        return Err("INIT não foi entregue");
+    }
 
     // 2. Esperar 10ms
-    // crate::arch::delay(10_000);
+    pit::busy_wait_ms(INIT_DELAY_MS);
 
     // 3. Enviar SIPI (Startup IPI) com o vetor do trampolim
-    // crate::arch::apic::send_sipi(apic_id, sipi_vector);
     crate::kdebug!("Enviando SIPI 1 (Vector ", sipi_vector as u64);
+    if !lapic::send_startup(apic_id, sipi_vector) {
+        return Err("SIPI não foi entregue");
+    }
 
     // 4. Esperar 200us
-    // crate::arch::delay(200);
+    pit::busy_wait_ms(SIPI_DELAY_MS);
 
-    // 5. Enviar segundo SIPI (resiliência)
-    // crate::arch::apic::send_sipi(apic_id, sipi_vector);
+    // 5. Enviar segundo SIPI (resiliência; ignorado se o AP já acordou)
     crate::kdebug!("Enviando SIPI 2");
+    if !lapic::send_startup(apic_id, sipi_vector) {
+        return Err("SIPI não foi entregue");
+    }
 
     Ok(())
 }
+
+/// Entrada em Rust dos APs, vinda do trampolim
+extern "C" fn ap_entry(cpu_id: u64) -> ! {
+    let cpu_id = cpu_id as CpuId;
+    unsafe {
+        crate::arch::x86_64::gdt::init_ap();
+        super::percpu::init_ap(cpu_id);
+        crate::arch::x86_64::interrupts::load_idt();
+        lapic::init();
+    }
+    crate::arch::x86_64::smap::init_ap();
+
+    super::register_apic_id(Cpu::apic_id(), cpu_id);
+    // Contador antes da topologia: o BSP espera pela topologia e depois lê
+    // o contador
+    super::mark_online();
+    TOPOLOGY.lock().set_online(cpu_id);
+    crate::kinfo!("(SMP) AP online, CPU:", cpu_id);
+
+    crate::sched::core::idle::ap_idle_loop()
+}
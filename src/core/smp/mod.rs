@@ -1,15 +1,60 @@
-/// Arquivo: core/smp/mod.rs
-///
-/// Propósito: Módulo de Multiprocessamento Simétrico (SMP).
-/// Gerencia a descoberta, inicialização e comunicação entre múltiplos cores de CPU.
-///
-/// Módulos contidos:
-/// - `percpu`: Variáveis locais de CPU.
-/// - `topology`: Detecção de Cores/Sockets.
-/// - `bringup`: Inicialização de APs (Application Processors).
-/// - `ipi`: Inter-Processor Interrupts.
+//! Arquivo: core/smp/mod.rs
+//!
+//! Propósito: Módulo de Multiprocessamento Simétrico (SMP).
+//! Gerencia a descoberta, inicialização e comunicação entre múltiplos cores de CPU.
+//!
+//! Módulos contidos:
+//! - `percpu`: Área por CPU (`GS` base, `this_cpu()`) e variáveis locais de CPU.
+//! - `topology`: Detecção de Cores/Sockets.
+//! - `bringup`: Inicialização de APs (Application Processors).
+//! - `ipi`: Inter-Processor Interrupts.
 
-pub mod percpu;
-pub mod topology;
 pub mod bringup;
 pub mod ipi;
+pub mod percpu;
+pub mod topology;
+
+#[cfg(feature = "self_test")]
+pub mod test;
+
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use topology::CpuId;
+
+/// CPUs online, BSP incluso
+static ONLINE_CPUS: AtomicU32 = AtomicU32::new(1);
+
+/// Slot de `APIC_TO_CPU` sem CPU registrada
+const NO_CPU: u8 = u8::MAX;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const UNMAPPED: AtomicU8 = AtomicU8::new(NO_CPU);
+
+/// APIC ID (xAPIC, 8 bits) → id lógico
+static APIC_TO_CPU: [AtomicU8; 256] = [UNMAPPED; 256];
+
+/// Número de CPUs online (1 até o bringup dos APs)
+pub fn cpu_count() -> usize {
+    ONLINE_CPUS.load(Ordering::Acquire) as usize
+}
+
+/// Id lógico da CPU atual (0 = BSP).
+///
+/// Vem do APIC ID inicial (CPUID), sem depender de page tables; antes do
+/// bringup todo mundo é a CPU 0.
+pub fn current_cpu_id() -> CpuId {
+    let apic_id = crate::arch::x86_64::cpu::Cpu::apic_id() as usize;
+    match APIC_TO_CPU[apic_id & 0xFF].load(Ordering::Relaxed) {
+        NO_CPU => 0,
+        id => id as CpuId,
+    }
+}
+
+/// Associa o APIC ID de uma CPU ao id lógico dela
+fn register_apic_id(apic_id: u32, cpu: CpuId) {
+    APIC_TO_CPU[(apic_id & 0xFF) as usize].store(cpu as u8, Ordering::Relaxed);
+}
+
+/// Conta um AP que terminou o bringup
+fn mark_online() {
+    ONLINE_CPUS.fetch_add(1, Ordering::Release);
+}
@@ -5,12 +5,20 @@
 /// evitando contenda de locks (cache contention) e melhorando escalabilidade.
 ///
 /// Detalhes de Implementação:
-/// - Abordagem baseada em Array: `PerCpu<T>` mantém um array `[T; MAX_CPUS]`.
-/// - O acesso é indexado pelo ID da CPU atual (`crate::arch::Cpu::current_core_id()`).
-/// - Em x86_64, a arquitetura pode otimizar isso usando o segmento GS, mas aqui usamos
-///   uma abstração genérica segura baseada no trait Cpu.
+/// - Área por CPU ([`PerCpu`]): uma estrutura por core, com o endereço no
+///   `GS` base. [`this_cpu`] lê a própria área com acesso relativo a `gs:`,
+///   sem lock e sem consultar o APIC. O BSP usa uma área estática (válida
+///   desde `init_basics`); cada AP aloca a sua no bringup.
+/// - Variáveis avulsas ([`PerCpuVar<T>`]): array `[T; MAX_CPUS]` indexado
+///   pelo ID da CPU atual (`crate::arch::Cpu::current_core_id()`).
 // Variáveis Per-CPU
+use super::topology::CpuId;
+use crate::arch::x86_64::cpu::Cpu;
+use crate::sched::task::Task;
 use core::cell::UnsafeCell;
+use core::mem::offset_of;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicU64, Ordering};
 
 /// Número máximo de CPUs suportadas.
 /// TODO: Tornar configurável via cfg
@@ -21,26 +29,26 @@ pub const MAX_CPUS: usize = 32;
 /// # Exemplo
 ///
 /// ```ignore
-/// static COUNTER: PerCpu<u64> = PerCpu::new(0);
+/// static COUNTER: PerCpuVar<u64> = PerCpuVar::new(0);
 ///
 /// fn inc() {
 ///     let val = COUNTER.get_mut();
 ///     *val += 1;
 /// }
 /// ```
-pub struct PerCpu<T> {
+pub struct PerCpuVar<T> {
     // UnsafeCell permite mutabilidade interior, necessário pois statics são imutáveis
     // e o acesso per-cpu é logicamente "thread-local" (mas requer cuidado com preempção/interrupção).
     data: [UnsafeCell<T>; MAX_CPUS],
 }
 
-// PerCpu é Sync se T for Send (pois cada CPU acessa o seu slot exclusivo).
+// PerCpuVar é Sync se T for Send (pois cada CPU acessa o seu slot exclusivo).
 // Na verdade, se garantirmos que apenas a CPU N acessa o slot N, nem precisamos de Sync no T,
 // mas para inicialização e destruição talvez. Send é seguro.
-unsafe impl<T: Send> Sync for PerCpu<T> {}
+unsafe impl<T: Send> Sync for PerCpuVar<T> {}
 
-impl<T: Copy> PerCpu<T> {
-    /// Cria uma nova variável PerCpu.
+impl<T: Copy> PerCpuVar<T> {
+    /// Cria uma nova variável PerCpuVar.
     /// Requer que T seja Copy para inicializar o array (const).
     pub const fn new(initial_value: T) -> Self {
         // Inicialização manual para 32 CPUs pois UnsafeCell não é Copy
@@ -128,3 +136,137 @@ impl<T: Copy> PerCpu<T> {
         }
     }
 }
+
+// =============================================================================
+// ÁREA POR CPU (GS BASE)
+// =============================================================================
+
+const MSR_GS_BASE: u32 = 0xC000_0101;
+const MSR_KERNEL_GS_BASE: u32 = 0xC000_0102;
+
+/// Dados de uma CPU, apontados pelo `GS` base enquanto ela está no kernel.
+///
+/// O começo do layout é usado pelo assembly: `syscall.s` salva o RSP do
+/// usuário em `gs:[0]` e carrega a stack do kernel de `gs:[8]`.
+#[repr(C)]
+pub struct PerCpu {
+    /// RSP do usuário salvo na entrada do SYSCALL (`gs:[0]`)
+    pub user_rsp: AtomicU64,
+    /// Stack do kernel para o SYSCALL (`gs:[8]`)
+    pub kernel_rsp: AtomicU64,
+    /// Endereço desta estrutura (`gs:[16]`), lido por [`this_cpu`]
+    self_ptr: AtomicPtr<PerCpu>,
+    /// Id lógico da CPU
+    pub cpu_id: CpuId,
+    /// Task em execução (o `Task` dentro do `Pin<Box>` em `CURRENT`); nulo
+    /// até o scheduler assumir a CPU
+    pub current_task: AtomicPtr<Task>,
+    /// Magazine do alocador por CPU; nulo enquanto o heap não tem cache por CPU
+    pub magazine: AtomicPtr<()>,
+    /// Seções de leitura RCU abertas nesta CPU (ver `sync::rcu`)
+    pub rcu_nesting: AtomicU32,
+    /// Grace period vigente no último estado quiescente desta CPU
+    pub rcu_quiescent: AtomicU64,
+    /// Locks com nível segurados por esta CPU (ver `sync::order`); só é
+    /// preenchida com a feature `lock_order`
+    pub held_locks: crate::sync::order::HeldLocks,
+}
+
+const _: () = assert!(offset_of!(PerCpu, user_rsp) == 0);
+const _: () = assert!(offset_of!(PerCpu, kernel_rsp) == 8);
+const _: () = assert!(offset_of!(PerCpu, self_ptr) == 16);
+
+impl PerCpu {
+    pub const fn new(cpu_id: CpuId) -> Self {
+        Self {
+            user_rsp: AtomicU64::new(0),
+            kernel_rsp: AtomicU64::new(0),
+            self_ptr: AtomicPtr::new(ptr::null_mut()),
+            cpu_id,
+            current_task: AtomicPtr::new(ptr::null_mut()),
+            magazine: AtomicPtr::new(ptr::null_mut()),
+            rcu_nesting: AtomicU32::new(0),
+            rcu_quiescent: AtomicU64::new(0),
+            held_locks: crate::sync::order::HeldLocks::new(),
+        }
+    }
+}
+
+/// Área do BSP: estática, para valer antes do heap
+static BSP_AREA: PerCpu = PerCpu::new(0);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_AREA: AtomicPtr<PerCpu> = AtomicPtr::new(ptr::null_mut());
+
+/// Áreas publicadas, por id lógico
+static AREAS: [AtomicPtr<PerCpu>; MAX_CPUS] = [NO_AREA; MAX_CPUS];
+
+/// Área da CPU atual.
+///
+/// Vale no BSP desde `init_basics` e em cada AP desde o bringup. Precisa do
+/// `GS` do kernel: em handlers de IRQ vindos do user mode, só depois do
+/// `swapgs`.
+#[inline]
+pub fn this_cpu() -> &'static PerCpu {
+    let area: *const PerCpu;
+    // SAFETY: o GS base aponta para uma `PerCpu` 'static (init_bsp/init_ap)
+    unsafe {
+        core::arch::asm!(
+            "mov {}, gs:[16]",
+            out(reg) area,
+            options(nostack, readonly, preserves_flags)
+        );
+        &*area
+    }
+}
+
+/// Área da CPU atual, ou `None` se o `GS` base ainda não aponta para ela
+/// (começo do boot e do bringup de um AP, ou `GS` do usuário)
+pub fn try_this_cpu() -> Option<&'static PerCpu> {
+    if Cpu::read_msr(MSR_GS_BASE) == 0 {
+        return None;
+    }
+    Some(this_cpu())
+}
+
+/// Área da CPU `cpu_id`, se ela já subiu
+pub fn area(cpu_id: CpuId) -> Option<&'static PerCpu> {
+    let area = AREAS.get(cpu_id as usize)?.load(Ordering::Acquire);
+    // SAFETY: só áreas 'static são publicadas
+    unsafe { area.as_ref() }
+}
+
+/// Instala a área do BSP no `GS` base.
+///
+/// # Safety
+/// Uma vez, no BSP, no começo do boot.
+pub unsafe fn init_bsp() {
+    install(&BSP_AREA);
+}
+
+/// Aloca e instala a área de um AP.
+///
+/// # Safety
+/// Uma vez por AP, no próprio AP, com o heap pronto.
+pub unsafe fn init_ap(cpu_id: CpuId) {
+    install(alloc::boxed::Box::leak(alloc::boxed::Box::new(
+        PerCpu::new(cpu_id),
+    )));
+}
+
+/// Aponta o `GS` base para `area` e a publica em [`area`].
+///
+/// O `GS` do usuário (KERNEL_GS_BASE, trocado por `swapgs`) começa zerado.
+unsafe fn install(area: &'static PerCpu) {
+    let addr = area as *const PerCpu;
+    area.self_ptr.store(addr as *mut PerCpu, Ordering::Relaxed);
+
+    Cpu::write_msr(MSR_GS_BASE, addr as u64);
+    Cpu::write_msr(MSR_KERNEL_GS_BASE, 0);
+
+    // Publica o que o próprio GS enxerga
+    let seen = this_cpu();
+    if let Some(slot) = AREAS.get(seen.cpu_id as usize) {
+        slot.store(seen as *const PerCpu as *mut PerCpu, Ordering::Release);
+    }
+}
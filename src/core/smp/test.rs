@@ -0,0 +1,48 @@
+//! # Testes de SMP
+//!
+//! Executados apenas com a feature `self_test`, depois do bringup dos APs
+//! (`bringup::init`).
+
+use super::percpu::{self, this_cpu};
+use super::topology::TOPOLOGY;
+use alloc::vec::Vec;
+
+pub fn run_tests() {
+    crate::kinfo!("(SMP) Iniciando testes de áreas por CPU...");
+    test_bsp_area();
+    test_distinct_areas();
+    crate::kinfo!("(SMP) Testes de áreas por CPU concluídos com SUCESSO.");
+}
+
+/// No BSP, `this_cpu` é a área da CPU 0 publicada no boot
+fn test_bsp_area() {
+    let cpu = this_cpu();
+    assert_eq!(cpu.cpu_id, 0);
+    assert_eq!(super::current_cpu_id(), 0);
+    let published = percpu::area(0).expect("(SMP) BSP sem área publicada");
+    assert!(core::ptr::eq(cpu, published));
+}
+
+/// Cada core online publicou, pelo próprio `gs:`, uma área distinta com o
+/// seu id
+fn test_distinct_areas() {
+    let online: Vec<_> = TOPOLOGY
+        .lock()
+        .iter()
+        .filter(|cpu| cpu.online)
+        .map(|cpu| cpu.logical_id)
+        .collect();
+    if online.len() < 2 {
+        crate::kinfo!("(SMP) Apenas um core online; áreas distintas não testadas");
+        return;
+    }
+
+    let mut seen: Vec<*const percpu::PerCpu> = Vec::new();
+    for id in online {
+        let area = percpu::area(id).expect("(SMP) Core online sem área publicada");
+        assert_eq!(area.cpu_id, id);
+        let addr = area as *const percpu::PerCpu;
+        assert!(!seen.contains(&addr), "(SMP) Dois cores com a mesma área");
+        seen.push(addr);
+    }
+}
@@ -66,6 +66,18 @@ impl CpuTopology {
         logical_id
     }
 
+    /// Marca uma CPU como online (o AP terminou o bringup)
+    pub fn set_online(&mut self, id: CpuId) {
+        if let Some(cpu) = self.cpus.get_mut(id as usize) {
+            cpu.online = true;
+        }
+    }
+
+    /// A CPU está online?
+    pub fn is_online(&self, id: CpuId) -> bool {
+        self.cpus.get(id as usize).is_some_and(|cpu| cpu.online)
+    }
+
     /// Retorna o número total de CPUs detectadas
     pub fn count(&self) -> usize {
         self.cpus.len()
@@ -41,21 +41,14 @@ impl SystemClock {
         self.boot_time_seconds.store(seconds, Ordering::Relaxed);
     }
 
-    /// Retorna o tempo atual aproximado (Base + Uptime)
-    /// Para precisão de nanosegundos, precisaríamos somar (Jiffies * NS_PER_TICK) ou ler TSC.
+    /// Retorna o tempo atual (Base + Uptime do relógio monotônico)
     pub fn now(&self) -> TimeSpec {
         let base = self.boot_time_seconds.load(Ordering::Relaxed);
-        let uptime_jiffies = super::jiffies::get_jiffies();
-
-        // Conversão simples Jiffies -> Segundos
-        // Assumindo HZ=100 (10ms por tick)
-        let uptime_seconds = uptime_jiffies / super::jiffies::HZ;
-        let remaining_ticks = uptime_jiffies % super::jiffies::HZ;
-        let nanos = (remaining_ticks * (1_000_000_000 / super::jiffies::HZ)) as u32;
+        let uptime_ns = super::monotonic::now_ns();
 
         TimeSpec {
-            seconds: base + uptime_seconds,
-            nanos,
+            seconds: base + uptime_ns / 1_000_000_000,
+            nanos: (uptime_ns % 1_000_000_000) as u32,
         }
     }
 }
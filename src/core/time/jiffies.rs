@@ -16,9 +16,15 @@ use core::sync::atomic::{AtomicU64, Ordering};
 /// Visibilidade de crate para permitir incremento pelo timer handler.
 pub(crate) static JIFFIES: AtomicU64 = AtomicU64::new(0);
 
-/// Frequência do Tick (Ticks por segundo)
-/// TODO: Tornar configurável
+/// Frequência do Tick (Ticks por segundo).
+/// 100 Hz por padrão; a feature `hz_1000` troca para 1000 Hz.
+#[cfg(not(feature = "hz_1000"))]
 pub const HZ: u64 = 100;
+#[cfg(feature = "hz_1000")]
+pub const HZ: u64 = 1000;
+
+/// Duração de um tick em nanossegundos
+pub const NANOS_PER_TICK: u64 = 1_000_000_000 / HZ;
 
 /// Retorna o número atual de jiffies.
 #[inline]
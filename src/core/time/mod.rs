@@ -3,10 +3,25 @@
 pub mod clock;
 pub mod hrtimer;
 pub mod jiffies;
+pub mod monotonic;
+pub mod sleep;
+pub mod tick;
 pub mod timer;
+pub mod wheel;
+
+pub use jiffies::HZ;
+pub use monotonic::{now_ns, now_ticks};
+pub use sleep::sleep_ns;
 
 /// Inicializa subsistema de tempo
 pub fn init() {
     crate::kinfo!("(Time) Init");
-    // TODO: Init PIT if needed, or HPET/TSC via drivers
+    monotonic::init();
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(feature = "self_test")]
+pub mod test;
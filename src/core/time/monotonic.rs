@@ -0,0 +1,127 @@
+//! # Relógio monotônico
+//!
+//! Tempo desde o boot com resolução de nanossegundos, derivado do TSC.
+//!
+//! - [`now_ticks`]: contador cru, em unidades de [`frequency`].
+//! - [`now_ns`]: nanossegundos desde [`init`].
+//!
+//! O TSC só é usado se for invariante e a calibração contra o PIT der um
+//! valor plausível. Caso contrário [`now_ns`] cai para o relógio escolhido
+//! em `drivers::timer` (o HPET, se houver; senão o tick, com resolução de
+//! `1/HZ`) e [`now_ticks`] para os jiffies.
+
+use super::jiffies::{get_jiffies, HZ};
+use crate::drivers::timer::tsc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Frequência mínima aceita do TSC; abaixo disso a calibração falhou
+const MIN_TSC_HZ: u64 = 100_000_000;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Frequência do TSC em Hz; 0 enquanto o relógio usa os jiffies
+static TSC_HZ: AtomicU64 = AtomicU64::new(0);
+
+/// Leitura do TSC no momento da calibração (zero do relógio)
+static TSC_BASE: AtomicU64 = AtomicU64::new(0);
+
+/// Calibra o TSC e passa a usá-lo como fonte do relógio.
+pub fn init() {
+    if !tsc::is_invariant() {
+        crate::kwarn!("(Time) TSC não invariante; relógio monotônico via jiffies");
+        return;
+    }
+
+    let hz = tsc::calibrate();
+    if hz < MIN_TSC_HZ {
+        crate::kwarn!("(Time) Calibração do TSC falhou, Hz:", hz);
+        return;
+    }
+
+    use_tsc(hz);
+    crate::kinfo!("(Time) Relógio monotônico via TSC, Hz:", hz);
+}
+
+/// Zera o relógio na leitura atual do TSC e fixa a frequência
+fn use_tsc(hz: u64) {
+    TSC_BASE.store(tsc::read(), Ordering::Relaxed);
+    // Release: quem vê a frequência vê a base
+    TSC_HZ.store(hz, Ordering::Release);
+}
+
+/// Frequência de [`now_ticks`] em Hz
+#[inline]
+pub fn frequency() -> u64 {
+    match TSC_HZ.load(Ordering::Acquire) {
+        0 => HZ,
+        hz => hz,
+    }
+}
+
+/// Contador cru desde o boot, em unidades de [`frequency`]
+#[inline]
+pub fn now_ticks() -> u64 {
+    match TSC_HZ.load(Ordering::Acquire) {
+        0 => get_jiffies(),
+        _ => tsc::read().wrapping_sub(TSC_BASE.load(Ordering::Relaxed)),
+    }
+}
+
+/// Nanossegundos desde o boot
+#[inline]
+pub fn now_ns() -> u64 {
+    match TSC_HZ.load(Ordering::Acquire) {
+        0 => crate::drivers::timer::clock().now_ns(),
+        hz => ticks_to_ns(
+            tsc::read().wrapping_sub(TSC_BASE.load(Ordering::Relaxed)),
+            hz,
+        ),
+    }
+}
+
+/// Converte `ticks` de um contador a `hz` para nanossegundos
+#[inline]
+pub fn ticks_to_ns(ticks: u64, hz: u64) -> u64 {
+    if hz == 0 {
+        return 0;
+    }
+    (ticks as u128 * NANOS_PER_SEC / hz as u128) as u64
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticks_to_ns() {
+        assert_eq!(ticks_to_ns(3_000_000_000, 3_000_000_000), 1_000_000_000);
+        assert_eq!(ticks_to_ns(1, 100), 10_000_000);
+        assert_eq!(ticks_to_ns(7, 0), 0);
+        // Sem overflow com contadores grandes
+        assert_eq!(ticks_to_ns(u64::MAX / 2, u64::MAX / 2), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_now_ns_monotonic() {
+        let mut last = now_ns();
+        for _ in 0..10_000 {
+            let now = now_ns();
+            assert!(now >= last);
+            last = now;
+        }
+
+        // Mesma coisa depois de trocar para o TSC (frequência fictícia)
+        use_tsc(2_000_000_000);
+        let mut last = now_ns();
+        for _ in 0..10_000 {
+            let now = now_ns();
+            assert!(now >= last);
+            last = now;
+        }
+        assert!(now_ticks() > 0);
+    }
+}
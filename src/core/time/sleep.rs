@@ -0,0 +1,56 @@
+//! # Sleep de alta resolução
+//!
+//! [`sleep_ns`] fixa o prazo da task atual (relógio monotônico) e a bloqueia
+//! pelo scheduler. Ao estacionar a task dormindo, o scheduler arma o prazo
+//! na roda global ([`arm`]); o handler do tick chama [`expired`] e acorda as
+//! tasks vencidas, na ordem dos prazos.
+//!
+//! A roda é indexada pelo TID. Uma task morta enquanto dorme tem o prazo
+//! desarmado ([`disarm`]).
+
+use super::jiffies::NANOS_PER_TICK;
+use super::wheel::TimerWheel;
+use crate::sync::Spinlock;
+use alloc::vec::Vec;
+
+/// Prazos de sleep por TID; criada no primeiro uso
+static SLEEP_TIMERS: Spinlock<Option<TimerWheel<u32>>> = Spinlock::new(None);
+
+/// Dorme por `ns` nanossegundos.
+///
+/// A resolução efetiva é o período do tick: o prazo é conferido a cada
+/// interrupção do timer. Retorna os nanossegundos que faltavam se a task
+/// voltou antes do prazo.
+pub fn sleep_ns(ns: u64) -> u64 {
+    if ns == 0 {
+        crate::sched::core::yield_now();
+        return 0;
+    }
+    let deadline = super::now_ns().saturating_add(ns);
+    crate::sched::core::sleep_until(deadline);
+    deadline.saturating_sub(super::now_ns())
+}
+
+/// Arma o prazo de `tid` (substitui um anterior)
+pub(crate) fn arm(tid: u32, deadline_ns: u64) {
+    SLEEP_TIMERS
+        .lock()
+        .get_or_insert_with(|| TimerWheel::new(NANOS_PER_TICK))
+        .insert(tid, deadline_ns);
+}
+
+/// Desarma o prazo de `tid`. Retorna `false` se não havia.
+pub(crate) fn disarm(tid: u32) -> bool {
+    SLEEP_TIMERS
+        .lock()
+        .as_mut()
+        .is_some_and(|wheel| wheel.cancel(tid))
+}
+
+/// TIDs com prazo vencido em `now_ns`, do mais cedo ao mais tarde
+pub(crate) fn expired(now_ns: u64) -> Vec<u32> {
+    match SLEEP_TIMERS.lock().as_mut() {
+        Some(wheel) => wheel.expire(now_ns),
+        None => Vec::new(),
+    }
+}
@@ -0,0 +1,39 @@
+//! # Testes de tempo
+//!
+//! Executados apenas com a feature `self_test`, depois de [`super::tick::start`]
+//! (relógio escolhido e tick ligado).
+
+use crate::core::time::jiffies::{get_jiffies, NANOS_PER_TICK};
+use crate::drivers::timer;
+use crate::sched::core::yield_now;
+
+pub fn run_tests() {
+    crate::kinfo!("(Time) Iniciando testes de tempo...");
+    test_selected_clock_advances();
+    crate::kinfo!("(Time) Testes de tempo concluídos com SUCESSO.");
+}
+
+/// O relógio escolhido no boot anda, e anda junto com o tick.
+fn test_selected_clock_advances() {
+    let clock = timer::clock();
+    crate::kinfo!("(Time) Relógio em teste:", clock.name());
+
+    let first = clock.now_ns();
+    let first_mono = super::now_ns();
+    let start = get_jiffies();
+    while get_jiffies() < start + 3 {
+        yield_now();
+    }
+    let second = clock.now_ns();
+
+    assert!(second > first, "(Time) Relógio parado");
+    // Dois ticks inteiros passaram entre as leituras
+    assert!(
+        second - first >= 2 * NANOS_PER_TICK,
+        "(Time) Relógio mais lento que o tick"
+    );
+    assert!(
+        super::now_ns() > first_mono,
+        "(Time) Relógio monotônico parado"
+    );
+}
@@ -0,0 +1,58 @@
+//! # Fonte do tick periódico
+//!
+//! O tick do scheduler (jiffies, quantum, sleep queue) vem do primeiro
+//! timer detectado na ordem de `drivers::timer::TICK_ORDER`: timer do LAPIC,
+//! HPET (no IRQ 0, em legacy replacement) ou PIT. No mesmo passo o relógio
+//! de `drivers::timer` é escolhido (HPET, se houver).
+//!
+//! As fontes caem no mesmo handler (`timer_handler` em interrupts.s), que
+//! só precisa saber a quem mandar o EOI: ver [`eoi`].
+//!
+//! A frequência é [`HZ`](super::jiffies::HZ), fixada em tempo de compilação.
+
+use super::jiffies::{HZ, NANOS_PER_TICK};
+use crate::arch::x86_64::apic::lapic;
+use crate::drivers::timer::{self, TimerKind};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+static SOURCE: AtomicU8 = AtomicU8::new(TimerKind::Pit as u8);
+
+/// Fonte de tick em uso
+pub fn source() -> TimerKind {
+    TimerKind::from_u8(SOURCE.load(Ordering::Relaxed))
+}
+
+/// Detecta os timers, escolhe o relógio e liga o tick periódico. Chamar com
+/// o scheduler pronto e o ACPI lido.
+pub fn start() {
+    timer::detect();
+    let clock = timer::select_clock();
+    crate::kinfo!("(Tick) Relógio:", clock.name());
+
+    for kind in timer::candidates(&timer::TICK_ORDER) {
+        let device = timer::device(kind);
+        if device.set_periodic(NANOS_PER_TICK).is_err() {
+            crate::kwarn!("(Tick) Timer recusou o modo periódico:", device.name());
+            continue;
+        }
+
+        SOURCE.store(kind as u8, Ordering::Relaxed);
+        // PIT e HPET (legacy replacement) chegam pelo IRQ 0; com o LAPIC o
+        // PIT fica programado, mas mascarado no PIC
+        if kind != TimerKind::ApicTimer {
+            crate::arch::x86_64::interrupts::pic_enable_irq(0);
+        }
+        crate::kinfo!("(Tick) Fonte do tick:", device.name());
+        crate::kinfo!("(Tick) Tick periódico, Hz:", HZ);
+        return;
+    }
+}
+
+/// Fim de interrupção do tick, para a fonte em uso
+#[inline]
+pub fn eoi() {
+    match source() {
+        TimerKind::ApicTimer => unsafe { lapic::eoi() },
+        TimerKind::Pit | TimerKind::Hpet => crate::arch::x86_64::ports::outb(0x20, 0x20),
+    }
+}
@@ -45,5 +45,4 @@ impl Timer {
     }
 }
 
-// TODO: Implementar Timers List ou Timer Wheel para gerenciamento eficiente
-// de múltiplos timers ativos.
+// Para prazos em massa (ex.: tasks dormindo), ver `super::wheel::TimerWheel`.
@@ -0,0 +1,182 @@
+//! # Timer wheel
+//!
+//! Prazos absolutos em nanossegundos (relógio de [`super::monotonic`])
+//! distribuídos em [`SLOTS`] baldes de `granularity_ns` cada. Inserir e
+//! cancelar custam O(log n) (índice por chave); o avanço só visita os baldes
+//! dos ticks decorridos. Prazos além de uma volta completa ficam no balde e
+//! são ignorados até a volta certa.
+//!
+//! Cada chave tem no máximo um prazo ativo: inserir de novo substitui.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Número de baldes (uma volta = `SLOTS * granularity_ns`)
+pub const SLOTS: usize = 256;
+
+/// Roda de prazos indexada por chave
+pub struct TimerWheel<K: Copy + Ord> {
+    granularity_ns: u64,
+    slots: Vec<Vec<(K, u64)>>,
+    /// Chave → balde onde está o prazo
+    index: BTreeMap<K, usize>,
+    /// Último tick processado por [`TimerWheel::expire`]
+    current_tick: u64,
+}
+
+impl<K: Copy + Ord> TimerWheel<K> {
+    /// Roda vazia. `granularity_ns` normalmente é o período do tick.
+    pub fn new(granularity_ns: u64) -> Self {
+        Self {
+            granularity_ns: granularity_ns.max(1),
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+            index: BTreeMap::new(),
+            current_tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Prazo ativo de `key`
+    pub fn deadline(&self, key: K) -> Option<u64> {
+        let slot = *self.index.get(&key)?;
+        self.slots[slot]
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|&(_, deadline)| deadline)
+    }
+
+    /// Agenda `key` para `deadline_ns`, substituindo um prazo anterior.
+    ///
+    /// Um prazo já vencido sai no próximo [`TimerWheel::expire`].
+    pub fn insert(&mut self, key: K, deadline_ns: u64) {
+        self.cancel(key);
+        // Nunca atrás do tick atual, senão esperaria uma volta inteira
+        let tick = (deadline_ns / self.granularity_ns).max(self.current_tick);
+        let slot = tick as usize % SLOTS;
+        self.slots[slot].push((key, deadline_ns));
+        self.index.insert(key, slot);
+    }
+
+    /// Remove o prazo de `key`. Retorna `false` se não havia.
+    pub fn cancel(&mut self, key: K) -> bool {
+        let Some(slot) = self.index.remove(&key) else {
+            return false;
+        };
+        let bucket = &mut self.slots[slot];
+        if let Some(pos) = bucket.iter().position(|(k, _)| *k == key) {
+            bucket.swap_remove(pos);
+        }
+        true
+    }
+
+    /// Retira os prazos vencidos em `now_ns`, do mais cedo ao mais tarde
+    /// (empates pela chave).
+    pub fn expire(&mut self, now_ns: u64) -> Vec<K> {
+        let mut expired: Vec<(u64, K)> = Vec::new();
+        let target_tick = now_ns / self.granularity_ns;
+
+        if !self.index.is_empty() && target_tick >= self.current_tick {
+            // O balde do tick atual é revisitado: pode ter prazos mais
+            // adiante no mesmo tick
+            let ticks = (target_tick - self.current_tick + 1).min(SLOTS as u64);
+            for offset in 0..ticks {
+                let slot = (self.current_tick + offset) as usize % SLOTS;
+                let bucket = &mut self.slots[slot];
+                let mut i = 0;
+                while i < bucket.len() {
+                    if bucket[i].1 <= now_ns {
+                        let (key, deadline) = bucket.swap_remove(i);
+                        self.index.remove(&key);
+                        expired.push((deadline, key));
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        self.current_tick = self.current_tick.max(target_tick);
+
+        expired.sort_unstable();
+        expired.into_iter().map(|(_, key)| key).collect()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    #[test]
+    fn test_wake_order_by_deadline() {
+        // Tick de 10 ms; a task 2 dorme mais que a 1
+        let mut wheel = TimerWheel::new(10 * MS);
+        wheel.insert(2u32, 45 * MS);
+        wheel.insert(1u32, 12 * MS);
+
+        assert!(wheel.expire(5 * MS).is_empty());
+        assert_eq!(wheel.expire(20 * MS), [1]);
+        assert!(wheel.expire(40 * MS).is_empty());
+        assert_eq!(wheel.expire(50 * MS), [2]);
+        assert!(wheel.is_empty());
+
+        // Os dois vencem no mesmo avanço: ordem pelo prazo
+        wheel.insert(7, 90 * MS);
+        wheel.insert(8, 70 * MS);
+        assert_eq!(wheel.expire(100 * MS), [8, 7]);
+    }
+
+    #[test]
+    fn test_same_tick_and_past_deadlines() {
+        let mut wheel = TimerWheel::new(10 * MS);
+        assert!(wheel.expire(100 * MS).is_empty());
+
+        // Mais adiante no tick atual: sai na próxima chamada dentro do tick
+        wheel.insert(1u32, 108 * MS);
+        assert!(wheel.expire(105 * MS).is_empty());
+        assert_eq!(wheel.expire(109 * MS), [1]);
+
+        // Já vencido ao inserir
+        wheel.insert(2, 50 * MS);
+        assert_eq!(wheel.expire(109 * MS), [2]);
+    }
+
+    #[test]
+    fn test_beyond_one_revolution() {
+        let mut wheel = TimerWheel::new(MS);
+        let far = (SLOTS as u64 * 3 + 5) * MS;
+        wheel.insert(1u32, far);
+
+        // Passa pelo mesmo balde em voltas anteriores sem disparar
+        assert!(wheel.expire(5 * MS).is_empty());
+        assert!(wheel.expire((SLOTS as u64 + 5) * MS).is_empty());
+        assert_eq!(wheel.deadline(1), Some(far));
+        assert_eq!(wheel.expire(far), [1]);
+    }
+
+    #[test]
+    fn test_cancel_and_reinsert() {
+        let mut wheel = TimerWheel::new(10 * MS);
+        wheel.insert(1u32, 20 * MS);
+        wheel.insert(2u32, 30 * MS);
+        assert!(wheel.cancel(1));
+        assert!(!wheel.cancel(1));
+
+        // Reinserir substitui o prazo anterior
+        wheel.insert(2, 80 * MS);
+        assert_eq!(wheel.len(), 1);
+        assert!(wheel.expire(50 * MS).is_empty());
+        assert_eq!(wheel.expire(80 * MS), [2]);
+    }
+}
@@ -0,0 +1,78 @@
+//! # Trabalho com atraso
+//!
+//! [`schedule_after`] guarda a closure e arma o prazo numa roda de timers
+//! (`core::time::wheel`). A cada tick, [`run_expired`] move as vencidas para
+//! a [`SYSTEM_WQ`], na ordem dos prazos: a closure roda no worker, nunca no
+//! handler da interrupção.
+
+use super::workqueue::{OnceWork, SYSTEM_WQ};
+use crate::core::time::jiffies::NANOS_PER_TICK;
+use crate::core::time::wheel::TimerWheel;
+use crate::sync::Spinlock;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+
+/// Identificador de um trabalho com atraso, para [`cancel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DelayedWork(u64);
+
+struct Delayed {
+    next_id: u64,
+    timers: Option<TimerWheel<u64>>,
+    pending: BTreeMap<u64, Box<dyn FnOnce() + Send>>,
+}
+
+static DELAYED: Spinlock<Delayed> = Spinlock::new(Delayed {
+    next_id: 0,
+    timers: None,
+    pending: BTreeMap::new(),
+});
+
+/// Agenda `f` para rodar na fila do sistema depois de `delay_ns` nanossegundos.
+///
+/// A resolução é o período do tick.
+pub fn schedule_after<F>(delay_ns: u64, f: F) -> DelayedWork
+where
+    F: FnOnce() + Send + 'static,
+{
+    let deadline = crate::core::time::now_ns().saturating_add(delay_ns);
+
+    let mut delayed = DELAYED.lock();
+    let id = delayed.next_id;
+    delayed.next_id += 1;
+    delayed.pending.insert(id, Box::new(f));
+    delayed
+        .timers
+        .get_or_insert_with(|| TimerWheel::new(NANOS_PER_TICK))
+        .insert(id, deadline);
+    DelayedWork(id)
+}
+
+/// Cancela um trabalho ainda não vencido. Retorna `false` se ele já foi
+/// entregue à fila (ou cancelado).
+pub fn cancel(work: DelayedWork) -> bool {
+    let mut delayed = DELAYED.lock();
+    if let Some(timers) = delayed.timers.as_mut() {
+        timers.cancel(work.0);
+    }
+    delayed.pending.remove(&work.0).is_some()
+}
+
+/// Entrega à fila do sistema os trabalhos vencidos em `now_ns`.
+///
+/// Chamado pelo handler do tick.
+pub(crate) fn run_expired(now_ns: u64) {
+    let mut guard = DELAYED.lock();
+    let delayed = &mut *guard;
+    let Some(timers) = delayed.timers.as_mut() else {
+        return;
+    };
+    if timers.is_empty() {
+        return;
+    }
+    for id in timers.expire(now_ns) {
+        if let Some(func) = delayed.pending.remove(&id) {
+            SYSTEM_WQ.enqueue(OnceWork::new(func));
+        }
+    }
+}
@@ -1,5 +1,49 @@
 //! Trabalho Diferido
+//!
+//! [`schedule`] enfileira uma closure na fila do sistema ([`SYSTEM_WQ`]);
+//! a thread `kworker` a executa em ordem, fora de contexto de interrupção,
+//! podendo bloquear. [`schedule_after`] faz o mesmo depois de um atraso.
+//!
+//! Handlers de interrupção usam isto para adiar o que não precisa rodar com
+//! a IRQ em atendimento (logs, processamento de dados recebidos...).
 
 pub mod deferred;
+pub mod delayed;
 pub mod tasklet;
 pub mod workqueue;
+
+pub use delayed::{cancel, schedule_after, DelayedWork};
+pub use workqueue::SYSTEM_WQ;
+
+use workqueue::OnceWork;
+
+/// Enfileira `f` para rodar no worker do sistema.
+///
+/// Seguro em contexto de interrupção.
+pub fn schedule<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    SYSTEM_WQ.enqueue(OnceWork::new(f));
+}
+
+/// Cria a thread que consome a fila do sistema. Requer o scheduler pronto.
+pub fn init() {
+    crate::sched::core::spawn_kernel_thread("kworker", worker_thread);
+    crate::kinfo!("(Work) Worker do sistema iniciado");
+}
+
+/// Worker do sistema: dorme até haver trabalho e executa tudo em ordem
+extern "C" fn worker_thread() -> ! {
+    loop {
+        SYSTEM_WQ.wait_for_work();
+        SYSTEM_WQ.process_all();
+    }
+}
+
+// =============================================================================
+// TESTES
+// =============================================================================
+
+#[cfg(feature = "self_test")]
+pub mod test;
@@ -0,0 +1,63 @@
+//! # Testes do trabalho diferido
+//!
+//! Executados apenas com a feature `self_test`, depois de [`super::init`] e
+//! com o tick ligado (o trabalho com atraso depende dele).
+
+use crate::sched::core::yield_now;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Atraso do trabalho agendado com `schedule_after`
+const DELAY_NS: u64 = 50_000_000;
+
+static RAN: AtomicBool = AtomicBool::new(false);
+static DELAYED_AT: AtomicU64 = AtomicU64::new(0);
+
+pub fn run_tests() {
+    crate::kinfo!("(Work) Iniciando testes de trabalho diferido...");
+    test_schedule_runs();
+    test_schedule_after_waits();
+    test_cancel();
+    crate::kinfo!("(Work) Testes de trabalho diferido concluídos com SUCESSO.");
+}
+
+/// Uma closure agendada roda no worker.
+fn test_schedule_runs() {
+    RAN.store(false, Ordering::SeqCst);
+    super::schedule(|| RAN.store(true, Ordering::SeqCst));
+
+    while !RAN.load(Ordering::SeqCst) {
+        yield_now();
+    }
+}
+
+/// Uma closure com atraso não roda antes do prazo.
+fn test_schedule_after_waits() {
+    DELAYED_AT.store(0, Ordering::SeqCst);
+    let start = crate::core::time::now_ns();
+    super::schedule_after(DELAY_NS, || {
+        DELAYED_AT.store(crate::core::time::now_ns(), Ordering::SeqCst);
+    });
+
+    while DELAYED_AT.load(Ordering::SeqCst) == 0 {
+        yield_now();
+    }
+    let ran_at = DELAYED_AT.load(Ordering::SeqCst);
+    assert!(
+        ran_at - start >= DELAY_NS,
+        "(Work) Trabalho com atraso rodou antes do prazo"
+    );
+}
+
+/// Um trabalho cancelado nunca roda.
+fn test_cancel() {
+    RAN.store(false, Ordering::SeqCst);
+    let work = super::schedule_after(DELAY_NS, || RAN.store(true, Ordering::SeqCst));
+    assert!(super::cancel(work));
+    assert!(!super::cancel(work));
+
+    crate::core::time::sleep_ns(2 * DELAY_NS);
+    assert!(
+        !RAN.load(Ordering::SeqCst),
+        "(Work) Trabalho cancelado rodou"
+    );
+}
@@ -6,14 +6,16 @@
 //! Detalhes de Implementação:
 //! - Usa `VecDeque` protegido por `Spinlock` para armazenar trabalhos.
 //! - Suporta execução de itens enfileirados.
-//! - Projetado para ser consumido por threads dedicadas (worker threads) no scheduler.
+//! - Consumido por worker threads bloqueadas em [`WorkQueue::wait_for_work`];
+//!   `enqueue` acorda uma delas (seguro em contexto de interrupção).
 
+use crate::sched::WaitQueue;
 use crate::sync::spinlock::Spinlock;
 use alloc::boxed::Box;
 use alloc::collections::VecDeque;
 
 /// Trait para itens de trabalho
-pub trait WorkItem: Send + Sync {
+pub trait WorkItem: Send {
     /// Executa o trabalho
     fn run(&mut self);
 }
@@ -38,9 +40,35 @@ impl WorkItem for ClosureWork {
     }
 }
 
+/// Trabalho de execução única (`FnOnce`)
+pub struct OnceWork {
+    func: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl OnceWork {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        Self {
+            func: Some(Box::new(f)),
+        }
+    }
+}
+
+impl WorkItem for OnceWork {
+    fn run(&mut self) {
+        if let Some(func) = self.func.take() {
+            func();
+        }
+    }
+}
+
 /// Fila de trabalho
 pub struct WorkQueue {
     queue: Spinlock<VecDeque<Box<dyn WorkItem>>>,
+    /// Worker threads esperando trabalho
+    workers: WaitQueue,
 }
 
 impl WorkQueue {
@@ -50,17 +78,25 @@ impl WorkQueue {
     pub const fn new() -> Self {
         Self {
             queue: Spinlock::new(VecDeque::new()),
+            workers: WaitQueue::new(),
         }
     }
 
     /// Enfileira um trabalho para execução futura
     pub fn enqueue<W: WorkItem + 'static>(&self, work: W) {
-        let mut q = self.queue.lock();
-        q.push_back(Box::new(work));
+        self.queue.lock().push_back(Box::new(work));
+
+        // Fora do lock da fila: o worker reavalia a fila com o lock da WaitQueue
+        self.workers.wake_one();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
 
-        // TODO: Acordar worker thread associada a esta fila se estiver dormindo
-        // Isso requer integração com o scheduler (que não podemos importar diretamente aqui)
-        // Possível solução: Callback hook ou polling.
+    /// Bloqueia a thread atual até haver trabalho na fila
+    pub fn wait_for_work(&self) {
+        self.workers.wait_until(|| !self.is_empty());
     }
 
     /// Processa todos os itens pendentes na fila (Flush)
@@ -1,2 +1,491 @@
-//! AHCI Driver
-// TODO: Implementation logic
+//! # Driver AHCI (SATA)
+//!
+//! Driver básico para controladoras AHCI (classe PCI 0x01, subclasse 0x06,
+//! interface 0x01), como o dispositivo `ahci` do QEMU. Só leitura por
+//! enquanto.
+//!
+//! ## Referências
+//!
+//! - [Serial ATA AHCI 1.3.1](https://www.intel.com/content/www/us/en/io/serial-ata/serial-ata-ahci-spec-rev1-3-1.html)
+//!
+//! ## Funcionamento
+//!
+//! Os registradores do HBA ficam no BAR5 (ABAR, MMIO pelo HHDM). Cada porta
+//! implementada (`PI`) com um disco SATA ligado (`SSTS.DET = 3`,
+//! `SSTS.IPM = 1`, assinatura `0x101`) vira um `BlockDevice`.
+//!
+//! Cada porta tem uma página de DMA com as estruturas que o HBA lê e
+//! escreve:
+//!
+//! ```text
+//! 0x000  Command List (32 headers x 32 bytes; só o slot 0 é usado)
+//! 0x400  FIS Receive Area (256 bytes; D2H Register FIS em +0x40)
+//! 0x500  Command Table (CFIS em +0x00, PRDT em +0x80, uma entrada)
+//! 0x800  Buffer de dados (um setor)
+//! ```
+//!
+//! Cada comando (IDENTIFY, READ DMA EXT) usa o slot 0 e é síncrono: o
+//! driver seta `CI`, espera o HBA limpá-lo e confere o D2H Register FIS
+//! recebido (status e erro do disco).
+
+#![allow(dead_code)]
+
+use super::traits::{BlockDevice, BlockError};
+use crate::drivers::pci::{self, PciDevice};
+use crate::mm::pfm::iommu::DmaBuffer;
+use crate::sync::Spinlock;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{fence, Ordering};
+
+/// Classe/subclasse/interface PCI de controladoras AHCI
+const CLASS_STORAGE: u8 = 0x01;
+const SUBCLASS_SATA: u8 = 0x06;
+const PROG_IF_AHCI: u8 = 0x01;
+
+/// BAR com os registradores do HBA (ABAR)
+const ABAR: usize = 5;
+
+/// Tamanho de setor
+const SECTOR_SIZE: usize = 512;
+
+/// Iterações de polling antes de desistir de um comando
+const POLL_LIMIT: u32 = 10_000_000;
+
+/// Portas por HBA
+const MAX_PORTS: usize = 32;
+
+/// Registradores globais do HBA
+mod hba {
+    pub const CAP: usize = 0x00;
+    pub const GHC: usize = 0x04;
+    pub const PI: usize = 0x0C;
+    pub const VS: usize = 0x10;
+    /// GHC: AHCI Enable
+    pub const GHC_AE: u32 = 1 << 31;
+    /// Início dos registradores das portas; 0x80 bytes cada
+    pub const PORTS: usize = 0x100;
+    pub const PORT_SIZE: usize = 0x80;
+}
+
+/// Registradores de uma porta (offset a partir da porta)
+mod port {
+    pub const CLB: usize = 0x00; // 8 bytes
+    pub const FB: usize = 0x08; // 8 bytes
+    pub const IS: usize = 0x10;
+    pub const IE: usize = 0x14;
+    pub const CMD: usize = 0x18;
+    pub const TFD: usize = 0x20;
+    pub const SIG: usize = 0x24;
+    pub const SSTS: usize = 0x28;
+    pub const SERR: usize = 0x30;
+    pub const CI: usize = 0x38;
+
+    /// CMD: Start
+    pub const CMD_ST: u32 = 1 << 0;
+    /// CMD: FIS Receive Enable
+    pub const CMD_FRE: u32 = 1 << 4;
+    /// CMD: FIS Receive Running
+    pub const CMD_FR: u32 = 1 << 14;
+    /// CMD: Command List Running
+    pub const CMD_CR: u32 = 1 << 15;
+
+    /// IS: Task File Error Status
+    pub const IS_TFES: u32 = 1 << 30;
+
+    /// Assinatura de disco SATA
+    pub const SIG_ATA: u32 = 0x0000_0101;
+}
+
+/// Bits de status do ATA (TFD e D2H FIS)
+mod ata_status {
+    pub const BSY: u8 = 0x80;
+    pub const DRQ: u8 = 0x08;
+    pub const ERR: u8 = 0x01;
+}
+
+/// Comandos ATA
+mod cmd {
+    pub const READ_DMA_EXT: u8 = 0x25;
+    pub const IDENTIFY: u8 = 0xEC;
+}
+
+/// Layout da página de DMA de uma porta
+mod layout {
+    pub const CMD_LIST: usize = 0x000;
+    pub const FIS: usize = 0x400;
+    /// D2H Register FIS dentro da FIS Receive Area
+    pub const RFIS: usize = FIS + 0x40;
+    pub const CMD_TABLE: usize = 0x500;
+    /// PRDT dentro da Command Table
+    pub const PRDT: usize = CMD_TABLE + 0x80;
+    pub const DATA: usize = 0x800;
+    pub const SIZE: usize = DATA + super::SECTOR_SIZE;
+}
+
+/// Tipo do Register FIS host-to-device
+const FIS_TYPE_REG_H2D: u8 = 0x27;
+
+/// Register FIS host-to-device (20 bytes) de um comando LBA48
+fn h2d_fis(command: u8, lba: u64, count: u16) -> [u8; 20] {
+    let mut fis = [0u8; 20];
+    fis[0] = FIS_TYPE_REG_H2D;
+    // C = 1: o FIS carrega um comando
+    fis[1] = 0x80;
+    fis[2] = command;
+    fis[4] = lba as u8;
+    fis[5] = (lba >> 8) as u8;
+    fis[6] = (lba >> 16) as u8;
+    // Device: modo LBA
+    fis[7] = 0x40;
+    fis[8] = (lba >> 24) as u8;
+    fis[9] = (lba >> 32) as u8;
+    fis[10] = (lba >> 40) as u8;
+    fis[12] = count as u8;
+    fis[13] = (count >> 8) as u8;
+    fis
+}
+
+/// Há um disco SATA pronto na porta (`SSTS` e `SIG`)
+fn is_sata_disk(ssts: u32, sig: u32) -> bool {
+    let det = ssts & 0xF;
+    let ipm = (ssts >> 8) & 0xF;
+    det == 3 && ipm == 1 && sig == port::SIG_ATA
+}
+
+/// Número de setores nos dados do IDENTIFY (LBA48, ou LBA28 sem ele)
+fn identify_sectors(identify: &[u16; 256]) -> u64 {
+    if identify[83] & (1 << 10) != 0 {
+        (identify[100] as u64)
+            | ((identify[101] as u64) << 16)
+            | ((identify[102] as u64) << 32)
+            | ((identify[103] as u64) << 48)
+    } else {
+        (identify[60] as u64) | ((identify[61] as u64) << 16)
+    }
+}
+
+/// Registradores MMIO do HBA
+struct Hba {
+    base: *mut u8,
+}
+
+// SAFETY: MMIO do HBA; cada porta é acessada só com o lock dela
+unsafe impl Send for Hba {}
+unsafe impl Sync for Hba {}
+
+impl Hba {
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { core::ptr::read_volatile(self.base.add(offset) as *const u32) }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        unsafe { core::ptr::write_volatile(self.base.add(offset) as *mut u32, value) }
+    }
+
+    fn port_read(&self, n: usize, reg: usize) -> u32 {
+        self.read(hba::PORTS + n * hba::PORT_SIZE + reg)
+    }
+
+    fn port_write(&self, n: usize, reg: usize, value: u32) {
+        self.write(hba::PORTS + n * hba::PORT_SIZE + reg, value)
+    }
+
+    /// Espera os bits `mask` de um registrador da porta ficarem em zero
+    fn port_wait_clear(&self, n: usize, reg: usize, mask: u32) -> bool {
+        for _ in 0..POLL_LIMIT {
+            if self.port_read(n, reg) & mask == 0 {
+                return true;
+            }
+            core::hint::spin_loop();
+        }
+        false
+    }
+}
+
+/// Disco SATA em uma porta do HBA
+pub struct AhciPort {
+    hba: Arc<Hba>,
+    /// Número da porta no HBA
+    port: usize,
+    /// Página de DMA da porta; o lock serializa os comandos
+    dma: Spinlock<DmaBuffer>,
+    /// Total de setores
+    sectors: u64,
+}
+
+impl AhciPort {
+    /// Prepara a porta `n` e identifica o disco
+    fn new(hba: Arc<Hba>, n: usize) -> Option<Self> {
+        let dma = DmaBuffer::new(layout::SIZE).ok()?;
+
+        // Parar a porta antes de trocar CLB/FB
+        let command = hba.port_read(n, port::CMD);
+        hba.port_write(n, port::CMD, command & !(port::CMD_ST | port::CMD_FRE));
+        if !hba.port_wait_clear(n, port::CMD, port::CMD_CR | port::CMD_FR) {
+            crate::kerror!("(AHCI) Porta não parou:", n as u64);
+            return None;
+        }
+
+        let base = dma.phys().as_u64();
+        hba.port_write(n, port::CLB, (base + layout::CMD_LIST as u64) as u32);
+        hba.port_write(
+            n,
+            port::CLB + 4,
+            ((base + layout::CMD_LIST as u64) >> 32) as u32,
+        );
+        hba.port_write(n, port::FB, (base + layout::FIS as u64) as u32);
+        hba.port_write(n, port::FB + 4, ((base + layout::FIS as u64) >> 32) as u32);
+
+        // Sem interrupções; limpar erros e status antigos (W1C)
+        hba.port_write(n, port::IE, 0);
+        hba.port_write(n, port::SERR, u32::MAX);
+        hba.port_write(n, port::IS, u32::MAX);
+
+        let command = hba.port_read(n, port::CMD);
+        hba.port_write(n, port::CMD, command | port::CMD_FRE);
+        hba.port_write(n, port::CMD, command | port::CMD_FRE | port::CMD_ST);
+
+        let mut drive = Self {
+            hba,
+            port: n,
+            dma: Spinlock::new(dma),
+            sectors: 0,
+        };
+
+        let mut identify = [0u16; 256];
+        {
+            let dma = drive.dma.lock();
+            drive.issue(&dma, cmd::IDENTIFY, 0).ok()?;
+            // SAFETY: setor de dados preenchido pelo IDENTIFY
+            unsafe {
+                let words = dma.as_ptr().add(layout::DATA) as *const u16;
+                for (i, word) in identify.iter_mut().enumerate() {
+                    *word = core::ptr::read_volatile(words.add(i));
+                }
+            }
+        }
+        drive.sectors = identify_sectors(&identify);
+        if drive.sectors == 0 {
+            crate::kerror!("(AHCI) IDENTIFY sem setores na porta:", n as u64);
+            return None;
+        }
+        Some(drive)
+    }
+
+    /// Executa `command` sobre um setor no slot 0 e espera a conclusão.
+    ///
+    /// O resultado vem do D2H Register FIS que o disco devolve; `IS.TFES`
+    /// adianta a falha sem esperar o `CI`.
+    fn issue(&self, dma: &DmaBuffer, command: u8, lba: u64) -> Result<(), BlockError> {
+        let n = self.port;
+        let hba = &self.hba;
+        let page = dma.as_ptr();
+        let phys = dma.phys().as_u64();
+
+        if !hba.port_wait_clear(n, port::TFD, (ata_status::BSY | ata_status::DRQ) as u32) {
+            return Err(BlockError::Busy);
+        }
+
+        // SAFETY: estruturas dentro da página de DMA da porta, com o lock;
+        // o HBA só as lê depois do CI
+        unsafe {
+            // Command header 0: FIS de 5 dwords, leitura, uma entrada na PRDT
+            let header = page.add(layout::CMD_LIST) as *mut u32;
+            core::ptr::write_volatile(header, 5 | (1 << 16));
+            core::ptr::write_volatile(header.add(1), 0);
+            let table = phys + layout::CMD_TABLE as u64;
+            core::ptr::write_volatile(header.add(2), table as u32);
+            core::ptr::write_volatile(header.add(3), (table >> 32) as u32);
+
+            let fis = h2d_fis(command, lba, 1);
+            core::ptr::write_bytes(page.add(layout::CMD_TABLE), 0, 0x80);
+            core::ptr::copy_nonoverlapping(fis.as_ptr(), page.add(layout::CMD_TABLE), fis.len());
+
+            // PRD: o setor vai para o buffer de dados
+            let prd = page.add(layout::PRDT) as *mut u32;
+            let data = phys + layout::DATA as u64;
+            core::ptr::write_volatile(prd, data as u32);
+            core::ptr::write_volatile(prd.add(1), (data >> 32) as u32);
+            core::ptr::write_volatile(prd.add(2), 0);
+            core::ptr::write_volatile(prd.add(3), SECTOR_SIZE as u32 - 1);
+
+            // Status antigo no FIS recebido não pode passar por conclusão
+            core::ptr::write_bytes(page.add(layout::RFIS), 0, 20);
+        }
+        fence(Ordering::SeqCst);
+
+        hba.port_write(n, port::IS, u32::MAX);
+        hba.port_write(n, port::CI, 1);
+
+        let mut polls = POLL_LIMIT;
+        loop {
+            if hba.port_read(n, port::IS) & port::IS_TFES != 0 {
+                break;
+            }
+            if hba.port_read(n, port::CI) & 1 == 0 {
+                break;
+            }
+            if polls == 0 {
+                crate::kerror!("(AHCI) Timeout no comando, porta:", n as u64);
+                return Err(BlockError::IoError);
+            }
+            polls -= 1;
+            core::hint::spin_loop();
+        }
+        fence(Ordering::SeqCst);
+
+        // SAFETY: FIS escrito pelo HBA na conclusão
+        let (status, error) = unsafe {
+            (
+                core::ptr::read_volatile(page.add(layout::RFIS + 2)),
+                core::ptr::read_volatile(page.add(layout::RFIS + 3)),
+            )
+        };
+        let tfes = hba.port_read(n, port::IS) & port::IS_TFES != 0;
+        if tfes || status & ata_status::ERR != 0 {
+            crate::kerror!("(AHCI) Comando falhou, erro=", error as u64);
+            hba.port_write(n, port::IS, u32::MAX);
+            return Err(BlockError::IoError);
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for AhciPort {
+    fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+        if lba >= self.sectors {
+            return Err(BlockError::InvalidBlock);
+        }
+        if buf.len() < SECTOR_SIZE {
+            return Err(BlockError::InvalidBuffer);
+        }
+
+        let dma = self.dma.lock();
+        self.issue(&dma, cmd::READ_DMA_EXT, lba)?;
+        // SAFETY: setor lido pelo HBA, com o lock
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                dma.as_ptr().add(layout::DATA),
+                buf.as_mut_ptr(),
+                SECTOR_SIZE,
+            );
+        }
+        Ok(())
+    }
+
+    fn write_block(&self, _lba: u64, _buf: &[u8]) -> Result<(), BlockError> {
+        // TODO: WRITE DMA EXT
+        Err(BlockError::ReadOnly)
+    }
+
+    fn block_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.sectors
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn device_type(&self) -> &'static str {
+        "ahci"
+    }
+}
+
+/// Liga o modo AHCI de uma controladora e abre os discos das portas ativas
+fn init_controller(dev: PciDevice) -> Vec<Arc<dyn BlockDevice>> {
+    let mut disks: Vec<Arc<dyn BlockDevice>> = Vec::new();
+    let abar = match dev.bar(ABAR) {
+        Some(bar) if !bar.is_io => bar,
+        _ => {
+            crate::kerror!("(AHCI) BAR5 não é MMIO");
+            return disks;
+        }
+    };
+    dev.enable_memory_space();
+    dev.enable_bus_master();
+
+    // SAFETY: ABAR da controladora, pelo HHDM
+    let hba = Arc::new(Hba {
+        base: unsafe { crate::mm::addr::phys_to_virt::<u8>(abar.address) },
+    });
+    hba.write(hba::GHC, hba.read(hba::GHC) | hba::GHC_AE);
+    crate::kinfo!("(AHCI) Versão:", hba.read(hba::VS));
+
+    let implemented = hba.read(hba::PI);
+    for n in (0..MAX_PORTS).filter(|n| implemented & (1 << n) != 0) {
+        let ssts = hba.port_read(n, port::SSTS);
+        let sig = hba.port_read(n, port::SIG);
+        if !is_sata_disk(ssts, sig) {
+            continue;
+        }
+        match AhciPort::new(hba.clone(), n) {
+            Some(disk) => {
+                crate::kinfo!("(AHCI) Disco SATA na porta:", n as u64);
+                crate::kinfo!("(AHCI) Setores:", disk.sectors);
+                disks.push(Arc::new(disk));
+            }
+            None => crate::kerror!("(AHCI) Falha ao abrir a porta:", n as u64),
+        }
+    }
+    disks
+}
+
+/// Inicializa as controladoras AHCI encontradas no PCI; retorna um disco
+/// por porta SATA ativa
+pub fn init() -> Vec<Arc<dyn BlockDevice>> {
+    crate::kinfo!("(AHCI) Procurando controladoras...");
+
+    pci::find_by_class(CLASS_STORAGE, SUBCLASS_SATA)
+        .into_iter()
+        .filter(|dev| dev.prog_if == PROG_IF_AHCI)
+        .flat_map(init_controller)
+        .collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_h2d_fis_lba48() {
+        let fis = h2d_fis(cmd::READ_DMA_EXT, 0x0000_1234_5678_9ABC, 1);
+        assert_eq!(&fis[..4], &[0x27, 0x80, 0x25, 0x00]);
+        assert_eq!(&fis[4..8], &[0xBC, 0x9A, 0x78, 0x40]);
+        assert_eq!(&fis[8..11], &[0x56, 0x34, 0x12]);
+        assert_eq!(&fis[12..14], &[1, 0]);
+    }
+
+    #[test]
+    fn test_active_port_detection() {
+        // DET = 3 (presente, comunicando), IPM = 1 (ativo)
+        assert!(is_sata_disk(0x113, port::SIG_ATA));
+        // Sem disco, ou em economia de energia
+        assert!(!is_sata_disk(0x000, port::SIG_ATA));
+        assert!(!is_sata_disk(0x613, port::SIG_ATA));
+        // ATAPI (0xEB140101) não é disco
+        assert!(!is_sata_disk(0x113, 0xEB14_0101));
+    }
+
+    #[test]
+    fn test_identify_sectors() {
+        let mut identify = [0u16; 256];
+        identify[60] = 0x5678;
+        identify[61] = 0x0012;
+        assert_eq!(identify_sectors(&identify), 0x0012_5678);
+
+        identify[83] = 1 << 10;
+        identify[100] = 0x0000;
+        identify[101] = 0x0010;
+        identify[102] = 0x0001;
+        assert_eq!(identify_sectors(&identify), 0x0001_0010_0000);
+    }
+}
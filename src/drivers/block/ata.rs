@@ -14,10 +14,18 @@
 //! | 0x1F5  | LBA High         |
 //! | 0x1F6  | Drive/Head       |
 //! | 0x1F7  | Status/Command   |
+//! | 0x3F6  | Alt Status       |
+//!
+//! ## Escrita
+//! `write_block` usa WRITE SECTORS (0x30) em PIO e, em seguida, FLUSH CACHE
+//! (0xE7): quando retorna `Ok`, o setor já saiu do cache do drive. Um ERR no
+//! status vira `IoError` (o Error Register vai para o log) e um DF (falha do
+//! drive) vira `HardwareError`.
 
 #![allow(dead_code)]
 
 use super::traits::{BlockDevice, BlockError};
+use crate::sync::Spinlock;
 use alloc::sync::Arc;
 use core::arch::asm;
 
@@ -32,12 +40,14 @@ mod ports {
     pub const DRIVE_HEAD: u16 = 0x1F6;
     pub const STATUS: u16 = 0x1F7;
     pub const COMMAND: u16 = 0x1F7;
+    pub const ALT_STATUS: u16 = 0x3F6;
 }
 
 /// Bits do Status Register
 mod status {
     pub const BSY: u8 = 0x80; // Busy
     pub const DRDY: u8 = 0x40; // Drive Ready
+    pub const DF: u8 = 0x20; // Drive Fault
     pub const DRQ: u8 = 0x08; // Data Request
     pub const ERR: u8 = 0x01; // Error
 }
@@ -46,6 +56,7 @@ mod status {
 mod cmd {
     pub const READ_SECTORS: u8 = 0x20;
     pub const WRITE_SECTORS: u8 = 0x30;
+    pub const FLUSH_CACHE: u8 = 0xE7;
     pub const IDENTIFY: u8 = 0xEC;
 }
 
@@ -55,6 +66,8 @@ pub struct AtaDrive {
     drive: u8,
     /// Número total de setores
     sectors: u64,
+    /// Serializa os comandos: cada um é uma sequência de portas
+    io: Spinlock<()>,
 }
 
 impl AtaDrive {
@@ -109,38 +122,54 @@ impl AtaDrive {
         crate::kinfo!("(ATA) Setores:", sectors);
         crate::kinfo!("(ATA) Capacidade MB:", (sectors * 512) / (1024 * 1024));
 
-        Some(Self { drive: 0, sectors })
+        Some(Self {
+            drive: 0,
+            sectors,
+            io: Spinlock::new(()),
+        })
     }
 
-    /// Lê setores usando PIO LBA28
-    fn read_sectors_pio(&self, lba: u64, count: u8, buf: &mut [u8]) -> Result<(), BlockError> {
-        if lba > 0x0FFFFFFF {
+    /// Seleciona o drive e programa LBA28 e contagem; o comando vem depois
+    ///
+    /// # Safety
+    /// Acesso direto às portas do canal primário, com `io` adquirido.
+    unsafe fn setup_lba28(&self, lba: u64, count: u8) -> Result<(), BlockError> {
+        let end = lba
+            .checked_add(count as u64)
+            .ok_or(BlockError::InvalidBlock)?;
+        if count == 0 || end > self.sectors.min(0x1000_0000) {
             return Err(BlockError::InvalidBlock);
         }
-
         let lba = lba as u32;
 
-        unsafe {
-            // Esperar drive pronto
-            if !wait_ready() {
-                return Err(BlockError::IoError);
-            }
+        if !wait_ready() {
+            return Err(BlockError::Busy);
+        }
+        outb(
+            ports::DRIVE_HEAD,
+            0xE0 | (self.drive << 4) | ((lba >> 24) & 0x0F) as u8,
+        );
+        delay_400ns();
+        outb(ports::SECTOR_COUNT, count);
+        outb(ports::LBA_LO, (lba & 0xFF) as u8);
+        outb(ports::LBA_MID, ((lba >> 8) & 0xFF) as u8);
+        outb(ports::LBA_HI, ((lba >> 16) & 0xFF) as u8);
+        Ok(())
+    }
+
+    /// Lê setores usando PIO LBA28
+    fn read_sectors_pio(&self, lba: u64, count: u8, buf: &mut [u8]) -> Result<(), BlockError> {
+        let _io = self.io.lock();
 
-            // Configurar LBA e drive
-            outb(ports::DRIVE_HEAD, 0xE0 | ((lba >> 24) & 0x0F) as u8);
-            outb(ports::SECTOR_COUNT, count);
-            outb(ports::LBA_LO, (lba & 0xFF) as u8);
-            outb(ports::LBA_MID, ((lba >> 8) & 0xFF) as u8);
-            outb(ports::LBA_HI, ((lba >> 16) & 0xFF) as u8);
+        unsafe {
+            self.setup_lba28(lba, count)?;
 
             // Enviar comando READ
             outb(ports::COMMAND, cmd::READ_SECTORS);
 
             // Ler cada setor
             for sector in 0..count as usize {
-                if !wait_drq() {
-                    return Err(BlockError::IoError);
-                }
+                wait_data()?;
 
                 // Ler 256 words (512 bytes)
                 let offset = sector * 512;
@@ -154,6 +183,33 @@ impl AtaDrive {
 
         Ok(())
     }
+
+    /// Escreve setores usando PIO LBA28 e esvazia o cache do drive
+    fn write_sectors_pio(&self, lba: u64, count: u8, buf: &[u8]) -> Result<(), BlockError> {
+        let _io = self.io.lock();
+
+        unsafe {
+            self.setup_lba28(lba, count)?;
+
+            // Enviar comando WRITE
+            outb(ports::COMMAND, cmd::WRITE_SECTORS);
+
+            // O drive pede cada setor com DRQ
+            for sector in 0..count as usize {
+                wait_data()?;
+
+                let offset = sector * 512;
+                for i in (0..512).step_by(2) {
+                    let word = u16::from_le_bytes([buf[offset + i], buf[offset + i + 1]]);
+                    outw(ports::DATA, word);
+                }
+            }
+
+            // Último setor aceito: esperar o fim e esvaziar o cache
+            wait_done()?;
+            flush_cache()
+        }
+    }
 }
 
 impl BlockDevice for AtaDrive {
@@ -164,9 +220,11 @@ impl BlockDevice for AtaDrive {
         self.read_sectors_pio(block, 1, buf)
     }
 
-    fn write_block(&self, _block: u64, _buf: &[u8]) -> Result<(), BlockError> {
-        // TODO: Implementar escrita
-        Err(BlockError::ReadOnly)
+    fn write_block(&self, block: u64, buf: &[u8]) -> Result<(), BlockError> {
+        if buf.len() < 512 {
+            return Err(BlockError::InvalidBuffer);
+        }
+        self.write_sectors_pio(block, 1, buf)
     }
 
     fn block_size(&self) -> usize {
@@ -177,9 +235,20 @@ impl BlockDevice for AtaDrive {
         self.sectors
     }
 
-    fn is_read_only(&self) -> bool {
-        // Por enquanto, somente leitura
-        true
+    fn device_type(&self) -> &'static str {
+        "ata"
+    }
+
+    fn flush(&self) -> Result<(), BlockError> {
+        let _io = self.io.lock();
+        unsafe {
+            if !wait_ready() {
+                return Err(BlockError::Busy);
+            }
+            outb(ports::DRIVE_HEAD, 0xE0 | (self.drive << 4));
+            delay_400ns();
+            flush_cache()
+        }
     }
 }
 
@@ -208,6 +277,62 @@ fn wait_drq() -> bool {
     false
 }
 
+/// Espera o drive pedir ou entregar um setor (DRQ), reportando ERR e DF
+fn wait_data() -> Result<(), BlockError> {
+    for _ in 0..100000 {
+        let status = unsafe { inb(ports::STATUS) };
+        if status & status::BSY != 0 {
+            continue;
+        }
+        check_error(status)?;
+        if status & status::DRQ != 0 {
+            return Ok(());
+        }
+    }
+    Err(BlockError::Busy)
+}
+
+/// Espera o fim de um comando (BSY=0), reportando ERR e DF
+fn wait_done() -> Result<(), BlockError> {
+    for _ in 0..100000 {
+        let status = unsafe { inb(ports::STATUS) };
+        if status & status::BSY == 0 {
+            return check_error(status);
+        }
+    }
+    Err(BlockError::Busy)
+}
+
+/// Traduz os bits de erro do status
+fn check_error(status: u8) -> Result<(), BlockError> {
+    if status & status::DF != 0 {
+        crate::kerror!("(ATA) Falha do drive (DF), status=", status as u64);
+        return Err(BlockError::HardwareError);
+    }
+    if status & status::ERR != 0 {
+        let error = unsafe { inb(ports::ERROR) };
+        crate::kerror!("(ATA) Comando falhou, error=", error as u64);
+        return Err(BlockError::IoError);
+    }
+    Ok(())
+}
+
+/// Envia FLUSH CACHE e espera o drive gravar o cache de escrita
+///
+/// # Safety
+/// Acesso direto às portas do canal primário, com o drive já selecionado.
+unsafe fn flush_cache() -> Result<(), BlockError> {
+    outb(ports::COMMAND, cmd::FLUSH_CACHE);
+    wait_done()
+}
+
+/// Espera ~400ns (quatro leituras do Alt Status) após selecionar o drive
+fn delay_400ns() {
+    for _ in 0..4 {
+        unsafe { inb(ports::ALT_STATUS) };
+    }
+}
+
 // I/O helpers
 unsafe fn inb(port: u16) -> u8 {
     let value: u8;
@@ -225,6 +350,10 @@ unsafe fn inw(port: u16) -> u16 {
     value
 }
 
+unsafe fn outw(port: u16, value: u16) {
+    asm!("out dx, ax", in("dx") port, in("ax") value, options(nomem, nostack));
+}
+
 /// Inicializa o driver ATA e retorna o dispositivo se encontrado
 pub fn init() -> Option<Arc<dyn BlockDevice>> {
     AtaDrive::new().map(|d| Arc::new(d) as Arc<dyn BlockDevice>)
@@ -6,28 +6,52 @@
 //!
 //! | Driver      | Status      | Descrição                    |
 //! |-------------|-------------|------------------------------|
-//! | ATA/IDE     | Funcional   | Para QEMU fat:rw: disks      |
-//! | VirtIO-BLK  | Em progresso| Disco paravirtualizado QEMU  |
-//! | AHCI        | Planejado   | SATA/AHCI                    |
-//! | NVMe        | Planejado   | NVMe SSDs                    |
-//! | Ramdisk     | Planejado   | Disco em memória             |
+//! | ATA/IDE     | Funcional   | PIO, leitura e escrita       |
+//! | VirtIO-BLK  | Funcional   | Disco paravirtualizado QEMU  |
+//! | AHCI        | Parcial     | SATA/AHCI, só leitura (DMA)  |
+//! | NVMe        | Funcional   | Namespace 1, I/O por polling |
+//! | Ramdisk     | Funcional   | Disco em memória             |
+//!
+//! Cada dispositivo registrado recebe um nome `<tipo><n>` (`ata0`, `ram1`),
+//! com `n` contando os dispositivos do mesmo tipo.
+//!
+//! Tabelas de partição (MBR/GPT) são lidas por [`partition`].
 
 pub mod ahci;
 pub mod ata;
 pub mod nvme;
+pub mod partition;
 pub mod ramdisk;
 pub mod traits;
 pub mod virtio_blk;
 pub mod virtqueue;
 
+#[cfg(any(
+    feature = "virtio_blk_test",
+    feature = "ata_test",
+    feature = "nvme_test",
+    feature = "ahci_test"
+))]
+pub mod test;
+
+pub use partition::{Partition, PartitionDevice, PartitionError};
+pub use ramdisk::RamDisk;
 pub use traits::{BlockDevice, BlockDeviceInfo, BlockError};
 
 use crate::sync::Spinlock;
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
+/// Dispositivo no registro, com o nome atribuído
+struct Registered {
+    name: String,
+    device: Arc<dyn BlockDevice>,
+}
+
 /// Registro global de dispositivos de bloco
-static BLOCK_DEVICES: Spinlock<Vec<Arc<dyn BlockDevice>>> = Spinlock::new(Vec::new());
+static BLOCK_DEVICES: Spinlock<Vec<Registered>> = Spinlock::new(Vec::new());
 
 /// Inicializa o subsistema de dispositivos de bloco
 pub fn init() {
@@ -42,25 +66,72 @@ pub fn init() {
         register_device(device);
     }
 
-    // Tenta VirtIO-BLK se ATA não funcionou
-    if BLOCK_DEVICES.lock().is_empty() {
-        if let Some(device) = virtio_blk::init() {
-            register_device(device);
-        }
+    // VirtIO-BLK convive com o ATA; o primeiro registrado continua sendo o
+    // disco padrão (`first_device`)
+    if let Some(device) = virtio_blk::init() {
+        crate::kinfo!("(Block) VirtIO-BLK registrado");
+        register_device(device);
+    }
+
+    for device in nvme::init() {
+        crate::kinfo!("(Block) NVMe registrado");
+        register_device(device);
+    }
+
+    for device in ahci::init() {
+        crate::kinfo!("(Block) AHCI registrado");
+        register_device(device);
     }
 
     let count = BLOCK_DEVICES.lock().len();
     crate::kinfo!("(Block) Dispositivos detectados:", count as u64);
+
+    #[cfg(any(
+        feature = "virtio_blk_test",
+        feature = "ata_test",
+        feature = "nvme_test",
+        feature = "ahci_test"
+    ))]
+    test::run_tests();
 }
 
-/// Registra um novo dispositivo de bloco
-pub fn register_device(device: Arc<dyn BlockDevice>) {
-    BLOCK_DEVICES.lock().push(device);
+/// Registra um novo dispositivo de bloco. Retorna o nome atribuído.
+pub fn register_device(device: Arc<dyn BlockDevice>) -> String {
+    let mut devices = BLOCK_DEVICES.lock();
+    let kind = device.device_type();
+    let same_kind = devices
+        .iter()
+        .filter(|r| r.device.device_type() == kind)
+        .count();
+    let name = format!("{}{}", kind, same_kind);
+    devices.push(Registered {
+        name: name.clone(),
+        device,
+    });
+    name
 }
 
 /// Obtém um dispositivo de bloco pelo índice
 pub fn get_device(index: usize) -> Option<Arc<dyn BlockDevice>> {
-    BLOCK_DEVICES.lock().get(index).cloned()
+    BLOCK_DEVICES.lock().get(index).map(|r| r.device.clone())
+}
+
+/// Obtém um dispositivo de bloco pelo nome atribuído
+pub fn find_device(name: &str) -> Option<Arc<dyn BlockDevice>> {
+    BLOCK_DEVICES
+        .lock()
+        .iter()
+        .find(|r| r.name == name)
+        .map(|r| r.device.clone())
+}
+
+/// Nomes e informações dos dispositivos registrados, em ordem de registro
+pub fn devices() -> Vec<(String, BlockDeviceInfo)> {
+    BLOCK_DEVICES
+        .lock()
+        .iter()
+        .map(|r| (r.name.clone(), r.device.info()))
+        .collect()
 }
 
 /// Obtém o primeiro dispositivo de bloco disponível
@@ -1,2 +1,631 @@
-//! NVMe Driver
-// TODO: Implementation logic
+//! # Driver NVMe
+//!
+//! Driver mínimo para controladoras NVMe (classe PCI 0x01, subclasse 0x08),
+//! como o dispositivo `nvme` do QEMU.
+//!
+//! ## Referências
+//!
+//! - [NVM Express Base Specification 1.4](https://nvmexpress.org/specifications/)
+//!
+//! ## Funcionamento
+//!
+//! Os registradores ficam no BAR0 (MMIO, acessado pelo HHDM). A
+//! inicialização segue a seção 7.6.1 da especificação:
+//! 1. desliga a controladora (`CC.EN = 0`) e espera `CSTS.RDY = 0`;
+//! 2. programa a fila de administração (`AQA`, `ASQ`, `ACQ`) e liga;
+//! 3. IDENTIFY da controladora e do namespace 1 (tamanho e setor);
+//! 4. cria um par de filas de I/O (CQ e depois SQ, id 1).
+//!
+//! O I/O é síncrono e por polling, sem interrupções: cada comando vai para
+//! a SQ, a doorbell é tocada e a CQ é lida até aparecer a conclusão.
+//!
+//! ## Phase bit
+//! A controladora escreve cada entrada da CQ com o bit de fase invertido a
+//! cada volta na fila. A CQ começa zerada e a primeira volta usa fase 1:
+//! uma entrada é nova quando a fase dela é a esperada, e a esperada troca
+//! quando o head dá a volta ([`CqCursor`]).
+//!
+//! Os dados passam por um `DmaBuffer` do dispositivo (o buffer do chamador
+//! está no heap e não tem endereço físico utilizável), com um setor por
+//! comando e um único PRP.
+
+#![allow(dead_code)]
+
+use super::traits::{BlockDevice, BlockError};
+use crate::drivers::pci::{self, PciDevice};
+use crate::mm::pfm::iommu::DmaBuffer;
+use crate::sync::Spinlock;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{fence, Ordering};
+
+/// Classe/subclasse PCI de controladoras NVMe
+const CLASS_STORAGE: u8 = 0x01;
+const SUBCLASS_NVME: u8 = 0x08;
+
+/// Iterações de polling antes de desistir de um comando
+const POLL_LIMIT: u32 = 10_000_000;
+
+/// Entradas da fila de administração
+const ADMIN_QUEUE_SIZE: u16 = 16;
+
+/// Entradas da fila de I/O (limitado por `CAP.MQES`)
+const IO_QUEUE_SIZE: u16 = 64;
+
+/// Id do par de filas de I/O
+const IO_QUEUE_ID: u16 = 1;
+
+/// Namespace usado como disco
+const NAMESPACE_ID: u32 = 1;
+
+/// Tamanho de página da controladora (`CC.MPS = 0`)
+const PAGE_SIZE: usize = 4096;
+
+/// Offsets dos registradores (BAR0)
+mod regs {
+    pub const CAP: usize = 0x00; // 8 bytes
+    pub const VS: usize = 0x08;
+    pub const CC: usize = 0x14;
+    pub const CSTS: usize = 0x1C;
+    pub const AQA: usize = 0x24;
+    pub const ASQ: usize = 0x28; // 8 bytes
+    pub const ACQ: usize = 0x30; // 8 bytes
+    pub const DOORBELLS: usize = 0x1000;
+}
+
+/// Bits do Controller Configuration
+mod cc {
+    pub const EN: u32 = 1 << 0;
+    /// Tamanho da entrada da SQ: 2^6 = 64 bytes
+    pub const IOSQES: u32 = 6 << 16;
+    /// Tamanho da entrada da CQ: 2^4 = 16 bytes
+    pub const IOCQES: u32 = 4 << 20;
+}
+
+/// Bits do Controller Status
+mod csts {
+    pub const RDY: u32 = 1 << 0;
+    /// Controller Fatal Status
+    pub const CFS: u32 = 1 << 1;
+}
+
+/// Opcodes de administração
+mod admin_op {
+    pub const CREATE_IO_SQ: u8 = 0x01;
+    pub const CREATE_IO_CQ: u8 = 0x05;
+    pub const IDENTIFY: u8 = 0x06;
+}
+
+/// Opcodes de I/O (NVM command set)
+mod io_op {
+    pub const FLUSH: u8 = 0x00;
+    pub const WRITE: u8 = 0x01;
+    pub const READ: u8 = 0x02;
+}
+
+/// Valores de CNS do IDENTIFY
+mod cns {
+    pub const NAMESPACE: u32 = 0x00;
+    pub const CONTROLLER: u32 = 0x01;
+}
+
+/// Entrada da Submission Queue (64 bytes)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Command {
+    /// Opcode (bits 7:0) e Command Identifier (bits 31:16)
+    cdw0: u32,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    mptr: u64,
+    prp1: u64,
+    prp2: u64,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+}
+
+impl Command {
+    fn new(opcode: u8, nsid: u32) -> Self {
+        Self {
+            cdw0: opcode as u32,
+            nsid,
+            ..Self::default()
+        }
+    }
+}
+
+/// Entrada da Completion Queue (16 bytes)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Completion {
+    /// Resultado específico do comando
+    dw0: u32,
+    dw1: u32,
+    sq_head: u16,
+    sq_id: u16,
+    cid: u16,
+    /// Phase tag (bit 0) e Status Field (bits 15:1)
+    status: u16,
+}
+
+/// Posição de leitura da CQ e a fase esperada da próxima entrada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CqCursor {
+    head: u16,
+    phase: bool,
+    size: u16,
+}
+
+impl CqCursor {
+    /// Início de uma CQ zerada: a primeira volta tem fase 1
+    const fn new(size: u16) -> Self {
+        Self {
+            head: 0,
+            phase: true,
+            size,
+        }
+    }
+
+    /// A entrada com este `status` foi escrita nesta volta
+    fn is_new(&self, status: u16) -> bool {
+        (status & 1 != 0) == self.phase
+    }
+
+    /// Consome a entrada do head; a fase inverte ao dar a volta
+    fn advance(&mut self) {
+        self.head += 1;
+        if self.head == self.size {
+            self.head = 0;
+            self.phase = !self.phase;
+        }
+    }
+}
+
+/// Converte o Status Field de uma conclusão (sem o phase tag)
+fn status_to_result(status: u16) -> Result<(), BlockError> {
+    let field = status >> 1;
+    let code = field & 0xFF;
+    let kind = (field >> 8) & 0x7;
+    match (kind, code) {
+        (0, 0) => Ok(()),
+        // Genérico: LBA fora do namespace
+        (0, 0x80) => Err(BlockError::InvalidBlock),
+        // Erros de mídia e integridade
+        (2, _) => Err(BlockError::IoError),
+        _ => Err(BlockError::HardwareError),
+    }
+}
+
+/// Registradores MMIO da controladora
+struct Regs {
+    base: *mut u8,
+    /// Distância entre doorbells, em bytes (`4 << CAP.DSTRD`)
+    stride: usize,
+}
+
+// SAFETY: MMIO da controladora; o acesso é serializado pelos locks das filas
+unsafe impl Send for Regs {}
+unsafe impl Sync for Regs {}
+
+impl Regs {
+    fn read32(&self, offset: usize) -> u32 {
+        unsafe { core::ptr::read_volatile(self.base.add(offset) as *const u32) }
+    }
+
+    fn write32(&self, offset: usize, value: u32) {
+        unsafe { core::ptr::write_volatile(self.base.add(offset) as *mut u32, value) }
+    }
+
+    fn read64(&self, offset: usize) -> u64 {
+        self.read32(offset) as u64 | ((self.read32(offset + 4) as u64) << 32)
+    }
+
+    fn write64(&self, offset: usize, value: u64) {
+        self.write32(offset, value as u32);
+        self.write32(offset + 4, (value >> 32) as u32);
+    }
+
+    /// Doorbell do tail da SQ `qid`
+    fn sq_doorbell(&self, qid: u16, tail: u16) {
+        self.write32(
+            regs::DOORBELLS + 2 * qid as usize * self.stride,
+            tail as u32,
+        );
+    }
+
+    /// Doorbell do head da CQ `qid`
+    fn cq_doorbell(&self, qid: u16, head: u16) {
+        self.write32(
+            regs::DOORBELLS + (2 * qid as usize + 1) * self.stride,
+            head as u32,
+        );
+    }
+
+    /// Espera `CSTS.RDY` chegar a `ready`; falha em CFS ou timeout
+    fn wait_ready(&self, ready: bool) -> bool {
+        for _ in 0..POLL_LIMIT {
+            let status = self.read32(regs::CSTS);
+            if status & csts::CFS != 0 {
+                return false;
+            }
+            if (status & csts::RDY != 0) == ready {
+                return true;
+            }
+            core::hint::spin_loop();
+        }
+        false
+    }
+}
+
+/// Par SQ/CQ com os cursores do driver
+struct QueuePair {
+    id: u16,
+    size: u16,
+    sq: DmaBuffer,
+    cq: DmaBuffer,
+    sq_tail: u16,
+    cursor: CqCursor,
+    next_cid: u16,
+}
+
+impl QueuePair {
+    fn new(id: u16, size: u16) -> Option<Self> {
+        let sq = DmaBuffer::new(size as usize * core::mem::size_of::<Command>()).ok()?;
+        let cq = DmaBuffer::new(size as usize * core::mem::size_of::<Completion>()).ok()?;
+        Some(Self {
+            id,
+            size,
+            sq,
+            cq,
+            sq_tail: 0,
+            cursor: CqCursor::new(size),
+            next_cid: 0,
+        })
+    }
+
+    /// Envia `cmd` e espera a conclusão; retorna o DW0 do resultado
+    fn submit(&mut self, regs: &Regs, mut cmd: Command) -> Result<u32, BlockError> {
+        let cid = self.next_cid;
+        self.next_cid = self.next_cid.wrapping_add(1);
+        cmd.cdw0 = (cmd.cdw0 & 0xFFFF) | ((cid as u32) << 16);
+
+        // SAFETY: slot `sq_tail` dentro da SQ; a controladora só o lê depois
+        // da doorbell
+        unsafe {
+            let slot = (self.sq.as_ptr() as *mut Command).add(self.sq_tail as usize);
+            core::ptr::write_volatile(slot, cmd);
+        }
+        self.sq_tail = (self.sq_tail + 1) % self.size;
+        fence(Ordering::SeqCst);
+        regs.sq_doorbell(self.id, self.sq_tail);
+
+        let entries = self.cq.as_ptr() as *const Completion;
+        let mut polls = POLL_LIMIT;
+        let done = loop {
+            // SAFETY: slot `head` dentro da CQ, escrito pela controladora
+            let entry = unsafe { core::ptr::read_volatile(entries.add(self.cursor.head as usize)) };
+            if self.cursor.is_new(entry.status) {
+                break entry;
+            }
+            if polls == 0 {
+                crate::kerror!("(NVMe) Timeout no comando, opcode=", cmd.cdw0 & 0xFF);
+                return Err(BlockError::IoError);
+            }
+            polls -= 1;
+            core::hint::spin_loop();
+        };
+        fence(Ordering::SeqCst);
+
+        self.cursor.advance();
+        regs.cq_doorbell(self.id, self.cursor.head);
+
+        if done.cid != cid {
+            crate::kerror!(
+                "(NVMe) Conclusão de comando inesperada, cid=",
+                done.cid as u64
+            );
+            return Err(BlockError::HardwareError);
+        }
+        if let Err(e) = status_to_result(done.status) {
+            crate::kerror!("(NVMe) Comando falhou, status=", (done.status >> 1) as u64);
+            return Err(e);
+        }
+        Ok(done.dw0)
+    }
+}
+
+/// Fila de I/O e o buffer de dados, sempre usados juntos
+struct IoState {
+    queue: QueuePair,
+    data: DmaBuffer,
+}
+
+/// Namespace 1 de uma controladora NVMe
+pub struct NvmeDrive {
+    /// Dispositivo PCI associado
+    pci_device: PciDevice,
+    regs: Regs,
+    /// Fila de administração; o lock serializa os comandos
+    admin: Spinlock<QueuePair>,
+    /// Estado de I/O; o lock serializa as requisições
+    io: Spinlock<IoState>,
+    /// Tamanho do setor do namespace
+    block_size: usize,
+    /// Total de setores do namespace
+    total_blocks: u64,
+}
+
+impl NvmeDrive {
+    /// Inicializa a controladora e o namespace 1
+    pub fn new(pci_device: PciDevice) -> Option<Self> {
+        crate::kinfo!("(NVMe) Inicializando controladora...");
+
+        let bar = match pci_device.bar(0) {
+            Some(bar) if !bar.is_io => bar,
+            _ => {
+                crate::kerror!("(NVMe) BAR0 não é MMIO");
+                return None;
+            }
+        };
+        pci_device.enable_memory_space();
+        pci_device.enable_bus_master();
+
+        // SAFETY: BAR0 da controladora, pelo HHDM
+        let base = unsafe { crate::mm::addr::phys_to_virt::<u8>(bar.address) };
+        let mut regs = Regs { base, stride: 4 };
+        let cap = regs.read64(regs::CAP);
+        regs.stride = 4 << ((cap >> 32) & 0xF);
+        crate::kinfo!("(NVMe) Versão:", regs.read32(regs::VS));
+
+        let device = Self::init_controller(pci_device, regs, cap);
+        match device {
+            Some(ref device) => {
+                crate::kinfo!("(NVMe) Inicializado com sucesso!");
+                crate::kinfo!("(NVMe) Setores:", device.total_blocks);
+                crate::kinfo!("(NVMe) Tamanho do setor:", device.block_size);
+            }
+            None => crate::kerror!("(NVMe) Falha na inicialização!"),
+        }
+        device
+    }
+
+    fn init_controller(pci_device: PciDevice, regs: Regs, cap: u64) -> Option<Self> {
+        // Entradas por fila suportadas (MQES é 0-based)
+        let max_entries = ((cap & 0xFFFF) + 1).min(u16::MAX as u64) as u16;
+        // Só o NVM command set (CAP.CSS bit 0 = bit 37)
+        if cap & (1 << 37) == 0 {
+            crate::kerror!("(NVMe) NVM command set não suportado");
+            return None;
+        }
+
+        // 1. Desligar a controladora
+        let config = regs.read32(regs::CC);
+        if config & cc::EN != 0 {
+            regs.write32(regs::CC, config & !cc::EN);
+        }
+        if !regs.wait_ready(false) {
+            crate::kerror!("(NVMe) Controladora não desligou");
+            return None;
+        }
+
+        // 2. Fila de administração
+        let mut admin = QueuePair::new(0, ADMIN_QUEUE_SIZE.min(max_entries))?;
+        let size = admin.size as u32 - 1;
+        regs.write32(regs::AQA, (size << 16) | size);
+        regs.write64(regs::ASQ, admin.sq.phys().as_u64());
+        regs.write64(regs::ACQ, admin.cq.phys().as_u64());
+
+        regs.write32(regs::CC, cc::EN | cc::IOSQES | cc::IOCQES);
+        if !regs.wait_ready(true) {
+            crate::kerror!("(NVMe) Controladora não ficou pronta");
+            return None;
+        }
+
+        let data = DmaBuffer::new(PAGE_SIZE).ok()?;
+
+        // 3. IDENTIFY: controladora e namespace
+        let mut identify = Command::new(admin_op::IDENTIFY, 0);
+        identify.prp1 = data.phys().as_u64();
+        identify.cdw10 = cns::CONTROLLER;
+        admin.submit(&regs, identify).ok()?;
+        // SAFETY: página de dados preenchida pelo IDENTIFY
+        let namespaces = unsafe { core::ptr::read_volatile(data.as_ptr().add(516) as *const u32) };
+        if namespaces < NAMESPACE_ID {
+            crate::kerror!("(NVMe) Controladora sem namespaces");
+            return None;
+        }
+
+        identify.nsid = NAMESPACE_ID;
+        identify.cdw10 = cns::NAMESPACE;
+        admin.submit(&regs, identify).ok()?;
+        // SAFETY: idem
+        let (total_blocks, block_size) = unsafe {
+            let page = data.as_ptr();
+            let nsze = core::ptr::read_volatile(page as *const u64);
+            let flbas = core::ptr::read_volatile(page.add(26)) & 0xF;
+            let lbaf = core::ptr::read_volatile(page.add(128 + 4 * flbas as usize) as *const u32);
+            (nsze, 1usize << ((lbaf >> 16) & 0xFF))
+        };
+        if total_blocks == 0 || !(512..=PAGE_SIZE).contains(&block_size) {
+            crate::kerror!("(NVMe) Formato do namespace não suportado");
+            return None;
+        }
+
+        // 4. Par de filas de I/O: a CQ antes da SQ que a usa
+        let queue = QueuePair::new(IO_QUEUE_ID, IO_QUEUE_SIZE.min(max_entries))?;
+        let queue_info = ((queue.size as u32 - 1) << 16) | IO_QUEUE_ID as u32;
+
+        let mut create_cq = Command::new(admin_op::CREATE_IO_CQ, 0);
+        create_cq.prp1 = queue.cq.phys().as_u64();
+        create_cq.cdw10 = queue_info;
+        // Fisicamente contígua, sem interrupção
+        create_cq.cdw11 = 1;
+        admin.submit(&regs, create_cq).ok()?;
+
+        let mut create_sq = Command::new(admin_op::CREATE_IO_SQ, 0);
+        create_sq.prp1 = queue.sq.phys().as_u64();
+        create_sq.cdw10 = queue_info;
+        // CQ associada e fisicamente contígua
+        create_sq.cdw11 = ((IO_QUEUE_ID as u32) << 16) | 1;
+        admin.submit(&regs, create_sq).ok()?;
+
+        Some(Self {
+            pci_device,
+            regs,
+            admin: Spinlock::new(admin),
+            io: Spinlock::new(IoState { queue, data }),
+            block_size,
+            total_blocks,
+        })
+    }
+
+    /// Lê ou escreve um setor pelo buffer de DMA
+    fn do_io(&self, lba: u64, buf: &mut [u8], is_write: bool) -> Result<(), BlockError> {
+        let mut io = self.io.lock();
+        let IoState { queue, data } = &mut *io;
+
+        if is_write {
+            // SAFETY: buffer de DMA exclusivo deste dispositivo, com o lock
+            unsafe {
+                core::ptr::copy_nonoverlapping(buf.as_ptr(), data.as_ptr(), self.block_size);
+            }
+        }
+
+        let opcode = if is_write { io_op::WRITE } else { io_op::READ };
+        let mut cmd = Command::new(opcode, NAMESPACE_ID);
+        cmd.prp1 = data.phys().as_u64();
+        cmd.cdw10 = lba as u32;
+        cmd.cdw11 = (lba >> 32) as u32;
+        // Número de blocos, 0-based
+        cmd.cdw12 = 0;
+        queue.submit(&self.regs, cmd)?;
+
+        if !is_write {
+            // SAFETY: a controladora terminou de escrever (conclusão lida)
+            unsafe {
+                core::ptr::copy_nonoverlapping(data.as_ptr(), buf.as_mut_ptr(), self.block_size);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for NvmeDrive {
+    fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+        if lba >= self.total_blocks {
+            return Err(BlockError::InvalidBlock);
+        }
+        if buf.len() < self.block_size {
+            return Err(BlockError::InvalidBuffer);
+        }
+        self.do_io(lba, buf, false)
+    }
+
+    fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), BlockError> {
+        if lba >= self.total_blocks {
+            return Err(BlockError::InvalidBlock);
+        }
+        if buf.len() < self.block_size {
+            return Err(BlockError::InvalidBuffer);
+        }
+
+        // `do_io` só lê de `buf` em escritas; a cópia local evita o cast
+        let mut sector = alloc::vec![0u8; self.block_size];
+        sector.copy_from_slice(&buf[..self.block_size]);
+        self.do_io(lba, &mut sector, true)
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.total_blocks
+    }
+
+    fn device_type(&self) -> &'static str {
+        "nvme"
+    }
+
+    fn flush(&self) -> Result<(), BlockError> {
+        let mut io = self.io.lock();
+        io.queue
+            .submit(&self.regs, Command::new(io_op::FLUSH, NAMESPACE_ID))
+            .map(|_| ())
+    }
+}
+
+/// Inicializa as controladoras NVMe encontradas no PCI (namespace 1 de
+/// cada uma)
+pub fn init() -> Vec<Arc<dyn BlockDevice>> {
+    crate::kinfo!("(NVMe) Procurando controladoras...");
+
+    pci::find_by_class(CLASS_STORAGE, SUBCLASS_NVME)
+        .into_iter()
+        .filter_map(|dev| {
+            crate::kinfo!("(NVMe) Controladora encontrada!");
+            crate::kinfo!("  Bus:", dev.bus as u64);
+            crate::kinfo!("  Device:", dev.device as u64);
+            crate::kinfo!("  Function:", dev.function as u64);
+            NvmeDrive::new(dev).map(|d| Arc::new(d) as Arc<dyn BlockDevice>)
+        })
+        .collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cq_phase_flips_on_wrap() {
+        let mut cq = CqCursor::new(3);
+        // CQ zerada: nada novo até a controladora escrever com fase 1
+        assert!(!cq.is_new(0));
+        assert!(cq.is_new(1));
+
+        for _ in 0..3 {
+            cq.advance();
+        }
+        assert_eq!(cq.head, 0);
+        // Segunda volta: as entradas antigas (fase 1) não contam mais
+        assert!(!cq.is_new(1));
+        assert!(cq.is_new(0));
+
+        for _ in 0..3 {
+            cq.advance();
+        }
+        assert_eq!(cq, CqCursor::new(3));
+    }
+
+    #[test]
+    fn test_status_field() {
+        assert_eq!(status_to_result(0x0001), Ok(()));
+        // SCT 0, SC 0x80 (LBA fora do namespace), com phase tag
+        assert_eq!(
+            status_to_result((0x80 << 1) | 1),
+            Err(BlockError::InvalidBlock)
+        );
+        // SCT 2 (mídia), SC 0x81 (erro de leitura)
+        assert_eq!(
+            status_to_result(((2 << 8) | 0x81) << 1),
+            Err(BlockError::IoError)
+        );
+        // SCT 0, SC 0x01 (opcode inválido)
+        assert_eq!(status_to_result(1 << 1), Err(BlockError::HardwareError));
+    }
+
+    #[test]
+    fn test_entry_layout() {
+        assert_eq!(core::mem::size_of::<Command>(), 64);
+        assert_eq!(core::mem::size_of::<Completion>(), 16);
+    }
+}
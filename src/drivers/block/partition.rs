@@ -0,0 +1,455 @@
+//! # Tabelas de Partição
+//!
+//! Leitura de MBR e GPT de um [`BlockDevice`].
+//!
+//! ## Detecção
+//!
+//! 1. LBA 0 precisa terminar em `55 AA` (MBR ou MBR protetor).
+//! 2. Se alguma das quatro entradas primárias for do tipo `0xEE` (MBR
+//!    protetor), a tabela de verdade é a GPT: header em LBA 1 com assinatura
+//!    `EFI PART`, seguido do array de entradas.
+//! 3. Senão, valem as quatro entradas primárias do MBR (partições estendidas
+//!    não são seguidas).
+//!
+//! Os CRCs da GPT não são verificados; header e entradas só passam por
+//! checagem de tamanho e limites.
+//!
+//! ## PartitionDevice
+//!
+//! Uma partição vira um [`BlockDevice`] próprio com [`PartitionDevice`]: o
+//! LBA 0 dela é o `start_lba` no disco. Filesystems montam o
+//! `PartitionDevice` e não sabem onde a partição começa.
+
+use super::traits::{BlockDevice, BlockError};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Setor com a MBR
+const MBR_LBA: u64 = 0;
+/// Início da tabela de partições da MBR
+const MBR_TABLE_OFFSET: usize = 0x1BE;
+const MBR_ENTRY_SIZE: usize = 16;
+const MBR_ENTRIES: usize = 4;
+/// Tipo do MBR protetor de um disco GPT
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xEE;
+
+/// Setor com o header da GPT
+const GPT_HEADER_LBA: u64 = 1;
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// Menor entrada válida da especificação
+const GPT_MIN_ENTRY_SIZE: usize = 128;
+/// Limite de entradas lidas (o padrão de quase todo particionador é 128)
+const GPT_MAX_ENTRIES: usize = 1024;
+/// Nome da partição: 36 unidades UTF-16LE a partir de 56
+const GPT_NAME_OFFSET: usize = 56;
+const GPT_NAME_UNITS: usize = 36;
+
+/// Tipos de MBR que contêm FAT
+const MBR_FAT_TYPES: &[u8] = &[0x01, 0x04, 0x06, 0x0B, 0x0C, 0x0E, 0xEF];
+
+/// GUIDs de tipo GPT que contêm FAT, na ordem de bytes do disco
+/// (os três primeiros campos em little-endian)
+const GPT_FAT_TYPES: &[[u8; 16]] = &[
+    // EFI System Partition, C12A7328-F81F-11D2-BA4B-00A0C93EC93B
+    [
+        0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9,
+        0x3B,
+    ],
+    // Microsoft Basic Data, EBD0A0A2-B9E5-4433-87C0-68B6B72699C7
+    [
+        0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99,
+        0xC7,
+    ],
+];
+
+/// Erros de leitura da tabela de partições
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionError {
+    /// Falha de I/O no dispositivo
+    Io(BlockError),
+    /// LBA 0 sem assinatura `55 AA`
+    NoTable,
+    /// MBR protetor sem GPT válida atrás
+    InvalidGpt,
+}
+
+impl From<BlockError> for PartitionError {
+    fn from(e: BlockError) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Partição encontrada na tabela
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Primeiro bloco
+    pub start_lba: u64,
+    /// Tamanho em blocos
+    pub sector_count: u64,
+    /// GUID de tipo (GPT), na ordem de bytes do disco; zero em MBR
+    pub type_guid: [u8; 16],
+    /// Byte de tipo (MBR); 0 em GPT
+    pub mbr_type: u8,
+    /// Nome (GPT); vazio em MBR
+    pub name: String,
+}
+
+impl Partition {
+    /// O tipo indica um FAT (ou uma ESP, que é FAT)?
+    pub fn is_fat(&self) -> bool {
+        if self.type_guid == [0; 16] {
+            MBR_FAT_TYPES.contains(&self.mbr_type)
+        } else {
+            GPT_FAT_TYPES.contains(&self.type_guid)
+        }
+    }
+}
+
+/// Lê a tabela de partições de `device`
+///
+/// Retorna as partições não vazias na ordem da tabela.
+pub fn read_partitions(device: &dyn BlockDevice) -> Result<Vec<Partition>, PartitionError> {
+    let block_size = device.block_size();
+    let mut sector = vec![0u8; block_size];
+    device.read_block(MBR_LBA, &mut sector)?;
+    if block_size < 512 || sector[510] != 0x55 || sector[511] != 0xAA {
+        return Err(PartitionError::NoTable);
+    }
+
+    let mbr = parse_mbr(&sector);
+    if mbr.iter().any(|p| p.mbr_type == MBR_TYPE_GPT_PROTECTIVE) {
+        return read_gpt(device);
+    }
+    Ok(mbr)
+}
+
+/// As quatro entradas primárias (as vazias são puladas)
+fn parse_mbr(sector: &[u8]) -> Vec<Partition> {
+    (0..MBR_ENTRIES)
+        .map(|i| {
+            let start = MBR_TABLE_OFFSET + i * MBR_ENTRY_SIZE;
+            &sector[start..start + MBR_ENTRY_SIZE]
+        })
+        .filter(|entry| entry[4] != 0)
+        .map(|entry| Partition {
+            start_lba: read_u32(entry, 8) as u64,
+            sector_count: read_u32(entry, 12) as u64,
+            type_guid: [0; 16],
+            mbr_type: entry[4],
+            name: String::new(),
+        })
+        .filter(|p| p.sector_count != 0)
+        .collect()
+}
+
+/// Header e array de entradas da GPT
+fn read_gpt(device: &dyn BlockDevice) -> Result<Vec<Partition>, PartitionError> {
+    let block_size = device.block_size();
+    let mut header = vec![0u8; block_size];
+    device.read_block(GPT_HEADER_LBA, &mut header)?;
+    if &header[..8] != GPT_SIGNATURE {
+        return Err(PartitionError::InvalidGpt);
+    }
+
+    let entries_lba = read_u64(&header, 72);
+    let count = read_u32(&header, 80) as usize;
+    let entry_size = read_u32(&header, 84) as usize;
+    if entry_size < GPT_MIN_ENTRY_SIZE
+        || entry_size > block_size
+        || block_size % entry_size != 0
+        || count > GPT_MAX_ENTRIES
+    {
+        return Err(PartitionError::InvalidGpt);
+    }
+
+    let per_block = block_size / entry_size;
+    let mut partitions = Vec::new();
+    let mut block = vec![0u8; block_size];
+    for index in 0..count {
+        if index % per_block == 0 {
+            let lba = entries_lba
+                .checked_add((index / per_block) as u64)
+                .ok_or(PartitionError::InvalidGpt)?;
+            device.read_block(lba, &mut block)?;
+        }
+        let start = (index % per_block) * entry_size;
+        if let Some(partition) = parse_gpt_entry(&block[start..start + entry_size]) {
+            partitions.push(partition);
+        }
+    }
+    Ok(partitions)
+}
+
+/// Entrada da GPT; `None` para entradas sem uso (tipo zero) ou invertidas
+fn parse_gpt_entry(entry: &[u8]) -> Option<Partition> {
+    let mut type_guid = [0u8; 16];
+    type_guid.copy_from_slice(&entry[..16]);
+    if type_guid == [0; 16] {
+        return None;
+    }
+
+    let first = read_u64(entry, 32);
+    let last = read_u64(entry, 40);
+    if last < first {
+        return None;
+    }
+
+    let units = (0..GPT_NAME_UNITS)
+        .map(|i| {
+            u16::from_le_bytes([
+                entry[GPT_NAME_OFFSET + 2 * i],
+                entry[GPT_NAME_OFFSET + 2 * i + 1],
+            ])
+        })
+        .take_while(|&unit| unit != 0);
+    let name = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+
+    Some(Partition {
+        start_lba: first,
+        sector_count: last - first + 1,
+        type_guid,
+        mbr_type: 0,
+        name,
+    })
+}
+
+/// Faixa `[start_lba, start_lba + sector_count)` de outro dispositivo
+pub struct PartitionDevice {
+    device: Arc<dyn BlockDevice>,
+    start_lba: u64,
+    sector_count: u64,
+}
+
+impl PartitionDevice {
+    /// Partição de `device`; falha se ela passar do fim do disco
+    pub fn new(
+        device: Arc<dyn BlockDevice>,
+        start_lba: u64,
+        sector_count: u64,
+    ) -> Result<Self, BlockError> {
+        let end = start_lba
+            .checked_add(sector_count)
+            .ok_or(BlockError::OutOfBounds)?;
+        if end > device.total_blocks() {
+            return Err(BlockError::OutOfBounds);
+        }
+        Ok(Self {
+            device,
+            start_lba,
+            sector_count,
+        })
+    }
+
+    /// O disco inteiro (sem tabela de partições)
+    pub fn whole(device: Arc<dyn BlockDevice>) -> Self {
+        let sector_count = device.total_blocks();
+        Self {
+            device,
+            start_lba: 0,
+            sector_count,
+        }
+    }
+
+    /// Dispositivo para uma entrada da tabela
+    pub fn from_partition(
+        device: Arc<dyn BlockDevice>,
+        partition: &Partition,
+    ) -> Result<Self, BlockError> {
+        Self::new(device, partition.start_lba, partition.sector_count)
+    }
+
+    pub fn start_lba(&self) -> u64 {
+        self.start_lba
+    }
+
+    /// LBA no disco de `lba` (relativo à partição)
+    fn translate(&self, lba: u64) -> Result<u64, BlockError> {
+        if lba >= self.sector_count {
+            return Err(BlockError::OutOfBounds);
+        }
+        Ok(self.start_lba + lba)
+    }
+}
+
+impl BlockDevice for PartitionDevice {
+    fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+        self.device.read_block(self.translate(lba)?, buf)
+    }
+
+    fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), BlockError> {
+        self.device.write_block(self.translate(lba)?, buf)
+    }
+
+    fn read_blocks(&self, start: u64, count: usize, buf: &mut [u8]) -> Result<(), BlockError> {
+        // A faixa inteira precisa caber: o pedido segue em um só para o disco
+        if count > 0 {
+            self.translate(start.saturating_add(count as u64 - 1))?;
+        }
+        self.device.read_blocks(self.translate(start)?, count, buf)
+    }
+
+    fn block_size(&self) -> usize {
+        self.device.block_size()
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.sector_count
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.device.is_read_only()
+    }
+
+    fn device_type(&self) -> &'static str {
+        self.device.device_type()
+    }
+
+    fn flush(&self) -> Result<(), BlockError> {
+        self.device.flush()
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::block::RamDisk;
+
+    const BASIC_DATA: [u8; 16] = GPT_FAT_TYPES[1];
+    const LINUX_FS: [u8; 16] = [
+        0xAF, 0x3D, 0xC6, 0x0F, 0x83, 0x84, 0x72, 0x47, 0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D,
+        0xE4,
+    ];
+
+    fn mbr_entry(sector: &mut [u8], index: usize, kind: u8, start: u32, count: u32) {
+        let at = MBR_TABLE_OFFSET + index * MBR_ENTRY_SIZE;
+        sector[at + 4] = kind;
+        sector[at + 8..at + 12].copy_from_slice(&start.to_le_bytes());
+        sector[at + 12..at + 16].copy_from_slice(&count.to_le_bytes());
+    }
+
+    fn signed_sector() -> [u8; 512] {
+        let mut sector = [0u8; 512];
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
+        sector
+    }
+
+    fn gpt_entry(block: &mut [u8], slot: usize, guid: [u8; 16], first: u64, last: u64, name: &str) {
+        let entry = &mut block[slot * 128..(slot + 1) * 128];
+        entry[..16].copy_from_slice(&guid);
+        entry[32..40].copy_from_slice(&first.to_le_bytes());
+        entry[40..48].copy_from_slice(&last.to_le_bytes());
+        for (i, unit) in name.encode_utf16().enumerate() {
+            let at = GPT_NAME_OFFSET + 2 * i;
+            entry[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_gpt_behind_protective_mbr() {
+        let disk = RamDisk::new(64, 512);
+        let mut mbr = signed_sector();
+        mbr_entry(&mut mbr, 0, MBR_TYPE_GPT_PROTECTIVE, 1, 63);
+        disk.write_block(0, &mbr).unwrap();
+
+        let mut header = [0u8; 512];
+        header[..8].copy_from_slice(GPT_SIGNATURE);
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&5u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        disk.write_block(1, &header).unwrap();
+
+        // 4 entradas por bloco: a quinta fica no LBA 3
+        let mut entries = [0u8; 512];
+        gpt_entry(&mut entries, 0, LINUX_FS, 34, 39, "root");
+        gpt_entry(&mut entries, 2, BASIC_DATA, 40, 59, "DADOS");
+        disk.write_block(2, &entries).unwrap();
+        let mut entries = [0u8; 512];
+        gpt_entry(&mut entries, 0, BASIC_DATA, 60, 63, "extra");
+        disk.write_block(3, &entries).unwrap();
+
+        let partitions = read_partitions(&disk).unwrap();
+        assert_eq!(partitions.len(), 3);
+        assert_eq!(partitions[0].name, "root");
+        assert_eq!(partitions[0].type_guid, LINUX_FS);
+        assert!(!partitions[0].is_fat());
+
+        let fat = partitions.iter().find(|p| p.is_fat()).unwrap();
+        assert_eq!((fat.start_lba, fat.sector_count), (40, 20));
+        assert_eq!(fat.name, "DADOS");
+        assert_eq!(partitions[2].start_lba, 60);
+
+        // MBR protetor sem header GPT
+        disk.write_block(1, &[0u8; 512]).unwrap();
+        assert_eq!(read_partitions(&disk), Err(PartitionError::InvalidGpt));
+    }
+
+    #[test]
+    fn test_mbr_reads_all_primary_entries() {
+        let disk = RamDisk::new(8, 512);
+        assert_eq!(read_partitions(&disk), Err(PartitionError::NoTable));
+
+        let mut mbr = signed_sector();
+        mbr_entry(&mut mbr, 0, 0x83, 2048, 1000);
+        mbr_entry(&mut mbr, 2, 0x0C, 4096, 2000);
+        mbr_entry(&mut mbr, 3, 0x07, 8192, 0);
+        disk.write_block(0, &mbr).unwrap();
+
+        let partitions = read_partitions(&disk).unwrap();
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].mbr_type, 0x83);
+        assert!(!partitions[0].is_fat());
+        assert_eq!(
+            (partitions[1].start_lba, partitions[1].sector_count),
+            (4096, 2000)
+        );
+        assert!(partitions[1].is_fat());
+    }
+
+    #[test]
+    fn test_partition_device_translates_offsets() {
+        let disk = Arc::new(RamDisk::new(16, 512));
+        let part = PartitionDevice::new(disk.clone(), 10, 4).unwrap();
+        assert_eq!(part.total_blocks(), 4);
+
+        part.write_block(0, &[0xAA; 512]).unwrap();
+        part.write_block(3, &[0xBB; 512]).unwrap();
+
+        let mut buf = [0u8; 512];
+        disk.read_block(10, &mut buf).unwrap();
+        assert_eq!(buf, [0xAA; 512]);
+        disk.read_block(13, &mut buf).unwrap();
+        assert_eq!(buf, [0xBB; 512]);
+        part.read_block(3, &mut buf).unwrap();
+        assert_eq!(buf, [0xBB; 512]);
+
+        // Fim da partição, mesmo com o disco tendo mais blocos
+        assert_eq!(part.read_block(4, &mut buf), Err(BlockError::OutOfBounds));
+        assert_eq!(part.write_block(4, &buf), Err(BlockError::OutOfBounds));
+        let mut two = [0u8; 1024];
+        part.read_blocks(2, 2, &mut two).unwrap();
+        assert_eq!(two[512..], [0xBB; 512]);
+        assert_eq!(
+            part.read_blocks(3, 2, &mut two),
+            Err(BlockError::OutOfBounds)
+        );
+        assert!(PartitionDevice::new(disk.clone(), 10, 7).is_err());
+        assert_eq!(PartitionDevice::whole(disk).total_blocks(), 16);
+    }
+}
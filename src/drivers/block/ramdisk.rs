@@ -1,2 +1,94 @@
 //! Ramdisk Driver
-// TODO: Implementation logic
+//!
+//! Dispositivo de bloco em memória (heap). Útil para testes e como disco
+//! volátil; o conteúdo começa zerado e some com o dispositivo.
+
+use super::traits::{BlockDevice, BlockError};
+use crate::sync::Spinlock;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Disco em memória
+pub struct RamDisk {
+    data: Spinlock<Vec<u8>>,
+    block_size: usize,
+    total_blocks: u64,
+}
+
+impl RamDisk {
+    /// Cria um ramdisk zerado com `total_blocks` blocos de `block_size` bytes.
+    pub fn new(total_blocks: u64, block_size: usize) -> Self {
+        Self {
+            data: Spinlock::new(vec![0u8; total_blocks as usize * block_size]),
+            block_size,
+            total_blocks,
+        }
+    }
+
+    /// Faixa de bytes do bloco `lba`
+    fn range(&self, lba: u64, buf_len: usize) -> Result<core::ops::Range<usize>, BlockError> {
+        if lba >= self.total_blocks {
+            return Err(BlockError::InvalidBlock);
+        }
+        if buf_len < self.block_size {
+            return Err(BlockError::InvalidBuffer);
+        }
+        let start = lba as usize * self.block_size;
+        Ok(start..start + self.block_size)
+    }
+}
+
+impl BlockDevice for RamDisk {
+    fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+        let range = self.range(lba, buf.len())?;
+        buf[..self.block_size].copy_from_slice(&self.data.lock()[range]);
+        Ok(())
+    }
+
+    fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), BlockError> {
+        let range = self.range(lba, buf.len())?;
+        self.data.lock()[range].copy_from_slice(&buf[..self.block_size]);
+        Ok(())
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.total_blocks
+    }
+
+    fn device_type(&self) -> &'static str {
+        "ram"
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_roundtrip() {
+        let disk = RamDisk::new(4, 512);
+        let block = [0x5Au8; 512];
+        disk.write_block(3, &block).unwrap();
+
+        let mut buf = [0u8; 512];
+        disk.read_block(3, &mut buf).unwrap();
+        assert_eq!(buf, block);
+        disk.read_block(2, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 0));
+
+        assert_eq!(disk.read_block(4, &mut buf), Err(BlockError::InvalidBlock));
+        assert_eq!(
+            disk.write_block(0, &block[..100]),
+            Err(BlockError::InvalidBuffer)
+        );
+        assert_eq!(disk.info().size_bytes(), 2048);
+    }
+}
@@ -0,0 +1,147 @@
+//! # Testes de integração dos discos
+//!
+//! Executados no boot sob QEMU, cada um com a sua feature e o seu disco:
+//! - `virtio_blk_test`: disco virtio (`-drive file=disk.img,if=virtio,format=raw`);
+//! - `ata_test`: disco IDE no canal primário (`-drive file=disk.img,if=ide,format=raw`);
+//! - `nvme_test`: controladora NVMe
+//!   (`-drive file=nvme.img,if=none,id=nvm -device nvme,serial=forge,drive=nvm`);
+//! - `ahci_test`: disco SATA na controladora AHCI
+//!   (`-device ahci,id=ahci -drive id=sata,file=disk.img,if=none,format=raw
+//!   -device ide-hd,drive=sata,bus=ahci.0`), só leitura.
+//!
+//! O último setor do disco é usado como rascunho e restaurado no final.
+
+use super::{find_device, BlockError};
+use alloc::vec;
+
+pub fn run_tests() {
+    crate::kinfo!("(Block) Iniciando testes de disco...");
+    #[cfg(feature = "virtio_blk_test")]
+    test_virtio_roundtrip();
+    #[cfg(feature = "ata_test")]
+    test_ata_roundtrip();
+    #[cfg(feature = "nvme_test")]
+    test_nvme_roundtrip();
+    #[cfg(feature = "ahci_test")]
+    test_ahci_read();
+    crate::kinfo!("(Block) Testes de disco concluídos com SUCESSO.");
+}
+
+/// Escrita, leitura de volta e limites em um disco virtio real.
+#[cfg(feature = "virtio_blk_test")]
+fn test_virtio_roundtrip() {
+    let disk = find_device("virtio0").expect("(Block) Nenhum disco virtio registrado");
+    let size = disk.block_size();
+    let last = disk.total_blocks() - 1;
+    assert_eq!(size, 512);
+
+    let mut original = vec![0u8; size];
+    disk.read_block(last, &mut original)
+        .expect("(Block) Falha ao ler último setor");
+
+    if !disk.is_read_only() {
+        let pattern: alloc::vec::Vec<u8> = (0..size).map(|i| (i * 7 + 3) as u8).collect();
+        disk.write_block(last, &pattern)
+            .expect("(Block) Falha ao escrever setor");
+
+        let mut readback = vec![0u8; size];
+        disk.read_block(last, &mut readback).unwrap();
+        assert_eq!(readback, pattern, "(Block) Setor lido difere do escrito");
+
+        disk.write_block(last, &original).unwrap();
+    }
+
+    // Várias leituras seguidas reaproveitam os descritores
+    let mut buf = vec![0u8; size];
+    for _ in 0..64 {
+        disk.read_block(0, &mut buf).unwrap();
+    }
+
+    assert_eq!(
+        disk.read_block(last + 1, &mut buf),
+        Err(BlockError::InvalidBlock)
+    );
+    assert_eq!(
+        disk.read_block(0, &mut buf[..100]),
+        Err(BlockError::InvalidBuffer)
+    );
+}
+
+/// Escrita PIO de um setor no ATA: lido de volta igual, e erros de limite.
+#[cfg(feature = "ata_test")]
+fn test_ata_roundtrip() {
+    sector_roundtrip("ata0");
+}
+
+/// Um setor escrito por NVMe WRITE volta igual pelo READ.
+#[cfg(feature = "nvme_test")]
+fn test_nvme_roundtrip() {
+    sector_roundtrip("nvme0");
+}
+
+/// Leituras AHCI repetidas devolvem o mesmo setor; escrita é recusada.
+#[cfg(feature = "ahci_test")]
+fn test_ahci_read() {
+    let disk = find_device("ahci0").expect("(Block) Nenhum disco AHCI registrado");
+    let size = disk.block_size();
+    let last = disk.total_blocks() - 1;
+    assert_eq!(size, 512);
+    assert!(disk.is_read_only());
+
+    let mut first = vec![0u8; size];
+    disk.read_block(0, &mut first)
+        .expect("(Block) Falha ao ler setor 0");
+    let mut buf = vec![0u8; size];
+    disk.read_block(last, &mut buf)
+        .expect("(Block) Falha ao ler último setor");
+    for _ in 0..64 {
+        disk.read_block(0, &mut buf).unwrap();
+        assert_eq!(buf, first, "(Block) Leituras do mesmo setor diferem");
+    }
+
+    assert_eq!(
+        disk.read_block(last + 1, &mut buf),
+        Err(BlockError::InvalidBlock)
+    );
+    assert_eq!(
+        disk.read_block(0, &mut buf[..100]),
+        Err(BlockError::InvalidBuffer)
+    );
+    assert_eq!(disk.write_block(0, &first), Err(BlockError::ReadOnly));
+}
+
+/// Escreve um padrão no último setor de `name`, lê de volta, restaura o
+/// conteúdo original e confere os erros de limite.
+#[cfg(any(feature = "ata_test", feature = "nvme_test"))]
+fn sector_roundtrip(name: &str) {
+    let disk = find_device(name).expect("(Block) Disco do teste não registrado");
+    let size = disk.block_size();
+    let last = disk.total_blocks() - 1;
+    assert!(!disk.is_read_only(), "(Block) Disco somente leitura");
+
+    let mut original = vec![0u8; size];
+    disk.read_block(last, &mut original)
+        .expect("(Block) Falha ao ler último setor");
+
+    let pattern: alloc::vec::Vec<u8> = (0..size).map(|i| (i * 13 + 5) as u8).collect();
+    disk.write_block(last, &pattern)
+        .expect("(Block) Falha ao escrever setor");
+    disk.flush().expect("(Block) Flush falhou");
+
+    let mut readback = vec![0u8; size];
+    disk.read_block(last, &mut readback).unwrap();
+    assert_eq!(readback, pattern, "(Block) Setor lido difere do escrito");
+
+    disk.write_block(last, &original).unwrap();
+    disk.read_block(last, &mut readback).unwrap();
+    assert_eq!(readback, original, "(Block) Setor não foi restaurado");
+
+    assert_eq!(
+        disk.write_block(last + 1, &pattern),
+        Err(BlockError::InvalidBlock)
+    );
+    assert_eq!(
+        disk.write_block(0, &pattern[..100]),
+        Err(BlockError::InvalidBuffer)
+    );
+}
@@ -38,6 +38,8 @@ pub enum BlockError {
     Busy,
     /// Erro genérico de hardware
     HardwareError,
+    /// Acesso além do fim de uma partição
+    OutOfBounds,
 }
 
 impl fmt::Display for BlockError {
@@ -50,6 +52,7 @@ impl fmt::Display for BlockError {
             BlockError::InvalidBuffer => write!(f, "Tamanho do buffer inválido"),
             BlockError::Busy => write!(f, "Dispositivo ocupado"),
             BlockError::HardwareError => write!(f, "Erro de hardware"),
+            BlockError::OutOfBounds => write!(f, "Acesso fora da partição"),
         }
     }
 }
@@ -91,22 +94,44 @@ pub trait BlockDevice: Send + Sync {
         false
     }
 
+    /// Tipo do dispositivo (ex: "ata", "virtio", "ram"); também o prefixo
+    /// do nome atribuído no registro
+    fn device_type(&self) -> &'static str {
+        "block"
+    }
+
+    /// Informações do dispositivo
+    fn info(&self) -> BlockDeviceInfo {
+        BlockDeviceInfo {
+            name: self.device_type(),
+            block_size: self.block_size(),
+            total_blocks: self.total_blocks(),
+            read_only: self.is_read_only(),
+        }
+    }
+
     /// Força a escrita de dados em cache para o dispositivo
     fn flush(&self) -> Result<(), BlockError> {
         Ok(())
     }
 
-    /// Lê múltiplos blocos contíguos
-    fn read_blocks(&self, start_lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+    /// Lê `count` blocos contíguos a partir de `start` para o início de
+    /// `buf` (mínimo `count * block_size` bytes)
+    ///
+    /// A implementação padrão faz uma leitura por bloco. Drivers que aceitam
+    /// requisições de vários setores (ATA, virtio, NVMe) devem sobrescrever
+    /// para enviar uma só.
+    fn read_blocks(&self, start: u64, count: usize, buf: &mut [u8]) -> Result<(), BlockError> {
         let block_size = self.block_size();
-        if buf.len() % block_size != 0 {
+        let len = count
+            .checked_mul(block_size)
+            .ok_or(BlockError::InvalidBuffer)?;
+        if buf.len() < len {
             return Err(BlockError::InvalidBuffer);
         }
 
-        let num_blocks = buf.len() / block_size;
-        for i in 0..num_blocks {
-            let offset = i * block_size;
-            self.read_block(start_lba + i as u64, &mut buf[offset..offset + block_size])?;
+        for (i, block) in buf[..len].chunks_exact_mut(block_size).enumerate() {
+            self.read_block(start + i as u64, block)?;
         }
         Ok(())
     }
@@ -151,3 +176,66 @@ impl BlockDeviceInfo {
         self.size_bytes() / (1024 * 1024)
     }
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Disco em que o bloco `n` é preenchido com `n`; conta as leituras
+    struct Numbered {
+        reads: AtomicUsize,
+    }
+
+    impl BlockDevice for Numbered {
+        fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+            if lba >= self.total_blocks() {
+                return Err(BlockError::InvalidBlock);
+            }
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            buf[..512].fill(lba as u8);
+            Ok(())
+        }
+
+        fn write_block(&self, _lba: u64, _buf: &[u8]) -> Result<(), BlockError> {
+            Err(BlockError::ReadOnly)
+        }
+
+        fn block_size(&self) -> usize {
+            512
+        }
+
+        fn total_blocks(&self) -> u64 {
+            8
+        }
+    }
+
+    #[test]
+    fn test_default_read_blocks_assembles_sectors() {
+        let disk = Numbered {
+            reads: AtomicUsize::new(0),
+        };
+        let mut buf = alloc::vec![0xFFu8; 4 * 512];
+        disk.read_blocks(2, 3, &mut buf).unwrap();
+
+        assert_eq!(disk.reads.load(Ordering::Relaxed), 3);
+        for (i, block) in buf.chunks(512).take(3).enumerate() {
+            assert!(block.iter().all(|&b| b == 2 + i as u8));
+        }
+        // Além de `count * block_size` o buffer fica intacto
+        assert!(buf[3 * 512..].iter().all(|&b| b == 0xFF));
+
+        assert_eq!(
+            disk.read_blocks(0, 2, &mut buf[..1000]),
+            Err(BlockError::InvalidBuffer)
+        );
+        assert_eq!(
+            disk.read_blocks(6, 3, &mut buf),
+            Err(BlockError::InvalidBlock)
+        );
+    }
+}
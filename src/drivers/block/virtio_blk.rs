@@ -13,6 +13,9 @@
 //! eficiente entre guest e host. O dispositivo aparece no barramento PCI
 //! com vendor=0x1AF4 (Red Hat/Virtio) e device=0x1001 (block).
 //!
+//! Usamos a interface legada (transitional): registradores no BAR0 de I/O,
+//! uma única virtqueue (0) e I/O síncrono por polling do used ring.
+//!
 //! ## Protocolo VirtIO Block
 //!
 //! ```text
@@ -22,13 +25,19 @@
 //! │  type, sector   │   (R/W buffer)  │   (resultado)   │
 //! └─────────────────┴─────────────────┴─────────────────┘
 //! ```
+//!
+//! Os três pedaços vivem num `DmaBuffer` do próprio dispositivo (o buffer do
+//! chamador está no heap e não tem endereço físico utilizável); os dados são
+//! copiados de/para ele em cada operação.
 
 #![allow(dead_code)]
 
 use super::traits::{BlockDevice, BlockError};
-use super::virtqueue::{desc_flags, Virtqueue, QUEUE_SIZE};
+use super::virtqueue::{desc_flags, Virtqueue};
+use crate::arch::x86_64::ports::{inl, inw, outb, outl, outw};
 use crate::drivers::pci::{self, PciDevice};
-use crate::mm::{PhysAddr, VirtAddr};
+use crate::mm::pfm::iommu::DmaBuffer;
+use crate::mm::PhysAddr;
 use crate::sync::Spinlock;
 use alloc::sync::Arc;
 use core::sync::atomic::{fence, Ordering};
@@ -36,6 +45,9 @@ use core::sync::atomic::{fence, Ordering};
 /// Tamanho padrão de setor
 const SECTOR_SIZE: usize = 512;
 
+/// Iterações de polling antes de desistir de uma requisição
+const POLL_LIMIT: u32 = 10_000_000;
+
 /// Tipos de operação VirtIO Block
 mod blk_type {
     pub const IN: u32 = 0; // Leitura
@@ -47,20 +59,28 @@ mod blk_status {
     pub const OK: u8 = 0;
     pub const IOERR: u8 = 1;
     pub const UNSUPP: u8 = 2;
+    /// Valor escrito antes do envio; o dispositivo sempre o sobrescreve
+    pub const PENDING: u8 = 0xFF;
+}
+
+/// Features do virtio-blk que o driver entende
+mod features {
+    /// Dispositivo somente leitura
+    pub const RO: u32 = 1 << 5;
 }
 
-/// Offsets dos registradores VirtIO Legacy (BAR0)
+/// Offsets dos registradores VirtIO Legacy (BAR0, I/O)
 mod regs {
-    pub const DEVICE_FEATURES: u64 = 0x00;
-    pub const DRIVER_FEATURES: u64 = 0x04;
-    pub const QUEUE_ADDRESS: u64 = 0x08;
-    pub const QUEUE_SIZE: u64 = 0x0C;
-    pub const QUEUE_SELECT: u64 = 0x0E;
-    pub const QUEUE_NOTIFY: u64 = 0x10;
-    pub const DEVICE_STATUS: u64 = 0x12;
-    pub const ISR_STATUS: u64 = 0x13;
-    // Configuração específica do blk (offset 0x14+)
-    pub const BLK_CAPACITY: u64 = 0x14; // 8 bytes
+    pub const DEVICE_FEATURES: u16 = 0x00;
+    pub const DRIVER_FEATURES: u16 = 0x04;
+    pub const QUEUE_ADDRESS: u16 = 0x08;
+    pub const QUEUE_SIZE: u16 = 0x0C;
+    pub const QUEUE_SELECT: u16 = 0x0E;
+    pub const QUEUE_NOTIFY: u16 = 0x10;
+    pub const DEVICE_STATUS: u16 = 0x12;
+    pub const ISR_STATUS: u16 = 0x13;
+    // Configuração específica do blk (offset 0x14+, sem MSI-X)
+    pub const BLK_CAPACITY: u16 = 0x14; // 8 bytes
 }
 
 /// Status do dispositivo VirtIO
@@ -73,6 +93,14 @@ mod status {
     pub const FAILED: u8 = 128;
 }
 
+/// Layout do buffer de requisição
+mod req_layout {
+    pub const HEADER: usize = 0;
+    pub const STATUS: usize = 16;
+    pub const DATA: usize = 512;
+    pub const SIZE: usize = DATA + super::SECTOR_SIZE;
+}
+
 /// Header do request VirtIO Block (16 bytes)
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -85,220 +113,195 @@ struct BlkReqHeader {
     sector: u64,
 }
 
+/// Virtqueue e o buffer de requisição, sempre usados juntos
+struct IoState {
+    queue: Virtqueue,
+    req: DmaBuffer,
+}
+
 /// Dispositivo de Bloco VirtIO
 pub struct VirtioBlk {
     /// Dispositivo PCI associado
     pci_device: PciDevice,
-    /// Endereço base dos registradores
-    mmio_base: VirtAddr,
+    /// Porta base dos registradores (BAR0)
+    io_base: u16,
     /// Total de setores
     total_sectors: u64,
-    /// Virtqueue para requisições
-    queue: Spinlock<Option<Virtqueue>>,
-    /// Buffer para request header (alocado uma vez)
-    req_header: Spinlock<BlkReqHeader>,
-    /// Buffer para status (1 byte)
-    status_buf: Spinlock<u8>,
-    /// Se o dispositivo foi inicializado com sucesso
-    initialized: bool,
+    /// Feature RO negociada
+    read_only: bool,
+    /// Estado de I/O; o lock serializa as requisições
+    io: Spinlock<IoState>,
 }
 
-// SAFETY: VirtioBlk usa locking interno
-unsafe impl Send for VirtioBlk {}
-unsafe impl Sync for VirtioBlk {}
-
 impl VirtioBlk {
     /// Cria e inicializa um dispositivo virtio-blk a partir de um PciDevice
     pub fn new(pci_device: PciDevice) -> Option<Self> {
         crate::kinfo!("(VirtIO-BLK) Inicializando dispositivo...");
 
-        // Habilitar Bus Master e Memory Space
+        // Interface legada: registradores em I/O space
+        let io_base = match pci_device.io_bar(0) {
+            Some(port) => port,
+            None => {
+                crate::kerror!("(VirtIO-BLK) BAR0 não é de I/O (dispositivo só moderno?)");
+                return None;
+            }
+        };
+        pci_device.enable_io_space();
         pci_device.enable_bus_master();
-        pci_device.enable_memory_space();
-
-        // Obter endereço MMIO do BAR0
-        let mmio_addr = pci_device.bar_address(0)?;
-        let mmio_base = VirtAddr::new(mmio_addr);
 
-        crate::kinfo!("(VirtIO-BLK) MMIO base:", mmio_addr);
-
-        let mut device = Self {
-            pci_device,
-            mmio_base,
-            total_sectors: 0,
-            queue: Spinlock::new(None),
-            req_header: Spinlock::new(BlkReqHeader {
-                req_type: 0,
-                reserved: 0,
-                sector: 0,
-            }),
-            status_buf: Spinlock::new(0),
-            initialized: false,
-        };
+        crate::kinfo!("(VirtIO-BLK) I/O base:", io_base as u64);
 
-        // Inicializar o dispositivo seguindo o protocolo VirtIO
-        if device.init_device() {
-            device.initialized = true;
-            crate::kinfo!("(VirtIO-BLK) Inicializado com sucesso!");
-            crate::kinfo!("(VirtIO-BLK) Capacidade:", device.total_sectors);
-            Some(device)
-        } else {
-            crate::kerror!("(VirtIO-BLK) Falha na inicialização!");
-            None
+        let device = Self::init_device(pci_device, io_base);
+        match device {
+            Some(ref device) => {
+                crate::kinfo!("(VirtIO-BLK) Inicializado com sucesso!");
+                crate::kinfo!("(VirtIO-BLK) Capacidade:", device.total_sectors);
+            }
+            None => {
+                // Sinaliza ao dispositivo que desistimos dele
+                outb(io_base + regs::DEVICE_STATUS, status::FAILED);
+                crate::kerror!("(VirtIO-BLK) Falha na inicialização!");
+            }
         }
+        device
     }
 
-    /// Inicializa o dispositivo VirtIO
-    fn init_device(&mut self) -> bool {
-        unsafe {
-            // 1. Reset (escrever 0 no status)
-            self.write_reg8(regs::DEVICE_STATUS, status::RESET);
-
-            // 2. Set ACKNOWLEDGE
-            self.write_reg8(regs::DEVICE_STATUS, status::ACKNOWLEDGE);
-
-            // 3. Set DRIVER
-            self.write_reg8(regs::DEVICE_STATUS, status::ACKNOWLEDGE | status::DRIVER);
-
-            // 4. Ler features do dispositivo
-            let device_features = self.read_reg32(regs::DEVICE_FEATURES);
-            crate::kdebug!("(VirtIO-BLK) Features:", device_features as u64);
+    /// Inicializa o dispositivo VirtIO (seção 3.1.1 / 4.1.5.1.3)
+    fn init_device(pci_device: PciDevice, io_base: u16) -> Option<Self> {
+        // 1. Reset (escrever 0 no status)
+        outb(io_base + regs::DEVICE_STATUS, status::RESET);
 
-            // 5. Negociar features (aceitar todas por enquanto)
-            self.write_reg32(regs::DRIVER_FEATURES, device_features);
+        // 2. Set ACKNOWLEDGE
+        outb(io_base + regs::DEVICE_STATUS, status::ACKNOWLEDGE);
 
-            // 6. Set FEATURES_OK
-            self.write_reg8(
-                regs::DEVICE_STATUS,
-                status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK,
-            );
+        // 3. Set DRIVER
+        outb(
+            io_base + regs::DEVICE_STATUS,
+            status::ACKNOWLEDGE | status::DRIVER,
+        );
 
-            // 7. Verificar se FEATURES_OK foi aceito
-            let status = self.read_reg8(regs::DEVICE_STATUS);
-            if status & status::FEATURES_OK == 0 {
-                crate::kerror!("(VirtIO-BLK) Features não aceitas!");
-                return false;
-            }
+        // 4. Ler features do dispositivo
+        let device_features = inl(io_base + regs::DEVICE_FEATURES);
+        crate::kdebug!("(VirtIO-BLK) Features:", device_features as u64);
 
-            // 8. Configurar virtqueue
-            self.write_reg16(regs::QUEUE_SELECT, 0); // Selecionar queue 0
+        // 5. Negociar: só aceitamos o que o driver entende. A interface
+        //    legada não tem FEATURES_OK; a escrita já fecha a negociação.
+        let accepted = device_features & features::RO;
+        outl(io_base + regs::DRIVER_FEATURES, accepted);
 
-            let queue_size = self.read_reg16(regs::QUEUE_SIZE);
-            crate::kdebug!("(VirtIO-BLK) Queue size:", queue_size as u64);
+        // 6. Configurar virtqueue 0
+        outw(io_base + regs::QUEUE_SELECT, 0);
 
-            if queue_size == 0 {
-                crate::kerror!("(VirtIO-BLK) Queue size inválido!");
-                return false;
-            }
+        // Na interface legada o tamanho é imposto pelo dispositivo
+        let queue_size = inw(io_base + regs::QUEUE_SIZE);
+        crate::kdebug!("(VirtIO-BLK) Queue size:", queue_size as u64);
 
-            // Criar virtqueue
-            let queue = match Virtqueue::new(queue_size.min(QUEUE_SIZE)) {
-                Some(q) => q,
-                None => {
-                    crate::kerror!("(VirtIO-BLK) Falha ao criar virtqueue!");
-                    return false;
-                }
-            };
-
-            // Passar endereço físico da queue para o dispositivo
-            // O endereço é dividido por 4096 (page size)
-            let queue_pfn = queue.phys_addr().as_u64() / 4096;
-            self.write_reg32(regs::QUEUE_ADDRESS, queue_pfn as u32);
-
-            *self.queue.lock() = Some(queue);
-
-            // 9. Ler capacidade do disco
-            let cap_lo = self.read_reg32(regs::BLK_CAPACITY);
-            let cap_hi = self.read_reg32(regs::BLK_CAPACITY + 4);
-            self.total_sectors = ((cap_hi as u64) << 32) | (cap_lo as u64);
-
-            // 10. Set DRIVER_OK
-            self.write_reg8(
-                regs::DEVICE_STATUS,
-                status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK | status::DRIVER_OK,
-            );
-
-            true
+        if queue_size < 3 || !queue_size.is_power_of_two() {
+            crate::kerror!("(VirtIO-BLK) Queue size inválido!");
+            return None;
         }
-    }
-
-    /// Lê um registrador (32 bits)
-    #[inline]
-    unsafe fn read_reg32(&self, offset: u64) -> u32 {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::read_volatile(addr as *const u32)
-    }
 
-    /// Escreve em um registrador (32 bits)
-    #[inline]
-    unsafe fn write_reg32(&self, offset: u64, value: u32) {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::write_volatile(addr as *mut u32, value);
-    }
+        let queue = match Virtqueue::new(queue_size) {
+            Some(q) => q,
+            None => {
+                crate::kerror!("(VirtIO-BLK) Falha ao criar virtqueue!");
+                return None;
+            }
+        };
+        let req = match DmaBuffer::new(req_layout::SIZE) {
+            Ok(buf) => buf,
+            Err(_) => {
+                crate::kerror!("(VirtIO-BLK) Falha ao alocar buffer de requisição!");
+                return None;
+            }
+        };
 
-    /// Lê um registrador (16 bits)
-    #[inline]
-    unsafe fn read_reg16(&self, offset: u64) -> u16 {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::read_volatile(addr as *const u16)
-    }
+        // Passar endereço físico da queue para o dispositivo
+        // O endereço é dividido por 4096 (page size)
+        let queue_pfn = queue.phys_addr().as_u64() / 4096;
+        outl(io_base + regs::QUEUE_ADDRESS, queue_pfn as u32);
 
-    /// Escreve em um registrador (16 bits)
-    #[inline]
-    unsafe fn write_reg16(&self, offset: u64, value: u16) {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::write_volatile(addr as *mut u16, value);
-    }
+        // 7. Ler capacidade do disco
+        let cap_lo = inl(io_base + regs::BLK_CAPACITY);
+        let cap_hi = inl(io_base + regs::BLK_CAPACITY + 4);
+        let total_sectors = ((cap_hi as u64) << 32) | (cap_lo as u64);
 
-    /// Lê um registrador (8 bits)
-    #[inline]
-    unsafe fn read_reg8(&self, offset: u64) -> u8 {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::read_volatile(addr as *const u8)
-    }
+        // 8. Set DRIVER_OK
+        outb(
+            io_base + regs::DEVICE_STATUS,
+            status::ACKNOWLEDGE | status::DRIVER | status::DRIVER_OK,
+        );
 
-    /// Escreve em um registrador (8 bits)
-    #[inline]
-    unsafe fn write_reg8(&self, offset: u64, value: u8) {
-        let addr = self.mmio_base.as_u64() + offset;
-        core::ptr::write_volatile(addr as *mut u8, value);
+        Some(Self {
+            pci_device,
+            io_base,
+            total_sectors,
+            read_only: accepted & features::RO != 0,
+            io: Spinlock::new(IoState { queue, req }),
+        })
     }
 
     /// Notifica o dispositivo de uma nova requisição
     #[inline]
-    unsafe fn notify(&self) {
-        self.write_reg16(regs::QUEUE_NOTIFY, 0);
+    fn notify(&self) {
+        outw(self.io_base + regs::QUEUE_NOTIFY, 0);
     }
 
-    /// Executa uma operação de I/O
+    /// Executa uma operação de I/O de um setor.
+    ///
+    /// Em escrita, `buf` é copiado para o buffer de DMA antes do envio; em
+    /// leitura, o setor é copiado para `buf` depois da conclusão.
     fn do_io(&self, sector: u64, buf: &mut [u8], is_write: bool) -> Result<(), BlockError> {
-        let mut queue_guard = self.queue.lock();
-        let queue = queue_guard.as_mut().ok_or(BlockError::NotFound)?;
-
-        // Preparar header
-        let mut header = self.req_header.lock();
-        header.req_type = if is_write {
-            blk_type::OUT
-        } else {
-            blk_type::IN
+        let mut io = self.io.lock();
+        let IoState { queue, req } = &mut *io;
+        let base = req.as_ptr();
+        let phys = req.phys().as_u64();
+
+        // Preparar header, status e (escrita) dados
+        let header = BlkReqHeader {
+            req_type: if is_write {
+                blk_type::OUT
+            } else {
+                blk_type::IN
+            },
+            reserved: 0,
+            sector,
         };
-        header.reserved = 0;
-        header.sector = sector;
-
-        // Reset status
-        *self.status_buf.lock() = 0xFF;
+        // SAFETY: offsets dentro do DmaBuffer, exclusivo deste dispositivo
+        unsafe {
+            core::ptr::write_volatile(base.add(req_layout::HEADER) as *mut BlkReqHeader, header);
+            core::ptr::write_volatile(base.add(req_layout::STATUS), blk_status::PENDING);
+            if is_write {
+                core::ptr::copy_nonoverlapping(
+                    buf.as_ptr(),
+                    base.add(req_layout::DATA),
+                    SECTOR_SIZE,
+                );
+            }
+        }
 
         // Alocar 3 descritores: header, data, status
         let desc0 = queue.alloc_desc().ok_or(BlockError::Busy)?;
-        let desc1 = queue.alloc_desc().ok_or(BlockError::Busy)?;
-        let desc2 = queue.alloc_desc().ok_or(BlockError::Busy)?;
+        let desc1 = match queue.alloc_desc() {
+            Some(d) => d,
+            None => {
+                queue.free_desc(desc0);
+                return Err(BlockError::Busy);
+            }
+        };
+        let desc2 = match queue.alloc_desc() {
+            Some(d) => d,
+            None => {
+                queue.free_desc(desc1);
+                queue.free_desc(desc0);
+                return Err(BlockError::Busy);
+            }
+        };
 
-        // Configurar descritores
         // Desc 0: Header (device-readable)
-        let header_ptr = &*header as *const BlkReqHeader;
         queue.set_desc(
             desc0,
-            PhysAddr::new(header_ptr as u64),
+            PhysAddr::new(phys + req_layout::HEADER as u64),
             core::mem::size_of::<BlkReqHeader>() as u32,
             desc_flags::NEXT,
             desc1,
@@ -312,73 +315,83 @@ impl VirtioBlk {
         };
         queue.set_desc(
             desc1,
-            PhysAddr::new(buf.as_ptr() as u64),
-            buf.len() as u32,
+            PhysAddr::new(phys + req_layout::DATA as u64),
+            SECTOR_SIZE as u32,
             data_flags,
             desc2,
         );
 
         // Desc 2: Status (device-writable)
-        let status_ptr = &*self.status_buf.lock() as *const u8;
         queue.set_desc(
             desc2,
-            PhysAddr::new(status_ptr as u64),
+            PhysAddr::new(phys + req_layout::STATUS as u64),
             1,
             desc_flags::WRITE,
             0,
         );
 
-        // Adicionar ao available ring
+        // Adicionar ao available ring e notificar
         queue.push_avail(desc0);
-
-        // Memory barrier
         fence(Ordering::SeqCst);
-
-        // Notificar dispositivo
-        unsafe {
-            self.notify();
-        }
+        self.notify();
 
         // Aguardar completion (polling)
-        let mut timeout = 1_000_000u32;
-        while !queue.has_used() && timeout > 0 {
+        let mut polls = POLL_LIMIT;
+        while !queue.has_used() && polls > 0 {
             core::hint::spin_loop();
-            timeout -= 1;
+            polls -= 1;
         }
 
-        if timeout == 0 {
-            crate::kerror!("(VirtIO-BLK) Timeout na operação!");
-            // Liberar descritores
-            queue.free_desc(desc0);
-            queue.free_desc(desc1);
-            queue.free_desc(desc2);
+        if polls == 0 {
+            // O dispositivo ainda é dono da cadeia: os descritores ficam
+            // alocados para não serem reutilizados enquanto ele pode escrever
+            crate::kerror!("(VirtIO-BLK) Timeout na operação! Setor:", sector);
             return Err(BlockError::IoError);
         }
 
-        // Pop do used ring
-        let _ = queue.pop_used();
+        let used = queue.pop_used();
 
         // Liberar descritores
         queue.free_desc(desc0);
         queue.free_desc(desc1);
         queue.free_desc(desc2);
 
-        // Verificar status
-        let status = *self.status_buf.lock();
-        match status {
-            blk_status::OK => Ok(()),
-            blk_status::IOERR => Err(BlockError::IoError),
-            _ => Err(BlockError::HardwareError),
+        if used.map(|elem| elem.id) != Some(desc0 as u32) {
+            crate::kerror!("(VirtIO-BLK) Conclusão de cadeia inesperada");
+            return Err(BlockError::HardwareError);
+        }
+
+        // SAFETY: o dispositivo terminou de escrever (used ring atualizado)
+        let status = unsafe { core::ptr::read_volatile(base.add(req_layout::STATUS)) };
+        status_to_result(status)?;
+
+        if !is_write {
+            // SAFETY: idem
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    base.add(req_layout::DATA),
+                    buf.as_mut_ptr(),
+                    SECTOR_SIZE,
+                );
+            }
         }
+        Ok(())
+    }
+}
+
+/// Converte o byte de status do dispositivo
+fn status_to_result(status: u8) -> Result<(), BlockError> {
+    match status {
+        blk_status::OK => Ok(()),
+        blk_status::IOERR => Err(BlockError::IoError),
+        blk_status::UNSUPP => Err(BlockError::HardwareError),
+        // Nenhum status escrito ou valor fora da especificação
+        _ => Err(BlockError::IoError),
     }
 }
 
 impl BlockDevice for VirtioBlk {
     fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
-        if !self.initialized {
-            return Err(BlockError::NotFound);
-        }
-
         if lba >= self.total_sectors {
             return Err(BlockError::InvalidBlock);
         }
@@ -390,10 +403,9 @@ impl BlockDevice for VirtioBlk {
     }
 
     fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), BlockError> {
-        if !self.initialized {
-            return Err(BlockError::NotFound);
+        if self.read_only {
+            return Err(BlockError::ReadOnly);
         }
-
         if lba >= self.total_sectors {
             return Err(BlockError::InvalidBlock);
         }
@@ -401,11 +413,10 @@ impl BlockDevice for VirtioBlk {
             return Err(BlockError::InvalidBuffer);
         }
 
-        // Cast para &mut [u8] é necessário pela interface
-        // SAFETY: O dispositivo não modifica o buffer em writes
-        let buf_mut =
-            unsafe { core::slice::from_raw_parts_mut(buf.as_ptr() as *mut u8, buf.len()) };
-        self.do_io(lba, buf_mut, true)
+        // `do_io` só lê de `buf` em escritas; a cópia local evita o cast
+        let mut sector = [0u8; SECTOR_SIZE];
+        sector.copy_from_slice(&buf[..SECTOR_SIZE]);
+        self.do_io(lba, &mut sector, true)
     }
 
     fn block_size(&self) -> usize {
@@ -415,6 +426,14 @@ impl BlockDevice for VirtioBlk {
     fn total_blocks(&self) -> u64 {
         self.total_sectors
     }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn device_type(&self) -> &'static str {
+        "virtio"
+    }
 }
 
 /// Tenta inicializar dispositivo virtio-blk
@@ -436,3 +455,29 @@ pub fn init() -> Option<Arc<dyn BlockDevice>> {
 
     Some(Arc::new(device))
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_byte() {
+        assert_eq!(status_to_result(blk_status::OK), Ok(()));
+        assert_eq!(
+            status_to_result(blk_status::IOERR),
+            Err(BlockError::IoError)
+        );
+        assert_eq!(
+            status_to_result(blk_status::UNSUPP),
+            Err(BlockError::HardwareError)
+        );
+        assert_eq!(
+            status_to_result(blk_status::PENDING),
+            Err(BlockError::IoError)
+        );
+    }
+}
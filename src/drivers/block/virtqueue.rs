@@ -29,6 +29,7 @@
 
 #![allow(dead_code)]
 
+use crate::mm::pfm::iommu::DmaBuffer;
 use crate::mm::{PhysAddr, VirtAddr};
 use crate::sync::Spinlock;
 use alloc::vec::Vec;
@@ -111,8 +112,8 @@ pub struct Virtqueue {
     free_head: u16,
     /// Número de descritores livres
     num_free: u16,
-    /// Endereço físico da queue (para passar ao dispositivo)
-    phys_addr: PhysAddr,
+    /// Memória contígua que contém os três rings
+    ring: DmaBuffer,
     /// Lock para operações
     lock: Spinlock<()>,
 }
@@ -131,27 +132,28 @@ impl Virtqueue {
         let avail_size = 2 + 2 + (2 * size as usize) + 2; // flags + idx + ring + used_event
         let used_size = 2 + 2 + (8 * size as usize) + 2; // flags + idx + ring + avail_event
 
-        // Alinhamentos: descriptors (16), available (2), used (4)
-        let total_size =
-            align_up(desc_size, 16) + align_up(avail_size, 2) + align_up(used_size, 4096); // used precisa estar em página separada
+        // Layout legado: descriptors + available contíguos, used na próxima página
+        let used_offset = align_up(desc_size + avail_size, 4096);
+        let total_size = used_offset + align_up(used_size, 4096);
 
         crate::kdebug!("(Virtqueue) Alocando:", total_size as u64);
 
-        // Alocar memória contígua alinhada a página
-        let layout = core::alloc::Layout::from_size_align(total_size, 4096).ok()?;
-        let base = unsafe { alloc::alloc::alloc_zeroed(layout) };
-        if base.is_null() {
-            crate::kerror!("(Virtqueue) Falha na alocação!");
-            return None;
-        }
+        // Alocar memória fisicamente contígua alinhada a página
+        let ring = match DmaBuffer::new(total_size) {
+            Ok(buf) => buf,
+            Err(_) => {
+                crate::kerror!("(Virtqueue) Falha na alocação!");
+                return None;
+            }
+        };
 
+        let base = ring.as_ptr();
         let base_addr = base as u64;
 
         // Calcular ponteiros
         let desc = base as *mut VirtqDesc;
-        let avail = (base_addr + align_up(desc_size, 16) as u64) as *mut VirtqAvail;
-        let used = (base_addr + align_up(desc_size, 16) as u64 + align_up(avail_size, 4096) as u64)
-            as *mut VirtqUsed;
+        let avail = (base_addr + desc_size as u64) as *mut VirtqAvail;
+        let used = (base_addr + used_offset as u64) as *mut VirtqUsed;
 
         // Inicializar lista de descritores livres
         unsafe {
@@ -161,10 +163,6 @@ impl Virtqueue {
             }
         }
 
-        // Obter endereço físico (assumindo identity mapping ou HHDM)
-        // TODO: Converter para endereço físico real via page tables
-        let phys_addr = PhysAddr::new(base_addr);
-
         crate::kinfo!("(Virtqueue) Criada com tamanho:", size as u64);
 
         Some(Self {
@@ -175,14 +173,14 @@ impl Virtqueue {
             last_used_idx: 0,
             free_head: 0,
             num_free: size,
-            phys_addr,
+            ring,
             lock: Spinlock::new(()),
         })
     }
 
     /// Retorna o endereço físico da queue (para configurar no dispositivo)
     pub fn phys_addr(&self) -> PhysAddr {
-        self.phys_addr
+        self.ring.phys()
     }
 
     /// Retorna o tamanho da queue
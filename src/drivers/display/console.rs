@@ -0,0 +1,347 @@
+//! # Console de vídeo
+//!
+//! Terminal de texto desenhado direto no framebuffer do bootloader, com a
+//! fonte 8x16 de [`super::font`]. Os logs do kernel passam por aqui além da
+//! serial (ver `core::debug::klog`).
+//!
+//! - `\n` desce uma linha e volta à coluna 0; `\r` só volta à coluna 0.
+//! - Na última linha, a tela sobe uma linha de texto (memmove do
+//!   framebuffer) e a linha nova é limpa.
+//! - Bytes fora do ASCII aparecem como `?`, um por caractere UTF-8.
+//!
+//! Sem framebuffer (`addr` zero ou formato `BltOnly`) o console não é
+//! criado e os logs ficam só na serial. Quando o compositor apresenta o
+//! primeiro quadro o console é desligado, para não desenhar por cima.
+
+use super::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::core::boot::handoff::{FramebufferInfo, PixelFormat};
+use crate::sync::Spinlock;
+
+/// Bytes por pixel (o bootloader só entrega modos de 32 bits)
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Cor do texto (cinza claro) e do fundo, em RGB
+const FG_RGB: (u8, u8, u8) = (0xAA, 0xAA, 0xAA);
+const BG_RGB: (u8, u8, u8) = (0x00, 0x00, 0x00);
+
+/// Largura de uma tabulação, em colunas
+const TAB_WIDTH: usize = 8;
+
+/// Console global; `None` sem framebuffer ou depois de [`detach`]
+static CONSOLE: Spinlock<Option<FramebufferConsole>> = Spinlock::new(None);
+
+/// Codifica uma cor RGB no formato de pixel do framebuffer
+fn encode_pixel(format: PixelFormat, (r, g, b): (u8, u8, u8)) -> u32 {
+    match format {
+        // Byte 0 = vermelho
+        PixelFormat::Rgb => r as u32 | (g as u32) << 8 | (b as u32) << 16,
+        // Byte 0 = azul. Bitmask sem máscaras no handoff: assume BGR, como o CRTC
+        _ => b as u32 | (g as u32) << 8 | (r as u32) << 16,
+    }
+}
+
+/// Console de texto sobre um framebuffer linear de 32 bits
+pub struct FramebufferConsole {
+    /// Início do framebuffer (mapeado no HHDM)
+    base: *mut u8,
+    /// Bytes por linha de pixels
+    pitch: usize,
+    width: usize,
+    height: usize,
+    /// Tamanho em células de texto
+    cols: usize,
+    rows: usize,
+    /// Cursor
+    col: usize,
+    row: usize,
+    fg: u32,
+    bg: u32,
+}
+
+// O ponteiro só é usado com o lock de `CONSOLE`
+unsafe impl Send for FramebufferConsole {}
+
+impl FramebufferConsole {
+    /// Console sobre o framebuffer descrito pelo bootloader.
+    ///
+    /// `None` se não há framebuffer utilizável ou ele não comporta um glifo.
+    pub fn new(info: &FramebufferInfo) -> Option<Self> {
+        if info.addr == 0 || info.format == PixelFormat::BltOnly {
+            return None;
+        }
+        let base = unsafe { crate::mm::addr::phys_to_virt::<u8>(info.addr) };
+        unsafe {
+            Self::from_raw(
+                base,
+                info.stride as usize * BYTES_PER_PIXEL,
+                info.width as usize,
+                info.height as usize,
+                info.format,
+            )
+        }
+    }
+
+    /// Console sobre um buffer arbitrário.
+    ///
+    /// # Safety
+    /// `base` deve apontar para `pitch * height` bytes graváveis, válidos
+    /// enquanto o console existir.
+    pub unsafe fn from_raw(
+        base: *mut u8,
+        pitch: usize,
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+    ) -> Option<Self> {
+        let cols = width / GLYPH_WIDTH;
+        let rows = height / GLYPH_HEIGHT;
+        if base.is_null() || cols == 0 || rows == 0 || pitch < width * BYTES_PER_PIXEL {
+            return None;
+        }
+        Some(Self {
+            base,
+            pitch,
+            width,
+            height,
+            cols,
+            rows,
+            col: 0,
+            row: 0,
+            fg: encode_pixel(format, FG_RGB),
+            bg: encode_pixel(format, BG_RGB),
+        })
+    }
+
+    /// Posição do cursor (coluna, linha)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.col, self.row)
+    }
+
+    /// Tamanho em células de texto (colunas, linhas)
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// Limpa a tela e leva o cursor ao início
+    pub fn clear(&mut self) {
+        for y in 0..self.height {
+            self.fill_span(y, 0, self.width, self.bg);
+        }
+        self.col = 0;
+        self.row = 0;
+    }
+
+    pub fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.new_line(),
+            b'\r' => self.col = 0,
+            b'\t' => {
+                let next = (self.col / TAB_WIDTH + 1) * TAB_WIDTH;
+                while self.col < next.min(self.cols) {
+                    self.put_char(b' ');
+                }
+            }
+            // Continuação UTF-8: o caractere já foi desenhado no byte inicial
+            0x80..=0xBF => {}
+            _ => self.put_char(byte),
+        }
+    }
+
+    /// Desenha na posição do cursor e avança, quebrando a linha no fim
+    fn put_char(&mut self, c: u8) {
+        if self.col >= self.cols {
+            self.new_line();
+        }
+        self.draw_glyph(self.col, self.row, c);
+        self.col += 1;
+    }
+
+    fn new_line(&mut self) {
+        self.col = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    /// Sobe o conteúdo uma linha de texto e limpa a última
+    fn scroll(&mut self) {
+        let line_bytes = GLYPH_HEIGHT * self.pitch;
+        let kept = (self.rows - 1) * line_bytes;
+        unsafe {
+            core::ptr::copy(self.base.add(line_bytes), self.base, kept);
+        }
+        let first = (self.rows - 1) * GLYPH_HEIGHT;
+        for y in first..self.rows * GLYPH_HEIGHT {
+            self.fill_span(y, 0, self.cols * GLYPH_WIDTH, self.bg);
+        }
+    }
+
+    fn draw_glyph(&mut self, col: usize, row: usize, c: u8) {
+        let glyph = font::glyph(c);
+        let x0 = col * GLYPH_WIDTH;
+        let y0 = row * GLYPH_HEIGHT;
+        for (dy, &bits) in glyph.iter().enumerate() {
+            let line = self.pixel_ptr(x0, y0 + dy);
+            for dx in 0..GLYPH_WIDTH {
+                let color = if bits & (0x80 >> dx) != 0 {
+                    self.fg
+                } else {
+                    self.bg
+                };
+                unsafe { core::ptr::write_volatile(line.add(dx), color) };
+            }
+        }
+    }
+
+    fn fill_span(&mut self, y: usize, x: usize, len: usize, color: u32) {
+        let line = self.pixel_ptr(x, y);
+        for i in 0..len {
+            unsafe { core::ptr::write_volatile(line.add(i), color) };
+        }
+    }
+
+    fn pixel_ptr(&self, x: usize, y: usize) -> *mut u32 {
+        unsafe {
+            self.base
+                .add(y * self.pitch + x * BYTES_PER_PIXEL)
+                .cast::<u32>()
+        }
+    }
+}
+
+// =============================================================================
+// CONSOLE GLOBAL
+// =============================================================================
+
+/// Cria o console sobre o framebuffer do bootloader, se houver.
+pub fn init(info: &FramebufferInfo) {
+    match FramebufferConsole::new(info) {
+        Some(mut console) => {
+            console.clear();
+            *CONSOLE.lock() = Some(console);
+            crate::kinfo!("(Console) Console de vídeo ativo");
+        }
+        None => {
+            crate::kwarn!("(Console) Sem framebuffer; logs apenas na serial");
+        }
+    }
+}
+
+/// Há console de vídeo ativo?
+pub fn is_active() -> bool {
+    CONSOLE.lock().is_some()
+}
+
+/// Escreve texto no console, se ativo.
+///
+/// Usado no caminho de log: se o lock já estiver tomado (log emitido de
+/// dentro do próprio console), o texto vai só para a serial.
+pub fn write_str(s: &str) {
+    write_bytes(s.as_bytes());
+}
+
+/// Escreve bytes no console, se ativo (ver [`write_str`])
+pub fn write_bytes(bytes: &[u8]) {
+    if let Some(mut guard) = CONSOLE.try_lock() {
+        if let Some(console) = guard.as_mut() {
+            console.write_bytes(bytes);
+        }
+    }
+}
+
+/// Desliga o console; o framebuffer passa a ser só do compositor.
+pub fn detach() {
+    CONSOLE.lock().take();
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Console 3x2 células sobre um buffer de memória
+    fn console(pixels: &mut [u32]) -> FramebufferConsole {
+        let width = 3 * GLYPH_WIDTH;
+        unsafe {
+            FramebufferConsole::from_raw(
+                pixels.as_mut_ptr().cast(),
+                width * BYTES_PER_PIXEL,
+                width,
+                2 * GLYPH_HEIGHT,
+                PixelFormat::Rgb,
+            )
+            .unwrap()
+        }
+    }
+
+    /// A célula (col, row) contém o glifo de `c`?
+    fn cell_is(con: &FramebufferConsole, pixels: &[u32], col: usize, row: usize, c: u8) -> bool {
+        let stride = con.pitch / BYTES_PER_PIXEL;
+        font::glyph(c).iter().enumerate().all(|(dy, &bits)| {
+            (0..GLYPH_WIDTH).all(|dx| {
+                let pixel = pixels[(row * GLYPH_HEIGHT + dy) * stride + col * GLYPH_WIDTH + dx];
+                let on = bits & (0x80 >> dx) != 0;
+                pixel == if on { con.fg } else { con.bg }
+            })
+        })
+    }
+
+    #[test]
+    fn test_cursor_and_control_chars() {
+        let mut pixels = vec![0u32; 3 * 8 * 2 * 16];
+        let mut con = console(&mut pixels);
+
+        con.write_str("ab");
+        assert_eq!(con.cursor(), (2, 0));
+        con.write_str("\rX");
+        assert_eq!(con.cursor(), (1, 0));
+        con.write_str("\n");
+        assert_eq!(con.cursor(), (0, 1));
+        // UTF-8 de dois bytes ocupa uma célula
+        con.write_str("ç");
+        assert_eq!(con.cursor(), (1, 1));
+
+        assert!(cell_is(&con, &pixels, 0, 0, b'X'));
+        assert!(cell_is(&con, &pixels, 1, 0, b'b'));
+        assert!(cell_is(&con, &pixels, 0, 1, b'?'));
+    }
+
+    #[test]
+    fn test_wrap_and_scroll() {
+        let mut pixels = vec![0u32; 3 * 8 * 2 * 16];
+        let mut con = console(&mut pixels);
+
+        // Quebra na 4ª coluna, depois rola ao passar da última linha
+        con.write_str("abcdef");
+        assert_eq!(con.cursor(), (3, 1));
+        con.write_str("g");
+        assert_eq!(con.cursor(), (1, 1));
+
+        assert!(cell_is(&con, &pixels, 0, 0, b'd'));
+        assert!(cell_is(&con, &pixels, 2, 0, b'f'));
+        assert!(cell_is(&con, &pixels, 0, 1, b'g'));
+        assert!(cell_is(&con, &pixels, 1, 1, b' '));
+    }
+
+    #[test]
+    fn test_encode_pixel() {
+        assert_eq!(encode_pixel(PixelFormat::Rgb, (1, 2, 3)), 0x030201);
+        assert_eq!(encode_pixel(PixelFormat::Bgr, (1, 2, 3)), 0x010203);
+    }
+}
@@ -121,6 +121,11 @@ impl Crtc {
             .get(buffer_handle)
             .ok_or(CrtcError::InvalidBuffer)?;
 
+        if self.front_buffer.is_none() {
+            // Primeiro quadro do compositor: o console de texto sai de cena
+            super::console::detach();
+        }
+
         // Copiar buffer para framebuffer de hardware
         self.copy_to_hardware(buffer)?;
 
@@ -145,6 +150,11 @@ impl Crtc {
             .get(buffer_handle)
             .ok_or(CrtcError::InvalidBuffer)?;
 
+        if self.front_buffer.is_none() {
+            // Primeiro quadro do compositor: o console de texto sai de cena
+            super::console::detach();
+        }
+
         if damage.is_empty() {
             // Sem damage = copia tudo
             self.copy_to_hardware(buffer)?;
@@ -12,13 +12,19 @@
 //! │   Manager   │  (Output)   │  (Future)   │
 //! └─────────────┴─────────────┴─────────────┘
 //! ```
+//!
+//! Até o compositor assumir, o framebuffer mostra o [`console`] de texto
+//! com os logs do kernel.
 
 pub mod buffer;
+pub mod console;
 pub mod crtc;
+pub mod font;
 
 use crate::core::boot::handoff::FramebufferInfo as HandoffFbInfo;
 
 pub use buffer::{BufferManager, DisplayBuffer, BUFFER_MANAGER};
+pub use console::FramebufferConsole;
 pub use crtc::{Crtc, DISPLAY_CRTC};
 
 /// Inicializa o subsistema de display.
@@ -31,5 +37,8 @@ pub fn init(info: HandoffFbInfo) {
     // Inicializar CRTC com informações do bootloader
     crtc::init(info);
 
+    // Console de texto (sem framebuffer, os logs ficam só na serial)
+    console::init(&info);
+
     crate::kinfo!("(Display) Subsistema inicializado com sucesso!");
 }
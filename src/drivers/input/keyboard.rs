@@ -1,5 +1,10 @@
 //! PS/2 Keyboard Driver (Interrupt Driven)
+//!
+//! O IRQ 1 lê o scancode da porta 0x60, o [`Decoder`] o traduz e o evento
+//! vai para uma fila circular. Consumidores: [`read_key`] (kernel),
+//! `/dev/input` e a syscall `SYS_KEYBOARD_READ`. Todos tiram da mesma fila.
 
+use super::scancode::{Decoder, KeyEvent, Modifiers};
 use crate::arch::x86_64::ports::{inb, outb};
 use crate::sync::Spinlock;
 
@@ -8,8 +13,16 @@ const STATUS_PORT: u16 = 0x64;
 const CMD_PORT: u16 = 0x64;
 const BUFFER_SIZE: usize = 256;
 
+/// Posição vazia da fila
+const NO_EVENT: KeyEvent = KeyEvent {
+    keycode: 0,
+    pressed: false,
+    ascii: None,
+    modifiers: Modifiers::empty(),
+};
+
 struct KeyboardBuffer {
-    data: [u8; BUFFER_SIZE],
+    data: [KeyEvent; BUFFER_SIZE],
     head: usize,
     tail: usize,
 }
@@ -17,16 +30,16 @@ struct KeyboardBuffer {
 impl KeyboardBuffer {
     const fn new() -> Self {
         Self {
-            data: [0; BUFFER_SIZE],
+            data: [NO_EVENT; BUFFER_SIZE],
             head: 0,
             tail: 0,
         }
     }
 
-    fn push(&mut self, byte: u8) {
+    fn push(&mut self, event: KeyEvent) {
         let next_head = (self.head + 1) % BUFFER_SIZE;
         if next_head != self.tail {
-            self.data[self.head] = byte;
+            self.data[self.head] = event;
             self.head = next_head;
         } else {
             // Buffer full, drop packet
@@ -34,18 +47,31 @@ impl KeyboardBuffer {
         }
     }
 
-    fn pop(&mut self) -> Option<u8> {
+    fn pop(&mut self) -> Option<KeyEvent> {
         if self.head == self.tail {
             None
         } else {
-            let byte = self.data[self.tail];
+            let event = self.data[self.tail];
             self.tail = (self.tail + 1) % BUFFER_SIZE;
-            Some(byte)
+            Some(event)
         }
     }
+
+    fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+}
+
+/// Estado do teclado: decodificador e fila de eventos
+struct Keyboard {
+    decoder: Decoder,
+    events: KeyboardBuffer,
 }
 
-static KBD_BUFFER: Spinlock<KeyboardBuffer> = Spinlock::new(KeyboardBuffer::new());
+static KEYBOARD: Spinlock<Keyboard> = Spinlock::new(Keyboard {
+    decoder: Decoder::new(),
+    events: KeyboardBuffer::new(),
+});
 
 pub fn init() {
     // 1. Habilitar a porta do teclado no controlador 8042
@@ -100,15 +126,28 @@ pub fn handle_irq() {
     // onde o bit já foi limpo por outra operação.
     let scancode = inb(DATA_PORT);
     crate::kdebug!("(KBD) IRQ: scancode=", scancode as u64);
-    KBD_BUFFER.lock().push(scancode);
+
+    let queued = {
+        let mut keyboard = KEYBOARD.lock();
+        match keyboard.decoder.feed(scancode) {
+            Some(event) => {
+                keyboard.events.push(event);
+                true
+            }
+            None => false,
+        }
+    };
+    if queued {
+        crate::sched::sync::poll::notify();
+    }
 }
 
-/// Consome um scancode do buffer
-pub fn pop_scancode() -> Option<u8> {
-    KBD_BUFFER.lock().pop()
+/// Consome o próximo evento de tecla, se houver
+pub fn read_key() -> Option<KeyEvent> {
+    KEYBOARD.lock().events.pop()
 }
 
-// Manter compatibilidade caso algo use polling direto (opcional)
-pub fn read_scancode() -> Option<u8> {
-    pop_scancode()
+/// Há eventos de tecla na fila?
+pub fn has_key() -> bool {
+    !KEYBOARD.lock().events.is_empty()
 }
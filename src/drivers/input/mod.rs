@@ -2,6 +2,10 @@
 
 pub mod keyboard;
 pub mod mouse;
+pub mod scancode;
+
+pub use keyboard::read_key;
+pub use scancode::{KeyCode, KeyEvent, Modifiers};
 
 /// Inicializa subsistema de entrada
 pub fn init() {
@@ -0,0 +1,361 @@
+//! # Scancodes PS/2 (set 1)
+//!
+//! Traduz os bytes do teclado (set 1, o que o 8042 entrega com tradução
+//! ligada) em [`KeyEvent`]s, com o estado dos modificadores.
+//!
+//! - Bit 7 do scancode: tecla solta (break); sem ele, pressionada (make).
+//! - `E0` antes do código: tecla estendida (setas, Ctrl/Alt direitos...).
+//!   O keycode delas é `0x100 | código`.
+//! - `E1`: Pause, uma sequência de 6 bytes sem break; é descartada.
+//! - `E0 2A`/`E0 36` (e os breaks): shift falso que o teclado manda em volta
+//!   de PrintScreen e das setas; ignorados.
+//!
+//! O layout é US. Caps Lock alterna com o make (auto-repeat não conta) e só
+//! afeta letras; Ctrl com letra gera o caractere de controle (Ctrl+C =
+//! 0x03).
+
+use crate::bitflags;
+
+/// Código de uma tecla: o scancode sem o bit de break, com `0x100` nas
+/// estendidas
+pub type KeyCode = u16;
+
+/// Keycodes com nome (os que o decodificador trata à parte)
+pub mod keys {
+    use super::KeyCode;
+
+    pub const ESC: KeyCode = 0x01;
+    pub const BACKSPACE: KeyCode = 0x0E;
+    pub const TAB: KeyCode = 0x0F;
+    pub const ENTER: KeyCode = 0x1C;
+    pub const LEFT_CTRL: KeyCode = 0x1D;
+    pub const LEFT_SHIFT: KeyCode = 0x2A;
+    pub const RIGHT_SHIFT: KeyCode = 0x36;
+    pub const LEFT_ALT: KeyCode = 0x38;
+    pub const SPACE: KeyCode = 0x39;
+    pub const CAPS_LOCK: KeyCode = 0x3A;
+
+    pub const KEYPAD_ENTER: KeyCode = 0x11C;
+    pub const RIGHT_CTRL: KeyCode = 0x11D;
+    pub const KEYPAD_SLASH: KeyCode = 0x135;
+    pub const RIGHT_ALT: KeyCode = 0x138;
+    pub const HOME: KeyCode = 0x147;
+    pub const UP: KeyCode = 0x148;
+    pub const PAGE_UP: KeyCode = 0x149;
+    pub const LEFT: KeyCode = 0x14B;
+    pub const RIGHT: KeyCode = 0x14D;
+    pub const END: KeyCode = 0x14F;
+    pub const DOWN: KeyCode = 0x150;
+    pub const PAGE_DOWN: KeyCode = 0x151;
+    pub const INSERT: KeyCode = 0x152;
+    pub const DELETE: KeyCode = 0x153;
+}
+
+bitflags! {
+    /// Modificadores ativos
+    pub struct Modifiers: u8 {
+        const LEFT_SHIFT  = 1 << 0;
+        const RIGHT_SHIFT = 1 << 1;
+        const LEFT_CTRL   = 1 << 2;
+        const RIGHT_CTRL  = 1 << 3;
+        const LEFT_ALT    = 1 << 4;
+        const RIGHT_ALT   = 1 << 5;
+        const CAPS_LOCK   = 1 << 6;
+    }
+}
+
+impl Modifiers {
+    pub fn shift(&self) -> bool {
+        self.intersection(Self::LEFT_SHIFT | Self::RIGHT_SHIFT) != Self::empty()
+    }
+
+    pub fn ctrl(&self) -> bool {
+        self.intersection(Self::LEFT_CTRL | Self::RIGHT_CTRL) != Self::empty()
+    }
+
+    pub fn alt(&self) -> bool {
+        self.intersection(Self::LEFT_ALT | Self::RIGHT_ALT) != Self::empty()
+    }
+
+    pub fn caps_lock(&self) -> bool {
+        self.contains(Self::CAPS_LOCK)
+    }
+}
+
+/// Uma tecla pressionada ou solta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub keycode: KeyCode,
+    pub pressed: bool,
+    /// Caractere gerado (só em teclas pressionadas)
+    pub ascii: Option<u8>,
+    /// Modificadores depois desta tecla
+    pub modifiers: Modifiers,
+}
+
+/// Tamanho de [`KeyEvent::to_bytes`]
+pub const EVENT_SIZE: usize = 4;
+
+impl KeyEvent {
+    /// Scancode de 7 bits, sem o prefixo `E0`
+    pub fn scancode(&self) -> u8 {
+        (self.keycode & 0x7F) as u8
+    }
+
+    /// Registro de 4 bytes lido em `/dev/input`:
+    ///
+    /// | Byte | Conteúdo                                                   |
+    /// |------|------------------------------------------------------------|
+    /// | 0-1  | keycode (LE)                                               |
+    /// | 2    | bit 0 pressionada, 1 shift, 2 ctrl, 3 alt, 4 caps lock      |
+    /// | 3    | caractere ASCII, 0 se nenhum                               |
+    pub fn to_bytes(&self) -> [u8; EVENT_SIZE] {
+        let flags = self.pressed as u8
+            | ((self.modifiers.shift() as u8) << 1)
+            | ((self.modifiers.ctrl() as u8) << 2)
+            | ((self.modifiers.alt() as u8) << 3)
+            | ((self.modifiers.caps_lock() as u8) << 4);
+        let [low, high] = self.keycode.to_le_bytes();
+        [low, high, flags, self.ascii.unwrap_or(0)]
+    }
+}
+
+/// Caracteres do layout US por scancode (0x00..=0x39); 0 = nenhum
+const NORMAL: &[u8; 0x3A] =
+    b"\0\x1b1234567890-=\x08\tqwertyuiop[]\n\0asdfghjkl;'`\0\\zxcvbnm,./\0*\0 ";
+/// Idem, com shift
+const SHIFTED: &[u8; 0x3A] =
+    b"\0\x1b!@#$%^&*()_+\x08\tQWERTYUIOP{}\n\0ASDFGHJKL:\"~\0|ZXCVBNM<>?\0*\0 ";
+
+/// Bytes do controlador que não são teclas (erro, ACK, echo, resend)
+fn is_control_byte(byte: u8) -> bool {
+    matches!(byte, 0x00 | 0xFA | 0xEE | 0xFE | 0xFF)
+}
+
+/// Caractere de `keycode` com os modificadores `mods`
+fn translate(keycode: KeyCode, mods: Modifiers) -> Option<u8> {
+    let base = match keycode {
+        keys::KEYPAD_ENTER => b'\n',
+        keys::KEYPAD_SLASH => b'/',
+        code if (code as usize) < NORMAL.len() => {
+            let table = if mods.shift() { SHIFTED } else { NORMAL };
+            table[code as usize]
+        }
+        _ => 0,
+    };
+    if base == 0 {
+        return None;
+    }
+    if !base.is_ascii_alphabetic() {
+        return Some(base);
+    }
+
+    let letter = if mods.caps_lock() {
+        // Caps Lock inverte o efeito do shift nas letras
+        base ^ 0x20
+    } else {
+        base
+    };
+    if mods.ctrl() {
+        return Some(letter & 0x1F);
+    }
+    Some(letter)
+}
+
+/// Máquina de estados do teclado: prefixos e modificadores
+pub struct Decoder {
+    /// Último byte foi `E0`
+    extended: bool,
+    /// Bytes restantes de uma sequência `E1` (Pause)
+    skip: u8,
+    modifiers: Modifiers,
+    /// Caps Lock está pressionada (o auto-repeat não alterna de novo)
+    caps_held: bool,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Self {
+            extended: false,
+            skip: 0,
+            modifiers: Modifiers::empty(),
+            caps_held: false,
+        }
+    }
+
+    /// Modificadores ativos
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Consome um byte do teclado; retorna o evento quando ele fecha uma tecla
+    pub fn feed(&mut self, byte: u8) -> Option<KeyEvent> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+        match byte {
+            0xE0 => {
+                self.extended = true;
+                return None;
+            }
+            0xE1 => {
+                self.skip = 5;
+                return None;
+            }
+            byte if is_control_byte(byte) => return None,
+            _ => {}
+        }
+
+        let extended = core::mem::take(&mut self.extended);
+        let pressed = byte & 0x80 == 0;
+        let code = (byte & 0x7F) as KeyCode;
+        if extended && (code == keys::LEFT_SHIFT || code == keys::RIGHT_SHIFT) {
+            return None;
+        }
+        let keycode = if extended { 0x100 | code } else { code };
+
+        let modifier = match keycode {
+            keys::LEFT_SHIFT => Some(Modifiers::LEFT_SHIFT),
+            keys::RIGHT_SHIFT => Some(Modifiers::RIGHT_SHIFT),
+            keys::LEFT_CTRL => Some(Modifiers::LEFT_CTRL),
+            keys::RIGHT_CTRL => Some(Modifiers::RIGHT_CTRL),
+            keys::LEFT_ALT => Some(Modifiers::LEFT_ALT),
+            keys::RIGHT_ALT => Some(Modifiers::RIGHT_ALT),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            if pressed {
+                self.modifiers.insert(modifier);
+            } else {
+                self.modifiers.remove(modifier);
+            }
+        }
+        if keycode == keys::CAPS_LOCK {
+            if pressed && !self.caps_held {
+                self.modifiers.toggle(Modifiers::CAPS_LOCK);
+            }
+            self.caps_held = pressed;
+        }
+
+        let ascii = if pressed && modifier.is_none() {
+            translate(keycode, self.modifiers)
+        } else {
+            None
+        };
+        Some(KeyEvent {
+            keycode,
+            pressed,
+            ascii,
+            modifiers: self.modifiers,
+        })
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Caracteres gerados por uma sequência de scancodes
+    fn typed(decoder: &mut Decoder, bytes: &[u8]) -> Vec<u8> {
+        bytes
+            .iter()
+            .filter_map(|&b| decoder.feed(b))
+            .filter_map(|e| e.ascii)
+            .collect()
+    }
+
+    #[test]
+    fn test_shift_and_release() {
+        let mut decoder = Decoder::new();
+        // "Hi!\n": LShift+H, i, RShift+1, Enter
+        let bytes = [
+            0x2A, 0x23, 0xA3, 0xAA, // H
+            0x17, 0x97, // i
+            0x36, 0x02, 0x82, 0xB6, // !
+            0x1C, 0x9C, // \n
+        ];
+        assert_eq!(typed(&mut decoder, &bytes), b"Hi!\n");
+        assert_eq!(decoder.modifiers(), Modifiers::empty());
+
+        // Soltar um shift com o outro pressionado mantém o shift
+        let bytes = [0x2A, 0x36, 0xAA, 0x1E, 0x9E, 0xB6, 0x1E];
+        assert_eq!(typed(&mut decoder, &bytes), b"Aa");
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut decoder = Decoder::new();
+        // Caps Lock com auto-repeat (dois makes) alterna uma vez só
+        let bytes = [0x3A, 0x3A, 0xBA, 0x10, 0x90, 0x02, 0x82];
+        assert_eq!(typed(&mut decoder, &bytes), b"Q1");
+        assert!(decoder.modifiers().caps_lock());
+
+        // Caps Lock + shift: letra minúscula, símbolo com shift
+        let bytes = [0x2A, 0x10, 0x90, 0x02, 0x82, 0xAA];
+        assert_eq!(typed(&mut decoder, &bytes), b"q!");
+
+        // Segundo toque desliga
+        typed(&mut decoder, &[0x3A, 0xBA]);
+        assert!(!decoder.modifiers().caps_lock());
+        assert_eq!(typed(&mut decoder, &[0x10]), b"q");
+    }
+
+    #[test]
+    fn test_extended_and_ctrl() {
+        let mut decoder = Decoder::new();
+
+        // Seta para cima: E0 48 / E0 C8, sem caractere
+        assert!(decoder.feed(0xE0).is_none());
+        let up = decoder.feed(0x48).unwrap();
+        assert_eq!(up.keycode, keys::UP);
+        assert!(up.pressed && up.ascii.is_none());
+        decoder.feed(0xE0);
+        let released = decoder.feed(0xC8).unwrap();
+        assert_eq!(released.keycode, keys::UP);
+        assert!(!released.pressed);
+
+        // Shift falso em volta de PrintScreen não mexe nos modificadores
+        for b in [0xE0, 0x2A, 0xE0, 0x37, 0xE0, 0xB7, 0xE0, 0xAA] {
+            decoder.feed(b);
+        }
+        assert_eq!(decoder.modifiers(), Modifiers::empty());
+
+        // Pause (E1 ...) é descartado inteiro
+        let pause = [0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5];
+        assert!(pause.iter().all(|&b| decoder.feed(b).is_none()));
+
+        // Ctrl direito + C = 0x03; Enter do keypad
+        let bytes = [0xE0, 0x1D, 0x2E, 0xAE, 0xE0, 0x9D, 0xE0, 0x1C];
+        assert_eq!(typed(&mut decoder, &bytes), b"\x03\n");
+
+        // ACK e erros do controlador não viram teclas
+        assert!(decoder.feed(0xFA).is_none());
+        assert!(decoder.feed(0x00).is_none());
+    }
+
+    #[test]
+    fn test_event_bytes() {
+        let mut decoder = Decoder::new();
+        decoder.feed(0x2A);
+        let event = decoder.feed(0x1E).unwrap();
+        assert_eq!(event.to_bytes(), [0x1E, 0x00, 0b0000_0011, b'A']);
+        assert_eq!(event.scancode(), 0x1E);
+
+        decoder.feed(0xE0);
+        let event = decoder.feed(0xD3).unwrap();
+        assert_eq!(event.keycode, keys::DELETE);
+        assert_eq!(event.to_bytes(), [0x53, 0x01, 0b0000_0010, 0]);
+    }
+}
@@ -25,7 +25,7 @@ pub fn init() {
     serial::init();
 
     // 3. Inicializar Timers
-    timer::init_pit(1000); // 1000 Hz
+    timer::init_pit(crate::core::time::HZ as u32);
 
     // 4. Inicializar Vídeo
     // (Video é inicializado no kernel_main com BootInfo)
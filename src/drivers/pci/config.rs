@@ -18,6 +18,9 @@
 //! │  1b   │  7b   │  8b   │ 5b │ 3b │6b │
 //! └───────┴───────┴───────┴────┴───────┘
 //! ```
+//!
+//! A enumeração acessa o espaço de configuração por [`ConfigAccess`], para
+//! poder ser exercitada com um espaço simulado; [`PortIo`] é o acesso real.
 
 use core::arch::asm;
 
@@ -109,3 +112,116 @@ pub fn write_config_word(bus: u8, device: u8, function: u8, offset: u8, value: u
     let new_value = (current & !mask) | ((value as u32) << shift);
     write_config(bus, device, function, offset, new_value);
 }
+
+// =============================================================================
+// ABSTRAÇÃO DE ACESSO
+// =============================================================================
+
+/// Acesso ao espaço de configuração (registros de 32 bits alinhados)
+pub trait ConfigAccess {
+    fn read(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32;
+
+    fn write(&self, bus: u8, device: u8, function: u8, offset: u8, value: u32);
+
+    fn read_word(&self, bus: u8, device: u8, function: u8, offset: u8) -> u16 {
+        let value = self.read(bus, device, function, offset);
+        ((value >> ((offset & 2) * 8)) & 0xFFFF) as u16
+    }
+
+    fn read_byte(&self, bus: u8, device: u8, function: u8, offset: u8) -> u8 {
+        let value = self.read(bus, device, function, offset);
+        ((value >> ((offset & 3) * 8)) & 0xFF) as u8
+    }
+
+    fn write_word(&self, bus: u8, device: u8, function: u8, offset: u8, value: u16) {
+        let current = self.read(bus, device, function, offset);
+        let shift = (offset & 2) * 8;
+        let mask = 0xFFFF << shift;
+        let new_value = (current & !mask) | ((value as u32) << shift);
+        self.write(bus, device, function, offset, new_value);
+    }
+}
+
+/// Espaço de configuração real, pelas portas 0xCF8/0xCFC
+pub struct PortIo;
+
+impl ConfigAccess for PortIo {
+    fn read(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+        read_config(bus, device, function, offset)
+    }
+
+    fn write(&self, bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+        write_config(bus, device, function, offset, value);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+/// Espaço de configuração simulado para os testes da enumeração
+#[cfg(test)]
+pub mod mock {
+    use super::ConfigAccess;
+    use alloc::collections::BTreeMap;
+    use core::cell::RefCell;
+
+    /// Função simulada: 64 registros e, por BAR, os bits graváveis
+    pub struct MockFunction {
+        regs: [u32; 64],
+        bar_masks: [u32; 6],
+    }
+
+    /// Espaço de configuração simulado, endereçado por (bus, dev, func)
+    #[derive(Default)]
+    pub struct MockConfig {
+        functions: RefCell<BTreeMap<(u8, u8, u8), MockFunction>>,
+    }
+
+    impl MockConfig {
+        pub fn add(&self, bdf: (u8, u8, u8), id: u32, class: u32, header: u8, bars: &[(u32, u32)]) {
+            let mut function = MockFunction {
+                regs: [0; 64],
+                bar_masks: [0; 6],
+            };
+            function.regs[0] = id;
+            function.regs[2] = class << 8;
+            function.regs[3] = (header as u32) << 16;
+            for (i, &(value, mask)) in bars.iter().enumerate() {
+                function.regs[4 + i] = value;
+                function.bar_masks[i] = mask;
+            }
+            self.functions.borrow_mut().insert(bdf, function);
+        }
+
+        pub fn set(&self, bdf: (u8, u8, u8), offset: u8, value: u32) {
+            self.functions.borrow_mut().get_mut(&bdf).unwrap().regs[offset as usize / 4] = value;
+        }
+
+        pub fn reg(&self, bdf: (u8, u8, u8), offset: u8) -> u32 {
+            self.functions.borrow()[&bdf].regs[offset as usize / 4]
+        }
+    }
+
+    impl ConfigAccess for MockConfig {
+        fn read(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+            self.functions
+                .borrow()
+                .get(&(bus, device, function))
+                .map_or(0xFFFF_FFFF, |f| f.regs[offset as usize / 4])
+        }
+
+        fn write(&self, bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+            let mut functions = self.functions.borrow_mut();
+            let Some(f) = functions.get_mut(&(bus, device, function)) else {
+                return;
+            };
+            let index = offset as usize / 4;
+            f.regs[index] = match index.checked_sub(4).and_then(|bar| f.bar_masks.get(bar)) {
+                // BAR: só os bits de endereço implementados aceitam escrita
+                Some(&mask) => (value & mask) | (f.regs[index] & !mask),
+                None => value,
+            };
+        }
+    }
+}
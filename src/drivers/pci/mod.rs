@@ -5,8 +5,10 @@
 //! ## Funcionalidades
 //!
 //! - Acesso ao espaço de configuração PCI
-//! - Enumeração de dispositivos
-//! - Detecção de VirtIO devices
+//! - Enumeração de dispositivos (inclusive multi-função)
+//! - Decodificação e medição dos BARs
+//! - Busca por classe, fabricante ou Vendor/Device ID
+//! - Interrupções MSI-X ([`enable_msix`])
 //!
 //! ## Uso
 //!
@@ -18,12 +20,21 @@
 //! if let Some(dev) = pci::find_virtio_blk() {
 //!     println!("VirtIO Block encontrado!");
 //! }
+//!
+//! // Controladoras NVMe (classe 0x01, subclasse 0x08), sem novo scan
+//! for dev in pci::find_by_class(0x01, 0x08) {
+//!     let regs = dev.bar_address(0);
+//! }
 //! ```
 
 pub mod config;
+pub mod msix;
 pub mod pci;
 
+pub use config::{ConfigAccess, PortIo};
+pub use msix::enable_msix;
 pub use pci::{
-    all_devices, find_device, find_virtio_blk, scan, PciDevice, DEVICE_VIRTIO_BLK,
+    all_devices, enumerate, find_by_class, find_by_vendor, find_capability, find_device,
+    find_virtio_blk, scan, Bar, PciDevice, PciError, CAP_MSI, CAP_MSIX, DEVICE_VIRTIO_BLK,
     DEVICE_VIRTIO_NET, VENDOR_REDHAT,
 };
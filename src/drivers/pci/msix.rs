@@ -0,0 +1,226 @@
+//! # MSI-X
+//!
+//! Interrupções sinalizadas por mensagem: o dispositivo escreve `data` no
+//! endereço `0xFEE0_0000 | apic_id << 12` e o LAPIC entrega o vetor, sem
+//! passar pelo PIC/IOAPIC. Cada entrada da tabela tem seu vetor, o que
+//! permite uma interrupção por fila (virtio, NVMe).
+//!
+//! ## Capability (ID 0x11)
+//!
+//! | Offset | Tamanho | Descrição                                   |
+//! |--------|---------|---------------------------------------------|
+//! | +0x02  | 2       | Message Control (tamanho, mask, enable)     |
+//! | +0x04  | 4       | Table Offset (bits 31:3) / BIR (bits 2:0)   |
+//! | +0x08  | 4       | PBA Offset / BIR                            |
+//!
+//! Cada entrada da tabela (no BAR indicado pelo BIR) tem 16 bytes: endereço
+//! baixo, endereço alto, dados e controle (bit 0 = mascarada).
+
+use super::config::{ConfigAccess, PortIo};
+use super::pci::{PciDevice, PciError, CAP_MSIX};
+use crate::arch::x86_64::apic::lapic;
+use crate::arch::x86_64::idt;
+use alloc::vec::Vec;
+
+/// Message Control: tamanho da tabela - 1
+const CONTROL_TABLE_SIZE: u16 = 0x07FF;
+/// Message Control: mascara todas as entradas
+const CONTROL_FUNCTION_MASK: u16 = 1 << 14;
+/// Message Control: MSI-X habilitado
+const CONTROL_ENABLE: u16 = 1 << 15;
+
+/// Offset do registro Command
+const REG_COMMAND: u8 = 0x04;
+
+/// Command: desliga INTx
+const COMMAND_INTX_DISABLE: u16 = 1 << 10;
+
+/// Bytes por entrada da tabela
+const ENTRY_SIZE: u64 = 16;
+
+/// Vector Control: entrada mascarada
+const ENTRY_MASKED: u32 = 1;
+
+/// Janela de endereços de mensagem do LAPIC
+const MSI_ADDRESS_BASE: u32 = 0xFEE0_0000;
+
+/// Endereço e dado de uma mensagem fixa, por borda, para `apic_id`
+fn msi_message(apic_id: u32, vector: u8) -> (u32, u32) {
+    (MSI_ADDRESS_BASE | (apic_id & 0xFF) << 12, vector as u32)
+}
+
+/// Localização da tabela MSI-X de um dispositivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MsixTable {
+    /// Offset da capability no espaço de configuração
+    cap: u8,
+    /// Número de entradas
+    size: u16,
+    /// Endereço físico da primeira entrada
+    phys: u64,
+}
+
+/// Lê a capability MSI-X e resolve o BAR da tabela
+fn locate_table(cfg: &impl ConfigAccess, dev: &PciDevice) -> Result<MsixTable, PciError> {
+    let (bus, device, function) = (dev.bus, dev.device, dev.function);
+    let cap = super::pci::find_capability(cfg, bus, device, function, CAP_MSIX)
+        .ok_or(PciError::CapabilityNotFound)?;
+
+    let control = cfg.read_word(bus, device, function, cap + 2);
+    let table = cfg.read(bus, device, function, cap + 4);
+
+    let bar = dev
+        .bar((table & 0x7) as usize)
+        .ok_or(PciError::InvalidBar)?;
+    if bar.is_io {
+        return Err(PciError::InvalidBar);
+    }
+
+    Ok(MsixTable {
+        cap,
+        size: (control & CONTROL_TABLE_SIZE) + 1,
+        phys: bar.address + (table & !0x7) as u64,
+    })
+}
+
+/// Habilita MSI-X com `vectors` entradas apontando para vetores novos da IDT.
+///
+/// As entradas `0..vectors` são programadas para o LAPIC da CPU atual e
+/// desmascaradas; as demais ficam mascaradas. Os vetores retornados (na
+/// ordem das entradas) chegam com um handler padrão: instale o do driver com
+/// `idt::install_handler`. Sem capability MSI-X, retorna
+/// [`PciError::CapabilityNotFound`] e o chamador segue com INTx.
+pub fn enable_msix(dev: &PciDevice, vectors: u8) -> Result<Vec<u8>, PciError> {
+    let cfg = PortIo;
+    let table = locate_table(&cfg, dev)?;
+    if vectors == 0 || vectors as u16 > table.size {
+        return Err(PciError::InvalidVectorCount);
+    }
+    let allocated = idt::alloc_vectors(vectors as usize).ok_or(PciError::NoFreeVectors)?;
+
+    let (bus, device, function) = (dev.bus, dev.device, dev.function);
+    let control_reg = table.cap + 2;
+    let control = cfg.read_word(bus, device, function, control_reg);
+
+    // A tabela é MMIO e as mensagens são escritas do dispositivo (bus master)
+    dev.enable_memory_space();
+    dev.enable_bus_master();
+
+    // Habilitar com tudo mascarado enquanto a tabela é preenchida
+    cfg.write_word(
+        bus,
+        device,
+        function,
+        control_reg,
+        control | CONTROL_ENABLE | CONTROL_FUNCTION_MASK,
+    );
+
+    let base = unsafe { crate::mm::addr::phys_to_virt::<u32>(table.phys) };
+    let apic_id = lapic::id();
+    for entry in 0..table.size as usize {
+        let words = unsafe { base.add(entry * (ENTRY_SIZE as usize / 4)) };
+        unsafe {
+            match allocated.get(entry) {
+                Some(&vector) => {
+                    let (address, data) = msi_message(apic_id, vector);
+                    core::ptr::write_volatile(words, address);
+                    core::ptr::write_volatile(words.add(1), 0);
+                    core::ptr::write_volatile(words.add(2), data);
+                    core::ptr::write_volatile(words.add(3), 0);
+                }
+                None => core::ptr::write_volatile(words.add(3), ENTRY_MASKED),
+            }
+        }
+    }
+
+    // INTx desligado: o dispositivo passa a sinalizar só por mensagem
+    let command = cfg.read_word(bus, device, function, REG_COMMAND);
+    cfg.write_word(bus, device, function, REG_COMMAND, command | COMMAND_INTX_DISABLE);
+
+    cfg.write_word(
+        bus,
+        device,
+        function,
+        control_reg,
+        (control | CONTROL_ENABLE) & !CONTROL_FUNCTION_MASK,
+    );
+
+    crate::kinfo!("(PCI) MSI-X habilitado, vetores:", allocated.len() as u64);
+    Ok(allocated)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::mock::MockConfig;
+    use super::super::pci::enumerate;
+    use super::*;
+
+    /// Dispositivo com capabilities MSI (0x40) -> MSI-X (0x50), tabela de 8
+    /// entradas no BAR2 a +0x2000
+    fn mock_msix_device() -> MockConfig {
+        let cfg = MockConfig::default();
+        cfg.add(
+            (0, 4, 0),
+            0x1041_1AF4,
+            0x01_00_00,
+            0x00,
+            &[
+                (0xC081, 0xFFFF_FFC0),
+                (0xFEB0_0000, 0xFFFF_F000),
+                (0xFE00_000C, 0xFFFF_C000),
+                (0, 0xFFFF_FFFF),
+            ],
+        );
+        cfg.set((0, 4, 0), 0x04, 0x0010 << 16);
+        cfg.set((0, 4, 0), 0x34, 0x40);
+        cfg.set((0, 4, 0), 0x40, 0x0000_5005);
+        cfg.set((0, 4, 0), 0x50, 0x0007_0011);
+        cfg.set((0, 4, 0), 0x54, 0x0000_2002);
+        cfg
+    }
+
+    #[test]
+    fn test_locate_table() {
+        let cfg = mock_msix_device();
+        let dev = &enumerate(&cfg)[0];
+        assert_eq!(
+            locate_table(&cfg, dev),
+            Ok(MsixTable {
+                cap: 0x50,
+                size: 8,
+                phys: 0xFE00_2000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_capability() {
+        let cfg = mock_msix_device();
+        // Lista termina no MSI
+        cfg.set((0, 4, 0), 0x40, 0x0000_0005);
+        let dev = &enumerate(&cfg)[0];
+        assert_eq!(locate_table(&cfg, dev), Err(PciError::CapabilityNotFound));
+
+        // Sem lista de capabilities no Status
+        cfg.set((0, 4, 0), 0x04, 0);
+        assert_eq!(locate_table(&cfg, dev), Err(PciError::CapabilityNotFound));
+    }
+
+    #[test]
+    fn test_table_in_io_bar() {
+        let cfg = mock_msix_device();
+        cfg.set((0, 4, 0), 0x54, 0x0000_2000);
+        let dev = &enumerate(&cfg)[0];
+        assert_eq!(locate_table(&cfg, dev), Err(PciError::InvalidBar));
+    }
+
+    #[test]
+    fn test_msi_message() {
+        assert_eq!(msi_message(0, 0x50), (0xFEE0_0000, 0x50));
+        assert_eq!(msi_message(3, 0x61), (0xFEE0_3000, 0x61));
+    }
+}
@@ -15,8 +15,15 @@
 //! | 0x0E   | 1       | Header Type         |
 //! | 0x10   | 4       | BAR0                |
 //! | ...    | ...     | ...                 |
-
-use super::config;
+//!
+//! ## BARs
+//! O tamanho de cada BAR é medido escrevendo `0xFFFF_FFFF` e lendo de volta
+//! os bits de endereço que o dispositivo deixa gravar (com a decodificação
+//! desligada no Command); o valor original é restaurado em seguida. BARs de
+//! 64 bits ocupam dois registros: o segundo fica como `None` em
+//! [`PciDevice::bars`].
+
+use super::config::{self, ConfigAccess, PortIo};
 use crate::sync::Spinlock;
 use alloc::vec::Vec;
 
@@ -35,6 +42,62 @@ pub const DEVICE_VIRTIO_NET: u16 = 0x1000;
 /// Device ID do VirtIO GPU
 pub const DEVICE_VIRTIO_GPU: u16 = 0x1050;
 
+/// Offset do registro Command
+const REG_COMMAND: u8 = 0x04;
+
+/// Offset do BAR0
+const REG_BAR0: u8 = 0x10;
+
+/// Command: I/O Space e Memory Space Enable
+const COMMAND_DECODE: u16 = 0x03;
+
+/// Header type: bit de dispositivo multi-função
+const HEADER_MULTIFUNCTION: u8 = 0x80;
+
+/// Offset do registro Status
+const REG_STATUS: u8 = 0x06;
+
+/// Status: há lista de capabilities
+const STATUS_CAP_LIST: u16 = 1 << 4;
+
+/// Offset do ponteiro para a primeira capability
+const REG_CAP_PTR: u8 = 0x34;
+
+/// Limite de capabilities percorridas (protege contra listas em ciclo)
+const MAX_CAPABILITIES: usize = 48;
+
+/// ID da capability MSI
+pub const CAP_MSI: u8 = 0x05;
+
+/// ID da capability MSI-X
+pub const CAP_MSIX: u8 = 0x11;
+
+/// Erros do subsistema PCI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PciError {
+    /// O dispositivo não tem a capability pedida (p.ex. MSI-X)
+    CapabilityNotFound,
+    /// Número de vetores zero ou maior que a tabela do dispositivo
+    InvalidVectorCount,
+    /// BAR indicado pela capability não existe ou não é de memória
+    InvalidBar,
+    /// Sem vetores livres na IDT
+    NoFreeVectors,
+}
+
+/// Base Address Register decodificado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bar {
+    /// Endereço base (porta, para BARs de I/O)
+    pub address: u64,
+    /// Tamanho da região em bytes
+    pub size: u64,
+    /// Espaço de I/O (portas) em vez de memória
+    pub is_io: bool,
+    /// Memória prefetchable
+    pub is_prefetchable: bool,
+}
+
 /// Informações de um dispositivo PCI
 #[derive(Debug, Clone)]
 pub struct PciDevice {
@@ -58,32 +121,40 @@ pub struct PciDevice {
     pub revision: u8,
     /// Header type
     pub header_type: u8,
-    /// Base Address Registers
-    pub bars: [u32; 6],
+    /// Base Address Registers (`None`: não implementado, ou metade alta de
+    /// um BAR de 64 bits)
+    pub bars: [Option<Bar>; 6],
 }
 
 impl PciDevice {
     /// Lê as informações de um dispositivo PCI
     pub fn read(bus: u8, device: u8, function: u8) -> Option<Self> {
-        let vendor_id = config::read_config_word(bus, device, function, 0x00);
+        Self::probe(&PortIo, bus, device, function)
+    }
+
+    /// Lê as informações de um dispositivo por um acesso de configuração
+    pub fn probe(cfg: &impl ConfigAccess, bus: u8, device: u8, function: u8) -> Option<Self> {
+        let vendor_id = cfg.read_word(bus, device, function, 0x00);
 
         // Dispositivo não existe
         if vendor_id == VENDOR_INVALID {
             return None;
         }
 
-        let device_id = config::read_config_word(bus, device, function, 0x02);
-        let revision = config::read_config_byte(bus, device, function, 0x08);
-        let prog_if = config::read_config_byte(bus, device, function, 0x09);
-        let subclass = config::read_config_byte(bus, device, function, 0x0A);
-        let class_code = config::read_config_byte(bus, device, function, 0x0B);
-        let header_type = config::read_config_byte(bus, device, function, 0x0E);
-
-        // Ler BARs
-        let mut bars = [0u32; 6];
-        for i in 0..6 {
-            bars[i] = config::read_config(bus, device, function, 0x10 + (i as u8 * 4));
-        }
+        let device_id = cfg.read_word(bus, device, function, 0x02);
+        let revision = cfg.read_byte(bus, device, function, 0x08);
+        let prog_if = cfg.read_byte(bus, device, function, 0x09);
+        let subclass = cfg.read_byte(bus, device, function, 0x0A);
+        let class_code = cfg.read_byte(bus, device, function, 0x0B);
+        let header_type = cfg.read_byte(bus, device, function, 0x0E);
+
+        // Header 0 (endpoint) tem 6 BARs; header 1 (bridge PCI-PCI), 2
+        let bar_count = match header_type & !HEADER_MULTIFUNCTION {
+            0 => 6,
+            1 => 2,
+            _ => 0,
+        };
+        let bars = read_bars(cfg, bus, device, function, bar_count);
 
         Some(Self {
             bus,
@@ -110,6 +181,16 @@ impl PciDevice {
         self.vendor_id == VENDOR_REDHAT && self.device_id == DEVICE_VIRTIO_BLK
     }
 
+    /// Função 0 de um dispositivo com várias funções
+    pub fn is_multifunction(&self) -> bool {
+        self.header_type & HEADER_MULTIFUNCTION != 0
+    }
+
+    /// Offset da capability `id` no espaço de configuração
+    pub fn capability(&self, id: u8) -> Option<u8> {
+        find_capability(&PortIo, self.bus, self.device, self.function, id)
+    }
+
     /// Habilita Bus Mastering (necessário para DMA)
     pub fn enable_bus_master(&self) {
         let command = config::read_config_word(self.bus, self.device, self.function, 0x04);
@@ -124,84 +205,187 @@ impl PciDevice {
         config::write_config_word(self.bus, self.device, self.function, 0x04, command | 0x02);
     }
 
+    /// Habilita I/O Space Access
+    pub fn enable_io_space(&self) {
+        let command = config::read_config_word(self.bus, self.device, self.function, 0x04);
+        // Bit 0 = I/O Space Enable
+        config::write_config_word(self.bus, self.device, self.function, 0x04, command | 0x01);
+    }
+
+    /// BAR decodificado de índice `bar`
+    pub fn bar(&self, bar: usize) -> Option<Bar> {
+        *self.bars.get(bar)?
+    }
+
+    /// Obtém a porta base de um BAR de I/O
+    pub fn io_bar(&self, bar: usize) -> Option<u16> {
+        let bar = self.bar(bar)?;
+        bar.is_io.then_some(bar.address as u16)
+    }
+
     /// Obtém o endereço base de um BAR (Memory-mapped)
     pub fn bar_address(&self, bar: usize) -> Option<u64> {
-        if bar >= 6 {
-            return None;
-        }
+        let bar = self.bar(bar)?;
+        (!bar.is_io).then_some(bar.address)
+    }
+}
 
-        let value = self.bars[bar];
+/// Percorre a lista de capabilities de uma função atrás de `id`
+pub fn find_capability(
+    cfg: &impl ConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+    id: u8,
+) -> Option<u8> {
+    if cfg.read_word(bus, device, function, REG_STATUS) & STATUS_CAP_LIST == 0 {
+        return None;
+    }
 
-        // Bit 0 = 0 indica Memory BAR
-        if value & 1 != 0 {
-            return None; // É I/O BAR
+    let mut ptr = cfg.read_byte(bus, device, function, REG_CAP_PTR) & 0xFC;
+    for _ in 0..MAX_CAPABILITIES {
+        if ptr == 0 {
+            return None;
         }
+        if cfg.read_byte(bus, device, function, ptr) == id {
+            return Some(ptr);
+        }
+        ptr = cfg.read_byte(bus, device, function, ptr + 1) & 0xFC;
+    }
+    None
+}
 
-        // Bits 2:1 indicam tipo
-        let bar_type = (value >> 1) & 0x3;
+/// Lê e mede os primeiros `count` BARs de uma função
+fn read_bars(
+    cfg: &impl ConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+    count: usize,
+) -> [Option<Bar>; 6] {
+    let mut bars = [None; 6];
+    if count == 0 {
+        return bars;
+    }
 
-        match bar_type {
-            0 => {
-                // 32-bit address
-                Some((value & 0xFFFF_FFF0) as u64)
-            }
-            2 => {
-                // 64-bit address
-                if bar + 1 >= 6 {
-                    return None;
+    // Sem decodificação enquanto os BARs contêm 0xFFFF_FFFF
+    let command = cfg.read_word(bus, device, function, REG_COMMAND);
+    cfg.write_word(
+        bus,
+        device,
+        function,
+        REG_COMMAND,
+        command & !COMMAND_DECODE,
+    );
+
+    // Valor original e bits graváveis de um registro de BAR
+    let probe = |index: usize| {
+        let offset = REG_BAR0 + (index as u8) * 4;
+        let original = cfg.read(bus, device, function, offset);
+        cfg.write(bus, device, function, offset, 0xFFFF_FFFF);
+        let mask = cfg.read(bus, device, function, offset);
+        cfg.write(bus, device, function, offset, original);
+        (original, mask)
+    };
+
+    let mut index = 0;
+    while index < count {
+        let (low, low_mask) = probe(index);
+
+        if low & 1 != 0 {
+            // I/O: bits 31:2. Os 16 bits altos podem não ser implementados.
+            let mut mask = low_mask & !0x3;
+            if mask != 0 {
+                if mask & 0xFFFF_0000 == 0 {
+                    mask |= 0xFFFF_0000;
                 }
-                let high = self.bars[bar + 1] as u64;
-                Some(((high << 32) | (value & 0xFFFF_FFF0) as u64))
+                bars[index] = Some(Bar {
+                    address: (low & !0x3) as u64,
+                    size: (!mask).wrapping_add(1) as u64,
+                    is_io: true,
+                    is_prefetchable: false,
+                });
             }
-            _ => None,
+            index += 1;
+            continue;
+        }
+
+        // Memória: bits 2:1 = tipo (2 = 64 bits), bit 3 = prefetchable
+        let is_64bit = (low >> 1) & 0x3 == 2 && index + 1 < count;
+        let (high, high_mask) = if is_64bit {
+            probe(index + 1)
+        } else {
+            (0, 0xFFFF_FFFF)
+        };
+
+        let mask = ((high_mask as u64) << 32) | (low_mask & !0xF) as u64;
+        if low_mask & !0xF != 0 || (is_64bit && high_mask != 0) {
+            bars[index] = Some(Bar {
+                address: ((high as u64) << 32) | (low & !0xF) as u64,
+                size: (!mask).wrapping_add(1),
+                is_io: false,
+                is_prefetchable: low & 0x8 != 0,
+            });
         }
+        index += if is_64bit { 2 } else { 1 };
     }
+
+    cfg.write_word(bus, device, function, REG_COMMAND, command);
+    bars
 }
 
 /// Lista global de dispositivos PCI detectados
 static PCI_DEVICES: Spinlock<Vec<PciDevice>> = Spinlock::new(Vec::new());
 
-/// Escaneia o barramento PCI e detecta todos os dispositivos
-pub fn scan() {
-    crate::kinfo!("(PCI) Escaneando barramento...");
-
-    let mut devices = PCI_DEVICES.lock();
-    devices.clear();
-
-    let mut count = 0;
+/// Enumera todos os dispositivos visíveis por `cfg`
+pub fn enumerate(cfg: &impl ConfigAccess) -> Vec<PciDevice> {
+    let mut devices = Vec::new();
 
     // Escanear todos os barramentos (0-255)
     // Na prática, a maioria dos sistemas só usa o barramento 0
     for bus in 0..=255u8 {
         for device in 0..32u8 {
             // Verificar função 0
-            if let Some(dev) = PciDevice::read(bus, device, 0) {
-                let is_multi = (dev.header_type & 0x80) != 0;
-
-                log_device(&dev);
-                devices.push(dev);
-                count += 1;
-
-                // Se for multi-função, verificar funções 1-7
-                if is_multi {
-                    for function in 1..8u8 {
-                        if let Some(dev) = PciDevice::read(bus, device, function) {
-                            log_device(&dev);
-                            devices.push(dev);
-                            count += 1;
-                        }
+            let Some(dev) = PciDevice::probe(cfg, bus, device, 0) else {
+                continue;
+            };
+            let is_multi = dev.is_multifunction();
+            devices.push(dev);
+
+            // Só dispositivos multi-função respondem nas funções 1-7; nos
+            // demais, elas podem espelhar a função 0
+            if is_multi {
+                for function in 1..8u8 {
+                    if let Some(dev) = PciDevice::probe(cfg, bus, device, function) {
+                        devices.push(dev);
                     }
                 }
             }
         }
 
         // Otimização: se o barramento 0 não tiver dispositivos, provavelmente não há mais
-        if bus == 0 && count == 0 {
+        if bus == 0 && devices.is_empty() {
             break;
         }
     }
 
-    crate::kinfo!("(PCI) Dispositivos encontrados:", count as u64);
+    devices
+}
+
+/// Escaneia o barramento PCI e detecta todos os dispositivos.
+///
+/// A lista fica guardada para as buscas (`find_*`), sem novo scan.
+pub fn scan() -> Vec<PciDevice> {
+    crate::kinfo!("(PCI) Escaneando barramento...");
+
+    let devices = enumerate(&PortIo);
+    for dev in &devices {
+        log_device(dev);
+    }
+    PCI_DEVICES.lock().clone_from(&devices);
+
+    crate::kinfo!("(PCI) Dispositivos encontrados:", devices.len() as u64);
+    devices
 }
 
 /// Loga informações de um dispositivo
@@ -212,6 +396,8 @@ fn log_device(dev: &PciDevice) {
     crate::kdebug!("  Function:", dev.function as u64);
     crate::kdebug!("  Vendor:", dev.vendor_id as u64);
     crate::kdebug!("  DeviceID:", dev.device_id as u64);
+    crate::kdebug!("  Class:", dev.class_code as u64);
+    crate::kdebug!("  Subclass:", dev.subclass as u64);
 
     if dev.is_virtio_blk() {
         crate::kinfo!("(PCI) VirtIO Block detectado!");
@@ -227,6 +413,26 @@ pub fn find_device(vendor_id: u16, device_id: u16) -> Option<PciDevice> {
         .cloned()
 }
 
+/// Dispositivos de uma classe/subclasse (p.ex. 0x01/0x08 = NVMe)
+pub fn find_by_class(class_code: u8, subclass: u8) -> Vec<PciDevice> {
+    let devices = PCI_DEVICES.lock();
+    devices
+        .iter()
+        .filter(|d| d.class_code == class_code && d.subclass == subclass)
+        .cloned()
+        .collect()
+}
+
+/// Dispositivos de um fabricante
+pub fn find_by_vendor(vendor_id: u16) -> Vec<PciDevice> {
+    let devices = PCI_DEVICES.lock();
+    devices
+        .iter()
+        .filter(|d| d.vendor_id == vendor_id)
+        .cloned()
+        .collect()
+}
+
 /// Procura um dispositivo VirtIO Block
 pub fn find_virtio_blk() -> Option<PciDevice> {
     find_device(VENDOR_REDHAT, DEVICE_VIRTIO_BLK)
@@ -236,3 +442,125 @@ pub fn find_virtio_blk() -> Option<PciDevice> {
 pub fn all_devices() -> Vec<PciDevice> {
     PCI_DEVICES.lock().clone()
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::mock::MockConfig;
+    use super::*;
+
+    fn mock_bus() -> MockConfig {
+        let cfg = MockConfig::default();
+        // Host bridge
+        cfg.add((0, 0, 0), 0x1237_8086, 0x06_00_00, 0x00, &[]);
+        // Multi-função: IDE (BAR0 de I/O com 8 portas, 16 bits) + USB na função 2
+        cfg.add(
+            (0, 1, 0),
+            0x7010_8086,
+            0x01_01_80,
+            0x80,
+            &[(0xC001, 0x0000_FFF8)],
+        );
+        cfg.add(
+            (0, 1, 2),
+            0x7020_8086,
+            0x0C_03_00,
+            0x00,
+            &[(0xC041, 0xFFFF_FFE0)],
+        );
+        // Função única que espelha a função 0 em outra: não deve ser listada
+        cfg.add((0, 2, 0), 0x1111_1234, 0x03_00_00, 0x00, &[]);
+        cfg.add((0, 2, 1), 0x1111_1234, 0x03_00_00, 0x00, &[]);
+        // NVMe: BAR0 64 bits prefetchable de 16 KiB acima de 4 GiB, BAR2 32 bits de 4 KiB
+        cfg.add(
+            (0, 3, 0),
+            0x0010_1B36,
+            0x01_08_02,
+            0x00,
+            &[
+                (0xFEB0_000C, 0xFFFF_C000),
+                (0x0000_0008, 0xFFFF_FFFF),
+                (0xFEBF_0000, 0xFFFF_F000),
+            ],
+        );
+        cfg
+    }
+
+    #[test]
+    fn test_enumerate_functions() {
+        let devices = enumerate(&mock_bus());
+        let found: Vec<_> = devices
+            .iter()
+            .map(|d| (d.bus, d.device, d.function))
+            .collect();
+        assert_eq!(
+            found,
+            [(0, 0, 0), (0, 1, 0), (0, 1, 2), (0, 2, 0), (0, 3, 0)]
+        );
+
+        let ide = &devices[1];
+        assert!(ide.is_multifunction());
+        assert_eq!((ide.vendor_id, ide.device_id), (0x8086, 0x7010));
+        assert_eq!(
+            (ide.class_code, ide.subclass, ide.prog_if),
+            (0x01, 0x01, 0x80)
+        );
+    }
+
+    #[test]
+    fn test_bar_decoding() {
+        let cfg = mock_bus();
+        let devices = enumerate(&cfg);
+
+        let ide = &devices[1];
+        assert_eq!(
+            ide.bar(0),
+            Some(Bar {
+                address: 0xC000,
+                size: 8,
+                is_io: true,
+                is_prefetchable: false,
+            })
+        );
+        assert_eq!(ide.io_bar(0), Some(0xC000));
+        assert_eq!(ide.bar(1), None);
+        assert_eq!(devices[2].bar(0).map(|b| b.size), Some(32));
+
+        let nvme = &devices[4];
+        assert_eq!(
+            nvme.bar(0),
+            Some(Bar {
+                address: 0x8_FEB0_0000,
+                size: 16 * 1024,
+                is_io: false,
+                is_prefetchable: true,
+            })
+        );
+        // Metade alta do BAR de 64 bits
+        assert_eq!(nvme.bar(1), None);
+        assert_eq!(
+            nvme.bar(2),
+            Some(Bar {
+                address: 0xFEBF_0000,
+                size: 4096,
+                is_io: false,
+                is_prefetchable: false,
+            })
+        );
+        assert_eq!(nvme.io_bar(2), None);
+        assert_eq!(nvme.bar_address(2), Some(0xFEBF_0000));
+
+        // O sizing restaura os BARs originais
+        assert_eq!(cfg.reg((0, 3, 0), 0x10), 0xFEB0_000C);
+        assert_eq!(cfg.reg((0, 3, 0), 0x14), 0x0000_0008);
+        assert_eq!(cfg.reg((0, 1, 0), 0x10), 0xC001);
+    }
+
+    #[test]
+    fn test_empty_bus() {
+        assert!(enumerate(&MockConfig::default()).is_empty());
+    }
+}
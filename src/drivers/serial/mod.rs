@@ -2,9 +2,19 @@
 //!
 //! Driver simples para saída serial via porta COM1.
 //! Utilizado como fallback e debug principal.
+//!
+//! ## Recepção
+//! A IRQ 4 (vetor 36) esvazia a FIFO do UART para um anel próprio (`RX`),
+//! separado do anel de transmissão para que leitores não disputem o lock dos
+//! logs. O handler lê enquanto `LINE_STATUS` indicar dado pronto, então nada
+//! fica preso na FIFO entre uma interrupção e outra. Se o anel encher, o byte
+//! mais antigo é descartado e contado, como no lado de transmissão.
 
 use crate::arch::x86_64::ports::{inb, outb};
+use crate::klib::ringbuffer::RingBuffer;
+use crate::klib::string::conv::{u64_to_hex, HEX_MAX_LEN};
 use crate::sync::Spinlock;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 /// Endereço base da porta COM1
 const COM1_PORT: u16 = 0x3F8;
@@ -16,42 +26,124 @@ const LINE_CTRL: u16 = 3;
 const MODEM_CTRL: u16 = 4;
 const LINE_STATUS: u16 = 5;
 
+/// `LINE_STATUS`: há byte recebido em `DATA_REG`
+const LSR_DATA_READY: u8 = 0x01;
+
+/// `INT_ENABLE`: interrupção de dado recebido
+const IER_RX_AVAILABLE: u8 = 0x01;
+
+/// `MODEM_CTRL`: DTR, RTS e OUT2 (habilita a linha de IRQ)
+const MCR_NORMAL: u8 = 0x0B;
+
+/// IRQ da COM1 no PIC
+const COM1_IRQ: u8 = 4;
+
+/// `LINE_CTRL`: acesso aos registradores do divisor (DLAB)
+const LCR_DLAB: u8 = 0x80;
+
+/// Clock base do UART dividido por 16: baud com divisor 1
+const BASE_BAUD: u32 = 115_200;
+
+/// Configuração usada por [`init`]
+const DEFAULT_BAUD: u32 = 115_200;
+
 const SERIAL_BUFFER_SIZE: usize = 16 * 1024; // 16KB
-const SERIAL_BUFFER_MASK: usize = SERIAL_BUFFER_SIZE - 1;
+
+const RX_BUFFER_SIZE: usize = 4 * 1024; // 4KB
+
+// =============================================================================
+// CONFIGURAÇÃO DE LINHA
+// =============================================================================
+
+/// Paridade da linha
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    /// Bit de paridade sempre 1
+    Mark,
+    /// Bit de paridade sempre 0
+    Space,
+}
+
+impl Parity {
+    /// Bits 3..=5 de `LINE_CTRL`
+    fn lcr_bits(self) -> u8 {
+        match self {
+            Parity::None => 0x00,
+            Parity::Odd => 0x08,
+            Parity::Even => 0x18,
+            Parity::Mark => 0x28,
+            Parity::Space => 0x38,
+        }
+    }
+}
+
+/// Erros de configuração da porta serial
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialError {
+    /// Baud zero, acima do clock base ou que não divide o clock exatamente
+    UnsupportedBaud,
+    /// Bits de dados fora de 5..=8
+    InvalidDataBits,
+    /// Stop bits diferente de 1 ou 2
+    InvalidStopBits,
+}
+
+/// Divisor do clock base para `baud` (115200 → 1, 9600 → 12).
+fn baud_divisor(baud: u32) -> Result<u16, SerialError> {
+    if baud == 0 || baud > BASE_BAUD || BASE_BAUD % baud != 0 {
+        return Err(SerialError::UnsupportedBaud);
+    }
+    Ok((BASE_BAUD / baud) as u16)
+}
+
+/// Byte de `LINE_CTRL` (sem DLAB) para o formato de quadro pedido.
+fn line_control(data_bits: u8, parity: Parity, stop_bits: u8) -> Result<u8, SerialError> {
+    if !(5..=8).contains(&data_bits) {
+        return Err(SerialError::InvalidDataBits);
+    }
+    let stop = match stop_bits {
+        1 => 0x00,
+        // Com 5 bits de dados o UART usa 1.5 stop bits neste modo
+        2 => 0x04,
+        _ => return Err(SerialError::InvalidStopBits),
+    };
+    Ok((data_bits - 5) | stop | parity.lcr_bits())
+}
 
 pub struct SerialPort {
-    buffer: [u8; SERIAL_BUFFER_SIZE],
-    head: usize,
-    tail: usize,
-    dropped_count: usize,
+    /// Anel de transmissão; cheio, descarta o byte mais antigo
+    buffer: RingBuffer<u8, SERIAL_BUFFER_SIZE>,
 }
 
 static SERIAL: Spinlock<SerialPort> = Spinlock::new(SerialPort {
-    buffer: [0; SERIAL_BUFFER_SIZE],
-    head: 0,
-    tail: 0,
-    dropped_count: 0,
+    buffer: RingBuffer::new(),
 });
 
 impl SerialPort {
-    /// Inicializa a porta serial COM1
-    pub fn init(&mut self) {
-        // Desabilitar interrupções do hardware
+    /// Programa a porta serial COM1 com o divisor e o formato de linha dados
+    fn program(&mut self, divisor: u16, line: u8) {
+        // Desabilitar interrupções do hardware (preservando o RX, se ativo)
+        let int_enable = inb(COM1_PORT + INT_ENABLE) & IER_RX_AVAILABLE;
         outb(COM1_PORT + INT_ENABLE, 0x00);
 
         // Setar Baud Rate (DLAB enabled)
-        outb(COM1_PORT + LINE_CTRL, 0x80);
-        outb(COM1_PORT + DATA_REG, 0x01); // Divisor Low = 1 (115200 baud)
-        outb(COM1_PORT + INT_ENABLE, 0x00); // Divisor High
+        outb(COM1_PORT + LINE_CTRL, LCR_DLAB);
+        outb(COM1_PORT + DATA_REG, divisor as u8); // Divisor Low
+        outb(COM1_PORT + INT_ENABLE, (divisor >> 8) as u8); // Divisor High
 
-        // Configurar linha: 8 bits, sem paridade, 1 stop bit
-        outb(COM1_PORT + LINE_CTRL, 0x03);
+        // Configurar linha: bits de dados, paridade e stop bits
+        outb(COM1_PORT + LINE_CTRL, line);
 
         // Habilitar FIFO, limpar buffers, 14-byte threshold
         outb(COM1_PORT + FIFO_CTRL, 0xC7);
 
         // Habilitar IRQs (Master), RTS/DSR set
-        outb(COM1_PORT + MODEM_CTRL, 0x0B);
+        outb(COM1_PORT + MODEM_CTRL, MCR_NORMAL);
+
+        outb(COM1_PORT + INT_ENABLE, int_enable);
     }
 
     /// Verifica se pode transmitir
@@ -64,21 +156,13 @@ impl SerialPort {
         // FAST PATH: Se o buffer estiver vazio e o hardware estiver pronto,
         // enviamos diretamente para o hardware. Isso remove overhead de buffer
         // e garante que logs apareçam imediatamente no QEMU/Simuladores.
-        if self.head == self.tail && self.is_transmit_empty() {
+        if self.buffer.is_empty() && self.is_transmit_empty() {
             outb(COM1_PORT, byte);
             return;
         }
 
-        let next_head = (self.head + 1) & SERIAL_BUFFER_MASK;
-
-        // Se o buffer estiver cheio, avançamos a cauda (perdemos o mais antigo)
-        if next_head == self.tail {
-            self.tail = (self.tail + 1) & SERIAL_BUFFER_MASK;
-            self.dropped_count += 1;
-        }
-
-        self.buffer[self.head] = byte;
-        self.head = next_head;
+        // Se o buffer estiver cheio, o mais antigo é perdido (ver `dropped_count`)
+        self.buffer.push(byte);
 
         // Tenta enviar o que puder
         self.drain_greedy();
@@ -91,13 +175,18 @@ impl SerialPort {
         // Limitamos a 128 bytes por drain para não prender a CPU eternamente
         // em hardware real lento, mas ser agressivo o suficiente para o log sair.
         let mut count = 0;
-        while self.head != self.tail && self.is_transmit_empty() && count < 128 {
-            outb(COM1_PORT, self.buffer[self.tail]);
-            self.tail = (self.tail + 1) & SERIAL_BUFFER_MASK;
+        while count < 128 && self.is_transmit_empty() {
+            let Some(byte) = self.buffer.pop() else { break };
+            outb(COM1_PORT, byte);
             count += 1;
         }
     }
 
+    /// Bytes de log descartados por anel de transmissão cheio
+    pub fn dropped_count(&self) -> usize {
+        self.buffer.dropped_count()
+    }
+
     /// Mantido para compatibilidade, aponta para o novo greedy drain
     fn drain_internal(&mut self) {
         self.drain_greedy();
@@ -106,33 +195,140 @@ impl SerialPort {
     /// Força a descarga total do buffer (bloqueante).
     /// Útil para situações críticas como pânico.
     pub fn force_flush(&mut self) {
-        while self.head != self.tail {
+        while let Some(byte) = self.buffer.pop() {
             while !self.is_transmit_empty() {
                 core::hint::spin_loop();
             }
-            outb(COM1_PORT, self.buffer[self.tail]);
-            self.tail = (self.tail + 1) & SERIAL_BUFFER_MASK;
+            outb(COM1_PORT, byte);
         }
     }
 
     /// Escreve hex interno (sem lock).
     /// Removido o prefixo " 0x" pois já é gerado pelo klog SerialDebug trait.
     fn write_hex_internal(&mut self, value: u64) {
-        for i in (0..16).rev() {
-            let digit = ((value >> (i * 4)) & 0xF) as u8;
-            let c = if digit < 10 {
-                b'0' + digit
-            } else {
-                b'A' + digit - 10
-            };
-            self.write_byte_internal(c);
+        let mut digits = [b'0'; HEX_MAX_LEN];
+        u64_to_hex(&mut digits, value);
+        for byte in digits {
+            self.write_byte_internal(byte);
+        }
+    }
+}
+
+// =============================================================================
+// RECEPÇÃO
+// =============================================================================
+
+/// Anel de recepção, alimentado pela IRQ; cheio, descarta o mais antigo.
+///
+/// O lock ordena a FIFO do UART com o anel (ver `read_byte`) e serializa
+/// leitores de CPUs diferentes.
+type RxBuffer = RingBuffer<u8, RX_BUFFER_SIZE>;
+
+static RX: Spinlock<RxBuffer> = Spinlock::new(RxBuffer::new());
+
+/// Move para o anel tudo que estiver na FIFO do UART (requer lock do RX)
+fn receive_pending(rx: &RxBuffer) {
+    while inb(COM1_PORT + LINE_STATUS) & LSR_DATA_READY != 0 {
+        rx.push(inb(COM1_PORT + DATA_REG));
+    }
+}
+
+/// Há um aviso de overrun agendado e ainda não emitido
+static OVERRUN_REPORT_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Handler da IRQ 4 (chamado pelo stub em `arch::x86_64::interrupts`)
+///
+/// Só esvazia a FIFO; o aviso de bytes perdidos vai para o worker
+/// (`core::work`), um por rajada.
+pub fn handle_irq() {
+    let overran = {
+        let rx = RX.lock();
+        let before = rx.dropped_count();
+        receive_pending(&rx);
+        rx.dropped_count() != before
+    };
+
+    if overran && !OVERRUN_REPORT_PENDING.swap(true, Ordering::AcqRel) {
+        crate::core::work::schedule(|| {
+            OVERRUN_REPORT_PENDING.store(false, Ordering::Release);
+            crate::kwarn!(
+                "(Serial) Anel de recepção cheio; bytes descartados:",
+                rx_overruns() as u64
+            );
+        });
+    }
+}
+
+/// Liga a interrupção de recepção no UART e a IRQ 4 no PIC.
+///
+/// Deve ser chamado depois de `init_pics`, que mascara todas as IRQs.
+pub fn enable_rx_interrupts() {
+    // Bytes chegados antes disso ficam no anel, não na FIFO
+    let rx = RX.lock();
+    receive_pending(&rx);
+    outb(COM1_PORT + INT_ENABLE, IER_RX_AVAILABLE);
+    crate::arch::x86_64::interrupts::pic_enable_irq(COM1_IRQ);
+}
+
+/// Retira um byte recebido, se houver (non-blocking)
+pub fn read_byte() -> Option<u8> {
+    let rx = RX.lock();
+    // A FIFO é consultada sob o mesmo lock: um byte que chegou depois da
+    // última IRQ não fica para trás de outro já enfileirado
+    receive_pending(&rx);
+    rx.pop()
+}
+
+/// Lê até `\n` (incluído) ou até encher `buf`, com o que já foi recebido.
+///
+/// Retorna o número de bytes copiados; não bloqueia.
+pub fn read_line(buf: &mut [u8]) -> usize {
+    let rx = RX.lock();
+    receive_pending(&rx);
+
+    let mut count = 0;
+    while count < buf.len() {
+        let Some(byte) = rx.pop() else { break };
+        buf[count] = byte;
+        count += 1;
+        if byte == b'\n' {
+            break;
         }
     }
+    count
+}
+
+/// Bytes descartados por anel de recepção cheio
+pub fn rx_overruns() -> usize {
+    RX.lock().dropped_count()
 }
 
-/// Inicializa serial
+// =============================================================================
+// API
+// =============================================================================
+
+/// Inicializa serial (115200 baud, 8N1)
 pub fn init() {
-    SERIAL.lock().init();
+    configure(DEFAULT_BAUD, 8, Parity::None, 1).expect("(Serial) Configuração padrão inválida");
+}
+
+/// Reconfigura baud e formato de quadro da COM1.
+///
+/// O baud precisa dividir exatamente o clock base de 115200. Bytes ainda no
+/// anel de transmissão são enviados antes, com a configuração antiga.
+pub fn configure(
+    baud: u32,
+    data_bits: u8,
+    parity: Parity,
+    stop_bits: u8,
+) -> Result<(), SerialError> {
+    let divisor = baud_divisor(baud)?;
+    let line = line_control(data_bits, parity, stop_bits)?;
+
+    let mut serial = SERIAL.lock();
+    serial.force_flush();
+    serial.program(divisor, line);
+    Ok(())
 }
 
 /// Tenta descarregar o buffer (non-blocking)
@@ -176,6 +372,14 @@ pub fn write_str(s: &str) {
     }
 }
 
+/// Escreve bytes arbitrários (atômico, sem exigir UTF-8)
+pub fn write_bytes(bytes: &[u8]) {
+    let mut serial = SERIAL.lock();
+    for &byte in bytes {
+        serial.write_byte_internal(byte);
+    }
+}
+
 /// Força a descarga total do buffer (bloqueante)
 pub fn force_flush() {
     SERIAL.lock().force_flush();
@@ -188,3 +392,57 @@ pub fn write_hex(value: u64) {
     serial.write_byte_internal(b'x');
     serial.write_hex_internal(value);
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(feature = "self_test")]
+pub mod test;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baud_divisor() {
+        assert_eq!(baud_divisor(9600), Ok(12));
+        assert_eq!(baud_divisor(115_200), Ok(1));
+        assert_eq!(baud_divisor(300), Ok(384));
+        assert_eq!(baud_divisor(0), Err(SerialError::UnsupportedBaud));
+        assert_eq!(baud_divisor(230_400), Err(SerialError::UnsupportedBaud));
+        assert_eq!(baud_divisor(7000), Err(SerialError::UnsupportedBaud));
+    }
+
+    #[test]
+    fn test_line_control() {
+        assert_eq!(line_control(8, Parity::None, 1), Ok(0x03));
+        assert_eq!(line_control(7, Parity::Even, 1), Ok(0x1A));
+        assert_eq!(line_control(5, Parity::Odd, 2), Ok(0x0C));
+        assert_eq!(
+            line_control(9, Parity::None, 1),
+            Err(SerialError::InvalidDataBits)
+        );
+        assert_eq!(
+            line_control(8, Parity::None, 3),
+            Err(SerialError::InvalidStopBits)
+        );
+    }
+
+    #[test]
+    fn test_rx_ring_drops_oldest() {
+        let rx = RxBuffer::new();
+        for i in 0..RX_BUFFER_SIZE + 9 {
+            rx.push(i as u8);
+        }
+        assert_eq!(rx.dropped_count(), 9);
+        assert_eq!(rx.pop(), Some(9));
+
+        let mut count = 1;
+        while rx.pop().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, RX_BUFFER_SIZE);
+        assert_eq!(rx.pop(), None);
+    }
+}
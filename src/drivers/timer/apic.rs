@@ -0,0 +1,114 @@
+//! Timer do Local APIC
+//!
+//! Contador decrescente por CPU, sem frequência conhecida: [`probe`] mede
+//! contra o canal 2 do PIT. As interrupções chegam no vetor
+//! `lapic::TIMER_VECTOR`. Como relógio conta o tick, como o PIT.
+
+use super::{ClockSource, TimerDevice, TimerError};
+use crate::arch::x86_64::apic::lapic;
+use crate::arch::x86_64::cpu::Cpu;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Frequência mínima aceita do timer do LAPIC (após o divisor).
+///
+/// Abaixo disso a calibração falhou (PIT ausente, timer parado...).
+const MIN_APIC_TIMER_HZ: u64 = 1_000_000;
+
+/// O timer do LAPIC do BSP
+pub struct ApicTimer {
+    /// Frequência medida (Hz, divisor 16); 0 sem LAPIC
+    hz: AtomicU64,
+}
+
+pub static APIC_TIMER: ApicTimer = ApicTimer {
+    hz: AtomicU64::new(0),
+};
+
+/// Liga o LAPIC e calibra o timer; `false` sem APIC ou com uma calibração
+/// implausível.
+pub fn probe() -> bool {
+    if !Cpu::has_apic() {
+        return false;
+    }
+    let hz = unsafe {
+        lapic::init();
+        lapic::calibrate_timer()
+    };
+    if hz < MIN_APIC_TIMER_HZ {
+        crate::kwarn!("(APIC) Calibração do timer do LAPIC falhou, Hz:", hz);
+        return false;
+    }
+    APIC_TIMER.hz.store(hz, Ordering::Relaxed);
+    crate::kinfo!("(APIC) Timer do LAPIC calibrado, Hz:", hz);
+    true
+}
+
+/// Contagem inicial do timer a `timer_hz` para um período de `interval_ns`.
+///
+/// `None` se o período não cabe no contador de 32 bits ou é zero.
+pub fn initial_count(timer_hz: u64, interval_ns: u64) -> Option<u32> {
+    // Arredonda para o mais próximo
+    let count = super::ns_to_ticks(interval_ns, timer_hz.saturating_mul(2)).div_ceil(2);
+    match u32::try_from(count) {
+        Ok(0) | Err(_) => None,
+        Ok(count) => Some(count),
+    }
+}
+
+impl ApicTimer {
+    fn hz(&self) -> Result<u64, TimerError> {
+        match self.hz.load(Ordering::Relaxed) {
+            0 => Err(TimerError::Unavailable),
+            hz => Ok(hz),
+        }
+    }
+}
+
+impl ClockSource for ApicTimer {
+    fn name(&self) -> &'static str {
+        "APIC"
+    }
+
+    fn now_ns(&self) -> u64 {
+        crate::core::time::jiffies::get_jiffies() * crate::core::time::jiffies::NANOS_PER_TICK
+    }
+}
+
+impl TimerDevice for ApicTimer {
+    fn set_oneshot(&self, deadline_ns: u64) -> Result<(), TimerError> {
+        let hz = self.hz()?;
+        let delta = deadline_ns.saturating_sub(self.now_ns());
+        let count = super::ns_to_ticks(delta, hz).clamp(1, u32::MAX as u64);
+        unsafe { lapic::start_oneshot_timer(lapic::TIMER_VECTOR, count as u32) };
+        Ok(())
+    }
+
+    fn set_periodic(&self, interval_ns: u64) -> Result<(), TimerError> {
+        let count = initial_count(self.hz()?, interval_ns).ok_or(TimerError::OutOfRange)?;
+        unsafe { lapic::start_periodic_timer(lapic::TIMER_VECTOR, count) };
+        Ok(())
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_count() {
+        // 62,5 MHz após o divisor; 100 Hz e 1000 Hz
+        assert_eq!(initial_count(62_500_000, 10_000_000), Some(625_000));
+        assert_eq!(initial_count(62_500_000, 1_000_000), Some(62_500));
+        // Arredonda para o mais próximo
+        assert_eq!(initial_count(1_000_150, 1_000_000), Some(1000));
+        assert_eq!(initial_count(1_000_600, 1_000_000), Some(1001));
+        // Zero ou fora dos 32 bits
+        assert_eq!(initial_count(10, 1_000_000), None);
+        assert_eq!(initial_count(u64::MAX / 2, 1_000_000_000), None);
+        assert_eq!(initial_count(1_000_000, 0), None);
+    }
+}
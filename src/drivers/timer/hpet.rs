@@ -1,2 +1,184 @@
 //! High Precision Event Timer
-// TODO: Implementar HPET
+//!
+//! Contador de 64 bits com período fixo (em femtossegundos, nos
+//! registradores), mapeado em MMIO no endereço da tabela ACPI `HPET`. É o
+//! melhor relógio da lista (ver [`super`]): conta sozinho, sem depender do
+//! tick.
+//!
+//! Como gerador de interrupções usa o comparador 0 em legacy replacement:
+//! ele assume o IRQ 0 do PIT (e o comparador 1 o IRQ 8 do RTC), então o
+//! tick continua chegando pelo PIC, sem IOAPIC.
+
+use super::{ClockSource, TimerDevice, TimerError};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Registradores (offsets no bloco MMIO)
+mod regs {
+    pub const CAP: usize = 0x000;
+    pub const CONF: usize = 0x010;
+    pub const COUNTER: usize = 0x0F0;
+    pub const T0_CONF: usize = 0x100;
+    pub const T0_COMPARATOR: usize = 0x108;
+}
+
+/// CAP: o bloco suporta legacy replacement
+const CAP_LEG_RT: u64 = 1 << 15;
+/// CONF: contador principal ligado
+const CONF_ENABLE: u64 = 1 << 0;
+/// CONF: comparadores 0 e 1 nos IRQs 0 e 8
+const CONF_LEG_RT: u64 = 1 << 1;
+/// Tn_CONF: interrupção ligada
+const TN_INT_ENB: u64 = 1 << 2;
+/// Tn_CONF: modo periódico
+const TN_PERIODIC: u64 = 1 << 3;
+/// Tn_CONF: o comparador suporta modo periódico
+const TN_PER_CAP: u64 = 1 << 4;
+/// Tn_CONF: a próxima escrita no comparador define o período
+const TN_VAL_SET: u64 = 1 << 6;
+
+/// Maior período aceito pela especificação (100 ns)
+const MAX_PERIOD_FS: u64 = 100_000_000;
+const FS_PER_NS: u128 = 1_000_000;
+
+/// Prazo mínimo de um one-shot, em ticks do contador (o comparador só
+/// dispara na igualdade; um prazo que já passou esperaria a volta inteira)
+const MIN_ONESHOT_TICKS: u64 = 64;
+
+/// Desvio máximo aceito entre o período informado e o medido (1/8)
+const CALIBRATION_TOLERANCE_SHIFT: u32 = 3;
+
+/// O bloco HPET
+pub struct Hpet {
+    /// Endereço virtual dos registradores; 0 sem HPET
+    base: AtomicU64,
+    /// Período do contador em femtossegundos
+    period_fs: AtomicU64,
+}
+
+pub static HPET: Hpet = Hpet {
+    base: AtomicU64::new(0),
+    period_fs: AtomicU64::new(0),
+};
+
+/// Procura o HPET na tabela ACPI e o liga; `false` sem tabela, com um
+/// período inválido ou um contador que não bate com o PIT.
+pub fn probe() -> bool {
+    let Some(phys) = crate::arch::x86_64::acpi::hpet_base() else {
+        return false;
+    };
+    // SAFETY: bloco MMIO informado pelo firmware, pelo HHDM
+    let base = unsafe { crate::mm::addr::phys_to_virt::<u8>(phys) } as u64;
+
+    let period_fs = read(base, regs::CAP) >> 32;
+    if period_fs == 0 || period_fs > MAX_PERIOD_FS {
+        crate::kwarn!("(HPET) Período inválido (fs):", period_fs);
+        return false;
+    }
+
+    // Parado, zerado e ligado: o relógio começa aqui
+    let conf = read(base, regs::CONF) & !(CONF_ENABLE | CONF_LEG_RT);
+    write(base, regs::CONF, conf);
+    write(base, regs::COUNTER, 0);
+    write(base, regs::CONF, conf | CONF_ENABLE);
+
+    let hz = (1_000_000_000_000_000 / period_fs as u128) as u64;
+    let measured = super::calibrate(|| read(base, regs::COUNTER));
+    if measured.abs_diff(hz) > hz >> CALIBRATION_TOLERANCE_SHIFT {
+        crate::kwarn!("(HPET) Contador não bate com o PIT, Hz:", measured);
+        write(base, regs::CONF, conf);
+        return false;
+    }
+
+    HPET.period_fs.store(period_fs, Ordering::Relaxed);
+    // Release: quem vê a base vê o período
+    HPET.base.store(base, Ordering::Release);
+    crate::kinfo!("(HPET) Contador ligado, Hz:", hz);
+    true
+}
+
+fn read(base: u64, reg: usize) -> u64 {
+    // SAFETY: registrador do bloco HPET
+    unsafe { core::ptr::read_volatile((base as usize + reg) as *const u64) }
+}
+
+fn write(base: u64, reg: usize, value: u64) {
+    // SAFETY: registrador do bloco HPET
+    unsafe { core::ptr::write_volatile((base as usize + reg) as *mut u64, value) }
+}
+
+impl Hpet {
+    fn base(&self) -> Result<u64, TimerError> {
+        match self.base.load(Ordering::Acquire) {
+            0 => Err(TimerError::Unavailable),
+            base => Ok(base),
+        }
+    }
+
+    /// Ticks do contador em `ns` nanossegundos
+    fn ns_to_ticks(&self, ns: u64) -> u64 {
+        let ticks = ns as u128 * FS_PER_NS / self.period_fs.load(Ordering::Relaxed) as u128;
+        u64::try_from(ticks).unwrap_or(u64::MAX)
+    }
+
+    /// Legacy replacement disponível, ou `Unsupported`
+    fn legacy_route(&self, base: u64) -> Result<(), TimerError> {
+        if read(base, regs::CAP) & CAP_LEG_RT == 0 {
+            return Err(TimerError::Unsupported);
+        }
+        Ok(())
+    }
+}
+
+impl ClockSource for Hpet {
+    fn name(&self) -> &'static str {
+        "HPET"
+    }
+
+    fn now_ns(&self) -> u64 {
+        let Ok(base) = self.base() else {
+            return 0;
+        };
+        let ticks = read(base, regs::COUNTER) as u128;
+        (ticks * self.period_fs.load(Ordering::Relaxed) as u128 / FS_PER_NS) as u64
+    }
+}
+
+impl TimerDevice for Hpet {
+    fn set_oneshot(&self, deadline_ns: u64) -> Result<(), TimerError> {
+        let base = self.base()?;
+        self.legacy_route(base)?;
+
+        let earliest = read(base, regs::COUNTER).wrapping_add(MIN_ONESHOT_TICKS);
+        let target = self.ns_to_ticks(deadline_ns).max(earliest);
+        write(base, regs::T0_CONF, TN_INT_ENB);
+        write(base, regs::T0_COMPARATOR, target);
+        let conf = read(base, regs::CONF);
+        write(base, regs::CONF, conf | CONF_LEG_RT | CONF_ENABLE);
+        Ok(())
+    }
+
+    fn set_periodic(&self, interval_ns: u64) -> Result<(), TimerError> {
+        let base = self.base()?;
+        self.legacy_route(base)?;
+        if read(base, regs::T0_CONF) & TN_PER_CAP == 0 {
+            return Err(TimerError::Unsupported);
+        }
+        let period = self.ns_to_ticks(interval_ns);
+        if period == 0 {
+            return Err(TimerError::OutOfRange);
+        }
+
+        // O período só é aceito com o contador parado
+        let conf = read(base, regs::CONF);
+        write(base, regs::CONF, conf & !CONF_ENABLE);
+        write(base, regs::T0_CONF, TN_INT_ENB | TN_PERIODIC | TN_VAL_SET);
+        write(
+            base,
+            regs::T0_COMPARATOR,
+            read(base, regs::COUNTER).wrapping_add(period),
+        );
+        write(base, regs::T0_COMPARATOR, period);
+        write(base, regs::CONF, conf | CONF_LEG_RT | CONF_ENABLE);
+        Ok(())
+    }
+}
@@ -1,11 +1,189 @@
-//! Timer Drivers
+//! # Timer Drivers
+//!
+//! Cada chip de tempo (PIT, HPET, timer do LAPIC) implementa dois papéis:
+//! - [`ClockSource`]: relógio, em nanossegundos desde que foi ligado;
+//! - [`TimerDevice`]: gerador de interrupções, one-shot ou periódico.
+//!
+//! O `core::time` não fala com os chips: no boot, [`detect`] testa o que
+//! existe e ele escolhe pela ordem de preferência de cada papel:
+//!
+//! | Papel   | Ordem              | Motivo                                   |
+//! |---------|--------------------|------------------------------------------|
+//! | Relógio | HPET, APIC, PIT    | Só o HPET tem contador livre; os outros contam o tick |
+//! | Tick    | APIC, HPET, PIT    | O timer do LAPIC é por CPU e não passa pelo PIC |
+//!
+//! O PIT sempre existe e fecha as duas listas. O TSC (ver [`tsc`]) não gera
+//! interrupções e fica de fora: o relógio monotônico o usa direto quando é
+//! invariante.
 
+pub mod apic;
 pub mod hpet;
 pub mod pit;
 pub mod tsc;
 
 pub use pit::init as init_pit;
 
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Janela de [`calibrate`] (a maior que o canal 2 do PIT cobre)
+pub const CALIBRATION_MS: u32 = pit::MAX_BUSY_WAIT_MS;
+
+/// Erros ao programar um timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerError {
+    /// Chip ausente ou não detectado
+    Unavailable,
+    /// O chip não tem esse modo
+    Unsupported,
+    /// Intervalo fora do alcance do contador
+    OutOfRange,
+}
+
+/// Relógio
+pub trait ClockSource: Sync {
+    /// Nome curto, para o log
+    fn name(&self) -> &'static str;
+
+    /// Nanossegundos desde que o chip foi ligado
+    fn now_ns(&self) -> u64;
+}
+
+/// Gerador de interrupções do tick
+///
+/// A interrupção cai no handler do tick (`timer_handler`); o EOI depende do
+/// chip (ver `core::time::tick::eoi`).
+pub trait TimerDevice: ClockSource {
+    /// Uma interrupção quando [`ClockSource::now_ns`] chegar a
+    /// `deadline_ns`. Prazos além do contador disparam antes; quem chama
+    /// reprograma.
+    fn set_oneshot(&self, deadline_ns: u64) -> Result<(), TimerError>;
+
+    /// Uma interrupção a cada `interval_ns`
+    fn set_periodic(&self, interval_ns: u64) -> Result<(), TimerError>;
+}
+
+/// Chips de tempo conhecidos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TimerKind {
+    /// PIT no IRQ 0, via PIC
+    Pit = 0,
+    /// Timer do LAPIC no vetor `lapic::TIMER_VECTOR`
+    ApicTimer = 1,
+    /// HPET, no IRQ 0 via legacy replacement
+    Hpet = 2,
+}
+
+impl TimerKind {
+    /// Tipo guardado como `u8` (num atômico)
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::ApicTimer,
+            2 => Self::Hpet,
+            _ => Self::Pit,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Preferência para o relógio
+pub const CLOCK_ORDER: [TimerKind; 3] = [TimerKind::Hpet, TimerKind::ApicTimer, TimerKind::Pit];
+
+/// Preferência para o tick
+pub const TICK_ORDER: [TimerKind; 3] = [TimerKind::ApicTimer, TimerKind::Hpet, TimerKind::Pit];
+
+/// Chips detectados, um bit por [`TimerKind`]; o PIT sempre está lá
+static AVAILABLE: AtomicU8 = AtomicU8::new(1 << TimerKind::Pit as u8);
+
+/// Relógio escolhido por [`select_clock`]
+static CLOCK: AtomicU8 = AtomicU8::new(TimerKind::Pit as u8);
+
+/// Detecta os chips de tempo, na ordem HPET (tabela ACPI), APIC (CPUID e
+/// calibração contra o PIT), PIT. Chamar uma vez, depois do ACPI e com
+/// interrupções desligadas (as calibrações bloqueiam a CPU).
+pub fn detect() {
+    let mut available = TimerKind::Pit.bit();
+    if hpet::probe() {
+        available |= TimerKind::Hpet.bit();
+    }
+    if apic::probe() {
+        available |= TimerKind::ApicTimer.bit();
+    }
+    AVAILABLE.store(available, Ordering::Relaxed);
+}
+
+/// O chip foi detectado?
+pub fn is_available(kind: TimerKind) -> bool {
+    AVAILABLE.load(Ordering::Relaxed) & kind.bit() != 0
+}
+
+/// Primeiro chip de `order` presente em `available`; o PIT se nenhum
+pub fn first_available(order: &[TimerKind], available: u8) -> TimerKind {
+    order
+        .iter()
+        .copied()
+        .find(|kind| available & kind.bit() != 0)
+        .unwrap_or(TimerKind::Pit)
+}
+
+/// Chips de `order` detectados, na ordem
+pub fn candidates(order: &[TimerKind]) -> impl Iterator<Item = TimerKind> + '_ {
+    order.iter().copied().filter(|&kind| is_available(kind))
+}
+
+/// Escolhe o relógio por [`CLOCK_ORDER`] entre os detectados
+pub fn select_clock() -> &'static dyn ClockSource {
+    let kind = first_available(&CLOCK_ORDER, AVAILABLE.load(Ordering::Relaxed));
+    CLOCK.store(kind as u8, Ordering::Relaxed);
+    clock()
+}
+
+/// Relógio em uso (o PIT até [`select_clock`])
+#[inline]
+pub fn clock() -> &'static dyn ClockSource {
+    match TimerKind::from_u8(CLOCK.load(Ordering::Relaxed)) {
+        TimerKind::Pit => &pit::PIT,
+        TimerKind::ApicTimer => &apic::APIC_TIMER,
+        TimerKind::Hpet => &hpet::HPET,
+    }
+}
+
+/// Gerador de interrupções de um chip
+pub fn device(kind: TimerKind) -> &'static dyn TimerDevice {
+    match kind {
+        TimerKind::Pit => &pit::PIT,
+        TimerKind::ApicTimer => &apic::APIC_TIMER,
+        TimerKind::Hpet => &hpet::HPET,
+    }
+}
+
+/// Mede a frequência (Hz) de um contador crescente contra o canal 2 do PIT.
+///
+/// Bloqueia a CPU por [`CALIBRATION_MS`]; chamar com interrupções
+/// desligadas para a medição não incluir handlers.
+pub fn calibrate(mut read: impl FnMut() -> u64) -> u64 {
+    let start = read();
+    pit::busy_wait_ms(CALIBRATION_MS);
+    hz_from_elapsed(read().wrapping_sub(start), CALIBRATION_MS)
+}
+
+/// Frequência de um contador que andou `elapsed` em `ms` milissegundos
+pub fn hz_from_elapsed(elapsed: u64, ms: u32) -> u64 {
+    if ms == 0 {
+        return 0;
+    }
+    (elapsed as u128 * 1000 / ms as u128) as u64
+}
+
+/// Ticks de um contador a `hz` em `ns` nanossegundos (saturando)
+pub fn ns_to_ticks(ns: u64, hz: u64) -> u64 {
+    let ticks = ns as u128 * hz as u128 / 1_000_000_000;
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+}
+
 /// Retorna ticks atuais do sistema (monotônico)
 pub fn ticks() -> u64 {
     // TODO: Implementar usando TSC ou HPET real
@@ -14,11 +192,10 @@ pub fn ticks() -> u64 {
 
 /// Retorna frequência do timer base em Hz
 ///
-/// NOTA: Deve corresponder ao valor passado para pit::init()
+/// É a frequência do tick, seja ele do PIT, do HPET ou do LAPIC (ver
+/// `core::time::tick`).
 pub fn frequency() -> u64 {
-    100 // Corresponde ao pit::init(100) definido no boot
-        // TODO: Implementar usando TSC ou HPET real,
-        // ler frequencia de variavle global e fazer o pit setar a vareavel
+    crate::core::time::HZ
 }
 
 /// Delay em milissegundos com cooperative multitasking
@@ -40,3 +217,46 @@ pub fn delay_ms(ms: u64) {
         crate::sched::yield_now();
     }
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection_order() {
+        let all = TimerKind::Pit.bit() | TimerKind::ApicTimer.bit() | TimerKind::Hpet.bit();
+        assert_eq!(first_available(&CLOCK_ORDER, all), TimerKind::Hpet);
+        assert_eq!(first_available(&TICK_ORDER, all), TimerKind::ApicTimer);
+
+        // Sem LAPIC: o HPET assume o tick
+        let no_apic = TimerKind::Pit.bit() | TimerKind::Hpet.bit();
+        assert_eq!(first_available(&TICK_ORDER, no_apic), TimerKind::Hpet);
+
+        // Sem HPET: o relógio conta o tick do LAPIC
+        let no_hpet = TimerKind::Pit.bit() | TimerKind::ApicTimer.bit();
+        assert_eq!(first_available(&CLOCK_ORDER, no_hpet), TimerKind::ApicTimer);
+
+        // Só o PIT, ou nada marcado
+        assert_eq!(
+            first_available(&CLOCK_ORDER, TimerKind::Pit.bit()),
+            TimerKind::Pit
+        );
+        assert_eq!(first_available(&TICK_ORDER, 0), TimerKind::Pit);
+    }
+
+    #[test]
+    fn test_calibration_math() {
+        // 54 ms de um contador a 14,318 MHz (HPET do QEMU/ICH)
+        assert_eq!(hz_from_elapsed(773_172, 54), 14_318_000);
+        assert_eq!(hz_from_elapsed(1, 0), 0);
+        assert_eq!(hz_from_elapsed(u64::MAX, 1000), u64::MAX);
+
+        assert_eq!(ns_to_ticks(10_000_000, 1_193_182), 11_931);
+        assert_eq!(ns_to_ticks(1_000_000_000, 62_500_000), 62_500_000);
+        assert_eq!(ns_to_ticks(u64::MAX, u64::MAX), u64::MAX);
+    }
+}
@@ -1,27 +1,125 @@
 //! Programmable Interval Timer (8254)
+//!
+//! Sempre presente; o último da lista de detecção (ver [`super`]). Como
+//! relógio conta o tick, então só anda se o tick estiver ligado.
 
-use crate::arch::x86_64::ports::outb;
+use super::{ClockSource, TimerDevice, TimerError};
+use crate::arch::x86_64::ports::{inb, outb};
 
 /// Frequência base do PIT (Hz)
 const PIT_FREQUENCY: u32 = 1193182;
 
 /// Portas do PIT
 const PIT_CHANNEL0: u16 = 0x40;
+const PIT_CHANNEL2: u16 = 0x42;
 const PIT_COMMAND: u16 = 0x43;
 
-/// Inicializa PIT para frequência específica
-pub fn init(frequency_hz: u32) {
-    let divisor = PIT_FREQUENCY / frequency_hz;
+/// Porta B do controlador do sistema: bit 0 = gate do canal 2, bit 1 =
+/// speaker, bit 5 = saída (OUT2) do canal 2
+const SYSTEM_PORT_B: u16 = 0x61;
 
-    // Channel 0, lobyte/hibyte, mode 3 (square wave)
-    outb(PIT_COMMAND, 0x36);
+/// Maior espera possível em [`busy_wait_ms`] (contador de 16 bits)
+pub const MAX_BUSY_WAIT_MS: u32 = 54;
 
-    // Divisor
-    outb(PIT_CHANNEL0, (divisor & 0xFF) as u8);
-    outb(PIT_CHANNEL0, ((divisor >> 8) & 0xFF) as u8);
+/// Comando do canal 0: lobyte/hibyte, mode 3 (square wave)
+const MODE_SQUARE_WAVE: u8 = 0x36;
+/// Comando do canal 0: lobyte/hibyte, mode 0 (interrupt on terminal count)
+const MODE_ONESHOT: u8 = 0x30;
+
+/// Inicializa PIT para frequência específica
+pub fn init(frequency_hz: u32) {
+    program(MODE_SQUARE_WAVE, PIT_FREQUENCY / frequency_hz);
 
     // NOTA: IRQ0 é habilitado separadamente após o scheduler estar pronto
     // NÃO habilitar aqui para evitar interrupções durante a inicialização
 
     crate::kinfo!("(PIT) Inicializado com freq=", frequency_hz as u64);
 }
+
+/// Programa o canal 0 no modo `mode` com o contador `count`
+fn program(mode: u8, count: u32) {
+    outb(PIT_COMMAND, mode);
+    outb(PIT_CHANNEL0, (count & 0xFF) as u8);
+    outb(PIT_CHANNEL0, ((count >> 8) & 0xFF) as u8);
+}
+
+/// Contador do canal 0 para `ns` nanossegundos; `None` fora dos 16 bits
+fn count_for(ns: u64) -> Option<u32> {
+    match super::ns_to_ticks(ns, PIT_FREQUENCY as u64) {
+        0 => None,
+        // 0 no contador vale 65536
+        count if count <= 0x1_0000 => Some(count as u32),
+        _ => None,
+    }
+}
+
+/// O PIT como relógio e gerador do tick
+pub struct Pit;
+
+pub static PIT: Pit = Pit;
+
+impl ClockSource for Pit {
+    fn name(&self) -> &'static str {
+        "PIT"
+    }
+
+    fn now_ns(&self) -> u64 {
+        crate::core::time::jiffies::get_jiffies() * crate::core::time::jiffies::NANOS_PER_TICK
+    }
+}
+
+impl TimerDevice for Pit {
+    fn set_oneshot(&self, deadline_ns: u64) -> Result<(), TimerError> {
+        let delta = deadline_ns.saturating_sub(self.now_ns());
+        let count = super::ns_to_ticks(delta, PIT_FREQUENCY as u64).clamp(1, 0x1_0000);
+        program(MODE_ONESHOT, count as u32);
+        Ok(())
+    }
+
+    fn set_periodic(&self, interval_ns: u64) -> Result<(), TimerError> {
+        let count = count_for(interval_ns).ok_or(TimerError::OutOfRange)?;
+        program(MODE_SQUARE_WAVE, count);
+        Ok(())
+    }
+}
+
+/// Espera `ms` milissegundos contando no canal 2, sem IRQ nem scheduler.
+///
+/// Usado para calibrar outros timers. Limitado a [`MAX_BUSY_WAIT_MS`].
+pub fn busy_wait_ms(ms: u32) {
+    let count = PIT_FREQUENCY * ms.min(MAX_BUSY_WAIT_MS) / 1000;
+    let saved = inb(SYSTEM_PORT_B);
+
+    // Gate do canal 2 ligado, speaker desligado
+    outb(SYSTEM_PORT_B, (saved & !0x02) | 0x01);
+
+    // Channel 2, lobyte/hibyte, mode 0 (OUT2 sobe ao chegar em zero)
+    outb(PIT_COMMAND, 0xB0);
+    outb(PIT_CHANNEL2, (count & 0xFF) as u8);
+    outb(PIT_CHANNEL2, ((count >> 8) & 0xFF) as u8);
+
+    while inb(SYSTEM_PORT_B) & 0x20 == 0 {
+        core::hint::spin_loop();
+    }
+
+    outb(SYSTEM_PORT_B, saved);
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_for() {
+        // 100 Hz
+        assert_eq!(count_for(10_000_000), Some(11_931));
+        // Limite do contador de 16 bits (~54,9 ms)
+        assert_eq!(count_for(54_925_000), Some(65_535));
+        assert_eq!(count_for(60_000_000), None);
+        assert_eq!(count_for(100), None);
+    }
+}
@@ -1,2 +1,26 @@
 //! Timestamp Counter
-// TODO: Implementar TSC calibration
+//!
+//! O TSC conta ciclos desde o reset. Só serve de relógio se for invariante
+//! (CPUID.80000007H:EDX[8]): frequência constante, independente de P-states
+//! e C-states. A frequência é medida contra o canal 2 do PIT.
+
+use crate::arch::x86_64::cpu::Cpu;
+
+/// Leitura do contador
+#[inline(always)]
+pub fn read() -> u64 {
+    Cpu::rdtsc()
+}
+
+/// O TSC tem frequência constante (invariant TSC)?
+pub fn is_invariant() -> bool {
+    crate::arch::x86_64::cpuid::features().invariant_tsc
+}
+
+/// Mede a frequência do TSC em Hz.
+///
+/// Bloqueia a CPU por [`super::CALIBRATION_MS`]; chamar com interrupções
+/// desligadas para a medição não incluir handlers.
+pub fn calibrate() -> u64 {
+    super::calibrate(read)
+}
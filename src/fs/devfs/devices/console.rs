@@ -0,0 +1,35 @@
+//! `/dev/console`: escritas vão para a serial
+
+use super::super::{CharDevice, DevNum, DeviceOps, DEV_CONSOLE};
+use crate::fs::vfs::inode::FsError;
+use crate::sched::sync::Pollable;
+
+pub struct ConsoleDevice;
+
+impl DeviceOps for ConsoleDevice {
+    fn read(&self, _offset: u64, _buf: &mut [u8]) -> Result<usize, FsError> {
+        // Sem entrada de console por enquanto
+        Ok(0)
+    }
+
+    fn write(&self, _offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        match core::str::from_utf8(buf) {
+            Ok(text) => crate::drivers::serial::write_str(text),
+            Err(_) => crate::drivers::serial::write_bytes(buf),
+        }
+        Ok(buf.len())
+    }
+}
+
+impl CharDevice for ConsoleDevice {
+    fn name(&self) -> &'static str {
+        "console"
+    }
+
+    fn number(&self) -> DevNum {
+        DEV_CONSOLE
+    }
+}
+
+/// Nunca bloqueia: sempre pronto para ler e escrever
+impl Pollable for ConsoleDevice {}
@@ -0,0 +1,35 @@
+//! `/dev/fb0`: texto escrito vai para o console de vídeo
+//!
+//! Só é registrado quando há framebuffer (ver
+//! [`crate::drivers::display::console`]).
+
+use super::super::{CharDevice, DevNum, DeviceOps, DEV_FB0};
+use crate::drivers::display::console;
+use crate::fs::vfs::inode::FsError;
+use crate::sched::sync::Pollable;
+
+pub struct FramebufferDevice;
+
+impl DeviceOps for FramebufferDevice {
+    fn read(&self, _offset: u64, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Ok(0)
+    }
+
+    fn write(&self, _offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        console::write_bytes(buf);
+        Ok(buf.len())
+    }
+}
+
+impl CharDevice for FramebufferDevice {
+    fn name(&self) -> &'static str {
+        "fb0"
+    }
+
+    fn number(&self) -> DevNum {
+        DEV_FB0
+    }
+}
+
+/// Nunca bloqueia: sempre pronto para ler e escrever
+impl Pollable for FramebufferDevice {}
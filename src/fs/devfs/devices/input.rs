@@ -0,0 +1,89 @@
+//! `/dev/input`: eventos do teclado, 4 bytes cada (ver
+//! [`KeyEvent::to_bytes`])
+//!
+//! A leitura não bloqueia: devolve os eventos inteiros que couberem no
+//! buffer, ou 0 com a fila vazia; `poll` avisa quando há eventos.
+
+use super::super::{CharDevice, DevNum, DeviceOps, DEV_INPUT};
+use crate::drivers::input::keyboard;
+use crate::drivers::input::scancode::{KeyEvent, EVENT_SIZE};
+use crate::fs::vfs::inode::FsError;
+use crate::sched::sync::Pollable;
+use crate::syscall::abi::types::poll_events;
+
+pub struct InputDevice;
+
+/// Copia eventos de `next` para `buf` enquanto couberem; retorna os bytes
+fn fill_events(buf: &mut [u8], mut next: impl FnMut() -> Option<KeyEvent>) -> usize {
+    let mut done = 0;
+    for record in buf.chunks_exact_mut(EVENT_SIZE) {
+        let Some(event) = next() else {
+            break;
+        };
+        record.copy_from_slice(&event.to_bytes());
+        done += EVENT_SIZE;
+    }
+    done
+}
+
+impl DeviceOps for InputDevice {
+    fn read(&self, _offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        if buf.len() < EVENT_SIZE {
+            return Err(FsError::InvalidArgument);
+        }
+        Ok(fill_events(buf, keyboard::read_key))
+    }
+
+    fn write(&self, _offset: u64, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+}
+
+impl CharDevice for InputDevice {
+    fn name(&self) -> &'static str {
+        "input"
+    }
+
+    fn number(&self) -> DevNum {
+        DEV_INPUT
+    }
+}
+
+/// Pronto para ler com eventos na fila; nunca para escrever
+impl Pollable for InputDevice {
+    fn poll_ready(&self) -> u16 {
+        if keyboard::has_key() {
+            poll_events::IN
+        } else {
+            0
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::input::scancode::Decoder;
+
+    #[test]
+    fn test_fill_whole_events() {
+        let mut decoder = Decoder::new();
+        let mut bytes = [0x1E, 0x9E, 0x30].into_iter();
+        let mut next = || bytes.by_ref().find_map(|b| decoder.feed(b));
+
+        // Só cabem dois eventos inteiros em 10 bytes
+        let mut buf = [0xFFu8; 10];
+        assert_eq!(fill_events(&mut buf, &mut next), 8);
+        assert_eq!(&buf[..4], &[0x1E, 0, 0b1, b'a']);
+        assert_eq!(&buf[4..8], &[0x1E, 0, 0b0, 0]);
+        assert_eq!(&buf[8..], &[0xFF, 0xFF]);
+
+        assert_eq!(fill_events(&mut buf, &mut next), 4);
+        assert_eq!(&buf[..4], &[0x30, 0, 0b1, b'b']);
+        assert_eq!(fill_events(&mut buf, &mut next), 0);
+    }
+}
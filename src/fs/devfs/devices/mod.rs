@@ -0,0 +1,15 @@
+//! Dispositivos de caractere do `/dev`
+
+pub mod console;
+pub mod fb;
+pub mod input;
+pub mod null;
+pub mod random;
+pub mod zero;
+
+pub use console::ConsoleDevice;
+pub use fb::FramebufferDevice;
+pub use input::InputDevice;
+pub use null::NullDevice;
+pub use random::{kernel_rng_fill, RandomDevice};
+pub use zero::ZeroDevice;
@@ -0,0 +1,48 @@
+//! `/dev/null`: leituras retornam EOF, escritas são descartadas
+
+use super::super::{CharDevice, DevNum, DeviceOps, DEV_NULL};
+use crate::fs::vfs::inode::FsError;
+use crate::sched::sync::Pollable;
+
+pub struct NullDevice;
+
+impl DeviceOps for NullDevice {
+    fn read(&self, _offset: u64, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Ok(0)
+    }
+
+    fn write(&self, _offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        Ok(buf.len())
+    }
+}
+
+impl CharDevice for NullDevice {
+    fn name(&self) -> &'static str {
+        "null"
+    }
+
+    fn number(&self) -> DevNum {
+        DEV_NULL
+    }
+}
+
+/// Nunca bloqueia: sempre pronto para ler e escrever
+impl Pollable for NullDevice {}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_discards_everything() {
+        let mut buf = [0xAAu8; 8];
+        assert_eq!(NullDevice.read(0, &mut buf), Ok(0));
+        assert_eq!(buf, [0xAA; 8]);
+        assert_eq!(NullDevice.write(0, &[1u8; 4096]), Ok(4096));
+        assert_eq!(NullDevice.write(0, &[]), Ok(0));
+    }
+}
@@ -0,0 +1,280 @@
+//! `/dev/urandom`: CSPRNG do kernel
+//!
+//! Gerador ChaCha20 (RFC 8439) com *fast key erasure*: depois de cada
+//! leitura a chave é substituída por um bloco novo do próprio fluxo, então
+//! comprometer o estado não revela o que já foi entregue.
+//!
+//! ## Semente
+//! Coletada no primeiro uso: jitter do `rdtsc` (o mesmo contador usado no
+//! ASLR do heap) e, se a CPU tiver, `rdrand`. Cada leitura ainda mistura um
+//! `rdtsc` fresco antes de gerar, de modo que duas cópias do estado (p.ex.
+//! herdadas num fork) divergem já na próxima leitura.
+//!
+//! Leituras nunca bloqueiam. Dentro do kernel, use [`kernel_rng_fill`].
+
+use super::super::{CharDevice, DevNum, DeviceOps, DEV_URANDOM};
+use crate::arch::Cpu;
+use crate::fs::vfs::inode::FsError;
+use crate::sched::sync::Pollable;
+use crate::sync::Spinlock;
+
+/// "expand 32-byte k"
+const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// Tamanho do bloco ChaCha20 em bytes
+const BLOCK_SIZE: usize = 64;
+
+/// Bytes gerados por aquisição do lock (limita o tempo com IRQs desligadas)
+const CHUNK_SIZE: usize = 4096;
+
+/// Amostras de `rdtsc` coletadas na semente inicial
+const BOOT_TSC_SAMPLES: usize = 64;
+
+/// Entrada (palavras 12..16) reservada para derivação de chave; o contador
+/// do fluxo nunca chega a ela
+const RESEED_TAG: u32 = 0xFFFF_FFFF;
+
+static RNG: Spinlock<ChaCha20Rng> = Spinlock::new(ChaCha20Rng::new());
+
+// =============================================================================
+// CHACHA20
+// =============================================================================
+
+#[inline(always)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// Bloco ChaCha20. `input` são as palavras 12..16 (contador e nonce).
+fn chacha20_block(key: &[u32; 8], input: &[u32; 4]) -> [u32; 16] {
+    let mut initial = [0u32; 16];
+    initial[..4].copy_from_slice(&SIGMA);
+    initial[4..12].copy_from_slice(key);
+    initial[12..].copy_from_slice(input);
+
+    let mut state = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+    for (word, init) in state.iter_mut().zip(initial) {
+        *word = word.wrapping_add(init);
+    }
+    state
+}
+
+/// Estado do gerador
+pub struct ChaCha20Rng {
+    key: [u32; 8],
+    counter: u64,
+    seeded: bool,
+}
+
+impl ChaCha20Rng {
+    /// Gerador sem semente (chave zero). Só serve para a estática.
+    pub const fn new() -> Self {
+        Self {
+            key: [0; 8],
+            counter: 0,
+            seeded: false,
+        }
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    /// Mistura 64 bits de entropia na chave.
+    pub fn reseed(&mut self, entropy: u64) {
+        let input = [
+            RESEED_TAG,
+            RESEED_TAG,
+            entropy as u32,
+            (entropy >> 32) as u32,
+        ];
+        let block = chacha20_block(&self.key, &input);
+        self.key.copy_from_slice(&block[..8]);
+        self.seeded = true;
+    }
+
+    /// Preenche `buf` com o fluxo e troca a chave.
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(BLOCK_SIZE) {
+            let block = self.next_block();
+            for (dst, word) in chunk.chunks_mut(4).zip(block) {
+                dst.copy_from_slice(&word.to_le_bytes()[..dst.len()]);
+            }
+        }
+        // Fast key erasure
+        let block = self.next_block();
+        self.key.copy_from_slice(&block[..8]);
+    }
+
+    fn next_block(&mut self) -> [u32; 16] {
+        let input = [self.counter as u32, (self.counter >> 32) as u32, 0, 0];
+        self.counter += 1;
+        chacha20_block(&self.key, &input)
+    }
+}
+
+impl Default for ChaCha20Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Semente inicial: jitter do TSC e `rdrand`, se houver.
+fn seed_from_boot_entropy(rng: &mut ChaCha20Rng) {
+    let mut last = Cpu::rdtsc();
+    for i in 0..BOOT_TSC_SAMPLES {
+        // Trabalho de duração variável entre amostras para expor jitter
+        for _ in 0..(last & 0x3F) + i as u64 {
+            core::hint::spin_loop();
+        }
+        let now = Cpu::rdtsc();
+        rng.reseed(now ^ now.wrapping_sub(last).rotate_left(32));
+        last = now;
+    }
+
+    let mut hardware = 0;
+    for _ in 0..4 {
+        if let Some(value) = Cpu::rdrand() {
+            rng.reseed(value);
+            hardware += 1;
+        }
+    }
+    if hardware == 0 {
+        crate::kwarn!("(Random) Sem rdrand; semente apenas do TSC");
+    }
+}
+
+/// Preenche `buf` com bytes do CSPRNG do kernel.
+pub fn kernel_rng_fill(buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(CHUNK_SIZE) {
+        let mut rng = RNG.lock();
+        if !rng.is_seeded() {
+            seed_from_boot_entropy(&mut rng);
+        }
+        rng.reseed(Cpu::rdtsc());
+        rng.fill(chunk);
+    }
+}
+
+// =============================================================================
+// DISPOSITIVO
+// =============================================================================
+
+pub struct RandomDevice;
+
+impl DeviceOps for RandomDevice {
+    fn read(&self, _offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        kernel_rng_fill(buf);
+        Ok(buf.len())
+    }
+
+    /// Escritas são aceitas e misturadas ao estado, como no Linux.
+    fn write(&self, _offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        let mut rng = RNG.lock();
+        for chunk in buf.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            rng.reseed(u64::from_le_bytes(word));
+        }
+        Ok(buf.len())
+    }
+}
+
+impl CharDevice for RandomDevice {
+    fn name(&self) -> &'static str {
+        "urandom"
+    }
+
+    fn number(&self) -> DevNum {
+        DEV_URANDOM
+    }
+}
+
+/// Nunca bloqueia: sempre pronto para ler e escrever
+impl Pollable for RandomDevice {}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 8439, seção 2.3.2
+    #[test]
+    fn test_chacha20_block_vector() {
+        let mut key = [0u32; 8];
+        for (i, word) in key.iter_mut().enumerate() {
+            let b = (i * 4) as u32;
+            *word = u32::from_le_bytes([b as u8, b as u8 + 1, b as u8 + 2, b as u8 + 3]);
+        }
+        let block = chacha20_block(&key, &[1, 0x0900_0000, 0x4a00_0000, 0]);
+        assert_eq!(
+            block,
+            [
+                0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204,
+                0x4e6cd4c3, 0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de,
+                0xe883d0cb, 0x4e3c50a2,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_distribution() {
+        let mut rng = ChaCha20Rng::new();
+        rng.reseed(0x1234_5678_9ABC_DEF0);
+
+        let mut buf = alloc::vec![0u8; 256 * 256];
+        rng.fill(&mut buf);
+
+        // Esperado: 256 ocorrências por valor (desvio padrão ~16)
+        let mut counts = [0usize; 256];
+        for &b in &buf {
+            counts[b as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| (160..=352).contains(&c)));
+
+        // Leituras seguidas não se repetem
+        let mut again = alloc::vec![0u8; buf.len()];
+        rng.fill(&mut again);
+        assert_ne!(buf, again);
+    }
+
+    #[test]
+    fn test_reseed_diverges() {
+        let mut a = ChaCha20Rng::new();
+        let mut b = ChaCha20Rng::new();
+        a.reseed(42);
+        b.reseed(42);
+
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        a.fill(&mut x);
+        b.fill(&mut y);
+        assert_eq!(x, y);
+
+        // Cópias do mesmo estado divergem após misturar entropia distinta
+        a.reseed(1);
+        b.reseed(2);
+        a.fill(&mut x);
+        b.fill(&mut y);
+        assert_ne!(x, y);
+    }
+}
@@ -0,0 +1,243 @@
+//! # DevFS - dispositivos em /dev
+//!
+//! Registro de dispositivos de caractere por nome, montado no VFS em `/dev`.
+//! O número de inode de um dispositivo é o seu `DevNum`; 0 é o diretório.
+//!
+//! | Caminho        | Dispositivo           | Número |
+//! |----------------|-----------------------|--------|
+//! | `/dev/null`    | [`NullDevice`]        | 1:3    |
+//! | `/dev/zero`    | [`ZeroDevice`]        | 1:5    |
+//! | `/dev/urandom` | [`RandomDevice`]      | 1:9    |
+//! | `/dev/console` | [`ConsoleDevice`]     | 5:1    |
+//! | `/dev/input`   | [`InputDevice`]       | 13:64  |
+//! | `/dev/fb0`     | [`FramebufferDevice`] | 29:0   |
+//!
+//! `/dev/fb0` só existe se o bootloader entregou um framebuffer.
+
+pub mod devices;
+
+pub use devices::{
+    kernel_rng_fill, ConsoleDevice, FramebufferDevice, InputDevice, NullDevice, RandomDevice,
+    ZeroDevice,
+};
+
+use crate::fs::vfs::inode::{DirEntry, FileType, FsError, InodeNum};
+use crate::fs::vfs::Filesystem;
+use crate::sched::sync::Pollable;
+use crate::sync::Spinlock;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// =============================================================================
+// NÚMEROS DE DISPOSITIVO
+// =============================================================================
+
+/// Número de dispositivo (major:minor)
+pub type DevNum = u32;
+
+/// Compõe um `DevNum` (12 bits de major, 20 de minor)
+pub const fn makedev(major: u32, minor: u32) -> DevNum {
+    (major << 20) | (minor & 0xF_FFFF)
+}
+
+pub const DEV_NULL: DevNum = makedev(1, 3);
+pub const DEV_ZERO: DevNum = makedev(1, 5);
+pub const DEV_RANDOM: DevNum = makedev(1, 8);
+pub const DEV_URANDOM: DevNum = makedev(1, 9);
+pub const DEV_CONSOLE: DevNum = makedev(5, 1);
+/// Como o `event0` do Linux
+pub const DEV_INPUT: DevNum = makedev(13, 64);
+pub const DEV_FB0: DevNum = makedev(29, 0);
+
+/// Ponto de montagem no VFS
+pub const DEVFS_MOUNT_POINT: &str = "/dev";
+
+// =============================================================================
+// TRAITS
+// =============================================================================
+
+/// Operações de I/O de um dispositivo
+pub trait DeviceOps: Send + Sync {
+    /// Lê a partir de `offset` (ignorado por dispositivos de fluxo)
+    fn read(&self, offset: u64, buf: &mut [u8]) -> Result<usize, FsError>;
+
+    /// Escreve a partir de `offset` (ignorado por dispositivos de fluxo)
+    fn write(&self, offset: u64, buf: &[u8]) -> Result<usize, FsError>;
+}
+
+/// Dispositivo de caractere exposto em `/dev` (e esperável por `poll`)
+pub trait CharDevice: DeviceOps + Pollable {
+    /// Nome do nó em `/dev`
+    fn name(&self) -> &'static str;
+
+    /// Número do dispositivo
+    fn number(&self) -> DevNum;
+}
+
+// =============================================================================
+// REGISTRO
+// =============================================================================
+
+/// Dispositivos registrados, por nome
+static DEVICES: Spinlock<BTreeMap<String, Arc<dyn CharDevice>>> = Spinlock::new(BTreeMap::new());
+
+/// Diretório `/dev`
+pub struct DevFS;
+
+impl DevFS {
+    /// Registra um dispositivo em `/dev/<name>`.
+    pub fn register(device: Arc<dyn CharDevice>) -> Result<(), FsError> {
+        let mut devices = DEVICES.lock();
+        let name = device.name();
+        if devices.contains_key(name) || devices.values().any(|d| d.number() == device.number()) {
+            return Err(FsError::AlreadyExists);
+        }
+        devices.insert(String::from(name), device);
+        Ok(())
+    }
+
+    /// Remove `/dev/<name>`.
+    pub fn unregister(name: &str) -> Result<(), FsError> {
+        DEVICES
+            .lock()
+            .remove(name)
+            .map(|_| ())
+            .ok_or(FsError::NotFound)
+    }
+
+    /// Dispositivo em `path` (`/dev/null` ou `null`)
+    pub fn open(path: &str) -> Result<Arc<dyn CharDevice>, FsError> {
+        let name = device_name(path);
+        DEVICES.lock().get(name).cloned().ok_or(FsError::NotFound)
+    }
+
+    /// Lê do dispositivo em `path`
+    pub fn read(path: &str, buf: &mut [u8]) -> Result<usize, FsError> {
+        Self::open(path)?.read(0, buf)
+    }
+
+    /// Escreve no dispositivo em `path`
+    pub fn write(path: &str, buf: &[u8]) -> Result<usize, FsError> {
+        Self::open(path)?.write(0, buf)
+    }
+
+    /// Nomes registrados, em ordem
+    pub fn list() -> Vec<String> {
+        DEVICES.lock().keys().cloned().collect()
+    }
+
+    fn by_number(ino: InodeNum) -> Result<Arc<dyn CharDevice>, FsError> {
+        if ino == ROOT_INO {
+            return Err(FsError::IsDirectory);
+        }
+        DEVICES
+            .lock()
+            .values()
+            .find(|d| d.number() as InodeNum == ino)
+            .cloned()
+            .ok_or(FsError::NotFound)
+    }
+}
+
+const ROOT_INO: InodeNum = 0;
+
+impl Filesystem for DevFS {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        if path.is_empty() {
+            return Ok(ROOT_INO);
+        }
+        Ok(Self::open(path)?.number() as InodeNum)
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        Self::by_number(ino)?.read(offset, buf)
+    }
+
+    fn write(&self, ino: InodeNum, offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        Self::by_number(ino)?.write(offset, buf)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        if ino != ROOT_INO {
+            return Err(FsError::NotDirectory);
+        }
+        Ok(DEVICES
+            .lock()
+            .iter()
+            .map(|(name, device)| DirEntry {
+                name: name.clone(),
+                ino: device.number() as InodeNum,
+                file_type: FileType::CharDevice,
+            })
+            .collect())
+    }
+
+    /// Dispositivos não têm tamanho: `TRUNCATE` é ignorado
+    fn truncate(&self, ino: InodeNum, _size: u64) -> Result<(), FsError> {
+        Self::by_number(ino).map(|_| ())
+    }
+
+    fn read_only(&self) -> bool {
+        false
+    }
+}
+
+/// Nome do dispositivo em um caminho absoluto ou relativo a `/dev`
+fn device_name(path: &str) -> &str {
+    let path = path.trim_start_matches('/');
+    path.strip_prefix("dev/").unwrap_or(path)
+}
+
+// =============================================================================
+// INICIALIZAÇÃO
+// =============================================================================
+
+/// Registra os dispositivos essenciais e monta `/dev`
+pub fn init() {
+    register_essential_devices();
+    if crate::fs::vfs::mount(DEVFS_MOUNT_POINT, Arc::new(DevFS)).is_err() {
+        crate::kwarn!("(DevFS) Ponto de montagem ocupado:", DEVFS_MOUNT_POINT);
+    }
+}
+
+/// Registra null, zero, urandom, console e input (e fb0, se houver
+/// framebuffer)
+pub fn register_essential_devices() {
+    let mut essentials: Vec<Arc<dyn CharDevice>> = alloc::vec![
+        Arc::new(NullDevice),
+        Arc::new(ZeroDevice),
+        Arc::new(RandomDevice),
+        Arc::new(ConsoleDevice),
+        Arc::new(InputDevice),
+    ];
+    if crate::drivers::display::console::is_active() {
+        essentials.push(Arc::new(FramebufferDevice));
+    }
+    for device in essentials {
+        let name = device.name();
+        if DevFS::register(device).is_ok() {
+            crate::kinfo!("(DevFS) Registrado /dev/", name);
+        } else {
+            crate::kwarn!("(DevFS) Dispositivo já registrado:", name);
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_name() {
+        assert_eq!(device_name("/dev/null"), "null");
+        assert_eq!(device_name("dev/zero"), "zero");
+        assert_eq!(device_name("console"), "console");
+        assert_eq!(device_name("/console"), "console");
+    }
+}
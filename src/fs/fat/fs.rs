@@ -1,12 +1,20 @@
 //! # FatFs - Struct Principal do Filesystem FAT
 //!
 //! Versão Stack-Safe e Otimizada.
+//!
+//! O conteúdo dos arquivos passa pelo page cache (`mm::cache::filemap`):
+//! a primeira leitura de uma página vai ao disco, as seguintes não. Escritas
+//! ficam em páginas sujas até o [`sync`](FatFs::sync); só sobrescrevem dados
+//! dentro do tamanho atual do arquivo (não alocam clusters).
 
 use super::bpb::Bpb;
 use super::dir::DirEntry;
 use super::PublicDirEntry;
-use crate::drivers::block::BlockDevice;
-use crate::fs::vfs::inode::FsError;
+use crate::drivers::block::{partition, BlockDevice, PartitionDevice, PartitionError};
+use crate::fs::vfs::inode::{DirEntry as VfsDirEntry, FsError, InodeNum};
+use crate::fs::vfs::Filesystem;
+use crate::mm::cache::filemap::{self, PageIo};
+use crate::mm::cache::pagecache::DevId;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
@@ -26,32 +34,43 @@ pub enum FatType {
 // =============================================================================
 
 pub struct FatFs {
-    device: Arc<dyn BlockDevice>,
+    /// Partição do FAT (LBA 0 = boot sector)
+    device: PartitionDevice,
     bpb: Bpb,
     fat_type: FatType,
-    partition_offset: u64,
+    /// Dispositivo das páginas deste FAT no page cache
+    cache_dev: DevId,
 }
 
-impl FatFs {
-    pub fn mount(device: Arc<dyn BlockDevice>) -> Result<Self, FsError> {
-        let mut sector0 = [0u8; 512];
-        device
-            .read_block(0, &mut sector0)
-            .map_err(|_| FsError::IoError)?;
+/// Onde está o FAT em `device`: o disco inteiro, se o LBA 0 já é um boot
+/// sector, ou a primeira partição FAT da tabela.
+pub fn find_partition(device: Arc<dyn BlockDevice>) -> Result<PartitionDevice, FsError> {
+    let mut sector0 = [0u8; 512];
+    device
+        .read_block(0, &mut sector0)
+        .map_err(|_| FsError::IoError)?;
 
-        let partition_start = if sector0[0] == 0xEB || sector0[0] == 0xE9 {
-            0u64
-        } else if sector0[510] == 0x55 && sector0[511] == 0xAA {
-            let part_entry = &sector0[0x1BE..0x1BE + 16];
-            u32::from_le_bytes([part_entry[8], part_entry[9], part_entry[10], part_entry[11]])
-                as u64
-        } else {
-            return Err(FsError::InvalidFormat);
-        };
+    if sector0[0] == 0xEB || sector0[0] == 0xE9 {
+        return Ok(PartitionDevice::whole(device));
+    }
 
+    let partitions = partition::read_partitions(device.as_ref()).map_err(|e| match e {
+        PartitionError::Io(_) => FsError::IoError,
+        _ => FsError::InvalidFormat,
+    })?;
+    let fat = partitions
+        .iter()
+        .find(|p| p.is_fat())
+        .ok_or(FsError::InvalidFormat)?;
+    PartitionDevice::from_partition(device, fat).map_err(|_| FsError::InvalidFormat)
+}
+
+impl FatFs {
+    /// Monta o FAT que começa no LBA 0 de `device`
+    pub fn mount(device: PartitionDevice) -> Result<Self, FsError> {
         let mut boot_sector = [0u8; 512];
         device
-            .read_block(partition_start, &mut boot_sector)
+            .read_block(0, &mut boot_sector)
             .map_err(|_| FsError::IoError)?;
 
         let bpb = Bpb::parse(&boot_sector).ok_or(FsError::InvalidFormat)?;
@@ -69,7 +88,7 @@ impl FatFs {
             device,
             bpb,
             fat_type,
-            partition_offset: partition_start,
+            cache_dev: filemap::new_dev(),
         })
     }
 
@@ -81,23 +100,18 @@ impl FatFs {
             .map_err(|_| FsError::IoError)
     }
 
-    /// Lê um cluster inteiro para um buffer (usado por file.rs)
+    /// Lê um cluster inteiro para um buffer
     pub fn read_cluster(&self, cluster: u32, buf: &mut [u8]) -> Result<usize, FsError> {
         let cluster_size = self.bpb.cluster_size();
         if buf.len() < cluster_size {
             return Err(FsError::IoError);
         }
 
-        let first_sector = self.bpb.cluster_to_sector(cluster) + self.partition_offset;
-        let sectors_per_cluster = self.bpb.sectors_per_cluster as u64;
-
-        for i in 0..sectors_per_cluster {
-            let sector = first_sector + i;
-            let offset = (i as usize) * 512;
-            self.device
-                .read_block(sector, &mut buf[offset..offset + 512])
-                .map_err(|_| FsError::IoError)?;
-        }
+        // Um pedido só para o cluster inteiro
+        let first_sector = self.bpb.cluster_to_sector(cluster);
+        self.device
+            .read_blocks(first_sector, self.bpb.sectors_per_cluster as usize, buf)
+            .map_err(|_| FsError::IoError)?;
 
         Ok(cluster_size)
     }
@@ -110,8 +124,7 @@ impl FatFs {
             FatType::Fat32 => (cluster * 4) as usize,
         };
 
-        let fat_sector =
-            self.partition_offset + self.bpb.reserved_sectors as u64 + (fat_offset / 512) as u64;
+        let fat_sector = self.bpb.reserved_sectors as u64 + (fat_offset / 512) as u64;
         let entry_offset = fat_offset % 512;
 
         if self.read_sector(fat_sector, &mut sector_buf).is_err() {
@@ -202,34 +215,9 @@ impl FatFs {
     }
 
     fn read_file_data(&self, first_cluster: u32, size: u32) -> Option<Vec<u8>> {
-        let mut data = Vec::with_capacity(size as usize);
-        let sectors_per_cluster = self.bpb.sectors_per_cluster as u64;
-        let mut sector_buf = [0u8; 512];
-        let mut remaining = size as usize;
-        let mut cluster = first_cluster;
-
-        loop {
-            let first_sector = self.bpb.cluster_to_sector(cluster) + self.partition_offset;
-            for i in 0..sectors_per_cluster {
-                if self.read_sector(first_sector + i, &mut sector_buf).is_err() {
-                    return None;
-                }
-                let to_copy = remaining.min(512);
-                data.extend_from_slice(&sector_buf[..to_copy]);
-                remaining -= to_copy;
-                if remaining == 0 {
-                    break;
-                }
-            }
-
-            if remaining == 0 {
-                break;
-            }
-            match self.next_cluster(cluster) {
-                Some(next) => cluster = next,
-                None => break,
-            }
-        }
+        let mut data = alloc::vec![0u8; size as usize];
+        let read = self.read_at(first_cluster, size, 0, &mut data).ok()?;
+        data.truncate(read);
         Some(data)
     }
 
@@ -244,7 +232,7 @@ impl FatFs {
         let mut cluster = dir_cluster;
 
         loop {
-            let first_sector = self.bpb.cluster_to_sector(cluster) + self.partition_offset;
+            let first_sector = self.bpb.cluster_to_sector(cluster);
             for s in 0..sectors_per_cluster {
                 if self.read_sector(first_sector + s, &mut sector_buf).is_err() {
                     return None;
@@ -280,9 +268,7 @@ impl FatFs {
 
     fn find_in_root_dir(&self, name: &str) -> Option<DirEntry> {
         let root_dir_sectors = ((self.bpb.root_entry_count as u32 * 32) + 511) / 512;
-        let first_root_sector = self.partition_offset
-            + self.bpb.reserved_sectors as u64
-            + (self.bpb.num_fats as u64 * self.bpb.sectors_per_fat() as u64);
+        let first_root_sector = self.bpb.root_dir_sector();
         let mut sector_buf = [0u8; 512];
 
         for i in 0..root_dir_sectors as u64 {
@@ -384,9 +370,7 @@ impl FatFs {
 
     fn list_root_dir(&self, entries: &mut Vec<PublicDirEntry>) {
         let root_dir_sectors = ((self.bpb.root_entry_count as u32 * 32) + 511) / 512;
-        let first_root_sector = self.partition_offset
-            + self.bpb.reserved_sectors as u64
-            + (self.bpb.num_fats as u64 * self.bpb.sectors_per_fat() as u64);
+        let first_root_sector = self.bpb.root_dir_sector();
         let mut sector_buf = [0u8; 512];
 
         for i in 0..root_dir_sectors as u64 {
@@ -417,7 +401,7 @@ impl FatFs {
         let mut cluster = start_cluster;
 
         loop {
-            let first_sector = self.bpb.cluster_to_sector(cluster) + self.partition_offset;
+            let first_sector = self.bpb.cluster_to_sector(cluster);
             for s in 0..sectors_per_cluster {
                 if self.read_sector(first_sector + s, &mut sector_buf).is_err() {
                     break;
@@ -445,4 +429,263 @@ impl FatFs {
     pub fn cluster_size(&self) -> usize {
         self.bpb.cluster_size()
     }
+
+    /// Lê bytes de um arquivo (`first_cluster`, `size`) a partir de `offset`,
+    /// pelo page cache. Lê até o fim de `buf` ou do arquivo.
+    pub fn read_at(
+        &self,
+        first_cluster: u32,
+        size: u32,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize, FsError> {
+        let ino = encode_ino(first_cluster, size, false);
+        filemap::read(self.cache_dev, self, ino, size as u64, offset, buf)
+    }
+
+    /// Grava no disco as páginas sujas deste FAT
+    pub fn sync(&self) -> Result<(), FsError> {
+        filemap::flush(self.cache_dev, self)?;
+        self.device.flush().map_err(|_| FsError::IoError)
+    }
+
+    /// Percorre os clusters de um arquivo que cobrem `len` bytes a partir de
+    /// `offset`, chamando `f(cluster, offset no cluster, posição, bytes)`.
+    /// Retorna quantos bytes a cadeia cobriu (menos que `len` se acabar antes).
+    fn walk_extent(
+        &self,
+        first_cluster: u32,
+        offset: u64,
+        len: usize,
+        mut f: impl FnMut(u32, usize, usize, usize) -> Result<(), FsError>,
+    ) -> Result<usize, FsError> {
+        let cluster_size = self.cluster_size();
+        let mut cluster = first_cluster;
+        for _ in 0..offset / cluster_size as u64 {
+            match self.next_cluster(cluster) {
+                Some(next) => cluster = next,
+                None => return Ok(0),
+            }
+        }
+
+        let mut in_cluster = (offset % cluster_size as u64) as usize;
+        let mut done = 0;
+        while done < len && cluster >= 2 {
+            let n = (cluster_size - in_cluster).min(len - done);
+            f(cluster, in_cluster, done, n)?;
+            done += n;
+            in_cluster = 0;
+            if done < len {
+                match self.next_cluster(cluster) {
+                    Some(next) => cluster = next,
+                    None => break,
+                }
+            }
+        }
+        Ok(done)
+    }
+
+    /// Lê do disco, sem cache, `buf.len()` bytes do arquivo a partir de
+    /// `offset`
+    fn read_extent(
+        &self,
+        first_cluster: u32,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize, FsError> {
+        let mut cluster_buf = alloc::vec![0u8; self.cluster_size()];
+        self.walk_extent(first_cluster, offset, buf.len(), |cluster, at, pos, n| {
+            self.read_cluster(cluster, &mut cluster_buf)?;
+            buf[pos..pos + n].copy_from_slice(&cluster_buf[at..at + n]);
+            Ok(())
+        })
+    }
+
+    /// Grava no disco, sem cache, `buf` no arquivo a partir de `offset`
+    /// (read-modify-write de cada cluster)
+    fn write_extent(&self, first_cluster: u32, offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        let cluster_size = self.cluster_size();
+        let mut cluster_buf = alloc::vec![0u8; cluster_size];
+        self.walk_extent(first_cluster, offset, buf.len(), |cluster, at, pos, n| {
+            if n < cluster_size {
+                self.read_cluster(cluster, &mut cluster_buf)?;
+            }
+            cluster_buf[at..at + n].copy_from_slice(&buf[pos..pos + n]);
+            self.device
+                .write_blocks(self.bpb.cluster_to_sector(cluster), &cluster_buf)
+                .map_err(|_| FsError::IoError)
+        })
+    }
+
+    /// Cluster do diretório raiz (0 = área fixa do FAT12/16)
+    fn root_cluster(&self) -> u32 {
+        if self.fat_type == FatType::Fat32 {
+            self.bpb.root_cluster
+        } else {
+            0
+        }
+    }
+
+    /// Resolve um caminho relativo à raiz para o número de inode do nó
+    fn resolve(&self, path: &str) -> Result<InodeNum, FsError> {
+        let mut ino = encode_ino(self.root_cluster(), 0, true);
+        for component in path.split('/').filter(|s| !s.is_empty()) {
+            let (cluster, _, is_dir) = decode_ino(ino);
+            if !is_dir {
+                return Err(FsError::NotDirectory);
+            }
+            let entry = self
+                .find_entry(cluster, component)
+                .ok_or(FsError::NotFound)?;
+            ino = encode_ino(entry.first_cluster(), entry.size, entry.is_directory());
+        }
+        Ok(ino)
+    }
+}
+
+// =============================================================================
+// PAGE CACHE
+// =============================================================================
+
+impl PageIo for FatFs {
+    fn read_page(&self, ino: InodeNum, index: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let (cluster, size, _) = decode_ino(ino);
+        let offset = index * buf.len() as u64;
+        let len = (size as u64).saturating_sub(offset).min(buf.len() as u64) as usize;
+        let read = self.read_extent(cluster, offset, &mut buf[..len])?;
+        buf[read..].fill(0);
+        Ok(())
+    }
+
+    fn write_page(&self, ino: InodeNum, index: u64, buf: &[u8]) -> Result<(), FsError> {
+        let (cluster, size, _) = decode_ino(ino);
+        let offset = index * buf.len() as u64;
+        let len = (size as u64).saturating_sub(offset).min(buf.len() as u64) as usize;
+        self.write_extent(cluster, offset, &buf[..len])?;
+        Ok(())
+    }
+}
+
+impl Drop for FatFs {
+    fn drop(&mut self) {
+        if self.sync().is_err() {
+            crate::kerror!("(FAT) Páginas sujas perdidas ao desmontar");
+        }
+        filemap::invalidate(self.cache_dev, None);
+    }
+}
+
+// =============================================================================
+// VFS
+// =============================================================================
+
+/// Bit de diretório no número de inode
+const INO_DIR: u64 = 1 << 63;
+
+/// FAT não tem números de inode: o número carrega o próprio nó
+/// (`INO_DIR | cluster << 32 | tamanho`), sem tabela a manter.
+pub(super) fn encode_ino(cluster: u32, size: u32, is_dir: bool) -> InodeNum {
+    let dir = if is_dir { INO_DIR } else { 0 };
+    dir | ((cluster as u64) << 32) | size as u64
+}
+
+/// Inverso de `encode_ino`: (cluster, tamanho, é diretório)
+fn decode_ino(ino: InodeNum) -> (u32, u32, bool) {
+    (
+        ((ino & !INO_DIR) >> 32) as u32,
+        ino as u32,
+        ino & INO_DIR != 0,
+    )
+}
+
+impl Filesystem for FatFs {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        self.resolve(path)
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let (cluster, size, is_dir) = decode_ino(ino);
+        if is_dir {
+            return Err(FsError::IsDirectory);
+        }
+        self.read_at(cluster, size, offset, buf)
+    }
+
+    /// Sobrescreve dados dentro do tamanho atual do arquivo
+    fn write(&self, ino: InodeNum, offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        let (_, size, is_dir) = decode_ino(ino);
+        if is_dir {
+            return Err(FsError::IsDirectory);
+        }
+        if self.device.is_read_only() {
+            return Err(FsError::ReadOnly);
+        }
+        let room = (size as u64).saturating_sub(offset);
+        if room == 0 && !buf.is_empty() {
+            return Err(FsError::NoSpace);
+        }
+        let len = buf.len().min(room as usize);
+        filemap::write(self.cache_dev, self, ino, offset, &buf[..len])
+    }
+
+    fn size(&self, ino: InodeNum) -> Result<u64, FsError> {
+        match decode_ino(ino) {
+            (_, _, true) => Err(FsError::IsDirectory),
+            (_, size, false) => Ok(size as u64),
+        }
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<VfsDirEntry>, FsError> {
+        let (cluster, _, is_dir) = decode_ino(ino);
+        if !is_dir {
+            return Err(FsError::NotDirectory);
+        }
+
+        let mut entries = Vec::new();
+        if cluster == 0 && self.fat_type != FatType::Fat32 {
+            self.list_root_dir(&mut entries);
+        } else {
+            self.list_cluster_dir(cluster, &mut entries);
+        }
+        Ok(entries
+            .iter()
+            .filter(|e| e.name != "." && e.name != "..")
+            .map(PublicDirEntry::to_vfs)
+            .collect())
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        FatFs::sync(self)
+    }
+
+    fn read_only(&self) -> bool {
+        self.device.is_read_only()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ino_roundtrip() {
+        for (cluster, size, is_dir) in [
+            (0, 0, true),
+            (0, 0, false),
+            (2, 4096, false),
+            (0x0FFF_FFF7, u32::MAX, false),
+            (0x0FFF_FFF7, 0, true),
+        ] {
+            assert_eq!(
+                decode_ino(encode_ino(cluster, size, is_dir)),
+                (cluster, size, is_dir)
+            );
+        }
+        // Raiz do FAT16 e arquivo vazio não colidem
+        assert_ne!(encode_ino(0, 0, true), encode_ino(0, 0, false));
+    }
 }
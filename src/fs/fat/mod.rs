@@ -26,16 +26,21 @@ pub mod fs;
 // Re-exports públicos
 pub use fs::{FatFs, FatType};
 
+use crate::fs::vfs::inode::{DirEntry, FileType};
 use crate::sync::Spinlock;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 // =============================================================================
 // INSTÂNCIA GLOBAL
 // =============================================================================
 
-/// Instância global do FAT montado
-static MOUNTED_FAT: Spinlock<Option<FatFs>> = Spinlock::new(None);
+/// Instância global do FAT montado (a mesma montada no VFS)
+static MOUNTED_FAT: Spinlock<Option<Arc<FatFs>>> = Spinlock::new(None);
+
+/// Ponto de montagem do primeiro disco FAT no VFS
+pub const FAT_MOUNT_POINT: &str = "/data";
 
 // =============================================================================
 // API PÚBLICA
@@ -47,9 +52,13 @@ pub fn init() {
 
     // Tentar montar o primeiro dispositivo de bloco
     if let Some(device) = crate::drivers::block::first_device() {
-        match FatFs::mount(device) {
+        match fs::find_partition(device).and_then(FatFs::mount) {
             Ok(fat) => {
                 crate::kinfo!("(FAT) Filesystem montado com sucesso!");
+                let fat = Arc::new(fat);
+                if crate::fs::vfs::mount(FAT_MOUNT_POINT, fat.clone()).is_err() {
+                    crate::kwarn!("(FAT) Ponto de montagem ocupado:", FAT_MOUNT_POINT);
+                }
                 *MOUNTED_FAT.lock() = Some(fat);
             }
             Err(e) => {
@@ -61,24 +70,19 @@ pub fn init() {
     }
 }
 
-/// Lê um arquivo do FAT montado
+/// Lê um arquivo do FAT montado (caminho relativo à raiz do disco)
 pub fn read_file(path: &str) -> Option<Vec<u8>> {
-    let guard = MOUNTED_FAT.lock();
-    if let Some(fat) = guard.as_ref() {
-        fat.read_file(path)
-    } else {
-        None
-    }
+    mounted()?.read_file(path)
 }
 
 /// Lista entradas de um diretório do FAT montado
 pub fn list_directory(path: &str) -> Option<Vec<PublicDirEntry>> {
-    let guard = MOUNTED_FAT.lock();
-    if let Some(fat) = guard.as_ref() {
-        fat.list_directory(path)
-    } else {
-        None
-    }
+    mounted()?.list_directory(path)
+}
+
+/// FAT montado, sem manter o lock durante o I/O
+fn mounted() -> Option<Arc<FatFs>> {
+    MOUNTED_FAT.lock().clone()
 }
 
 // =============================================================================
@@ -93,3 +97,18 @@ pub struct PublicDirEntry {
     pub size: u32,
     pub first_cluster: u32,
 }
+
+impl PublicDirEntry {
+    /// Converte para a entrada de diretório do VFS
+    pub fn to_vfs(&self) -> DirEntry {
+        DirEntry {
+            name: self.name.clone(),
+            ino: fs::encode_ino(self.first_cluster, self.size, self.is_directory),
+            file_type: if self.is_directory {
+                FileType::Directory
+            } else {
+                FileType::Regular
+            },
+        }
+    }
+}
@@ -1,64 +1,189 @@
 #![allow(dead_code)]
 //! InitramFS - filesystem em memória do boot
-
-use crate::fs::vfs::inode::{DirEntry, FsError, InodeOps};
+//!
+//! O tar do bootloader é lido uma vez em [`init`] e vira um índice de
+//! diretórios: cada nó é um diretório (filhos por nome) ou um arquivo (a
+//! posição da entrada no tar). Headers são lidos só pelo parser de
+//! `fs::tar`. O número de inode é a posição do nó no
+//! índice; 0 é a raiz. Diretórios que só aparecem como prefixo de um caminho
+//! (sem entrada `'5'` própria) são criados implicitamente.
+
+use crate::fs::tar::{EntryKind, TarArchive, TarEntry};
+use crate::fs::vfs::inode::{DirEntry, FileType, FsError, InodeNum, InodeOps};
+use crate::fs::vfs::{path, Filesystem};
 use crate::mm::VirtAddr;
 use crate::sync::Spinlock;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::slice;
 
 /// Armazenamento global do Initramfs (Raw Bytes)
 static INITRAMFS_DATA: Spinlock<Option<&'static [u8]>> = Spinlock::new(None);
 
-/// Header USTAR (Tamanho fixo 512 bytes)
-const TAR_BLOCK_SIZE: usize = 512;
-const TAR_NAME_OFFSET: usize = 0;
-const TAR_NAME_LEN: usize = 100;
-const TAR_SIZE_OFFSET: usize = 124;
-const TAR_SIZE_LEN: usize = 12;
-const TAR_TYPE_OFFSET: usize = 156;
-const TAR_MAGIC_OFFSET: usize = 257;
-
-/// Helper para parsear octal
-fn parse_octal(data: &[u8]) -> usize {
-    let mut size = 0;
-    for &byte in data {
-        if byte < b'0' || byte > b'7' {
-            break;
+/// Índice de diretórios, montado em `init`
+static INDEX: Spinlock<Option<Index>> = Spinlock::new(None);
+
+const ROOT_INO: InodeNum = 0;
+
+enum Node {
+    Dir(BTreeMap<String, InodeNum>),
+    /// `TarEntry::offset` da entrada
+    File(usize),
+}
+
+/// Árvore de diretórios do tar
+struct Index {
+    nodes: Vec<Node>,
+}
+
+impl Index {
+    /// Indexa todas as entradas de `data`
+    ///
+    /// Nomes são normalizados (`./`, `/` inicial e barras repetidas
+    /// ignorados). Entradas que não são arquivo nem diretório são puladas.
+    fn build(data: &[u8]) -> Self {
+        let mut index = Self {
+            nodes: alloc::vec![Node::Dir(BTreeMap::new())],
+        };
+        for entry in TarArchive::new(data).entries() {
+            let normalized = path::normalize(entry.name);
+            match entry.kind {
+                EntryKind::Directory => {
+                    index.mkdir_p(&normalized);
+                }
+                EntryKind::File => {
+                    let Some((dir, name)) = split_last(&normalized) else {
+                        continue;
+                    };
+                    let Some(parent) = index.mkdir_p(dir) else {
+                        continue;
+                    };
+                    index.insert(parent, name, Node::File(entry.offset));
+                }
+                EntryKind::Other(_) => {}
+            }
+        }
+        index
+    }
+
+    /// Diretório `normalized`, criando os que faltarem. `None` se algum
+    /// componente for um arquivo.
+    fn mkdir_p(&mut self, normalized: &str) -> Option<InodeNum> {
+        let mut current = ROOT_INO;
+        for component in path::PathComponents::new(normalized) {
+            current = match self.child(current, component) {
+                Some(ino) if self.is_dir(ino) => ino,
+                Some(_) => return None,
+                None => self.insert(current, component, Node::Dir(BTreeMap::new())),
+            };
+        }
+        Some(current)
+    }
+
+    /// Adiciona `node` como `name` em `parent`; um nome repetido aponta para
+    /// o nó novo (a última entrada do tar vence).
+    fn insert(&mut self, parent: InodeNum, name: &str, node: Node) -> InodeNum {
+        let ino = self.nodes.len() as InodeNum;
+        self.nodes.push(node);
+        if let Some(Node::Dir(children)) = self.nodes.get_mut(parent as usize) {
+            children.insert(String::from(name), ino);
+        }
+        ino
+    }
+
+    fn node(&self, ino: InodeNum) -> Result<&Node, FsError> {
+        self.nodes.get(ino as usize).ok_or(FsError::NotFound)
+    }
+
+    fn is_dir(&self, ino: InodeNum) -> bool {
+        matches!(self.nodes.get(ino as usize), Some(Node::Dir(_)))
+    }
+
+    fn child(&self, dir: InodeNum, name: &str) -> Option<InodeNum> {
+        match self.nodes.get(dir as usize)? {
+            Node::Dir(children) => children.get(name).copied(),
+            Node::File(_) => None,
+        }
+    }
+
+    /// Resolve um caminho relativo à raiz do initramfs
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        let normalized = path::normalize(path);
+        let mut current = ROOT_INO;
+        for component in path::PathComponents::new(&normalized) {
+            if !self.is_dir(current) {
+                return Err(FsError::NotDirectory);
+            }
+            current = self.child(current, component).ok_or(FsError::NotFound)?;
+        }
+        Ok(current)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        match self.node(ino)? {
+            Node::Dir(children) => Ok(children
+                .iter()
+                .map(|(name, &child)| DirEntry {
+                    name: name.clone(),
+                    ino: child,
+                    file_type: if self.is_dir(child) {
+                        FileType::Directory
+                    } else {
+                        FileType::Regular
+                    },
+                })
+                .collect()),
+            Node::File(_) => Err(FsError::NotDirectory),
         }
-        size = size * 8 + (byte - b'0') as usize;
     }
-    size
+
+    /// Posição da entrada de um arquivo no tar
+    fn entry_offset(&self, ino: InodeNum) -> Result<usize, FsError> {
+        match self.node(ino)? {
+            Node::File(offset) => Ok(*offset),
+            Node::Dir(_) => Err(FsError::IsDirectory),
+        }
+    }
+}
+
+/// Separa um caminho normalizado em (diretório, último componente).
+/// `None` para a raiz.
+fn split_last(normalized: &str) -> Option<(&str, &str)> {
+    let pos = normalized.rfind('/')?;
+    let name = &normalized[pos + 1..];
+    if name.is_empty() {
+        return None;
+    }
+    Some((&normalized[..pos], name))
 }
 
-/// Helper para alinhar para próximo bloco de 512 bytes
-fn align_up_512(size: usize) -> usize {
-    (size + TAR_BLOCK_SIZE - 1) & !(TAR_BLOCK_SIZE - 1)
+/// Entrada do tar de um arquivo do initramfs
+fn file_entry(ino: InodeNum) -> Result<TarEntry<'static>, FsError> {
+    let data = (*INITRAMFS_DATA.lock()).ok_or(FsError::NotFound)?;
+    let offset = INDEX
+        .lock()
+        .as_ref()
+        .ok_or(FsError::NotFound)?
+        .entry_offset(ino)?;
+    TarArchive::new(data)
+        .entry_at(offset)
+        .ok_or(FsError::InvalidFormat)
 }
 
-/// Inode do initramfs
+/// Inode do initramfs (consulta o índice global)
 struct InitramfsInode {
-    data: *const u8,
-    size: usize,
+    ino: InodeNum,
 }
 
 impl InodeOps for InitramfsInode {
-    fn lookup(&self, _name: &str) -> Option<u64> {
-        // TODO: implementar lookup via VFS inode system
-        None
+    fn lookup(&self, name: &str) -> Option<InodeNum> {
+        INDEX.lock().as_ref()?.child(self.ino, name)
     }
 
     fn read(&self, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
-        let offset = offset as usize;
-        if offset >= self.size {
-            return Ok(0);
-        }
-
-        let to_read = buf.len().min(self.size - offset);
-        unsafe {
-            core::ptr::copy_nonoverlapping(self.data.add(offset), buf.as_mut_ptr(), to_read);
-        }
-        Ok(to_read)
+        let entry = file_entry(self.ino)?;
+        Ok(TarArchive::read_entry(&entry, offset as usize, buf))
     }
 
     fn write(&self, _offset: u64, _buf: &[u8]) -> Result<usize, FsError> {
@@ -66,138 +191,118 @@ impl InodeOps for InitramfsInode {
     }
 
     fn readdir(&self) -> Result<Vec<DirEntry>, FsError> {
-        Ok(Vec::new())
+        INDEX
+            .lock()
+            .as_ref()
+            .ok_or(FsError::NotFound)?
+            .readdir(self.ino)
     }
 }
 
-unsafe impl Sync for InitramfsInode {}
-unsafe impl Send for InitramfsInode {}
+/// Initramfs como backend do VFS.
+///
+/// Somente leitura; os números de inode são os do índice.
+pub struct InitramFs;
+
+impl Filesystem for InitramFs {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        INDEX.lock().as_ref().ok_or(FsError::NotFound)?.lookup(path)
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        InitramfsInode { ino }.read(offset, buf)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        InitramfsInode { ino }.readdir()
+    }
 
-/// Carrega initramfs da memória
+    fn size(&self, ino: InodeNum) -> Result<u64, FsError> {
+        Ok(file_entry(ino)?.data.len() as u64)
+    }
+}
+
+/// Carrega initramfs da memória e indexa seus diretórios
 pub fn init(addr: VirtAddr, size: usize) {
     crate::kinfo!("(InitramFS) Carregando de addr=", addr.as_u64());
     crate::kinfo!("(InitramFS) Tamanho:", size as u64);
 
     // SAFETY: O bootloader garante que esta memória é válida e contém o initramfs
-    let data = unsafe { slice::from_raw_parts(addr.as_ptr(), size) };
+    let data: &'static [u8] = unsafe { slice::from_raw_parts(addr.as_ptr(), size) };
+    let index = Index::build(data);
+    crate::kinfo!("(InitramFS) Nós indexados:", index.nodes.len() as u64);
+
     *INITRAMFS_DATA.lock() = Some(data);
+    *INDEX.lock() = Some(index);
 }
 
 /// Busca um arquivo no initramfs e retorna seus dados
 /// Usado diretamente pelo spawn() enquanto VFS não está pronto
 pub fn lookup_file(path: &str) -> Option<&'static [u8]> {
-    let guard = INITRAMFS_DATA.lock();
-    let data = (*guard)?;
+    let ino = INDEX.lock().as_ref()?.lookup(path).ok()?;
+    file_entry(ino).ok().map(|entry| entry.data)
+}
 
-    // Normalizar path: remover leading slashes
-    let path_bytes = path.as_bytes();
-    let mut start = 0;
-    while start < path_bytes.len() && (path_bytes[start] == b'/' || path_bytes[start] == b'.') {
-        start += 1;
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tar::builder::{finish, push};
+
+    fn names(index: &Index, path: &str) -> Vec<String> {
+        let dir = index.lookup(path).unwrap();
+        index
+            .readdir(dir)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
     }
-    let search_bytes = &path_bytes[start..];
-
-    crate::ktrace!("(InitramFS) Início do Loop de Busca");
-
-    let mut offset = 0;
-    while offset + TAR_BLOCK_SIZE <= data.len() {
-        let header = &data[offset..offset + TAR_BLOCK_SIZE];
-
-        // Verificar magic "ustar" (com ou sem null terminator, ou espaço)
-        if &header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5] != b"ustar" {
-            break;
-        }
-
-        // Ler tamanho primeiro para validar offset
-        let size = parse_octal(&header[TAR_SIZE_OFFSET..TAR_SIZE_OFFSET + TAR_SIZE_LEN]);
-
-        // Ler nome (com limite seguro) - MANUAL BYTES ONLY
-        let name_bytes = &header[TAR_NAME_OFFSET..TAR_NAME_OFFSET + TAR_NAME_LEN];
-
-        // Calcular tamanho manualmente
-        let mut name_len = 0;
-        while name_len < TAR_NAME_LEN {
-            if name_bytes[name_len] == 0 {
-                break;
-            }
-            name_len += 1;
-        }
-        let name_slice = &name_bytes[..name_len];
-
-        // Comparacao manual com "init" para debug (byte a byte)
-        if name_len >= 4 {
-            for i in 0..=name_len - 4 {
-                if &name_slice[i..i + 4] == b"init" {
-                    crate::ktrace!("(TAR) Check: (found init substring)");
-                    break;
-                }
-            }
-        }
-
-        // Normalização MANUAL de bytes (remove ./ e /)
-        let mut start_idx = 0;
-        while start_idx < name_len {
-            let c = name_slice[start_idx];
-            if c == b'.' || c == b'/' {
-                start_idx += 1;
-            } else {
-                break;
-            }
-        }
-        let normalized_bytes = &name_slice[start_idx..];
-
-        let is_match = normalized_bytes == search_bytes;
-
-        // Tipo '0' ou '\0' é arquivo normal
-        let type_flag = header[TAR_TYPE_OFFSET];
-        let is_file = type_flag == b'0' || type_flag == 0;
-
-        if is_file && is_match {
-            // Log do arquivo encontrado
-
-            crate::ktrace!("(TAR) Matched! Size:", size as u64);
-
-            let file_start = offset + TAR_BLOCK_SIZE;
 
-            // Segurança: Verificar limites antes de criar slice
-            if file_start
-                .checked_add(size)
-                .map_or(true, |end| end > data.len())
-            {
-                crate::kerror!("(InitramFS) Arquivo truncado ou overflow");
-                return None;
-            }
-
-            crate::ktrace!("(TAR) Criando slice...");
-            // Usando from_raw_parts para evitar problemas com lifetimes/bounds check implícitos
-            let ptr = unsafe { data.as_ptr().add(file_start) };
-            let slice = unsafe { slice::from_raw_parts(ptr, size) };
-
-            crate::ktrace!("(TAR) Retornando slice len:", slice.len() as u64);
-            return Some(slice);
-        }
-
-        // Avançar para próximo header
-        let file_block_size = align_up_512(size);
-
-        match offset
-            .checked_add(TAR_BLOCK_SIZE)
-            .and_then(|o| o.checked_add(file_block_size))
-        {
-            Some(new_offset) => {
-                if new_offset <= offset {
-                    crate::kerror!("(InitramFS) Loop infinito detectado!");
-                    break;
-                }
-                offset = new_offset;
-            }
-            None => {
-                crate::kerror!("(InitramFS) Overflow de offset!");
-                break;
-            }
-        }
+    #[test]
+    fn test_index_lists_tar_contents() {
+        let mut archive = Vec::new();
+        push(&mut archive, "./", b'5', &[]);
+        push(&mut archive, "./system/", b'5', &[]);
+        push(&mut archive, "./system/core/supervisor", b'0', b"ELF");
+        push(&mut archive, "apps/shell", 0, b"sh");
+        push(&mut archive, "/boot/empty/", b'5', &[]);
+        push(&mut archive, "./system/link", b'2', &[]);
+        finish(&mut archive);
+
+        let index = Index::build(&archive);
+        assert_eq!(names(&index, "/"), ["apps", "boot", "system"]);
+        assert_eq!(names(&index, "system"), ["core"]);
+        assert_eq!(names(&index, "/system/core"), ["supervisor"]);
+        assert!(names(&index, "boot/empty").is_empty());
+
+        let root = index.readdir(ROOT_INO).unwrap();
+        assert!(root.iter().all(|e| e.file_type == FileType::Directory));
+
+        // Caminhos aninhados chegam aos dados certos
+        let ino = index.lookup("system/core/supervisor").unwrap();
+        let tar = TarArchive::new(&archive);
+        let data = |ino| tar.entry_at(index.entry_offset(ino).unwrap()).unwrap().data;
+        assert_eq!(data(ino), b"ELF");
+        assert_eq!(data(index.lookup("/apps/shell").unwrap()), b"sh");
     }
 
-    crate::ktrace!("(InitramFS) Arquivo não encontrado.");
-    None
+    #[test]
+    fn test_index_lookup_errors() {
+        let mut archive = Vec::new();
+        push(&mut archive, "system/init", b'0', b"x");
+        finish(&mut archive);
+
+        let index = Index::build(&archive);
+        assert_eq!(index.lookup("system/missing"), Err(FsError::NotFound));
+        assert_eq!(index.lookup("system/init/x"), Err(FsError::NotDirectory));
+        assert_eq!(
+            index.readdir(index.lookup("system/init").unwrap()).err(),
+            Some(FsError::NotDirectory)
+        );
+        assert_eq!(index.entry_offset(ROOT_INO), Err(FsError::IsDirectory));
+    }
 }
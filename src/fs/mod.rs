@@ -52,12 +52,27 @@ pub use vfs::inode::{Inode, InodeOps};
 // FILESYSTEM IMPLEMENTATIONS
 // =============================================================================
 
+/// TAR - parser de arquivos ustar (usado pelo InitramFS)
+pub mod tar;
+
 /// InitramFS (boot) - TAR-based initial ramdisk
 pub mod initramfs;
 
+/// DevFS - dispositivos de caractere em /dev
+pub mod devfs;
+
+/// ProcFS - informações do kernel e das tasks em /proc
+pub mod procfs;
+
+/// SysFS - objetos do kernel (dispositivos de bloco) em /sys
+pub mod sysfs;
+
 /// FAT Filesystem (FAT16/FAT32)
 pub mod fat;
 
+/// TmpFS - filesystem volátil em memória
+pub mod tmpfs;
+
 /// RFS - Redstone File System (futuro)
 pub mod rfs;
 
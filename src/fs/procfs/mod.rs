@@ -0,0 +1,480 @@
+//! # ProcFS - informações do kernel em /proc
+//!
+//! Nada é armazenado: cada leitura gera o conteúdo na hora.
+//!
+//! | Caminho              | Conteúdo                                  |
+//! |----------------------|-------------------------------------------|
+//! | `/proc/version`      | Versão do kernel                          |
+//! | `/proc/filesystems`  | Backends de filesystem suportados         |
+//! | `/proc/meminfo`      | Memória física (PFM) e heap do kernel     |
+//! | `/proc/uptime`       | Segundos desde o boot                     |
+//! | `/proc/kmsg`         | Linhas retidas do log do kernel           |
+//! | `/proc/<pid>/status` | Nome, estado, prioridade e memória        |
+//! | `/proc/<pid>/cmdline`| Linha de comando (terminada em NUL)       |
+//! | `/proc/<pid>/stat`   | Uma linha com os mesmos campos, numéricos |
+//!
+//! Os diretórios `<pid>` vêm das tasks vivas no scheduler
+//! ([`crate::sched::core::tasks`]); um PID que já saiu simplesmente deixa de
+//! existir.
+
+use crate::fs::vfs::inode::{DirEntry, FileType, FsError, InodeNum};
+use crate::fs::vfs::Filesystem;
+use crate::sched::core::{task_info, tasks, TaskInfo};
+use crate::sched::task::TaskState;
+use crate::sys::types::Tid;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Ponto de montagem no VFS
+pub const PROCFS_MOUNT_POINT: &str = "/proc";
+
+// =============================================================================
+// ENTRADAS
+// =============================================================================
+
+/// Conteúdo de uma entrada fixa
+pub enum ProcContent {
+    /// Texto constante
+    Static(&'static str),
+    /// Gerado a cada leitura
+    Generated(fn() -> String),
+}
+
+/// Entrada fixa na raiz de `/proc`
+pub struct ProcEntry {
+    pub name: &'static str,
+    pub content: ProcContent,
+}
+
+impl ProcEntry {
+    fn render(&self) -> String {
+        match self.content {
+            ProcContent::Static(text) => String::from(text),
+            ProcContent::Generated(generate) => generate(),
+        }
+    }
+}
+
+static ENTRIES: [ProcEntry; 5] = [
+    ProcEntry {
+        name: "version",
+        content: ProcContent::Static(concat!(
+            "Forge (RedstoneOS) ",
+            env!("CARGO_PKG_VERSION"),
+            "\n"
+        )),
+    },
+    ProcEntry {
+        name: "filesystems",
+        content: ProcContent::Static(
+            "nodev\tproc\nnodev\tdevfs\nnodev\ttmpfs\n\tinitramfs\n\tfat\n",
+        ),
+    },
+    ProcEntry {
+        name: "meminfo",
+        content: ProcContent::Generated(meminfo),
+    },
+    ProcEntry {
+        name: "uptime",
+        content: ProcContent::Generated(uptime),
+    },
+    ProcEntry {
+        name: "kmsg",
+        content: ProcContent::Generated(kmsg),
+    },
+];
+
+/// Arquivos de cada diretório `<pid>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PidFile {
+    Status,
+    Cmdline,
+    Stat,
+}
+
+impl PidFile {
+    const ALL: [PidFile; 3] = [PidFile::Status, PidFile::Cmdline, PidFile::Stat];
+
+    fn name(self) -> &'static str {
+        match self {
+            PidFile::Status => "status",
+            PidFile::Cmdline => "cmdline",
+            PidFile::Stat => "stat",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+}
+
+/// Nó de `/proc` identificado por um caminho
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Root,
+    Entry(usize),
+    PidDir(u32),
+    Pid(u32, PidFile),
+}
+
+/// Interpreta um caminho relativo a `/proc` (sem checar se o PID existe)
+fn parse(path: &str) -> Option<Node> {
+    let mut parts = path.split('/').filter(|p| !p.is_empty());
+    let first = match parts.next() {
+        Some(first) => first,
+        None => return Some(Node::Root),
+    };
+
+    let node = match first.parse::<u32>() {
+        Ok(pid) => match parts.next() {
+            None => Node::PidDir(pid),
+            Some(file) => Node::Pid(pid, PidFile::from_name(file)?),
+        },
+        Err(_) => Node::Entry(ENTRIES.iter().position(|e| e.name == first)?),
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(node)
+}
+
+/// Caminho absoluto (`/proc/1/stat`) ou relativo (`1/stat`) → relativo
+fn relative(path: &str) -> &str {
+    let path = path.trim_start_matches('/');
+    if path == "proc" {
+        return "";
+    }
+    path.strip_prefix("proc/").unwrap_or(path)
+}
+
+// --- Números de inode ---
+//
+// 0 = raiz, 1.. = entradas fixas, PID_INO | pid << 4 | arquivo = diretórios
+// e arquivos de PID (arquivo 0 = o próprio diretório).
+
+const ROOT_INO: InodeNum = 0;
+const PID_INO: InodeNum = 1 << 40;
+
+fn ino_of(node: Node) -> InodeNum {
+    match node {
+        Node::Root => ROOT_INO,
+        Node::Entry(index) => index as InodeNum + 1,
+        Node::PidDir(pid) => PID_INO | (pid as InodeNum) << 4,
+        Node::Pid(pid, file) => PID_INO | (pid as InodeNum) << 4 | (file as InodeNum + 1),
+    }
+}
+
+fn node_of(ino: InodeNum) -> Option<Node> {
+    if ino == ROOT_INO {
+        return Some(Node::Root);
+    }
+    if ino & PID_INO == 0 {
+        let index = (ino - 1) as usize;
+        return (index < ENTRIES.len()).then_some(Node::Entry(index));
+    }
+    let pid = ((ino & !PID_INO) >> 4) as u32;
+    match ino & 0xF {
+        0 => Some(Node::PidDir(pid)),
+        file => PidFile::ALL
+            .get(file as usize - 1)
+            .map(|&file| Node::Pid(pid, file)),
+    }
+}
+
+// =============================================================================
+// MEMINFO
+// =============================================================================
+
+/// `/proc/meminfo`.
+///
+/// `MemFree` soma os frames livres no PFM e o espaço ainda livre no heap: a
+/// região do heap é reservada inteira no boot, então alocações do kernel só
+/// aparecem no uso do heap, não no PFM.
+fn meminfo() -> String {
+    const KB: u64 = 1024;
+    let frame_kb = crate::mm::config::PAGE_SIZE as u64 / KB;
+    let pfm = *crate::mm::pfm::get().lock().stats();
+    let heap = crate::mm::heap::stats();
+
+    let lines = [
+        ("MemTotal", pfm.total_frames * frame_kb),
+        (
+            "MemFree",
+            pfm.free_frames * frame_kb + heap.free() as u64 / KB,
+        ),
+        ("KernelFrames", pfm.kernel_frames * frame_kb),
+        ("UserFrames", pfm.user_frames * frame_kb),
+        ("Shared", pfm.shared_frames * frame_kb),
+        ("Pinned", pfm.pinned_frames * frame_kb),
+        ("Device", pfm.device_frames * frame_kb),
+        ("HeapTotal", heap.size as u64 / KB),
+        ("HeapUsed", heap.used as u64 / KB),
+        ("Slab", heap.slab as u64 / KB),
+    ];
+    format_kb_lines(&lines) + &accounting_lines()
+}
+
+/// Uma linha `Acct<Subsistema>` por subsistema contabilizado
+#[cfg(feature = "memory_accounting")]
+fn accounting_lines() -> String {
+    use crate::mm::accounting::{get_usage, Subsystem};
+
+    let names: Vec<String> = Subsystem::all()
+        .iter()
+        .map(|subsys| format!("Acct{}", subsys.name()))
+        .collect();
+    let lines: Vec<(&str, u64)> = names
+        .iter()
+        .zip(Subsystem::all())
+        .map(|(name, subsys)| (name.as_str(), get_usage(*subsys) as u64 / 1024))
+        .collect();
+    format_kb_lines(&lines)
+}
+
+#[cfg(not(feature = "memory_accounting"))]
+fn accounting_lines() -> String {
+    String::new()
+}
+
+/// Linhas `Nome:   valor kB`, com os valores alinhados
+fn format_kb_lines(lines: &[(&str, u64)]) -> String {
+    let mut out = String::new();
+    for (name, kb) in lines {
+        out.push_str(&format!("{:<16}{:>10} kB\n", format!("{}:", name), kb));
+    }
+    out
+}
+
+fn uptime() -> String {
+    format_uptime(crate::core::time::now_ns())
+}
+
+/// `segundos.centésimos`, como a primeira coluna do `/proc/uptime` do Linux
+fn format_uptime(ns: u64) -> String {
+    format!(
+        "{}.{:02}\n",
+        ns / 1_000_000_000,
+        ns % 1_000_000_000 / 10_000_000
+    )
+}
+
+/// `/proc/kmsg`: o log inteiro, como `sys_klog_read` sem filtro de nível
+fn kmsg() -> String {
+    use crate::core::debug::{klog::Level, kmsg};
+
+    let mut text = alloc::vec![0u8; kmsg::capacity()];
+    let len = kmsg::read(&mut text, Level::Trace);
+    text.truncate(len);
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+// =============================================================================
+// CONTEÚDO DOS PIDS
+// =============================================================================
+
+/// Letra e descrição do estado, como no Linux
+fn state_code(state: TaskState) -> (char, &'static str) {
+    match state {
+        TaskState::Created | TaskState::Ready | TaskState::Running => ('R', "running"),
+        TaskState::Blocked | TaskState::Sleeping => ('S', "sleeping"),
+        TaskState::Stopped => ('T', "stopped"),
+        TaskState::Zombie => ('Z', "zombie"),
+        TaskState::Dead => ('X', "dead"),
+    }
+}
+
+fn render(info: &TaskInfo, file: PidFile) -> String {
+    let ppid = info.parent.map(|p| p.as_u32()).unwrap_or(0);
+    let (code, description) = state_code(info.state);
+    match file {
+        PidFile::Status => format!(
+            "Name:\t{}\nState:\t{} ({})\nPid:\t{}\nPPid:\t{}\nPriority:\t{}\n\
+             VmSize:\t{} kB\nVmRSS:\t{} kB\nvoluntary_ctxt_switches:\t{}\n\
+             nonvoluntary_ctxt_switches:\t{}\n",
+            info.name,
+            code,
+            description,
+            info.tid.as_u32(),
+            ppid,
+            info.priority,
+            info.mapped_pages * 4,
+            info.resident_pages * 4,
+            info.voluntary_switches,
+            info.involuntary_switches,
+        ),
+        PidFile::Cmdline => format!("{}\0", info.name),
+        PidFile::Stat => format!(
+            "{} ({}) {} {} {} {} {} {}\n",
+            info.tid.as_u32(),
+            info.name,
+            code,
+            ppid,
+            info.priority,
+            info.cpu_time,
+            info.mapped_pages * 4096,
+            info.resident_pages,
+        ),
+    }
+}
+
+// =============================================================================
+// PROCFS
+// =============================================================================
+
+/// Diretório `/proc`
+pub struct ProcFS;
+
+impl ProcFS {
+    /// Conteúdo do arquivo em `path` (`/proc/version`, `/proc/7/status`...).
+    ///
+    /// `None` se o caminho não é um arquivo de `/proc` ou o PID não existe.
+    pub fn read(path: &str) -> Option<String> {
+        Self::content(parse(relative(path))?)
+    }
+
+    /// Entradas do diretório em `path`: a raiz lista as entradas fixas e um
+    /// diretório por PID vivo.
+    pub fn list(path: &str) -> Option<Vec<DirEntry>> {
+        Self::entries(parse(relative(path))?)
+    }
+
+    fn content(node: Node) -> Option<String> {
+        match node {
+            Node::Entry(index) => Some(ENTRIES[index].render()),
+            Node::Pid(pid, file) => Some(render(&task_info(Tid::new(pid))?, file)),
+            Node::Root | Node::PidDir(_) => None,
+        }
+    }
+
+    fn entries(node: Node) -> Option<Vec<DirEntry>> {
+        match node {
+            Node::Root => {
+                let fixed = ENTRIES.iter().enumerate().map(|(index, entry)| DirEntry {
+                    name: String::from(entry.name),
+                    ino: ino_of(Node::Entry(index)),
+                    file_type: FileType::Regular,
+                });
+                let pids = tasks().into_iter().map(|info| {
+                    let pid = info.tid.as_u32();
+                    DirEntry {
+                        name: format!("{}", pid),
+                        ino: ino_of(Node::PidDir(pid)),
+                        file_type: FileType::Directory,
+                    }
+                });
+                Some(fixed.chain(pids).collect())
+            }
+            Node::PidDir(pid) => {
+                task_info(Tid::new(pid))?;
+                Some(
+                    PidFile::ALL
+                        .into_iter()
+                        .map(|file| DirEntry {
+                            name: String::from(file.name()),
+                            ino: ino_of(Node::Pid(pid, file)),
+                            file_type: FileType::Regular,
+                        })
+                        .collect(),
+                )
+            }
+            Node::Entry(_) | Node::Pid(..) => None,
+        }
+    }
+}
+
+impl Filesystem for ProcFS {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        let node = parse(path).ok_or(FsError::NotFound)?;
+        if let Node::PidDir(pid) | Node::Pid(pid, _) = node {
+            task_info(Tid::new(pid)).ok_or(FsError::NotFound)?;
+        }
+        Ok(ino_of(node))
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let node = node_of(ino).ok_or(FsError::NotFound)?;
+        let content = match node {
+            Node::Root | Node::PidDir(_) => return Err(FsError::IsDirectory),
+            _ => Self::content(node).ok_or(FsError::NotFound)?,
+        };
+
+        let bytes = content.as_bytes();
+        let start = (offset as usize).min(bytes.len());
+        let count = buf.len().min(bytes.len() - start);
+        buf[..count].copy_from_slice(&bytes[start..start + count]);
+        Ok(count)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        let node = node_of(ino).ok_or(FsError::NotFound)?;
+        Self::entries(node).ok_or(FsError::NotDirectory)
+    }
+}
+
+// =============================================================================
+// INICIALIZAÇÃO
+// =============================================================================
+
+/// Monta `/proc`
+pub fn init() {
+    if crate::fs::vfs::mount(PROCFS_MOUNT_POINT, Arc::new(ProcFS)).is_err() {
+        crate::kwarn!("(ProcFS) Ponto de montagem ocupado:", PROCFS_MOUNT_POINT);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_paths() {
+        assert_eq!(parse(relative("/proc")), Some(Node::Root));
+        assert_eq!(parse(relative("/proc/version")), Some(Node::Entry(0)));
+        assert_eq!(parse(relative("/proc/42")), Some(Node::PidDir(42)));
+        assert_eq!(
+            parse(relative("/proc/42/status")),
+            Some(Node::Pid(42, PidFile::Status))
+        );
+        assert_eq!(parse("7/cmdline"), Some(Node::Pid(7, PidFile::Cmdline)));
+        assert_eq!(parse("7/environ"), None);
+        assert_eq!(parse("7/stat/x"), None);
+        assert_eq!(parse("nope"), None);
+    }
+
+    #[test]
+    fn test_format_kb_lines() {
+        let text = format_kb_lines(&[("MemTotal", 2048), ("Slab", 7)]);
+        assert_eq!(
+            text,
+            "MemTotal:             2048 kB\nSlab:                    7 kB\n"
+        );
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(0), "0.00\n");
+        assert_eq!(format_uptime(12_345_678_901), "12.34\n");
+    }
+
+    #[test]
+    fn test_ino_roundtrip() {
+        let nodes = [
+            Node::Root,
+            Node::Entry(1),
+            Node::PidDir(9),
+            Node::Pid(9, PidFile::Stat),
+            Node::Pid(u32::MAX, PidFile::Status),
+        ];
+        for node in nodes {
+            assert_eq!(node_of(ino_of(node)), Some(node));
+        }
+        assert_eq!(node_of(ENTRIES.len() as InodeNum + 1), None);
+    }
+}
@@ -0,0 +1,319 @@
+//! # SysFS - objetos do kernel em /sys
+//!
+//! Como no ProcFS, o conteúdo é gerado a cada leitura.
+//!
+//! | Caminho                   | Conteúdo                              |
+//! |---------------------------|---------------------------------------|
+//! | `/sys/kernel/ostype`      | Nome do sistema                       |
+//! | `/sys/kernel/osrelease`   | Versão do kernel                      |
+//! | `/sys/kernel/arch`        | Arquitetura                           |
+//! | `/sys/block/<dev>/size`   | Tamanho do dispositivo, em bytes      |
+//! | `/sys/block/<dev>/type`   | Tipo do dispositivo (`ata`, `ram`...) |
+//!
+//! `/sys/block` lista o registro de [`crate::drivers::block`] no momento
+//! da leitura, pelos nomes atribuídos lá (`ata0`, `ram0`...).
+
+use crate::drivers::block;
+use crate::fs::vfs::inode::{DirEntry, FileType, FsError, InodeNum};
+use crate::fs::vfs::Filesystem;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Ponto de montagem no VFS
+pub const SYSFS_MOUNT_POINT: &str = "/sys";
+
+// =============================================================================
+// ENTRADAS
+// =============================================================================
+
+/// Atributo fixo em `/sys/kernel`
+pub struct SysEntry {
+    pub name: &'static str,
+    pub content: &'static str,
+}
+
+static KERNEL_ENTRIES: [SysEntry; 3] = [
+    SysEntry {
+        name: "ostype",
+        content: "Forge\n",
+    },
+    SysEntry {
+        name: "osrelease",
+        content: concat!(env!("CARGO_PKG_VERSION"), "\n"),
+    },
+    SysEntry {
+        name: "arch",
+        content: "x86_64\n",
+    },
+];
+
+/// Atributos de cada dispositivo em `/sys/block/<dev>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockAttr {
+    Size,
+    Type,
+}
+
+impl BlockAttr {
+    const ALL: [BlockAttr; 2] = [BlockAttr::Size, BlockAttr::Type];
+
+    fn name(self) -> &'static str {
+        match self {
+            BlockAttr::Size => "size",
+            BlockAttr::Type => "type",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|attr| attr.name() == name)
+    }
+}
+
+/// Nó de `/sys`. Dispositivos são identificados pela posição no registro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Root,
+    KernelDir,
+    Kernel(usize),
+    BlockDir,
+    Device(usize),
+    DeviceAttr(usize, BlockAttr),
+}
+
+/// Posição no registro do dispositivo de bloco `name`
+fn device_index(name: &str) -> Option<usize> {
+    block::devices().iter().position(|(n, _)| n == name)
+}
+
+/// Interpreta um caminho relativo a `/sys`
+fn parse(path: &str) -> Option<Node> {
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    let node = match parts.as_slice() {
+        [] => Node::Root,
+        ["kernel"] => Node::KernelDir,
+        ["kernel", name] => Node::Kernel(KERNEL_ENTRIES.iter().position(|e| e.name == *name)?),
+        ["block"] => Node::BlockDir,
+        ["block", dev] => Node::Device(device_index(dev)?),
+        ["block", dev, attr] => Node::DeviceAttr(device_index(dev)?, BlockAttr::from_name(attr)?),
+        _ => return None,
+    };
+    Some(node)
+}
+
+/// Caminho absoluto (`/sys/block`) ou relativo (`block`) → relativo
+fn relative(path: &str) -> &str {
+    let path = path.trim_start_matches('/');
+    if path == "sys" {
+        return "";
+    }
+    path.strip_prefix("sys/").unwrap_or(path)
+}
+
+// --- Números de inode ---
+//
+// 0 = raiz, 1 = /sys/kernel, 2 = /sys/block, KERNEL_INO | i = atributos do
+// kernel, BLOCK_INO | índice << 4 | atributo = dispositivos (atributo 0 = o
+// diretório do dispositivo).
+
+const ROOT_INO: InodeNum = 0;
+const KERNEL_DIR_INO: InodeNum = 1;
+const BLOCK_DIR_INO: InodeNum = 2;
+const KERNEL_INO: InodeNum = 1 << 32;
+const BLOCK_INO: InodeNum = 1 << 40;
+
+fn ino_of(node: Node) -> InodeNum {
+    match node {
+        Node::Root => ROOT_INO,
+        Node::KernelDir => KERNEL_DIR_INO,
+        Node::BlockDir => BLOCK_DIR_INO,
+        Node::Kernel(index) => KERNEL_INO | index as InodeNum,
+        Node::Device(index) => BLOCK_INO | (index as InodeNum) << 4,
+        Node::DeviceAttr(index, attr) => {
+            BLOCK_INO | (index as InodeNum) << 4 | (attr as InodeNum + 1)
+        }
+    }
+}
+
+fn node_of(ino: InodeNum) -> Option<Node> {
+    match ino {
+        ROOT_INO => Some(Node::Root),
+        KERNEL_DIR_INO => Some(Node::KernelDir),
+        BLOCK_DIR_INO => Some(Node::BlockDir),
+        _ if ino & BLOCK_INO != 0 => {
+            let index = ((ino & !BLOCK_INO) >> 4) as usize;
+            match ino & 0xF {
+                0 => Some(Node::Device(index)),
+                attr => BlockAttr::ALL
+                    .get(attr as usize - 1)
+                    .map(|&attr| Node::DeviceAttr(index, attr)),
+            }
+        }
+        _ if ino & KERNEL_INO != 0 => {
+            let index = (ino & !KERNEL_INO) as usize;
+            (index < KERNEL_ENTRIES.len()).then_some(Node::Kernel(index))
+        }
+        _ => None,
+    }
+}
+
+fn dir_entry(name: String, node: Node, file_type: FileType) -> DirEntry {
+    DirEntry {
+        name,
+        ino: ino_of(node),
+        file_type,
+    }
+}
+
+// =============================================================================
+// SYSFS
+// =============================================================================
+
+/// Diretório `/sys`
+pub struct SysFS;
+
+impl SysFS {
+    /// Conteúdo do atributo em `path` (`/sys/block/ram0/size`...).
+    ///
+    /// `None` se o caminho não é um atributo ou o dispositivo não existe.
+    pub fn read(path: &str) -> Option<String> {
+        Self::content(parse(relative(path))?)
+    }
+
+    /// Entradas do diretório em `path`
+    pub fn list(path: &str) -> Option<Vec<DirEntry>> {
+        Self::entries(parse(relative(path))?)
+    }
+
+    fn content(node: Node) -> Option<String> {
+        match node {
+            Node::Kernel(index) => Some(String::from(KERNEL_ENTRIES[index].content)),
+            Node::DeviceAttr(index, attr) => {
+                let (_, info) = block::devices().into_iter().nth(index)?;
+                Some(match attr {
+                    BlockAttr::Size => format!("{}\n", info.size_bytes()),
+                    BlockAttr::Type => format!("{}\n", info.name),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn entries(node: Node) -> Option<Vec<DirEntry>> {
+        let entries = match node {
+            Node::Root => alloc::vec![
+                dir_entry(String::from("kernel"), Node::KernelDir, FileType::Directory),
+                dir_entry(String::from("block"), Node::BlockDir, FileType::Directory),
+            ],
+            Node::KernelDir => KERNEL_ENTRIES
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    dir_entry(
+                        String::from(entry.name),
+                        Node::Kernel(index),
+                        FileType::Regular,
+                    )
+                })
+                .collect(),
+            Node::BlockDir => block::devices()
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, _))| dir_entry(name, Node::Device(index), FileType::Directory))
+                .collect(),
+            Node::Device(index) => {
+                if index >= block::device_count() {
+                    return None;
+                }
+                BlockAttr::ALL
+                    .into_iter()
+                    .map(|attr| {
+                        dir_entry(
+                            String::from(attr.name()),
+                            Node::DeviceAttr(index, attr),
+                            FileType::Regular,
+                        )
+                    })
+                    .collect()
+            }
+            Node::Kernel(_) | Node::DeviceAttr(..) => return None,
+        };
+        Some(entries)
+    }
+}
+
+impl Filesystem for SysFS {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        parse(path).map(ino_of).ok_or(FsError::NotFound)
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let node = node_of(ino).ok_or(FsError::NotFound)?;
+        let content = match node {
+            Node::Kernel(_) | Node::DeviceAttr(..) => {
+                Self::content(node).ok_or(FsError::NotFound)?
+            }
+            _ => return Err(FsError::IsDirectory),
+        };
+
+        let bytes = content.as_bytes();
+        let start = (offset as usize).min(bytes.len());
+        let count = buf.len().min(bytes.len() - start);
+        buf[..count].copy_from_slice(&bytes[start..start + count]);
+        Ok(count)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        let node = node_of(ino).ok_or(FsError::NotFound)?;
+        Self::entries(node).ok_or(FsError::NotDirectory)
+    }
+}
+
+// =============================================================================
+// INICIALIZAÇÃO
+// =============================================================================
+
+/// Monta `/sys`
+pub fn init() {
+    if crate::fs::vfs::mount(SYSFS_MOUNT_POINT, Arc::new(SysFS)).is_err() {
+        crate::kwarn!("(SysFS) Ponto de montagem ocupado:", SYSFS_MOUNT_POINT);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ino_roundtrip() {
+        let nodes = [
+            Node::Root,
+            Node::KernelDir,
+            Node::BlockDir,
+            Node::Kernel(2),
+            Node::Device(0),
+            Node::Device(5),
+            Node::DeviceAttr(5, BlockAttr::Type),
+            Node::DeviceAttr(0, BlockAttr::Size),
+        ];
+        for node in nodes {
+            assert_eq!(node_of(ino_of(node)), Some(node));
+        }
+        assert_eq!(node_of(KERNEL_INO | 7), None);
+    }
+
+    #[test]
+    fn test_parse_static_paths() {
+        assert_eq!(parse(relative("/sys")), Some(Node::Root));
+        assert_eq!(parse(relative("/sys/kernel")), Some(Node::KernelDir));
+        assert_eq!(parse(relative("/sys/kernel/arch")), Some(Node::Kernel(2)));
+        assert_eq!(parse("block/"), Some(Node::BlockDir));
+        assert_eq!(parse("kernel/nope"), None);
+        assert_eq!(parse("kernel/arch/x"), None);
+    }
+}
@@ -0,0 +1,358 @@
+//! # TAR - leitura de arquivos ustar
+//!
+//! Parser somente leitura sobre um buffer em memória. Cada entrada ocupa um
+//! header de 512 bytes seguido dos dados, arredondados para blocos de 512.
+//! A iteração termina no primeiro header que não for ustar (inclusive os
+//! blocos zerados do fim do arquivo) ou que não caiba no buffer.
+//!
+//! Todo acesso a tar no kernel passa por aqui: o InitramFS indexa as entradas
+//! com [`TarArchive::entries`] e lê os dados com [`TarArchive::entry_at`].
+//!
+//! ## Nomes
+//! Os nomes gravados podem vir com `./` ou `/` no início e com `/` no fim
+//! (diretórios). [`TarArchive::find`] e [`TarArchive::readdir`] comparam a
+//! forma normalizada ([`TarEntry::path`]).
+
+use crate::klib::string::parse_u64;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Tamanho de bloco (e de header) do tar
+pub const BLOCK_SIZE: usize = 512;
+
+const NAME_OFFSET: usize = 0;
+const NAME_LEN: usize = 100;
+const SIZE_OFFSET: usize = 124;
+const SIZE_LEN: usize = 12;
+const TYPE_OFFSET: usize = 156;
+const MAGIC_OFFSET: usize = 257;
+const MAGIC: &[u8] = b"ustar";
+
+/// Tipo de uma entrada (campo `typeflag`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// `'0'` ou NUL
+    File,
+    /// `'5'`
+    Directory,
+    /// Links, dispositivos, FIFOs e extensões
+    Other(u8),
+}
+
+impl EntryKind {
+    fn from_flag(flag: u8) -> Self {
+        match flag {
+            b'0' | 0 => Self::File,
+            b'5' => Self::Directory,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Entrada do arquivo
+#[derive(Debug, Clone, Copy)]
+pub struct TarEntry<'a> {
+    /// Nome como gravado no header (pode começar com `./` ou `/`)
+    pub name: &'a str,
+    pub kind: EntryKind,
+    pub data: &'a [u8],
+    /// Offset de `data` no arquivo
+    pub offset: usize,
+}
+
+impl<'a> TarEntry<'a> {
+    /// Nome sem `./`, `/` iniciais nem `/` final (`""` para a raiz)
+    pub fn path(&self) -> &'a str {
+        normalize_name(self.name)
+    }
+}
+
+/// Entrada de [`TarArchive::readdir`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarDirEntry {
+    pub name: String,
+    pub kind: EntryKind,
+}
+
+/// Arquivo tar em memória
+#[derive(Debug, Clone, Copy)]
+pub struct TarArchive<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TarArchive<'a> {
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Itera as entradas na ordem do arquivo
+    pub fn entries(&self) -> TarIterator<'a> {
+        TarIterator {
+            data: self.data,
+            offset: 0,
+        }
+    }
+
+    /// Entrada cujo caminho normalizado é `path` (a última, se repetida)
+    pub fn find(&self, path: &str) -> Option<TarEntry<'a>> {
+        let path = normalize_name(path);
+        self.entries().filter(|entry| entry.path() == path).last()
+    }
+
+    /// Entrada cujos dados começam em `offset` (o [`TarEntry::offset`] de
+    /// uma iteração anterior)
+    pub fn entry_at(&self, offset: usize) -> Option<TarEntry<'a>> {
+        let header = offset.checked_sub(BLOCK_SIZE)?;
+        TarIterator {
+            data: self.data,
+            offset: header,
+        }
+        .next()
+    }
+
+    /// Copia os dados de `entry` a partir de `offset`; 0 no fim
+    pub fn read_entry(entry: &TarEntry<'_>, offset: usize, buf: &mut [u8]) -> usize {
+        let Some(rest) = entry.data.get(offset..) else {
+            return 0;
+        };
+        let len = buf.len().min(rest.len());
+        buf[..len].copy_from_slice(&rest[..len]);
+        len
+    }
+
+    /// Filhos diretos do diretório `dir`, por nome.
+    ///
+    /// Diretórios sem entrada própria (só prefixo de outro caminho) aparecem
+    /// como `Directory`.
+    pub fn readdir(&self, dir: &str) -> Vec<TarDirEntry> {
+        let dir = normalize_name(dir);
+        let mut children: Vec<TarDirEntry> = Vec::new();
+        for entry in self.entries() {
+            let path = entry.path();
+            let rest = if dir.is_empty() {
+                path
+            } else {
+                match path
+                    .strip_prefix(dir)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                {
+                    Some(rest) => rest,
+                    None => continue,
+                }
+            };
+            if rest.is_empty() {
+                continue;
+            }
+            let (name, kind) = match rest.split_once('/') {
+                Some((name, _)) => (name, EntryKind::Directory),
+                None => (rest, entry.kind),
+            };
+            match children.iter_mut().find(|child| child.name == name) {
+                Some(child) => child.kind = kind,
+                None => children.push(TarDirEntry {
+                    name: String::from(name),
+                    kind,
+                }),
+            }
+        }
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        children
+    }
+}
+
+/// Iterador sobre as entradas de um [`TarArchive`]
+pub struct TarIterator<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for TarIterator<'a> {
+    type Item = TarEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self.data.get(self.offset..self.offset + BLOCK_SIZE)?;
+        if &header[MAGIC_OFFSET..MAGIC_OFFSET + MAGIC.len()] != MAGIC {
+            return None;
+        }
+
+        let size = parse_octal(&header[SIZE_OFFSET..SIZE_OFFSET + SIZE_LEN])?;
+        let start = self.offset + BLOCK_SIZE;
+        let data = self.data.get(start..start.checked_add(size)?)?;
+
+        let raw_name = &header[NAME_OFFSET..NAME_OFFSET + NAME_LEN];
+        let name_len = raw_name.iter().position(|&b| b == 0).unwrap_or(NAME_LEN);
+        let name = core::str::from_utf8(&raw_name[..name_len]).ok()?;
+
+        // Um header corrompido não pode fazer a iteração voltar ou parar
+        // no mesmo lugar: o próximo offset é sempre maior
+        self.offset = start.checked_add(align_up(size))?;
+
+        Some(TarEntry {
+            name,
+            kind: EntryKind::from_flag(header[TYPE_OFFSET]),
+            data,
+            offset: start,
+        })
+    }
+}
+
+/// Remove `./` e `/` do início e `/` do fim
+fn normalize_name(mut name: &str) -> &str {
+    loop {
+        if let Some(rest) = name.strip_prefix("./") {
+            name = rest;
+        } else if let Some(rest) = name.strip_prefix('/') {
+            name = rest;
+        } else {
+            break;
+        }
+    }
+    if name == "." {
+        return "";
+    }
+    name.trim_end_matches('/')
+}
+
+/// Número octal ASCII (terminado por NUL, com espaços opcionais)
+fn parse_octal(field: &[u8]) -> Option<usize> {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    let text = core::str::from_utf8(&field[..end]).ok()?;
+    parse_u64(text, 8)?.try_into().ok()
+}
+
+/// Arredonda para o próximo bloco
+fn align_up(size: usize) -> usize {
+    size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE
+}
+
+/// Monta arquivos tar em memória para os testes dos filesystems
+#[cfg(test)]
+pub mod builder {
+    use super::*;
+
+    /// Anexa uma entrada a `archive`
+    pub fn push(archive: &mut Vec<u8>, name: &str, flag: u8, data: &[u8]) {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        let size = alloc::format!("{:011o}", data.len());
+        header[SIZE_OFFSET..SIZE_OFFSET + 11].copy_from_slice(size.as_bytes());
+        header[TYPE_OFFSET] = flag;
+        header[MAGIC_OFFSET..MAGIC_OFFSET + 6].copy_from_slice(b"ustar\0");
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len() + align_up(data.len()) - data.len(), 0);
+    }
+
+    /// Fecha o arquivo com os dois blocos zerados
+    pub fn finish(archive: &mut Vec<u8>) {
+        archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::builder::{finish, push};
+    use super::*;
+
+    #[test]
+    fn test_iterates_entries() {
+        let mut archive = Vec::new();
+        push(&mut archive, "./system/", b'5', &[]);
+        push(&mut archive, "./system/init", b'0', b"\x7fELF");
+        push(&mut archive, "readme", 0, &[b'x'; 600]);
+        finish(&mut archive);
+
+        let entries: Vec<_> = TarArchive::new(&archive).entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "./system/");
+        assert_eq!(entries[0].kind, EntryKind::Directory);
+        assert_eq!(entries[1].kind, EntryKind::File);
+        assert_eq!(entries[1].data, b"\x7fELF");
+        assert_eq!(entries[1].offset, 2 * BLOCK_SIZE);
+        assert_eq!(entries[2].data.len(), 600);
+        assert_eq!(entries[2].offset, 4 * BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_truncated_entry_stops_iteration() {
+        let mut archive = Vec::new();
+        push(&mut archive, "a", b'0', b"abc");
+        push(&mut archive, "b", b'0', &[1; 100]);
+        archive.truncate(BLOCK_SIZE * 3 + 10);
+
+        let names: Vec<_> = TarArchive::new(&archive)
+            .entries()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["a"]);
+    }
+
+    #[test]
+    fn test_find_and_read_entry() {
+        let mut archive = Vec::new();
+        push(&mut archive, "./system/core/supervisor", b'0', b"first");
+        push(&mut archive, "/etc/motd", b'0', b"hello world");
+        push(&mut archive, "system/core/supervisor", b'0', b"second");
+        finish(&mut archive);
+        let tar = TarArchive::new(&archive);
+
+        let motd = tar.find("etc/motd").unwrap();
+        assert_eq!(motd.path(), "etc/motd");
+        let mut buf = [0u8; 5];
+        assert_eq!(TarArchive::read_entry(&motd, 6, &mut buf), 5);
+        assert_eq!(&buf, b"world");
+        assert_eq!(TarArchive::read_entry(&motd, 11, &mut buf), 0);
+        assert_eq!(TarArchive::read_entry(&motd, 50, &mut buf), 0);
+
+        // Entrada repetida: vale a última
+        let supervisor = tar.find("/system/core/supervisor").unwrap();
+        assert_eq!(supervisor.data, b"second");
+        assert_eq!(tar.entry_at(supervisor.offset).unwrap().data, b"second");
+        assert!(tar.entry_at(supervisor.offset + 1).is_none());
+        assert!(tar.find("system/core").is_none());
+    }
+
+    #[test]
+    fn test_readdir_includes_implicit_directories() {
+        let mut archive = Vec::new();
+        push(&mut archive, "./", b'5', &[]);
+        push(&mut archive, "./system/core/supervisor", b'0', b"x");
+        push(&mut archive, "./system/", b'5', &[]);
+        push(&mut archive, "./boot.cfg", b'0', b"y");
+        push(&mut archive, "./system/link", b'2', &[]);
+        finish(&mut archive);
+        let tar = TarArchive::new(&archive);
+
+        let entry = |name: &str, kind| TarDirEntry {
+            name: String::from(name),
+            kind,
+        };
+        assert_eq!(
+            tar.readdir("/"),
+            [
+                entry("boot.cfg", EntryKind::File),
+                entry("system", EntryKind::Directory)
+            ]
+        );
+        assert_eq!(
+            tar.readdir("./system/"),
+            [
+                entry("core", EntryKind::Directory),
+                entry("link", EntryKind::Other(b'2'))
+            ]
+        );
+        assert!(tar.readdir("boot.cfg").is_empty());
+    }
+
+    #[test]
+    fn test_parse_octal_size_field() {
+        assert_eq!(parse_octal(b"00000001750\0"), Some(1000));
+        assert_eq!(parse_octal(b"   1750 \0"), Some(1000));
+        assert_eq!(parse_octal(b"\0\0\0"), None);
+        assert_eq!(parse_octal(b"17z0\0"), None);
+    }
+}
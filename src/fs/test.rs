@@ -0,0 +1,435 @@
+//! # Testes de filesystem
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot.
+
+use crate::fs::devfs::DevFS;
+use crate::fs::procfs::ProcFS;
+use crate::fs::sysfs::SysFS;
+use crate::fs::tmpfs::TmpFsMount;
+use crate::fs::vfs;
+use crate::fs::vfs::file::{OpenFlags, SeekFrom};
+use crate::fs::vfs::inode::FsError;
+use crate::fs::FileOps;
+use alloc::format;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub fn run_tests() {
+    crate::kinfo!("(FS) Iniciando testes de filesystem...");
+    test_vfs_runtime_child();
+    test_dentry_cache_hit();
+    test_mount_tmpfs();
+    test_file_lseek();
+    test_open_flags();
+    test_devfs_null_zero();
+    test_devfs_urandom();
+    test_procfs_pid_status();
+    test_procfs_meminfo_tracks_heap();
+    test_sysfs_block_ramdisk();
+    test_fat_reads_hit_page_cache();
+    crate::kinfo!("(FS) Testes de filesystem concluídos com SUCESSO.");
+}
+
+/// Um diretório criado em runtime é resolvido por caminho, sem tabela fixa.
+fn test_vfs_runtime_child() {
+    assert_eq!(vfs::lookup("/"), Ok(0));
+    let runtime = vfs::lookup("/runtime").expect("(FS) /runtime ausente");
+
+    let dir = vfs::mkdir("/runtime/fs-test").expect("(FS) Falha ao criar diretório");
+    assert_ne!(dir, runtime);
+    assert_eq!(vfs::lookup("/runtime/fs-test"), Ok(dir));
+    assert_eq!(vfs::lookup("/runtime/./fs-test/../fs-test/"), Ok(dir));
+
+    // Resolução atravessa o novo diretório
+    let inner = vfs::mkdir("/runtime/fs-test/inner").unwrap();
+    assert_eq!(vfs::lookup("/runtime/fs-test/inner"), Ok(inner));
+
+    assert_eq!(vfs::mkdir("/runtime/fs-test"), Err(FsError::AlreadyExists));
+    assert_eq!(vfs::mkdir("/nope/child"), Err(FsError::NotFound));
+    assert_eq!(vfs::lookup("/runtime/fs-test/nope"), Err(FsError::NotFound));
+}
+
+/// A segunda resolução do mesmo caminho sai inteira do dentry cache.
+fn test_dentry_cache_hit() {
+    vfs::mkdir("/runtime/dcache-test").expect("(FS) Falha ao criar diretório");
+    let first = vfs::lookup("/runtime/dcache-test").unwrap();
+
+    let before = vfs::dentry::stats();
+    assert_eq!(vfs::lookup("/runtime/dcache-test"), Ok(first));
+    let after = vfs::dentry::stats();
+    // Um acerto por componente, nenhuma falta
+    assert_eq!(after.hits - before.hits, 2);
+    assert_eq!(after.misses, before.misses);
+
+    // Desmontar qualquer coisa esvazia o cache
+    vfs::mount("/runtime/dcache-test", Arc::new(TmpFsMount::new(4096))).unwrap();
+    vfs::unmount("/runtime/dcache-test").unwrap();
+    assert_eq!(vfs::dentry::stats().entries, 0);
+    assert_eq!(vfs::lookup("/runtime/dcache-test"), Ok(first));
+}
+
+/// Arquivo escrito via `vfs::open` em `/tmp` chega ao tmpfs montado lá, e o
+/// ponto de montagem mais longo vence.
+fn test_mount_tmpfs() {
+    let tmp = Arc::new(TmpFsMount::new(64 * 1024));
+    vfs::mount("/tmp", tmp.clone()).expect("(FS) Falha ao montar /tmp");
+    assert_eq!(vfs::mount("/tmp", tmp.clone()), Err(FsError::AlreadyExists));
+
+    let rw = OpenFlags(OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE);
+    let file = vfs::open("/tmp/hello.txt", rw).expect("(FS) Falha ao criar arquivo");
+    assert_eq!(file.write(b"ola, vfs"), Ok(8));
+
+    // Mesmo conteúdo pelo backend e por uma nova abertura
+    assert_eq!(tmp.lock().read_file("/hello.txt"), Ok(&b"ola, vfs"[..]));
+    let file = vfs::open("/tmp/hello.txt", OpenFlags(OpenFlags::READ)).unwrap();
+    let mut buf = [0u8; 16];
+    assert_eq!(file.read(&mut buf), Ok(8));
+    assert_eq!(&buf[..8], b"ola, vfs");
+    assert_eq!(
+        vfs::read_file("/tmp/hello.txt").as_deref(),
+        Some(&b"ola, vfs"[..])
+    );
+
+    // Montagem aninhada: `/tmp/inner/x` não vai para o tmpfs de `/tmp`
+    let inner = Arc::new(TmpFsMount::new(4096));
+    vfs::mount("/tmp/inner", inner.clone()).unwrap();
+    vfs::open("/tmp/inner/x", rw).unwrap();
+    assert!(inner.lock().lookup("/x").is_ok());
+    assert!(tmp.lock().lookup("/inner/x").is_err());
+
+    vfs::unmount("/tmp/inner").unwrap();
+    vfs::unmount("/tmp").unwrap();
+    assert_eq!(vfs::unmount("/tmp"), Err(FsError::NotFound));
+    assert!(vfs::open("/tmp/hello.txt", OpenFlags(OpenFlags::READ)).is_err());
+}
+
+/// `lseek` move o cursor de leituras e escritas seguintes; posição negativa
+/// é recusada e posição além do fim lê 0 bytes.
+fn test_file_lseek() {
+    vfs::mount("/runtime/lseek", Arc::new(TmpFsMount::new(4096))).unwrap();
+    let rw = OpenFlags(OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE);
+    let file = vfs::open("/runtime/lseek/data", rw).expect("(FS) Falha ao criar arquivo");
+    assert_eq!(file.write(b"0123456789"), Ok(10));
+
+    let mut buf = [0u8; 4];
+    assert_eq!(file.lseek(SeekFrom::Start(3)), Ok(3));
+    assert_eq!(file.read(&mut buf), Ok(4));
+    assert_eq!(&buf, b"3456");
+    assert_eq!(file.lseek(SeekFrom::Current(-6)), Ok(1));
+    assert_eq!(file.read(&mut buf[..2]), Ok(2));
+    assert_eq!(&buf[..2], b"12");
+    assert_eq!(file.lseek(SeekFrom::End(-2)), Ok(8));
+    assert_eq!(file.read(&mut buf), Ok(2));
+    assert_eq!(&buf[..2], b"89");
+
+    assert_eq!(
+        file.lseek(SeekFrom::Current(-11)),
+        Err(FsError::InvalidArgument)
+    );
+    assert_eq!(file.lseek(SeekFrom::End(5)), Ok(15));
+    assert_eq!(file.read(&mut buf), Ok(0));
+
+    // Escrever além do fim estende o arquivo
+    assert_eq!(file.write(b"!"), Ok(1));
+    assert_eq!(file.size(), Ok(16));
+
+    drop(file);
+    vfs::unmount("/runtime/lseek").unwrap();
+}
+
+/// `TRUNCATE` esvazia o arquivo na abertura, `APPEND` escreve sempre no fim
+/// (mesmo depois de um seek) e flags de escrita num backend somente leitura
+/// falham com `ReadOnly`.
+fn test_open_flags() {
+    let tmp = Arc::new(TmpFsMount::new(4096));
+    vfs::mount("/runtime/oflags", tmp.clone()).unwrap();
+    tmp.lock().create_file("/log", b"antigo").unwrap();
+
+    let trunc = OpenFlags(OpenFlags::READ | OpenFlags::WRITE | OpenFlags::TRUNCATE);
+    let file = vfs::open("/runtime/oflags/log", trunc).expect("(FS) Falha ao truncar");
+    assert_eq!(file.size(), Ok(0));
+    assert_eq!(file.write(b"abc"), Ok(3));
+    assert_eq!(tmp.lock().read_file("/log"), Ok(&b"abc"[..]));
+
+    let append = OpenFlags(OpenFlags::READ | OpenFlags::WRITE | OpenFlags::APPEND);
+    let file = vfs::open("/runtime/oflags/log", append).unwrap();
+    assert_eq!(file.write(b"de"), Ok(2));
+    file.seek(0);
+    assert_eq!(file.write(b"f"), Ok(1));
+    assert_eq!(tmp.lock().read_file("/log"), Ok(&b"abcdef"[..]));
+
+    // `CREATE` e `TRUNCATE` juntos num arquivo novo
+    let create = OpenFlags(OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE);
+    vfs::open("/runtime/oflags/novo", create).expect("(FS) Falha ao criar arquivo");
+    assert_eq!(tmp.lock().read_file("/novo"), Ok(&b""[..]));
+    vfs::unmount("/runtime/oflags").unwrap();
+
+    // procfs é somente leitura
+    let read = OpenFlags(OpenFlags::READ);
+    assert!(vfs::open("/proc/meminfo", read).is_ok());
+    for flag in [OpenFlags::WRITE, OpenFlags::APPEND, OpenFlags::TRUNCATE] {
+        let flags = OpenFlags(OpenFlags::READ | flag);
+        assert_eq!(
+            vfs::open("/proc/meminfo", flags).err(),
+            Some(FsError::ReadOnly)
+        );
+    }
+    let create = OpenFlags(OpenFlags::WRITE | OpenFlags::CREATE);
+    assert_eq!(
+        vfs::open("/proc/novo", create).err(),
+        Some(FsError::ReadOnly)
+    );
+}
+
+/// `/dev/zero` zera um buffer sujo; `/dev/null` consome tudo e lê EOF.
+fn test_devfs_null_zero() {
+    let rw = OpenFlags(OpenFlags::READ | OpenFlags::WRITE);
+
+    let zero = vfs::open("/dev/zero", rw).expect("(FS) /dev/zero ausente");
+    let mut buf = [0xA5u8; 100];
+    assert_eq!(zero.read(&mut buf), Ok(100));
+    assert!(buf.iter().all(|&b| b == 0), "(FS) /dev/zero não zerou");
+
+    let null = vfs::open("/dev/null", rw).expect("(FS) /dev/null ausente");
+    assert_eq!(null.write(&[0x42; 300]), Ok(300));
+    assert_eq!(null.read(&mut buf), Ok(0));
+
+    // Mesmo roteamento pela API do DevFS
+    assert_eq!(DevFS::write("/dev/null", b"abc"), Ok(3));
+    assert!(DevFS::open("/dev/console").is_ok());
+    assert!(vfs::open("/dev/nope", rw).is_err());
+}
+
+/// `/dev/urandom` entrega bytes variados e não repete leituras.
+fn test_devfs_urandom() {
+    let urandom =
+        vfs::open("/dev/urandom", OpenFlags(OpenFlags::READ)).expect("(FS) /dev/urandom ausente");
+    let mut first = [0u8; 4096];
+    assert_eq!(urandom.read(&mut first), Ok(first.len()));
+
+    let mut counts = [0usize; 256];
+    for &b in first.iter() {
+        counts[b as usize] += 1;
+    }
+    let distinct = counts.iter().filter(|&&c| c > 0).count();
+    let max = counts.iter().copied().max().unwrap_or(0);
+    assert!(
+        distinct > 200,
+        "(FS) /dev/urandom com poucos valores distintos"
+    );
+    assert!(max < 64, "(FS) /dev/urandom com byte dominante");
+
+    let mut second = [0u8; 4096];
+    crate::fs::devfs::kernel_rng_fill(&mut second);
+    assert_ne!(first, second, "(FS) CSPRNG repetiu a saída");
+}
+
+static PROC_TASK_STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn proc_test_task() -> ! {
+    while !PROC_TASK_STOP.load(Ordering::SeqCst) {
+        crate::sched::yield_now();
+    }
+    crate::sched::core::exit_current(0);
+}
+
+/// Uma task recém-criada aparece em `/proc` com o próprio status.
+fn test_procfs_pid_status() {
+    PROC_TASK_STOP.store(false, Ordering::SeqCst);
+    let tid = crate::sched::core::spawn_kernel_thread("procfs-test", proc_test_task);
+    let pid = tid.as_u32();
+    let path = format!("/proc/{}/status", pid);
+
+    let status = ProcFS::read(&path).expect("(FS) status do PID ausente");
+    assert!(status.contains("Name:\tprocfs-test\n"));
+    assert!(status.contains(&format!("Pid:\t{}\n", pid)));
+    assert!(status.contains("State:\tR"));
+
+    let pid_name = format!("{}", pid);
+    let root = ProcFS::list("/proc").expect("(FS) /proc sem listagem");
+    assert!(root.iter().any(|e| e.name == pid_name));
+
+    // Mesmo conteúdo pelo VFS
+    let file = vfs::open(&path, OpenFlags(OpenFlags::READ)).expect("(FS) /proc via VFS");
+    let mut buf = [0u8; 512];
+    let n = file.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], status.as_bytes());
+
+    PROC_TASK_STOP.store(true, Ordering::SeqCst);
+    while crate::sched::core::task_info(tid).is_some_and(|t| t.state.is_runnable()) {
+        crate::sched::yield_now();
+    }
+
+    // PID que nunca existiu
+    assert!(ProcFS::read("/proc/4000000000/status").is_none());
+    assert!(vfs::open("/proc/4000000000/status", OpenFlags(OpenFlags::READ)).is_err());
+}
+
+/// Valor em kB de `field` em `/proc/meminfo`
+fn meminfo_kb(field: &str) -> u64 {
+    let text = ProcFS::read("/proc/meminfo").expect("(FS) /proc/meminfo ausente");
+    text.lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse().ok())
+        .expect("(FS) Campo ausente em /proc/meminfo")
+}
+
+/// Um `Vec` grande reduz o `MemFree` reportado.
+fn test_procfs_meminfo_tracks_heap() {
+    const SIZE: usize = 4 * 1024 * 1024;
+
+    let before = meminfo_kb("MemFree");
+    assert!(meminfo_kb("MemTotal") >= before);
+
+    let big = alloc::vec![0xA5u8; SIZE];
+    let during = meminfo_kb("MemFree");
+    assert!(
+        during + (SIZE as u64 / 1024) / 2 <= before,
+        "(FS) MemFree não refletiu a alocação"
+    );
+
+    drop(big);
+    assert!(meminfo_kb("MemFree") > during);
+}
+
+/// Um ramdisk registrado aparece em `/sys/block` com o tamanho certo.
+fn test_sysfs_block_ramdisk() {
+    use crate::drivers::block::{register_device, RamDisk};
+
+    let name = register_device(Arc::new(RamDisk::new(64, 512)));
+    assert!(name.starts_with("ram"));
+
+    let block = SysFS::list("/sys/block").expect("(FS) /sys/block sem listagem");
+    assert!(block.iter().any(|e| e.name == name));
+
+    let size = SysFS::read(&format!("/sys/block/{}/size", name)).expect("(FS) size ausente");
+    assert_eq!(size, "32768\n");
+    assert_eq!(
+        SysFS::read(&format!("/sys/block/{}/type", name)).as_deref(),
+        Some("ram\n")
+    );
+    assert!(SysFS::read("/sys/kernel/ostype").is_some());
+
+    let file = vfs::open(
+        &format!("/sys/block/{}/size", name),
+        OpenFlags(OpenFlags::READ),
+    )
+    .expect("(FS) /sys via VFS");
+    let mut buf = [0u8; 16];
+    let n = file.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"32768\n");
+
+    assert!(SysFS::read("/sys/block/nope0/size").is_none());
+}
+
+/// Ramdisk que conta as leituras que chegam ao dispositivo
+struct CountingDisk {
+    disk: crate::drivers::block::RamDisk,
+    reads: core::sync::atomic::AtomicUsize,
+}
+
+impl crate::drivers::block::BlockDevice for CountingDisk {
+    fn read_block(
+        &self,
+        lba: u64,
+        buf: &mut [u8],
+    ) -> Result<(), crate::drivers::block::BlockError> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.disk.read_block(lba, buf)
+    }
+
+    fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), crate::drivers::block::BlockError> {
+        self.disk.write_block(lba, buf)
+    }
+
+    fn block_size(&self) -> usize {
+        512
+    }
+
+    fn total_blocks(&self) -> u64 {
+        self.disk.total_blocks()
+    }
+}
+
+/// Grava a entrada `cluster` de uma FAT12
+fn fat12_set(fat: &mut [u8], cluster: usize, value: u16) {
+    let at = cluster + cluster / 2;
+    if cluster & 1 != 0 {
+        fat[at] = (fat[at] & 0x0F) | (value << 4) as u8;
+        fat[at + 1] = (value >> 4) as u8;
+    } else {
+        fat[at] = value as u8;
+        fat[at + 1] = (fat[at + 1] & 0xF0) | ((value >> 8) & 0x0F) as u8;
+    }
+}
+
+/// A segunda leitura de um arquivo FAT sai do page cache, sem tocar o
+/// disco; uma escrita só chega ao disco no `sync`.
+fn test_fat_reads_hit_page_cache() {
+    use crate::drivers::block::{BlockDevice, PartitionDevice, RamDisk};
+    use crate::fs::fat::FatFs;
+    use crate::fs::vfs::Filesystem;
+
+    // FAT12: boot (0), FAT (1), raiz (2), dados de 3 em diante, 1 setor
+    // por cluster. DATA.BIN ocupa os clusters 2 → 3 → 4.
+    let disk = Arc::new(CountingDisk {
+        disk: RamDisk::new(64, 512),
+        reads: core::sync::atomic::AtomicUsize::new(0),
+    });
+    let mut sector = [0u8; 512];
+    sector[0] = 0xEB;
+    sector[11..13].copy_from_slice(&512u16.to_le_bytes());
+    sector[13] = 1;
+    sector[14..16].copy_from_slice(&1u16.to_le_bytes());
+    sector[16] = 1;
+    sector[17..19].copy_from_slice(&16u16.to_le_bytes());
+    sector[19..21].copy_from_slice(&64u16.to_le_bytes());
+    sector[22..24].copy_from_slice(&1u16.to_le_bytes());
+    sector[510] = 0x55;
+    sector[511] = 0xAA;
+    disk.write_block(0, &sector).unwrap();
+
+    sector.fill(0);
+    fat12_set(&mut sector, 2, 3);
+    fat12_set(&mut sector, 3, 4);
+    fat12_set(&mut sector, 4, 0xFFF);
+    disk.write_block(1, &sector).unwrap();
+
+    sector.fill(0);
+    sector[..11].copy_from_slice(b"DATA    BIN");
+    sector[26..28].copy_from_slice(&2u16.to_le_bytes());
+    sector[28..32].copy_from_slice(&1500u32.to_le_bytes());
+    disk.write_block(2, &sector).unwrap();
+
+    for cluster in 0..3u8 {
+        disk.write_block(3 + cluster as u64, &[b'a' + cluster; 512])
+            .unwrap();
+    }
+
+    let fs = FatFs::mount(PartitionDevice::whole(disk.clone())).expect("(FS) FAT de teste");
+    let ino = fs.lookup("DATA.BIN").expect("(FS) DATA.BIN ausente");
+    let mut buf = alloc::vec![0u8; 1500];
+
+    assert_eq!(fs.read(ino, 0, &mut buf), Ok(1500));
+    assert!(buf[..512].iter().all(|&b| b == b'a'));
+    assert!(buf[1024..].iter().all(|&b| b == b'c'));
+
+    let reads = disk.reads.load(Ordering::Relaxed);
+    buf.fill(0);
+    assert_eq!(fs.read(ino, 0, &mut buf), Ok(1500));
+    assert_eq!(buf[600], b'b');
+    assert_eq!(disk.reads.load(Ordering::Relaxed), reads);
+
+    // Escrita fica suja no cache até o sync
+    assert_eq!(fs.write(ino, 510, b"XYZ"), Ok(3));
+    disk.disk.read_block(3, &mut sector).unwrap();
+    assert_eq!(sector[511], b'a');
+    fs.sync().unwrap();
+    disk.disk.read_block(3, &mut sector).unwrap();
+    assert_eq!(&sector[510..], b"XY");
+    disk.disk.read_block(4, &mut sector).unwrap();
+    assert_eq!(sector[0], b'Z');
+
+    // Só até o tamanho do arquivo
+    assert_eq!(fs.write(ino, 1500, b"!"), Err(FsError::NoSpace));
+}
@@ -0,0 +1,543 @@
+//! # TmpFS - filesystem volátil em memória
+//!
+//! Árvore de nós no heap: diretórios guardam `BTreeMap<String, NodeId>` e
+//! arquivos um `Vec<u8>` que cresce sob demanda.
+//!
+//! ## Limite
+//! `max_size` vale para a árvore inteira: a soma dos tamanhos de todos os
+//! arquivos nunca passa dele. Escritas que estourariam o limite falham com
+//! `FsError::NoSpace` sem alterar nada.
+//!
+//! ## Page cache
+//! Montado no VFS ([`TmpFsMount`]), as leituras passam pelo page cache
+//! (`mm::cache::filemap`) como as de um disco; a árvore é o backend.
+//! Escritas vão direto para a árvore e invalidam as páginas do arquivo.
+//!
+//! ## Caminhos
+//! Todas as operações aceitam caminhos com vários componentes
+//! (`/tmp/foo/bar`), relativos à raiz do tmpfs. `.` e `..` são resolvidos
+//! antes da busca.
+
+use crate::fs::vfs::inode::{DirEntry, FileType, FsError, InodeNum};
+use crate::fs::vfs::{path, Filesystem};
+use crate::mm::cache::filemap::{self, PageIo};
+use crate::mm::cache::pagecache::DevId;
+use crate::sync::{Spinlock, SpinlockGuard};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Identificador de nó (usado como número de inode)
+pub type NodeId = InodeNum;
+
+/// Nó raiz
+pub const ROOT: NodeId = 0;
+
+/// Limite padrão (16 MiB)
+pub const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+enum NodeKind {
+    File(Vec<u8>),
+    Dir(BTreeMap<String, NodeId>),
+}
+
+struct Node {
+    kind: NodeKind,
+}
+
+/// Instância de tmpfs
+pub struct TmpFS {
+    nodes: BTreeMap<NodeId, Node>,
+    next_id: NodeId,
+    /// Bytes ocupados por arquivos
+    used: usize,
+    max_size: usize,
+}
+
+impl TmpFS {
+    /// Cria um tmpfs vazio (só a raiz) limitado a `max_size` bytes.
+    pub fn new(max_size: usize) -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            ROOT,
+            Node {
+                kind: NodeKind::Dir(BTreeMap::new()),
+            },
+        );
+        Self {
+            nodes,
+            next_id: ROOT + 1,
+            used: 0,
+            max_size,
+        }
+    }
+
+    /// Bytes ocupados
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Limite de bytes
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Resolve `path` para um nó.
+    pub fn lookup(&self, path: &str) -> Result<NodeId, FsError> {
+        let normalized = path::normalize(path);
+        let mut current = ROOT;
+        for component in path::PathComponents::new(&normalized) {
+            current = self.child(current, component)?;
+        }
+        Ok(current)
+    }
+
+    /// Cria o diretório `path`; o pai precisa existir.
+    pub fn mkdir(&mut self, path: &str) -> Result<NodeId, FsError> {
+        let (parent, name) = self.split_parent(path)?;
+        self.insert(parent, name, NodeKind::Dir(BTreeMap::new()))
+    }
+
+    /// Cria `path` e todos os diretórios intermediários (`mkdir -p`).
+    /// Componentes já existentes precisam ser diretórios.
+    pub fn mkdir_p(&mut self, path: &str) -> Result<NodeId, FsError> {
+        let normalized = path::normalize(path);
+        let mut current = ROOT;
+        for component in path::PathComponents::new(&normalized) {
+            current = match self.child(current, component) {
+                Ok(id) if self.is_dir(id) => id,
+                Ok(_) => return Err(FsError::NotDirectory),
+                Err(FsError::NotFound) => {
+                    self.insert(current, component, NodeKind::Dir(BTreeMap::new()))?
+                }
+                Err(e) => return Err(e),
+            };
+        }
+        Ok(current)
+    }
+
+    /// Cria o arquivo `path` com `data`, criando os diretórios pais que
+    /// faltarem.
+    pub fn create_file(&mut self, path: &str, data: &[u8]) -> Result<NodeId, FsError> {
+        let normalized = path::normalize(path);
+        let (dir, name) = split_last(&normalized).ok_or(FsError::AlreadyExists)?;
+        if self.lookup(&normalized).is_ok() {
+            return Err(FsError::AlreadyExists);
+        }
+        self.reserve(data.len())?;
+
+        let parent = self.mkdir_p(dir)?;
+        let id = self.insert(parent, name, NodeKind::File(data.to_vec()))?;
+        self.used += data.len();
+        Ok(id)
+    }
+
+    /// Conteúdo completo do arquivo `path`.
+    pub fn read_file(&self, path: &str) -> Result<&[u8], FsError> {
+        let id = self.lookup(path)?;
+        self.data(id)
+    }
+
+    /// Lê de `offset` em diante; retorna 0 no fim do arquivo.
+    pub fn read(&self, id: NodeId, offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        let data = self.data(id)?;
+        if offset >= data.len() {
+            return Ok(0);
+        }
+        let n = buf.len().min(data.len() - offset);
+        buf[..n].copy_from_slice(&data[offset..offset + n]);
+        Ok(n)
+    }
+
+    /// Escreve em `offset`, estendendo o arquivo (com zeros) se preciso.
+    pub fn write(&mut self, id: NodeId, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        let len = self.data(id)?.len();
+        let end = offset.checked_add(buf.len()).ok_or(FsError::NoSpace)?;
+        let growth = end.saturating_sub(len);
+        self.reserve(growth)?;
+
+        let data = match self.nodes.get_mut(&id).map(|n| &mut n.kind) {
+            Some(NodeKind::File(data)) => data,
+            _ => return Err(FsError::NotFound),
+        };
+        if growth > 0 {
+            data.resize(end, 0);
+        }
+        data[offset..end].copy_from_slice(buf);
+        self.used += growth;
+        Ok(buf.len())
+    }
+
+    /// Trunca (ou estende com zeros) o arquivo para `size` bytes.
+    pub fn truncate(&mut self, id: NodeId, size: usize) -> Result<(), FsError> {
+        let len = self.data(id)?.len();
+        self.reserve(size.saturating_sub(len))?;
+        if let Some(NodeKind::File(data)) = self.nodes.get_mut(&id).map(|n| &mut n.kind) {
+            data.resize(size, 0);
+        }
+        self.used = self.used - len + size;
+        Ok(())
+    }
+
+    /// Remove um arquivo ou diretório vazio.
+    pub fn remove(&mut self, path: &str) -> Result<(), FsError> {
+        let normalized = path::normalize(path);
+        let (dir, name) = split_last(&normalized).ok_or(FsError::PermissionDenied)?;
+        let parent = self.lookup(dir)?;
+        let id = self.child(parent, name)?;
+
+        match &self.nodes[&id].kind {
+            NodeKind::Dir(children) if !children.is_empty() => return Err(FsError::NotEmpty),
+            NodeKind::Dir(_) => {}
+            NodeKind::File(data) => self.used -= data.len(),
+        }
+        self.nodes.remove(&id);
+        if let Some(NodeKind::Dir(children)) = self.nodes.get_mut(&parent).map(|n| &mut n.kind) {
+            children.remove(name);
+        }
+        Ok(())
+    }
+
+    /// Entradas do diretório `path`, em ordem de nome.
+    pub fn list(&self, path: &str) -> Result<Vec<DirEntry>, FsError> {
+        self.readdir(self.lookup(path)?)
+    }
+
+    /// Entradas do diretório `id`, em ordem de nome.
+    pub fn readdir(&self, id: NodeId) -> Result<Vec<DirEntry>, FsError> {
+        let children = self.children(id)?;
+        Ok(children
+            .iter()
+            .map(|(name, &ino)| DirEntry {
+                name: name.clone(),
+                ino,
+                file_type: self.file_type(ino),
+            })
+            .collect())
+    }
+
+    /// Caminhos completos de tudo abaixo de `path` (pré-ordem, por nome).
+    pub fn list_recursive(&self, path: &str) -> Result<Vec<String>, FsError> {
+        let normalized = path::normalize(path);
+        let root = self.lookup(&normalized)?;
+        self.children(root)?;
+
+        let prefix = if normalized == "/" {
+            String::new()
+        } else {
+            normalized
+        };
+        let mut out = Vec::new();
+        // Pilha explícita: a profundidade da árvore não é limitada
+        let mut pending = Vec::new();
+        self.push_children(&mut pending, &prefix, root);
+        while let Some((entry_path, ino)) = pending.pop() {
+            if self.is_dir(ino) {
+                self.push_children(&mut pending, &entry_path, ino);
+            }
+            out.push(entry_path);
+        }
+        Ok(out)
+    }
+
+    /// Tipo do nó
+    pub fn file_type(&self, id: NodeId) -> FileType {
+        if self.is_dir(id) {
+            FileType::Directory
+        } else {
+            FileType::Regular
+        }
+    }
+
+    // --- Funções internas ---
+
+    /// Empilha os filhos de `dir` em ordem reversa (o menor nome sai primeiro)
+    fn push_children(&self, pending: &mut Vec<(String, NodeId)>, dir_path: &str, dir: NodeId) {
+        if let Ok(children) = self.children(dir) {
+            for (name, &ino) in children.iter().rev() {
+                let mut full = String::from(dir_path);
+                full.push('/');
+                full.push_str(name);
+                pending.push((full, ino));
+            }
+        }
+    }
+
+    fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes.get(&id).map(|n| &n.kind), Some(NodeKind::Dir(_)))
+    }
+
+    fn children(&self, id: NodeId) -> Result<&BTreeMap<String, NodeId>, FsError> {
+        match self.nodes.get(&id).map(|n| &n.kind) {
+            Some(NodeKind::Dir(children)) => Ok(children),
+            Some(NodeKind::File(_)) => Err(FsError::NotDirectory),
+            None => Err(FsError::NotFound),
+        }
+    }
+
+    fn data(&self, id: NodeId) -> Result<&[u8], FsError> {
+        match self.nodes.get(&id).map(|n| &n.kind) {
+            Some(NodeKind::File(data)) => Ok(data),
+            Some(NodeKind::Dir(_)) => Err(FsError::IsDirectory),
+            None => Err(FsError::NotFound),
+        }
+    }
+
+    fn child(&self, dir: NodeId, name: &str) -> Result<NodeId, FsError> {
+        self.children(dir)?
+            .get(name)
+            .copied()
+            .ok_or(FsError::NotFound)
+    }
+
+    fn split_parent(&self, path: &str) -> Result<(NodeId, String), FsError> {
+        let normalized = path::normalize(path);
+        let (dir, name) = split_last(&normalized).ok_or(FsError::AlreadyExists)?;
+        let parent = self.lookup(dir)?;
+        Ok((parent, String::from(name)))
+    }
+
+    fn insert(
+        &mut self,
+        parent: NodeId,
+        name: impl Into<String>,
+        kind: NodeKind,
+    ) -> Result<NodeId, FsError> {
+        let id = self.next_id;
+        let children = match self.nodes.get_mut(&parent).map(|n| &mut n.kind) {
+            Some(NodeKind::Dir(children)) => children,
+            Some(NodeKind::File(_)) => return Err(FsError::NotDirectory),
+            None => return Err(FsError::NotFound),
+        };
+        let name = name.into();
+        if children.contains_key(&name) {
+            return Err(FsError::AlreadyExists);
+        }
+        children.insert(name, id);
+        self.nodes.insert(id, Node { kind });
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Verifica se cabem mais `bytes` no limite
+    fn reserve(&self, bytes: usize) -> Result<(), FsError> {
+        match self.used.checked_add(bytes) {
+            Some(total) if total <= self.max_size => Ok(()),
+            _ => Err(FsError::NoSpace),
+        }
+    }
+}
+
+// =============================================================================
+// VFS BACKEND
+// =============================================================================
+
+/// TmpFS compartilhado, montável no VFS (`vfs::mount`)
+pub struct TmpFsMount {
+    inner: Spinlock<TmpFS>,
+    /// Dispositivo das páginas deste tmpfs no page cache
+    cache_dev: DevId,
+}
+
+impl TmpFsMount {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            inner: Spinlock::new(TmpFS::new(max_size)),
+            cache_dev: filemap::new_dev(),
+        }
+    }
+
+    /// Acesso direto à árvore.
+    ///
+    /// Não passa pelo page cache: alterar por aqui um arquivo já lido pelo
+    /// VFS deixa páginas velhas no cache (criar arquivos novos é seguro).
+    pub fn lock(&self) -> SpinlockGuard<'_, TmpFS> {
+        self.inner.lock()
+    }
+}
+
+impl PageIo for TmpFsMount {
+    fn read_page(&self, ino: InodeNum, index: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let offset = (index as usize).saturating_mul(buf.len());
+        let n = self.inner.lock().read(ino, offset, buf)?;
+        buf[n..].fill(0);
+        Ok(())
+    }
+}
+
+impl Drop for TmpFsMount {
+    fn drop(&mut self) {
+        filemap::invalidate(self.cache_dev, None);
+    }
+}
+
+impl Filesystem for TmpFsMount {
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError> {
+        self.inner.lock().lookup(path)
+    }
+
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let size = self.size(ino)?;
+        filemap::read(self.cache_dev, self, ino, size, offset, buf)
+    }
+
+    fn write(&self, ino: InodeNum, offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        let written = self.inner.lock().write(ino, offset as usize, buf)?;
+        filemap::invalidate(self.cache_dev, Some(ino));
+        Ok(written)
+    }
+
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError> {
+        self.inner.lock().readdir(ino)
+    }
+
+    fn size(&self, ino: InodeNum) -> Result<u64, FsError> {
+        Ok(self.inner.lock().data(ino)?.len() as u64)
+    }
+
+    fn create(&self, path: &str) -> Result<InodeNum, FsError> {
+        self.inner.lock().create_file(path, &[])
+    }
+
+    fn truncate(&self, ino: InodeNum, size: u64) -> Result<(), FsError> {
+        self.inner.lock().truncate(ino, size as usize)?;
+        filemap::invalidate(self.cache_dev, Some(ino));
+        Ok(())
+    }
+
+    fn read_only(&self) -> bool {
+        false
+    }
+}
+
+/// Separa um caminho normalizado em (diretório, último componente).
+/// `None` para a raiz.
+fn split_last(normalized: &str) -> Option<(&str, &str)> {
+    let pos = normalized.rfind('/')?;
+    let name = &normalized[pos + 1..];
+    if name.is_empty() {
+        return None;
+    }
+    let dir = if pos == 0 { "/" } else { &normalized[..pos] };
+    Some((dir, name))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_creation() {
+        let mut fs = TmpFS::new(DEFAULT_MAX_SIZE);
+        let dir = fs.mkdir_p("/tmp/foo/bar").unwrap();
+        assert_eq!(fs.lookup("/tmp/foo/bar"), Ok(dir));
+        // Idempotente
+        assert_eq!(fs.mkdir_p("/tmp/foo/bar"), Ok(dir));
+
+        // Pai inexistente: mkdir falha, create_file cria os pais
+        assert_eq!(fs.mkdir("/a/b"), Err(FsError::NotFound));
+        fs.create_file("/a/b/c.txt", b"x").unwrap();
+        assert_eq!(
+            fs.file_type(fs.lookup("/a/b").unwrap()),
+            FileType::Directory
+        );
+
+        assert_eq!(
+            fs.create_file("/a/b/c.txt", b"y"),
+            Err(FsError::AlreadyExists)
+        );
+        assert_eq!(fs.mkdir_p("/a/b/c.txt/d"), Err(FsError::NotDirectory));
+    }
+
+    #[test]
+    fn test_path_read() {
+        let mut fs = TmpFS::new(DEFAULT_MAX_SIZE);
+        fs.create_file("/tmp/foo/bar", b"hello").unwrap();
+        assert_eq!(fs.read_file("/tmp/foo/bar"), Ok(&b"hello"[..]));
+        assert_eq!(fs.read_file("/tmp/./foo/../foo/bar"), Ok(&b"hello"[..]));
+        assert_eq!(fs.read_file("/tmp/foo"), Err(FsError::IsDirectory));
+        assert_eq!(fs.read_file("/tmp/nope"), Err(FsError::NotFound));
+
+        // Escrita além do fim estende com zeros, sem limite fixo de tamanho
+        let id = fs.lookup("/tmp/foo/bar").unwrap();
+        fs.write(id, 8192, b"!").unwrap();
+        let data = fs.read_file("/tmp/foo/bar").unwrap();
+        assert_eq!(data.len(), 8193);
+        assert_eq!(&data[..5], b"hello");
+        assert_eq!(data[5], 0);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read(id, 1, &mut buf), Ok(4));
+        assert_eq!(&buf, b"ello");
+        assert_eq!(fs.read(id, 9000, &mut buf), Ok(0));
+    }
+
+    #[test]
+    fn test_recursive_list() {
+        let mut fs = TmpFS::new(DEFAULT_MAX_SIZE);
+        fs.create_file("/tmp/b.txt", b"").unwrap();
+        fs.create_file("/tmp/a/x", b"").unwrap();
+        fs.mkdir_p("/tmp/a/y/z").unwrap();
+        fs.create_file("/etc/conf", b"").unwrap();
+
+        assert_eq!(
+            fs.list_recursive("/").unwrap(),
+            [
+                "/etc",
+                "/etc/conf",
+                "/tmp",
+                "/tmp/a",
+                "/tmp/a/x",
+                "/tmp/a/y",
+                "/tmp/a/y/z",
+                "/tmp/b.txt",
+            ]
+        );
+        assert_eq!(
+            fs.list_recursive("/tmp/a").unwrap(),
+            ["/tmp/a/x", "/tmp/a/y", "/tmp/a/y/z"]
+        );
+
+        let names: Vec<String> = fs
+            .list("/tmp")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["a", "b.txt"]);
+        assert_eq!(fs.list("/tmp/b.txt").err(), Some(FsError::NotDirectory));
+    }
+
+    #[test]
+    fn test_max_size_accounting() {
+        let mut fs = TmpFS::new(10);
+        fs.create_file("/d/one", b"12345").unwrap();
+        let two = fs.create_file("/d/e/two", b"123").unwrap();
+        assert_eq!(fs.used(), 8);
+
+        // Estouraria o limite: nada muda
+        assert_eq!(fs.create_file("/three", b"123"), Err(FsError::NoSpace));
+        assert_eq!(fs.write(two, 3, b"456"), Err(FsError::NoSpace));
+        assert_eq!(fs.lookup("/three"), Err(FsError::NotFound));
+        assert_eq!(fs.used(), 8);
+
+        // Sobrescrever dentro do tamanho atual não consome espaço
+        fs.write(two, 0, b"abc").unwrap();
+        fs.write(two, 3, b"de").unwrap();
+        assert_eq!(fs.used(), 10);
+
+        assert_eq!(fs.remove("/d"), Err(FsError::NotEmpty));
+        fs.remove("/d/one").unwrap();
+        assert_eq!(fs.used(), 5);
+        fs.truncate(two, 1).unwrap();
+        assert_eq!(fs.used(), 1);
+        fs.remove("/d/e/two").unwrap();
+        fs.remove("/d/e").unwrap();
+        assert_eq!(fs.used(), 0);
+        assert!(fs.list("/d").unwrap().is_empty());
+    }
+}
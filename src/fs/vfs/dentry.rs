@@ -1,15 +1,236 @@
 //! Directory Entry Cache
 //!
 //! Cache para acelerar lookups de path para inode.
+//!
+//! Guarda o inode de cada componente já resolvido, pela chave
+//! `(inode do pai, nome)`. `vfs::lookup` consulta o cache antes da árvore de
+//! inodes e o preenche a cada resolução que passou pela árvore. Só nomes que
+//! existem entram (não há entradas negativas).
+//!
+//! O tamanho é limitado a [`DCACHE_CAPACITY`] entradas; passando disso, a
+//! usada há mais tempo sai (LRU).
+//!
+//! ## Invalidação
+//! - [`invalidate_inode`]: um inode removido sai como filho e como pai.
+//! - [`invalidate_all`]: desmontagem (`mount::unmount`).
 
-use super::inode::InodeNum;
-use alloc::string::String;
-use alloc::sync::Arc;
+use crate::klib::hash::{fnv1a, HashTable};
 use crate::sync::Spinlock;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use super::inode::InodeNum;
+
+/// Entradas mantidas no cache global
+pub const DCACHE_CAPACITY: usize = 256;
+
+/// `(inode do pai, hash do nome)`: o nome completo fica na entrada, para não
+/// alocar uma `String` a cada consulta
+type DentryKey = (InodeNum, u64);
+
+struct Dentry {
+    name: String,
+    ino: InodeNum,
+    /// Momento do último uso (chave em `DentryCache::lru`)
+    stamp: u64,
+}
+
+/// Estatísticas do cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DcacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// Cache `(pai, nome) -> inode` com despejo LRU
+pub struct DentryCache {
+    entries: HashTable<DentryKey, Dentry>,
+    /// Entradas por momento do último uso (a primeira é a mais antiga)
+    lru: BTreeMap<u64, DentryKey>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl DentryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashTable::new(capacity.max(1)),
+            lru: BTreeMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Inode de `name` dentro de `parent`, se estiver no cache
+    pub fn lookup(&mut self, parent: InodeNum, name: &str) -> Option<InodeNum> {
+        let key = (parent, fnv1a(name));
+        let stamp = self.tick();
+        match self.entries.get_mut(&key) {
+            Some(dentry) if dentry.name == name => {
+                self.lru.remove(&dentry.stamp);
+                self.lru.insert(stamp, key);
+                dentry.stamp = stamp;
+                self.hits += 1;
+                Some(dentry.ino)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Registra `name` em `parent` como `ino`
+    pub fn insert(&mut self, parent: InodeNum, name: &str, ino: InodeNum) {
+        let key = (parent, fnv1a(name));
+        let stamp = self.tick();
+        let dentry = Dentry {
+            name: String::from(name),
+            ino,
+            stamp,
+        };
+        // Mesma chave (ou colisão de hash): a entrada nova substitui
+        if let Some(old) = self.entries.insert(key, dentry) {
+            self.lru.remove(&old.stamp);
+        }
+        self.lru.insert(stamp, key);
+
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.lru.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// Remove as entradas em que `ino` é o filho ou o pai
+    pub fn invalidate_inode(&mut self, ino: InodeNum) {
+        let stale: alloc::vec::Vec<(DentryKey, u64)> = self
+            .entries
+            .iter()
+            .filter(|(key, dentry)| key.0 == ino || dentry.ino == ino)
+            .map(|(key, dentry)| (*key, dentry.stamp))
+            .collect();
+        for (key, stamp) in stale {
+            self.entries.remove(&key);
+            self.lru.remove(&stamp);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries = HashTable::new(self.capacity);
+        self.lru.clear();
+    }
+
+    pub fn stats(&self) -> DcacheStats {
+        DcacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
+
+/// Cache global, criado no primeiro uso
+static DCACHE: Spinlock<Option<DentryCache>> = Spinlock::new(None);
+
+fn with_cache<R>(f: impl FnOnce(&mut DentryCache) -> R) -> R {
+    let mut cache = DCACHE.lock();
+    f(cache.get_or_insert_with(|| DentryCache::new(DCACHE_CAPACITY)))
+}
+
+/// Consulta o cache global
+pub fn lookup(parent: InodeNum, name: &str) -> Option<InodeNum> {
+    with_cache(|cache| cache.lookup(parent, name))
+}
+
+/// Preenche o cache global
+pub fn insert(parent: InodeNum, name: &str, ino: InodeNum) {
+    with_cache(|cache| cache.insert(parent, name, ino));
+}
+
+/// Esquece `ino` (chamar ao remover o inode)
+pub fn invalidate_inode(ino: InodeNum) {
+    with_cache(|cache| cache.invalidate_inode(ino));
+}
+
+/// Esvazia o cache global
+pub fn invalidate_all() {
+    with_cache(DentryCache::clear);
+}
+
+/// Estatísticas do cache global
+pub fn stats() -> DcacheStats {
+    with_cache(|cache| cache.stats())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lookup_hits() {
+        let mut cache = DentryCache::new(8);
+        assert_eq!(cache.lookup(0, "system"), None);
+        cache.insert(0, "system", 1);
+        assert_eq!(cache.lookup(0, "system"), Some(1));
+        assert_eq!(cache.lookup(0, "system"), Some(1));
+        // Mesmo nome sob outro pai é outra entrada
+        assert_eq!(cache.lookup(1, "system"), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 1));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = DentryCache::new(3);
+        cache.insert(0, "a", 1);
+        cache.insert(0, "b", 2);
+        cache.insert(0, "c", 3);
+        // "a" passa a ser a mais recente; "b" é a mais antiga
+        assert_eq!(cache.lookup(0, "a"), Some(1));
+        cache.insert(0, "d", 4);
+
+        assert_eq!(cache.stats().entries, 3);
+        assert_eq!(cache.lookup(0, "b"), None);
+        assert_eq!(cache.lookup(0, "a"), Some(1));
+        assert_eq!(cache.lookup(0, "c"), Some(3));
+        assert_eq!(cache.lookup(0, "d"), Some(4));
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let mut cache = DentryCache::new(8);
+        cache.insert(0, "apps", 5);
+        cache.insert(5, "shell", 6);
+        cache.insert(0, "boot", 7);
+
+        // 5 sai como filho ("apps") e como pai ("shell")
+        cache.invalidate_inode(5);
+        assert_eq!(cache.lookup(0, "apps"), None);
+        assert_eq!(cache.lookup(5, "shell"), None);
+        assert_eq!(cache.lookup(0, "boot"), Some(7));
 
-pub struct Dentry {
-    pub name: String,
-    pub ino: InodeNum,
-    pub parent: Option<Arc<Spinlock<Dentry>>>,
-    // TODO: children cache
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.lookup(0, "boot"), None);
+        // Reinserir após limpar não deixa lixo no LRU
+        cache.insert(0, "boot", 7);
+        assert_eq!(cache.lru.len(), 1);
+    }
 }
@@ -1,8 +1,10 @@
 #![allow(dead_code)]
 //! Arquivo aberto
 
-use super::inode::{FsError, Inode};
+use super::inode::{FsError, Inode, InodeNum};
+use super::mount::Filesystem;
 use crate::sync::Mutex;
+use alloc::sync::Arc;
 
 /// Flags de abertura
 #[derive(Debug, Clone, Copy)]
@@ -14,6 +16,33 @@ impl OpenFlags {
     pub const APPEND: u32 = 4;
     pub const CREATE: u32 = 8;
     pub const TRUNCATE: u32 = 16;
+
+    /// Pede um backend gravável (`WRITE`, `APPEND` ou `TRUNCATE`)
+    pub fn writes(&self) -> bool {
+        self.0 & (Self::WRITE | Self::APPEND | Self::TRUNCATE) != 0
+    }
+}
+
+/// Referência de um seek (`lseek`: SEEK_SET, SEEK_CUR, SEEK_END)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    Start(u64),
+    Current(i64),
+    End(i64),
+}
+
+/// Posição resultante de `from`, com o cursor em `current` e o arquivo com
+/// `size` bytes.
+///
+/// `None` se o resultado for negativo ou não couber em 64 bits. Passar do
+/// fim é permitido: leituras lá retornam 0.
+pub fn seek_target(current: u64, size: u64, from: SeekFrom) -> Option<u64> {
+    let (base, delta) = match from {
+        SeekFrom::Start(position) => return Some(position),
+        SeekFrom::Current(delta) => (current, delta),
+        SeekFrom::End(delta) => (size, delta),
+    };
+    base.checked_add_signed(delta)
 }
 
 /// Operações de arquivo
@@ -23,10 +52,21 @@ pub trait FileOps {
     fn seek(&self, position: u64);
 }
 
+/// Nó por trás de um arquivo aberto
+enum Node {
+    /// Inode da árvore interna do VFS
+    Inode(*const Inode),
+    /// Inode de um backend montado
+    Mounted {
+        fs: Arc<dyn Filesystem>,
+        ino: InodeNum,
+    },
+}
+
 /// Arquivo aberto
 pub struct File {
-    /// Inode associado
-    inode: *const Inode,
+    /// Nó associado
+    node: Node,
     /// Posição atual
     offset: Mutex<u64>,
     /// Flags de abertura
@@ -51,7 +91,16 @@ impl File {
     /// Cria arquivo aberto
     pub fn new(inode: *const Inode, flags: OpenFlags) -> Self {
         Self {
-            inode,
+            node: Node::Inode(inode),
+            offset: Mutex::new(0),
+            flags,
+        }
+    }
+
+    /// Abre o inode `ino` de um backend montado
+    pub fn mounted(fs: Arc<dyn Filesystem>, ino: InodeNum, flags: OpenFlags) -> Self {
+        Self {
+            node: Node::Mounted { fs, ino },
             offset: Mutex::new(0),
             flags,
         }
@@ -59,18 +108,25 @@ impl File {
 
     /// Lê dados
     pub fn read_impl(&self, buf: &mut [u8]) -> Result<usize, FsError> {
-        let inode = unsafe { &*self.inode };
         let mut offset = self.offset.lock();
-        let bytes = inode.ops.read(*offset, buf)?;
+        let bytes = match &self.node {
+            Node::Inode(inode) => unsafe { &**inode }.ops.read(*offset, buf)?,
+            Node::Mounted { fs, ino } => fs.read(*ino, *offset, buf)?,
+        };
         *offset += bytes as u64;
         Ok(bytes)
     }
 
-    /// Escreve dados
+    /// Escreve dados (com `APPEND`, sempre no fim do arquivo)
     pub fn write_impl(&self, buf: &[u8]) -> Result<usize, FsError> {
-        let inode = unsafe { &*self.inode };
         let mut offset = self.offset.lock();
-        let bytes = inode.ops.write(*offset, buf)?;
+        if self.flags.0 & OpenFlags::APPEND != 0 {
+            *offset = self.size()?;
+        }
+        let bytes = match &self.node {
+            Node::Inode(inode) => unsafe { &**inode }.ops.write(*offset, buf)?,
+            Node::Mounted { fs, ino } => fs.write(*ino, *offset, buf)?,
+        };
         *offset += bytes as u64;
         Ok(bytes)
     }
@@ -79,4 +135,48 @@ impl File {
     pub fn seek_impl(&self, position: u64) {
         *self.offset.lock() = position;
     }
+
+    /// Tamanho atual do arquivo em bytes
+    pub fn size(&self) -> Result<u64, FsError> {
+        match &self.node {
+            Node::Inode(inode) => Ok(unsafe { &**inode }.size),
+            Node::Mounted { fs, ino } => fs.size(*ino),
+        }
+    }
+
+    /// Move o cursor (`lseek`) e retorna a nova posição.
+    ///
+    /// Posição final negativa é `InvalidArgument` e não move o cursor.
+    pub fn lseek(&self, from: SeekFrom) -> Result<u64, FsError> {
+        let size = match from {
+            SeekFrom::End(_) => self.size()?,
+            _ => 0,
+        };
+        let mut offset = self.offset.lock();
+        *offset = seek_target(*offset, size, from).ok_or(FsError::InvalidArgument)?;
+        Ok(*offset)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_target() {
+        assert_eq!(seek_target(10, 100, SeekFrom::Start(42)), Some(42));
+        assert_eq!(seek_target(10, 100, SeekFrom::Current(5)), Some(15));
+        assert_eq!(seek_target(10, 100, SeekFrom::Current(-10)), Some(0));
+        assert_eq!(seek_target(10, 100, SeekFrom::End(-1)), Some(99));
+        // Depois do fim é permitido
+        assert_eq!(seek_target(10, 100, SeekFrom::End(50)), Some(150));
+
+        assert_eq!(seek_target(10, 100, SeekFrom::Current(-11)), None);
+        assert_eq!(seek_target(10, 100, SeekFrom::End(-101)), None);
+        assert_eq!(seek_target(u64::MAX, 0, SeekFrom::Current(1)), None);
+    }
 }
@@ -1,5 +1,8 @@
 //! Inode - metadados de arquivo
 
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
 /// Tipo de arquivo
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileType {
@@ -59,6 +62,8 @@ pub struct Inode {
     pub atime: u64,
     pub mtime: u64,
     pub ctime: u64,
+    /// Filhos por nome (apenas diretórios)
+    pub children: BTreeMap<String, InodeNum>,
     /// Operações específicas
     pub ops: &'static dyn InodeOps,
 }
@@ -86,7 +91,7 @@ pub struct DirEntry {
 }
 
 /// Erro de filesystem
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FsError {
     NotFound,
     NotDirectory,
@@ -96,4 +101,7 @@ pub enum FsError {
     ReadOnly,
     NoSpace,
     InvalidFormat,
+    AlreadyExists,
+    NotEmpty,
+    InvalidArgument,
 }
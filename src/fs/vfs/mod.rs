@@ -30,34 +30,30 @@ pub mod inode;
 pub mod mount;
 pub mod path;
 
-pub use file::FileOps;
 use file::{File, OpenFlags};
+pub use file::{FileOps, SeekFrom};
 use inode::{DirEntry, FileMode, FileType, FsError, Inode, InodeNum, InodeOps};
+pub use mount::{mount, sync, unmount, Filesystem};
 
 use crate::sync::Spinlock;
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
 
-/// Instância raiz do VFS (placeholder)
-pub struct RootVfs;
-pub const ROOT_VFS: RootVfs = RootVfs;
+/// Árvore de inodes (em `Box`: `File` guarda ponteiro para o inode, que não
+/// pode mudar de lugar quando o mapa cresce)
+static INODES: Spinlock<BTreeMap<InodeNum, Box<Inode>>> = Spinlock::new(BTreeMap::new());
 
-impl RootVfs {
-    /// Lock dummy (placeholder)
-    pub fn lock(&self) -> &Self {
-        self
-    }
-
-    /// Lookup placeholder
-    pub fn lookup(&self, _path: &str) -> Result<Inode, FsError> {
-        Err(FsError::NotFound)
-    }
-}
-
-/// Árvore de inodes
-static INODES: Spinlock<BTreeMap<InodeNum, Inode>> = Spinlock::new(BTreeMap::new());
+/// Próximo número de inode livre (0 é a raiz)
+static NEXT_INO: AtomicU64 = AtomicU64::new(1);
 
-/// Operações dummy para diretórios placeholder
+/// Operações dummy para diretórios placeholder.
+///
+/// Os filhos ficam em `Inode::children`, consultado pelo `lookup` do VFS;
+/// este `lookup` só responde por nomes que o backend resolve sozinho.
 struct DummyDirOps;
 
 impl InodeOps for DummyDirOps {
@@ -90,10 +86,35 @@ fn create_dir_inode(ino: InodeNum) -> Inode {
         atime: 0,
         mtime: 0,
         ctime: 0,
+        children: BTreeMap::new(),
         ops: &DUMMY_DIR_OPS,
     }
 }
 
+/// Cria um diretório vazio `name` dentro de `parent`
+fn insert_dir(
+    inodes: &mut BTreeMap<InodeNum, Box<Inode>>,
+    parent: InodeNum,
+    name: &str,
+) -> Result<InodeNum, FsError> {
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return Err(FsError::InvalidFormat);
+    }
+    let dir = inodes.get_mut(&parent).ok_or(FsError::NotFound)?;
+    if dir.file_type != FileType::Directory {
+        return Err(FsError::NotDirectory);
+    }
+    if dir.children.contains_key(name) {
+        return Err(FsError::AlreadyExists);
+    }
+
+    let ino = NEXT_INO.fetch_add(1, Ordering::SeqCst);
+    dir.children.insert(String::from(name), ino);
+    dir.nlink += 1;
+    inodes.insert(ino, Box::new(create_dir_inode(ino)));
+    Ok(ino)
+}
+
 /// Inicializa o VFS e cria a hierarquia de diretórios
 pub fn init() {
     crate::kinfo!("(VFS) Inicializando...");
@@ -101,72 +122,120 @@ pub fn init() {
     let mut inodes = INODES.lock();
 
     // Raiz /
-    inodes.insert(0, create_dir_inode(0));
+    inodes.insert(0, Box::new(create_dir_inode(0)));
 
     // Hierarquia RedstoneOS
     let dirs = [
-        (1, "system"),
-        (2, "apps"),
-        (3, "users"),
-        (4, "devices"),
-        (5, "volumes"),
-        (6, "runtime"),
-        (7, "state"),
-        (8, "data"),
-        (9, "net"),
-        (10, "snapshots"),
-        (11, "boot"),
+        "system",
+        "apps",
+        "users",
+        "devices",
+        "volumes",
+        "runtime",
+        "state",
+        "data",
+        "net",
+        "snapshots",
+        "boot",
     ];
 
-    for (id, name) in dirs {
-        inodes.insert(id, create_dir_inode(id));
-        crate::kinfo!("(VFS) Criado /", name);
+    for name in dirs {
+        if insert_dir(&mut inodes, 0, name).is_ok() {
+            crate::kinfo!("(VFS) Criado /", name);
+        }
     }
+    drop(inodes);
+
+    // Backends iniciais: initramfs na raiz, estado volátil em tmpfs
+    let _ = mount("/", Arc::new(crate::fs::initramfs::InitramFs));
+    let _ = mount(
+        "/runtime",
+        Arc::new(crate::fs::tmpfs::TmpFsMount::new(
+            crate::fs::tmpfs::DEFAULT_MAX_SIZE,
+        )),
+    );
+    crate::fs::devfs::init();
+    crate::fs::procfs::init();
+    crate::fs::sysfs::init();
 }
 
-/// Abre um arquivo
-pub fn open(path: &str, flags: OpenFlags) -> Result<File, FsError> {
+/// Cria o diretório `path`; o pai precisa existir.
+pub fn mkdir(path: &str) -> Result<InodeNum, FsError> {
     let normalized = path::normalize(path);
-    let ino = lookup(&normalized)?;
+    let (parent, name) = match normalized.rfind('/') {
+        Some(0) => ("/", &normalized[1..]),
+        Some(pos) => (&normalized[..pos], &normalized[pos + 1..]),
+        None => return Err(FsError::InvalidFormat),
+    };
+    let parent_ino = lookup(parent)?;
+    insert_dir(&mut INODES.lock(), parent_ino, name)
+}
+
+/// Abre um arquivo.
+///
+/// Caminhos sob um ponto de montagem vão para o backend (com `CREATE`, o
+/// arquivo é criado se não existir; com `TRUNCATE`, é esvaziado); o que o
+/// backend não conhece cai na árvore interna do VFS. Flags de escrita num
+/// backend somente leitura falham com `ReadOnly`.
+pub fn open(path: &str, flags: OpenFlags) -> Result<File, FsError> {
+    if let Some((fs, rel)) = mount::resolve(path) {
+        let found = match fs.lookup(&rel) {
+            Err(FsError::NotFound) if flags.0 & OpenFlags::CREATE != 0 => fs.create(&rel),
+            other => other,
+        };
+        match found {
+            Ok(_) if flags.writes() && fs.read_only() => return Err(FsError::ReadOnly),
+            Ok(ino) => {
+                if flags.0 & OpenFlags::TRUNCATE != 0 {
+                    fs.truncate(ino, 0)?;
+                }
+                return Ok(File::mounted(fs, ino, flags));
+            }
+            Err(FsError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let ino = lookup(path)?;
 
     let inodes = INODES.lock();
     let inode = inodes.get(&ino).ok_or(FsError::NotFound)?;
 
-    Ok(File::new(inode as *const Inode, flags))
+    Ok(File::new(&**inode as *const Inode, flags))
 }
 
-/// Resolve caminho para número de inode
-fn lookup(path: &str) -> Result<InodeNum, FsError> {
-    if path == "/" {
+/// Resolve caminho para número de inode.
+///
+/// Cada componente é procurado primeiro no dentry cache; só os ausentes
+/// passam pela árvore de inodes, e entram no cache.
+pub fn lookup(path: &str) -> Result<InodeNum, FsError> {
+    let normalized = path::normalize(path);
+    if normalized == "/" {
         return Ok(0);
     }
 
     let mut current_ino: InodeNum = 0;
+    let inodes = INODES.lock();
 
-    for component in path::PathComponents::new(path) {
-        let inodes = INODES.lock();
-        let inode = inodes.get(&current_ino).ok_or(FsError::NotFound)?;
+    for component in path::PathComponents::new(&normalized) {
+        if let Some(ino) = dentry::lookup(current_ino, component) {
+            current_ino = ino;
+            continue;
+        }
 
-        if let Some(next) = inode.ops.lookup(component) {
-            current_ino = next;
-        } else {
-            // Fallback para diretórios raiz estáticos
-            // TODO: Implementar lookup real via mount table
-            match (current_ino, component) {
-                (0, "system") => current_ino = 1,
-                (0, "apps") => current_ino = 2,
-                (0, "users") => current_ino = 3,
-                (0, "devices") => current_ino = 4,
-                (0, "volumes") => current_ino = 5,
-                (0, "runtime") => current_ino = 6,
-                (0, "state") => current_ino = 7,
-                (0, "data") => current_ino = 8,
-                (0, "net") => current_ino = 9,
-                (0, "snapshots") => current_ino = 10,
-                (0, "boot") => current_ino = 11,
-                _ => return Err(FsError::NotFound),
-            }
+        let inode = inodes.get(&current_ino).ok_or(FsError::NotFound)?;
+        if inode.file_type != FileType::Directory {
+            return Err(FsError::NotDirectory);
         }
+
+        let child = inode
+            .children
+            .get(component)
+            .copied()
+            .or_else(|| inode.ops.lookup(component))
+            .ok_or(FsError::NotFound)?;
+        dentry::insert(current_ino, component, child);
+        current_ino = child;
     }
 
     Ok(current_ino)
@@ -175,6 +244,7 @@ fn lookup(path: &str) -> Result<InodeNum, FsError> {
 /// Lê o conteúdo completo de um arquivo pelo caminho.
 ///
 /// Esta função roteia para o backend correto:
+/// - caminhos sob um ponto de montagem → backend montado
 /// - `/system/core/*` → InitRAMFS (bootstrap)
 /// - `/system/services/*`, `/apps/*`, etc → FAT no disco
 ///
@@ -184,6 +254,21 @@ fn lookup(path: &str) -> Result<InodeNum, FsError> {
 pub fn read_file(path: &str) -> Option<alloc::vec::Vec<u8>> {
     crate::ktrace!("(VFS) read_file():", path);
 
+    // Rota 0: pontos de montagem
+    if mount::resolve(path).is_some() {
+        if let Ok(file) = open(path, OpenFlags(OpenFlags::READ)) {
+            let mut data = Vec::new();
+            let mut chunk = [0u8; 512];
+            loop {
+                match file.read_impl(&mut chunk) {
+                    Ok(0) => return Some(data),
+                    Ok(n) => data.extend_from_slice(&chunk[..n]),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
     // Rota 1: InitRAMFS para arquivos de bootstrap
     // O initramfs contém apenas /system/core/supervisor
     if path.starts_with("/system/core/") {
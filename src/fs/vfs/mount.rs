@@ -1,11 +1,167 @@
 //! Mount points
+//!
+//! Tabela global que associa um prefixo de caminho do VFS a um backend.
+//! A resolução escolhe o ponto de montagem mais longo que casa com o caminho
+//! (por componente: `/tmp` cobre `/tmp/x`, mas não `/tmpx`) e entrega ao
+//! backend o resto do caminho, relativo e sem `/` inicial (`""` é a raiz do
+//! backend).
 
-use super::inode::InodeNum;
+use super::inode::{DirEntry, FsError, InodeNum};
+use crate::sync::Spinlock;
 use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Backend de filesystem montável
+pub trait Filesystem: Send + Sync {
+    /// Resolve um caminho relativo à raiz do backend
+    fn lookup(&self, path: &str) -> Result<InodeNum, FsError>;
+
+    /// Lê dados de um arquivo
+    fn read(&self, ino: InodeNum, offset: u64, buf: &mut [u8]) -> Result<usize, FsError>;
+
+    /// Escreve dados em um arquivo
+    fn write(&self, _ino: InodeNum, _offset: u64, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    /// Lista um diretório
+    fn readdir(&self, ino: InodeNum) -> Result<Vec<DirEntry>, FsError>;
+
+    /// Tamanho de um arquivo em bytes (base do `SeekFrom::End`).
+    ///
+    /// 0 para arquivos sem tamanho fixo (dispositivos, conteúdo gerado).
+    fn size(&self, _ino: InodeNum) -> Result<u64, FsError> {
+        Ok(0)
+    }
+
+    /// Cria um arquivo vazio no caminho relativo
+    fn create(&self, _path: &str) -> Result<InodeNum, FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    /// Trunca (ou estende com zeros) um arquivo para `size` bytes
+    fn truncate(&self, _ino: InodeNum, _size: u64) -> Result<(), FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    /// Grava no dispositivo o que está pendente em memória (páginas sujas)
+    fn sync(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    /// Backend não aceita escrita: `vfs::open` recusa flags de escrita com
+    /// `ReadOnly`. Como os padrões de `write`/`create`, só quem grava
+    /// sobrescreve.
+    fn read_only(&self) -> bool {
+        true
+    }
+}
 
 pub struct Mount {
-    pub device: String,
+    /// Caminho normalizado do ponto de montagem
     pub path: String,
-    pub root_ino: InodeNum,
-    // TODO: fs instance ref
+    /// Backend montado
+    pub fs: Arc<dyn Filesystem>,
+}
+
+/// Pontos de montagem ativos
+static MOUNTS: Spinlock<Vec<Mount>> = Spinlock::new(Vec::new());
+
+/// Monta `fs` em `path`.
+pub fn mount(path: &str, fs: Arc<dyn Filesystem>) -> Result<(), FsError> {
+    let path = super::path::normalize(path);
+    let mut mounts = MOUNTS.lock();
+    if mounts.iter().any(|m| m.path == path) {
+        return Err(FsError::AlreadyExists);
+    }
+    crate::kinfo!("(VFS) Montado em ", path.as_str());
+    mounts.push(Mount { path, fs });
+    Ok(())
+}
+
+/// Desmonta o backend em `path`.
+pub fn unmount(path: &str) -> Result<(), FsError> {
+    let path = super::path::normalize(path);
+    let mut mounts = MOUNTS.lock();
+    let index = mounts
+        .iter()
+        .position(|m| m.path == path)
+        .ok_or(FsError::NotFound)?;
+    let removed = mounts.remove(index);
+    drop(mounts);
+    // Grava o pendente antes que o backend suma (sem o lock: faz I/O)
+    if removed.fs.sync().is_err() {
+        crate::kerror!("(VFS) Falha no sync ao desmontar", removed.path.as_str());
+    }
+    drop(removed);
+    // Inodes do backend desmontado não podem mais sair do cache
+    super::dentry::invalidate_all();
+    Ok(())
+}
+
+/// Grava em todos os backends montados o que está pendente em memória.
+/// Segue mesmo se um falhar; retorna o último erro.
+pub fn sync() -> Result<(), FsError> {
+    let backends: Vec<Arc<dyn Filesystem>> = MOUNTS.lock().iter().map(|m| m.fs.clone()).collect();
+    let mut result = Ok(());
+    for fs in backends {
+        if let Err(e) = fs.sync() {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+/// Backend responsável por `path` e o caminho relativo a ele.
+pub fn resolve(path: &str) -> Option<(Arc<dyn Filesystem>, String)> {
+    let path = super::path::normalize(path);
+    let mounts = MOUNTS.lock();
+
+    let mut best: Option<(&Mount, &str)> = None;
+    for mount in mounts.iter() {
+        if let Some(rest) = relative_to(&mount.path, &path) {
+            let longer = match best {
+                Some((current, _)) => mount.path.len() > current.path.len(),
+                None => true,
+            };
+            if longer {
+                best = Some((mount, rest));
+            }
+        }
+    }
+    best.map(|(mount, rest)| (mount.fs.clone(), String::from(rest)))
+}
+
+/// Resto de `path` abaixo de `mount_point`, se ele estiver montado lá.
+/// Ambos normalizados.
+fn relative_to<'a>(mount_point: &str, path: &'a str) -> Option<&'a str> {
+    if mount_point == "/" {
+        return Some(path.trim_start_matches('/'));
+    }
+    let rest = path.strip_prefix(mount_point)?;
+    if rest.is_empty() {
+        Some("")
+    } else {
+        rest.strip_prefix('/')
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_to() {
+        assert_eq!(relative_to("/", "/init"), Some("init"));
+        assert_eq!(relative_to("/", "/"), Some(""));
+        assert_eq!(relative_to("/tmp", "/tmp"), Some(""));
+        assert_eq!(relative_to("/tmp", "/tmp/a/b"), Some("a/b"));
+        assert_eq!(relative_to("/tmp", "/tmpx"), None);
+        assert_eq!(relative_to("/dev", "/tmp/dev"), None);
+    }
 }
@@ -0,0 +1,243 @@
+//! IPC EventFd
+//!
+//! Contador de eventos (estilo `eventfd`)
+//!
+//! Um `u64` compartilhado: `write(n)` soma `n`, `read` devolve o valor e
+//! zera. No modo semáforo, `read` devolve 1 e decrementa. É a forma mais
+//! barata de uma task avisar outra: sem fila nem cópia de mensagem, e
+//! avisos repetidos antes da leitura se acumulam em um só valor.
+//!
+//! ## Bloqueio
+//! - `read` bloqueia enquanto o contador é 0.
+//! - `write` bloqueia enquanto a soma passaria de [`EVENTFD_MAX`].
+//!
+//! As esperas usam `WaitQueue::wait_until`, como em `Pipe`. `poll` vê o
+//! contador legível quando não é zero e gravável enquanto cabe mais 1.
+
+use crate::sched::sync::{poll, Pollable, WaitQueue};
+use crate::sync::{Mutex, Spinlock};
+use crate::syscall::abi::types::poll_events;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Maior valor do contador (`u64::MAX` fica reservado, como no Linux)
+pub const EVENTFD_MAX: u64 = u64::MAX - 1;
+
+/// Falha de uma operação no contador
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFdError {
+    /// A operação bloquearia (contador zerado ou cheio)
+    WouldBlock,
+    /// `write` de um valor que nunca caberia (`u64::MAX`)
+    InvalidValue,
+}
+
+// =============================================================================
+// CONTADOR
+// =============================================================================
+
+/// Regras do contador, sem bloqueio
+struct Counter {
+    value: u64,
+    semaphore: bool,
+}
+
+impl Counter {
+    fn try_add(&mut self, n: u64) -> Result<(), EventFdError> {
+        if n > EVENTFD_MAX {
+            return Err(EventFdError::InvalidValue);
+        }
+        if n > EVENTFD_MAX - self.value {
+            return Err(EventFdError::WouldBlock);
+        }
+        self.value += n;
+        Ok(())
+    }
+
+    fn try_take(&mut self) -> Result<u64, EventFdError> {
+        if self.value == 0 {
+            return Err(EventFdError::WouldBlock);
+        }
+        if self.semaphore {
+            self.value -= 1;
+            Ok(1)
+        } else {
+            Ok(core::mem::take(&mut self.value))
+        }
+    }
+}
+
+// =============================================================================
+// EVENTFD
+// =============================================================================
+
+struct EventFdShared {
+    counter: Mutex<Counter>,
+    /// Leitores aguardando o contador sair de 0
+    readable: WaitQueue,
+    /// Escritores aguardando espaço no contador
+    writable: WaitQueue,
+}
+
+/// Contador de eventos compartilhado (clones apontam para o mesmo contador)
+#[derive(Clone)]
+pub struct EventFd(Arc<EventFdShared>);
+
+impl EventFd {
+    /// Contador começando em `initial`; `semaphore` liga o modo semáforo
+    pub fn new(initial: u64, semaphore: bool) -> Self {
+        Self(Arc::new(EventFdShared {
+            counter: Mutex::new(Counter {
+                value: initial.min(EVENTFD_MAX),
+                semaphore,
+            }),
+            readable: WaitQueue::new(),
+            writable: WaitQueue::new(),
+        }))
+    }
+
+    /// Soma `n`, bloqueando enquanto a soma passaria de [`EVENTFD_MAX`]
+    pub fn write(&self, n: u64) -> Result<(), EventFdError> {
+        if n > EVENTFD_MAX {
+            return Err(EventFdError::InvalidValue);
+        }
+        self.0
+            .writable
+            .wait_until(|| self.0.counter.lock().try_add(n).is_ok());
+        self.wake_readers();
+        Ok(())
+    }
+
+    /// Soma `n` sem bloquear
+    pub fn try_write(&self, n: u64) -> Result<(), EventFdError> {
+        self.0.counter.lock().try_add(n)?;
+        self.wake_readers();
+        Ok(())
+    }
+
+    /// Lê (zerando, ou decrementando no modo semáforo), bloqueando enquanto
+    /// o contador for 0
+    pub fn read(&self) -> u64 {
+        let mut value = 0;
+        self.0
+            .readable
+            .wait_until(|| match self.0.counter.lock().try_take() {
+                Ok(taken) => {
+                    value = taken;
+                    true
+                }
+                Err(_) => false,
+            });
+        self.wake_writers();
+        value
+    }
+
+    /// Lê sem bloquear: `WouldBlock` com o contador em 0
+    pub fn try_read(&self) -> Result<u64, EventFdError> {
+        let value = self.0.counter.lock().try_take()?;
+        self.wake_writers();
+        Ok(value)
+    }
+
+    /// Valor atual, sem consumir
+    pub fn value(&self) -> u64 {
+        self.0.counter.lock().value
+    }
+
+    pub fn is_semaphore(&self) -> bool {
+        self.0.counter.lock().semaphore
+    }
+
+    fn wake_readers(&self) {
+        self.0.readable.wake_all();
+        poll::notify();
+    }
+
+    fn wake_writers(&self) {
+        self.0.writable.wake_all();
+        poll::notify();
+    }
+}
+
+impl Pollable for EventFd {
+    fn poll_ready(&self) -> u16 {
+        let value = self.value();
+        let mut ready = 0;
+        if value > 0 {
+            ready |= poll_events::IN;
+        }
+        if value < EVENTFD_MAX {
+            ready |= poll_events::OUT;
+        }
+        ready
+    }
+}
+
+// =============================================================================
+// REGISTRO
+// =============================================================================
+
+/// Contadores criados por syscall, pelo id guardado no handle
+/// (`HandleType::Event`). Como as portas do `manager`, ficam registrados
+/// mesmo depois de o handle fechar.
+static REGISTRY: Spinlock<BTreeMap<usize, EventFd>> = Spinlock::new(BTreeMap::new());
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Registra `eventfd` e retorna o id para o handle
+pub fn register(eventfd: EventFd) -> usize {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    REGISTRY.lock().insert(id, eventfd);
+    id
+}
+
+/// Contador registrado com `id`
+pub fn lookup(id: usize) -> Option<EventFd> {
+    REGISTRY.lock().get(&id).cloned()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(value: u64, semaphore: bool) -> Counter {
+        Counter { value, semaphore }
+    }
+
+    #[test]
+    fn test_read_drains_accumulated_writes() {
+        let mut c = counter(0, false);
+        assert_eq!(c.try_take(), Err(EventFdError::WouldBlock));
+        c.try_add(3).unwrap();
+        c.try_add(4).unwrap();
+        assert_eq!(c.try_take(), Ok(7));
+        assert_eq!(c.value, 0);
+        assert_eq!(c.try_take(), Err(EventFdError::WouldBlock));
+    }
+
+    #[test]
+    fn test_semaphore_decrements_by_one() {
+        let mut c = counter(2, true);
+        assert_eq!(c.try_take(), Ok(1));
+        assert_eq!(c.try_take(), Ok(1));
+        assert_eq!(c.try_take(), Err(EventFdError::WouldBlock));
+        c.try_add(1).unwrap();
+        assert_eq!(c.try_take(), Ok(1));
+    }
+
+    #[test]
+    fn test_overflow_limits() {
+        let mut c = counter(EVENTFD_MAX - 1, false);
+        c.try_add(1).unwrap();
+        // Cheio: qualquer soma bloquearia; soma de 0 sempre cabe
+        assert_eq!(c.try_add(1), Err(EventFdError::WouldBlock));
+        assert_eq!(c.try_add(0), Ok(()));
+        assert_eq!(c.try_add(u64::MAX), Err(EventFdError::InvalidValue));
+        assert_eq!(c.try_take(), Ok(EVENTFD_MAX));
+    }
+}
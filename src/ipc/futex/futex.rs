@@ -1,55 +1,151 @@
 //! Fast Userspace Mutex
+//!
+//! ## Buckets
+//! Os esperando ficam em `FUTEX_BUCKETS` buckets indexados por hash do
+//! endereço. Cada bucket mapeia endereço → `WaitQueue`, então um `wake` só
+//! acorda quem espera naquele endereço exato.
+//!
+//! ## Wakeups perdidos
+//! `wait` compara a palavra com o lock da `WaitQueue` adquirido e entra na
+//! fila antes de soltá-lo. Quem acorda altera a palavra ANTES de chamar
+//! `wake` (que toma o mesmo lock), logo o wake ou encontra a task já na fila
+//! ou a comparação já enxerga o valor novo.
 
-use crate::mm::VirtAddr;
+use crate::mm::{MapFlags, VirtAddr};
 use crate::sched::sync::WaitQueue;
 use crate::sync::Spinlock;
+use crate::syscall::uaccess::{UserAccessGuard, USER_SPACE_END};
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU32, Ordering};
 
-/// Tabela global de futexes
-static FUTEX_TABLE: Spinlock<BTreeMap<u64, WaitQueue>> = Spinlock::new(BTreeMap::new());
+/// Número de buckets (potência de 2)
+const FUTEX_BUCKETS: usize = 64;
+
+/// Bucket de espera: endereço → fila
+struct Bucket {
+    queues: Spinlock<BTreeMap<u64, Arc<WaitQueue>>>,
+}
+
+impl Bucket {
+    const fn new() -> Self {
+        Self {
+            queues: Spinlock::new(BTreeMap::new()),
+        }
+    }
+}
+
+/// Tabela global de buckets
+static BUCKETS: [Bucket; FUTEX_BUCKETS] = [const { Bucket::new() }; FUTEX_BUCKETS];
+
+/// Bucket responsável por um endereço (hash multiplicativo)
+fn bucket_of(addr: u64) -> &'static Bucket {
+    let hash = (addr >> 2).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    &BUCKETS[(hash >> (64 - FUTEX_BUCKETS.trailing_zeros())) as usize]
+}
+
+/// Lê a palavra do futex sem arriscar falta de página
+///
+/// Roda com o lock da `WaitQueue` e interrupções desligadas, onde uma falta
+/// não pode ser resolvida: a página tem de estar presente nas tabelas ativas
+/// (e ser de usuário, para endereço de usuário). `None` caso contrário.
+fn load_word(addr: VirtAddr) -> Option<u32> {
+    let (_, flags) = crate::mm::translate_with_flags(addr.as_u64())?;
+    if addr.as_u64() < USER_SPACE_END as u64 && !flags.contains(MapFlags::USER) {
+        return None;
+    }
+    // Com SMAP, a palavra de usuário só é lida com o AC ligado
+    let _uaccess = UserAccessGuard::new();
+    // SAFETY: alinhado a 4 e presente; só leitura atômica
+    let word = unsafe { &*addr.as_ptr::<AtomicU32>() };
+    Some(word.load(Ordering::SeqCst))
+}
 
 /// Futex - primitiva de sincronização userspace
 pub struct Futex;
 
 impl Futex {
-    /// Wait: dorme se *addr == expected
+    /// Wait: dorme se `*addr == expected`, até um `wake` no mesmo endereço.
+    ///
+    /// Retorna `WouldBlock` se o valor já é outro, `Interrupted` se um sinal
+    /// chegou para a thread e `InvalidAddress` se a página da palavra não
+    /// está presente (a syscall a valida e a traz antes). Wakeups espúrios
+    /// são possíveis; o chamador deve reverificar a palavra.
     pub fn wait(addr: VirtAddr, expected: u32) -> Result<(), FutexError> {
-        // Ler valor atual
-        let current = unsafe { *(addr.as_ptr::<u32>()) };
-
-        if current != expected {
-            return Err(FutexError::WouldBlock);
+        if !addr.is_aligned(4) || addr.as_u64() == 0 {
+            return Err(FutexError::InvalidAddress);
         }
 
-        // Adicionar à wait queue
-        let mut table = FUTEX_TABLE.lock();
-        let queue = table.entry(addr.as_u64()).or_insert_with(WaitQueue::new);
+        let bucket = bucket_of(addr.as_u64());
+        let queue = bucket
+            .queues
+            .lock()
+            .entry(addr.as_u64())
+            .or_insert_with(|| Arc::new(WaitQueue::new()))
+            .clone();
 
-        // Dormir
-        queue.wait();
+        let mut unmapped = false;
+        let slept = queue.wait_if_interruptible(|| match load_word(addr) {
+            Some(word) => word == expected,
+            None => {
+                unmapped = true;
+                false
+            }
+        });
+        drop(queue);
+        Self::release_queue(bucket, addr.as_u64());
 
-        Ok(())
+        match slept {
+            Ok(true) => Ok(()),
+            Ok(false) if unmapped => Err(FutexError::InvalidAddress),
+            Ok(false) => Err(FutexError::WouldBlock),
+            Err(_) => Err(FutexError::Interrupted),
+        }
     }
 
-    /// Wake: acorda até N threads esperando em addr
+    /// Wake: acorda até `count` threads esperando em `addr`.
+    ///
+    /// Retorna quantas foram acordadas.
     pub fn wake(addr: VirtAddr, count: u32) -> u32 {
-        let table = FUTEX_TABLE.lock();
+        let bucket = bucket_of(addr.as_u64());
+        let queue = match bucket.queues.lock().get(&addr.as_u64()) {
+            Some(queue) => queue.clone(),
+            None => return 0,
+        };
 
-        if let Some(queue) = table.get(&addr.as_u64()) {
-            let mut woken = 0;
-            for _ in 0..count {
-                queue.wake_one();
-                woken += 1;
+        let woken = queue.wake_many(count as usize) as u32;
+        drop(queue);
+        Self::release_queue(bucket, addr.as_u64());
+        woken
+    }
+
+    /// Remove a fila do bucket quando ninguém mais a referencia.
+    ///
+    /// Uma task entre o lookup e o bloqueio segura um `Arc`, então a fila
+    /// não pode sumir debaixo dela.
+    fn release_queue(bucket: &Bucket, addr: u64) {
+        let mut queues = bucket.queues.lock();
+        if let Some(queue) = queues.get(&addr) {
+            if Arc::strong_count(queue) == 1 && queue.is_empty() {
+                queues.remove(&addr);
             }
-            woken
-        } else {
-            0
         }
     }
+
+    /// Número de threads esperando em `addr`
+    pub fn waiters(addr: VirtAddr) -> usize {
+        bucket_of(addr.as_u64())
+            .queues
+            .lock()
+            .get(&addr.as_u64())
+            .map_or(0, |queue| queue.len())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FutexError {
     WouldBlock,
     InvalidAddress,
+    /// Sinal entregue durante a espera
+    Interrupted,
 }
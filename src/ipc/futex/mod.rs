@@ -3,4 +3,4 @@
 //! Fast Userspace Mutex.
 
 pub mod futex;
-pub use futex::Futex;
+pub use futex::{Futex, FutexError};
@@ -1,4 +1,6 @@
+use crate::sched::sync::{poll, Pollable};
 use crate::sync::Spinlock;
+use crate::syscall::abi::types::poll_events;
 use alloc::collections::BTreeMap;
 use alloc::collections::VecDeque;
 use alloc::string::String;
@@ -14,6 +16,21 @@ struct Port {
     wait_write: crate::sched::sync::waitqueue::WaitQueue,
 }
 
+/// `IN` com mensagens na fila, `OUT` com espaço
+impl Pollable for Port {
+    fn poll_ready(&self) -> u16 {
+        let len = self.queue.lock().len();
+        let mut ready = 0;
+        if len > 0 {
+            ready |= poll_events::IN;
+        }
+        if len < self.capacity {
+            ready |= poll_events::OUT;
+        }
+        ready
+    }
+}
+
 static PORT_REGISTRY: Spinlock<Option<BTreeMap<String, Arc<Port>>>> = Spinlock::new(None);
 static PORT_HANDLES: Spinlock<Vec<Arc<Port>>> = Spinlock::new(Vec::new());
 
@@ -40,6 +57,11 @@ fn get_port_by_handle(handle: usize) -> Option<Arc<Port>> {
     handles.get(handle).cloned()
 }
 
+/// Porta `handle` vista por `poll`
+pub fn pollable(handle: usize) -> Option<Arc<dyn Pollable>> {
+    get_port_by_handle(handle).map(|port| port as Arc<dyn Pollable>)
+}
+
 pub fn create_port(name: &str, capacity: usize) -> Result<usize, ()> {
     // Lazily init registry
     let mut registry_guard = PORT_REGISTRY.lock();
@@ -87,6 +109,8 @@ pub fn send_msg(handle: usize, data: &[u8]) -> Result<usize, ()> {
             return Err(()); // Full (TODO: Block)
         }
         queue.push_back(Vec::from(data));
+        drop(queue);
+        poll::notify();
         Ok(data.len())
     } else {
         Err(())
@@ -95,8 +119,9 @@ pub fn send_msg(handle: usize, data: &[u8]) -> Result<usize, ()> {
 
 pub fn recv_msg(handle: usize, buf: &mut [u8]) -> Result<usize, ()> {
     if let Some(port) = get_port_by_handle(handle) {
-        let mut queue = port.queue.lock();
-        if let Some(msg) = queue.pop_front() {
+        let msg = port.queue.lock().pop_front();
+        if let Some(msg) = msg {
+            poll::notify();
             let len = core::cmp::min(buf.len(), msg.len());
             buf[..len].copy_from_slice(&msg[..len]);
             Ok(len)
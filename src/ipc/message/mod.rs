@@ -2,8 +2,15 @@
 //!
 //! Mensagens são a única forma de comunicação entre processos.
 //! Elas são agnósticas de conteúdo (byte array) mas podem carregar Handles.
+//!
+//! ## Transferência de handles
+//! Um handle anexado com `attach_handle` sai da tabela do remetente (exige o
+//! right `TRANSFER`) e viaja na mensagem como `TransferredHandle`. Na entrega,
+//! `install_handles` o instala na tabela do destinatário com um valor local
+//! novo. A instalação é tudo-ou-nada: sem slots suficientes, nada muda.
 
 use crate::security::capability::CapHandle;
+use crate::syscall::handle::{Handle, HandleRights, HandleTable, HandleType};
 use alloc::vec::Vec;
 
 /// Tamanho máximo do payload de dados em bytes.
@@ -24,6 +31,30 @@ pub struct MessageHeader {
     pub flags: u8,
 }
 
+/// Máximo de handles por mensagem.
+pub const MAX_MESSAGE_HANDLES: usize = 16;
+
+/// Handle em trânsito (fora de qualquer tabela).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferredHandle {
+    pub htype: HandleType,
+    pub object: usize,
+    pub rights: HandleRights,
+}
+
+/// Erros de transferência de handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferError {
+    /// Handle inexistente ou com generation antiga
+    InvalidHandle,
+    /// O handle não tem o right `TRANSFER`
+    NotTransferable,
+    /// A mensagem já carrega `MAX_MESSAGE_HANDLES`
+    TooManyHandles,
+    /// A tabela do destinatário não tem slots suficientes
+    TableFull,
+}
+
 /// A Mensagem IPC completa.
 #[derive(Debug, Clone)]
 pub struct Message {
@@ -33,6 +64,8 @@ pub struct Message {
     /// Capabilities sendo transferidas (delegation).
     /// O Kernel move a ownership dessas caps do remetente para o destinatário.
     pub caps: Vec<CapHandle>,
+    /// Handles em trânsito, retirados da tabela do remetente.
+    pub handles: Vec<TransferredHandle>,
 }
 
 impl Message {
@@ -49,6 +82,7 @@ impl Message {
             },
             data,
             caps: Vec::new(),
+            handles: Vec::new(),
         }
     }
 
@@ -59,4 +93,133 @@ impl Message {
             self.header.cap_count += 1;
         }
     }
+
+    /// Move `handle` da tabela do remetente para a mensagem.
+    ///
+    /// Exige o right `TRANSFER`; em caso de erro a tabela não é alterada.
+    pub fn attach_handle(
+        &mut self,
+        from: &mut HandleTable,
+        handle: Handle,
+    ) -> Result<(), TransferError> {
+        let entry = from.get(handle).ok_or(TransferError::InvalidHandle)?;
+        if !entry.rights.contains(HandleRights::TRANSFER) {
+            return Err(TransferError::NotTransferable);
+        }
+        if self.handles.len() >= MAX_MESSAGE_HANDLES {
+            return Err(TransferError::TooManyHandles);
+        }
+
+        let (htype, object, rights) = from.take(handle).ok_or(TransferError::InvalidHandle)?;
+        self.handles.push(TransferredHandle {
+            htype,
+            object,
+            rights,
+        });
+        Ok(())
+    }
+
+    /// Instala os handles em trânsito na tabela do destinatário.
+    ///
+    /// Retorna os novos valores locais, na ordem em que foram anexados. Se a
+    /// tabela não comporta todos, retorna `TableFull` e os handles continuam
+    /// na mensagem.
+    pub fn install_handles(
+        &mut self,
+        into: &mut HandleTable,
+    ) -> Result<Vec<Handle>, TransferError> {
+        if into.free_slots() < self.handles.len() {
+            return Err(TransferError::TableFull);
+        }
+
+        let installed = self
+            .handles
+            .drain(..)
+            .map(|h| {
+                into.alloc(h.htype, h.object, h.rights)
+                    .expect("slot livre verificado acima")
+            })
+            .collect();
+        Ok(installed)
+    }
+
+    /// Devolve os handles em trânsito para `table` (ex.: envio que falhou).
+    ///
+    /// Handles que não couberem são descartados; retorna quantos voltaram.
+    pub fn restore_handles(&mut self, table: &mut HandleTable) -> usize {
+        let mut restored = 0;
+        for h in self.handles.drain(..) {
+            if table.alloc(h.htype, h.object, h.rights).is_some() {
+                restored += 1;
+            }
+        }
+        restored
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transferable() -> HandleRights {
+        HandleRights::READ | HandleRights::TRANSFER
+    }
+
+    #[test]
+    fn test_transfer_moves_handle() {
+        let mut sender = HandleTable::with_capacity(4);
+        let mut receiver = HandleTable::with_capacity(4);
+        let h = sender.alloc(HandleType::Port, 42, transferable()).unwrap();
+
+        let mut msg = Message::new(1, Vec::new());
+        msg.attach_handle(&mut sender, h).unwrap();
+        // Saiu da tabela do remetente
+        assert!(sender.get(h).is_none());
+
+        let installed = msg.install_handles(&mut receiver).unwrap();
+        assert_eq!(installed.len(), 1);
+        let entry = receiver.get(installed[0]).unwrap();
+        assert_eq!(entry.object, 42);
+        assert_eq!(entry.htype, HandleType::Port);
+        assert!(msg.handles.is_empty());
+    }
+
+    #[test]
+    fn test_transfer_requires_right() {
+        let mut sender = HandleTable::with_capacity(4);
+        let h = sender
+            .alloc(HandleType::File, 7, HandleRights::READ)
+            .unwrap();
+
+        let mut msg = Message::new(1, Vec::new());
+        assert_eq!(
+            msg.attach_handle(&mut sender, h),
+            Err(TransferError::NotTransferable)
+        );
+        // Continua com o remetente
+        assert!(sender.get(h).is_some());
+    }
+
+    #[test]
+    fn test_receiver_table_full() {
+        let mut sender = HandleTable::with_capacity(4);
+        let mut receiver = HandleTable::with_capacity(1);
+        receiver.alloc(HandleType::File, 1, HandleRights::READ);
+
+        let h = sender.alloc(HandleType::Port, 9, transferable()).unwrap();
+        let mut msg = Message::new(1, Vec::new());
+        msg.attach_handle(&mut sender, h).unwrap();
+
+        assert_eq!(
+            msg.install_handles(&mut receiver),
+            Err(TransferError::TableFull)
+        );
+        // Nada se perdeu: o handle segue na mensagem
+        assert_eq!(msg.handles.len(), 1);
+        assert_eq!(msg.restore_handles(&mut sender), 1);
+    }
 }
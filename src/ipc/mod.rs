@@ -9,6 +9,7 @@
 //! | Port      | 1:N       | Sim      | Opcional |
 //! | Channel   | 1:1       | Sim      | Opcional |
 //! | Pipe      | 1:1       | Stream   | Sim      |
+//! | EventFd   | N:N       | Contador | Opcional |
 //! | SharedMem | N:N       | Zero     | Não      |
 //! | Futex     | Primitive | N/A      | Sim      |
 //!
@@ -32,8 +33,8 @@ pub mod port;
 pub mod channel;
 
 pub use channel::Channel;
-pub use message::Message;
-pub use port::{Port, PortHandle};
+pub use message::{Message, TransferError, TransferredHandle};
+pub use port::{HandleIpcError, Port, PortHandle};
 
 // =============================================================================
 // STREAMING
@@ -44,6 +45,11 @@ pub mod pipe;
 
 pub use pipe::Pipe;
 
+/// Contadores de eventos (estilo `eventfd`)
+pub mod eventfd;
+
+pub use eventfd::EventFd;
+
 // =============================================================================
 // SHARED MEMORY
 // =============================================================================
@@ -51,7 +57,7 @@ pub use pipe::Pipe;
 /// Memória compartilhada (zero-copy)
 pub mod shm;
 
-pub use shm::SharedMemory;
+pub use shm::{SharedMemory, ShmHandle};
 
 // =============================================================================
 // SYNCHRONIZATION
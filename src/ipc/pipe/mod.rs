@@ -3,4 +3,4 @@
 //! Pipes unidirecionais.
 
 pub mod pipe;
-pub use pipe::Pipe;
+pub use pipe::{Pipe, PipeReader, PipeWriter};
@@ -1,60 +1,358 @@
-#![allow(dead_code)]
 //! Pipes unidirecionais
 //!
-//! Implementado como um wrapper sobre Port para fornecer semântica de stream/uni-direcional.
+//! Stream de bytes sobre um buffer circular de capacidade fixa, com uma
+//! ponta de escrita (`PipeWriter`) e uma de leitura (`PipeReader`).
+//!
+//! ## Bloqueio
+//! - `write` bloqueia enquanto o buffer está cheio e só retorna quando todos
+//!   os bytes foram copiados (ou a ponta de leitura fechou).
+//! - `read` bloqueia enquanto o buffer está vazio e a ponta de escrita existe.
+//!   Com a escrita fechada e o buffer drenado, retorna 0 (EOF).
+//!
+//! As esperas usam `WaitQueue::wait_until`, com a condição avaliada sob o
+//! lock da fila, como em `PortHandle`.
 
-use super::super::message::Message;
-use super::super::port::{IpcError, Port};
+use super::super::port::IpcError;
+use crate::sched::sync::{poll, Pollable, WaitQueue};
 use crate::sync::Mutex;
+use crate::syscall::abi::types::poll_events;
+use alloc::boxed::Box;
 use alloc::sync::Arc;
+use alloc::vec;
+
+/// Capacidade padrão do buffer (bytes)
+pub const PIPE_CAPACITY: usize = 4096;
+
+// =============================================================================
+// BUFFER CIRCULAR
+// =============================================================================
+
+/// Buffer circular de bytes
+struct ByteRing {
+    buf: Box<[u8]>,
+    /// Índice do byte mais antigo
+    head: usize,
+    /// Bytes armazenados
+    len: usize,
+}
+
+impl ByteRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            buf: vec![0u8; capacity].into_boxed_slice(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn free(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Copia o máximo possível de `data`; retorna quantos bytes couberam
+    fn write(&mut self, data: &[u8]) -> usize {
+        let count = core::cmp::min(data.len(), self.free());
+        let tail = (self.head + self.len) % self.capacity();
+        let first = core::cmp::min(count, self.capacity() - tail);
+
+        self.buf[tail..tail + first].copy_from_slice(&data[..first]);
+        self.buf[..count - first].copy_from_slice(&data[first..count]);
+        self.len += count;
+        count
+    }
+
+    /// Copia até `out.len()` bytes; retorna quantos foram lidos
+    fn read(&mut self, out: &mut [u8]) -> usize {
+        let count = core::cmp::min(out.len(), self.len);
+        let first = core::cmp::min(count, self.capacity() - self.head);
+
+        out[..first].copy_from_slice(&self.buf[self.head..self.head + first]);
+        out[first..count].copy_from_slice(&self.buf[..count - first]);
+        self.head = (self.head + count) % self.capacity();
+        self.len -= count;
+        count
+    }
+}
+
+// =============================================================================
+// PIPE
+// =============================================================================
 
-pub struct Pipe {
-    port: Arc<Mutex<Port>>,
+struct PipeState {
+    ring: ByteRing,
+    reader_open: bool,
+    writer_open: bool,
 }
 
+impl PipeState {
+    fn new(capacity: usize) -> Self {
+        Self {
+            ring: ByteRing::new(core::cmp::max(capacity, 1)),
+            reader_open: true,
+            writer_open: true,
+        }
+    }
+
+    /// Leitura sem bloqueio: `Empty` se não há dados e a escrita está aberta
+    fn try_read(&mut self, buf: &mut [u8]) -> Result<usize, IpcError> {
+        let count = self.ring.read(buf);
+        if count == 0 && self.writer_open && !buf.is_empty() {
+            return Err(IpcError::Empty);
+        }
+        Ok(count)
+    }
+
+    /// Escrita sem bloqueio: copia o que couber
+    fn try_write(&mut self, data: &[u8]) -> Result<usize, IpcError> {
+        if !self.reader_open {
+            return Err(IpcError::Closed);
+        }
+        let count = self.ring.write(data);
+        if count == 0 && !data.is_empty() {
+            return Err(IpcError::Full);
+        }
+        Ok(count)
+    }
+}
+
+/// Estado compartilhado pelas duas pontas
+struct PipeShared {
+    state: Mutex<PipeState>,
+    /// Leitores aguardando dados (ou EOF)
+    readable: WaitQueue,
+    /// Escritores aguardando espaço
+    writable: WaitQueue,
+}
+
+impl PipeShared {
+    /// Dados (ou EOF) para os leitores
+    fn wake_readers(&self) {
+        self.readable.wake_all();
+        poll::notify();
+    }
+
+    /// Espaço (ou leitura fechada) para os escritores
+    fn wake_writers(&self) {
+        self.writable.wake_all();
+        poll::notify();
+    }
+}
+
+pub struct Pipe;
+
 impl Pipe {
+    /// Cria um pipe com a capacidade padrão
     pub fn new() -> (PipeReader, PipeWriter) {
-        let port = Arc::new(Mutex::new(Port::new(32))); // Buffer size default
-        (PipeReader { port: port.clone() }, PipeWriter { port })
+        Self::with_capacity(PIPE_CAPACITY)
+    }
+
+    /// Cria um pipe com `capacity` bytes de buffer (mínimo 1)
+    pub fn with_capacity(capacity: usize) -> (PipeReader, PipeWriter) {
+        let shared = Arc::new(PipeShared {
+            state: Mutex::new(PipeState::new(capacity)),
+            readable: WaitQueue::new(),
+            writable: WaitQueue::new(),
+        });
+        (
+            PipeReader {
+                shared: shared.clone(),
+            },
+            PipeWriter { shared },
+        )
     }
 }
 
+/// Ponta de leitura
 pub struct PipeReader {
-    port: Arc<Mutex<Port>>,
+    shared: Arc<PipeShared>,
 }
 
 impl PipeReader {
-    pub fn read(&self) -> Result<Message, IpcError> {
-        let mut port = self.port.lock();
-        // recv returns Result<Message, PortStatus> which acts as IpcError
-        match port.recv() {
-            Ok(msg) => Ok(msg),
-            Err(status) => Err(status),
+    /// Lê até `buf.len()` bytes, bloqueando enquanto o pipe está vazio.
+    ///
+    /// Retorna 0 quando a ponta de escrita fechou e não há mais dados.
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, IpcError> {
+        if buf.is_empty() {
+            return Ok(0);
         }
+
+        let mut count = 0;
+        self.shared.readable.wait_until(|| {
+            let mut state = self.shared.state.lock();
+            count = state.ring.read(buf);
+            count > 0 || !state.writer_open
+        });
+
+        if count > 0 {
+            self.shared.wake_writers();
+        }
+        Ok(count)
     }
 
-    pub fn try_read(&self) -> Result<Message, IpcError> {
-        let mut port = self.port.lock();
-        // recv is non-blocking if we check empty?
-        // Port::recv checks queue.pop_front().
-        // It returns Empty if empty.
-        match port.recv() {
-            Ok(msg) => Ok(msg),
-            Err(status) => Err(status),
+    /// Como `read`, mas sem bloquear: `Empty` se não há dados ainda
+    pub fn try_read(&self, buf: &mut [u8]) -> Result<usize, IpcError> {
+        let count = self.shared.state.lock().try_read(buf)?;
+
+        if count > 0 {
+            self.shared.wake_writers();
         }
+        Ok(count)
     }
 }
 
+/// Legível com dados no buffer; `HUP` quando a escrita fechou
+impl Pollable for PipeReader {
+    fn poll_ready(&self) -> u16 {
+        let state = self.shared.state.lock();
+        let mut ready = 0;
+        if state.ring.len > 0 {
+            ready |= poll_events::IN;
+        }
+        if !state.writer_open {
+            ready |= poll_events::HUP;
+        }
+        ready
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.shared.state.lock().reader_open = false;
+        self.shared.wake_writers();
+    }
+}
+
+/// Ponta de escrita
 pub struct PipeWriter {
-    port: Arc<Mutex<Port>>,
+    shared: Arc<PipeShared>,
 }
 
 impl PipeWriter {
-    pub fn write(&self, msg: Message) -> Result<(), IpcError> {
-        let mut port = self.port.lock();
-        match port.send(msg) {
-            super::super::port::PortStatus::Ok => Ok(()),
-            err => Err(err),
+    /// Escreve todo `data`, bloqueando enquanto o buffer está cheio.
+    ///
+    /// Se a leitura fechar no meio, retorna os bytes já transferidos; sem
+    /// nenhum byte transferido, retorna `Closed` (broken pipe).
+    pub fn write(&self, data: &[u8]) -> Result<usize, IpcError> {
+        let mut written = 0;
+        let mut closed = false;
+
+        while written < data.len() && !closed {
+            let mut chunk = 0;
+            self.shared.writable.wait_until(|| {
+                let mut state = self.shared.state.lock();
+                if !state.reader_open {
+                    closed = true;
+                    return true;
+                }
+                chunk = state.ring.write(&data[written..]);
+                chunk > 0
+            });
+
+            if chunk > 0 {
+                written += chunk;
+                self.shared.wake_readers();
+            }
+        }
+
+        if written == 0 && closed {
+            Err(IpcError::Closed)
+        } else {
+            Ok(written)
+        }
+    }
+
+    /// Escreve o que couber sem bloquear.
+    ///
+    /// Retorna os bytes copiados (possivelmente menos que `data.len()`),
+    /// `Full` se nada coube e `Closed` se a leitura fechou.
+    pub fn try_write(&self, data: &[u8]) -> Result<usize, IpcError> {
+        let count = self.shared.state.lock().try_write(data)?;
+
+        if count > 0 {
+            self.shared.wake_readers();
+        }
+        Ok(count)
+    }
+}
+
+/// Gravável com espaço no buffer; `ERR` quando a leitura fechou
+impl Pollable for PipeWriter {
+    fn poll_ready(&self) -> u16 {
+        let state = self.shared.state.lock();
+        if !state.reader_open {
+            poll_events::ERR
+        } else if state.ring.free() > 0 {
+            poll_events::OUT
+        } else {
+            0
         }
     }
 }
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.shared.state.lock().writer_open = false;
+        self.shared.wake_readers();
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_wraparound() {
+        let mut ring = ByteRing::new(4);
+        assert_eq!(ring.write(b"abc"), 3);
+        let mut out = [0u8; 2];
+        assert_eq!(ring.read(&mut out), 2);
+        assert_eq!(&out, b"ab");
+
+        // Escreve passando do fim do buffer
+        assert_eq!(ring.write(b"defg"), 3);
+        let mut out = [0u8; 8];
+        assert_eq!(ring.read(&mut out), 4);
+        assert_eq!(&out[..4], b"cdef");
+    }
+
+    #[test]
+    fn test_partial_write_reports_transferred() {
+        let mut pipe = PipeState::new(4);
+        assert_eq!(pipe.try_write(b"hello"), Ok(4));
+        assert_eq!(pipe.try_write(b"o"), Err(IpcError::Full));
+
+        let mut out = [0u8; 3];
+        assert_eq!(pipe.try_read(&mut out), Ok(3));
+        assert_eq!(&out, b"hel");
+        assert_eq!(pipe.try_write(b"o!!!"), Ok(3));
+    }
+
+    #[test]
+    fn test_eof_after_writer_closes() {
+        let mut pipe = PipeState::new(8);
+        let mut out = [0u8; 8];
+        assert_eq!(pipe.try_read(&mut out), Err(IpcError::Empty));
+
+        pipe.try_write(b"bye").unwrap();
+        pipe.writer_open = false;
+
+        // Dados pendentes ainda são entregues antes do EOF
+        assert_eq!(pipe.try_read(&mut out), Ok(3));
+        assert_eq!(pipe.try_read(&mut out), Ok(0));
+    }
+
+    #[test]
+    fn test_broken_pipe() {
+        let mut pipe = PipeState::new(8);
+        pipe.reader_open = false;
+        assert_eq!(pipe.try_write(b"x"), Err(IpcError::Closed));
+    }
+}
@@ -7,10 +7,14 @@
 mod registry;
 pub use registry::{PortId, PortRegistry, PORT_REGISTRY};
 
-use super::message::Message;
+use super::message::{Message, TransferError};
+use crate::sched::sync::{poll, Pollable, WaitQueue};
 use crate::sync::Mutex;
+use crate::syscall::abi::types::poll_events;
+use crate::syscall::handle::{Handle, HandleTable};
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 /// Status de uma operação na Porta.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,19 +27,46 @@ pub enum PortStatus {
 
 pub type IpcError = PortStatus;
 
+/// Falha ao enviar/receber uma mensagem que carrega handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleIpcError {
+    /// A porta recusou a operação (cheia, vazia, fechada)
+    Port(PortStatus),
+    /// Os handles não puderam ser anexados ou instalados
+    Transfer(TransferError),
+}
+
 /// Estrutura interna da Porta.
+///
+/// `capacity == 0` é o modo **rendezvous**: não há buffer e um envio só é
+/// aceito quando há um receptor bloqueado em `recv_blocking` aguardando.
 pub struct Port {
     /// Fila de mensagens pendentes.
     queue: VecDeque<Message>,
-    /// Capacidade máxima da fila (backpressure).
+    /// Capacidade máxima da fila (backpressure). 0 = rendezvous.
     capacity: usize,
     /// Se a porta está aberta para novos envios.
     active: bool,
+    /// Receptores bloqueados em `recv_blocking`.
+    waiting_receivers: usize,
+    /// Próximo ticket entregue a um remetente bloqueado.
+    next_ticket: u64,
+    /// Ticket do remetente bloqueado que pode enviar agora.
+    serving: u64,
+}
+
+/// Estado compartilhado por todos os handles de uma porta.
+struct PortShared {
+    port: Mutex<Port>,
+    /// Remetentes aguardando espaço na fila.
+    space: WaitQueue,
+    /// Receptores aguardando mensagens.
+    data: WaitQueue,
 }
 
 /// Wrapper thread-safe para Portas (Reference Counted).
 #[derive(Clone)]
-pub struct PortHandle(Arc<Mutex<Port>>);
+pub struct PortHandle(Arc<PortShared>);
 
 impl Port {
     pub fn new(capacity: usize) -> Self {
@@ -43,9 +74,50 @@ impl Port {
             queue: VecDeque::with_capacity(capacity),
             capacity,
             active: true,
+            waiting_receivers: 0,
+            next_ticket: 0,
+            serving: 0,
         }
     }
 
+    /// Porta sem buffer (modo rendezvous).
+    pub fn rendezvous() -> Self {
+        Self::new(0)
+    }
+
+    pub fn is_rendezvous(&self) -> bool {
+        self.capacity == 0
+    }
+
+    /// Há lugar para mais uma mensagem?
+    fn has_room(&self) -> bool {
+        if self.is_rendezvous() {
+            self.waiting_receivers > self.queue.len()
+        } else {
+            self.queue.len() < self.capacity
+        }
+    }
+
+    /// Há remetentes bloqueados aguardando a vez?
+    fn has_blocked_senders(&self) -> bool {
+        self.next_ticket != self.serving
+    }
+
+    /// `IN` com mensagens na fila, `OUT` se um `send` seria aceito, `HUP`
+    /// depois de fechada
+    fn poll_ready(&self) -> u16 {
+        let mut ready = 0;
+        if !self.queue.is_empty() {
+            ready |= poll_events::IN;
+        }
+        if !self.active {
+            ready |= poll_events::HUP;
+        } else if self.has_room() && !self.has_blocked_senders() {
+            ready |= poll_events::OUT;
+        }
+        ready
+    }
+
     pub fn send(&mut self, msg: Message) -> PortStatus {
         let msg_id = msg.header.id;
 
@@ -54,15 +126,40 @@ impl Port {
             return PortStatus::Closed;
         }
 
-        if self.queue.len() >= self.capacity {
+        // Remetentes bloqueados têm preferência (sem "furar a fila")
+        if !self.has_room() || self.has_blocked_senders() {
             crate::ktrace!("(IPC) send: Porta cheia. msg_id=", msg_id);
             return PortStatus::Full;
         }
 
-        crate::ktrace!("(IPC) send: Mensagem enfileirada ID=", msg_id);
+        self.push(msg);
+        PortStatus::Ok
+    }
+
+    fn push(&mut self, msg: Message) {
+        crate::ktrace!("(IPC) send: Mensagem enfileirada ID=", msg.header.id);
         crate::ktrace!("(IPC) send: Mensagem bytes=", msg.header.data_len as u64);
         self.queue.push_back(msg);
-        PortStatus::Ok
+    }
+
+    /// Recebe instalando os handles da mensagem em `into`.
+    ///
+    /// Se `into` não comporta os handles, a mensagem permanece na fila e o
+    /// erro é `TableFull` (o receptor pode liberar slots e tentar de novo).
+    pub fn recv_with_handles(
+        &mut self,
+        into: &mut HandleTable,
+    ) -> Result<(Message, Vec<Handle>), HandleIpcError> {
+        if let Some(front) = self.queue.front() {
+            if into.free_slots() < front.handles.len() {
+                return Err(HandleIpcError::Transfer(TransferError::TableFull));
+            }
+        }
+        let mut msg = self.recv().map_err(HandleIpcError::Port)?;
+        let handles = msg
+            .install_handles(into)
+            .map_err(HandleIpcError::Transfer)?;
+        Ok((msg, handles))
     }
 
     pub fn recv(&mut self) -> Result<Message, PortStatus> {
@@ -79,27 +176,289 @@ impl Port {
 
 impl PortHandle {
     pub fn new(capacity: usize) -> Self {
-        Self(Arc::new(Mutex::new(Port::new(capacity))))
+        Self(Arc::new(PortShared {
+            port: Mutex::new(Port::new(capacity)),
+            space: WaitQueue::new(),
+            data: WaitQueue::new(),
+        }))
     }
 
+    /// Porta sem buffer: `send_blocking` só completa com um receptor esperando.
+    pub fn rendezvous() -> Self {
+        Self::new(0)
+    }
+
+    /// Envia sem bloquear. Retorna `Full` se não houver espaço.
     pub fn send(&self, msg: Message) -> PortStatus {
-        self.0.lock().send(msg)
+        let status = self.0.port.lock().send(msg);
+        if status == PortStatus::Ok {
+            self.wake_receivers();
+        }
+        status
+    }
+
+    /// Envia, bloqueando enquanto a porta estiver cheia.
+    ///
+    /// Remetentes bloqueados são atendidos em ordem de chegada (tickets): um
+    /// remetente só envia quando é a sua vez e há espaço. Se a porta for
+    /// fechada durante a espera, todos recebem `Closed`.
+    pub fn send_blocking(&self, msg: Message) -> PortStatus {
+        let ticket = {
+            let mut port = self.0.port.lock();
+            if !port.active {
+                return PortStatus::Closed;
+            }
+            if port.has_room() && !port.has_blocked_senders() {
+                port.push(msg);
+                drop(port);
+                self.wake_receivers();
+                return PortStatus::Ok;
+            }
+            let ticket = port.next_ticket;
+            port.next_ticket += 1;
+            ticket
+        };
+
+        let mut msg = Some(msg);
+        let mut status = PortStatus::Full;
+        self.0.space.wait_until(|| {
+            let mut port = self.0.port.lock();
+            if !port.active {
+                status = PortStatus::Closed;
+                return true;
+            }
+            if port.serving == ticket && port.has_room() {
+                port.serving += 1;
+                port.push(msg.take().unwrap());
+                status = PortStatus::Ok;
+                return true;
+            }
+            false
+        });
+
+        if status == PortStatus::Ok {
+            self.wake_receivers();
+            // O próximo ticket pode ter espaço também
+            self.0.space.wake_all();
+        }
+        status
+    }
+
+    /// Envia sem bloquear, movendo `handles` da tabela do remetente.
+    ///
+    /// Todos os handles precisam do right `TRANSFER`. Se algum falhar, ou se
+    /// a porta recusar a mensagem, os handles já retirados voltam para `from`.
+    pub fn send_with_handles(
+        &self,
+        mut msg: Message,
+        from: &mut HandleTable,
+        handles: &[Handle],
+    ) -> Result<(), HandleIpcError> {
+        for &handle in handles {
+            if let Err(e) = msg.attach_handle(from, handle) {
+                msg.restore_handles(from);
+                return Err(HandleIpcError::Transfer(e));
+            }
+        }
+
+        let mut port = self.0.port.lock();
+        if !port.active {
+            drop(port);
+            msg.restore_handles(from);
+            return Err(HandleIpcError::Port(PortStatus::Closed));
+        }
+        if !port.has_room() || port.has_blocked_senders() {
+            drop(port);
+            msg.restore_handles(from);
+            return Err(HandleIpcError::Port(PortStatus::Full));
+        }
+        port.push(msg);
+        drop(port);
+
+        self.wake_receivers();
+        Ok(())
+    }
+
+    /// Recebe sem bloquear, instalando os handles em `into`.
+    pub fn recv_with_handles(
+        &self,
+        into: &mut HandleTable,
+    ) -> Result<(Message, Vec<Handle>), HandleIpcError> {
+        let result = self.0.port.lock().recv_with_handles(into);
+        if result.is_ok() {
+            self.wake_senders();
+        }
+        result
     }
 
     /// Recebe uma mensagem da porta (Non-blocking).
     pub fn recv(&self) -> Result<Message, PortStatus> {
-        self.0.lock().recv()
+        let result = self.0.port.lock().recv();
+        if result.is_ok() {
+            self.wake_senders();
+        }
+        result
+    }
+
+    /// Recebe uma mensagem, bloqueando enquanto a porta estiver vazia.
+    ///
+    /// Em modo rendezvous, a presença do receptor libera um remetente.
+    pub fn recv_blocking(&self) -> Result<Message, PortStatus> {
+        self.0.port.lock().waiting_receivers += 1;
+        self.wake_senders();
+
+        let mut result = Err(PortStatus::Empty);
+        self.0.data.wait_until(|| {
+            let mut port = self.0.port.lock();
+            match port.recv() {
+                Err(PortStatus::Empty) => false,
+                other => {
+                    port.waiting_receivers -= 1;
+                    result = other;
+                    true
+                }
+            }
+        });
+
+        if result.is_ok() {
+            self.wake_senders();
+        }
+        result
+    }
+
+    /// Acorda os remetentes bloqueados para reavaliarem seus tickets.
+    ///
+    /// Usa `wake_all`: a ordem na WaitQueue pode diferir da ordem dos tickets,
+    /// então todos reavaliam e apenas o da vez envia.
+    fn wake_senders(&self) {
+        self.0.space.wake_all();
+        poll::notify();
+    }
+
+    /// Acorda um receptor bloqueado (há mensagem nova).
+    fn wake_receivers(&self) {
+        self.0.data.wake_one();
+        poll::notify();
     }
 
     /// Fecha a porta, impedindo novos envios.
+    ///
+    /// Remetentes e receptores bloqueados são acordados e recebem `Closed`
+    /// (receptores ainda drenam as mensagens pendentes).
     pub fn close(&self) {
         crate::kdebug!("(IPC) port: Fechando porta...");
-        let mut port = self.0.lock();
-        port.active = false;
+        self.0.port.lock().active = false;
+        self.0.space.wake_all();
+        self.0.data.wake_all();
+        poll::notify();
     }
 
     /// Retorna o número de mensagens pendentes.
     pub fn pending_count(&self) -> usize {
-        self.0.lock().queue.len()
+        self.0.port.lock().queue.len()
+    }
+}
+
+impl Pollable for PortHandle {
+    fn poll_ready(&self) -> u16 {
+        self.0.port.lock().poll_ready()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn msg(id: u64) -> Message {
+        Message::new(id, vec![id as u8])
+    }
+
+    #[test]
+    fn test_capacity_backpressure() {
+        let mut port = Port::new(2);
+        assert_eq!(port.send(msg(1)), PortStatus::Ok);
+        assert_eq!(port.send(msg(2)), PortStatus::Ok);
+        assert_eq!(port.send(msg(3)), PortStatus::Full);
+        assert_eq!(port.recv().unwrap().header.id, 1);
+        assert_eq!(port.send(msg(3)), PortStatus::Ok);
+    }
+
+    #[test]
+    fn test_no_barging_past_blocked_senders() {
+        let mut port = Port::new(1);
+        port.next_ticket = 1; // um remetente bloqueado aguardando
+        assert!(port.has_room());
+        assert_eq!(port.send(msg(1)), PortStatus::Full);
+        port.serving = 1;
+        assert_eq!(port.send(msg(1)), PortStatus::Ok);
+    }
+
+    #[test]
+    fn test_rendezvous_needs_receiver() {
+        let mut port = Port::rendezvous();
+        assert!(port.is_rendezvous());
+        assert_eq!(port.send(msg(1)), PortStatus::Full);
+
+        port.waiting_receivers = 1;
+        assert_eq!(port.send(msg(1)), PortStatus::Ok);
+        // Um receptor, uma mensagem
+        assert_eq!(port.send(msg(2)), PortStatus::Full);
+    }
+
+    #[test]
+    fn test_poll_ready() {
+        use poll_events::{HUP, IN, OUT};
+
+        let mut port = Port::new(1);
+        assert_eq!(port.poll_ready(), OUT);
+        port.send(msg(1));
+        assert_eq!(port.poll_ready(), IN);
+        port.active = false;
+        assert_eq!(port.poll_ready(), IN | HUP);
+        port.recv().unwrap();
+        assert_eq!(port.poll_ready(), HUP);
+    }
+
+    #[test]
+    fn test_closed_port() {
+        let mut port = Port::new(4);
+        port.send(msg(1));
+        port.active = false;
+        assert_eq!(port.send(msg(2)), PortStatus::Closed);
+        assert_eq!(port.recv().unwrap().header.id, 1);
+        assert_eq!(port.recv().unwrap_err(), PortStatus::Closed);
+    }
+
+    #[test]
+    fn test_recv_with_handles_keeps_message_when_table_full() {
+        use crate::syscall::handle::{HandleRights, HandleType};
+
+        let mut sender = HandleTable::with_capacity(2);
+        let mut receiver = HandleTable::with_capacity(1);
+        receiver.alloc(HandleType::File, 1, HandleRights::READ);
+
+        let h = sender
+            .alloc(HandleType::Port, 5, HandleRights::TRANSFER)
+            .unwrap();
+        let mut m = msg(1);
+        m.attach_handle(&mut sender, h).unwrap();
+
+        let mut port = Port::new(4);
+        port.send(m);
+        assert_eq!(
+            port.recv_with_handles(&mut receiver).unwrap_err(),
+            HandleIpcError::Transfer(TransferError::TableFull)
+        );
+        assert_eq!(port.queue.len(), 1);
+
+        let mut receiver = HandleTable::with_capacity(1);
+        let (m, handles) = port.recv_with_handles(&mut receiver).unwrap();
+        assert_eq!(m.header.id, 1);
+        assert_eq!(receiver.get(handles[0]).unwrap().object, 5);
     }
 }
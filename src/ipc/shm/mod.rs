@@ -4,4 +4,4 @@
 
 mod shm;
 
-pub use shm::{SharedMemory, ShmError, ShmId, SHM_REGISTRY};
+pub use shm::{SharedMemory, ShmError, ShmHandle, ShmId, SHM_REGISTRY};
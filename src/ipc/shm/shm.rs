@@ -1,13 +1,27 @@
 //! # Shared Memory Implementation
 //!
 //! Implementação completa de memória compartilhada.
+//!
+//! ## Contagem de referências
+//! Os frames vêm do PFM como `FrameState::Owned`, com contagem 1 (a
+//! referência do próprio objeto). Cada mapeamento em um `AddressSpace` soma
+//! uma referência por frame (`pfm::inc_ref`); desmapear ou soltar o último
+//! `ShmHandle` devolve a sua. O frame só volta ao PMM quando a contagem
+//! chega a zero, então um processo pode continuar usando a região depois que
+//! o criador descartou o handle.
 
+use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
+use crate::mm::aspace::AddressSpace;
+use crate::mm::pfm::frame::FrameFlags;
+use crate::mm::pfm::{PfmError, Pid, PID_KERNEL};
 use crate::mm::pmm::{FRAME_ALLOCATOR, FRAME_SIZE};
-use crate::mm::vmm::{map_page_with_pmm, MapFlags};
+use crate::mm::vmm::MapFlags;
 use crate::mm::{PhysAddr, VirtAddr};
 use crate::sync::Spinlock;
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 // ============================================================================
 // TIPOS
@@ -23,6 +37,17 @@ impl ShmId {
     }
 }
 
+static NEXT_SHM_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Mapeamento ativo da região em um address space
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    /// PML4 do address space (identifica o espaço)
+    cr3: u64,
+    /// Endereço base do mapeamento
+    base: VirtAddr,
+}
+
 /// Região de memória compartilhada
 pub struct SharedMemory {
     /// ID único
@@ -31,59 +56,194 @@ pub struct SharedMemory {
     pub frames: Vec<PhysAddr>,
     /// Tamanho em bytes
     pub size: usize,
-    /// Contagem de referência
-    pub ref_count: u32,
+    /// Dono dos frames no PFM
+    owner: Pid,
+    /// Address spaces onde a região está mapeada
+    mappings: Spinlock<Vec<Mapping>>,
+}
+
+/// Handle para uma região compartilhada.
+///
+/// Clonar o handle não copia a memória; os frames são liberados quando o
+/// último handle é solto e nenhum address space os mapeia mais.
+#[derive(Clone)]
+pub struct ShmHandle(Arc<SharedMemory>);
+
+impl core::ops::Deref for ShmHandle {
+    type Target = SharedMemory;
+
+    fn deref(&self) -> &SharedMemory {
+        &self.0
+    }
+}
+
+/// Dono dos frames criados pela task atual (kernel se não houver processo)
+fn current_owner() -> Pid {
+    crate::sched::core::CURRENT
+        .lock()
+        .as_ref()
+        .map_or(PID_KERNEL, |task| task.tid.as_u32() as Pid)
+}
+
+/// Devolve uma referência de `frame` ao PFM (libera ao chegar em zero).
+///
+/// Frames fora da faixa rastreada pelo PFM não têm contagem: só voltam ao
+/// PMM quando `last` (a referência do próprio objeto) é solta.
+fn put_frame(frame: PhysAddr, owner: Pid, last: bool) {
+    match crate::mm::pfm::free_frame(frame, owner) {
+        Ok(()) => {}
+        Err(PfmError::FrameNotFound) if last => FRAME_ALLOCATOR.lock().deallocate_frame(frame),
+        Err(PfmError::FrameNotFound) => {}
+        Err(_) => crate::kwarn!("(SHM) Falha ao soltar frame:", frame.as_u64()),
+    }
 }
 
 impl SharedMemory {
-    /// Cria região compartilhada com frames alocados
-    pub fn create(id: ShmId, size: usize) -> Result<Self, ShmError> {
+    /// Cria região compartilhada com frames zerados
+    pub fn create(size: usize) -> Result<ShmHandle, ShmError> {
+        if size == 0 {
+            return Err(ShmError::InvalidSize);
+        }
         let num_frames = (size + FRAME_SIZE as usize - 1) / FRAME_SIZE as usize;
+        let owner = current_owner();
 
         let mut frames = Vec::with_capacity(num_frames);
-        let pmm = FRAME_ALLOCATOR.lock();
-
         for _ in 0..num_frames {
-            if let Some(frame_addr) = pmm.allocate_frame() {
-                // Zerar o frame
-                unsafe {
-                    // CORREÇÃO: Usar HHDM para acessar memória física
-                    let virt_addr = crate::mm::addr::phys_to_virt::<u8>(frame_addr.as_u64());
-                    core::ptr::write_bytes(virt_addr, 0, FRAME_SIZE as usize);
+            let frame = crate::mm::pfm::get()
+                .lock()
+                .alloc_frame(owner, FrameFlags::USER);
+            match frame {
+                Ok(frame_addr) => {
+                    // Zerar o frame via HHDM
+                    unsafe {
+                        let virt_addr = crate::mm::addr::phys_to_virt::<u8>(frame_addr.as_u64());
+                        core::ptr::write_bytes(virt_addr, 0, FRAME_SIZE as usize);
+                    }
+                    frames.push(frame_addr);
                 }
-                frames.push(frame_addr);
-            } else {
-                // Liberar frames já alocados
-                for f in frames {
-                    pmm.deallocate_frame(f);
+                Err(_) => {
+                    // Liberar frames já alocados
+                    for f in frames {
+                        put_frame(f, owner, true);
+                    }
+                    return Err(ShmError::OutOfMemory);
                 }
-                return Err(ShmError::OutOfMemory);
             }
         }
 
-        Ok(Self {
-            id,
+        Ok(ShmHandle(Arc::new(Self {
+            id: ShmId(NEXT_SHM_ID.fetch_add(1, Ordering::SeqCst)),
             frames,
             size,
-            ref_count: 1,
-        })
+            owner,
+            mappings: Spinlock::new(Vec::new()),
+        })))
+    }
+
+    /// Mapeia a região em `aspace`, em um endereço livre escolhido pelo espaço.
+    ///
+    /// Se a região já está mapeada neste address space, retorna o mapeamento
+    /// existente (independente de `prot`).
+    pub fn map(&self, aspace: &mut AddressSpace, prot: Protection) -> Result<VirtAddr, ShmError> {
+        self.map_at(aspace, None, prot)
     }
 
-    /// Mapeia a região no address space do processo atual
-    pub fn map(&self, base_vaddr: u64) -> Result<VirtAddr, ShmError> {
-        let mut pmm = FRAME_ALLOCATOR.lock();
-        let flags = MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::USER;
+    /// Como `map`, mas tentando o endereço `hint`
+    pub fn map_at(
+        &self,
+        aspace: &mut AddressSpace,
+        hint: Option<VirtAddr>,
+        prot: Protection,
+    ) -> Result<VirtAddr, ShmError> {
+        let cr3 = aspace.cr3();
+        let mut mappings = self.mappings.lock();
+        if let Some(existing) = mappings.iter().find(|m| m.cr3 == cr3) {
+            return Ok(existing.base);
+        }
+
+        let base = aspace
+            .map_region(
+                hint,
+                self.size,
+                prot,
+                VmaFlags::SHARED,
+                MemoryIntent::SharedMemory,
+            )
+            .map_err(|_| ShmError::MapFailed)?;
 
-        for (i, frame_addr) in self.frames.iter().enumerate() {
-            let vaddr = base_vaddr + (i as u64 * FRAME_SIZE);
-            let phys = frame_addr.as_u64();
+        let mut flags = MapFlags::PRESENT | MapFlags::USER;
+        if prot.can_write() {
+            flags |= MapFlags::WRITABLE;
+        }
+        if prot.can_exec() {
+            flags |= MapFlags::EXECUTABLE;
+        }
 
-            if let Err(_) = map_page_with_pmm(vaddr, phys, flags, &mut *pmm) {
+        for (i, frame) in self.frames.iter().enumerate() {
+            let vaddr = base.as_u64() + i as u64 * FRAME_SIZE;
+            let mapped = {
+                let mut pmm = FRAME_ALLOCATOR.lock();
+                crate::mm::vmm::mapper::map_page_in_target_p4(
+                    cr3,
+                    vaddr,
+                    frame.as_u64(),
+                    flags,
+                    &mut *pmm,
+                )
+            };
+            if mapped.is_err() {
+                self.unmap_pages(cr3, base, i);
+                let _ = aspace.unmap_region(base, self.size);
                 return Err(ShmError::MapFailed);
             }
+            let _ = crate::mm::pfm::inc_ref(*frame);
         }
 
-        Ok(VirtAddr::new(base_vaddr))
+        mappings.push(Mapping { cr3, base });
+        Ok(base)
+    }
+
+    /// Remove o mapeamento da região em `aspace`
+    pub fn unmap(&self, aspace: &mut AddressSpace) -> Result<(), ShmError> {
+        let cr3 = aspace.cr3();
+        let mapping = {
+            let mut mappings = self.mappings.lock();
+            let pos = mappings
+                .iter()
+                .position(|m| m.cr3 == cr3)
+                .ok_or(ShmError::NotMapped)?;
+            mappings.swap_remove(pos)
+        };
+
+        self.unmap_pages(cr3, mapping.base, self.frames.len());
+        let _ = aspace.unmap_region(mapping.base, self.size);
+        Ok(())
+    }
+
+    /// Limpa as PTEs das primeiras `count` páginas e solta as referências
+    fn unmap_pages(&self, cr3: u64, base: VirtAddr, count: usize) {
+        let active = crate::mm::vmm::mapper::read_cr3() == cr3;
+        for (i, frame) in self.frames.iter().take(count).enumerate() {
+            let vaddr = base.as_u64() + i as u64 * FRAME_SIZE;
+            // SAFETY: a página foi mapeada por `map_at`, então a PT existe
+            unsafe {
+                let _ = crate::mm::vmm::mapper::write_pte_in_p4(cr3, vaddr, 0);
+            }
+            if active {
+                crate::mm::vmm::tlb::flush(vaddr);
+            }
+            put_frame(*frame, self.owner, false);
+        }
+    }
+
+    /// Endereço onde a região está mapeada em `aspace`, se estiver
+    pub fn mapping_in(&self, aspace: &AddressSpace) -> Option<VirtAddr> {
+        let cr3 = aspace.cr3();
+        self.mappings
+            .lock()
+            .iter()
+            .find(|m| m.cr3 == cr3)
+            .map(|m| m.base)
     }
 
     /// Retorna tamanho em bytes
@@ -92,73 +252,50 @@ impl SharedMemory {
     }
 }
 
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        // Solta a referência do objeto; mapeamentos vivos mantêm os frames
+        for frame in self.frames.drain(..) {
+            put_frame(frame, self.owner, true);
+        }
+    }
+}
+
 // ============================================================================
 // REGISTRY GLOBAL
 // ============================================================================
 
 /// Registry global de regiões SHM
 pub struct ShmRegistry {
-    regions: BTreeMap<ShmId, SharedMemory>,
-    next_id: u64,
+    regions: BTreeMap<ShmId, ShmHandle>,
 }
 
 impl ShmRegistry {
     pub const fn new() -> Self {
         Self {
             regions: BTreeMap::new(),
-            next_id: 1,
         }
     }
 
     /// Cria nova região SHM
     pub fn create(&mut self, size: usize) -> Result<ShmId, ShmError> {
-        let id = ShmId(self.next_id);
-        self.next_id += 1;
-
-        let shm = SharedMemory::create(id, size)?;
+        let shm = SharedMemory::create(size)?;
+        let id = shm.id;
         self.regions.insert(id, shm);
 
         Ok(id)
     }
 
-    /// Obtém região por ID
-    pub fn get(&self, id: ShmId) -> Option<&SharedMemory> {
-        self.regions.get(&id)
-    }
-
-    /// Obtém região mutável por ID
-    pub fn get_mut(&mut self, id: ShmId) -> Option<&mut SharedMemory> {
-        self.regions.get_mut(&id)
-    }
-
-    /// Incrementa ref count
-    pub fn add_ref(&mut self, id: ShmId) -> bool {
-        if let Some(shm) = self.regions.get_mut(&id) {
-            shm.ref_count += 1;
-            true
-        } else {
-            false
-        }
+    /// Obtém handle da região por ID
+    pub fn get(&self, id: ShmId) -> Option<ShmHandle> {
+        self.regions.get(&id).cloned()
     }
 
-    /// Remove referência e possivelmente libera
-    pub fn release(&mut self, id: ShmId) {
-        let should_free = if let Some(shm) = self.regions.get_mut(&id) {
-            shm.ref_count = shm.ref_count.saturating_sub(1);
-            shm.ref_count == 0
-        } else {
-            false
-        };
-
-        if should_free {
-            if let Some(shm) = self.regions.remove(&id) {
-                // Liberar frames
-                let pmm = FRAME_ALLOCATOR.lock();
-                for frame_addr in shm.frames {
-                    pmm.deallocate_frame(frame_addr);
-                }
-            }
-        }
+    /// Remove a região do registry.
+    ///
+    /// Os frames continuam vivos enquanto houver handles ou mapeamentos.
+    pub fn remove(&mut self, id: ShmId) -> Option<ShmHandle> {
+        self.regions.remove(&id)
     }
 }
 
@@ -173,6 +310,7 @@ pub static SHM_REGISTRY: Spinlock<ShmRegistry> = Spinlock::new(ShmRegistry::new(
 pub enum ShmError {
     OutOfMemory,
     InvalidId,
+    InvalidSize,
     MapFailed,
     NotMapped,
 }
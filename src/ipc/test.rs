@@ -0,0 +1,273 @@
+//! # Testes de IPC
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot.
+
+use crate::ipc::eventfd::EventFdError;
+use crate::ipc::futex::FutexError;
+use crate::ipc::pipe::PipeWriter;
+use crate::ipc::port::PortStatus;
+use crate::ipc::{EventFd, Futex, Message, Pipe, PortHandle, SharedMemory};
+use crate::mm::aspace::vma::Protection;
+use crate::mm::aspace::AddressSpace;
+use crate::mm::vmm::mapper::translate_addr_in_p4;
+use crate::mm::VirtAddr;
+use crate::sched::core::{exit_current, yield_now};
+use crate::sched::sync::poll::{poll, PollEntry, Pollable};
+use crate::sched::test::spawn_kernel_task;
+use crate::sync::Spinlock;
+use crate::syscall::abi::types::poll_events;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+pub fn run_tests() {
+    crate::kinfo!("(IPC) Iniciando testes de IPC...");
+    test_futex_wait_wake();
+    test_shm_zero_copy();
+    test_shm_rmap();
+    test_pipe_producer_consumer();
+    test_poll_port();
+    test_eventfd_signaling();
+    crate::kinfo!("(IPC) Testes de IPC concluídos com SUCESSO.");
+}
+
+/// Palavra do futex de teste (0 = trancado, 1 = liberado)
+static FUTEX_WORD: AtomicU32 = AtomicU32::new(0);
+static FUTEX_DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn futex_addr() -> VirtAddr {
+    VirtAddr::new(&FUTEX_WORD as *const AtomicU32 as u64)
+}
+
+extern "C" fn futex_waiter() -> ! {
+    while FUTEX_WORD.load(Ordering::SeqCst) == 0 {
+        let _ = Futex::wait(futex_addr(), 0);
+    }
+    FUTEX_DONE.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Uma task bloqueada em `wait(0)` só termina após o valor mudar e um `wake`.
+fn test_futex_wait_wake() {
+    FUTEX_WORD.store(0, Ordering::SeqCst);
+    FUTEX_DONE.store(0, Ordering::SeqCst);
+
+    // Valor diferente do esperado: não bloqueia
+    assert_eq!(
+        Futex::wait(futex_addr(), 1),
+        Err(FutexError::WouldBlock),
+        "(IPC) Futex bloqueou com valor diferente"
+    );
+
+    spawn_kernel_task("ipc-test-futex", futex_waiter);
+
+    // Deixa a task rodar até bloquear
+    yield_now();
+    assert_eq!(
+        Futex::waiters(futex_addr()),
+        1,
+        "(IPC) Task não bloqueou no futex"
+    );
+    assert_eq!(FUTEX_DONE.load(Ordering::SeqCst), 0);
+
+    FUTEX_WORD.store(1, Ordering::SeqCst);
+    assert_eq!(Futex::wake(futex_addr(), 1), 1);
+
+    while FUTEX_DONE.load(Ordering::SeqCst) < 1 {
+        yield_now();
+    }
+    assert_eq!(Futex::waiters(futex_addr()), 0);
+    assert_eq!(Futex::wake(futex_addr(), 1), 0);
+}
+
+/// Dois address spaces mapeiam os mesmos frames; a contagem no PFM acompanha
+/// os mapeamentos e o handle.
+fn test_shm_zero_copy() {
+    let mut a = AddressSpace::new(1).expect("(IPC) Falha ao criar address space A");
+    let mut b = AddressSpace::new(2).expect("(IPC) Falha ao criar address space B");
+
+    let shm = SharedMemory::create(2 * 4096).expect("(IPC) Falha ao criar SHM");
+    let va = shm.map(&mut a, Protection::RW).unwrap();
+    let vb = shm.map(&mut b, Protection::READ).unwrap();
+
+    for i in 0..shm.frames.len() as u64 {
+        let pa = translate_addr_in_p4(a.cr3(), va.as_u64() + i * 4096);
+        let pb = translate_addr_in_p4(b.cr3(), vb.as_u64() + i * 4096);
+        assert_eq!(pa, pb, "(IPC) SHM mapeou frames diferentes");
+        assert_eq!(pa, Some(shm.frames[i as usize].as_u64()));
+    }
+
+    // Mapear de novo no mesmo espaço devolve o mapeamento existente
+    assert_eq!(shm.map(&mut a, Protection::RW).unwrap(), va);
+
+    let frame = shm.frames[0];
+    let refs = || crate::mm::pfm::get().lock().get_ref_count(frame).ok();
+    let tracked = refs().is_some();
+    if tracked {
+        assert_eq!(refs(), Some(3), "(IPC) Contagem de refs da SHM incorreta");
+    }
+
+    shm.unmap(&mut a).unwrap();
+    assert!(translate_addr_in_p4(a.cr3(), va.as_u64()).is_none());
+    if tracked {
+        assert_eq!(refs(), Some(2));
+    }
+
+    // Sem handle, o mapeamento de B mantém o frame vivo
+    drop(shm);
+    if tracked {
+        assert_eq!(refs(), Some(1));
+    }
+    assert_eq!(
+        translate_addr_in_p4(b.cr3(), vb.as_u64()),
+        Some(frame.as_u64())
+    );
+}
+
+/// Um frame de SHM mapeado em dois address spaces tem um rmap por espaço;
+/// desmapear de um deixa o do outro.
+fn test_shm_rmap() {
+    use crate::mm::pfm::rmap::{self, RMapEntry};
+
+    let mut a = AddressSpace::new(1).expect("(IPC) Falha ao criar address space A");
+    let mut b = AddressSpace::new(2).expect("(IPC) Falha ao criar address space B");
+    let shm = SharedMemory::create(4096).expect("(IPC) Falha ao criar SHM");
+    let frame = shm.frames[0];
+
+    let va = shm.map(&mut a, Protection::RW).unwrap();
+    let vb = shm.map(&mut b, Protection::READ).unwrap();
+    let entries = rmap::entries(frame);
+    assert_eq!(entries.len(), 2, "(IPC) rmap sem as duas PTEs da SHM");
+    assert!(entries.contains(&RMapEntry::new(a.cr3(), va.as_u64())));
+    assert!(entries.contains(&RMapEntry::new(b.cr3(), vb.as_u64())));
+
+    shm.unmap(&mut a).unwrap();
+    assert_eq!(rmap::entries(frame), [RMapEntry::new(b.cr3(), vb.as_u64())]);
+
+    // Destruir o address space leva junto as entradas dele
+    drop(shm);
+    let b_cr3 = b.cr3();
+    drop(b);
+    assert!(rmap::entries(frame).iter().all(|e| e.aspace_id != b_cr3));
+}
+
+/// Capacidade do pipe de teste (menor que o volume transferido)
+const PIPE_TEST_CAPACITY: usize = 64;
+const PIPE_TEST_BYTES: usize = PIPE_TEST_CAPACITY * 5 + 7;
+
+static PIPE_WRITER: Spinlock<Option<PipeWriter>> = Spinlock::new(None);
+
+extern "C" fn pipe_producer() -> ! {
+    let writer = PIPE_WRITER.lock().take().unwrap();
+    let data: alloc::vec::Vec<u8> = (0..PIPE_TEST_BYTES).map(|i| i as u8).collect();
+    // Bloqueia várias vezes: o volume é maior que o buffer
+    assert_eq!(writer.write(&data), Ok(PIPE_TEST_BYTES));
+    drop(writer);
+    exit_current(0);
+}
+
+/// Produtor escreve mais que a capacidade; o consumidor recebe tudo, em ordem,
+/// e depois EOF.
+fn test_pipe_producer_consumer() {
+    let (reader, writer) = Pipe::with_capacity(PIPE_TEST_CAPACITY);
+    *PIPE_WRITER.lock() = Some(writer);
+    spawn_kernel_task("ipc-test-pipe", pipe_producer);
+
+    let mut received = 0usize;
+    let mut buf = [0u8; 48];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        for (i, &byte) in buf[..n].iter().enumerate() {
+            assert_eq!(
+                byte,
+                (received + i) as u8,
+                "(IPC) Pipe entregou fora de ordem"
+            );
+        }
+        received += n;
+    }
+    assert_eq!(received, PIPE_TEST_BYTES, "(IPC) Pipe perdeu bytes");
+}
+
+static POLL_PORT: Spinlock<Option<PortHandle>> = Spinlock::new(None);
+
+extern "C" fn poll_sender() -> ! {
+    let port = POLL_PORT.lock().take().unwrap();
+    assert_eq!(port.send(Message::new(7, alloc::vec![7])), PortStatus::Ok);
+    exit_current(0);
+}
+
+/// Porta vazia não fica pronta para leitura (com ou sem prazo); a mensagem
+/// enviada por outra task acorda o `poll` bloqueado.
+fn test_poll_port() {
+    let port = PortHandle::new(4);
+    let source: Arc<dyn Pollable> = Arc::new(port.clone());
+    let mut entries = [
+        PollEntry::new(Some(source.clone()), poll_events::IN),
+        PollEntry::new(None, poll_events::IN),
+    ];
+
+    // Handle inválido sempre conta como pronto (NVAL)
+    assert_eq!(poll(&mut entries, Some(0)), 1);
+    assert_eq!(entries[0].revents, 0);
+    assert_eq!(entries[1].revents, poll_events::NVAL);
+
+    let entries = &mut entries[..1];
+    // Prazo de 1 ms vence sem eventos
+    assert_eq!(poll(entries, Some(1_000_000)), 0);
+
+    *POLL_PORT.lock() = Some(port.clone());
+    spawn_kernel_task("ipc-test-poll", poll_sender);
+    assert_eq!(
+        poll(entries, None),
+        1,
+        "(IPC) poll não acordou com a mensagem"
+    );
+    assert_eq!(entries[0].revents, poll_events::IN);
+
+    assert_eq!(port.recv().map(|m| m.header.id), Ok(7));
+    assert_eq!(poll(entries, Some(0)), 0);
+
+    // Espaço na fila: pronto para escrita
+    let mut writable = [PollEntry::new(Some(source), poll_events::OUT)];
+    assert_eq!(poll(&mut writable, Some(0)), 1);
+    assert_eq!(writable[0].revents, poll_events::OUT);
+}
+
+static EVENTFD: Spinlock<Option<EventFd>> = Spinlock::new(None);
+
+extern "C" fn eventfd_signaler() -> ! {
+    let eventfd = EVENTFD.lock().take().unwrap();
+    eventfd.write(2).unwrap();
+    eventfd.write(3).unwrap();
+    exit_current(0);
+}
+
+/// Escritas de outra task acordam a leitura bloqueada e se acumulam; o modo
+/// semáforo entrega de 1 em 1. `poll` só vê leitura com o contador > 0.
+fn test_eventfd_signaling() {
+    let eventfd = EventFd::new(0, false);
+    let mut entries = [PollEntry::new(
+        Some(Arc::new(eventfd.clone()) as Arc<dyn Pollable>),
+        poll_events::IN,
+    )];
+    assert_eq!(poll(&mut entries, Some(0)), 0);
+    assert_eq!(eventfd.try_read(), Err(EventFdError::WouldBlock));
+
+    *EVENTFD.lock() = Some(eventfd.clone());
+    spawn_kernel_task("ipc-test-eventfd", eventfd_signaler);
+    let mut total = eventfd.read();
+    // A primeira leitura pode ter acordado entre as duas escritas
+    if total < 5 {
+        total += eventfd.read();
+    }
+    assert_eq!(total, 5, "(IPC) EventFd perdeu escritas");
+    assert_eq!(poll(&mut entries, Some(0)), 0);
+
+    let semaphore = EventFd::new(2, true);
+    assert_eq!(semaphore.try_read(), Ok(1));
+    assert_eq!(semaphore.try_read(), Ok(1));
+    assert_eq!(semaphore.try_read(), Err(EventFdError::WouldBlock));
+}
@@ -11,7 +11,7 @@ impl<'a> Bitmap<'a> {
     pub fn new(data: &'a mut [u64], bits: usize) -> Self {
         Self { data, len: bits }
     }
-    
+
     /// Define um bit
     pub fn set(&mut self, index: usize) {
         debug_assert!(index < self.len);
@@ -19,7 +19,7 @@ impl<'a> Bitmap<'a> {
         let bit = index % 64;
         self.data[word] |= 1 << bit;
     }
-    
+
     /// Limpa um bit
     pub fn clear(&mut self, index: usize) {
         debug_assert!(index < self.len);
@@ -27,7 +27,7 @@ impl<'a> Bitmap<'a> {
         let bit = index % 64;
         self.data[word] &= !(1 << bit);
     }
-    
+
     /// Testa um bit
     pub fn test(&self, index: usize) -> bool {
         debug_assert!(index < self.len);
@@ -35,7 +35,7 @@ impl<'a> Bitmap<'a> {
         let bit = index % 64;
         (self.data[word] & (1 << bit)) != 0
     }
-    
+
     /// Encontra primeiro bit livre (0)
     pub fn find_first_zero(&self) -> Option<usize> {
         for (i, &word) in self.data.iter().enumerate() {
@@ -49,4 +49,140 @@ impl<'a> Bitmap<'a> {
         }
         None
     }
+
+    /// Encontra a primeira sequência de `len` bits livres consecutivos.
+    ///
+    /// Palavras inteiramente livres ou ocupadas são puladas de uma vez;
+    /// sequências podem atravessar a fronteira entre palavras.
+    pub fn find_free_run(&self, len: usize) -> Option<usize> {
+        if len == 0 || len > self.len {
+            return None;
+        }
+
+        let mut run_start = 0;
+        let mut run_len = 0;
+        for (i, &word) in self.data[..self.len.div_ceil(64)].iter().enumerate() {
+            // Bits além de `self.len` contam como ocupados
+            let valid = (self.len - i * 64).min(64);
+            let word = if valid < 64 {
+                word | (u64::MAX << valid)
+            } else {
+                word
+            };
+
+            if word == u64::MAX {
+                run_len = 0;
+                continue;
+            }
+
+            let mut pos = 0;
+            while pos < 64 {
+                let rest = word >> pos;
+                let free = (rest.trailing_zeros() as usize).min(64 - pos);
+                if free > 0 {
+                    if run_len == 0 {
+                        run_start = i * 64 + pos;
+                    }
+                    run_len += free;
+                    if run_len >= len {
+                        return Some(run_start);
+                    }
+                    pos += free;
+                    if pos == 64 {
+                        break;
+                    }
+                }
+                run_len = 0;
+                pos += (word >> pos).trailing_ones() as usize;
+            }
+        }
+        None
+    }
+
+    /// Define os bits `start..start + len`
+    pub fn set_range(&mut self, start: usize, len: usize) {
+        self.for_each_word(start, len, |word, mask| *word |= mask);
+    }
+
+    /// Limpa os bits `start..start + len`
+    pub fn clear_range(&mut self, start: usize, len: usize) {
+        self.for_each_word(start, len, |word, mask| *word &= !mask);
+    }
+
+    /// Chama `f` com cada palavra tocada pelo intervalo e a máscara dos bits
+    /// dele nessa palavra
+    fn for_each_word(&mut self, start: usize, len: usize, mut f: impl FnMut(&mut u64, u64)) {
+        debug_assert!(start + len <= self.len);
+        let end = start + len;
+        let mut index = start;
+        while index < end {
+            let bit = index % 64;
+            let count = (64 - bit).min(end - index);
+            let mask = if count == 64 {
+                u64::MAX
+            } else {
+                ((1u64 << count) - 1) << bit
+            };
+            f(&mut self.data[index / 64], mask);
+            index += count;
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_free_run_start_middle_end() {
+        let mut data = [0u64; 3];
+        let mut bitmap = Bitmap::new(&mut data, 150);
+        assert_eq!(bitmap.find_free_run(150), Some(0));
+
+        // Livre só 60..130 (atravessa a palavra 1) e 140..150
+        bitmap.set_range(0, 60);
+        bitmap.set_range(130, 10);
+        assert_eq!(bitmap.find_free_run(1), Some(60));
+        assert_eq!(bitmap.find_free_run(70), Some(60));
+        assert_eq!(bitmap.find_free_run(71), None);
+
+        bitmap.set_range(60, 70);
+        assert_eq!(bitmap.find_free_run(10), Some(140));
+        // Bits além de `len` não entram na sequência
+        assert_eq!(bitmap.find_free_run(11), None);
+    }
+
+    #[test]
+    fn test_find_free_run_skips_fragments() {
+        let mut data = [0u64; 2];
+        let mut bitmap = Bitmap::new(&mut data, 128);
+        // Bit sim, bit não: nenhuma sequência de 2
+        for i in (0..128).step_by(2) {
+            bitmap.set(i);
+        }
+        assert_eq!(bitmap.find_free_run(1), Some(1));
+        assert_eq!(bitmap.find_free_run(2), None);
+
+        bitmap.clear_range(63, 3);
+        assert_eq!(bitmap.find_free_run(3), Some(63));
+        assert_eq!(bitmap.find_free_run(0), None);
+    }
+
+    #[test]
+    fn test_set_and_clear_range_across_words() {
+        let mut data = [0u64; 3];
+        let mut bitmap = Bitmap::new(&mut data, 192);
+        bitmap.set_range(60, 72);
+        assert!(!bitmap.test(59));
+        assert!((60..132).all(|i| bitmap.test(i)));
+        assert!(!bitmap.test(132));
+
+        bitmap.clear_range(64, 64);
+        assert!(bitmap.test(63) && !bitmap.test(64) && !bitmap.test(127) && bitmap.test(128));
+        assert_eq!(data, [0xF << 60, 0, 0xF]);
+    }
 }
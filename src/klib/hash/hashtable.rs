@@ -3,8 +3,9 @@
 /// Propósito: Tabela Hash (Dicionário).
 /// Mapeia Chaves -> Valores usando uma função de hash para acesso O(1) médio.
 ///
-/// Detalhes de Implementação básica de Hashtable com Linear Probingsões (Vec de Buckets).
-/// - Função de Hash simples interna ou Trait Hash (vamos usar Hash trait do core).
+/// Detalhes de Implementação:
+/// - Encadeamento separado (Vec de Buckets), hash FNV-1a via trait `Hash` do core.
+/// - Dobra o número de buckets quando a carga passa de 3/4, redistribuindo tudo.
 
 /// Hash Table
 use alloc::vec::Vec;
@@ -12,18 +13,25 @@ use core::hash::{Hash, Hasher};
 // Nota: Em no_std, BuildHasherDefault não está sempre disponível facilmente sem std,
 // então implementamos um Hasher simples FNV-1a.
 
+/// Hasher FNV-1a de 64 bits
 pub struct FnvHasher {
     state: u64,
 }
 
 impl FnvHasher {
-    fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             state: 0xcbf29ce484222325,
         }
     }
 }
 
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hasher for FnvHasher {
     fn write(&mut self, bytes: &[u8]) {
         for &byte in bytes {
@@ -37,6 +45,17 @@ impl Hasher for FnvHasher {
     }
 }
 
+/// FNV-1a de `key` (via `Hash`)
+pub fn fnv1a<K: Hash + ?Sized>(key: &K) -> u64 {
+    let mut hasher = FnvHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Carga máxima (entradas por bucket) antes de dobrar os buckets: 3/4
+const MAX_LOAD_NUM: usize = 3;
+const MAX_LOAD_DEN: usize = 4;
+
 struct Entry<K, V> {
     key: K,
     value: V,
@@ -48,34 +67,62 @@ pub struct HashTable<K, V> {
 }
 
 impl<K: Hash + Eq, V> HashTable<K, V> {
+    /// Cria a tabela com `capacity` buckets (ao menos 1); ela cresce sozinha
     pub fn new(capacity: usize) -> Self {
-        let mut buckets = Vec::with_capacity(capacity);
-        for _ in 0..capacity {
-            buckets.push(Vec::new());
+        Self {
+            buckets: Self::empty_buckets(capacity.max(1)),
+            len: 0,
         }
-        Self { buckets, len: 0 }
+    }
+
+    fn empty_buckets(count: usize) -> Vec<Vec<Entry<K, V>>> {
+        let mut buckets = Vec::with_capacity(count);
+        buckets.resize_with(count, Vec::new);
+        buckets
     }
 
     fn get_bucket_index(&self, key: &K) -> usize {
-        let mut hasher = FnvHasher::new();
-        key.hash(&mut hasher);
-        (hasher.finish() as usize) % self.buckets.len()
+        (fnv1a(key) as usize) % self.buckets.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
+    /// Número de buckets atual
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Insere ou substitui; retorna o valor antigo
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
         let index = self.get_bucket_index(&key);
         let bucket = &mut self.buckets[index];
 
         // Verifica se chave já existe para atualizar
-        for entry in bucket.iter_mut() {
-            if entry.key == key {
-                entry.value = value;
-                return;
-            }
+        if let Some(entry) = bucket.iter_mut().find(|e| e.key == key) {
+            return Some(core::mem::replace(&mut entry.value, value));
         }
 
         bucket.push(Entry { key, value });
         self.len += 1;
+        if self.len * MAX_LOAD_DEN > self.buckets.len() * MAX_LOAD_NUM {
+            self.resize(self.buckets.len() * 2);
+        }
+        None
+    }
+
+    /// Redistribui todas as entradas em `count` buckets
+    fn resize(&mut self, count: usize) {
+        let old = core::mem::replace(&mut self.buckets, Self::empty_buckets(count));
+        for entry in old.into_iter().flatten() {
+            let index = self.get_bucket_index(&entry.key);
+            self.buckets[index].push(entry);
+        }
     }
 
     pub fn get(&self, key: &K) -> Option<&V> {
@@ -90,14 +137,92 @@ impl<K: Hash + Eq, V> HashTable<K, V> {
         None
     }
 
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.get_bucket_index(key);
+        self.buckets[index]
+            .iter_mut()
+            .find(|e| e.key == *key)
+            .map(|e| &mut e.value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
     pub fn remove(&mut self, key: &K) -> Option<V> {
         let index = self.get_bucket_index(key);
         let bucket = &mut self.buckets[index];
 
         if let Some(pos) = bucket.iter().position(|e| e.key == *key) {
             self.len -= 1;
-            return Some(bucket.remove(pos).value);
+            return Some(bucket.swap_remove(pos).value);
         }
         None
     }
+
+    /// Todos os pares, em ordem arbitrária
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        let mut hasher = FnvHasher::new();
+        hasher.write(b"");
+        assert_eq!(hasher.finish(), 0xcbf29ce484222325);
+        let mut hasher = FnvHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_grows_past_threshold_and_keeps_keys() {
+        let mut table = HashTable::new(4);
+        for key in 0..3u32 {
+            table.insert(key, key * 2);
+        }
+        assert_eq!(table.bucket_count(), 4);
+
+        // Passa de 3/4 de carga várias vezes
+        for key in 3..500u32 {
+            assert_eq!(table.insert(key, key * 2), None);
+        }
+        assert!(table.bucket_count() >= 500 * MAX_LOAD_DEN / MAX_LOAD_NUM);
+        assert_eq!(table.len(), 500);
+        for key in 0..500u32 {
+            assert_eq!(table.get(&key), Some(&(key * 2)));
+        }
+        assert!(!table.contains_key(&500));
+    }
+
+    #[test]
+    fn test_update_remove_and_iter() {
+        let mut table = HashTable::new(0);
+        assert_eq!(table.insert("init", 1), None);
+        assert_eq!(table.insert("shell", 2), None);
+        assert_eq!(table.insert("init", 10), Some(1));
+        *table.get_mut(&"shell").unwrap() += 1;
+
+        let mut pairs: Vec<_> = table.iter().map(|(k, v)| (*k, *v)).collect();
+        pairs.sort();
+        assert_eq!(pairs, [("init", 10), ("shell", 3)]);
+
+        assert_eq!(table.remove(&"init"), Some(10));
+        assert_eq!(table.remove(&"init"), None);
+        assert!(!table.contains_key(&"init"));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.iter().count(), 1);
+    }
 }
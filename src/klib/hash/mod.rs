@@ -1,3 +1,5 @@
 //! Hash implementations
 
 pub mod hashtable;
+
+pub use hashtable::{fnv1a, FnvHasher, HashTable};
@@ -0,0 +1,368 @@
+//! # Lista Intrusiva
+//!
+//! Lista duplamente encadeada em que os ponteiros ficam dentro do próprio
+//! elemento (um [`ListLink`] embutido), sem alocação por inserção. Remover
+//! um elemento qualquer é O(1): o elemento sabe onde está.
+//!
+//! ## Posse e endereço fixo
+//! A lista não é dona dos elementos: guarda `&'a T`. Enquanto a lista
+//! existir, o borrow impede que um elemento inserido seja movido ou
+//! destruído, então os ponteiros internos nunca ficam pendurados. Elementos
+//! com vida longa (estáticos, `Box::leak`, dados de uma task viva) servem
+//! diretamente; para um elemento na pilha, a lista precisa morrer antes dele.
+//!
+//! Um elemento está em no máximo uma lista por [`ListLink`]: o link guarda o
+//! id da lista dona. `push_*` de um elemento já encadeado entra em pânico, e
+//! `remove` numa lista que não é a dona retorna `false` sem tocar em nada.
+//! Ao ser destruída, a lista desencadeia tudo o que sobrou.
+//!
+//! ## Concorrência
+//! Os ponteiros de um link só mudam por métodos `&mut` da lista dona, então
+//! quem sincroniza a lista (ex.: um `Spinlock`) sincroniza os links.
+
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Ids de lista; 0 marca um link livre
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Ponteiros de lista embutidos num elemento `T`
+pub struct ListLink<T> {
+    /// Id da lista que contém o elemento (0 = nenhuma)
+    owner: AtomicUsize,
+    prev: UnsafeCell<Option<NonNull<T>>>,
+    next: UnsafeCell<Option<NonNull<T>>>,
+}
+
+// SAFETY: `prev`/`next` só são acessados pela lista dona (ver módulo)
+unsafe impl<T> Send for ListLink<T> {}
+unsafe impl<T> Sync for ListLink<T> {}
+
+impl<T> ListLink<T> {
+    pub const fn new() -> Self {
+        Self {
+            owner: AtomicUsize::new(0),
+            prev: UnsafeCell::new(None),
+            next: UnsafeCell::new(None),
+        }
+    }
+
+    /// O elemento está em alguma lista
+    pub fn is_linked(&self) -> bool {
+        self.owner.load(Ordering::Acquire) != 0
+    }
+}
+
+impl<T> Default for ListLink<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Elemento que pode entrar numa [`IntrusiveList`]
+///
+/// # Safety
+/// `link` deve retornar sempre o mesmo [`ListLink`], guardado dentro de
+/// `self` e usado por nenhuma outra implementação de `Linked`.
+pub unsafe trait Linked: Sized {
+    fn link(&self) -> &ListLink<Self>;
+}
+
+/// Lista intrusiva de elementos emprestados por `'a`
+pub struct IntrusiveList<'a, T: Linked> {
+    /// Atribuído na primeira inserção, para `new` continuar `const`
+    id: usize,
+    head: Option<NonNull<T>>,
+    tail: Option<NonNull<T>>,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+// SAFETY: a lista só entrega `&'a T`; os links são mexidos com `&mut self`
+unsafe impl<T: Linked + Sync> Send for IntrusiveList<'_, T> {}
+unsafe impl<T: Linked + Sync> Sync for IntrusiveList<'_, T> {}
+
+impl<'a, T: Linked> IntrusiveList<'a, T> {
+    pub const fn new() -> Self {
+        Self {
+            id: 0,
+            head: None,
+            tail: None,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&'a T> {
+        // SAFETY: elementos encadeados vivem por `'a`
+        self.head.map(|node| unsafe { &*node.as_ptr() })
+    }
+
+    pub fn back(&self) -> Option<&'a T> {
+        // SAFETY: elementos encadeados vivem por `'a`
+        self.tail.map(|node| unsafe { &*node.as_ptr() })
+    }
+
+    /// `node` está nesta lista
+    pub fn contains(&self, node: &T) -> bool {
+        self.id != 0 && node.link().owner.load(Ordering::Acquire) == self.id
+    }
+
+    /// Insere no fim.
+    ///
+    /// # Panics
+    /// Se `node` já estiver em alguma lista.
+    pub fn push_back(&mut self, node: &'a T) {
+        self.claim(node);
+        let ptr = NonNull::from(node);
+        // SAFETY: `node` acabou de ser reivindicado; `tail` é desta lista
+        unsafe {
+            *node.link().prev.get() = self.tail;
+            *node.link().next.get() = None;
+            match self.tail {
+                Some(tail) => *tail.as_ref().link().next.get() = Some(ptr),
+                None => self.head = Some(ptr),
+            }
+        }
+        self.tail = Some(ptr);
+        self.len += 1;
+    }
+
+    /// Insere no início.
+    ///
+    /// # Panics
+    /// Se `node` já estiver em alguma lista.
+    pub fn push_front(&mut self, node: &'a T) {
+        self.claim(node);
+        let ptr = NonNull::from(node);
+        // SAFETY: `node` acabou de ser reivindicado; `head` é desta lista
+        unsafe {
+            *node.link().prev.get() = None;
+            *node.link().next.get() = self.head;
+            match self.head {
+                Some(head) => *head.as_ref().link().prev.get() = Some(ptr),
+                None => self.tail = Some(ptr),
+            }
+        }
+        self.head = Some(ptr);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<&'a T> {
+        let node = self.front()?;
+        self.unlink(node);
+        Some(node)
+    }
+
+    pub fn pop_back(&mut self) -> Option<&'a T> {
+        let node = self.back()?;
+        self.unlink(node);
+        Some(node)
+    }
+
+    /// Remove `node`, em qualquer posição. Retorna `false` se ele não
+    /// estava nesta lista.
+    pub fn remove(&mut self, node: &T) -> bool {
+        if !self.contains(node) {
+            return false;
+        }
+        self.unlink(node);
+        true
+    }
+
+    /// Iteração do início ao fim
+    pub fn iter(&self) -> Iter<'_, 'a, T> {
+        Iter {
+            next: self.head,
+            _list: PhantomData,
+        }
+    }
+
+    /// Marca `node` como desta lista (pânico se já tiver dona)
+    fn claim(&mut self, node: &T) {
+        if self.id == 0 {
+            self.id = NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed);
+        }
+        let claimed =
+            node.link()
+                .owner
+                .compare_exchange(0, self.id, Ordering::AcqRel, Ordering::Acquire);
+        assert!(claimed.is_ok(), "IntrusiveList: nó já está em uma lista");
+    }
+
+    /// Tira `node` (desta lista) do encadeamento
+    fn unlink(&mut self, node: &T) {
+        let link = node.link();
+        // SAFETY: `node` é desta lista; vizinhos também, e vivem por `'a`
+        unsafe {
+            let prev = (*link.prev.get()).take();
+            let next = (*link.next.get()).take();
+            match prev {
+                Some(prev) => *prev.as_ref().link().next.get() = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => *next.as_ref().link().prev.get() = prev,
+                None => self.tail = prev,
+            }
+        }
+        link.owner.store(0, Ordering::Release);
+        self.len -= 1;
+    }
+}
+
+impl<T: Linked> Default for IntrusiveList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Linked> Drop for IntrusiveList<'_, T> {
+    fn drop(&mut self) {
+        // Libera os links para os elementos poderem entrar em outra lista
+        while self.pop_front().is_some() {}
+    }
+}
+
+/// Iterador do início ao fim de uma [`IntrusiveList`]
+pub struct Iter<'l, 'a, T: Linked> {
+    next: Option<NonNull<T>>,
+    /// A lista fica emprestada: ninguém a altera durante a iteração
+    _list: PhantomData<&'l IntrusiveList<'a, T>>,
+}
+
+impl<'a, T: Linked> Iterator for Iter<'_, 'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.next?;
+        // SAFETY: elemento encadeado, vivo por `'a`; a lista está emprestada
+        let node = unsafe { &*node.as_ptr() };
+        self.next = unsafe { *node.link().next.get() };
+        Some(node)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    struct Item {
+        value: u32,
+        link: ListLink<Item>,
+    }
+
+    unsafe impl Linked for Item {
+        fn link(&self) -> &ListLink<Self> {
+            &self.link
+        }
+    }
+
+    fn items<const N: usize>() -> [Item; N] {
+        core::array::from_fn(|i| Item {
+            value: i as u32,
+            link: ListLink::new(),
+        })
+    }
+
+    fn values(list: &IntrusiveList<'_, Item>) -> Vec<u32> {
+        list.iter().map(|item| item.value).collect()
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let items = items::<4>();
+        let mut list = IntrusiveList::new();
+        list.push_back(&items[1]);
+        list.push_back(&items[2]);
+        list.push_front(&items[0]);
+        list.push_back(&items[3]);
+        assert_eq!(values(&list), [0, 1, 2, 3]);
+        assert_eq!(list.len(), 4);
+
+        assert_eq!(list.pop_front().map(|i| i.value), Some(0));
+        assert_eq!(list.pop_back().map(|i| i.value), Some(3));
+        assert!(!items[0].link.is_linked());
+        assert_eq!(values(&list), [1, 2]);
+        assert_eq!(list.front().map(|i| i.value), Some(1));
+        assert_eq!(list.back().map(|i| i.value), Some(2));
+    }
+
+    #[test]
+    fn test_remove_middle_head_and_tail() {
+        let items = items::<5>();
+        let mut list = IntrusiveList::new();
+        for item in &items {
+            list.push_back(item);
+        }
+
+        assert!(list.remove(&items[2]));
+        assert_eq!(values(&list), [0, 1, 3, 4]);
+        assert!(list.remove(&items[0]));
+        assert_eq!(values(&list), [1, 3, 4]);
+        assert!(list.remove(&items[4]));
+        assert_eq!(values(&list), [1, 3]);
+        assert!(!list.remove(&items[4]));
+
+        // O elemento removido pode voltar, em outra posição
+        list.push_front(&items[2]);
+        assert_eq!(values(&list), [2, 1, 3]);
+    }
+
+    #[test]
+    fn test_remove_only_element() {
+        let items = items::<1>();
+        let mut list = IntrusiveList::new();
+        list.push_back(&items[0]);
+        assert!(list.remove(&items[0]));
+        assert!(list.is_empty());
+        assert!(list.front().is_none() && list.back().is_none());
+        assert_eq!(list.pop_front().map(|i| i.value), None);
+
+        list.push_back(&items[0]);
+        assert_eq!(values(&list), [0]);
+    }
+
+    #[test]
+    fn test_membership_is_per_list() {
+        let items = items::<2>();
+        let mut a = IntrusiveList::new();
+        let mut b = IntrusiveList::new();
+        a.push_back(&items[0]);
+        b.push_back(&items[1]);
+
+        assert!(!b.remove(&items[0]));
+        assert!(a.contains(&items[0]) && !b.contains(&items[0]));
+        assert_eq!(values(&a), [0]);
+
+        // Destruir a lista libera os elementos
+        drop(a);
+        assert!(!items[0].link.is_linked());
+        b.push_back(&items[0]);
+        assert_eq!(values(&b), [1, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_double_insert_panics() {
+        let items = items::<1>();
+        let mut list = IntrusiveList::new();
+        list.push_back(&items[0]);
+        list.push_back(&items[0]);
+    }
+}
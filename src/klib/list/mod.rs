@@ -1,3 +1,6 @@
 //! List implementations
 
+pub mod intrusive;
 pub mod linked;
+
+pub use intrusive::{IntrusiveList, Linked, ListLink};
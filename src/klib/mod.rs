@@ -11,8 +11,10 @@ pub mod bitflags;
 
 pub mod hash;
 pub mod list;
+pub mod ringbuffer;
 pub mod string;
 pub mod tree;
 
 pub use align::{align_down, align_up, is_aligned};
 pub use bitmap::Bitmap;
+pub use ringbuffer::RingBuffer;
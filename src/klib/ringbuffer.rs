@@ -0,0 +1,218 @@
+//! # Ring Buffer
+//!
+//! Fila circular de capacidade fixa `N` (potência de dois), sem alocação.
+//! Com a fila cheia, `push` descarta o elemento mais antigo e conta o
+//! descarte em [`RingBuffer::dropped_count`].
+//!
+//! ## Concorrência
+//! Sem lock para um produtor e um consumidor (`&self` em todos os métodos).
+//! `head` e `tail` são contadores que só crescem; o índice no array é o
+//! contador mascarado. Descartar o mais antigo move `tail` do lado do
+//! produtor, então o consumidor confirma cada leitura com um CAS em `tail`:
+//! se o produtor descartou aquele slot no meio, a leitura é jogada fora e o
+//! consumidor tenta de novo. Por isso `T: Copy`.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Fila circular SPSC de `N` elementos
+pub struct RingBuffer<T: Copy, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    /// Próxima escrita (só o produtor avança)
+    head: AtomicUsize,
+    /// Próxima leitura (consumidor, ou produtor ao descartar)
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// SAFETY: cada slot é escrito só pelo produtor, fora do intervalo visível ao
+// consumidor; leituras concorrentes com um descarte são descartadas
+unsafe impl<T: Copy + Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+    /// Índice no array a partir de um contador (falha de compilação se `N`
+    /// não for potência de dois)
+    const MASK: usize = {
+        assert!(
+            N.is_power_of_two(),
+            "RingBuffer: N precisa ser potência de dois"
+        );
+        N - 1
+    };
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY_SLOT: UnsafeCell<MaybeUninit<T>> = UnsafeCell::new(MaybeUninit::uninit());
+
+    pub const fn new() -> Self {
+        Self {
+            slots: [Self::EMPTY_SLOT; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        head.wrapping_sub(tail).min(N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Elementos descartados por fila cheia desde a criação
+    pub fn dropped_count(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Enfileira `value`. Retorna `true` se a fila estava cheia e o elemento
+    /// mais antigo foi descartado.
+    pub fn push(&self, value: T) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        let mut dropped = false;
+        if head.wrapping_sub(tail) >= N {
+            // Se o consumidor leu no meio, já há espaço e nada é descartado
+            if self
+                .tail
+                .compare_exchange(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                dropped = true;
+            }
+        }
+
+        // SAFETY: o slot de `head` está fora de [tail, head): ninguém o lê
+        // como válido até `head` avançar
+        unsafe { (*self.slots[head & Self::MASK].get()).write(value) };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        dropped
+    }
+
+    /// Retira o elemento mais antigo
+    pub fn pop(&self) -> Option<T> {
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let head = self.head.load(Ordering::Acquire);
+            if tail == head {
+                return None;
+            }
+            // SAFETY: slot em [tail, head), escrito antes do `head` lido. Se
+            // o produtor o descartar e reescrever agora, o CAS abaixo falha
+            // e o valor não é usado
+            let value = unsafe { (*self.slots[tail & Self::MASK].get()).assume_init_read() };
+            if self
+                .tail
+                .compare_exchange(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                return Some(value);
+            }
+        }
+    }
+}
+
+impl<const N: usize> RingBuffer<u8, N> {
+    /// Enfileira `bytes` em ordem. Retorna quantos bytes antigos foram
+    /// descartados para caber.
+    pub fn push_slice(&self, bytes: &[u8]) -> usize {
+        bytes.iter().filter(|&&byte| self.push(byte)).count()
+    }
+
+    /// Retira até `buf.len()` bytes. Retorna quantos foram copiados.
+    pub fn pop_slice(&self, buf: &mut [u8]) -> usize {
+        let mut count = 0;
+        for slot in buf.iter_mut() {
+            let Some(byte) = self.pop() else { break };
+            *slot = byte;
+            count += 1;
+        }
+        count
+    }
+}
+
+impl<T: Copy, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_and_wraparound() {
+        let ring: RingBuffer<u32, 4> = RingBuffer::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.pop(), None);
+
+        // Várias voltas pelo array
+        for round in 0..10 {
+            assert!(!ring.push(round * 2));
+            assert!(!ring.push(round * 2 + 1));
+            assert_eq!(ring.len(), 2);
+            assert_eq!(ring.pop(), Some(round * 2));
+            assert_eq!(ring.pop(), Some(round * 2 + 1));
+        }
+        assert!(ring.is_empty());
+        assert_eq!(ring.dropped_count(), 0);
+    }
+
+    #[test]
+    fn test_overflow_drops_oldest() {
+        let ring: RingBuffer<u8, 8> = RingBuffer::new();
+        for i in 0..8 {
+            assert!(!ring.push(i));
+        }
+        assert!(ring.is_full());
+        assert!(ring.push(8));
+        assert!(ring.push(9));
+        assert_eq!(ring.dropped_count(), 2);
+        assert_eq!(ring.len(), 8);
+
+        let mut out = [0u8; 16];
+        assert_eq!(ring.pop_slice(&mut out), 8);
+        assert_eq!(&out[..8], &[2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_byte_slices() {
+        let ring: RingBuffer<u8, 4> = RingBuffer::new();
+        assert_eq!(ring.push_slice(b"abcdef"), 2);
+        let mut out = [0u8; 3];
+        assert_eq!(ring.pop_slice(&mut out), 3);
+        assert_eq!(&out, b"cde");
+        assert_eq!(ring.push_slice(b"gh"), 0);
+        assert_eq!(ring.pop_slice(&mut out), 3);
+        assert_eq!(&out, b"fgh");
+        assert_eq!(ring.pop_slice(&mut out), 0);
+    }
+}
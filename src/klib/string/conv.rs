@@ -0,0 +1,110 @@
+//! Conversão entre inteiros e texto, sem alocação
+//!
+//! A escrita preenche o buffer a partir do fim: os dígitos ficam alinhados à
+//! direita e o que vem antes não é tocado. Para largura fixa com zeros à
+//! esquerda, basta passar um buffer já preenchido com `b'0'`.
+
+/// Dígitos de `u64::MAX` em decimal
+pub const DEC_MAX_LEN: usize = 20;
+
+/// Dígitos de `u64::MAX` em hexadecimal
+pub const HEX_MAX_LEN: usize = 16;
+
+const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Escreve `val` em decimal no fim de `buf` e retorna os dígitos.
+///
+/// # Panics
+/// Se `buf` não comportar os dígitos ([`DEC_MAX_LEN`] sempre basta).
+pub fn u64_to_dec(buf: &mut [u8], val: u64) -> &str {
+    write_radix(buf, val, 10)
+}
+
+/// Escreve `val` em hexadecimal maiúsculo, sem prefixo, no fim de `buf` e
+/// retorna os dígitos.
+///
+/// # Panics
+/// Se `buf` não comportar os dígitos ([`HEX_MAX_LEN`] sempre basta).
+pub fn u64_to_hex(buf: &mut [u8], val: u64) -> &str {
+    write_radix(buf, val, 16)
+}
+
+fn write_radix(buf: &mut [u8], mut val: u64, radix: u64) -> &str {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = DIGITS[(val % radix) as usize];
+        val /= radix;
+        if val == 0 {
+            break;
+        }
+    }
+    // SAFETY: só dígitos ASCII foram escritos em `buf[start..]`
+    unsafe { core::str::from_utf8_unchecked(&buf[start..]) }
+}
+
+/// Interpreta `s` como inteiro sem sinal na base `radix` (2 a 36).
+///
+/// Espaços em branco no início e no fim são ignorados. Retorna `None` para
+/// texto vazio, dígito inválido (inclusive sinal ou prefixo como `0x`) ou
+/// valor acima de `u64::MAX`.
+pub fn parse_u64(s: &str, radix: u32) -> Option<u64> {
+    if !(2..=36).contains(&radix) {
+        return None;
+    }
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    s.chars().try_fold(0u64, |value, c| {
+        value
+            .checked_mul(radix as u64)?
+            .checked_add(c.to_digit(radix)? as u64)
+    })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u64_to_dec() {
+        let mut buf = [0u8; DEC_MAX_LEN];
+        assert_eq!(u64_to_dec(&mut buf, 0), "0");
+        assert_eq!(u64_to_dec(&mut buf, 1234), "1234");
+        assert_eq!(u64_to_dec(&mut buf, u64::MAX), "18446744073709551615");
+    }
+
+    #[test]
+    fn test_u64_to_hex_right_aligned() {
+        let mut buf = [0u8; HEX_MAX_LEN];
+        assert_eq!(u64_to_hex(&mut buf, 0xBEEF), "BEEF");
+        assert_eq!(u64_to_hex(&mut buf, u64::MAX), "FFFFFFFFFFFFFFFF");
+
+        // Zeros à esquerda vêm do próprio buffer
+        let mut padded = [b'0'; 8];
+        u64_to_hex(&mut padded, 0x2A);
+        assert_eq!(&padded, b"0000002A");
+    }
+
+    #[test]
+    fn test_parse_u64() {
+        assert_eq!(parse_u64("  42\n", 10), Some(42));
+        assert_eq!(parse_u64("0000755", 8), Some(0o755));
+        assert_eq!(parse_u64("ff", 16), Some(0xFF));
+        assert_eq!(parse_u64("18446744073709551615", 10), Some(u64::MAX));
+
+        assert_eq!(parse_u64("18446744073709551616", 10), None);
+        assert_eq!(parse_u64("1FFFFFFFFFFFFFFFF", 16), None);
+        assert_eq!(parse_u64("", 10), None);
+        assert_eq!(parse_u64("   ", 10), None);
+        assert_eq!(parse_u64("12 3", 10), None);
+        assert_eq!(parse_u64("-1", 10), None);
+        assert_eq!(parse_u64("8", 8), None);
+        assert_eq!(parse_u64("1", 37), None);
+    }
+}
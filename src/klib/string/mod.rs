@@ -1,4 +1,6 @@
 //! String implementation
 
+pub mod conv;
 pub mod string;
+pub use conv::{parse_u64, u64_to_dec, u64_to_hex};
 pub use string::{strcmp, strlen, strncmp};
@@ -1,3 +1,5 @@
 //! Tree implementations
 
 pub mod rbtree;
+
+pub use rbtree::RbTree;
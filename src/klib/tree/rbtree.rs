@@ -1,84 +1,100 @@
-/// Arquivo: klib/tree/rbtree.rs
-///
-/// Propósito: Árvore Rubro-Negra (Red-Black Tree).
-/// Estrutura de dados balanceada para busca eficiente.
-///
-/// Detalhes de Implementação:
-/// - Chaves ordenáveis (Ord).
-/// - Nós alocados na heap (alloc).
-/// - Implementação simplificada (BST por enquanto) com estrutura para cores.
-
-/// Red-Black Tree
+//! # Árvore Red-Black
+//!
+//! Mapa ordenado genérico `RbTree<K: Ord, V>` (variante left-leaning), com
+//! nós na heap. Usado pelo CFS (chave `(vruntime, tid)`) e pelo
+//! `AddressSpace` (chave: endereço inicial da VMA).
+//!
+//! Busca, inserção e remoção são O(log n): a altura fica abaixo de
+//! `2 * log2(n + 1)`.
+//!
+//! ## Invariantes
+//! - Nenhum nó vermelho tem filho vermelho.
+//! - Todo caminho da raiz até uma folha tem o mesmo número de nós pretos.
+//! - Nós vermelhos são sempre filhos à esquerda (LLRB).
+
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::cmp::Ordering;
+use core::ops::{Bound, RangeBounds};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Color {
-    Red,
-    Black,
-}
+type Link<K, V> = Option<Box<Node<K, V>>>;
 
 struct Node<K, V> {
     key: K,
     value: V,
-    color: Color,
-    left: Option<Box<Node<K, V>>>,
-    right: Option<Box<Node<K, V>>>,
+    red: bool,
+    left: Link<K, V>,
+    right: Link<K, V>,
 }
 
 impl<K, V> Node<K, V> {
-    fn new(key: K, value: V) -> Self {
-        Self {
+    fn new(key: K, value: V) -> Box<Self> {
+        Box::new(Self {
             key,
             value,
-            color: Color::Red,
+            red: true,
             left: None,
             right: None,
-        }
+        })
     }
 }
 
-pub struct RBTree<K, V> {
-    root: Option<Box<Node<K, V>>>,
+/// Árvore Red-Black
+pub struct RbTree<K, V> {
+    root: Link<K, V>,
     len: usize,
 }
 
-impl<K: Ord, V> RBTree<K, V> {
+impl<K: Ord, V> RbTree<K, V> {
     pub const fn new() -> Self {
         Self { root: None, len: 0 }
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        // TODO: Implementar balanceamento RB completo.
-        // Por enquanto, inserção BST simples.
-        self.len += 1;
-        let new_node = Box::new(Node::new(key, value));
+    /// Insere (ou substitui) o valor associado a `key`
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut old = None;
+        let mut root = insert(self.root.take(), key, value, &mut old);
+        root.red = false;
+        self.root = Some(root);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
 
-        match &mut self.root {
-            None => {
-                self.root = Some(new_node);
-                // Raiz é sempre preta
-                if let Some(ref mut node) = self.root {
-                    node.color = Color::Black;
-                }
-            }
-            Some(root) => {
-                Self::insert_recursive(root, new_node);
-            }
+    /// Remove a entrada com chave `key`
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.get(key)?;
+
+        let mut root = self.root.take()?;
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.red = true;
+        }
+
+        let mut out = None;
+        self.root = remove(root, key, &mut out);
+        if let Some(root) = self.root.as_mut() {
+            root.red = false;
         }
+        self.len -= 1;
+        out
     }
 
-    fn insert_recursive(node: &mut Node<K, V>, new_node: Box<Node<K, V>>) {
-        match new_node.key.cmp(&node.key) {
-            Ordering::Less => match &mut node.left {
-                None => node.left = Some(new_node),
-                Some(left) => Self::insert_recursive(left, new_node),
-            },
-            Ordering::Greater | Ordering::Equal => match &mut node.right {
-                None => node.right = Some(new_node),
-                Some(right) => Self::insert_recursive(right, new_node),
-            },
+    /// Remove e retorna a entrada com a menor chave
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let mut root = self.root.take()?;
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.red = true;
         }
+
+        let (rest, min) = remove_min(root);
+        self.root = rest;
+        if let Some(root) = self.root.as_mut() {
+            root.red = false;
+        }
+        self.len -= 1;
+        let min = *min;
+        Some((min.key, min.value))
     }
 
     pub fn get(&self, key: &K) -> Option<&V> {
@@ -92,4 +108,470 @@ impl<K: Ord, V> RBTree<K, V> {
         }
         None
     }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut current = &mut self.root;
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => current = &mut node.left,
+                Ordering::Greater => current = &mut node.right,
+            }
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Entrada com a menor chave
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = node.left.as_ref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Entrada com a maior chave
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = node.right.as_ref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Entrada com a maior chave `<= key`
+    pub fn floor(&self, key: &K) -> Option<(&K, &V)> {
+        let mut current = &self.root;
+        let mut best = None;
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Less => current = &node.left,
+                Ordering::Greater => {
+                    best = Some((&node.key, &node.value));
+                    current = &node.right;
+                }
+            }
+        }
+        best
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iteração em ordem crescente de chave
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.seek(Bound::Unbounded)
+    }
+
+    /// Iteração em ordem a partir da primeira chave `>= key`
+    pub fn iter_from(&self, key: &K) -> Iter<'_, K, V> {
+        self.seek(Bound::Included(key))
+    }
+
+    /// Iteração em ordem sobre as chaves dentro de `range`
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        Range {
+            iter: self.seek(range.start_bound()),
+            last: self.last_within(range.end_bound()),
+        }
+    }
+
+    /// Remove todas as entradas
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Iterador parado antes da primeira chave dentro de `start`
+    fn seek(&self, start: Bound<&K>) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let inside = match start {
+                Bound::Included(key) => node.key >= *key,
+                Bound::Excluded(key) => node.key > *key,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                iter.stack.push(node);
+                current = &node.left;
+            } else {
+                current = &node.right;
+            }
+        }
+        iter
+    }
+
+    /// Maior chave dentro de `end` (pertence à árvore, então o `Range` não
+    /// precisa guardar o limite pedido)
+    fn last_within(&self, end: Bound<&K>) -> Option<&K> {
+        let mut current = &self.root;
+        let mut best = None;
+        while let Some(node) = current {
+            let inside = match end {
+                Bound::Included(key) => node.key <= *key,
+                Bound::Excluded(key) => node.key < *key,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                best = Some(&node.key);
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        best
+    }
+
+    /// Altura (nós no caminho mais longo até uma folha)
+    #[cfg(test)]
+    pub(crate) fn height(&self) -> usize {
+        fn height<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref()
+                .map_or(0, |n| 1 + core::cmp::max(height(&n.left), height(&n.right)))
+        }
+        height(&self.root)
+    }
+}
+
+impl<K: Ord, V> Default for RbTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Clone for RbTree<K, V> {
+    fn clone(&self) -> Self {
+        let mut tree = Self::new();
+        for (k, v) in self.iter() {
+            tree.insert(k.clone(), v.clone());
+        }
+        tree
+    }
+}
+
+/// Iterador em ordem
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+/// Iterador em ordem sobre um intervalo de chaves ([`RbTree::range`])
+pub struct Range<'a, K, V> {
+    iter: Iter<'a, K, V>,
+    /// Última chave do intervalo (`None`: intervalo vazio)
+    last: Option<&'a K>,
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.last?;
+        let (key, value) = self.iter.next()?;
+        if key > last {
+            self.last = None;
+            return None;
+        }
+        Some((key, value))
+    }
+}
+
+// =============================================================================
+// OPERAÇÕES INTERNAS (LLRB)
+// =============================================================================
+
+fn is_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().map_or(false, |n| n.red)
+}
+
+fn rotate_left<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.right.take().expect("rotate_left sem filho direito");
+    h.right = x.left.take();
+    x.red = h.red;
+    h.red = true;
+    x.left = Some(h);
+    x
+}
+
+fn rotate_right<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.left.take().expect("rotate_right sem filho esquerdo");
+    h.left = x.right.take();
+    x.red = h.red;
+    h.red = true;
+    x.right = Some(h);
+    x
+}
+
+fn flip_colors<K, V>(h: &mut Node<K, V>) {
+    h.red = !h.red;
+    if let Some(l) = h.left.as_mut() {
+        l.red = !l.red;
+    }
+    if let Some(r) = h.right.as_mut() {
+        r.red = !r.red;
+    }
+}
+
+fn fix_up<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    if is_red(&h.right) && !is_red(&h.left) {
+        h = rotate_left(h);
+    }
+    if is_red(&h.left) && is_red(&h.left.as_ref().unwrap().left) {
+        h = rotate_right(h);
+    }
+    if is_red(&h.left) && is_red(&h.right) {
+        flip_colors(&mut h);
+    }
+    h
+}
+
+fn move_red_left<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    flip_colors(&mut h);
+    if is_red(&h.right.as_ref().unwrap().left) {
+        h.right = Some(rotate_right(h.right.take().unwrap()));
+        h = rotate_left(h);
+        flip_colors(&mut h);
+    }
+    h
+}
+
+fn move_red_right<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    flip_colors(&mut h);
+    if is_red(&h.left.as_ref().unwrap().left) {
+        h = rotate_right(h);
+        flip_colors(&mut h);
+    }
+    h
+}
+
+fn insert<K: Ord, V>(link: Link<K, V>, key: K, value: V, old: &mut Option<V>) -> Box<Node<K, V>> {
+    let mut h = match link {
+        None => return Node::new(key, value),
+        Some(h) => h,
+    };
+
+    match key.cmp(&h.key) {
+        Ordering::Less => h.left = Some(insert(h.left.take(), key, value, old)),
+        Ordering::Greater => h.right = Some(insert(h.right.take(), key, value, old)),
+        Ordering::Equal => *old = Some(core::mem::replace(&mut h.value, value)),
+    }
+
+    fix_up(h)
+}
+
+fn remove_min<K, V>(mut h: Box<Node<K, V>>) -> (Link<K, V>, Box<Node<K, V>>) {
+    if h.left.is_none() {
+        return (None, h);
+    }
+    if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
+        h = move_red_left(h);
+    }
+    let (left, min) = remove_min(h.left.take().unwrap());
+    h.left = left;
+    (Some(fix_up(h)), min)
+}
+
+/// Remove `key` da subárvore. O chamador garante que a chave existe.
+fn remove<K: Ord, V>(mut h: Box<Node<K, V>>, key: &K, out: &mut Option<V>) -> Link<K, V> {
+    if *key < h.key {
+        if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
+            h = move_red_left(h);
+        }
+        h.left = remove(h.left.take().unwrap(), key, out);
+    } else {
+        if is_red(&h.left) {
+            h = rotate_right(h);
+        }
+        if *key == h.key && h.right.is_none() {
+            *out = Some(h.value);
+            return None;
+        }
+        if !is_red(&h.right) && !is_red(&h.right.as_ref().unwrap().left) {
+            h = move_red_right(h);
+        }
+        if *key == h.key {
+            let (right, min) = remove_min(h.right.take().unwrap());
+            h.right = right;
+            let min = *min;
+            h.key = min.key;
+            *out = Some(core::mem::replace(&mut h.value, min.value));
+        } else {
+            h.right = remove(h.right.take().unwrap(), key, out);
+        }
+    }
+    Some(fix_up(h))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Confere as invariantes da subárvore e retorna a sua altura preta
+    fn check<K: Ord, V>(link: &Link<K, V>, parent_red: bool) -> usize {
+        let Some(node) = link else {
+            return 1;
+        };
+        assert!(!(parent_red && node.red), "vermelho com filho vermelho");
+        assert!(!is_red(&node.right), "vermelho à direita");
+        if let Some(left) = &node.left {
+            assert!(left.key < node.key);
+        }
+        if let Some(right) = &node.right {
+            assert!(right.key > node.key);
+        }
+        let left = check(&node.left, node.red);
+        let right = check(&node.right, node.red);
+        assert_eq!(left, right, "alturas pretas diferentes");
+        left + !node.red as usize
+    }
+
+    fn check_tree<K: Ord, V>(tree: &RbTree<K, V>) {
+        assert!(!is_red(&tree.root));
+        check(&tree.root, false);
+        assert_eq!(tree.iter().count(), tree.len());
+    }
+
+    /// Permutação pseudoaleatória (xorshift) de `0..n`
+    fn shuffled(n: u64, mut seed: u64) -> Vec<u64> {
+        let mut keys: Vec<u64> = (0..n).collect();
+        for i in (1..keys.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            keys.swap(i, (seed % (i as u64 + 1)) as usize);
+        }
+        keys
+    }
+
+    #[test]
+    fn test_insert_remove_ordered() {
+        let mut tree = RbTree::new();
+        for k in [5u64, 1, 9, 3, 7, 2, 8] {
+            tree.insert(k, k * 10);
+        }
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.get(&3), Some(&30));
+        assert_eq!(tree.insert(3, 33), Some(30));
+        *tree.get_mut(&3).unwrap() += 1;
+        assert_eq!(tree.get(&3), Some(&34));
+        assert_eq!(tree.floor(&6).map(|(k, _)| *k), Some(5));
+        assert_eq!(tree.min(), Some((&1, &10)));
+        assert_eq!(tree.max(), Some((&9, &90)));
+
+        assert_eq!(tree.remove(&5), Some(50));
+        assert_eq!(tree.remove(&5), None);
+        let keys: Vec<u64> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, alloc::vec![1, 2, 3, 7, 8, 9]);
+
+        let tail: Vec<u64> = tree.iter_from(&4).map(|(k, _)| *k).collect();
+        assert_eq!(tail, alloc::vec![7, 8, 9]);
+
+        assert_eq!(tree.pop_first(), Some((1, 10)));
+        assert_eq!(tree.len(), 5);
+        check_tree(&tree);
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let mut tree = RbTree::new();
+        for k in (0..50u64).map(|k| k * 2) {
+            tree.insert(k, ());
+        }
+        let keys = |it: Range<'_, u64, ()>| it.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        assert_eq!(keys(tree.range(10..16)), [10, 12, 14]);
+        assert_eq!(keys(tree.range(9..=16)), [10, 12, 14, 16]);
+        assert_eq!(keys(tree.range(95..)), [96, 98]);
+        assert_eq!(keys(tree.range(..3)), [0, 2]);
+        assert_eq!(
+            keys(tree.range((Bound::Excluded(10), Bound::Excluded(14)))),
+            [12]
+        );
+        assert!(keys(tree.range(11..12)).is_empty());
+        assert!(keys(tree.range(200..)).is_empty());
+        assert_eq!(tree.range(..).count(), 50);
+    }
+
+    #[test]
+    fn test_shuffled_insert_keeps_order_and_balance() {
+        const COUNT: u64 = 2000;
+        let mut tree = RbTree::new();
+        for (i, k) in shuffled(COUNT, 0x9E37_79B9_7F4A_7C15)
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(tree.insert(k, k + 1), None);
+            if i % 128 == 0 {
+                check_tree(&tree);
+            }
+        }
+        check_tree(&tree);
+        assert!(tree.iter().map(|(k, _)| *k).eq(0..COUNT));
+        // LLRB: altura <= 2 * log2(n + 1) ~ 22
+        assert!(tree.height() <= 22);
+    }
+
+    #[test]
+    fn test_random_insert_remove_keeps_invariants() {
+        let mut tree = RbTree::new();
+        let mut present = alloc::vec![false; 512];
+        let ops = shuffled(4096, 0xDEAD_BEEF);
+        for (i, &op) in ops.iter().enumerate() {
+            let key = op % 512;
+            if op % 3 == 0 {
+                assert_eq!(tree.remove(&key).is_some(), present[key as usize]);
+                present[key as usize] = false;
+            } else {
+                tree.insert(key, i);
+                present[key as usize] = true;
+            }
+            if i % 64 == 0 {
+                check_tree(&tree);
+            }
+        }
+        check_tree(&tree);
+
+        let expected: Vec<u64> = (0..512).filter(|&k| present[k as usize]).collect();
+        let keys: Vec<u64> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, expected);
+
+        while let Some((key, _)) = tree.pop_first() {
+            assert!(present[key as usize]);
+            check_tree(&tree);
+        }
+        assert!(tree.is_empty());
+    }
 }
@@ -34,7 +34,7 @@
 //! - **Zero Trust**: Módulos são supervisionados, mesmo em Ring 0
 //! - **No Legacy**: Sem compatibilidade com padrões antigos
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(abi_x86_interrupt)]
 #![feature(alloc_error_handler)]
 #![feature(raw_ref_op)]
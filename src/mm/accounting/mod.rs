@@ -12,7 +12,7 @@
 //!
 //! 1. Associar cada alocação a um subsistema
 //! 2. Definir quotas (soft/hard limits)
-//! 3. Gerar relatórios de uso
+//! 3. Gerar relatórios de uso (com as violações de quota de cada um)
 //!
 //! ## 🏗️ Arquitetura
 //!
@@ -36,7 +36,9 @@
 pub mod stats;
 pub mod subsystem;
 
-pub use stats::{get_stats, print_memory_report, SubsystemStats};
+pub use stats::{
+    get_stats, init, memory_report, print_memory_report, quota_violations, SubsystemStats,
+};
 pub use subsystem::{get_current_subsystem, set_current_subsystem, Subsystem};
 
 // =============================================================================
@@ -44,9 +46,20 @@ pub use subsystem::{get_current_subsystem, set_current_subsystem, Subsystem};
 // =============================================================================
 
 /// Registra alocação no subsistema atual
+///
+/// Negada por quota, avisa com o subsistema, o uso e o limite (em bytes),
+/// para não confundir com falta de memória física. Roda dentro do heap:
+/// não aloca.
 pub fn record_alloc(bytes: usize) -> bool {
     let subsys = get_current_subsystem();
-    get_stats(subsys).record_alloc(bytes)
+    let stats = get_stats(subsys);
+    if stats.record_alloc(bytes) {
+        return true;
+    }
+    crate::kwarn!("(Accounting) Quota estourada em", subsys.name());
+    crate::kwarn!("(Accounting) Uso:", stats.allocated_bytes());
+    crate::kwarn!("(Accounting) Limite:", stats.quota_bytes());
+    false
 }
 
 /// Registra liberação no subsistema atual
@@ -74,8 +74,15 @@ impl SubsystemStats {
     }
 
     /// Registra liberação
+    ///
+    /// A liberação cai no subsistema atual, que pode não ser o que alocou:
+    /// o contador satura em zero em vez de dar a volta.
     pub fn record_free(&self, bytes: usize) {
-        self.allocated.fetch_sub(bytes, Ordering::Relaxed);
+        let _ = self
+            .allocated
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(current.saturating_sub(bytes))
+            });
         self.free_count.fetch_add(1, Ordering::Relaxed);
     }
 
@@ -132,6 +139,12 @@ impl SubsystemStats {
     }
 }
 
+impl Default for SubsystemStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // =============================================================================
 // ARMAZENAMENTO GLOBAL
 // =============================================================================
@@ -141,6 +154,7 @@ const MAX_SUBSYSTEMS: usize = 256;
 
 /// Estatísticas globais por subsistema
 static STATS: [SubsystemStats; MAX_SUBSYSTEMS] = {
+    #[allow(clippy::declare_interior_mutable_const)]
     const EMPTY: SubsystemStats = SubsystemStats::new();
     [EMPTY; MAX_SUBSYSTEMS]
 };
@@ -175,54 +189,104 @@ pub fn get_stats(subsys: Subsystem) -> &'static SubsystemStats {
 // RELATÓRIOS
 // =============================================================================
 
-/// Imprime relatório de uso de memória
-pub fn print_memory_report() {
-    crate::kinfo!("╔═══════════════════════════════════════════════════════════════╗");
-    crate::kinfo!("║              RELATÓRIO DE MEMÓRIA POR SUBSISTEMA              ║");
-    crate::kinfo!("╠════════════════╦════════════╦════════════╦════════════╦═══════╣");
-    crate::kinfo!("║   Subsistema   ║   Alocado  ║    Pico    ║   Quota    ║ Leaks?║");
-    crate::kinfo!("╠════════════════╬════════════╬════════════╬════════════╬═══════╣");
+/// Relatório de uso de memória: uma linha por subsistema com alocações ou
+/// violações de quota (alocado, pico, quota e alocações negadas por ela).
+///
+/// Aloca: não chamar com a quota do subsistema atual estourada.
+pub fn memory_report() -> alloc::string::String {
+    use core::fmt::Write;
+
+    let mut out = alloc::string::String::new();
+    let _ = writeln!(
+        out,
+        "╔═══════════════════════════════════════════════════════════════════════════╗"
+    );
+    let _ = writeln!(
+        out,
+        "║                    RELATÓRIO DE MEMÓRIA POR SUBSISTEMA                    ║"
+    );
+    let _ = writeln!(
+        out,
+        "╠════════════════╦════════════╦════════════╦════════════╦═══════════╦═══════╣"
+    );
+    let _ = writeln!(
+        out,
+        "║   Subsistema   ║  Alocado   ║    Pico    ║   Quota    ║ Violações ║ Leaks?║"
+    );
+    let _ = writeln!(
+        out,
+        "╠════════════════╬════════════╬════════════╬════════════╬═══════════╬═══════╣"
+    );
 
     let mut total_allocated = 0usize;
     let mut total_peak = 0usize;
+    let mut total_violations = 0usize;
 
     for subsys in Subsystem::all() {
         let stats = get_stats(*subsys);
+        let violations = stats.denials();
+        if stats.allocation_count() == 0 && violations == 0 {
+            continue;
+        }
+
         let allocated = stats.allocated_bytes();
         let peak = stats.peak_bytes();
         let quota = stats.quota_bytes();
-        let has_leak = stats.has_probable_leak();
-
-        if stats.allocation_count() > 0 {
-            total_allocated += allocated;
-            total_peak = total_peak.max(peak);
-
-            let quota_str = if quota > 0 {
-                format_bytes(quota)
-            } else {
-                "∞".into()
-            };
-
-            let leak_str = if has_leak { "⚠" } else { " " };
-
-            crate::kinfo!(
-                "║ {:14} ║ {:>10} ║ {:>10} ║ {:>10} ║   {}   ║",
-                subsys.name(),
-                format_bytes(allocated),
-                format_bytes(peak),
-                quota_str,
-                leak_str
-            );
-        }
+        total_allocated += allocated;
+        total_peak = total_peak.max(peak);
+        total_violations += violations;
+
+        let quota_str = if quota > 0 {
+            format_bytes(quota)
+        } else {
+            "∞".into()
+        };
+        let leak_str = if stats.has_probable_leak() {
+            "⚠"
+        } else {
+            " "
+        };
+
+        let _ = writeln!(
+            out,
+            "║ {:14} ║ {:>10} ║ {:>10} ║ {:>10} ║ {:>9} ║   {}   ║",
+            subsys.name(),
+            format_bytes(allocated),
+            format_bytes(peak),
+            quota_str,
+            violations,
+            leak_str
+        );
     }
 
-    crate::kinfo!("╠════════════════╬════════════╬════════════╬════════════╬═══════╣");
-    crate::kinfo!(
-        "║     TOTAL      ║ {:>10} ║ {:>10} ║     -      ║       ║",
+    let _ = writeln!(
+        out,
+        "╠════════════════╬════════════╬════════════╬════════════╬═══════════╬═══════╣"
+    );
+    let _ = writeln!(
+        out,
+        "║     TOTAL      ║ {:>10} ║ {:>10} ║     -      ║ {:>9} ║       ║",
         format_bytes(total_allocated),
-        format_bytes(total_peak)
+        format_bytes(total_peak),
+        total_violations
+    );
+    let _ = writeln!(
+        out,
+        "╚════════════════╩════════════╩════════════╩════════════╩═══════════╩═══════╝"
     );
-    crate::kinfo!("╚════════════════╩════════════╩════════════╩════════════╩═══════╝");
+    out
+}
+
+/// Imprime o [`memory_report`]
+pub fn print_memory_report() {
+    for line in memory_report().lines() {
+        crate::kinfo!(line);
+    }
+}
+
+/// Alocações negadas por quota, somando todos os subsistemas
+pub fn quota_violations() -> usize {
+    STATS.iter().map(SubsystemStats::denials).sum()
 }
 
 /// Imprime resumo curto
@@ -238,17 +302,15 @@ pub fn print_summary() {
         }
     }
 
-    crate::kinfo!(
-        "(Accounting) {} subsistemas ativos, {} bytes alocados",
+    crate::kinfo!(&alloc::format!(
+        "(Accounting) {} subsistemas ativos, {} alocados",
         with_usage,
         format_bytes(total)
-    );
+    ));
 }
 
 /// Formata bytes para exibição legível
 fn format_bytes(bytes: usize) -> alloc::string::String {
-    use alloc::string::ToString;
-
     if bytes >= 1024 * 1024 * 1024 {
         let gb = bytes / (1024 * 1024 * 1024);
         let mb = (bytes % (1024 * 1024 * 1024)) / (1024 * 1024);
@@ -276,13 +338,13 @@ pub fn check_for_leaks() {
     for subsys in Subsystem::all() {
         let stats = get_stats(*subsys);
         if stats.has_probable_leak() {
-            crate::kwarn!(
+            crate::kwarn!(&alloc::format!(
                 "(Accounting) Possível leak em {}: {} allocs, {} frees, {} bytes",
                 subsys.name(),
                 stats.allocation_count(),
                 stats.free_count(),
                 stats.allocated_bytes()
-            );
+            ));
             found_leaks = true;
         }
     }
@@ -295,11 +357,11 @@ pub fn check_for_leaks() {
 /// Reseta contadores (para testes)
 #[cfg(debug_assertions)]
 pub fn reset_all() {
-    for i in 0..MAX_SUBSYSTEMS {
-        STATS[i].allocated.store(0, Ordering::Relaxed);
-        STATS[i].alloc_count.store(0, Ordering::Relaxed);
-        STATS[i].free_count.store(0, Ordering::Relaxed);
-        STATS[i].peak.store(0, Ordering::Relaxed);
-        STATS[i].quota_denials.store(0, Ordering::Relaxed);
+    for stats in STATS.iter() {
+        stats.allocated.store(0, Ordering::Relaxed);
+        stats.alloc_count.store(0, Ordering::Relaxed);
+        stats.free_count.store(0, Ordering::Relaxed);
+        stats.peak.store(0, Ordering::Relaxed);
+        stats.quota_denials.store(0, Ordering::Relaxed);
     }
 }
@@ -86,6 +86,10 @@ impl VirtAddr {
         Self(self.0 + count)
     }
 
+    pub fn is_aligned(&self, align: u64) -> bool {
+        self.0 % align == 0
+    }
+
     pub fn align_down(&self, align: u64) -> Self {
         Self(self.0 & !(align - 1))
     }
@@ -108,8 +112,10 @@ impl VirtAddr {
 /// Faz panic se `phys >= PHYS_IDENTITY_LIMIT` (não temos mapeamento)
 #[inline(always)]
 pub unsafe fn phys_to_virt<T>(phys: u64) -> *mut T {
-    // Se o HHDM estiver inicializado, usa o offset dinâmico
-    if crate::mm::hhdm::is_initialized() {
+    // Se o HHDM estiver inicializado, usa o offset dinâmico. Nos testes de
+    // host nunca: as tabelas "físicas" estão na memória do processo, e o
+    // HHDM global pode ter sido inicializado por outro teste.
+    if crate::mm::hhdm::is_initialized() && !cfg!(test) {
         crate::mm::hhdm::phys_to_virt(phys)
     } else {
         // Fallback para identity map durante early boot (Ignite garante isso)
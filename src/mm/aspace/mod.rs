@@ -1,17 +1,20 @@
 //! # Address Space Manager
 
 pub mod heap;
-pub mod rbtree;
 pub mod shared;
 pub mod vma;
 
 extern crate alloc;
 
-use crate::mm::{PhysAddr, VirtAddr};
+use crate::klib::tree::RbTree;
+use crate::mm::{MapFlags, PhysAddr, VirtAddr};
 use crate::sync::Spinlock;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, Ordering};
-use vma::{MemoryIntent, Protection, VmaFlags, VMA};
+use vma::{MemoryIntent, Protection, VmaBacking, VmaFlags, VMA};
+
+/// VMAs indexadas pelo endereço inicial
+type VmaTree = RbTree<VirtAddr, VMA>;
 
 pub type Pid = u64;
 
@@ -39,7 +42,7 @@ pub struct AddressSpaceStats {
 
 pub struct AddressSpace {
     pml4: PhysAddr,
-    vmas: Vec<VMA>,
+    vmas: VmaTree,
     owner: Pid,
     stats: AddressSpaceStats,
     pcid: u16,
@@ -50,13 +53,12 @@ impl AddressSpace {
     pub fn new(owner: Pid) -> ASpaceResult<Self> {
         let pml4_phys = {
             let mut pmm = crate::mm::pmm::FRAME_ALLOCATOR.lock();
-            crate::mm::vmm::mapper::create_new_p4(&mut *pmm)
-                .map_err(|_| ASpaceError::OutOfMemory)?
+            crate::mm::vmm::mapper::create_new_p4(&mut pmm).map_err(|_| ASpaceError::OutOfMemory)?
         };
 
         Ok(Self {
             pml4: PhysAddr::new(pml4_phys),
-            vmas: Vec::new(),
+            vmas: VmaTree::new(),
             owner,
             stats: AddressSpaceStats::default(),
             pcid: 0,
@@ -67,6 +69,9 @@ impl AddressSpace {
     pub fn cr3(&self) -> u64 {
         self.pml4.as_u64()
     }
+    pub fn stats(&self) -> &AddressSpaceStats {
+        &self.stats
+    }
     pub fn owner(&self) -> Pid {
         self.owner
     }
@@ -78,6 +83,19 @@ impl AddressSpace {
         prot: Protection,
         flags: VmaFlags,
         intent: MemoryIntent,
+    ) -> ASpaceResult<VirtAddr> {
+        self.map_region_backed(hint, size, prot, flags, intent, VmaBacking::Anonymous)
+    }
+
+    /// Como `map_region`, mas com um backing explícito (ex.: segmento de arquivo)
+    pub fn map_region_backed(
+        &mut self,
+        hint: Option<VirtAddr>,
+        size: usize,
+        prot: Protection,
+        flags: VmaFlags,
+        intent: MemoryIntent,
+        backing: VmaBacking,
     ) -> ASpaceResult<VirtAddr> {
         if size == 0 {
             return Err(ASpaceError::InvalidSize);
@@ -107,53 +125,145 @@ impl AddressSpace {
         // This call will either confirm target_addr is free or return an error
         let addr = self.find_free_region(Some(target_addr), target_size)?;
 
-        // Criar VMA
-        let vma = VMA::new(addr, addr.offset(target_size as u64), prot, flags, intent);
+        // Criar VMA (mesclando com vizinhas compatíveis)
+        let vma = VMA::new(addr, addr.offset(target_size as u64), prot, flags, intent)
+            .with_backing(backing);
 
-        self.vmas.push(vma);
-        self.vmas.sort_by_key(|v| v.start);
-        self.stats.vma_count += 1;
+        insert_merged(&mut self.vmas, vma);
+        self.stats.vma_count = self.vmas.len() as u64;
         self.stats.mapped_pages += target_size as u64 / 4096;
         self.tlb_gen.fetch_add(1, Ordering::Release);
 
         Ok(addr)
     }
 
-    pub fn unmap_region(&mut self, addr: VirtAddr, _size: usize) -> ASpaceResult<()> {
-        let idx = self
-            .vmas
-            .iter()
-            .position(|v| v.start == addr)
-            .ok_or(ASpaceError::RegionNotFound)?;
-        let vma = self.vmas.remove(idx);
-        self.stats.vma_count = self.stats.vma_count.saturating_sub(1);
+    /// Remove o intervalo `[addr, addr + size)` das VMAs e desmapeia suas
+    /// páginas.
+    ///
+    /// VMAs totalmente cobertas são removidas, parcialmente cobertas são
+    /// truncadas e uma VMA que contém o intervalo no meio é dividida em duas.
+    /// Os frames das páginas perdem uma referência (ver [`release_frame`]) e
+    /// as tabelas de página que ficarem vazias voltam ao PMM.
+    pub fn unmap_region(&mut self, addr: VirtAddr, size: usize) -> ASpaceResult<()> {
+        use crate::mm::vmm::mapper::{unmap_range_in_p4, PTE_ADDR_MASK};
+
+        if size == 0 {
+            return Err(ASpaceError::InvalidSize);
+        }
+        let page_size = crate::mm::config::PAGE_SIZE as u64;
+        let start = addr.align_down(page_size);
+        let end = VirtAddr::new(addr.as_u64() + size as u64).align_up(page_size);
+
+        let removed = punch_hole(&mut self.vmas, start, end);
+        if removed == 0 {
+            return Err(ASpaceError::RegionNotFound);
+        }
+        unmap_range_in_p4(self.cr3(), start.as_u64(), end.as_u64(), &mut |_, pte| {
+            release_frame(PhysAddr::new(pte & PTE_ADDR_MASK))
+        })
+        .map_err(|_| ASpaceError::OutOfMemory)?;
+
+        self.stats.vma_count = self.vmas.len() as u64;
+        self.stats.mapped_pages = self.stats.mapped_pages.saturating_sub(removed / page_size);
+        self.tlb_gen.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Muda para `prot` a proteção de `[addr, addr + size)` e das páginas já
+    /// mapeadas nele.
+    ///
+    /// O intervalo pode atravessar várias VMAs, mas tem de estar inteiro
+    /// coberto por elas (`NotMapped` senão, sem mudar nada). VMAs parcialmente
+    /// cobertas são divididas e os pedaços com a proteção nova se fundem com
+    /// vizinhos compatíveis. As PTEs presentes são reescritas (ver
+    /// [`protect_pte`]) e o TLB é invalidado em todas as CPUs.
+    pub fn protect_region(
+        &mut self,
+        addr: VirtAddr,
+        size: usize,
+        prot: Protection,
+    ) -> ASpaceResult<()> {
+        use crate::mm::pfm::zero::is_zero_page;
+        use crate::mm::vmm::mapper::{protect_range_in_p4, PTE_ADDR_MASK};
+
+        if size == 0 {
+            return Err(ASpaceError::InvalidSize);
+        }
+        let page_size = crate::mm::config::PAGE_SIZE as u64;
+        let start = addr.align_down(page_size);
+        let end = VirtAddr::new(addr.as_u64() + size as u64).align_up(page_size);
+
+        if !protect_range(&mut self.vmas, start, end, prot) {
+            return Err(ASpaceError::NotMapped);
+        }
+        protect_range_in_p4(self.cr3(), start.as_u64(), end.as_u64(), &mut |pte| {
+            protect_pte(pte, prot, is_zero_page(PhysAddr::new(pte & PTE_ADDR_MASK)))
+        })
+        .map_err(|_| ASpaceError::OutOfMemory)?;
+        crate::arch::platform::smp::invalidate_range(start.as_u64(), end.as_u64());
+
+        self.stats.vma_count = self.vmas.len() as u64;
         self.tlb_gen.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
     pub fn find_vma(&self, addr: VirtAddr) -> Option<VMA> {
         self.vmas
-            .iter()
-            .find(|v| addr >= v.start && addr < v.end)
+            .floor(&addr)
+            .map(|(_, v)| v)
+            .filter(|v| v.contains(addr))
             .cloned()
     }
 
+    /// Estende para baixo a stack (`GROWS_DOWN`) logo acima de `addr`.
+    ///
+    /// `addr` tem que estar a até [`STACK_GROWTH_WINDOW`] da base da stack e a
+    /// stack estendida não pode passar de [`STACK_MAX_SIZE`]. Retorna a VMA
+    /// já estendida; as páginas novas ficam para a resolução de faltas.
+    ///
+    /// [`STACK_GROWTH_WINDOW`]: crate::mm::config::STACK_GROWTH_WINDOW
+    /// [`STACK_MAX_SIZE`]: crate::mm::config::STACK_MAX_SIZE
+    pub fn grow_stack(&mut self, addr: VirtAddr) -> ASpaceResult<VMA> {
+        use crate::mm::config::{PAGE_SIZE, STACK_GROWTH_WINDOW, STACK_MAX_SIZE};
+
+        let page = addr.align_down(PAGE_SIZE as u64);
+        let old_size = self
+            .vmas
+            .iter_from(&page)
+            .next()
+            .map_or(0, |(_, vma)| vma.size());
+        let vma = grow_down(
+            &mut self.vmas,
+            page,
+            STACK_GROWTH_WINDOW as u64,
+            STACK_MAX_SIZE as u64,
+        )
+        .ok_or(ASpaceError::RegionNotFound)?;
+
+        self.stats.mapped_pages += (vma.size() - old_size) / PAGE_SIZE as u64;
+        self.tlb_gen.fetch_add(1, Ordering::Release);
+        Ok(vma)
+    }
+
     fn find_free_region(&self, hint: Option<VirtAddr>, size: usize) -> ASpaceResult<VirtAddr> {
         if let Some(addr) = hint {
-            // Verificar se o endereço solicitado está livre
-            let end = addr.as_u64() + size as u64;
-            let mut conflict = false;
-            for vma in self.vmas.iter() {
-                if (addr.as_u64() < vma.end.as_u64()) && (end > vma.start.as_u64()) {
+            // Verificar se o endereço solicitado está livre: só a VMA anterior
+            // e as que começam dentro do intervalo podem conflitar.
+            let end = VirtAddr::new(addr.as_u64() + size as u64);
+            let before = self.vmas.floor(&addr).map(|(_, v)| v);
+            let after = self.vmas.iter_from(&addr).next().map(|(_, v)| v);
+
+            let conflict = before
+                .filter(|v| v.end > addr)
+                .or(after.filter(|v| v.start < end));
+
+            match conflict {
+                None => return Ok(addr),
+                Some(vma) => {
                     crate::kdebug!("(ASpace) Conflict detected with VMA:", vma.start.as_u64());
                     crate::kdebug!("(ASpace) VMA end:", vma.end.as_u64());
-                    conflict = true;
-                    break;
                 }
             }
-            if !conflict {
-                return Ok(addr);
-            }
             crate::kerror!("(ASpace) Sobreposicao de regiao para hint:", addr.as_u64());
             return Err(ASpaceError::RegionOverlap);
         }
@@ -161,16 +271,19 @@ impl AddressSpace {
         // Se não houver hint, procurar o primeiro gap disponível após 4GB
         // NOTA: Começamos em 4GB para evitar conflitos com a região do binário ELF (que costuma ficar em 4MB-1GB)
         let mut candidate = VirtAddr::new(0x0000_0001_0000_0000);
+        if let Some((_, prev)) = self.vmas.floor(&candidate) {
+            if prev.end > candidate {
+                candidate = prev.end;
+            }
+        }
 
-        // Como vmas está ordenado por start, podemos percorrer linearmente
-        for vma in self.vmas.iter() {
-            if vma.start >= candidate {
-                let gap_size = vma.start.as_u64() - candidate.as_u64();
-                if gap_size >= size as u64 {
-                    return Ok(candidate);
-                }
-                candidate = vma.end;
-            } else if vma.end > candidate {
+        // A árvore itera em ordem de start: percorrer só a partir do candidato
+        for (_, vma) in self.vmas.iter_from(&candidate) {
+            let gap_size = vma.start.as_u64() - candidate.as_u64();
+            if gap_size >= size as u64 {
+                return Ok(candidate);
+            }
+            if vma.end > candidate {
                 candidate = vma.end;
             }
         }
@@ -191,12 +304,565 @@ impl AddressSpace {
     pub unsafe fn activate(&self) {
         crate::arch::Cpu::write_cr3(self.pml4.as_u64());
     }
+
+    /// Desmapeia todas as páginas das VMAs, soltando a referência de cada
+    /// frame. Usado para desfazer um `fork` que falhou no meio: o `Drop` só
+    /// devolve a PML4.
+    fn release_user_pages(&mut self) {
+        use crate::mm::vmm::mapper::{unmap_range_in_p4, PTE_ADDR_MASK};
+
+        let cr3 = self.cr3();
+        for (_, vma) in self.vmas.iter() {
+            let _ = unmap_range_in_p4(cr3, vma.start.as_u64(), vma.end.as_u64(), &mut |_, pte| {
+                release_frame(PhysAddr::new(pte & PTE_ADDR_MASK))
+            });
+        }
+        self.vmas = VmaTree::new();
+    }
+
+    /// Duplica o address space com semântica Copy-on-Write.
+    ///
+    /// Todas as VMAs são clonadas. Páginas residentes de VMAs privadas graváveis
+    /// passam a ser somente-leitura (marcadas com `PTE_COW`) no pai e no filho;
+    /// VMAs `SHARED` mantêm o mapeamento original. Cada frame compartilhado
+    /// recebe uma referência extra no PFM.
+    ///
+    /// Se alguma PTE não puder ser escrita, o filho parcial é desmontado
+    /// (soltando as referências já tomadas) e o erro é devolvido.
+    pub fn fork(&self, new_owner: Pid) -> ASpaceResult<AddressSpace> {
+        use crate::mm::vmm::mapper::{
+            read_pte_in_p4, write_pte_in_p4, PTE_ADDR_MASK, PTE_COW, PTE_WRITABLE,
+        };
+
+        let mut child = AddressSpace::new(new_owner)?;
+        child.vmas = self.vmas.clone();
+        child.stats = self.stats.clone();
+
+        let page_size = crate::mm::config::PAGE_SIZE as u64;
+        let parent_p4 = self.pml4.as_u64();
+        let child_p4 = child.pml4.as_u64();
+        let mut shared_pages = 0u64;
+
+        for (_, vma) in self.vmas.iter() {
+            let cow = !vma.flags.contains(VmaFlags::SHARED) && vma.protection.can_write();
+            if cow {
+                if let Some(child_vma) = child.vmas.get_mut(&vma.start) {
+                    child_vma.flags.insert(VmaFlags::COW);
+                }
+            }
+
+            let mut vaddr = vma.start.as_u64();
+            while vaddr < vma.end.as_u64() {
+                if let Some(mut pte) = read_pte_in_p4(parent_p4, vaddr) {
+                    if cow {
+                        pte = (pte & !PTE_WRITABLE) | PTE_COW;
+                        // SAFETY: a página está presente no pai; só removemos o bit W
+                        if unsafe { write_pte_in_p4(parent_p4, vaddr, pte) }.is_err() {
+                            child.release_user_pages();
+                            return Err(ASpaceError::OutOfMemory);
+                        }
+                    }
+
+                    // Criar tabelas intermediárias no filho e copiar a PTE
+                    let frame = pte & PTE_ADDR_MASK;
+                    let mut flags = MapFlags::PRESENT | MapFlags::USER;
+                    if vma.protection.can_exec() {
+                        flags |= MapFlags::EXECUTABLE;
+                    }
+                    let mapped = {
+                        let mut pmm = crate::mm::pmm::FRAME_ALLOCATOR.lock();
+                        crate::mm::vmm::mapper::map_page_in_target_p4(
+                            child_p4, vaddr, frame, flags, &mut pmm,
+                        )
+                    };
+                    if mapped.is_err() {
+                        child.release_user_pages();
+                        return Err(ASpaceError::OutOfMemory);
+                    }
+
+                    // A PTE do filho já aponta para o frame: ela é uma
+                    // referência a mais. A página zero não tem dono e fica
+                    // fora da contagem; um frame fora do PFM não tem contagem.
+                    let frame = PhysAddr::new(frame);
+                    if !crate::mm::pfm::zero::is_zero_page(frame) {
+                        let _ = crate::mm::pfm::inc_ref(frame);
+                    }
+
+                    // SAFETY: tabelas criadas acima; mesmo frame do pai
+                    if unsafe { write_pte_in_p4(child_p4, vaddr, pte) }.is_err() {
+                        child.release_user_pages();
+                        return Err(ASpaceError::OutOfMemory);
+                    }
+                    shared_pages += 1;
+                }
+                vaddr += page_size;
+            }
+        }
+
+        child.stats.shared_pages += shared_pages;
+
+        // O pai perdeu permissões de escrita: invalidar o TLB se estiver ativo
+        if crate::mm::vmm::mapper::read_cr3() == parent_p4 {
+            crate::mm::vmm::tlb::flush_all();
+        }
+        self.tlb_gen.fetch_add(1, Ordering::Release);
+
+        crate::kdebug!("(ASpace) fork: paginas compartilhadas=", shared_pages);
+        Ok(child)
+    }
+}
+
+// =============================================================================
+// MANIPULAÇÃO DA ÁRVORE DE VMAs
+// =============================================================================
+
+/// Duas VMAs anônimas podem ser fundidas se forem adjacentes e semanticamente idênticas
+fn can_merge(a: &VMA, b: &VMA) -> bool {
+    a.end == b.start
+        && a.backing.is_anonymous()
+        && b.backing.is_anonymous()
+        && a.protection == b.protection
+        && a.flags == b.flags
+        && a.intent == b.intent
+}
+
+/// Insere `vma` na árvore, fundindo com vizinhas compatíveis
+fn insert_merged(vmas: &mut VmaTree, mut vma: VMA) {
+    // Vizinha à direita
+    if vmas
+        .get(&vma.end)
+        .map_or(false, |next| can_merge(&vma, next))
+    {
+        if let Some(next) = vmas.remove(&vma.end) {
+            vma.end = next.end;
+        }
+    }
+
+    // Vizinha à esquerda
+    let prev_key = vmas
+        .floor(&vma.start)
+        .filter(|(_, prev)| can_merge(prev, &vma))
+        .map(|(k, _)| *k);
+    if let Some(key) = prev_key {
+        if let Some(prev) = vmas.get_mut(&key) {
+            prev.end = vma.end;
+        }
+        return;
+    }
+
+    vmas.insert(vma.start, vma);
+}
+
+/// Remove `[start, end)` da árvore. Retorna quantos bytes deixaram de ser mapeados.
+///
+/// VMAs parcialmente cobertas são truncadas; uma VMA que contém o intervalo
+/// no meio vira duas.
+fn punch_hole(vmas: &mut VmaTree, start: VirtAddr, end: VirtAddr) -> u64 {
+    let mut hit: Vec<VMA> = Vec::new();
+
+    if let Some((key, prev)) = vmas.floor(&start) {
+        if *key < start && prev.end > start {
+            hit.push(prev.clone());
+        }
+    }
+    for (key, vma) in vmas.iter_from(&start) {
+        if *key >= end {
+            break;
+        }
+        hit.push(vma.clone());
+    }
+
+    let mut removed = 0u64;
+    for vma in hit {
+        vmas.remove(&vma.start);
+
+        let cut_start = core::cmp::max(vma.start, start);
+        let cut_end = core::cmp::min(vma.end, end);
+        removed += cut_end.as_u64() - cut_start.as_u64();
+
+        if vma.start < start {
+            let mut head = vma.clone();
+            head.end = start;
+            vmas.insert(head.start, head);
+        }
+        if vma.end > end {
+            let mut tail = vma;
+            tail.start = end;
+            vmas.insert(tail.start, tail);
+        }
+    }
+
+    removed
+}
+
+/// Aplica `prot` a `[start, end)`, dividindo as VMAs nas bordas. Retorna
+/// `false`, sem tocar na árvore, se algum trecho do intervalo não tiver VMA.
+fn protect_range(vmas: &mut VmaTree, start: VirtAddr, end: VirtAddr, prot: Protection) -> bool {
+    let mut hit: Vec<VMA> = Vec::new();
+
+    if let Some((key, prev)) = vmas.floor(&start) {
+        if *key < start && prev.end > start {
+            hit.push(prev.clone());
+        }
+    }
+    for (key, vma) in vmas.iter_from(&start) {
+        if *key >= end {
+            break;
+        }
+        hit.push(vma.clone());
+    }
+
+    // Cobertura contínua, sem buracos entre as VMAs
+    let mut covered = start;
+    for vma in &hit {
+        if vma.start > covered {
+            return false;
+        }
+        covered = vma.end;
+    }
+    if covered < end {
+        return false;
+    }
+
+    let mut changed = Vec::new();
+    for vma in hit {
+        vmas.remove(&vma.start);
+
+        if vma.start < start {
+            let mut head = vma.clone();
+            head.end = start;
+            vmas.insert(head.start, head);
+        }
+        if vma.end > end {
+            let mut tail = vma.clone();
+            tail.start = end;
+            vmas.insert(tail.start, tail);
+        }
+        let mut mid = vma;
+        mid.start = core::cmp::max(mid.start, start);
+        mid.end = core::cmp::min(mid.end, end);
+        mid.protection = prot;
+        changed.push(mid.start);
+        vmas.insert(mid.start, mid);
+    }
+
+    // Só agora, com todos os pedaços no lugar, fundir os que mudaram
+    for key in changed {
+        if let Some(vma) = vmas.remove(&key) {
+            insert_merged(vmas, vma);
+        }
+    }
+    true
+}
+
+/// PTE `pte` com as permissões de `prot`
+///
+/// W, USER e NX saem de `prot`; o frame e os demais bits ficam. Página
+/// ainda em CoW (ou a página zero, que passa a ser) não ganha W: a escrita
+/// continua passando pela resolução de CoW. `Protection::NONE` tira o
+/// acesso de usuário.
+fn protect_pte(pte: u64, prot: Protection, zero_page: bool) -> u64 {
+    use crate::mm::vmm::mapper::{PTE_COW, PTE_NO_EXEC, PTE_USER, PTE_WRITABLE};
+
+    let mut new = pte & !(PTE_WRITABLE | PTE_USER | PTE_NO_EXEC);
+    if prot != Protection::NONE {
+        new |= PTE_USER;
+    }
+    if !prot.can_exec() {
+        new |= PTE_NO_EXEC;
+    }
+    if prot.can_write() {
+        if zero_page {
+            new |= PTE_COW;
+        } else if pte & PTE_COW == 0 {
+            new |= PTE_WRITABLE;
+        }
+    }
+    new
+}
+
+/// Solta a referência de um frame que saiu das tabelas de página
+///
+/// A página zero nunca é liberada. O resto vai para
+/// [`PageFrameManager::release_mapped`](crate::mm::pfm::PageFrameManager::release_mapped),
+/// que confere e decrementa a contagem sob um único lock; um frame fora do
+/// PFM fica (em vez de arriscar liberar o frame de outro address space).
+fn release_frame(frame: PhysAddr) {
+    if crate::mm::pfm::zero::is_zero_page(frame) {
+        return;
+    }
+    crate::mm::pfm::release_mapped(frame);
+}
+
+/// Move para `page` a base da primeira VMA acima de `page`, se ela for
+/// `GROWS_DOWN`, começar a até `window` bytes de `page` e ficar com no máximo
+/// `max_size` bytes. Retorna a VMA resultante.
+fn grow_down(vmas: &mut VmaTree, page: VirtAddr, window: u64, max_size: u64) -> Option<VMA> {
+    let stack = vmas.iter_from(&page).next().map(|(_, vma)| vma.clone())?;
+    if !stack.flags.contains(VmaFlags::GROWS_DOWN)
+        || stack.start.as_u64() - page.as_u64() > window
+        || stack.end.as_u64() - page.as_u64() > max_size
+    {
+        return None;
+    }
+    // Não invadir a VMA de baixo
+    if vmas.floor(&page).map_or(false, |(_, prev)| prev.end > page) {
+        return None;
+    }
+
+    let mut vma = vmas.remove(&stack.start)?;
+    vma.start = page;
+    vmas.insert(page, vma.clone());
+    Some(vma)
 }
 
 impl Drop for AddressSpace {
     fn drop(&mut self) {
+        // A PML4 identifica o address space no rmap e pode ser reaproveitada
+        crate::mm::pfm::rmap::forget_aspace(self.pml4.as_u64());
         crate::mm::pmm::FRAME_ALLOCATOR
             .lock()
             .deallocate_frame(self.pml4);
     }
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vma(start: u64, end: u64) -> VMA {
+        VMA::new(
+            VirtAddr::new(start),
+            VirtAddr::new(end),
+            Protection::RW,
+            VmaFlags::empty(),
+            MemoryIntent::Data,
+        )
+    }
+
+    fn ranges(vmas: &VmaTree) -> Vec<(u64, u64)> {
+        vmas.iter()
+            .map(|(_, v)| (v.start.as_u64(), v.end.as_u64()))
+            .collect()
+    }
+
+    fn tree(list: &[(u64, u64)]) -> VmaTree {
+        let mut t = VmaTree::new();
+        for &(s, e) in list {
+            t.insert(VirtAddr::new(s), vma(s, e));
+        }
+        t
+    }
+
+    #[test]
+    fn test_punch_middle_splits() {
+        let mut vmas = tree(&[(0x1000, 0x5000)]);
+        let removed = punch_hole(&mut vmas, VirtAddr::new(0x2000), VirtAddr::new(0x3000));
+
+        assert_eq!(removed, 0x1000);
+        assert_eq!(
+            ranges(&vmas),
+            alloc::vec![(0x1000, 0x2000), (0x3000, 0x5000)]
+        );
+    }
+
+    #[test]
+    fn test_punch_prefix_suffix_and_whole() {
+        let mut vmas = tree(&[(0x1000, 0x4000), (0x8000, 0x9000)]);
+
+        punch_hole(&mut vmas, VirtAddr::new(0x0), VirtAddr::new(0x2000));
+        assert_eq!(ranges(&vmas)[0], (0x2000, 0x4000));
+
+        punch_hole(&mut vmas, VirtAddr::new(0x3000), VirtAddr::new(0x5000));
+        assert_eq!(ranges(&vmas)[0], (0x2000, 0x3000));
+
+        punch_hole(&mut vmas, VirtAddr::new(0x8000), VirtAddr::new(0x9000));
+        assert_eq!(vmas.len(), 1);
+
+        assert_eq!(
+            punch_hole(&mut vmas, VirtAddr::new(0xA000), VirtAddr::new(0xB000)),
+            0
+        );
+    }
+
+    fn protections(vmas: &VmaTree) -> Vec<Protection> {
+        vmas.iter().map(|(_, v)| v.protection).collect()
+    }
+
+    #[test]
+    fn test_protect_splits_and_merges() {
+        let mut vmas = tree(&[(0x1000, 0x4000), (0x4000, 0x8000)]);
+
+        // Atravessa as duas VMAs: cinco pedaços viram três
+        assert!(protect_range(
+            &mut vmas,
+            VirtAddr::new(0x2000),
+            VirtAddr::new(0x6000),
+            Protection::READ
+        ));
+        assert_eq!(
+            ranges(&vmas),
+            alloc::vec![(0x1000, 0x2000), (0x2000, 0x6000), (0x6000, 0x8000)]
+        );
+        assert_eq!(
+            protections(&vmas),
+            alloc::vec![Protection::RW, Protection::READ, Protection::RW]
+        );
+
+        // Volta a RW: tudo funde de novo
+        assert!(protect_range(
+            &mut vmas,
+            VirtAddr::new(0x2000),
+            VirtAddr::new(0x6000),
+            Protection::RW
+        ));
+        assert_eq!(ranges(&vmas), alloc::vec![(0x1000, 0x8000)]);
+    }
+
+    #[test]
+    fn test_protect_uncovered_range() {
+        let mut vmas = tree(&[(0x1000, 0x3000), (0x4000, 0x6000)]);
+
+        // Buraco entre as VMAs, além do fim e antes do início
+        for (start, end) in [(0x2000, 0x5000), (0x5000, 0x7000), (0x0, 0x2000)] {
+            assert!(!protect_range(
+                &mut vmas,
+                VirtAddr::new(start),
+                VirtAddr::new(end),
+                Protection::READ
+            ));
+        }
+        assert_eq!(
+            ranges(&vmas),
+            alloc::vec![(0x1000, 0x3000), (0x4000, 0x6000)]
+        );
+        assert_eq!(protections(&vmas), alloc::vec![Protection::RW; 2]);
+    }
+
+    #[test]
+    fn test_protect_pte() {
+        use crate::mm::vmm::mapper::{PTE_COW, PTE_NO_EXEC, PTE_USER, PTE_WRITABLE};
+        let pte = 0x1234_5000 | 1 | PTE_USER | PTE_WRITABLE | PTE_NO_EXEC;
+
+        // RW -> RX: perde W e NX, frame intacto
+        let rx = protect_pte(pte, Protection::RX, false);
+        assert_eq!(rx, 0x1234_5000 | 1 | PTE_USER);
+        assert_eq!(
+            protect_pte(rx, Protection::RW, false),
+            pte,
+            "RX -> RW não restaurou a PTE"
+        );
+
+        // Página em CoW e página zero não ganham W
+        let cow = (pte & !PTE_WRITABLE) | PTE_COW;
+        assert_eq!(protect_pte(cow, Protection::RW, false), cow);
+        let zero = pte & !PTE_WRITABLE;
+        assert_eq!(protect_pte(zero, Protection::RW, true), zero | PTE_COW);
+
+        assert_eq!(protect_pte(pte, Protection::NONE, false) & PTE_USER, 0);
+    }
+
+    fn stack(start: u64, end: u64) -> VMA {
+        VMA::new(
+            VirtAddr::new(start),
+            VirtAddr::new(end),
+            Protection::RW,
+            VmaFlags::GROWS_DOWN,
+            MemoryIntent::Stack,
+        )
+    }
+
+    #[test]
+    fn test_stack_grows_one_page() {
+        let mut vmas = VmaTree::new();
+        vmas.insert(VirtAddr::new(0x20000), stack(0x20000, 0x30000));
+
+        // Falta na página logo abaixo da base
+        let grown = grow_down(&mut vmas, VirtAddr::new(0x1F000), 0x10000, 0x100000).unwrap();
+
+        assert_eq!(grown.start, VirtAddr::new(0x1F000));
+        assert_eq!(grown.size(), 0x10000 + 0x1000);
+        assert_eq!(ranges(&vmas), alloc::vec![(0x1F000, 0x30000)]);
+    }
+
+    #[test]
+    fn test_stack_growth_limits() {
+        let mut vmas = tree(&[(0x1000, 0x2000)]);
+        vmas.insert(VirtAddr::new(0x20000), stack(0x20000, 0x30000));
+
+        // Fora da janela
+        assert!(grow_down(&mut vmas, VirtAddr::new(0x8000), 0x4000, 0x100000).is_none());
+        // Além do tamanho máximo
+        assert!(grow_down(&mut vmas, VirtAddr::new(0x1F000), 0x10000, 0x10000).is_none());
+        // VMA acima não é stack
+        assert!(grow_down(&mut vmas, VirtAddr::new(0x0), 0x10000, 0x100000).is_none());
+        assert_eq!(
+            ranges(&vmas),
+            alloc::vec![(0x1000, 0x2000), (0x20000, 0x30000)]
+        );
+
+        // Dentro da janela: a base vai direto para a página da falta
+        let grown = grow_down(&mut vmas, VirtAddr::new(0x1C000), 0x10000, 0x100000).unwrap();
+        assert_eq!(grown.start, VirtAddr::new(0x1C000));
+    }
+
+    #[test]
+    fn test_insert_merges_adjacent() {
+        let mut vmas = VmaTree::new();
+        insert_merged(&mut vmas, vma(0x1000, 0x2000));
+        insert_merged(&mut vmas, vma(0x3000, 0x4000));
+        assert_eq!(vmas.len(), 2);
+
+        // Preenche o buraco: as três viram uma só
+        insert_merged(&mut vmas, vma(0x2000, 0x3000));
+        assert_eq!(ranges(&vmas), alloc::vec![(0x1000, 0x4000)]);
+
+        // Intenção diferente não funde
+        let mut stack = vma(0x4000, 0x5000);
+        stack.intent = MemoryIntent::Stack;
+        insert_merged(&mut vmas, stack);
+        assert_eq!(vmas.len(), 2);
+    }
+
+    /// Sem relógio em `no_std`: compara o número de nós visitados por busca
+    /// (altura da árvore) com o custo médio da varredura linear do Vec antigo.
+    #[test]
+    fn test_vma_lookup_scales() {
+        const COUNT: u64 = 10_000;
+        let mut tree = VmaTree::new();
+        for i in 0..COUNT {
+            let start = VirtAddr::new(0x1000_0000 + i * 0x2000);
+            let vma = VMA::new(
+                start,
+                start.offset(0x1000),
+                Protection::RW,
+                VmaFlags::empty(),
+                MemoryIntent::Data,
+            );
+            tree.insert(start, vma);
+        }
+        assert_eq!(tree.len(), COUNT as usize);
+
+        // LLRB garante altura <= 2*log2(n+1) ~ 28
+        let tree_steps = tree.height();
+        let vec_steps = (COUNT / 2) as usize;
+        assert!(tree_steps <= 28);
+        assert!(tree_steps * 100 < vec_steps);
+
+        for i in (0..COUNT).step_by(97) {
+            let addr = VirtAddr::new(0x1000_0000 + i * 0x2000 + 0x800);
+            let (_, vma) = tree.floor(&addr).unwrap();
+            assert!(vma.contains(addr));
+        }
+
+        for i in 0..COUNT / 2 {
+            tree.remove(&VirtAddr::new(0x1000_0000 + i * 0x2000));
+        }
+        assert_eq!(tree.len(), (COUNT / 2) as usize);
+        assert!(tree.height() <= 28);
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! Cada região de memória virtual com intenção semântica.
 
-use crate::mm::VirtAddr;
+use crate::mm::{MapFlags, VirtAddr};
+use alloc::sync::Arc;
 
 /// Intenção de uso da memória
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -49,6 +50,18 @@ impl Protection {
             crate::mm::fault::AccessType::Execute => self.can_exec(),
         }
     }
+
+    /// Flags de página de usuário com esta proteção
+    pub fn map_flags(&self) -> MapFlags {
+        let mut flags = MapFlags::PRESENT | MapFlags::USER;
+        if self.can_write() {
+            flags |= MapFlags::WRITABLE;
+        }
+        if self.can_exec() {
+            flags |= MapFlags::EXECUTABLE;
+        }
+        flags
+    }
 }
 
 /// Flags de VMA
@@ -92,7 +105,25 @@ impl core::ops::BitOrAssign for VmaFlags {
 /// Backing de uma VMA
 #[derive(Debug, Clone)]
 pub enum VmaBacking {
+    /// Memória anônima (zerada no primeiro acesso)
     Anonymous,
+    /// Segmento de arquivo populado sob demanda
+    File {
+        /// Imagem completa do arquivo
+        image: Arc<[u8]>,
+        /// Offset do segmento dentro da imagem (p_offset)
+        offset: u64,
+        /// Bytes que vêm do arquivo (p_filesz); o restante é zerado
+        file_size: u64,
+        /// Endereço virtual do início do segmento (p_vaddr)
+        vaddr: u64,
+    },
+}
+
+impl VmaBacking {
+    pub fn is_anonymous(&self) -> bool {
+        matches!(self, VmaBacking::Anonymous)
+    }
 }
 
 /// Virtual Memory Area
@@ -124,6 +155,11 @@ impl VMA {
         }
     }
 
+    pub fn with_backing(mut self, backing: VmaBacking) -> Self {
+        self.backing = backing;
+        self
+    }
+
     pub fn size(&self) -> u64 {
         self.end.as_u64() - self.start.as_u64()
     }
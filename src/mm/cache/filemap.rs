@@ -0,0 +1,245 @@
+//! # Leitura e escrita de arquivos pelo page cache
+//!
+//! Cola entre os filesystems e o [`PageCache`](super::PageCache). O
+//! filesystem implementa [`PageIo`] (ler e gravar uma página do backend) e
+//! passa a ler e escrever por [`read`] e [`write`]:
+//! - **leitura:** página no cache é copiada direto; senão vem do backend
+//!   (read-through) e fica no cache;
+//! - **escrita:** a página (lida antes, se a escrita é parcial) recebe os
+//!   dados e fica suja; só [`flush`] a grava no backend (writeback).
+//!
+//! Cada instância de filesystem pega um [`DevId`] com [`new_dev`] para que
+//! inodes de filesystems diferentes não colidam no cache.
+//!
+//! ## Locks
+//! O I/O do backend roda sem o lock do cache (um `Spinlock`, que desliga
+//! interrupções). Cópias de e para as páginas acontecem com o lock, então
+//! uma página não some no meio da cópia.
+
+use super::pagecache::{self, CacheKey, DevId, PageState};
+use crate::fs::vfs::inode::{FsError, InodeNum};
+use crate::mm::config::PAGE_SIZE;
+use crate::mm::PhysAddr;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Backend das páginas de um filesystem
+pub trait PageIo {
+    /// Lê a página `index` do arquivo `ino` para `buf` (`PAGE_SIZE` bytes;
+    /// zeros depois do fim do arquivo)
+    fn read_page(&self, ino: InodeNum, index: u64, buf: &mut [u8]) -> Result<(), FsError>;
+
+    /// Grava a página `index` do arquivo `ino` (só a parte dentro do arquivo)
+    fn write_page(&self, _ino: InodeNum, _index: u64, _buf: &[u8]) -> Result<(), FsError> {
+        Err(FsError::ReadOnly)
+    }
+}
+
+/// Próximo `DevId` livre
+static NEXT_DEV: AtomicU32 = AtomicU32::new(1);
+
+/// Reserva um `DevId` para uma instância de filesystem
+pub fn new_dev() -> DevId {
+    NEXT_DEV.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Bytes da página em `frame`, pela physmap
+///
+/// # Safety
+/// `frame` tem de ser exclusivo de quem chama, ou uma página do cache com o
+/// lock do cache adquirido.
+unsafe fn page_bytes<'a>(frame: PhysAddr) -> &'a mut [u8] {
+    let ptr = crate::mm::physmap::phys_to_virt(frame).as_mut_ptr::<u8>();
+    core::slice::from_raw_parts_mut(ptr, PAGE_SIZE)
+}
+
+/// Frame para uma página nova; sem frames livres, descarta uma página
+/// limpa do cache e tenta de novo
+fn alloc_frame() -> Result<PhysAddr, FsError> {
+    crate::mm::reclaim::oom::retry_after_reclaim(
+        || crate::mm::pmm::FRAME_ALLOCATOR.lock().allocate_frame(),
+        || pagecache::shrink_clean(1) > 0,
+    )
+    .ok_or(FsError::NoSpace)
+}
+
+fn free_frame(frame: PhysAddr) {
+    crate::mm::pmm::FRAME_ALLOCATOR
+        .lock()
+        .deallocate_frame(frame);
+}
+
+/// Roda `f` sobre a página `key` com o lock do cache, se ela estiver lá;
+/// `dirty` marca a página como suja na mesma seção.
+fn with_cached<R>(key: CacheKey, dirty: bool, f: &mut impl FnMut(&mut [u8]) -> R) -> Option<R> {
+    pagecache::with_cache(|cache| {
+        let frame = cache.lookup(key)?.frame;
+        // SAFETY: página do cache, com o lock adquirido
+        let result = f(unsafe { page_bytes(frame) });
+        if dirty {
+            cache.set_state(key, PageState::Dirty);
+        }
+        Some(result)
+    })
+    .flatten()
+}
+
+/// Roda `f` sobre a página `index` de `ino`, carregando-a do backend se ela
+/// não estiver no cache.
+///
+/// Com `dirty`, a página fica suja. Se ela não couber no cache (cheio de
+/// páginas sujas ou presas), `f` roda sobre a cópia lida e, com `dirty`, a
+/// página vai direto para o backend.
+fn access<R>(
+    dev: DevId,
+    io: &dyn PageIo,
+    ino: InodeNum,
+    index: u64,
+    dirty: bool,
+    mut f: impl FnMut(&mut [u8]) -> R,
+) -> Result<R, FsError> {
+    let key = CacheKey::new(dev, ino, index);
+    if let Some(result) = with_cached(key, dirty, &mut f) {
+        return Ok(result);
+    }
+
+    let frame = alloc_frame()?;
+    // SAFETY: frame recém-alocado, só nosso até entrar no cache
+    let page = unsafe { page_bytes(frame) };
+    if let Err(e) = io.read_page(ino, index, page) {
+        free_frame(frame);
+        return Err(e);
+    }
+
+    if !pagecache::insert(key, frame) {
+        // Outro leitor chegou antes (vale a página dele) ou não há espaço
+        if let Some(result) = with_cached(key, dirty, &mut f) {
+            free_frame(frame);
+            return Ok(result);
+        }
+        let result = f(page);
+        let written = if dirty {
+            io.write_page(ino, index, page)
+        } else {
+            Ok(())
+        };
+        free_frame(frame);
+        return written.map(|_| result);
+    }
+
+    // Entrou no cache; pode ter sido evictada logo depois: lê de novo
+    match with_cached(key, dirty, &mut f) {
+        Some(result) => Ok(result),
+        None => access(dev, io, ino, index, dirty, f),
+    }
+}
+
+/// Lê de `offset` em diante do arquivo `ino` (de `size` bytes) pelo cache.
+/// Retorna os bytes lidos; 0 no fim do arquivo.
+pub fn read(
+    dev: DevId,
+    io: &dyn PageIo,
+    ino: InodeNum,
+    size: u64,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<usize, FsError> {
+    if offset >= size {
+        return Ok(0);
+    }
+    let end = size.min(offset.saturating_add(buf.len() as u64));
+    let mut pos = offset;
+    let mut done = 0;
+    while pos < end {
+        let in_page = (pos % PAGE_SIZE as u64) as usize;
+        let len = (PAGE_SIZE - in_page).min((end - pos) as usize);
+        let out = &mut buf[done..done + len];
+        access(dev, io, ino, pos / PAGE_SIZE as u64, false, |page| {
+            out.copy_from_slice(&page[in_page..in_page + len])
+        })?;
+        pos += len as u64;
+        done += len;
+    }
+    Ok(done)
+}
+
+/// Escreve `buf` em `offset` no arquivo `ino` pelo cache. As páginas ficam
+/// sujas até o [`flush`]; o chamador limita a escrita ao tamanho do arquivo.
+pub fn write(
+    dev: DevId,
+    io: &dyn PageIo,
+    ino: InodeNum,
+    offset: u64,
+    buf: &[u8],
+) -> Result<usize, FsError> {
+    let mut pos = offset;
+    let mut done = 0;
+    while done < buf.len() {
+        let in_page = (pos % PAGE_SIZE as u64) as usize;
+        let len = (PAGE_SIZE - in_page).min(buf.len() - done);
+        let data = &buf[done..done + len];
+        access(dev, io, ino, pos / PAGE_SIZE as u64, true, |page| {
+            page[in_page..in_page + len].copy_from_slice(data)
+        })?;
+        pos += len as u64;
+        done += len;
+    }
+    Ok(done)
+}
+
+/// Grava no backend todas as páginas sujas de `dev` (writeback).
+///
+/// Uma escrita durante a gravação deixa a página suja para o próximo
+/// `flush`. Em erro, a página continua suja e o `flush` segue com as
+/// outras; retorna o último erro.
+pub fn flush(dev: DevId, io: &dyn PageIo) -> Result<(), FsError> {
+    let dirty = pagecache::with_cache(|cache| cache.dirty(dev)).unwrap_or_default();
+    let mut copy = alloc::vec![0u8; PAGE_SIZE];
+    let mut result = Ok(());
+
+    for key in dirty {
+        let claimed = pagecache::with_cache(|cache| {
+            if cache.state(key) != Some(PageState::Dirty) {
+                return false;
+            }
+            let frame = match cache.lookup(key) {
+                Some(page) => page.frame,
+                None => return false,
+            };
+            // SAFETY: página do cache, com o lock adquirido
+            copy.copy_from_slice(unsafe { page_bytes(frame) });
+            cache.set_state(key, PageState::Writing)
+        })
+        .unwrap_or(false);
+        if !claimed {
+            continue;
+        }
+
+        let written = io.write_page(key.file_id, key.page_index, &copy);
+        let next = if written.is_ok() {
+            PageState::Clean
+        } else {
+            PageState::Dirty
+        };
+        pagecache::with_cache(|cache| {
+            if cache.state(key) == Some(PageState::Writing) {
+                cache.set_state(key, next);
+            }
+        });
+        if let Err(e) = written {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+/// Tira do cache as páginas de `ino` (ou de todo o `dev`, com `None`),
+/// sujas inclusive: usado quando o backend mudou por fora ou sumiu.
+pub fn invalidate(dev: DevId, ino: Option<InodeNum>) {
+    pagecache::with_cache(|cache| {
+        for key in cache.keys(dev, ino) {
+            if let Some(page) = cache.remove(key) {
+                free_frame(page.frame);
+            }
+        }
+    });
+}
@@ -27,10 +27,17 @@
 //!
 //! ## Características
 //!
-//! - **Read-ahead**: Pré-carrega páginas adjacentes
-//! - **Write-back**: Escreve páginas sujas em background
-//! - **LRU Eviction**: Remove páginas menos usadas quando sob pressão
+//! - **Read-through**: Leituras que faltam no cache vão ao dispositivo e ficam
+//! - **Write-back**: Escritas sujam a página; `sync` as grava no dispositivo
+//! - **LRU Eviction**: Remove páginas limpas menos usadas quando sob pressão
+//!
+//! ## Estrutura do Módulo
+//!
+//! - `pagecache.rs` - Armazenamento das páginas, LRU e estado sujo/limpo
+//! - `filemap.rs` - Leitura e escrita de arquivos pelo cache (`PageIo`)
 
+pub mod filemap;
 pub mod pagecache;
 
+pub use filemap::PageIo;
 pub use pagecache::{CachedPage, PageCache, PageCacheStats};
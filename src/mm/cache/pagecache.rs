@@ -1,25 +1,40 @@
 //! # Page Cache
+//!
+//! Páginas de arquivos guardadas em frames do PMM, indexadas por
+//! (dispositivo, inode, página). O [`filemap`](super::filemap) faz a leitura
+//! e a escrita por aqui; este módulo só guarda as páginas.
+//!
+//! ## LRU
+//! Cada acesso dá à página um carimbo novo e crescente; `lru` ordena as
+//! páginas por carimbo, então a primeira é a menos usada. A evicção (cache
+//! cheio ou [`shrink_clean`] sob pressão de memória) anda a LRU a partir da
+//! mais antiga e só leva páginas limpas e soltas: uma suja precisa de
+//! writeback antes.
 
-use crate::mm::{PhysAddr, PhysFrame};
+use crate::mm::PhysAddr;
 use crate::sync::Spinlock;
-use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 extern crate alloc;
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 
+/// Dispositivo (instância de filesystem) dono das páginas
+pub type DevId = u32;
 pub type FileId = u64;
 pub type PageIndex = u64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CacheKey {
+    pub dev: DevId,
     pub file_id: FileId,
     pub page_index: PageIndex,
 }
 
 impl CacheKey {
-    pub const fn new(file_id: FileId, page_index: PageIndex) -> Self {
+    pub const fn new(dev: DevId, file_id: FileId, page_index: PageIndex) -> Self {
         Self {
+            dev,
             file_id,
             page_index,
         }
@@ -39,30 +54,24 @@ pub struct CachedPage {
     pub key: CacheKey,
     pub frame: PhysAddr,
     pub state: PageState,
-    pub use_count: AtomicU32,
-    pub last_access: AtomicU64,
     pub pinned: AtomicBool,
     pub map_count: AtomicU32,
+    /// Carimbo do último acesso (chave na LRU)
+    stamp: u64,
 }
 
 impl CachedPage {
-    pub fn new(key: CacheKey, frame: PhysAddr) -> Self {
+    fn new(key: CacheKey, frame: PhysAddr, stamp: u64) -> Self {
         Self {
             key,
             frame,
             state: PageState::Clean,
-            use_count: AtomicU32::new(1),
-            last_access: AtomicU64::new(0),
             pinned: AtomicBool::new(false),
             map_count: AtomicU32::new(0),
+            stamp,
         }
     }
 
-    pub fn touch(&self, timestamp: u64) {
-        self.use_count.fetch_add(1, Ordering::Relaxed);
-        self.last_access.store(timestamp, Ordering::Relaxed);
-    }
-
     pub fn can_evict(&self) -> bool {
         !self.pinned.load(Ordering::Acquire)
             && self.map_count.load(Ordering::Acquire) == 0
@@ -83,104 +92,153 @@ pub struct PageCacheStats {
 
 pub struct PageCache {
     pages: BTreeMap<CacheKey, CachedPage>,
-    lru_list: Vec<CacheKey>,
+    /// Carimbo → página, da menos para a mais recentemente usada
+    lru: BTreeMap<u64, CacheKey>,
     max_pages: usize,
     stats: PageCacheStats,
-    current_time: u64,
+    clock: u64,
 }
 
 impl PageCache {
     pub fn new(max_pages: usize) -> Self {
         Self {
             pages: BTreeMap::new(),
-            lru_list: Vec::with_capacity(max_pages),
+            lru: BTreeMap::new(),
             max_pages,
             stats: PageCacheStats::default(),
-            current_time: 0,
+            clock: 0,
         }
     }
 
-    pub fn lookup(&mut self, file_id: FileId, page_index: PageIndex) -> Option<&CachedPage> {
-        let key = CacheKey::new(file_id, page_index);
-        self.current_time += 1;
+    /// Próximo carimbo da LRU
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
 
-        if let Some(page) = self.pages.get(&key) {
-            page.touch(self.current_time);
-            self.stats.hits += 1;
-            Some(page)
-        } else {
-            self.stats.misses += 1;
-            None
+    pub fn lookup(&mut self, key: CacheKey) -> Option<&CachedPage> {
+        let stamp = self.tick();
+        match self.pages.get_mut(&key) {
+            Some(page) => {
+                // Vai para o fim da LRU
+                self.lru.remove(&page.stamp);
+                self.lru.insert(stamp, key);
+                page.stamp = stamp;
+                self.stats.hits += 1;
+                Some(page)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
         }
     }
 
-    pub fn insert(&mut self, file_id: FileId, page_index: PageIndex, frame: PhysAddr) -> bool {
-        let key = CacheKey::new(file_id, page_index);
-
+    /// Guarda `frame` como a página `key` (limpa). Com o cache cheio,
+    /// evicta a página limpa menos usada; `false` se `key` já está no
+    /// cache ou não há o que evictar.
+    pub fn insert(&mut self, key: CacheKey, frame: PhysAddr) -> bool {
         if self.pages.contains_key(&key) {
             return false;
         }
 
-        if self.pages.len() >= self.max_pages {
-            if !self.try_evict_one() {
-                return false;
-            }
+        if self.pages.len() >= self.max_pages && !self.try_evict_one() {
+            return false;
         }
 
-        let page = CachedPage::new(key, frame);
-        self.pages.insert(key, page);
-        self.lru_list.push(key);
-        self.stats.cached_pages = self.pages.len() as u64;
+        let stamp = self.tick();
+        self.pages.insert(key, CachedPage::new(key, frame, stamp));
+        self.lru.insert(stamp, key);
         true
     }
 
-    pub fn remove(&mut self, file_id: FileId, page_index: PageIndex) -> Option<CachedPage> {
-        let key = CacheKey::new(file_id, page_index);
-        if let Some(pos) = self.lru_list.iter().position(|k| *k == key) {
-            self.lru_list.remove(pos);
-        }
-        let page = self.pages.remove(&key);
-        if page.is_some() {
-            self.stats.cached_pages = self.pages.len() as u64;
-        }
-        page
+    pub fn remove(&mut self, key: CacheKey) -> Option<CachedPage> {
+        let page = self.pages.remove(&key)?;
+        self.lru.remove(&page.stamp);
+        Some(page)
     }
 
-    fn try_evict_one(&mut self) -> bool {
-        let mut victim_idx = None;
-        let mut min_access = u64::MAX;
-
-        for (idx, key) in self.lru_list.iter().enumerate() {
-            if let Some(page) = self.pages.get(key) {
-                if page.can_evict() {
-                    let access = page.last_access.load(Ordering::Relaxed);
-                    if access < min_access {
-                        min_access = access;
-                        victim_idx = Some(idx);
-                    }
-                }
-            }
+    /// Estado da página `key`, se está no cache
+    pub fn state(&self, key: CacheKey) -> Option<PageState> {
+        self.pages.get(&key).map(|page| page.state)
+    }
+
+    /// Muda o estado da página `key`; `Writing` → `Clean` conta um writeback
+    pub fn set_state(&mut self, key: CacheKey, state: PageState) -> bool {
+        let Some(page) = self.pages.get_mut(&key) else {
+            return false;
+        };
+        if page.state == PageState::Writing && state == PageState::Clean {
+            self.stats.writebacks += 1;
         }
+        page.state = state;
+        true
+    }
 
-        if let Some(idx) = victim_idx {
-            let key = self.lru_list.remove(idx);
-            if let Some(page) = self.pages.remove(&key) {
-                if page.state == PageState::Dirty {
-                    self.stats.writebacks += 1;
-                }
-                crate::mm::pmm::FRAME_ALLOCATOR
-                    .lock()
-                    .deallocate_frame(page.frame);
-                self.stats.evictions += 1;
-                self.stats.cached_pages = self.pages.len() as u64;
-                return true;
-            }
+    /// Páginas sujas de `dev`, em ordem de arquivo e página
+    pub fn dirty(&self, dev: DevId) -> Vec<CacheKey> {
+        self.pages
+            .range(CacheKey::new(dev, 0, 0)..=CacheKey::new(dev, FileId::MAX, PageIndex::MAX))
+            .filter(|(_, page)| page.state == PageState::Dirty)
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    /// Páginas do arquivo `file_id` de `dev` (ou de todo o `dev`, com `None`)
+    pub fn keys(&self, dev: DevId, file_id: Option<FileId>) -> Vec<CacheKey> {
+        let (first, last) = match file_id {
+            Some(file) => (file, file),
+            None => (0, FileId::MAX),
+        };
+        self.pages
+            .range(CacheKey::new(dev, first, 0)..=CacheKey::new(dev, last, PageIndex::MAX))
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    /// Descarta até `target` páginas limpas e evictáveis, das menos
+    /// usadas para as mais; `release` recebe o frame de cada uma.
+    ///
+    /// Páginas sujas ficam (precisariam de writeback). Não aloca: roda no
+    /// caminho de OOM do heap. Retorna quantas saíram.
+    pub fn shrink(&mut self, target: usize, mut release: impl FnMut(PhysAddr)) -> usize {
+        let mut dropped = 0;
+        while dropped < target {
+            let pages = &self.pages;
+            let victim = self.lru.values().copied().find(|key| {
+                pages
+                    .get(key)
+                    .is_some_and(|page| page.state == PageState::Clean && page.can_evict())
+            });
+            let Some(page) = victim.and_then(|key| self.remove(key)) else {
+                break;
+            };
+            release(page.frame);
+            dropped += 1;
         }
-        false
+        self.stats.evictions += dropped as u64;
+        dropped
+    }
+
+    /// Evicta a página limpa menos usada, devolvendo o frame ao PMM
+    fn try_evict_one(&mut self) -> bool {
+        self.shrink(1, |frame| {
+            crate::mm::pmm::FRAME_ALLOCATOR
+                .lock()
+                .deallocate_frame(frame)
+        }) == 1
     }
 
     pub fn stats(&self) -> PageCacheStats {
-        self.stats
+        PageCacheStats {
+            cached_pages: self.pages.len() as u64,
+            dirty_pages: self
+                .pages
+                .values()
+                .filter(|page| page.state == PageState::Dirty)
+                .count() as u64,
+            ..self.stats
+        }
     }
     pub fn len(&self) -> usize {
         self.pages.len()
@@ -210,14 +268,143 @@ where
     guard.as_mut().map(f)
 }
 
-pub fn lookup(file_id: FileId, page_index: PageIndex) -> Option<PhysAddr> {
-    with_cache(|cache| cache.lookup(file_id, page_index).map(|p| p.frame)).flatten()
+pub fn lookup(key: CacheKey) -> Option<PhysAddr> {
+    with_cache(|cache| cache.lookup(key).map(|p| p.frame)).flatten()
 }
 
-pub fn insert(file_id: FileId, page_index: PageIndex, frame: PhysAddr) -> bool {
-    with_cache(|cache| cache.insert(file_id, page_index, frame)).unwrap_or(false)
+pub fn insert(key: CacheKey, frame: PhysAddr) -> bool {
+    with_cache(|cache| cache.insert(key, frame)).unwrap_or(false)
 }
 
 pub fn stats() -> PageCacheStats {
     with_cache(|cache| cache.stats()).unwrap_or_default()
 }
+
+/// Descarta até `target` páginas limpas do cache (reclaim sob pressão).
+///
+/// Não espera o lock do cache: se ele estiver ocupado (talvez por quem
+/// está sem memória), não libera nada.
+pub fn shrink_clean(target: usize) -> usize {
+    let Some(mut guard) = PAGE_CACHE.try_lock() else {
+        return 0;
+    };
+    let Some(cache) = guard.as_mut() else {
+        return 0;
+    };
+    cache.shrink(target, |frame| {
+        crate::mm::pmm::FRAME_ALLOCATOR
+            .lock()
+            .deallocate_frame(frame)
+    })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm::reclaim::oom::retry_after_reclaim;
+    use core::cell::RefCell;
+
+    const DEV: DevId = 1;
+
+    fn key(page: u64) -> CacheKey {
+        CacheKey::new(DEV, 7, page)
+    }
+
+    fn cache_with(count: u64) -> PageCache {
+        let mut cache = PageCache::new(8);
+        for i in 0..count {
+            assert!(cache.insert(key(i), PhysAddr::new(0x1000 * (i + 1))));
+        }
+        cache
+    }
+
+    #[test]
+    fn test_shrink_drops_only_clean_unpinned_pages() {
+        let mut cache = cache_with(4);
+        cache.lookup(key(0));
+        cache.set_state(key(2), PageState::Dirty);
+        cache.pages[&key(3)].pinned.store(true, Ordering::Relaxed);
+
+        let mut released = Vec::new();
+        // A menos usada (1) sai antes da recém-lida (0)
+        assert_eq!(cache.shrink(1, |frame| released.push(frame)), 1);
+        assert_eq!(released, [PhysAddr::new(0x2000)]);
+        assert_eq!(cache.shrink(usize::MAX, |frame| released.push(frame)), 1);
+        assert_eq!(released[1], PhysAddr::new(0x1000));
+        assert_eq!(cache.shrink(usize::MAX, |_| ()), 0);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lru.len(), 2);
+        let stats = cache.stats();
+        assert_eq!((stats.evictions, stats.dirty_pages), (2, 1));
+    }
+
+    #[test]
+    fn test_lru_order_follows_access() {
+        let mut cache = cache_with(3);
+        // Ordem de uso: 1, 0, 2 → a próxima a sair é a 1
+        cache.lookup(key(0));
+        cache.lookup(key(2));
+        let order: Vec<_> = cache.lru.values().map(|k| k.page_index).collect();
+        assert_eq!(order, [1, 0, 2]);
+
+        assert!(cache.lookup(key(9)).is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.cached_pages), (2, 1, 3));
+    }
+
+    #[test]
+    fn test_dirty_tracking_and_writeback() {
+        let mut cache = cache_with(3);
+        let other = CacheKey::new(DEV + 1, 7, 0);
+        assert!(cache.insert(other, PhysAddr::new(0x9000)));
+        cache.set_state(key(2), PageState::Dirty);
+        cache.set_state(key(0), PageState::Dirty);
+        cache.set_state(other, PageState::Dirty);
+
+        // Só as do dispositivo pedido, em ordem
+        assert_eq!(cache.dirty(DEV), [key(0), key(2)]);
+        assert_eq!(cache.keys(DEV, Some(7)).len(), 3);
+        assert_eq!(cache.keys(DEV + 1, None), [other]);
+
+        cache.set_state(key(0), PageState::Writing);
+        cache.set_state(key(0), PageState::Clean);
+        // Limpar sem writeback (descartar) não conta
+        cache.set_state(key(2), PageState::Clean);
+        assert_eq!(cache.stats().writebacks, 1);
+        assert_eq!(cache.dirty(DEV), []);
+        assert!(!cache.set_state(key(9), PageState::Dirty));
+    }
+
+    #[test]
+    fn test_shrink_under_pressure_unblocks_allocation() {
+        // Memória sem frames livres: a alocação falha até o cache ceder um
+        let free = RefCell::new(Vec::new());
+        let alloc = || free.borrow_mut().pop();
+        let mut cache = cache_with(2);
+        assert_eq!(alloc(), None);
+
+        let frame = retry_after_reclaim(alloc, || {
+            cache.shrink(1, |frame| free.borrow_mut().push(frame)) > 0
+        });
+        assert_eq!(frame, Some(PhysAddr::new(0x1000)));
+        assert_eq!(cache.len(), 1);
+
+        // Nada a descartar: não há segunda tentativa
+        let mut attempts = 0;
+        let mut pinned = cache_with(1);
+        pinned.pages[&key(0)].pinned.store(true, Ordering::Relaxed);
+        let frame = retry_after_reclaim(
+            || {
+                attempts += 1;
+                None::<PhysAddr>
+            },
+            || pinned.shrink(1, |_| ()) > 0,
+        );
+        assert_eq!((frame, attempts), (None, 1));
+    }
+}
@@ -37,11 +37,31 @@ pub const HEAP_VIRT_BASE: usize = 0xFFFF_9000_0000_0000;
 /// Tamanho inicial do heap (16 MiB).
 pub const HEAP_INITIAL_SIZE: usize = 16 * 1024 * 1024;
 
+/// Base da região de imagens de módulos do kernel.
+/// Fica sob a mesma entrada da PML4 que o heap: as entradas 256..511 são
+/// copiadas ao criar um address space, então páginas mapeadas aqui depois
+/// da criação continuam visíveis em todos eles.
+pub const MODULE_VIRT_BASE: usize = 0xFFFF_9040_0000_0000;
+
+/// Espaço virtual reservado para cada módulo (8 MiB).
+pub const MODULE_SLOT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Número de slots de módulo na região.
+pub const MODULE_SLOTS: usize = 1024;
+
 /// Endereço virtual fixo para o "Scratch Slot".
 /// Usado para mapear temporariamente páginas físicas para zeragem/cópia.
 /// Deve estar em uma região segura, não sobreposta pelo Identity Map ou Heap.
 pub const SCRATCH_VIRT: usize = 0xFFFF_FE00_0000_0000;
 
+/// Janela abaixo da base de uma stack `GROWS_DOWN` em que uma falta estende
+/// a stack (64 KiB: cobre um frame grande reservado antes do primeiro acesso).
+pub const STACK_GROWTH_WINDOW: usize = 64 * 1024;
+
+/// Tamanho máximo de uma stack que cresce sob demanda (8 MiB). Uma falta
+/// que passaria disso não estende a stack (SIGSEGV).
+pub const STACK_MAX_SIZE: usize = 8 * 1024 * 1024;
+
 // =============================================================================
 // CONFIGURAÇÃO DO ALLOCATOR
 // =============================================================================
@@ -1,5 +1,6 @@
 //! # Page Fault Handler
 
+use crate::mm::aspace::vma::VmaBacking;
 use crate::mm::{MapFlags, PhysAddr, VirtAddr};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,6 +20,16 @@ pub enum FaultResult {
     FatalError,
 }
 
+/// Bits do error code do #PF
+pub const PF_PRESENT: u64 = 1 << 0;
+pub const PF_WRITE: u64 = 1 << 1;
+pub const PF_USER: u64 = 1 << 2;
+pub const PF_RESERVED: u64 = 1 << 3;
+pub const PF_INSTRUCTION: u64 = 1 << 4;
+
+/// Início da metade alta (kernel) do espaço virtual
+const KERNEL_SPACE_START: u64 = 0xFFFF_8000_0000_0000;
+
 #[derive(Debug, Clone, Copy)]
 pub struct PageFaultInfo {
     pub addr: VirtAddr,
@@ -26,13 +37,15 @@ pub struct PageFaultInfo {
     pub error_code: u64,
     pub access: AccessType,
     pub user_mode: bool,
+    /// A página estava presente: violação de proteção, não falta de página
+    pub present: bool,
 }
 
 impl PageFaultInfo {
     pub fn from_error_code(addr: u64, ip: u64, error_code: u64) -> Self {
-        let access = if error_code & 0x10 != 0 {
+        let access = if error_code & PF_INSTRUCTION != 0 {
             AccessType::Execute
-        } else if error_code & 0x02 != 0 {
+        } else if error_code & PF_WRITE != 0 {
             AccessType::Write
         } else {
             AccessType::Read
@@ -42,43 +55,97 @@ impl PageFaultInfo {
             ip: VirtAddr::new(ip),
             error_code,
             access,
-            user_mode: error_code & 0x04 != 0,
+            user_mode: error_code & PF_USER != 0,
+            present: error_code & PF_PRESENT != 0,
+        }
+    }
+
+    /// Registra a falta decodificada (diagnóstico de falta não resolvida)
+    pub fn dump(&self) {
+        crate::kerror!("(Fault) CR2:", self.addr.as_u64());
+        crate::kerror!("(Fault) RIP:", self.ip.as_u64());
+        crate::kerror!("(Fault) Error code:", self.error_code);
+        crate::kerror!(
+            "(Fault) Causa:",
+            if self.present {
+                "violação de proteção"
+            } else {
+                "página ausente"
+            }
+        );
+        crate::kerror!(
+            "(Fault) Acesso:",
+            match self.access {
+                AccessType::Read => "leitura",
+                AccessType::Write => "escrita",
+                AccessType::Execute => "execução",
+            }
+        );
+        crate::kerror!(
+            "(Fault) Modo:",
+            if self.user_mode { "user" } else { "kernel" }
+        );
+        if self.error_code & PF_RESERVED != 0 {
+            crate::kerror!("(Fault) Bit reservado setado na page table");
         }
     }
 }
 
+/// Resolve um #PF pelas VMAs do AddressSpace da task atual.
+///
+/// - endereço sem VMA: logo abaixo de uma stack (`GROWS_DOWN`, dentro da
+///   janela e do tamanho máximo de `mm::config`) estende a VMA; qualquer
+///   outro vira `InvalidAddress` (SIGSEGV);
+/// - página presente (violação de proteção): escrita em página COW é
+///   resolvida com cópia, o resto é `ProtectionViolation`;
+/// - página ausente: populada (anônima pela página zero ou por um frame
+///   zerado, ou cópia do arquivo).
+///
+/// Faltas na metade do kernel nunca são resolvidas aqui.
 pub fn handle_page_fault(info: PageFaultInfo) -> FaultResult {
-    // 1. Verificar se é um endereço de Kernel Space
-    if info.addr.as_u64() >= 0xFFFF_8000_0000_0000 {
-        crate::kerror!("(Fault) Kernel Page Fault at:", info.addr.as_u64());
-        crate::kerror!("(Fault) Faulting RIP:", info.ip.as_u64());
+    // 1. Espaço do kernel não tem VMAs
+    if info.addr.as_u64() >= KERNEL_SPACE_START {
+        return if info.user_mode {
+            FaultResult::InvalidAddress
+        } else {
+            FaultResult::FatalError
+        };
+    }
+    // Bit reservado: page table corrompida, nada a popular
+    if info.error_code & PF_RESERVED != 0 {
         return FaultResult::FatalError;
     }
 
-    // 2. Obter AddressSpace da tarefa atual
-    let current_guard = crate::sched::core::CURRENT.lock();
-    let aspace_arc = match current_guard.as_ref() {
-        Some(task) => match &task.aspace {
-            Some(as_arc) => as_arc.clone(),
+    // 2. Obter AddressSpace da tarefa atual. try_lock: uma falta com CURRENT
+    // travado é bug do kernel e não pode travar aqui.
+    let aspace_arc = {
+        let Some(current_guard) = crate::sched::core::CURRENT.try_lock() else {
+            return FaultResult::FatalError;
+        };
+        match current_guard.as_ref().and_then(|task| task.aspace.clone()) {
+            Some(as_arc) => as_arc,
             None => return FaultResult::FatalError,
-        },
-        None => return FaultResult::FatalError,
+        }
     };
-    drop(current_guard);
 
-    let as_lock = aspace_arc.lock();
+    let mut as_lock = aspace_arc.lock();
 
-    // 3. Procurar VMA correspondente
+    // 3. Procurar VMA correspondente (ou crescer a stack logo acima)
     let vma = match as_lock.find_vma(info.addr) {
         Some(v) => v,
-        None => {
-            crate::kerror!(
-                "(Fault) Falha de Segmentacao (Sem VMA) em:",
-                info.addr.as_u64()
-            );
-            crate::kerror!("(Fault) RIP da Falha:", info.ip.as_u64());
-            return FaultResult::InvalidAddress;
-        }
+        None => match as_lock.grow_stack(info.addr) {
+            Ok(v) => {
+                crate::kdebug!("(Fault) Stack estendida até:", v.start.as_u64());
+                v
+            }
+            Err(_) => {
+                crate::kerror!(
+                    "(Fault) Falha de Segmentacao (Sem VMA) em:",
+                    info.addr.as_u64()
+                );
+                return FaultResult::InvalidAddress;
+            }
+        },
     };
 
     // 4. Validar permissões
@@ -87,59 +154,237 @@ pub fn handle_page_fault(info: PageFaultInfo) -> FaultResult {
         return FaultResult::ProtectionViolation;
     }
 
-    // 5. Resolver Fault (Lazy Allocation para Anonymous)
-    crate::kdebug!("(Fault) Lazy allocation for:", info.addr.as_u64());
+    let page = info.addr.align_down(crate::mm::config::PAGE_SIZE as u64);
 
-    // Converter Protection/VmaFlags para MapFlags (Simplificado)
-    let mut flags = MapFlags::PRESENT | MapFlags::USER;
-    if vma.protection.can_write() {
-        flags |= MapFlags::WRITABLE;
-    }
-    if vma.protection.can_exec() {
-        flags |= MapFlags::EXECUTABLE;
+    // 5. Página presente: só uma escrita em página COW tem conserto
+    if info.present {
+        if info.access == AccessType::Write {
+            if let Some(pte) = crate::mm::vmm::mapper::read_pte_in_p4(as_lock.cr3(), page.as_u64())
+            {
+                if pte & crate::mm::vmm::mapper::PTE_COW != 0 {
+                    crate::kdebug!("(Fault) COW em:", info.addr.as_u64());
+                    return match resolve_cow(as_lock.cr3(), page, pte) {
+                        Ok(_) => FaultResult::Success,
+                        Err(e) => e,
+                    };
+                }
+            }
+        }
+        crate::kerror!("(Fault) Protection Violation at:", info.addr.as_u64());
+        return FaultResult::ProtectionViolation;
     }
 
-    match lazy_alloc(info.addr.align_down(4096), flags) {
+    // 6. Página ausente: Lazy Allocation para Anonymous, cópia para arquivo
+    crate::kdebug!("(Fault) Lazy allocation for:", info.addr.as_u64());
+
+    let flags = vma.protection.map_flags();
+
+    let result = match &vma.backing {
+        VmaBacking::Anonymous => populate_anon_page(as_lock.cr3(), page, info.access, flags),
+        backing => populate_file_page(as_lock.cr3(), page, backing, flags),
+    };
+
+    match result {
         Ok(_) => FaultResult::Success,
         Err(e) => e,
     }
 }
 
-pub fn lazy_alloc(addr: VirtAddr, flags: MapFlags) -> Result<PhysAddr, FaultResult> {
-    let phys = crate::mm::pmm::FRAME_ALLOCATOR
-        .lock()
-        .allocate_frame()
-        .ok_or(FaultResult::OutOfMemory)?;
+/// Popula uma página de uma VMA com backing de arquivo.
+///
+/// Aloca (se ainda não mapeada) e zera um frame, depois copia a parte de
+/// `[vaddr, vaddr + file_size)` que cai nesta página. Chamar de novo sobre uma
+/// página já presente apenas copia os bytes deste backing, o que permite que
+/// dois segmentos compartilhem a mesma página.
+pub fn populate_file_page(
+    pml4: u64,
+    page: VirtAddr,
+    backing: &VmaBacking,
+    flags: MapFlags,
+) -> Result<PhysAddr, FaultResult> {
+    let page = page.as_u64();
+    let page_size = crate::mm::config::PAGE_SIZE as u64;
+
+    let phys = match crate::mm::vmm::mapper::translate_addr_in_p4(pml4, page) {
+        Some(phys) => phys & !(page_size - 1),
+        None => lazy_alloc(pml4, VirtAddr::new(page), flags)?.as_u64(),
+    };
+
+    if let VmaBacking::File {
+        image,
+        offset,
+        file_size,
+        vaddr,
+    } = backing
+    {
+        // Interseção da página com a parte do segmento que vem do arquivo
+        let copy_start = core::cmp::max(page, *vaddr);
+        let copy_end = core::cmp::min(page + page_size, vaddr + file_size);
+        if copy_start < copy_end {
+            let src = (offset + (copy_start - vaddr)) as usize;
+            let len = (copy_end - copy_start) as usize;
+            if src + len > image.len() {
+                return Err(FaultResult::InvalidAddress);
+            }
+            unsafe {
+                let dst =
+                    crate::mm::hhdm::phys_to_virt::<u8>(phys).add((copy_start - page) as usize);
+                core::ptr::copy_nonoverlapping(image.as_ptr().add(src), dst, len);
+            }
+        }
+    }
+
+    Ok(PhysAddr::new(phys))
+}
+
+/// Popula uma página anônima ausente.
+///
+/// Leitura ou execução mapeia a página zero compartilhada, somente leitura
+/// (e COW se `flags` pede escrita): nenhum frame é gasto até a primeira
+/// escrita. Uma escrita aloca direto um frame zerado.
+pub fn populate_anon_page(
+    pml4: u64,
+    page: VirtAddr,
+    access: AccessType,
+    flags: MapFlags,
+) -> Result<PhysAddr, FaultResult> {
+    if access != AccessType::Write && crate::mm::pfm::zero::zero_page_frame().is_some() {
+        map_zero_page(pml4, page, flags)
+    } else {
+        lazy_alloc(pml4, page, flags)
+    }
+}
+
+/// Mapeia `page` na página zero: somente leitura e, se `flags` pede
+/// escrita, marcada COW para a primeira escrita ganhar um frame próprio.
+pub fn map_zero_page(pml4: u64, page: VirtAddr, flags: MapFlags) -> Result<PhysAddr, FaultResult> {
+    use crate::mm::vmm::mapper::{read_pte_in_p4, write_pte_in_p4, PTE_COW};
+
+    let zero = crate::mm::pfm::zero::zero_page_frame().ok_or(FaultResult::OutOfMemory)?;
+    let mut read_only = flags;
+    read_only.remove(MapFlags::WRITABLE);
+    {
+        let mut pmm = crate::mm::pmm::FRAME_ALLOCATOR.lock();
+        crate::mm::vmm::mapper::map_page_in_target_p4(
+            pml4,
+            page.as_u64(),
+            zero.as_u64(),
+            read_only,
+            &mut pmm,
+        )
+        .map_err(|_| FaultResult::OutOfMemory)?;
+    }
+
+    if flags.contains(MapFlags::WRITABLE) {
+        let pte = read_pte_in_p4(pml4, page.as_u64()).ok_or(FaultResult::FatalError)?;
+        // SAFETY: acabou de ser mapeada; só ganha o bit COW
+        unsafe {
+            write_pte_in_p4(pml4, page.as_u64(), pte | PTE_COW)
+                .map_err(|_| FaultResult::FatalError)?;
+        }
+    }
+    Ok(zero)
+}
+
+/// Aloca um frame de usuário pelo PFM (contagem de referências em 1)
+fn alloc_user_page() -> Result<PhysAddr, FaultResult> {
+    crate::mm::pfm::alloc_user_frame(current_owner()).map_err(|_| FaultResult::OutOfMemory)
+}
+
+/// Dono dos frames alocados na falta: a task atual, ou o kernel se não
+/// houver processo ou se CURRENT já estiver travado nesta CPU.
+fn current_owner() -> crate::mm::pfm::Pid {
+    crate::sched::core::CURRENT
+        .try_lock()
+        .and_then(|current| {
+            current
+                .as_ref()
+                .map(|task| task.tid.as_u32() as crate::mm::pfm::Pid)
+        })
+        .unwrap_or(crate::mm::pfm::PID_KERNEL)
+}
+
+/// Mapeia `addr` em um frame novo e zerado
+pub fn lazy_alloc(pml4: u64, addr: VirtAddr, flags: MapFlags) -> Result<PhysAddr, FaultResult> {
+    let phys = alloc_user_page()?;
 
     unsafe {
         crate::mm::hhdm::zero_page(phys.as_u64());
     }
 
-    crate::mm::map_page(addr.as_u64(), phys.as_u64(), flags)
-        .map_err(|_| FaultResult::OutOfMemory)?;
+    let mapped = {
+        let mut pmm = crate::mm::pmm::FRAME_ALLOCATOR.lock();
+        crate::mm::vmm::mapper::map_page_in_target_p4(
+            pml4,
+            addr.as_u64(),
+            phys.as_u64(),
+            flags,
+            &mut pmm,
+        )
+    };
+    if mapped.is_err() {
+        crate::mm::pfm::release_mapped(phys);
+        return Err(FaultResult::OutOfMemory);
+    }
 
     Ok(phys)
 }
 
-pub fn resolve_cow(
-    addr: VirtAddr,
-    old_phys: PhysAddr,
-    flags: MapFlags,
-) -> Result<PhysAddr, FaultResult> {
-    let new_phys = crate::mm::pmm::FRAME_ALLOCATOR
-        .lock()
-        .allocate_frame()
-        .ok_or(FaultResult::OutOfMemory)?;
+/// Resolve uma escrita em página Copy-on-Write.
+///
+/// Se esta é a última referência ao frame, apenas devolve a permissão de
+/// escrita. Senão copia o conteúdo para um frame novo e solta a referência
+/// antiga por [`release_mapped`](crate::mm::pfm::release_mapped), que confere
+/// e decrementa sob o lock do PFM: se o outro dono soltou o frame nesse meio
+/// tempo, é aqui que ele é liberado. A página zero é sempre compartilhada:
+/// ganha um frame zerado, sem cópia, e nunca perde referência.
+pub fn resolve_cow(pml4: u64, addr: VirtAddr, pte: u64) -> Result<PhysAddr, FaultResult> {
+    use crate::mm::vmm::mapper::{write_pte_in_p4, PTE_ADDR_MASK, PTE_COW, PTE_WRITABLE};
 
-    unsafe {
-        let src: *const u8 = crate::mm::hhdm::phys_to_virt::<u8>(old_phys.as_u64());
-        let dst: *mut u8 = crate::mm::hhdm::phys_to_virt::<u8>(new_phys.as_u64());
-        core::ptr::copy_nonoverlapping(src, dst, crate::mm::config::PAGE_SIZE);
+    let old_phys = PhysAddr::new(pte & PTE_ADDR_MASK);
+    let flags = pte & !PTE_ADDR_MASK & !PTE_COW;
+
+    // Frames fora do PFM (erro) são tratados como compartilhados por segurança
+    let from_zero = crate::mm::pfm::zero::is_zero_page(old_phys);
+    let exclusive = !from_zero
+        && matches!(
+            crate::mm::pfm::get().lock().get_ref_count(old_phys),
+            Ok(0 | 1)
+        );
+
+    let new_phys = if exclusive {
+        old_phys
+    } else {
+        let new_phys = alloc_user_page()?;
+        unsafe {
+            if from_zero {
+                crate::mm::hhdm::zero_page(new_phys.as_u64());
+            } else {
+                crate::mm::hhdm::copy_page(old_phys.as_u64(), new_phys.as_u64());
+            }
+        }
+        new_phys
+    };
+
+    // SAFETY: a PTE existe (a falta veio dela); só troca frame e permissões
+    let written = unsafe {
+        write_pte_in_p4(
+            pml4,
+            addr.as_u64(),
+            new_phys.as_u64() | flags | PTE_WRITABLE,
+        )
+    };
+    if written.is_err() {
+        if new_phys != old_phys {
+            crate::mm::pfm::release_mapped(new_phys);
+        }
+        return Err(FaultResult::FatalError);
     }
+    crate::mm::vmm::tlb::flush(addr.as_u64());
 
-    let _ = crate::mm::unmap_page(addr.as_u64());
-    crate::mm::map_page(addr.as_u64(), new_phys.as_u64(), flags)
-        .map_err(|_| FaultResult::OutOfMemory)?;
+    if new_phys != old_phys && !from_zero {
+        crate::mm::pfm::release_mapped(old_phys);
+    }
 
     Ok(new_phys)
 }
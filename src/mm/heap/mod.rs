@@ -21,7 +21,7 @@
 //! ### ⚠️ Pontos de Atenção (Dívida Técnica CRÍTICA)
 //! - **Memory Leak by Design:** Como `dealloc` não recicla memória, qualquer driver ou serviço que aloque/desaloque repetidamente vai exaurir a RAM rapidamente.
 //! - **Fragmentação:** Não há coalescência de blocos.
-//! - **Single Global Lock:** Assim como no PMM, o `LockedHeap` usa um `Mutex` global, serializando todas as alocações do kernel.
+//! - **Single Global Lock:** Assim como no PMM, o `LockedHeap` usa um `Spinlock` global, serializando todas as alocações do kernel. Não pode ser um `sync::Mutex`: a espera do mutex passa pelo scheduler (herança de prioridade), que aloca.
 //!
 //! ## 🛠️ TODOs e Roadmap
 //! - [ ] **TODO: (Critical)** Migrar para **Slab Allocator** (objetos pequenos fixos) + **Buddy System** (páginas).
@@ -34,8 +34,10 @@
 
 // use crate::drivers::serial;
 use crate::mm::alloc::{BuddyAllocator, SlabAllocator};
-use crate::sync::Mutex;
+use crate::mm::vmm::{huge, MapFlags};
+use crate::sync::Spinlock;
 use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// Endereço virtual inicial do heap (Higher-Half)
 /// Definido em `mm::config` para consistência. Alterações requerem ajustes no VMM/Bootloader.
@@ -55,11 +57,39 @@ pub fn heap_start() -> usize {
     HEAP_START_ADDR.load(core::sync::atomic::Ordering::Relaxed)
 }
 
-/// Helper para ler Time Stamp Counter (TSC) para entropia
-fn rdtsc() -> u64 {
-    unsafe { core::arch::x86_64::_rdtsc() }
+/// Tamanho da região gerenciada pelo heap
+static HEAP_SIZE: AtomicUsize = AtomicUsize::new(0);
+/// Bytes entregues e ainda não liberados (Slab + Buddy)
+static HEAP_USED: AtomicUsize = AtomicUsize::new(0);
+/// Parte de `HEAP_USED` servida pelo Slab
+static SLAB_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// Uso do heap do kernel, em bytes
+#[derive(Debug, Clone, Copy)]
+pub struct HeapStats {
+    pub size: usize,
+    pub used: usize,
+    pub slab: usize,
 }
-#[global_allocator]
+
+impl HeapStats {
+    /// Bytes ainda disponíveis para alocação
+    pub fn free(&self) -> usize {
+        self.size.saturating_sub(self.used)
+    }
+}
+
+/// Fotografia do uso do heap (contadores atômicos, não toma o lock)
+pub fn stats() -> HeapStats {
+    HeapStats {
+        size: HEAP_SIZE.load(Ordering::Relaxed),
+        used: HEAP_USED.load(Ordering::Relaxed),
+        slab: SLAB_USED.load(Ordering::Relaxed),
+    }
+}
+
+// Nos testes de host o alocador é o da std
+#[cfg_attr(not(test), global_allocator)]
 static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
 /// Orquestrador de Alocação de Memória (Composite: Slab + Buddy)
@@ -82,19 +112,29 @@ impl HeapAllocator {
     pub unsafe fn init(&mut self, start: usize, size: usize) {
         self.buddy.init(start, size);
         self.heap_end = start + size;
+        HEAP_SIZE.store(size, Ordering::Relaxed);
     }
 
     pub unsafe fn alloc(&mut self, layout: Layout) -> *mut u8 {
         // Objetos pequenos (< 2048) vão para Slab, grandes para Buddy
-        if layout.size() <= 2048 {
+        let ptr = if layout.size() <= 2048 {
             self.slab.alloc(layout, &mut self.buddy)
         } else {
             self.buddy.alloc(layout)
+        };
+        if !ptr.is_null() {
+            HEAP_USED.fetch_add(layout.size(), Ordering::Relaxed);
+            if layout.size() <= 2048 {
+                SLAB_USED.fetch_add(layout.size(), Ordering::Relaxed);
+            }
         }
+        ptr
     }
 
     pub unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        HEAP_USED.fetch_sub(layout.size(), Ordering::Relaxed);
         if layout.size() <= 2048 {
+            SLAB_USED.fetch_sub(layout.size(), Ordering::Relaxed);
             // Slab precisa do buddy para liberar objetos oversized
             self.slab.dealloc(ptr, layout, &mut self.buddy)
         } else {
@@ -114,19 +154,19 @@ impl HeapAllocator {
     }
 }
 
-/// Estrutura encapsulando o HeapAllocator protegido por Mutex
+/// Estrutura encapsulando o HeapAllocator protegido por Spinlock
 /// -----------------------------------------------------------
 /// Garante exclusão mútua em cenários multicore simplificados.
 /// Acesso ao allocator deve sempre passar pelo lock.
 pub struct LockedHeap {
-    inner: Mutex<HeapAllocator>,
+    inner: Spinlock<HeapAllocator>,
 }
 
 impl LockedHeap {
     /// Construtor em tempo de compilação — sem heap inicializado
     pub const fn empty() -> Self {
         Self {
-            inner: Mutex::new(HeapAllocator::new()),
+            inner: Spinlock::new(HeapAllocator::new()),
         }
     }
 
@@ -165,37 +205,49 @@ impl LockedHeap {
 unsafe impl GlobalAlloc for LockedHeap {
     /// Aloca memória no heap
     /// ---------------------
-    /// Retorna `null_mut` em caso de OOM.
+    /// Se faltar memória, roda o reclaim (`mm::reclaim::oom`) e tenta de novo,
+    /// até `OOM_RETRIES` vezes. Retorna `null_mut` se ainda assim não houver.
+    ///
+    /// Com `memory_accounting`, a alocação é contabilizada no subsistema
+    /// atual (`mm::accounting`) e falha com `null_mut` se estourar a quota
+    /// dele, sem passar pelo reclaim.
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         // DEBUG desativado - gera muito overhead em loops rápidos
-        // static mut ALLOC_COUNT: usize = 0;
-        // let count = ALLOC_COUNT;
-        // ALLOC_COUNT += 1;
-
-        // crate::ktrace!("(Heap) [H1] alloc entrada, count=", count as u64);
-        // crate::ktrace!("(Heap) [H2] obtendo lock...");
-        let mut guard = self.inner.lock();
+        // crate::ktrace!("(Heap) alloc entrada, size=", layout.size() as u64);
 
-        // crate::ktrace!("(Heap) [H3] lock OK, chamando alloc...");
-        let ptr = guard.alloc(layout);
-
-        // crate::ktrace!("(Heap) [H4] alloc retornou ptr=", ptr as u64);
-
-        if ptr.is_null() {
-            crate::kerror!("(Heap) OOM! size=", layout.size() as u64);
+        #[cfg(feature = "memory_accounting")]
+        if !crate::mm::accounting::on_alloc(layout.size()) {
+            return core::ptr::null_mut();
         }
 
-        // Drop explícito do guard antes de retornar
-        drop(guard);
-        // crate::ktrace!("(Heap) [H5] guard dropped, retornando");
+        // O reclaim roda sem o lock do heap: descartar cache libera objetos
+        // do próprio heap
+        let pages = layout.size().div_ceil(crate::mm::config::PAGE_SIZE).max(1);
+        let ptr = crate::mm::reclaim::oom::retry_after_reclaim(
+            || {
+                let ptr = self.inner.lock().alloc(layout);
+                (!ptr.is_null()).then_some(ptr)
+            },
+            || crate::mm::reclaim::oom::reclaim(pages),
+        );
 
-        ptr
+        match ptr {
+            Some(ptr) => ptr,
+            None => {
+                crate::kerror!("(Heap) OOM! size=", layout.size() as u64);
+                #[cfg(feature = "memory_accounting")]
+                crate::mm::accounting::on_free(layout.size());
+                core::ptr::null_mut()
+            }
+        }
     }
 
     /// Libera memória (apenas decrementa contador lógico)
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         // Sem log aqui - muito frequente
-        self.inner.lock().dealloc(ptr, layout)
+        self.inner.lock().dealloc(ptr, layout);
+        #[cfg(feature = "memory_accounting")]
+        crate::mm::accounting::on_free(layout.size());
     }
 }
 
@@ -214,7 +266,7 @@ pub fn init(pmm: &mut crate::mm::pmm::BitmapFrameAllocator) -> bool {
     // IMPORTANTE: Limitamos a 64 slots (128 MiB max) para garantir que o heap
     // fique dentro da região PML4[288] pré-alocada pelo bootloader.
     // Offset máximo: 128 MiB (64 * 2MB)
-    let tsc = rdtsc();
+    let tsc = crate::arch::Cpu::rdtsc();
     // Usamos máscara 0x3F (64 slots) deslocada por 21 bits (2MB alignment)
     let random_offset = (tsc & 0x3F) as usize * 0x200000;
 
@@ -250,6 +302,13 @@ pub fn init(pmm: &mut crate::mm::pmm::BitmapFrameAllocator) -> bool {
             crate::ktrace!("(Heap) Alocando primeiro frame...");
         }
 
+        // Trechos de 2MB com RAM contígua viram uma huge page
+        if map_huge_chunk(page_addr, heap_end, flags, pmm) {
+            pages_mapped += (huge::HUGE_2MB / 4096) as usize;
+            page_addr += huge::HUGE_2MB as usize;
+            continue;
+        }
+
         let frame = match pmm.allocate_frame() {
             Some(f) => f,
             None => {
@@ -286,6 +345,36 @@ pub fn init(pmm: &mut crate::mm::pmm::BitmapFrameAllocator) -> bool {
     true
 }
 
+/// Mapeia `[page_addr, page_addr + 2MB)` com uma huge page, se o trecho
+/// estiver alinhado, couber no heap e o PMM tiver 512 frames contíguos.
+/// Retorna `false` (sem nada alocado) para o chamador seguir em 4KB.
+fn map_huge_chunk(
+    page_addr: usize,
+    heap_end: usize,
+    flags: u64,
+    pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
+) -> bool {
+    let size = huge::HUGE_2MB as usize;
+    if !huge::is_2mb_aligned(page_addr as u64) || heap_end - page_addr < size {
+        return false;
+    }
+    let frames = size / 4096;
+    let Some(phys) = pmm.allocate_contiguous(frames, frames) else {
+        return false;
+    };
+
+    let flags = MapFlags::from_bits_truncate(flags) | MapFlags::HUGE_2M;
+    if crate::mm::vmm::map_huge_page(page_addr as u64, phys.as_u64(), flags, pmm).is_ok() {
+        return true;
+    }
+
+    // Região já com PT (ex.: pré-mapeada pelo bootloader): devolve os frames
+    for i in 0..frames as u64 {
+        pmm.deallocate_frame(crate::mm::PhysAddr::new(phys.as_u64() + i * 4096));
+    }
+    false
+}
+
 // =============================================================================
 // IMPLEMENTAÇÕES MANUAIS DE ALOCAÇÃO (bypass __rust_alloc gerado)
 // =============================================================================
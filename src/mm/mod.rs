@@ -50,7 +50,7 @@ pub use pmm::{FrameAllocator, PhysFrame, FRAME_ALLOCATOR};
 
 /// Gerenciador de memória virtual
 pub mod vmm;
-pub use vmm::{map_page, translate_addr, unmap_page, MapFlags, PageTable};
+pub use vmm::{map_page, translate_addr, translate_with_flags, unmap_page, MapFlags, PageTable};
 
 // =============================================================================
 // ALLOCATORS
@@ -75,7 +75,8 @@ pub mod error;
 /// Operações de memória (memset, memcpy)
 pub mod ops;
 
-/// Handler de OOM
+/// Handler de OOM (nos testes de host, o da std)
+#[cfg(not(test))]
 pub mod oom;
 
 /// Tipos seguros (VMO, Pinned)
@@ -95,6 +96,9 @@ pub mod accounting;
 /// Higher Half Direct Map
 pub mod hhdm;
 
+/// Janela permanente com toda a RAM (sobre o HHDM)
+pub mod physmap;
+
 /// Early Boot Allocator
 pub mod early;
 
@@ -141,6 +145,9 @@ pub unsafe fn init(boot_info: &'static crate::core::boot::handoff::BootInfo) {
     crate::kinfo!("(MM) Inicializando PMM...");
     pmm::init(boot_info);
 
+    crate::kinfo!("(MM) Inicializando Physmap...");
+    physmap::init(boot_info, &mut pmm::FRAME_ALLOCATOR.lock());
+
     crate::kinfo!("(MM) Inicializando PFM...");
     // TODO: Precisamos de uma lista de FrameInfo alocada no heap ou early
     // Por enquanto, o PFM vai ser inicializado sob demanda ou em uma fase posterior
@@ -149,6 +156,15 @@ pub unsafe fn init(boot_info: &'static crate::core::boot::handoff::BootInfo) {
     crate::kinfo!("(MM) Inicializando Heap...");
     heap::init(&mut *pmm::FRAME_ALLOCATOR.lock());
 
+    #[cfg(feature = "memory_accounting")]
+    accounting::init();
+
+    crate::kinfo!("(MM) Inicializando página zero...");
+    pfm::zero::init_zero_page();
+
+    crate::kinfo!("(MM) Inicializando Page Cache...");
+    cache::pagecache::init_default();
+
     crate::kinfo!("(MM) Memória inicializada");
 }
 
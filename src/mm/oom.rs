@@ -5,8 +5,8 @@ use core::alloc::Layout;
 /// Chamado pelo Rust (`alloc` crate) quando uma alocação falha (retorna null)
 /// e o caller não tratou o erro (ex: `Box::new` falhando).
 ///
-/// Atualmente: Panic Kernel Panic.
-/// Futuro: Tentar recuperar, matar processos, ou hibernar.
+/// O heap já tentou recuperar memória (`mm::reclaim::oom::reclaim`) antes
+/// de devolver null; aqui só resta o pânico.
 #[alloc_error_handler]
 fn alloc_error_handler(layout: Layout) -> ! {
     crate::kerror!("(OOM) CRITICAL: Kernel Out of Memory!");
@@ -86,55 +86,6 @@ impl core::ops::BitOr for FrameFlags {
     }
 }
 
-// =============================================================================
-// RMAP DATA
-// =============================================================================
-
-#[derive(Debug)]
-pub struct RMapData {
-    data: [u64; 2],
-}
-
-impl RMapData {
-    pub const fn new() -> Self {
-        Self { data: [0, 0] }
-    }
-
-    pub fn add(&mut self, pte_addr: u64) {
-        for slot in &mut self.data {
-            if *slot == 0 {
-                *slot = pte_addr;
-                return;
-            }
-        }
-    }
-
-    pub fn remove(&mut self, pte_addr: u64) {
-        for slot in &mut self.data {
-            if *slot == pte_addr {
-                *slot = 0;
-                return;
-            }
-        }
-    }
-
-    pub fn clear(&mut self) {
-        self.data = [0, 0];
-    }
-    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
-        self.data.iter().copied().filter(|&a| a != 0)
-    }
-    pub fn count(&self) -> usize {
-        self.data.iter().filter(|&&a| a != 0).count()
-    }
-}
-
-impl Default for RMapData {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 // =============================================================================
 // FRAME INFO
 // =============================================================================
@@ -146,7 +97,6 @@ pub struct FrameInfo {
     numa_node: u16,
     flags: AtomicU32,
     _pad: u16,
-    rmap: RMapData,
 }
 
 impl FrameInfo {
@@ -157,7 +107,6 @@ impl FrameInfo {
             numa_node: 0,
             flags: AtomicU32::new(0),
             _pad: 0,
-            rmap: RMapData::new(),
         }
     }
 
@@ -188,22 +137,6 @@ impl FrameInfo {
     pub fn set_flags(&self, flags: FrameFlags) {
         self.flags.store(flags.bits(), Ordering::Release);
     }
-
-    pub fn rmap_add(&mut self, pte_addr: u64) {
-        self.rmap.add(pte_addr);
-    }
-    pub fn rmap_remove(&mut self, pte_addr: u64) {
-        self.rmap.remove(pte_addr);
-    }
-    pub fn rmap_clear(&mut self) {
-        self.rmap.clear();
-    }
-    pub fn rmap_iter(&self) -> impl Iterator<Item = u64> + '_ {
-        self.rmap.iter()
-    }
-    pub fn rmap_count(&self) -> usize {
-        self.rmap.count()
-    }
 }
 
 impl Default for FrameInfo {
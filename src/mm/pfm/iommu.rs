@@ -1,8 +1,12 @@
 //! # IOMMU API
 //!
 //! Gerenciamento de frames para DMA.
+//!
+//! - [`DmaRegion`]: região pinada associada a um dispositivo (liberação manual).
+//! - [`DmaBuffer`]: buffer contíguo do kernel para drivers, liberado no `Drop`.
 
-use super::{frame::FrameFlags, PfmResult, Pid};
+use super::{frame::FrameFlags, PfmError, PfmResult, Pid, PID_KERNEL};
+use crate::mm::config::PAGE_SIZE;
 use crate::mm::PhysAddr;
 
 pub struct DmaRegion {
@@ -34,14 +38,103 @@ pub fn alloc_dma_region(size: usize, owner: Pid, device_id: u32) -> PfmResult<Dm
 }
 
 pub fn free_dma_region(region: &DmaRegion) -> PfmResult<()> {
-    let pages = (region.size + crate::mm::config::PAGE_SIZE - 1) / crate::mm::config::PAGE_SIZE;
-    let mut pfm = super::get().lock();
+    let pages = (region.size + PAGE_SIZE - 1) / PAGE_SIZE;
+    super::get()
+        .lock()
+        .free_contiguous(region.phys_start, pages, region.owner)
+}
 
-    for i in 0..pages {
-        let addr =
-            PhysAddr::new(region.phys_start.as_u64() + (i * crate::mm::config::PAGE_SIZE) as u64);
-        let _ = pfm.unpin_frame(addr, region.owner);
-        let _ = pfm.free_frame(addr, region.owner);
+// ============================================================================
+// DMA BUFFER
+// ============================================================================
+
+/// Buffer fisicamente contíguo para descritores e dados de DMA.
+///
+/// A memória é zerada na alocação e acessada pelo kernel via HHDM.
+/// Os frames voltam ao PMM quando o buffer é destruído.
+pub struct DmaBuffer {
+    phys: PhysAddr,
+    virt: *mut u8,
+    len: usize,
+    pages: usize,
+}
+
+// SAFETY: o buffer é dono exclusivo dos frames; sincronização fica a cargo do driver
+unsafe impl Send for DmaBuffer {}
+unsafe impl Sync for DmaBuffer {}
+
+impl DmaBuffer {
+    /// Aloca `len` bytes contíguos alinhados a página (4KB).
+    pub fn new(len: usize) -> PfmResult<Self> {
+        Self::with_align(len, PAGE_SIZE)
+    }
+
+    /// Aloca `len` bytes contíguos com o endereço físico alinhado a `align` bytes.
+    pub fn with_align(len: usize, align: usize) -> PfmResult<Self> {
+        if len == 0 {
+            return Err(PfmError::OutOfBounds);
+        }
+        let pages = (len + PAGE_SIZE - 1) / PAGE_SIZE;
+        let phys = super::get().lock().alloc_contiguous_aligned(
+            PID_KERNEL,
+            pages,
+            align,
+            FrameFlags::empty(),
+        )?;
+
+        // SAFETY: os frames acabaram de ser alocados e são exclusivos deste buffer
+        let virt = unsafe { crate::mm::addr::phys_to_virt::<u8>(phys.as_u64()) };
+        unsafe {
+            core::ptr::write_bytes(virt, 0, pages * PAGE_SIZE);
+        }
+
+        Ok(Self {
+            phys,
+            virt,
+            len,
+            pages,
+        })
+    }
+
+    /// Endereço físico base (para programar o dispositivo)
+    #[inline]
+    pub fn phys(&self) -> PhysAddr {
+        self.phys
+    }
+
+    /// Ponteiro virtual para acesso pelo kernel
+    #[inline]
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.virt
+    }
+
+    /// Tamanho solicitado em bytes
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Sempre `false`: um buffer vazio não pode ser alocado
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Número de frames ocupados
+    #[inline]
+    pub fn pages(&self) -> usize {
+        self.pages
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        if super::get()
+            .lock()
+            .free_contiguous(self.phys, self.pages, PID_KERNEL)
+            .is_err()
+        {
+            crate::kerror!("(DMA) Falha ao liberar buffer:", self.phys.as_u64());
+        }
     }
-    Ok(())
 }
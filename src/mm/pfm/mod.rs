@@ -28,7 +28,7 @@ pub enum PfmError {
 
 pub type PfmResult<T> = Result<T, PfmError>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct PfmStats {
     pub total_frames: u64,
     pub free_frames: u64,
@@ -115,20 +115,94 @@ impl PageFrameManager {
         Ok(phys)
     }
 
+    /// Aloca `count` frames fisicamente contíguos, alinhados a 4KB.
     pub fn alloc_contiguous(
         &mut self,
         owner: Pid,
         count: usize,
         flags: FrameFlags,
     ) -> PfmResult<PhysAddr> {
-        if count == 0 {
+        self.alloc_contiguous_aligned(owner, count, crate::mm::config::PAGE_SIZE, flags)
+    }
+
+    /// Aloca `count` frames fisicamente contíguos cujo endereço base é múltiplo
+    /// de `align` bytes (potência de 2, no mínimo `PAGE_SIZE`).
+    pub fn alloc_contiguous_aligned(
+        &mut self,
+        owner: Pid,
+        count: usize,
+        align: usize,
+        flags: FrameFlags,
+    ) -> PfmResult<PhysAddr> {
+        if count == 0 || !align.is_power_of_two() {
             return Err(PfmError::OutOfBounds);
         }
-        let first = self.alloc_frame(owner, flags)?;
-        for _ in 1..count {
-            let _ = self.alloc_frame(owner, flags);
+        let align_frames = core::cmp::max(align / crate::mm::config::PAGE_SIZE, 1);
+
+        let base = crate::mm::pmm::FRAME_ALLOCATOR
+            .lock()
+            .allocate_contiguous(count, align_frames)
+            .ok_or(PfmError::OutOfMemory)?;
+
+        let state = if owner == PID_KERNEL {
+            FrameState::Kernel
+        } else {
+            FrameState::Owned { owner }
+        };
+
+        for i in 0..count {
+            let phys = PhysAddr::new(base.as_u64() + (i * crate::mm::config::PAGE_SIZE) as u64);
+            if let Some(index) = self.phys_to_index(phys) {
+                if let Some(frames) = &mut self.frames {
+                    frames[index].set_state(state);
+                    frames[index].set_flags(flags);
+                    frames[index].set_ref_count(1);
+                }
+            }
+        }
+        self.stats.free_frames = self.stats.free_frames.saturating_sub(count as u64);
+        self.stats.allocations += count as u64;
+        Ok(base)
+    }
+
+    /// Libera um bloco alocado por `alloc_contiguous`.
+    ///
+    /// Frames pinados ou de dispositivo são aceitos: o bloco inteiro volta ao PMM.
+    /// Tudo ou nada: se algum frame já estiver livre ou for de outro dono,
+    /// retorna o erro sem liberar nenhum.
+    pub fn free_contiguous(&mut self, base: PhysAddr, count: usize, owner: Pid) -> PfmResult<()> {
+        let page = crate::mm::config::PAGE_SIZE as u64;
+        let frame_at = |i: usize| PhysAddr::new(base.as_u64() + i as u64 * page);
+
+        for i in 0..count {
+            let Some(index) = self.phys_to_index(frame_at(i)) else {
+                continue;
+            };
+            if let Some(frames) = &self.frames {
+                match frames[index].state() {
+                    FrameState::Free => return Err(PfmError::AlreadyFree),
+                    FrameState::Owned { owner: o } if o != owner => return Err(PfmError::NotOwner),
+                    _ => {}
+                }
+            }
+        }
+
+        for i in 0..count {
+            let phys = frame_at(i);
+            if let Some(index) = self.phys_to_index(phys) {
+                if let Some(frames) = &mut self.frames {
+                    rmap::clear(phys);
+                    frames[index].set_ref_count(0);
+                    frames[index].set_state(FrameState::Free);
+                }
+            }
+            crate::mm::pmm::FRAME_ALLOCATOR
+                .lock()
+                .deallocate_frame(phys);
         }
-        Ok(first)
+        self.stats.free_frames += count as u64;
+        self.stats.frees += count as u64;
+        Ok(())
     }
 
     pub fn free_frame(&mut self, phys: PhysAddr, owner: Pid) -> PfmResult<()> {
@@ -145,7 +219,7 @@ impl PageFrameManager {
             }
             let new_count = frame.dec_ref_count();
             if new_count == 0 {
-                frame.rmap_clear();
+                rmap::clear(phys);
                 frame.set_state(FrameState::Free);
                 crate::mm::pmm::FRAME_ALLOCATOR
                     .lock()
@@ -157,6 +231,43 @@ impl PageFrameManager {
         Ok(())
     }
 
+    /// Solta uma referência de mapeamento a `phys` e libera o frame se ela
+    /// era a última.
+    ///
+    /// Consulta e decremento acontecem sob o mesmo lock do PFM: dois address
+    /// spaces soltando o mesmo frame CoW não podem ambos ler contagem 2 (e
+    /// vazar o frame) nem ambos ler 1 (e liberá-lo duas vezes). Retorna `true`
+    /// se o frame voltou ao PMM. Frames fora do PFM, pinados ou de
+    /// dispositivo ficam como estão.
+    pub fn release_mapped(&mut self, phys: PhysAddr) -> bool {
+        let Some(index) = self.phys_to_index(phys) else {
+            return false;
+        };
+        let Some(frames) = &mut self.frames else {
+            return false;
+        };
+        let frame = &mut frames[index];
+        if matches!(frame.state(), FrameState::Free | FrameState::Device) {
+            return false;
+        }
+        if frame.ref_count() > 1 {
+            frame.dec_ref_count();
+            return false;
+        }
+        if matches!(frame.state(), FrameState::Pinned { .. }) {
+            return false;
+        }
+        rmap::clear(phys);
+        frame.set_ref_count(0);
+        frame.set_state(FrameState::Free);
+        crate::mm::pmm::FRAME_ALLOCATOR
+            .lock()
+            .deallocate_frame(phys);
+        self.stats.free_frames += 1;
+        self.stats.frees += 1;
+        true
+    }
+
     pub fn inc_ref(&mut self, phys: PhysAddr) -> PfmResult<u32> {
         let index = self.phys_to_index(phys).ok_or(PfmError::FrameNotFound)?;
         if let Some(frames) = &mut self.frames {
@@ -173,6 +284,14 @@ impl PageFrameManager {
         Err(PfmError::FrameNotFound)
     }
 
+    pub fn get_ref_count(&self, phys: PhysAddr) -> PfmResult<u32> {
+        let index = self.phys_to_index(phys).ok_or(PfmError::FrameNotFound)?;
+        if let Some(frames) = &self.frames {
+            return Ok(frames[index].ref_count());
+        }
+        Err(PfmError::FrameNotFound)
+    }
+
     pub fn get_state(&self, phys: PhysAddr) -> PfmResult<FrameState> {
         let index = self.phys_to_index(phys).ok_or(PfmError::FrameNotFound)?;
         if let Some(frames) = &self.frames {
@@ -275,6 +394,10 @@ pub fn free_frame(phys: PhysAddr, owner: Pid) -> PfmResult<()> {
     get().lock().free_frame(phys, owner)
 }
 
+pub fn release_mapped(phys: PhysAddr) -> bool {
+    get().lock().release_mapped(phys)
+}
+
 pub fn inc_ref(phys: PhysAddr) -> PfmResult<u32> {
     get().lock().inc_ref(phys)
 }
@@ -1,12 +1,25 @@
 //! # Reverse Mappings (RMAP)
 //!
-//! Rastreia PTEs que apontam para um frame físico.
+//! Rastreia as PTEs que apontam para um frame físico: para cada frame, a
+//! lista de (address space, endereço virtual) que o mapeiam. Swap-out e COW
+//! usam a lista para achar e atualizar todas as PTEs de um frame.
+//!
+//! O mapper (`vmm::mapper`) mantém a tabela em dia a cada escrita de PTE de
+//! usuário. Mapeamentos de kernel não entram: não vão para o swap nem são
+//! COW, e o heap é mapeado antes de existir heap para a tabela. A página
+//! zero também fica de fora (está em todo address space e nunca sai da RAM).
+//!
+//! O address space é identificado pelo endereço físico da sua PML4 (o CR3).
+
+extern crate alloc;
 
 use super::PfmResult;
 use crate::mm::PhysAddr;
 use crate::sync::Spinlock;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RMapEntry {
     pub aspace_id: u64,
     pub virt_addr: u64,
@@ -21,47 +34,164 @@ impl RMapEntry {
     }
 }
 
-/// Adiciona entrada de rmap
-pub fn add(phys: PhysAddr, aspace_id: u64, virt_addr: u64) -> PfmResult<()> {
-    let pte_addr = (aspace_id << 48) | (virt_addr & 0x0000_FFFF_FFFF_F000);
-    if let Some(frames) = super::get().lock().frames.as_mut() {
-        let base = super::get().lock().base_phys;
-        let index = ((phys.as_u64() - base) / crate::mm::config::PAGE_SIZE as u64) as usize;
-        if index < frames.len() {
-            frames[index].rmap_add(pte_addr);
+/// Mapeamentos reversos, por frame
+pub struct RMapTable {
+    frames: BTreeMap<u64, Vec<RMapEntry>>,
+}
+
+impl RMapTable {
+    pub const fn new() -> Self {
+        Self {
+            frames: BTreeMap::new(),
+        }
+    }
+
+    /// Registra que `entry` aponta para `frame` (sem duplicar)
+    pub fn add(&mut self, frame: u64, entry: RMapEntry) {
+        let entries = self.frames.entry(frame).or_default();
+        if !entries.contains(&entry) {
+            entries.push(entry);
         }
     }
+
+    /// Esquece `entry` em `frame`; retorna se ela existia
+    pub fn remove(&mut self, frame: u64, entry: RMapEntry) -> bool {
+        let Some(entries) = self.frames.get_mut(&frame) else {
+            return false;
+        };
+        let Some(pos) = entries.iter().position(|e| *e == entry) else {
+            return false;
+        };
+        entries.swap_remove(pos);
+        if entries.is_empty() {
+            self.frames.remove(&frame);
+        }
+        true
+    }
+
+    /// Mapeamentos de `frame`
+    pub fn entries(&self, frame: u64) -> &[RMapEntry] {
+        self.frames.get(&frame).map_or(&[], Vec::as_slice)
+    }
+
+    /// Tira e devolve todos os mapeamentos de `frame`
+    pub fn take(&mut self, frame: u64) -> Vec<RMapEntry> {
+        self.frames.remove(&frame).unwrap_or_default()
+    }
+
+    /// Esquece tudo o que `aspace_id` mapeia; retorna quantas entradas saíram
+    pub fn forget_aspace(&mut self, aspace_id: u64) -> usize {
+        let mut removed = 0;
+        self.frames.retain(|_, entries| {
+            let before = entries.len();
+            entries.retain(|e| e.aspace_id != aspace_id);
+            removed += before - entries.len();
+            !entries.is_empty()
+        });
+        removed
+    }
+}
+
+impl Default for RMapTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static RMAP: Spinlock<RMapTable> = Spinlock::new(RMapTable::new());
+
+/// Endereço do frame de 4KB que contém `phys`
+fn frame_of(phys: PhysAddr) -> u64 {
+    phys.as_u64() & !(crate::mm::config::PAGE_SIZE as u64 - 1)
+}
+
+/// Endereço da página de 4KB que contém `virt_addr`
+fn page_of(virt_addr: u64) -> u64 {
+    virt_addr & !(crate::mm::config::PAGE_SIZE as u64 - 1)
+}
+
+/// Adiciona entrada de rmap
+pub fn add(phys: PhysAddr, aspace_id: u64, virt_addr: u64) -> PfmResult<()> {
+    let entry = RMapEntry::new(aspace_id, page_of(virt_addr));
+    RMAP.lock().add(frame_of(phys), entry);
     Ok(())
 }
 
 /// Remove entrada de rmap
 pub fn remove(phys: PhysAddr, aspace_id: u64, virt_addr: u64) -> PfmResult<()> {
-    let pte_addr = (aspace_id << 48) | (virt_addr & 0x0000_FFFF_FFFF_F000);
-    if let Some(frames) = super::get().lock().frames.as_mut() {
-        let base = super::get().lock().base_phys;
-        let index = ((phys.as_u64() - base) / crate::mm::config::PAGE_SIZE as u64) as usize;
-        if index < frames.len() {
-            frames[index].rmap_remove(pte_addr);
-        }
-    }
+    let entry = RMapEntry::new(aspace_id, page_of(virt_addr));
+    RMAP.lock().remove(frame_of(phys), entry);
     Ok(())
 }
 
 /// Conta mapeamentos de um frame
 pub fn count(phys: PhysAddr) -> PfmResult<usize> {
-    let pfm = super::get().lock();
-    if let Some(frames) = &pfm.frames {
-        let base = pfm.base_phys;
-        let index = ((phys.as_u64() - base) / crate::mm::config::PAGE_SIZE as u64) as usize;
-        if index < frames.len() {
-            return Ok(frames[index].rmap_count());
+    Ok(RMAP.lock().entries(frame_of(phys)).len())
+}
+
+/// Mapeamentos de um frame
+pub fn entries(phys: PhysAddr) -> Vec<RMapEntry> {
+    RMAP.lock().entries(frame_of(phys)).to_vec()
+}
+
+/// Esquece os mapeamentos de um frame que volta a ficar livre
+pub fn clear(phys: PhysAddr) {
+    RMAP.lock().take(frame_of(phys));
+}
+
+/// Esquece os mapeamentos de um address space que está sendo destruído
+pub fn forget_aspace(aspace_id: u64) -> usize {
+    RMAP.lock().forget_aspace(aspace_id)
+}
+
+/// Unmapeia de todos os address spaces; retorna quantas PTEs foram limpas
+pub fn unmap_all(phys: PhysAddr) -> PfmResult<usize> {
+    use crate::mm::vmm::mapper::{read_cr3, write_pte_in_p4};
+
+    // Escrever as PTEs sem o lock: o mapper atualiza o rmap ao escrever
+    let entries = RMAP.lock().take(frame_of(phys));
+    let current = read_cr3();
+    let mut cleared = 0;
+    for entry in entries {
+        // SAFETY: a PTE deixa de apontar para o frame; o TLB é invalidado
+        // abaixo se o address space estiver ativo
+        if unsafe { write_pte_in_p4(entry.aspace_id, entry.virt_addr, 0) }.is_ok() {
+            cleared += 1;
+        }
+        if entry.aspace_id == current {
+            crate::mm::vmm::tlb::flush(entry.virt_addr);
         }
     }
-    Ok(0)
+    Ok(cleared)
 }
 
-/// Unmapeia de todos os address spaces
-pub fn unmap_all(_phys: PhysAddr) -> PfmResult<usize> {
-    // TODO: Iterar sobre entries e invalidar PTEs
-    Ok(0)
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_frame_tracks_each_aspace() {
+        let mut table = RMapTable::new();
+        let frame = 0x20_0000;
+        let a = RMapEntry::new(0x1000, 0x40_0000);
+        let b = RMapEntry::new(0x2000, 0x7000_0000);
+
+        table.add(frame, a);
+        table.add(frame, b);
+        table.add(frame, a);
+        assert_eq!(table.entries(frame), &[a, b]);
+
+        assert!(table.remove(frame, a));
+        assert!(!table.remove(frame, a));
+        assert_eq!(table.entries(frame), &[b]);
+
+        table.add(0x30_0000, RMapEntry::new(0x2000, 0x7000_1000));
+        assert_eq!(table.forget_aspace(0x2000), 2);
+        assert!(table.entries(frame).is_empty());
+        assert!(table.entries(0x30_0000).is_empty());
+    }
 }
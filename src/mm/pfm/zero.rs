@@ -1,6 +1,12 @@
 //! # Zero-on-Alloc
 //!
 //! Zeragem de páginas para segurança e zero-fill-on-demand.
+//!
+//! ## Página zero
+//! Uma única página zerada, alocada no boot, para onde apontam as páginas
+//! anônimas ainda não escritas: somente leitura e COW, a primeira escrita
+//! troca a PTE por um frame próprio (`fault::resolve_cow`). Um mapeamento
+//! anônimo grande só consome frames nas páginas escritas.
 
 use crate::mm::PhysAddr;
 use core::sync::atomic::{AtomicU64, Ordering};
@@ -9,6 +15,56 @@ use core::sync::atomic::{AtomicU64, Ordering};
 pub static PAGES_ZEROED: AtomicU64 = AtomicU64::new(0);
 pub static BYTES_ZEROED: AtomicU64 = AtomicU64::new(0);
 
+/// Frame da página zero (0 = ainda não alocada)
+static ZERO_PAGE: AtomicU64 = AtomicU64::new(0);
+
+/// Bit WP do CR0: o kernel também respeita páginas somente leitura
+const CR0_WP: u64 = 1 << 16;
+
+/// Aloca a página zero compartilhada (uma vez, no boot)
+pub fn init_zero_page() {
+    if ZERO_PAGE.load(Ordering::Acquire) != 0 {
+        return;
+    }
+    let Some(phys) = crate::mm::pmm::FRAME_ALLOCATOR.lock().allocate_frame() else {
+        crate::kerror!("(MM) Sem frame para a página zero");
+        return;
+    };
+    zero_page(phys);
+
+    // Nunca volta ao PMM nem sai da RAM (sem efeito se o PFM não rastreia)
+    {
+        let mut pfm = super::get().lock();
+        let _ = pfm.mark_kernel(phys);
+        let _ = pfm.pin_frame(phys, super::PID_KERNEL);
+    }
+
+    // Sem WP, uma escrita do kernel numa página de usuário somente leitura
+    // (copy_to_user) passaria direto e sujaria a página zero
+    let cr0 = crate::arch::Cpu::read_cr0();
+    if cr0 & CR0_WP == 0 {
+        // SAFETY: só liga a proteção de escrita em Ring 0
+        unsafe { crate::arch::Cpu::write_cr0(cr0 | CR0_WP) };
+    }
+
+    ZERO_PAGE.store(phys.as_u64(), Ordering::Release);
+    crate::kinfo!("(MM) Página zero em", phys.as_u64());
+}
+
+/// Frame da página zero, se já alocada
+pub fn zero_page_frame() -> Option<PhysAddr> {
+    match ZERO_PAGE.load(Ordering::Acquire) {
+        0 => None,
+        phys => Some(PhysAddr::new(phys)),
+    }
+}
+
+/// `phys` é a página zero compartilhada
+pub fn is_zero_page(phys: PhysAddr) -> bool {
+    let zero = ZERO_PAGE.load(Ordering::Acquire);
+    zero != 0 && phys.as_u64() & !(crate::mm::config::PAGE_SIZE as u64 - 1) == zero
+}
+
 /// Zera uma página física
 #[inline]
 pub fn zero_page(phys: PhysAddr) {
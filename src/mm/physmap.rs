@@ -0,0 +1,193 @@
+//! # Physmap
+//!
+//! Janela permanente com toda a RAM física, em `hhdm::offset() + phys`.
+//!
+//! O bootloader entrega um HHDM, mas sem garantia de cobrir toda a RAM do
+//! mapa de memória. No boot, [`init`] percorre o mapa e mapeia na janela o
+//! que faltar, com a maior página que o alinhamento permitir (1GB, 2MB,
+//! 4KB), escrevível, não executável e global.
+//!
+//! Daí em diante, qualquer frame de RAM é acessível por [`phys_to_virt`], sem
+//! mapeamento temporário e com qualquer CR3 ativo: a janela fica na metade
+//! do kernel, copiada em toda PML4. É por ela que se zera um frame novo, se
+//! copia para outro address space (loader ELF) e se lê/escreve página de
+//! swap e de page cache.
+//!
+//! Só entra RAM (usável, ACPI, bootloader, kernel). Reservado, memória ruim
+//! e framebuffer ficam de fora: MMIO não tem lugar na janela.
+
+use crate::core::boot::handoff::{BootInfo, MemoryMapEntry, MemoryType};
+use crate::mm::config::PAGE_SIZE;
+use crate::mm::vmm::huge::HugePageSize;
+use crate::mm::vmm::mapper;
+use crate::mm::{MapFlags, PhysAddr, VirtAddr};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Fim (exclusivo) da RAM na janela; 0 antes de [`init`]
+static PHYSMAP_END: AtomicU64 = AtomicU64::new(0);
+
+/// A região do mapa de memória é RAM?
+fn is_ram(typ: MemoryType) -> bool {
+    matches!(
+        typ,
+        MemoryType::Usable
+            | MemoryType::AcpiReclaimable
+            | MemoryType::AcpiNvs
+            | MemoryType::BootloaderReclaimable
+            | MemoryType::KernelAndModules
+    )
+}
+
+/// Faixas `[início, fim)` de RAM do mapa, alinhadas a página (para fora)
+fn ram_ranges(regions: &[MemoryMapEntry]) -> impl Iterator<Item = (u64, u64)> + '_ {
+    let page = PAGE_SIZE as u64;
+    regions
+        .iter()
+        .filter(|r| is_ram(r.typ) && r.len != 0)
+        .map(move |r| {
+            (
+                r.base & !(page - 1),
+                (r.base + r.len + page - 1) & !(page - 1),
+            )
+        })
+}
+
+/// Mapeia na janela a RAM do mapa de memória que ainda não está lá
+///
+/// Chamado uma vez, depois do PMM (as tabelas novas vêm dele) e antes de
+/// qualquer address space de usuário (que copiam a metade do kernel).
+pub fn init(boot_info: &BootInfo, pmm: &mut crate::mm::pmm::BitmapFrameAllocator) {
+    let regions = unsafe {
+        core::slice::from_raw_parts(
+            boot_info.memory_map_addr as *const MemoryMapEntry,
+            boot_info.memory_map_len as usize,
+        )
+    };
+    let offset = crate::mm::hhdm::offset();
+    let pml4 = mapper::read_cr3();
+
+    let mut end = 0;
+    let mut added = 0u64;
+    for (start, stop) in ram_ranges(regions) {
+        let mut phys = start;
+        while phys < stop {
+            let virt = offset + phys;
+            // Já na janela (HHDM do bootloader): pula a página inteira
+            if let Some(size) = mapper::page_size_in_p4(pml4, virt) {
+                phys = (phys & !(size - 1)) + size;
+                continue;
+            }
+            match map_chunk(virt, phys, stop - phys, pmm) {
+                Ok(size) => {
+                    phys += size;
+                    added += size;
+                }
+                Err(e) => {
+                    crate::kerror!("(Physmap) Falha ao mapear:", e);
+                    crate::kerror!("(Physmap) Endereço físico=", phys);
+                    break;
+                }
+            }
+        }
+        end = end.max(stop);
+    }
+
+    PHYSMAP_END.store(end, Ordering::Release);
+    crate::kinfo!("(Physmap) RAM na janela até=", end);
+    crate::kinfo!("(Physmap) Bytes mapeados pelo kernel=", added);
+}
+
+/// Mapeia `virt -> phys` com a maior página que cabe em `len` bytes e
+/// retorna o tamanho usado
+///
+/// Uma huge page cujo lugar já tem tabela (parte da faixa mapeada em páginas
+/// menores) cai para o tamanho seguinte.
+fn map_chunk(
+    virt: u64,
+    phys: u64,
+    len: u64,
+    pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
+) -> Result<u64, &'static str> {
+    let flags = MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::GLOBAL | MapFlags::NO_EXECUTE;
+    for size in [HugePageSize::Page1GB, HugePageSize::Page2MB] {
+        if size.fits(virt, phys, len)
+            && mapper::map_huge_page(virt, phys, flags | size.flag(), pmm).is_ok()
+        {
+            return Ok(size.size());
+        }
+    }
+    mapper::map_page_with_pmm(virt, phys, flags, pmm)?;
+    Ok(PAGE_SIZE as u64)
+}
+
+/// Endereço de `phys` na janela
+///
+/// `phys` tem de ser RAM (ver [`contains`]).
+#[inline]
+pub fn phys_to_virt(phys: PhysAddr) -> VirtAddr {
+    VirtAddr::new(crate::mm::hhdm::offset() + phys.as_u64())
+}
+
+/// Endereço físico de um ponteiro da janela, `None` se estiver fora dela
+#[inline]
+pub fn virt_to_phys(virt: VirtAddr) -> Option<PhysAddr> {
+    let phys = virt.as_u64().checked_sub(crate::mm::hhdm::offset())?;
+    contains(PhysAddr::new(phys)).then_some(PhysAddr::new(phys))
+}
+
+/// `phys` está abaixo do fim da RAM da janela?
+///
+/// Buracos do mapa de memória (reservado, MMIO) abaixo do fim não contam
+/// como mapeados, mesmo que aqui deem `true`.
+#[inline]
+pub fn contains(phys: PhysAddr) -> bool {
+    phys.as_u64() < PHYSMAP_END.load(Ordering::Acquire)
+}
+
+/// Zera o frame em `frame` pela janela
+///
+/// # Safety
+/// `frame` é RAM e exclusivo de quem chama.
+pub unsafe fn zero_frame(frame: PhysAddr) {
+    core::ptr::write_bytes(phys_to_virt(frame).as_mut_ptr::<u8>(), 0, PAGE_SIZE);
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(base: u64, len: u64, typ: MemoryType) -> MemoryMapEntry {
+        MemoryMapEntry { base, len, typ }
+    }
+
+    #[test]
+    fn test_ram_ranges() {
+        let regions = [
+            entry(0, 0x9_FC00, MemoryType::Usable),
+            entry(0x9_FC00, 0x400, MemoryType::Reserved),
+            entry(0x10_0000, 0x7EE_0000, MemoryType::Usable),
+            entry(0x7FE_0000, 0x2_0000, MemoryType::AcpiReclaimable),
+            entry(0x8000_0000, 0x100_0000, MemoryType::Framebuffer),
+            entry(0xFEE0_0000, 0x1000, MemoryType::Reserved),
+            entry(0x1_0000_0000, 0, MemoryType::Usable),
+            entry(0x1_0000_0800, 0x1000, MemoryType::KernelAndModules),
+        ];
+
+        let ranges: alloc::vec::Vec<_> = ram_ranges(&regions).collect();
+        assert_eq!(
+            ranges,
+            alloc::vec![
+                // Fim no meio de uma página: arredonda para cima
+                (0, 0xA_0000),
+                (0x10_0000, 0x7FE_0000),
+                (0x7FE_0000, 0x800_0000),
+                // Início no meio de uma página: arredonda para baixo
+                (0x1_0000_0000, 0x1_0000_2000),
+            ]
+        );
+    }
+}
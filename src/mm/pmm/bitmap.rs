@@ -498,6 +498,46 @@ impl BitmapFrameAllocator {
         None
     }
 
+    /// Aloca `count` frames físicos contíguos.
+    ///
+    /// `align_frames` é o alinhamento do primeiro frame, em frames (potência de 2).
+    /// Retorna o endereço físico base do bloco, ou `None` se não existir uma
+    /// sequência livre grande o suficiente.
+    ///
+    /// Deve ser chamado com o lock de `FRAME_ALLOCATOR` adquirido: a busca e a
+    /// marcação não são atômicas como um todo.
+    pub fn allocate_contiguous(&self, count: usize, align_frames: usize) -> Option<PhysAddr> {
+        if count == 0 || !align_frames.is_power_of_two() {
+            return None;
+        }
+
+        let align = align_frames as u64;
+        let total = self.total_frames as u64;
+        let mut start = (FIRST_ALLOCATABLE_FRAME + align - 1) & !(align - 1);
+
+        while start + count as u64 <= total {
+            // Procurar o primeiro frame ocupado dentro da janela candidata
+            let busy = (start..start + count as u64).find(|&f| self.is_frame_used(f));
+
+            match busy {
+                None => {
+                    for frame in start..start + count as u64 {
+                        self.mark_frame(frame, true);
+                        self.stats.inc_alloc();
+                    }
+                    return Some(PhysAddr::new(start * PAGE_SIZE));
+                }
+                Some(used) => {
+                    // Recomeçar após o frame ocupado, respeitando o alinhamento
+                    start = (used + 1 + align - 1) & !(align - 1);
+                }
+            }
+        }
+
+        self.stats.failed_allocs.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
     /// Desaloca um frame físico
     pub fn deallocate_frame(&self, frame: PhysAddr) {
         let frame_idx = frame.as_u64() / PAGE_SIZE;
@@ -510,6 +550,11 @@ impl BitmapFrameAllocator {
         self.total_frames
     }
 
+    /// Retorna o número de frames em uso
+    pub fn used_frames(&self) -> usize {
+        self.stats.used_frames.load(Ordering::Relaxed)
+    }
+
     /// Verifica se um frame específico está marcado como usado
     pub fn is_frame_used(&self, frame_idx: u64) -> bool {
         if frame_idx >= self.total_frames as u64 {
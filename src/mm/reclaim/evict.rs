@@ -8,22 +8,30 @@ use core::sync::atomic::{AtomicU64, Ordering};
 /// Páginas evicted
 pub static PAGES_EVICTED: AtomicU64 = AtomicU64::new(0);
 
-/// Evicta páginas para liberar memória
+/// Evicta páginas para liberar memória; retorna quantas saíram
 pub fn evict_pages(target_pages: usize) -> usize {
     let mut evicted = 0;
 
-    for _ in 0..target_pages {
-        // TODO: Obter candidato do page ager
-        // TODO: Usar rmap para unmapear de todos os processos
-        // TODO: Escrever em swap se necessário
-        // TODO: Liberar frame
+    while evicted < target_pages {
+        let Some(phys) = next_candidate() else {
+            break;
+        };
+        if !evict_page(phys) {
+            break;
+        }
         evicted += 1;
     }
 
-    PAGES_EVICTED.fetch_add(evicted as u64, Ordering::Relaxed);
     evicted
 }
 
+/// Próxima página fria a evictar
+fn next_candidate() -> Option<PhysAddr> {
+    // TODO: Obter candidato do page ager (ainda não rastreia páginas
+    // anônimas); até lá não há o que evictar
+    None
+}
+
 /// Evicta uma página específica
 pub fn evict_page(phys: PhysAddr) -> bool {
     // 1. Obter rmap do frame
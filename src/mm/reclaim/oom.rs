@@ -1,56 +1,204 @@
 //! # OOM Killer
 //!
-//! Mata processos quando não há mais memória.
+//! Recupera memória quando uma alocação falha, do mais barato ao mais
+//! drástico:
+//! 1. descarta páginas limpas do page cache;
+//! 2. com swap habilitado, evicta páginas anônimas para o swap;
+//! 3. só se nada disso liberou memória, mata a task que mais ocupa RAM e
+//!    solta o espaço de endereçamento dela.
+//!
+//! O heap do kernel ([`LockedHeap`](crate::mm::heap::LockedHeap)) chama
+//! [`reclaim`] ao falhar e tenta a alocação de novo, até [`OOM_RETRIES`]
+//! vezes. O kill roda ali mesmo, no contexto de quem alocou: os locks que
+//! ele toma (filas do scheduler, zumbis) são spinlocks, então com as
+//! interrupções ligadas o chamador não segura nenhum deles. Em contexto
+//! atômico (interrupções desligadas) o reclaim só descarta cache e evicta.
 
-use core::sync::atomic::{AtomicU64, Ordering};
+use crate::sched::core::TaskMemory;
+use crate::sched::task::TaskState;
+use crate::sys::types::Tid;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 /// Processos mortos por OOM
 pub static OOM_KILLS: AtomicU64 = AtomicU64::new(0);
 
+/// Há um reclaim em andamento (uma alocação dentro dele não recomeça)
+static RECLAIMING: AtomicBool = AtomicBool::new(false);
+
+/// Rodadas de reclaim antes de uma alocação desistir
+///
+/// Os frames da vítima podem não bastar, ou outra CPU pode levá-los antes
+/// da nova tentativa: cada rodada pode matar mais uma task.
+pub const OOM_RETRIES: usize = 3;
+
+/// Tenta liberar `target` páginas; retorna `true` se liberou alguma coisa
+/// (vale tentar a alocação de novo).
+///
+/// Sem nada liberado e fora de contexto atômico, mata uma task
+/// ([`oom_kill`]) antes de retornar.
+pub fn reclaim(target: usize) -> bool {
+    if RECLAIMING.swap(true, Ordering::Acquire) {
+        return false;
+    }
+
+    let mut freed = crate::mm::cache::pagecache::shrink_clean(target);
+    if freed < target && crate::mm::swap::is_enabled() {
+        freed += super::evict::evict_pages(target - freed);
+    }
+    let progress = freed > 0 || (crate::arch::Cpu::interrupts_enabled() && oom_kill());
+
+    RECLAIMING.store(false, Ordering::Release);
+    progress
+}
+
+/// Tenta `alloc`; enquanto falhar e `reclaim` liberar algo, tenta de novo,
+/// até [`OOM_RETRIES`] vezes
+pub fn retry_after_reclaim<T>(
+    mut alloc: impl FnMut() -> Option<T>,
+    mut reclaim: impl FnMut() -> bool,
+) -> Option<T> {
+    for _ in 0..OOM_RETRIES {
+        if let Some(value) = alloc() {
+            return Some(value);
+        }
+        if !reclaim() {
+            return None;
+        }
+    }
+    alloc()
+}
+
 /// Seleciona e mata um processo para liberar memória
+///
+/// Toma os spinlocks do scheduler: não chamar com as interrupções
+/// desligadas (algum deles pode já estar com quem chama).
 pub fn oom_kill() -> bool {
-    crate::kerror!("(OOM) Out of memory! Selecting victim...");
-
-    // TODO: Implementar seleção de vítima
-    // Critérios:
-    // - Maior uso de memória
-    // - Menor tempo de CPU (menos importante)
-    // - Não é processo crítico do sistema
+    crate::kerror!("(OOM) Sem memória! Escolhendo vítima...");
 
-    let victim = select_victim();
+    let Some(tid) = select_victim() else {
+        crate::kerror!("(OOM) Nenhuma vítima possível!");
+        return false;
+    };
 
-    if let Some(pid) = victim {
-        crate::kerror!("(OOM) Killing process {}", pid);
-        // TODO: Enviar SIGKILL para o processo
-        OOM_KILLS.fetch_add(1, Ordering::Relaxed);
-        true
-    } else {
-        crate::kerror!("(OOM) No suitable victim found!");
-        false
+    crate::kerror!("(OOM) Matando task:", tid.as_u32() as u64);
+    let code = 128 + crate::sched::signal::SIGKILL;
+    let killed =
+        crate::sched::core::kill_ready(tid, code) || crate::sched::core::kill_sleeping(tid, code);
+    if !killed {
+        // Mudou de fila durante a escolha: morre ao voltar para user mode
+        let _ = crate::sched::signal::send(tid, crate::sched::signal::SIGKILL);
+        return false;
     }
+
+    crate::sched::task::lifecycle::release_memory(tid);
+    OOM_KILLS.fetch_add(1, Ordering::Relaxed);
+    true
 }
 
 /// Seleciona processo vítima
-fn select_victim() -> Option<u64> {
-    // TODO: Iterar sobre processos e calcular scores
-    // Por enquanto, retorna None
-    None
+fn select_victim() -> Option<Tid> {
+    let mut best = None;
+    crate::sched::core::for_each_memory(|task| {
+        best = pick(best, task, crate::sched::task::family::is_init);
+    });
+    best.map(|(tid, _)| tid)
 }
 
-/// Calcula score OOM de um processo
-/// Maior score = maior chance de ser morto
-pub fn oom_score(pid: u64) -> i32 {
-    // Fatores:
-    // + Uso de memória (RSS)
-    // + Tempo de execução
-    // - Prioridade
-    // - Se é processo crítico
-
-    let _ = pid;
-    0
+/// Score OOM de uma task: páginas residentes, ou `None` se ela não pode
+/// ser vítima.
+///
+/// Ficam de fora o init, as kernel threads (sem páginas de usuário), a
+/// task em execução (não dá para matá-la de fora) e quem já terminou.
+pub fn oom_score(task: &TaskMemory, is_init: impl Fn(Tid) -> bool) -> Option<u64> {
+    let killable = matches!(
+        task.state,
+        TaskState::Created | TaskState::Ready | TaskState::Blocked | TaskState::Sleeping
+    );
+    if !killable || task.mapped_pages == 0 || is_init(task.tid) {
+        return None;
+    }
+    Some(task.resident_pages)
+}
+
+/// Fica com a task de maior score entre `best` e `task`
+fn pick(
+    best: Option<(Tid, u64)>,
+    task: TaskMemory,
+    is_init: impl Fn(Tid) -> bool,
+) -> Option<(Tid, u64)> {
+    match (best, oom_score(&task, is_init)) {
+        (Some((_, top)), Some(score)) if score > top => Some((task.tid, score)),
+        (None, Some(score)) => Some((task.tid, score)),
+        (best, _) => best,
+    }
 }
 
-/// Marca processo como imune a OOM
-pub fn set_oom_immune(_pid: u64, _immune: bool) {
-    // TODO: Marcar processo
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    fn task(tid: u32, state: TaskState, mapped: u64, resident: u64) -> TaskMemory {
+        TaskMemory {
+            tid: Tid::new(tid),
+            state,
+            mapped_pages: mapped,
+            resident_pages: resident,
+        }
+    }
+
+    #[test]
+    fn test_victim_is_largest_killable_task() {
+        let is_init = |tid: Tid| tid.as_u32() == 1;
+        let tasks = [
+            task(1, TaskState::Ready, 900, 900),
+            task(2, TaskState::Ready, 0, 0),
+            task(3, TaskState::Running, 800, 800),
+            task(4, TaskState::Sleeping, 300, 120),
+            task(5, TaskState::Ready, 400, 250),
+            task(6, TaskState::Zombie, 700, 700),
+        ];
+        let best = tasks.iter().fold(None, |best, &t| pick(best, t, is_init));
+        assert_eq!(best, Some((Tid::new(5), 250)));
+
+        let only_kernel = [
+            task(1, TaskState::Ready, 10, 10),
+            task(7, TaskState::Blocked, 0, 0),
+        ];
+        assert_eq!(
+            only_kernel
+                .iter()
+                .fold(None, |best, &t| pick(best, t, is_init)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_retry_is_bounded() {
+        // A primeira vítima não basta; a segunda libera o suficiente
+        let kills = Cell::new(0);
+        let frame = retry_after_reclaim(
+            || (kills.get() >= 2).then_some(0x1000u64),
+            || {
+                kills.set(kills.get() + 1);
+                true
+            },
+        );
+        assert_eq!((frame, kills.get()), (Some(0x1000), 2));
+
+        // Reclaim sempre progride, alocação nunca passa: desiste
+        let mut attempts = 0;
+        let frame = retry_after_reclaim(
+            || {
+                attempts += 1;
+                None::<u64>
+            },
+            || true,
+        );
+        assert_eq!((frame, attempts), (None, OOM_RETRIES + 1));
+    }
 }
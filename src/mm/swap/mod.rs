@@ -1,8 +1,19 @@
 //! # Swap Subsystem
 //!
 //! Backing store para páginas evicted.
+//!
+//! O backing store é um `BlockDevice` dividido em slots de 4KB. Um bitmap
+//! (`klib::Bitmap`) controla quais slots estão ocupados; o slot `n` ocupa os
+//! setores `[n * setores_por_página, (n + 1) * setores_por_página)`.
 
+use crate::drivers::block::BlockDevice;
+use crate::klib::Bitmap;
+use crate::mm::config::PAGE_SIZE;
 use crate::mm::PhysAddr;
+use crate::sync::Spinlock;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 /// Swap está habilitado?
@@ -12,6 +23,9 @@ static SWAP_ENABLED: AtomicBool = AtomicBool::new(false);
 static PAGES_SWAPPED_OUT: AtomicU64 = AtomicU64::new(0);
 static PAGES_SWAPPED_IN: AtomicU64 = AtomicU64::new(0);
 
+/// Área de swap ativa
+static SWAP_AREA: Spinlock<Option<SwapArea>> = Spinlock::new(None);
+
 /// Slot de swap (índice no backing store)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SwapSlot(pub u64);
@@ -23,11 +37,149 @@ impl SwapSlot {
     }
 }
 
+// =============================================================================
+// SWAP AREA
+// =============================================================================
+
+/// Backing store de swap: dispositivo + bitmap de slots livres
+pub struct SwapArea {
+    device: Arc<dyn BlockDevice>,
+    /// Bits do bitmap de slots (1 = ocupado)
+    slot_bits: Vec<u64>,
+    /// Número total de slots
+    slot_count: usize,
+    /// Slots ocupados
+    used_slots: usize,
+    /// Setores por slot (PAGE_SIZE / block_size)
+    blocks_per_slot: u64,
+}
+
+impl SwapArea {
+    /// Cria a área sobre `device`, usando até `backing_size` bytes.
+    ///
+    /// Retorna `None` se o dispositivo não comportar ao menos um slot ou
+    /// se o tamanho de bloco não dividir `PAGE_SIZE`.
+    pub fn new(device: Arc<dyn BlockDevice>, backing_size: u64) -> Option<Self> {
+        let block_size = device.block_size();
+        if block_size == 0 || PAGE_SIZE % block_size != 0 {
+            return None;
+        }
+        if device.is_read_only() {
+            return None;
+        }
+
+        let device_bytes = device.total_blocks() * block_size as u64;
+        let usable = core::cmp::min(backing_size, device_bytes);
+        let slot_count = (usable / PAGE_SIZE as u64) as usize;
+        if slot_count == 0 {
+            return None;
+        }
+
+        Some(Self {
+            device,
+            slot_bits: vec![0u64; (slot_count + 63) / 64],
+            slot_count,
+            used_slots: 0,
+            blocks_per_slot: (PAGE_SIZE / block_size) as u64,
+        })
+    }
+
+    /// Número total de slots
+    pub fn slot_count(&self) -> usize {
+        self.slot_count
+    }
+
+    /// Número de slots ocupados
+    pub fn used_slots(&self) -> usize {
+        self.used_slots
+    }
+
+    fn bitmap(&mut self) -> Bitmap<'_> {
+        Bitmap::new(&mut self.slot_bits, self.slot_count)
+    }
+
+    /// Grava uma página em um slot livre
+    pub fn write_page(&mut self, page: &[u8]) -> Option<SwapSlot> {
+        if page.len() != PAGE_SIZE {
+            return None;
+        }
+
+        let mut bitmap = self.bitmap();
+        let index = bitmap.find_first_zero()?;
+        bitmap.set(index);
+
+        let lba = index as u64 * self.blocks_per_slot;
+        if self.device.write_blocks(lba, page).is_err() {
+            self.bitmap().clear(index);
+            return None;
+        }
+
+        self.used_slots += 1;
+        Some(SwapSlot(index as u64))
+    }
+
+    /// Lê o conteúdo de um slot ocupado para `page`
+    pub fn read_page(&self, slot: SwapSlot, page: &mut [u8]) -> bool {
+        if !self.is_used(slot) || page.len() != PAGE_SIZE {
+            return false;
+        }
+        let lba = slot.0 * self.blocks_per_slot;
+        self.device
+            .read_blocks(lba, self.blocks_per_slot as usize, page)
+            .is_ok()
+    }
+
+    /// Libera um slot. Retorna `false` se já estava livre.
+    pub fn release(&mut self, slot: SwapSlot) -> bool {
+        if !self.is_used(slot) {
+            return false;
+        }
+        self.bitmap().clear(slot.0 as usize);
+        self.used_slots -= 1;
+        true
+    }
+
+    /// Verifica se o slot está em uso
+    pub fn is_used(&self, slot: SwapSlot) -> bool {
+        if !slot.is_valid() || slot.0 >= self.slot_count as u64 {
+            return false;
+        }
+        let index = slot.0 as usize;
+        (self.slot_bits[index / 64] & (1 << (index % 64))) != 0
+    }
+}
+
+// =============================================================================
+// API GLOBAL
+// =============================================================================
+
 /// Inicializa swap com backing store
-pub fn init(_backing_size: u64) {
-    // TODO: Alocar bitmap de slots livres
+pub fn init(device: Arc<dyn BlockDevice>, backing_size: u64) {
+    let area = match SwapArea::new(device, backing_size) {
+        Some(area) => area,
+        None => {
+            crate::kerror!("(SWAP) Backing store inválido");
+            return;
+        }
+    };
+
+    let slots = area.slot_count() as u64;
+    *SWAP_AREA.lock() = Some(area);
     SWAP_ENABLED.store(true, Ordering::Release);
-    crate::kinfo!("(SWAP) Initialized");
+    crate::kinfo!("(SWAP) Initialized. Slots:", slots);
+}
+
+/// Desabilita o swap e solta o backing store
+///
+/// Falha (`false`) enquanto houver páginas no swap.
+pub fn disable() -> bool {
+    let mut area = SWAP_AREA.lock();
+    if area.as_ref().is_some_and(|area| area.used_slots() > 0) {
+        return false;
+    }
+    SWAP_ENABLED.store(false, Ordering::Release);
+    *area = None;
+    true
 }
 
 /// Verifica se swap está habilitado
@@ -36,36 +188,58 @@ pub fn is_enabled() -> bool {
 }
 
 /// Escreve página em swap
+///
+/// O frame continua alocado; cabe ao chamador liberá-lo após atualizar a PTE.
 pub fn swap_out(phys: PhysAddr) -> Option<SwapSlot> {
     if !is_enabled() {
         return None;
     }
 
-    // TODO: Encontrar slot livre
-    // TODO: Escrever conteúdo no backing store
+    let src = crate::mm::physmap::phys_to_virt(phys).as_ptr::<u8>();
+    // SAFETY: o frame é RAM, na physmap; lido apenas durante a cópia
+    let page = unsafe { core::slice::from_raw_parts(src, PAGE_SIZE) };
 
-    let _ = phys;
+    let slot = SWAP_AREA.lock().as_mut()?.write_page(page)?;
     PAGES_SWAPPED_OUT.fetch_add(1, Ordering::Relaxed);
-    None
+    Some(slot)
 }
 
 /// Lê página de swap
+///
+/// Aloca um frame novo, copia o conteúdo do slot e libera o slot.
 pub fn swap_in(slot: SwapSlot) -> Option<PhysAddr> {
-    if !slot.is_valid() {
+    if !slot.is_valid() || !is_enabled() {
         return None;
     }
 
-    // TODO: Alocar frame
-    // TODO: Ler do backing store
-    // TODO: Liberar slot
+    // Pelo PFM: a página volta mapeada, então nasce com uma referência
+    let frame = crate::mm::pfm::alloc_user_frame(crate::mm::pfm::PID_KERNEL).ok()?;
+    let dst = crate::mm::physmap::phys_to_virt(frame).as_mut_ptr::<u8>();
+    // SAFETY: frame recém-alocado, exclusivo desta função
+    let page = unsafe { core::slice::from_raw_parts_mut(dst, PAGE_SIZE) };
+
+    let mut guard = SWAP_AREA.lock();
+    let ok = match guard.as_mut() {
+        Some(area) => area.read_page(slot, page) && area.release(slot),
+        None => false,
+    };
+    drop(guard);
+
+    if !ok {
+        crate::mm::pfm::release_mapped(frame);
+        crate::kerror!("(SWAP) Falha ao ler slot:", slot.0);
+        return None;
+    }
 
     PAGES_SWAPPED_IN.fetch_add(1, Ordering::Relaxed);
-    None
+    Some(frame)
 }
 
 /// Libera slot de swap
-pub fn free_slot(_slot: SwapSlot) {
-    // TODO: Marcar slot como livre
+pub fn free_slot(slot: SwapSlot) {
+    if let Some(area) = SWAP_AREA.lock().as_mut() {
+        area.release(slot);
+    }
 }
 
 /// Estatísticas de swap
@@ -75,3 +249,100 @@ pub fn stats() -> (u64, u64) {
         PAGES_SWAPPED_IN.load(Ordering::Relaxed),
     )
 }
+
+/// Slots ocupados / total de slots
+pub fn usage() -> (usize, usize) {
+    match SWAP_AREA.lock().as_ref() {
+        Some(area) => (area.used_slots(), area.slot_count()),
+        None => (0, 0),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::block::BlockError;
+
+    /// Disco em memória com setores de 512 bytes
+    struct MemDisk {
+        data: Spinlock<Vec<u8>>,
+    }
+
+    impl MemDisk {
+        fn new(blocks: usize) -> Self {
+            Self {
+                data: Spinlock::new(vec![0u8; blocks * 512]),
+            }
+        }
+    }
+
+    impl BlockDevice for MemDisk {
+        fn read_block(&self, lba: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+            let off = lba as usize * 512;
+            let data = self.data.lock();
+            if off + 512 > data.len() {
+                return Err(BlockError::InvalidBlock);
+            }
+            buf[..512].copy_from_slice(&data[off..off + 512]);
+            Ok(())
+        }
+
+        fn write_block(&self, lba: u64, buf: &[u8]) -> Result<(), BlockError> {
+            let off = lba as usize * 512;
+            let mut data = self.data.lock();
+            if off + 512 > data.len() {
+                return Err(BlockError::InvalidBlock);
+            }
+            data[off..off + 512].copy_from_slice(&buf[..512]);
+            Ok(())
+        }
+
+        fn block_size(&self) -> usize {
+            512
+        }
+
+        fn total_blocks(&self) -> u64 {
+            (self.data.lock().len() / 512) as u64
+        }
+    }
+
+    #[test]
+    fn test_swap_round_trip() {
+        let disk = Arc::new(MemDisk::new(64));
+        let mut area = SwapArea::new(disk, 4 * PAGE_SIZE as u64).unwrap();
+        assert_eq!(area.slot_count(), 4);
+
+        let mut page = vec![0u8; PAGE_SIZE];
+        for (i, b) in page.iter_mut().enumerate() {
+            *b = (i * 7 + 3) as u8;
+        }
+
+        let first = area.write_page(&vec![0xAAu8; PAGE_SIZE]).unwrap();
+        let slot = area.write_page(&page).unwrap();
+        assert_ne!(first, slot);
+        assert_eq!(area.used_slots(), 2);
+
+        let mut back = vec![0u8; PAGE_SIZE];
+        assert!(area.read_page(slot, &mut back));
+        assert_eq!(back, page);
+
+        assert!(area.release(slot));
+        assert!(!area.release(slot));
+        assert!(!area.read_page(slot, &mut back));
+        assert_eq!(area.used_slots(), 1);
+    }
+
+    #[test]
+    fn test_swap_full() {
+        let disk = Arc::new(MemDisk::new(16));
+        let mut area = SwapArea::new(disk, u64::MAX).unwrap();
+        let page = vec![1u8; PAGE_SIZE];
+        assert!(area.write_page(&page).is_some());
+        assert!(area.write_page(&page).is_some());
+        assert!(area.write_page(&page).is_none());
+    }
+}
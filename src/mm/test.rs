@@ -0,0 +1,303 @@
+//! # Testes de Memória
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot
+//! (com PMM, heap e página zero prontos).
+
+use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
+use crate::mm::aspace::AddressSpace;
+use crate::mm::fault::{populate_anon_page, resolve_cow, AccessType};
+use crate::mm::pfm::zero;
+use crate::mm::physmap;
+use crate::mm::pmm::FRAME_ALLOCATOR;
+use crate::mm::vmm::mapper::{read_pte_in_p4, translate_addr_in_p4, PTE_COW, PTE_WRITABLE};
+use crate::mm::vmm::{map_page_with_pmm, unmap_page, MapFlags};
+use crate::mm::VirtAddr;
+
+pub fn run_tests() {
+    crate::kinfo!("(MM) Iniciando testes de memória...");
+    test_zero_page_anon_mapping();
+    test_physmap_round_trip();
+    test_unmap_frees_page_tables();
+    test_swap_out_in_round_trip();
+    #[cfg(feature = "memory_accounting")]
+    test_subsystem_quota();
+    #[cfg(feature = "memory_accounting")]
+    test_quota_violation_report();
+    crate::kinfo!("(MM) Testes de memória concluídos com SUCESSO.");
+}
+
+fn used_frames() -> usize {
+    FRAME_ALLOCATOR.lock().used_frames()
+}
+
+/// Um frame visto pela physmap e por um mapeamento comum (o slot de
+/// scratch) tem os mesmos bytes, nos dois sentidos.
+fn test_physmap_round_trip() {
+    const SCRATCH: u64 = crate::mm::config::SCRATCH_VIRT as u64;
+
+    let frame = FRAME_ALLOCATOR
+        .lock()
+        .allocate_frame()
+        .expect("(MM) Sem frame para o teste da physmap");
+    map_page_with_pmm(
+        SCRATCH,
+        frame.as_u64(),
+        MapFlags::PRESENT | MapFlags::WRITABLE,
+        &mut FRAME_ALLOCATOR.lock(),
+    )
+    .expect("(MM) Falha ao mapear o scratch");
+
+    let window = physmap::phys_to_virt(frame);
+    assert!(physmap::contains(frame), "(MM) Frame fora da physmap");
+    assert_eq!(physmap::virt_to_phys(window), Some(frame));
+
+    let scratch = SCRATCH as *mut u64;
+    let direct = window.as_mut_ptr::<u64>();
+    unsafe {
+        // Escrito pelo scratch, lido pela janela
+        for i in 0..512u64 {
+            core::ptr::write_volatile(scratch.add(i as usize), 0x5EED_0000_0000_0000 | i);
+        }
+        for i in 0..512u64 {
+            assert_eq!(
+                core::ptr::read_volatile(direct.add(i as usize)),
+                0x5EED_0000_0000_0000 | i,
+                "(MM) Physmap não enxerga o frame"
+            );
+        }
+        // E o contrário, zerando pela janela
+        physmap::zero_frame(frame);
+        for i in 0..512 {
+            assert_eq!(core::ptr::read_volatile(scratch.add(i)), 0);
+        }
+    }
+
+    unmap_page(SCRATCH).expect("(MM) Falha ao desmapear o scratch");
+    FRAME_ALLOCATOR.lock().deallocate_frame(frame);
+}
+
+/// 16 MiB anônimos lidos de ponta a ponta gastam só as page tables; a
+/// primeira escrita numa página gasta exatamente um frame.
+fn test_zero_page_anon_mapping() {
+    const SIZE: usize = 16 * 1024 * 1024;
+    const PAGE: u64 = crate::mm::config::PAGE_SIZE as u64;
+
+    zero::init_zero_page();
+    let zero_frame = zero::zero_page_frame().expect("(MM) Página zero ausente");
+
+    let mut aspace = AddressSpace::new(1).expect("(MM) Falha ao criar address space");
+    let start = aspace
+        .map_region(
+            None,
+            SIZE,
+            Protection::RW,
+            VmaFlags::empty(),
+            MemoryIntent::Heap,
+        )
+        .expect("(MM) Falha ao mapear região anônima");
+    let pml4 = aspace.cr3();
+    let flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE;
+
+    // Leitura de cada página: todas caem na página zero
+    let before = used_frames();
+    for i in 0..SIZE as u64 / PAGE {
+        let page = VirtAddr::new(start.as_u64() + i * PAGE);
+        let phys = populate_anon_page(pml4, page, AccessType::Read, flags)
+            .expect("(MM) Falha ao popular página anônima");
+        assert_eq!(phys, zero_frame, "(MM) Leitura não usou a página zero");
+    }
+    // Uma PT por 2 MiB (mais uma, se a região não for alinhada), PDPT e PDs
+    let tables = SIZE / (2 * 1024 * 1024) + 4;
+    assert!(
+        used_frames() - before <= tables,
+        "(MM) Mapeamento anônimo gastou frames sem escrita"
+    );
+
+    let last = start.as_u64() + SIZE as u64 - PAGE;
+    assert_eq!(
+        translate_addr_in_p4(pml4, last).map(|p| p & !(PAGE - 1)),
+        Some(zero_frame.as_u64())
+    );
+    assert!(
+        zero::is_zeroed(zero_frame),
+        "(MM) Leitura não devolve zeros"
+    );
+
+    // Primeira escrita: frame próprio e zerado, página zero intacta
+    let pte = read_pte_in_p4(pml4, last).expect("(MM) PTE ausente");
+    assert!(pte & PTE_COW != 0 && pte & PTE_WRITABLE == 0);
+    let before = used_frames();
+    let frame = resolve_cow(pml4, VirtAddr::new(last), pte).expect("(MM) COW falhou");
+    assert_ne!(frame, zero_frame, "(MM) Escrita ficou na página zero");
+    assert_eq!(used_frames(), before + 1);
+    assert!(zero::is_zeroed(frame), "(MM) Frame novo não está zerado");
+    assert!(zero::is_zeroed(zero_frame), "(MM) Página zero foi alterada");
+
+    let pte = read_pte_in_p4(pml4, last).expect("(MM) PTE ausente");
+    assert!(pte & PTE_WRITABLE != 0 && pte & PTE_COW == 0);
+
+    drop(aspace);
+    FRAME_ALLOCATOR.lock().deallocate_frame(frame);
+}
+
+/// Desmapear uma região inteira devolve ao PMM as page tables que o
+/// mapeamento criou.
+fn test_unmap_frees_page_tables() {
+    const SIZE: usize = 4 * 1024 * 1024;
+    const PAGE: u64 = crate::mm::config::PAGE_SIZE as u64;
+
+    zero::init_zero_page();
+    let mut aspace = AddressSpace::new(1).expect("(MM) Falha ao criar address space");
+    let start = aspace
+        .map_region(
+            None,
+            SIZE,
+            Protection::RW,
+            VmaFlags::empty(),
+            MemoryIntent::Heap,
+        )
+        .expect("(MM) Falha ao mapear região anônima");
+    let pml4 = aspace.cr3();
+    let flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE;
+
+    // Só leituras: as páginas são a página zero, os frames novos são tabelas
+    let before = used_frames();
+    for i in 0..SIZE as u64 / PAGE {
+        let page = VirtAddr::new(start.as_u64() + i * PAGE);
+        populate_anon_page(pml4, page, AccessType::Read, flags)
+            .expect("(MM) Falha ao popular página anônima");
+    }
+    assert!(used_frames() > before, "(MM) Nenhuma page table criada");
+
+    aspace
+        .unmap_region(start, SIZE)
+        .expect("(MM) Falha ao desmapear região");
+    assert_eq!(
+        used_frames(),
+        before,
+        "(MM) Page tables vazias não voltaram ao PMM"
+    );
+    assert_eq!(translate_addr_in_p4(pml4, start.as_u64()), None);
+}
+
+/// Uma página mandada para o swap (`swap_out`) volta intacta num frame
+/// novo (`swap_in`), que libera o slot.
+fn test_swap_out_in_round_trip() {
+    use crate::drivers::block::RamDisk;
+    use crate::mm::{pfm, swap};
+    use alloc::sync::Arc;
+
+    if swap::is_enabled() {
+        crate::kwarn!("(MM) Swap já habilitado; teste de swap ignorado");
+        return;
+    }
+    // 4 slots de página em setores de 512 bytes
+    let slots = 4;
+    let disk = Arc::new(RamDisk::new(32, 512));
+    swap::init(disk, (slots * crate::mm::config::PAGE_SIZE) as u64);
+    assert!(swap::is_enabled(), "(MM) Swap não habilitou");
+
+    let frame =
+        pfm::alloc_user_frame(pfm::PID_KERNEL).expect("(MM) Sem frame para o teste de swap");
+    let words = physmap::phys_to_virt(frame).as_mut_ptr::<u64>();
+    for i in 0..512u64 {
+        // SAFETY: frame recém-alocado, exclusivo do teste
+        unsafe { core::ptr::write_volatile(words.add(i as usize), 0x5A5A_0000_0000_0000 | i) };
+    }
+
+    let slot = swap::swap_out(frame).expect("(MM) swap_out falhou");
+    assert_eq!(swap::usage(), (1, slots));
+    pfm::release_mapped(frame);
+
+    let back = swap::swap_in(slot).expect("(MM) swap_in falhou");
+    let words = physmap::phys_to_virt(back).as_ptr::<u64>();
+    for i in 0..512u64 {
+        assert_eq!(
+            // SAFETY: frame devolvido pelo swap_in, exclusivo do teste
+            unsafe { core::ptr::read_volatile(words.add(i as usize)) },
+            0x5A5A_0000_0000_0000 | i,
+            "(MM) Página voltou do swap diferente"
+        );
+    }
+    assert_eq!(swap::usage(), (0, slots));
+    assert!(
+        swap::swap_in(slot).is_none(),
+        "(MM) Slot liberado lido de novo"
+    );
+
+    pfm::release_mapped(back);
+    assert!(swap::disable(), "(MM) Swap sem páginas não desabilitou");
+}
+
+/// Estourada a quota de um subsistema, o heap nega as alocações dele; os
+/// outros subsistemas seguem alocando.
+#[cfg(feature = "memory_accounting")]
+fn test_subsystem_quota() {
+    use crate::mm::accounting::{self, subsystem::SubsystemGuard, Subsystem};
+    use alloc::alloc::{alloc, dealloc, Layout};
+
+    let layout = Layout::from_size_align(256, 8).unwrap();
+    let base = accounting::get_usage(Subsystem::Test);
+    accounting::set_quota(Subsystem::Test, base + 384);
+
+    let (first, denied) = {
+        let _guard = SubsystemGuard::enter(Subsystem::Test);
+        // SAFETY: layout não vazio; liberados abaixo no mesmo subsistema
+        unsafe { (alloc(layout), alloc(layout)) }
+    };
+    let other = {
+        let _guard = SubsystemGuard::enter(Subsystem::IPC);
+        // SAFETY: idem
+        unsafe { alloc(layout) }
+    };
+
+    assert!(!first.is_null(), "(MM) Alocação dentro da quota negada");
+    assert!(denied.is_null(), "(MM) Alocação além da quota aceita");
+    assert!(!other.is_null(), "(MM) Quota de um subsistema afetou outro");
+    assert_eq!(accounting::get_usage(Subsystem::Test), base + 256);
+
+    {
+        let _guard = SubsystemGuard::enter(Subsystem::Test);
+        // SAFETY: alocado acima com o mesmo layout
+        unsafe { dealloc(first, layout) };
+    }
+    {
+        let _guard = SubsystemGuard::enter(Subsystem::IPC);
+        // SAFETY: idem
+        unsafe { dealloc(other, layout) };
+    }
+    assert_eq!(accounting::get_usage(Subsystem::Test), base);
+    accounting::set_quota(Subsystem::Test, Subsystem::Test.default_quota());
+}
+
+/// Uma alocação negada por quota conta como violação do subsistema e
+/// aparece no relatório.
+#[cfg(feature = "memory_accounting")]
+fn test_quota_violation_report() {
+    use crate::mm::accounting::{self, subsystem::SubsystemGuard, Subsystem};
+    use alloc::alloc::{alloc, Layout};
+
+    let layout = Layout::from_size_align(128, 8).unwrap();
+    let stats = accounting::get_stats(Subsystem::Audio);
+    let violations = stats.denials();
+    let total = accounting::quota_violations();
+    accounting::set_quota(Subsystem::Audio, stats.allocated_bytes() + 64);
+
+    let denied = {
+        let _guard = SubsystemGuard::enter(Subsystem::Audio);
+        // SAFETY: layout não vazio; negado pela quota, nada a liberar
+        unsafe { alloc(layout) }
+    };
+    accounting::set_quota(Subsystem::Audio, Subsystem::Audio.default_quota());
+
+    assert!(denied.is_null(), "(MM) Alocação além da quota aceita");
+    assert_eq!(stats.denials(), violations + 1);
+    assert_eq!(accounting::quota_violations(), total + 1);
+
+    let report = accounting::memory_report();
+    let line = report
+        .lines()
+        .find(|line| line.contains(Subsystem::Audio.name()))
+        .expect("(MM) Relatório sem o subsistema que estourou a quota");
+    assert!(line.contains(&alloc::format!("{}", violations + 1)));
+}
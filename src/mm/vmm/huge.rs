@@ -1,6 +1,17 @@
 //! # Huge Pages Support
 //!
 //! Suporte a Huge Pages (2MB e 1GB).
+//!
+//! Uma huge page é uma PDE (2MB) ou PDPTE (1GB) com o bit PS ligado,
+//! apontando direto para o frame. Reduz tabelas de página e pressão no TLB
+//! em regiões grandes e contíguas (heap, HHDM). Páginas de 1GB só se o CPUID
+//! anunciar (`CpuFeatures::huge_1g`).
+//!
+//! O mapper recebe o tamanho pelas flags (`MapFlags::HUGE_2M`/`HUGE_1G`). Se
+//! parte de uma huge page precisa de permissões próprias, ela é dividida:
+//! 1GB vira um PD com 512 páginas de 2MB, 2MB vira uma PT com 512 de 4KB,
+//! todas com as flags originais. `map_page*` e `unmap_page` dividem sozinhos
+//! ao encontrar uma huge page no caminho.
 
 use crate::mm::{MapFlags, PhysAddr, VirtAddr};
 
@@ -35,42 +46,78 @@ impl HugePageSize {
             HugePageSize::Page1GB => HUGE_1GB,
         }
     }
-}
 
-/// Mapeia uma huge page 2MB
-pub fn map_2mb(virt: VirtAddr, phys: PhysAddr, flags: MapFlags) -> crate::mm::error::MmResult<()> {
-    use crate::mm::config::*;
+    /// Tamanho pedido nas flags (`HUGE_1G` tem precedência)
+    pub fn from_flags(flags: MapFlags) -> Option<Self> {
+        if flags.contains(MapFlags::HUGE_1G) {
+            Some(HugePageSize::Page1GB)
+        } else if flags.contains(MapFlags::HUGE_2M) {
+            Some(HugePageSize::Page2MB)
+        } else {
+            None
+        }
+    }
 
-    if !is_2mb_aligned(virt.as_u64()) || !is_2mb_aligned(phys.as_u64()) {
-        return Err(crate::mm::MmError::InvalidAddress);
+    /// Flag de mapeamento deste tamanho
+    pub fn flag(&self) -> MapFlags {
+        match self {
+            HugePageSize::Page2MB => MapFlags::HUGE_2M,
+            HugePageSize::Page1GB => MapFlags::HUGE_1G,
+        }
     }
 
-    // Construir flags de PTE com PAGE_HUGE
-    let mut pte_flags = PAGE_PRESENT | PAGE_HUGE;
-    if flags.contains(MapFlags::WRITABLE) {
-        pte_flags |= PAGE_WRITABLE;
+    /// A CPU mapeia páginas deste tamanho? (2MB sempre, em long mode)
+    pub fn is_supported(&self) -> bool {
+        match self {
+            HugePageSize::Page2MB => true,
+            HugePageSize::Page1GB => crate::arch::cpu_features().huge_1g,
+        }
     }
-    if flags.contains(MapFlags::USER) {
-        pte_flags |= PAGE_USER;
+
+    /// Uma página deste tamanho cabe em `virt -> phys` (ambos alinhados, ao
+    /// menos `len` bytes) e a CPU a suporta?
+    pub fn fits(&self, virt: u64, phys: u64, len: u64) -> bool {
+        let bytes = self.size();
+        (virt | phys) & (bytes - 1) == 0 && len >= bytes && self.is_supported()
     }
-    if !flags.contains(MapFlags::EXECUTABLE) {
-        pte_flags |= PAGE_NO_EXEC;
+}
+
+/// Mapeia uma huge page 2MB
+pub fn map_2mb(virt: VirtAddr, phys: PhysAddr, flags: MapFlags) -> crate::mm::error::MmResult<()> {
+    map_huge(virt, phys, HugePageSize::Page2MB, flags)
+}
+
+/// Mapeia uma huge page 1GB
+pub fn map_1gb(virt: VirtAddr, phys: PhysAddr, flags: MapFlags) -> crate::mm::error::MmResult<()> {
+    map_huge(virt, phys, HugePageSize::Page1GB, flags)
+}
+
+fn map_huge(
+    virt: VirtAddr,
+    phys: PhysAddr,
+    size: HugePageSize,
+    flags: MapFlags,
+) -> crate::mm::error::MmResult<()> {
+    if (virt.as_u64() | phys.as_u64()) & (size.size() - 1) != 0 {
+        return Err(crate::mm::MmError::InvalidAddress);
     }
-    if flags.contains(MapFlags::GLOBAL) {
-        pte_flags |= PAGE_GLOBAL;
+    if !size.is_supported() {
+        return Err(crate::mm::MmError::InvalidFlags);
     }
 
-    // TODO: Implementar mapeamento real quando VMM estiver atualizado
-    let _ = (virt, phys, pte_flags);
-
-    Ok(())
+    super::mapper::map_huge_page(
+        virt.as_u64(),
+        phys.as_u64(),
+        flags | size.flag(),
+        &mut crate::mm::FRAME_ALLOCATOR.lock(),
+    )
+    .map_err(|_| crate::mm::MmError::AlreadyMapped)
 }
 
 /// Divide huge page em 4KB pages
 pub fn split_2mb_to_4kb(virt: VirtAddr) -> crate::mm::error::MmResult<()> {
-    // TODO: Implementar split
-    let _ = virt;
-    Ok(())
+    super::mapper::split_huge_page(virt.as_u64(), &mut crate::mm::FRAME_ALLOCATOR.lock())
+        .map_err(|_| crate::mm::MmError::OutOfMemory)
 }
 
 /// Merge 512 páginas 4KB em uma 2MB
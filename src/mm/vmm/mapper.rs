@@ -4,6 +4,7 @@
 //! Implementação real de mapeamento de páginas para x86_64.
 //! Manipula tabelas de página hierárquicas (PML4 -> PDPT -> PD -> PT).
 
+use super::huge::{HugePageSize, HUGE_1GB, HUGE_2MB};
 use super::vmm::MapFlags;
 use core::arch::asm;
 
@@ -17,6 +18,26 @@ const FLAG_PRESENT: u64 = 1 << 0;
 const FLAG_WRITABLE: u64 = 1 << 1;
 const FLAG_USER: u64 = 1 << 2;
 const FLAG_NO_EXEC: u64 = 1 << 63;
+/// PS (Page Size): a PDE/PDPTE aponta direto para uma huge page
+const FLAG_HUGE: u64 = 1 << 7;
+const FLAG_GLOBAL: u64 = 1 << 8;
+/// PAT de uma huge page (na PTE de 4KB o PAT fica no bit 7, o do PS)
+const FLAG_HUGE_PAT: u64 = 1 << 12;
+
+/// Máscaras do endereço físico em entradas de huge page
+const HUGE_2MB_MASK: u64 = 0x000F_FFFF_FFE0_0000;
+const HUGE_1GB_MASK: u64 = 0x000F_FFFF_C000_0000;
+
+/// Bit de PTE disponível para software: página Copy-on-Write
+pub const PTE_COW: u64 = 1 << 9;
+/// Bit de escrita da PTE (exportado para o tratamento de COW)
+pub const PTE_WRITABLE: u64 = FLAG_WRITABLE;
+/// Máscara do endereço físico em uma PTE
+pub const PTE_ADDR_MASK: u64 = PAGE_MASK;
+/// Bit de acesso de usuário da PTE
+pub const PTE_USER: u64 = FLAG_USER;
+/// Bit de não-execução da PTE
+pub const PTE_NO_EXEC: u64 = FLAG_NO_EXEC;
 
 /// Lê o registrador CR3 (endereço físico da PML4)
 #[inline]
@@ -122,10 +143,9 @@ pub fn grant_user_access(page_virt: u64) {
         let pt_phys = pde & PAGE_MASK;
 
         // PT
-        let mut pte = get_table_entry(pt_phys, pt_idx);
+        let pte = get_table_entry(pt_phys, pt_idx);
         if pte & FLAG_PRESENT != 0 {
-            pte |= FLAG_USER;
-            set_table_entry(pt_phys, pt_idx, pte);
+            set_pte(pml4_phys, pt_phys, pt_idx, page_virt, pte | FLAG_USER);
         }
 
         // Flush TLB
@@ -149,56 +169,221 @@ unsafe fn set_table_entry(table_phys: u64, index: usize, value: u64) {
     core::ptr::write_volatile(table_ptr.add(index), value);
 }
 
+/// Escreve a PTE `pt_idx` da PT em `pt_phys` (página `virt` da PML4
+/// `pml4_phys`), mantendo o rmap em dia.
+///
+/// Só PTEs de usuário entram no rmap, menos as da página zero (mapeada em
+/// todo lugar, nunca sai da RAM); trocar apenas as flags de uma PTE (ex.:
+/// tirar o W para COW) não mexe nele.
+#[inline]
+unsafe fn set_pte(pml4_phys: u64, pt_phys: u64, pt_idx: usize, virt: u64, pte: u64) {
+    use crate::mm::pfm::zero::is_zero_page;
+
+    let old = get_table_entry(pt_phys, pt_idx);
+    set_table_entry(pt_phys, pt_idx, pte);
+
+    let tracked = |pte: u64| {
+        pte & FLAG_PRESENT != 0
+            && pte & FLAG_USER != 0
+            && !is_zero_page(crate::mm::PhysAddr::new(pte & PAGE_MASK))
+    };
+    let same_frame = tracked(old) && tracked(pte) && old & PAGE_MASK == pte & PAGE_MASK;
+    if same_frame {
+        return;
+    }
+    if tracked(old) {
+        let _ = crate::mm::pfm::rmap::remove(
+            crate::mm::PhysAddr::new(old & PAGE_MASK),
+            pml4_phys,
+            virt,
+        );
+    }
+    if tracked(pte) {
+        let _ =
+            crate::mm::pfm::rmap::add(crate::mm::PhysAddr::new(pte & PAGE_MASK), pml4_phys, virt);
+    }
+}
+
 /// Traduz endereço virtual para físico usando uma PML4 específica
 pub fn translate_addr_in_p4(pml4_phys: u64, virt: u64) -> Option<u64> {
+    translate_with_flags_in_p4(pml4_phys, virt).map(|(phys, _)| phys.as_u64())
+}
+
+/// Traduz endereço virtual para físico em uma PML4 específica, com as flags
+/// efetivas do mapeamento
+///
+/// `WRITABLE` e `USER` só aparecem se todos os níveis da tabela os dão;
+/// `NO_EXECUTE`, se algum nível proíbe execução (senão, `EXECUTABLE`). Uma
+/// huge page traz `HUGE_2M`/`HUGE_1G` e o físico já soma o deslocamento
+/// dentro dela. Endereço não canônico dá `None`.
+pub fn translate_with_flags_in_p4(
+    pml4_phys: u64,
+    virt: u64,
+) -> Option<(crate::mm::PhysAddr, MapFlags)> {
+    if !is_canonical(virt) {
+        return None;
+    }
+
+    let mut allowed = FLAG_WRITABLE | FLAG_USER;
+    let mut no_exec = false;
+    let mut table_phys = pml4_phys;
+    // (deslocamento do índice, tamanho da página se a entrada for folha)
+    for (shift, size) in [(39, 0), (30, HUGE_1GB), (21, HUGE_2MB), (12, PAGE_SIZE)] {
+        let entry = unsafe { get_table_entry(table_phys, ((virt >> shift) & 0x1FF) as usize) };
+        if entry & FLAG_PRESENT == 0 {
+            return None;
+        }
+        allowed &= entry;
+        no_exec |= entry & FLAG_NO_EXEC != 0;
+
+        let leaf = size == PAGE_SIZE || (size != 0 && entry & FLAG_HUGE != 0);
+        if leaf {
+            let phys = (entry & PAGE_MASK & !(size - 1)) | (virt & (size - 1));
+            let flags = leaf_flags(entry, allowed, no_exec, size);
+            return Some((crate::mm::PhysAddr::new(phys), flags));
+        }
+        table_phys = entry & PAGE_MASK;
+    }
+    None
+}
+
+/// Endereço canônico: bits 63..48 iguais ao bit 47
+fn is_canonical(virt: u64) -> bool {
+    (((virt << 16) as i64) >> 16) as u64 == virt
+}
+
+/// Flags de uma entrada folha de `size`, com W/U (`allowed`) e NX do
+/// caminho inteiro
+fn leaf_flags(entry: u64, allowed: u64, no_exec: bool, size: u64) -> MapFlags {
+    // Bits que só a folha decide; o bit 7 de uma PTE é o PAT, não o PS
+    let leaf_bits = MapFlags::PRESENT
+        | MapFlags::WRITE_THROUGH
+        | MapFlags::NO_CACHE
+        | MapFlags::ACCESSED
+        | MapFlags::DIRTY
+        | MapFlags::GLOBAL;
+
+    let mut flags = MapFlags::from_bits_truncate((entry & leaf_bits.bits()) | allowed);
+    flags.insert(if no_exec {
+        MapFlags::NO_EXECUTE
+    } else {
+        MapFlags::EXECUTABLE
+    });
+    match size {
+        HUGE_2MB => flags.insert(MapFlags::HUGE_2M),
+        HUGE_1GB => flags.insert(MapFlags::HUGE_1G),
+        _ => {}
+    }
+    flags
+}
+
+/// Tamanho da página (4KB, 2MB ou 1GB) que mapeia `virt` em uma PML4
+/// específica, ou `None` se não mapeado
+pub fn page_size_in_p4(pml4_phys: u64, virt: u64) -> Option<u64> {
+    let (table_phys, index, size) = unsafe { leaf_entry(pml4_phys, virt) }?;
+    let entry = unsafe { get_table_entry(table_phys, index) };
+    (entry & FLAG_PRESENT != 0).then_some(size)
+}
+
+/// Entrada folha de `virt`: `(tabela, índice, tamanho da página)`
+///
+/// A folha é uma huge page (PDPTE/PDE com PS) ou a PTE, presente ou não.
+/// `None` se faltar uma tabela no caminho.
+unsafe fn leaf_entry(pml4_phys: u64, virt: u64) -> Option<(u64, usize, u64)> {
     let pml4_idx = ((virt >> 39) & 0x1FF) as usize;
     let pdpt_idx = ((virt >> 30) & 0x1FF) as usize;
     let pd_idx = ((virt >> 21) & 0x1FF) as usize;
     let pt_idx = ((virt >> 12) & 0x1FF) as usize;
-    let offset = virt & 0xFFF;
+
+    let pml4e = get_table_entry(pml4_phys, pml4_idx);
+    if pml4e & FLAG_PRESENT == 0 {
+        return None;
+    }
+    let pdpt_phys = pml4e & PAGE_MASK;
+    let pdpte = get_table_entry(pdpt_phys, pdpt_idx);
+    if pdpte & FLAG_PRESENT == 0 {
+        return None;
+    }
+    if pdpte & FLAG_HUGE != 0 {
+        return Some((pdpt_phys, pdpt_idx, HUGE_1GB));
+    }
+    let pd_phys = pdpte & PAGE_MASK;
+    let pde = get_table_entry(pd_phys, pd_idx);
+    if pde & FLAG_PRESENT == 0 {
+        return None;
+    }
+    if pde & FLAG_HUGE != 0 {
+        return Some((pd_phys, pd_idx, HUGE_2MB));
+    }
+    Some((pde & PAGE_MASK, pt_idx, PAGE_SIZE))
+}
+
+/// Localiza a PT que contém `virt` em uma PML4 específica
+///
+/// Retorna `None` se alguma tabela intermediária não existir ou se o
+/// endereço estiver coberto por uma huge page.
+fn find_pt_in_p4(pml4_phys: u64, virt: u64) -> Option<u64> {
+    let pml4_idx = ((virt >> 39) & 0x1FF) as usize;
+    let pdpt_idx = ((virt >> 30) & 0x1FF) as usize;
+    let pd_idx = ((virt >> 21) & 0x1FF) as usize;
 
     unsafe {
-        // PML4 -> PDPT
         let pml4e = get_table_entry(pml4_phys, pml4_idx);
         if pml4e & FLAG_PRESENT == 0 {
             return None;
         }
-        let pdpt_phys = pml4e & PAGE_MASK;
-
-        // PDPT -> PD
-        let pdpte = get_table_entry(pdpt_phys, pdpt_idx);
-        if pdpte & FLAG_PRESENT == 0 {
+        let pdpte = get_table_entry(pml4e & PAGE_MASK, pdpt_idx);
+        if pdpte & FLAG_PRESENT == 0 || pdpte & (1 << 7) != 0 {
             return None;
         }
-        if pdpte & (1 << 7) != 0 {
-            return Some((pdpte & 0x000F_FFFF_C000_0000) | (virt & 0x3FFF_FFFF));
-        }
-        let pd_phys = pdpte & PAGE_MASK;
-
-        // PD -> PT
-        let pde = get_table_entry(pd_phys, pd_idx);
-        if pde & FLAG_PRESENT == 0 {
-            return None;
-        }
-        if pde & (1 << 7) != 0 {
-            return Some((pde & 0x000F_FFFF_FFE0_0000) | (virt & 0x1F_FFFF));
-        }
-        let pt_phys = pde & PAGE_MASK;
-
-        // PT -> Frame
-        let pte = get_table_entry(pt_phys, pt_idx);
-        if pte & FLAG_PRESENT == 0 {
+        let pde = get_table_entry(pdpte & PAGE_MASK, pd_idx);
+        if pde & FLAG_PRESENT == 0 || pde & (1 << 7) != 0 {
             return None;
         }
-        let frame_phys = pte & PAGE_MASK;
+        Some(pde & PAGE_MASK)
+    }
+}
 
-        Some(frame_phys | offset)
+/// Lê a PTE (4KB) bruta de `virt` em uma PML4 específica, se presente
+pub fn read_pte_in_p4(pml4_phys: u64, virt: u64) -> Option<u64> {
+    let pt_phys = find_pt_in_p4(pml4_phys, virt)?;
+    let pte = unsafe { get_table_entry(pt_phys, ((virt >> 12) & 0x1FF) as usize) };
+    if pte & FLAG_PRESENT == 0 {
+        None
+    } else {
+        Some(pte)
     }
 }
 
+/// Sobrescreve a PTE (4KB) de `virt` em uma PML4 específica
+///
+/// As tabelas intermediárias já devem existir. Não invalida o TLB. O rmap
+/// acompanha a troca de frame.
+///
+/// # Safety
+/// O chamador garante que `pte` aponta para um frame válido e que nenhuma
+/// outra CPU depende da entrada antiga sem um flush posterior.
+pub unsafe fn write_pte_in_p4(pml4_phys: u64, virt: u64, pte: u64) -> Result<(), &'static str> {
+    let pt_phys = find_pt_in_p4(pml4_phys, virt).ok_or("(VMM) PT não presente")?;
+    set_pte(
+        pml4_phys,
+        pt_phys,
+        ((virt >> 12) & 0x1FF) as usize,
+        virt,
+        pte,
+    );
+    Ok(())
+}
+
 /// Traduz endereço virtual para físico usando as tabelas de página atuais
 pub fn translate_addr(virt: u64) -> Option<u64> {
-    translate_addr_in_p4(read_cr3(), virt)
+    translate_with_flags(virt).map(|(phys, _)| phys.as_u64())
+}
+
+/// Traduz endereço virtual para físico usando as tabelas de página atuais,
+/// com as flags efetivas (ver [`translate_with_flags_in_p4`])
+pub fn translate_with_flags(virt: u64) -> Option<(crate::mm::PhysAddr, MapFlags)> {
+    translate_with_flags_in_p4(read_cr3(), virt)
 }
 
 /// Mapeia uma página virtual para um frame físico
@@ -206,7 +391,19 @@ pub fn translate_addr(virt: u64) -> Option<u64> {
 /// NOTA: Assume que todas as tabelas intermediárias (PDPT, PD, PT) já existem.
 /// Se uma tabela intermediária não existir, retorna erro.
 /// Para criar tabelas automaticamente, use `map_page_with_pmm`.
+///
+/// Huge pages (`HUGE_2M`/`HUGE_1G`), e a divisão de uma huge page no
+/// caminho, alocam tabelas pelo `FRAME_ALLOCATOR`: não chamar com o lock dele.
 pub fn map_page(page_virt: u64, frame_phys: u64, flags: MapFlags) -> Result<(), &'static str> {
+    if HugePageSize::from_flags(flags).is_some() {
+        return map_huge_page(
+            page_virt,
+            frame_phys,
+            flags,
+            &mut crate::mm::FRAME_ALLOCATOR.lock(),
+        );
+    }
+
     let pml4_phys = read_cr3();
 
     let pml4_idx = ((page_virt >> 39) & 0x1FF) as usize;
@@ -235,22 +432,42 @@ pub fn map_page(page_virt: u64, frame_phys: u64, flags: MapFlags) -> Result<(),
         let pdpt_phys = pml4e & PAGE_MASK;
 
         // PDPT -> PD
-        let pdpte = get_table_entry(pdpt_phys, pdpt_idx);
+        let mut pdpte = get_table_entry(pdpt_phys, pdpt_idx);
         if pdpte & FLAG_PRESENT == 0 {
             return Err("(VMM) PDPTE não presente");
         }
+        if pdpte & FLAG_HUGE != 0 {
+            pdpte = split_huge_entry(
+                pml4_phys,
+                pdpt_phys,
+                pdpt_idx,
+                page_virt,
+                HUGE_2MB,
+                &mut alloc_table_frame,
+            )?;
+        }
         let pd_phys = pdpte & PAGE_MASK;
 
         // PD -> PT
-        let pde = get_table_entry(pd_phys, pd_idx);
+        let mut pde = get_table_entry(pd_phys, pd_idx);
         if pde & FLAG_PRESENT == 0 {
             return Err("(VMM) PDE não presente");
         }
+        if pde & FLAG_HUGE != 0 {
+            pde = split_huge_entry(
+                pml4_phys,
+                pd_phys,
+                pd_idx,
+                page_virt,
+                PAGE_SIZE,
+                &mut alloc_table_frame,
+            )?;
+        }
         let pt_phys = pde & PAGE_MASK;
 
         // Escreve a PTE final
         let pte = frame_phys | pte_flags;
-        set_table_entry(pt_phys, pt_idx, pte);
+        set_pte(pml4_phys, pt_phys, pt_idx, page_virt, pte);
 
         // Invalida TLB para esta página
         asm!("invlpg [{}]", in(reg) page_virt, options(nostack, preserves_flags));
@@ -260,44 +477,189 @@ pub fn map_page(page_virt: u64, frame_phys: u64, flags: MapFlags) -> Result<(),
 }
 
 /// Desmapeia uma página virtual
+///
+/// Dentro de uma huge page, ela é dividida antes e só os 4KB saem. Tabelas
+/// que ficarem vazias voltam ao PMM (ver [`unmap_range_in_p4`]).
 pub fn unmap_page(page_virt: u64) -> Result<(), &'static str> {
-    let pml4_phys = read_cr3();
-
-    let pml4_idx = ((page_virt >> 39) & 0x1FF) as usize;
-    let pdpt_idx = ((page_virt >> 30) & 0x1FF) as usize;
-    let pd_idx = ((page_virt >> 21) & 0x1FF) as usize;
-    let pt_idx = ((page_virt >> 12) & 0x1FF) as usize;
+    unmap_range_in_p4(read_cr3(), page_virt, page_virt + PAGE_SIZE, &mut |_, _| {}).map(|_| ())
+}
 
+/// Desmapeia `[start, end)` (alinhados a 4KB) em uma PML4 específica e
+/// retorna quantas tabelas de página foram liberadas
+///
+/// Huge pages inteiras dentro do intervalo saem de uma vez; as que ele
+/// corta são divididas antes. `release` recebe o endereço e a entrada de
+/// cada página que saiu: o frame continua sendo de quem chama. PTs, PDs e
+/// PDPTs que ficarem vazios voltam ao PMM e saem da tabela de cima, menos os
+/// da metade do kernel (PML4 256..512), compartilhados por todas as PML4.
+///
+/// Invalida o TLB local se a PML4 estiver ativa. As tabelas vêm e voltam
+/// pelo `FRAME_ALLOCATOR`: não chamar com o lock dele.
+pub fn unmap_range_in_p4(
+    pml4_phys: u64,
+    start: u64,
+    end: u64,
+    release: &mut dyn FnMut(u64, u64),
+) -> Result<usize, &'static str> {
+    let active = read_cr3() == pml4_phys;
     unsafe {
-        // Navega até a PT
-        let pml4e = get_table_entry(pml4_phys, pml4_idx);
-        if pml4e & FLAG_PRESENT == 0 {
-            return Ok(()); // Já não está mapeada
+        unmap_range_with(
+            pml4_phys,
+            start,
+            end,
+            active,
+            release,
+            &mut alloc_table_frame,
+            &mut free_table_frame,
+        )
+    }
+}
+
+unsafe fn unmap_range_with(
+    pml4_phys: u64,
+    start: u64,
+    end: u64,
+    active: bool,
+    release: &mut dyn FnMut(u64, u64),
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+    free_table: &mut dyn FnMut(u64),
+) -> Result<usize, &'static str> {
+    let flush = |virt: u64| {
+        if active {
+            asm!("invlpg [{}]", in(reg) virt, options(nostack, preserves_flags));
+        }
+    };
+
+    let mut freed = 0;
+    // Último endereço visitado numa PT ainda não podada
+    let mut pending: Option<u64> = None;
+    let mut virt = start;
+    while virt < end {
+        if let Some(last) = pending {
+            if last & !(HUGE_2MB - 1) != virt & !(HUGE_2MB - 1) {
+                freed += prune_tables(pml4_phys, last, free_table);
+                flush(last);
+                pending = None;
+            }
         }
-        let pdpt_phys = pml4e & PAGE_MASK;
 
-        let pdpte = get_table_entry(pdpt_phys, pdpt_idx);
-        if pdpte & FLAG_PRESENT == 0 {
-            return Ok(());
+        let Some((table_phys, index, size)) = leaf_entry(pml4_phys, virt) else {
+            virt += PAGE_SIZE;
+            continue;
+        };
+        let entry = get_table_entry(table_phys, index);
+        pending = Some(virt);
+        if entry & FLAG_PRESENT == 0 {
+            virt += PAGE_SIZE;
+            continue;
+        }
+        if size != PAGE_SIZE && (virt & (size - 1) != 0 || end - virt < size) {
+            split_huge_in_p4(pml4_phys, virt, alloc_table)?;
+            continue;
         }
-        let pd_phys = pdpte & PAGE_MASK;
 
-        let pde = get_table_entry(pd_phys, pd_idx);
-        if pde & FLAG_PRESENT == 0 {
-            return Ok(());
+        if size == PAGE_SIZE {
+            set_pte(pml4_phys, table_phys, index, virt, 0);
+        } else {
+            set_table_entry(table_phys, index, 0);
         }
-        let pt_phys = pde & PAGE_MASK;
+        flush(virt);
+        release(virt, entry);
+        virt += size;
+    }
+    if let Some(last) = pending {
+        freed += prune_tables(pml4_phys, last, free_table);
+        flush(last);
+    }
 
-        // Limpa a PTE
-        set_table_entry(pt_phys, pt_idx, 0);
+    Ok(freed)
+}
 
-        // Invalida TLB
-        asm!("invlpg [{}]", in(reg) page_virt, options(nostack, preserves_flags));
+/// Libera, de baixo para cima, as tabelas (PT, PD, PDPT) no caminho de
+/// `virt` que estão vazias, limpando a entrada que apontava para cada uma;
+/// retorna quantas saíram
+///
+/// A metade do kernel nunca é podada. Não invalida o TLB.
+unsafe fn prune_tables(pml4_phys: u64, virt: u64, free_table: &mut dyn FnMut(u64)) -> usize {
+    let indices = [
+        ((virt >> 39) & 0x1FF) as usize,
+        ((virt >> 30) & 0x1FF) as usize,
+        ((virt >> 21) & 0x1FF) as usize,
+    ];
+    if indices[0] >= 256 {
+        return 0;
+    }
+
+    // PML4, PDPT, PD, PT: até onde o caminho existir
+    let mut tables = [pml4_phys, 0, 0, 0];
+    let mut depth = 0;
+    while depth < 3 {
+        let entry = get_table_entry(tables[depth], indices[depth]);
+        if entry & FLAG_PRESENT == 0 || entry & FLAG_HUGE != 0 {
+            break;
+        }
+        tables[depth + 1] = entry & PAGE_MASK;
+        depth += 1;
+    }
+
+    let mut freed = 0;
+    for level in (1..=depth).rev() {
+        if !table_is_empty(tables[level]) {
+            break;
+        }
+        set_table_entry(tables[level - 1], indices[level - 1], 0);
+        free_table(tables[level]);
+        freed += 1;
     }
+    freed
+}
 
+/// Reescreve as PTEs presentes de `[start, end)` (alinhados a 4KB) em uma
+/// PML4 específica com `update(pte) -> pte nova`, sem invalidar o TLB
+///
+/// Huge pages no intervalo são divididas antes, para a mudança valer página
+/// a página (as PTs novas vêm do `FRAME_ALLOCATOR`: não chamar com o lock
+/// dele).
+pub fn protect_range_in_p4(
+    pml4_phys: u64,
+    start: u64,
+    end: u64,
+    update: &mut dyn FnMut(u64) -> u64,
+) -> Result<(), &'static str> {
+    unsafe { protect_range_with(pml4_phys, start, end, update, &mut alloc_table_frame) }
+}
+
+unsafe fn protect_range_with(
+    pml4_phys: u64,
+    start: u64,
+    end: u64,
+    update: &mut dyn FnMut(u64) -> u64,
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+) -> Result<(), &'static str> {
+    let mut virt = start;
+    while virt < end {
+        let Some((table_phys, index, size)) = leaf_entry(pml4_phys, virt) else {
+            virt += PAGE_SIZE;
+            continue;
+        };
+        let entry = get_table_entry(table_phys, index);
+        if entry & FLAG_PRESENT != 0 {
+            if size != PAGE_SIZE {
+                split_huge_in_p4(pml4_phys, virt, alloc_table)?;
+                continue;
+            }
+            set_pte(pml4_phys, table_phys, index, virt, update(entry));
+        }
+        virt += PAGE_SIZE;
+    }
     Ok(())
 }
 
+/// Nenhuma entrada presente na tabela?
+unsafe fn table_is_empty(table_phys: u64) -> bool {
+    (0..PT_ENTRIES).all(|i| get_table_entry(table_phys, i) & FLAG_PRESENT == 0)
+}
+
 /// Mapeia página, criando tabelas intermediárias se necessário
 ///
 /// Usa o PMM para alocar frames para novas tabelas de página.
@@ -307,6 +669,10 @@ pub fn map_page_with_pmm(
     flags: MapFlags,
     pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
 ) -> Result<(), &'static str> {
+    if HugePageSize::from_flags(flags).is_some() {
+        return map_huge_page(page_virt, frame_phys, flags, pmm);
+    }
+
     let pml4_phys = read_cr3();
 
     let pml4_idx = ((page_virt >> 39) & 0x1FF) as usize;
@@ -373,6 +739,17 @@ pub fn map_page_with_pmm(
             pdpte = pd_phys | table_flags;
             set_table_entry(pdpt_phys, pdpt_idx, pdpte);
         } else {
+            // Huge page de 1GB no caminho: vira um PD de 2MB
+            if pdpte & FLAG_HUGE != 0 {
+                pdpte = split_huge_entry(
+                    pml4_phys,
+                    pdpt_phys,
+                    pdpt_idx,
+                    page_virt,
+                    HUGE_2MB,
+                    &mut || pmm.allocate_frame().map(|f| f.addr()),
+                )?;
+            }
             // Se já existe, atualizar flags (USER e WRITABLE)
             let mut changed = false;
             if flags.contains(MapFlags::USER) && (pdpte & FLAG_USER == 0) {
@@ -400,6 +777,17 @@ pub fn map_page_with_pmm(
             pde = pt_phys | table_flags;
             set_table_entry(pd_phys, pd_idx, pde);
         } else {
+            // Huge page de 2MB no caminho: vira uma PT de 4KB
+            if pde & FLAG_HUGE != 0 {
+                pde = split_huge_entry(
+                    pml4_phys,
+                    pd_phys,
+                    pd_idx,
+                    page_virt,
+                    PAGE_SIZE,
+                    &mut || pmm.allocate_frame().map(|f| f.addr()),
+                )?;
+            }
             // Se já existe, atualizar flags (USER e WRITABLE)
             let mut changed = false;
             if flags.contains(MapFlags::USER) && (pde & FLAG_USER == 0) {
@@ -418,7 +806,7 @@ pub fn map_page_with_pmm(
 
         // Finalmente, escreve a PTE
         let pte = frame_phys | pte_flags;
-        set_table_entry(pt_phys, pt_idx, pte);
+        set_pte(pml4_phys, pt_phys, pt_idx, page_virt, pte);
 
         // Invalida TLB
         asm!("invlpg [{}]", in(reg) page_virt, options(nostack, preserves_flags));
@@ -438,6 +826,12 @@ pub fn map_page_in_target_p4(
     flags: MapFlags,
     pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
 ) -> Result<(), &'static str> {
+    if let Some(size) = HugePageSize::from_flags(flags) {
+        return map_huge_in_p4(target_p4, page_virt, frame_phys, size, flags, &mut || {
+            pmm.allocate_frame().map(|f| f.addr())
+        });
+    }
+
     let pml4_idx = ((page_virt >> 39) & 0x1FF) as usize;
     let pdpt_idx = ((page_virt >> 30) & 0x1FF) as usize;
     let pd_idx = ((page_virt >> 21) & 0x1FF) as usize;
@@ -489,6 +883,16 @@ pub fn map_page_in_target_p4(
             pdpte = pd_phys | table_flags;
             set_table_entry(pdpt_phys, pdpt_idx, pdpte);
         } else {
+            if pdpte & FLAG_HUGE != 0 {
+                pdpte = split_huge_entry(
+                    target_p4,
+                    pdpt_phys,
+                    pdpt_idx,
+                    page_virt,
+                    HUGE_2MB,
+                    &mut || pmm.allocate_frame().map(|f| f.addr()),
+                )?;
+            }
             if flags.contains(MapFlags::USER) && (pdpte & FLAG_USER == 0) {
                 pdpte |= FLAG_USER;
                 set_table_entry(pdpt_phys, pdpt_idx, pdpte);
@@ -506,6 +910,16 @@ pub fn map_page_in_target_p4(
             pde = pt_phys | table_flags;
             set_table_entry(pd_phys, pd_idx, pde);
         } else {
+            if pde & FLAG_HUGE != 0 {
+                pde = split_huge_entry(
+                    target_p4,
+                    pd_phys,
+                    pd_idx,
+                    page_virt,
+                    PAGE_SIZE,
+                    &mut || pmm.allocate_frame().map(|f| f.addr()),
+                )?;
+            }
             if flags.contains(MapFlags::USER) && (pde & FLAG_USER == 0) {
                 pde |= FLAG_USER;
                 set_table_entry(pd_phys, pd_idx, pde);
@@ -515,7 +929,7 @@ pub fn map_page_in_target_p4(
 
         // Escreve a PTE final
         let pte = frame_phys | pte_flags;
-        set_table_entry(pt_phys, pt_idx, pte);
+        set_pte(target_p4, pt_phys, pt_idx, page_virt, pte);
 
         // Não faz invlpg aqui pois a target P4 pode não estar ativa
     }
@@ -523,6 +937,268 @@ pub fn map_page_in_target_p4(
     Ok(())
 }
 
+/// Mapeia uma huge page nas tabelas atuais, criando as intermediárias
+///
+/// O tamanho vem das flags: 2MB com `MapFlags::HUGE_2M`, 1GB com
+/// `MapFlags::HUGE_1G` (só se o CPUID anunciar). `page_virt` e `frame_phys`
+/// alinhados ao tamanho. Huge pages não entram no rmap.
+pub fn map_huge_page(
+    page_virt: u64,
+    frame_phys: u64,
+    flags: MapFlags,
+    pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
+) -> Result<(), &'static str> {
+    let size = HugePageSize::from_flags(flags).ok_or("(VMM) flags sem tamanho de huge page")?;
+    map_huge_in_p4(read_cr3(), page_virt, frame_phys, size, flags, &mut || {
+        pmm.allocate_frame().map(|f| f.addr())
+    })?;
+
+    // Uma invalidação cobre a huge page inteira
+    unsafe {
+        asm!("invlpg [{}]", in(reg) page_virt, options(nostack, preserves_flags));
+    }
+    Ok(())
+}
+
+/// Divide a huge page que cobre `page_virt` (nas tabelas atuais) até páginas
+/// de 4KB, com as mesmas flags, para que parte dela possa mudar de permissão
+///
+/// Sem huge page em `page_virt`, não faz nada.
+pub fn split_huge_page(
+    page_virt: u64,
+    pmm: &mut crate::mm::pmm::BitmapFrameAllocator,
+) -> Result<(), &'static str> {
+    unsafe {
+        split_huge_in_p4(read_cr3(), page_virt, &mut || {
+            pmm.allocate_frame().map(|f| f.addr())
+        })?;
+        asm!("invlpg [{}]", in(reg) page_virt, options(nostack, preserves_flags));
+    }
+    Ok(())
+}
+
+/// Frame para uma tabela nova, direto do `FRAME_ALLOCATOR`
+fn alloc_table_frame() -> Option<u64> {
+    crate::mm::FRAME_ALLOCATOR
+        .lock()
+        .allocate_frame()
+        .map(|f| f.addr())
+}
+
+/// Devolve ao `FRAME_ALLOCATOR` uma tabela que ficou vazia
+fn free_table_frame(table_phys: u64) {
+    crate::mm::FRAME_ALLOCATOR
+        .lock()
+        .deallocate_frame(crate::mm::PhysAddr::new(table_phys));
+}
+
+/// Entrada de huge page (PDE de 2MB ou PDPTE de 1GB) apontando para `phys`
+fn huge_entry(phys: u64, flags: MapFlags) -> u64 {
+    let mut entry = phys | FLAG_PRESENT | FLAG_HUGE;
+    if flags.contains(MapFlags::WRITABLE) {
+        entry |= FLAG_WRITABLE;
+    }
+    if flags.contains(MapFlags::USER) {
+        entry |= FLAG_USER;
+    }
+    if flags.contains(MapFlags::GLOBAL) {
+        entry |= FLAG_GLOBAL;
+    }
+    if !flags.contains(MapFlags::EXECUTABLE) || flags.contains(MapFlags::NO_EXECUTE) {
+        entry |= FLAG_NO_EXEC;
+    }
+    entry
+}
+
+/// Entrada `index` da tabela que substitui a huge page `entry`
+///
+/// `child` é o tamanho das novas páginas: 2MB (divisão de 1GB, as entradas
+/// continuam huge) ou 4KB (divisão de 2MB, PTEs comuns).
+fn split_child(entry: u64, child: u64, index: usize) -> u64 {
+    let offset = index as u64 * child;
+    if child == HUGE_2MB {
+        return ((entry & HUGE_1GB_MASK) + offset) | (entry & !HUGE_1GB_MASK);
+    }
+    let mut flags = entry & !HUGE_2MB_MASK & !(FLAG_HUGE | FLAG_HUGE_PAT);
+    if entry & FLAG_HUGE_PAT != 0 {
+        flags |= FLAG_HUGE; // PAT da PTE
+    }
+    ((entry & HUGE_2MB_MASK) + offset) | flags
+}
+
+/// Troca a huge page da entrada `index` de `table_phys` por uma tabela com
+/// 512 páginas de `child` bytes (ver [`split_child`]) e retorna a nova
+/// entrada
+///
+/// `virt` é qualquer endereço dentro da huge page. PTEs de usuário entram no
+/// rmap. Não invalida o TLB: a tradução é a mesma.
+unsafe fn split_huge_entry(
+    pml4_phys: u64,
+    table_phys: u64,
+    index: usize,
+    virt: u64,
+    child: u64,
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+) -> Result<u64, &'static str> {
+    let entry = get_table_entry(table_phys, index);
+    let base = virt & !(child * PT_ENTRIES as u64 - 1);
+
+    let new_table = alloc_table().ok_or("(VMM) OOM ao dividir huge page")?;
+    zero_page(new_table);
+    for i in 0..PT_ENTRIES {
+        let child_entry = split_child(entry, child, i);
+        if child == PAGE_SIZE {
+            set_pte(
+                pml4_phys,
+                new_table,
+                i,
+                base + i as u64 * PAGE_SIZE,
+                child_entry,
+            );
+        } else {
+            set_table_entry(new_table, i, child_entry);
+        }
+    }
+
+    // A tabela herda as permissões; o NX fica nas folhas
+    let new_entry = new_table | (entry & (FLAG_PRESENT | FLAG_WRITABLE | FLAG_USER));
+    set_table_entry(table_phys, index, new_entry);
+    Ok(new_entry)
+}
+
+/// Divide as huge pages que cobrem `virt` até 4KB e retorna a PT de `virt`
+/// (`None` se o caminho não estiver mapeado)
+unsafe fn split_huge_in_p4(
+    pml4_phys: u64,
+    virt: u64,
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+) -> Result<Option<u64>, &'static str> {
+    let pml4_idx = ((virt >> 39) & 0x1FF) as usize;
+    let pdpt_idx = ((virt >> 30) & 0x1FF) as usize;
+    let pd_idx = ((virt >> 21) & 0x1FF) as usize;
+
+    let pml4e = get_table_entry(pml4_phys, pml4_idx);
+    if pml4e & FLAG_PRESENT == 0 {
+        return Ok(None);
+    }
+    let pdpt_phys = pml4e & PAGE_MASK;
+
+    let mut pdpte = get_table_entry(pdpt_phys, pdpt_idx);
+    if pdpte & FLAG_PRESENT == 0 {
+        return Ok(None);
+    }
+    if pdpte & FLAG_HUGE != 0 {
+        pdpte = split_huge_entry(pml4_phys, pdpt_phys, pdpt_idx, virt, HUGE_2MB, alloc_table)?;
+    }
+    let pd_phys = pdpte & PAGE_MASK;
+
+    let mut pde = get_table_entry(pd_phys, pd_idx);
+    if pde & FLAG_PRESENT == 0 {
+        return Ok(None);
+    }
+    if pde & FLAG_HUGE != 0 {
+        pde = split_huge_entry(pml4_phys, pd_phys, pd_idx, virt, PAGE_SIZE, alloc_table)?;
+    }
+    Ok(Some(pde & PAGE_MASK))
+}
+
+/// Tabela apontada pela entrada `index` de `table_phys`, criada se faltar
+///
+/// Uma huge page no caminho é dividida em páginas de `child` bytes. Como em
+/// `map_page_with_pmm`, entradas existentes ganham USER/WRITABLE de
+/// `table_flags`.
+unsafe fn next_table(
+    pml4_phys: u64,
+    table_phys: u64,
+    index: usize,
+    virt: u64,
+    child: u64,
+    table_flags: u64,
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+) -> Result<u64, &'static str> {
+    let mut entry = get_table_entry(table_phys, index);
+    if entry & FLAG_PRESENT == 0 {
+        let new_table = alloc_table().ok_or("(VMM) OOM ao alocar tabela de páginas")?;
+        zero_page(new_table);
+        set_table_entry(table_phys, index, new_table | table_flags);
+        return Ok(new_table);
+    }
+    if entry & FLAG_HUGE != 0 {
+        entry = split_huge_entry(pml4_phys, table_phys, index, virt, child, alloc_table)?;
+    }
+    let granted = entry | (table_flags & (FLAG_USER | FLAG_WRITABLE));
+    if granted != entry {
+        set_table_entry(table_phys, index, granted);
+    }
+    Ok(entry & PAGE_MASK)
+}
+
+/// Mapeia uma huge page de `size` em uma PML4 específica, sem invalidar o
+/// TLB
+///
+/// Uma huge page de 1GB que cubra uma de 2MB é dividida. Se a entrada já
+/// aponta para uma tabela (há páginas menores na região), falha.
+fn map_huge_in_p4(
+    pml4_phys: u64,
+    page_virt: u64,
+    frame_phys: u64,
+    size: HugePageSize,
+    flags: MapFlags,
+    alloc_table: &mut dyn FnMut() -> Option<u64>,
+) -> Result<(), &'static str> {
+    let bytes = size.size();
+    if page_virt & (bytes - 1) != 0 || frame_phys & (bytes - 1) != 0 {
+        return Err("(VMM) huge page desalinhada");
+    }
+    if !size.is_supported() {
+        return Err("(VMM) CPU sem páginas de 1GB");
+    }
+
+    let pml4_idx = ((page_virt >> 39) & 0x1FF) as usize;
+    let pdpt_idx = ((page_virt >> 30) & 0x1FF) as usize;
+    let pd_idx = ((page_virt >> 21) & 0x1FF) as usize;
+
+    let mut table_flags = FLAG_PRESENT | FLAG_WRITABLE;
+    if flags.contains(MapFlags::USER) {
+        table_flags |= FLAG_USER;
+    }
+
+    unsafe {
+        let pdpt_phys = next_table(
+            pml4_phys,
+            pml4_phys,
+            pml4_idx,
+            page_virt,
+            HUGE_1GB,
+            table_flags,
+            alloc_table,
+        )?;
+        let (table_phys, index) = match size {
+            HugePageSize::Page1GB => (pdpt_phys, pdpt_idx),
+            HugePageSize::Page2MB => {
+                let pd_phys = next_table(
+                    pml4_phys,
+                    pdpt_phys,
+                    pdpt_idx,
+                    page_virt,
+                    HUGE_2MB,
+                    table_flags,
+                    alloc_table,
+                )?;
+                (pd_phys, pd_idx)
+            }
+        };
+
+        let old = get_table_entry(table_phys, index);
+        if old & FLAG_PRESENT != 0 && old & FLAG_HUGE == 0 {
+            return Err("(VMM) região já mapeada por uma tabela de páginas");
+        }
+        set_table_entry(table_phys, index, huge_entry(frame_phys, flags));
+    }
+
+    Ok(())
+}
+
 /// Zera uma página física (usada para novas tabelas de página)
 #[inline]
 unsafe fn zero_page(phys_addr: u64) {
@@ -533,3 +1209,302 @@ unsafe fn zero_page(phys_addr: u64) {
         i += 1;
     }
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::super::vmm::PageTable;
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    /// Tabelas de página na memória do host: sem HHDM, `phys_to_virt` é a
+    /// identidade, então o "físico" de uma tabela é o próprio ponteiro
+    #[derive(Default)]
+    struct Tables(Vec<Box<PageTable>>);
+
+    impl Tables {
+        fn alloc(&mut self) -> Option<u64> {
+            let table = Box::new(PageTable { entries: [0; 512] });
+            let phys = &*table as *const PageTable as u64;
+            self.0.push(table);
+            Some(phys)
+        }
+    }
+
+    #[test]
+    fn test_map_and_translate_2mb() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        let virt = 0xFFFF_9000_0020_0000;
+        let phys = 0x4060_0000;
+        let flags = MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::HUGE_2M;
+
+        map_huge_in_p4(pml4, virt, phys, HugePageSize::Page2MB, flags, &mut || {
+            tables.alloc()
+        })
+        .unwrap();
+
+        // PML4 + PDPT + PD: a huge page dispensa a PT
+        assert_eq!(tables.0.len(), 3);
+        assert_eq!(translate_addr_in_p4(pml4, virt), Some(phys));
+        assert_eq!(
+            translate_addr_in_p4(pml4, virt + 0x1F_F123),
+            Some(phys + 0x1F_F123)
+        );
+        assert_eq!(translate_addr_in_p4(pml4, virt + HUGE_2MB), None);
+
+        // Desalinhada
+        assert!(map_huge_in_p4(
+            pml4,
+            virt + 0x1000,
+            phys,
+            HugePageSize::Page2MB,
+            flags,
+            &mut || { tables.alloc() }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_translate_with_flags_2mb() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        let virt = 0x0000_7F00_0040_0000;
+        let phys = 0x1_2340_0000;
+        let flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE | MapFlags::HUGE_2M;
+        map_huge_in_p4(pml4, virt, phys, HugePageSize::Page2MB, flags, &mut || {
+            tables.alloc()
+        })
+        .unwrap();
+
+        let (addr, got) = translate_with_flags_in_p4(pml4, virt + 0x1_2345).unwrap();
+        assert_eq!(addr.as_u64(), phys + 0x1_2345);
+        assert_eq!(got, flags | MapFlags::NO_EXECUTE);
+        let (addr, _) = translate_with_flags_in_p4(pml4, virt + HUGE_2MB - 1).unwrap();
+        assert_eq!(addr.as_u64(), phys + HUGE_2MB - 1);
+
+        // PDPTE só de leitura: a huge page deixa de ser escrevível
+        unsafe {
+            let pdpt = get_table_entry(pml4, 254) & PAGE_MASK;
+            set_table_entry(pdpt, 0, get_table_entry(pdpt, 0) & !FLAG_WRITABLE);
+        }
+        let (_, got) = translate_with_flags_in_p4(pml4, virt).unwrap();
+        assert!(!got.contains(MapFlags::WRITABLE) && got.contains(MapFlags::USER));
+
+        // Não canônico: nem olha as tabelas
+        assert_eq!(
+            translate_with_flags_in_p4(pml4, 0x0000_8000_0000_0000),
+            None
+        );
+        assert_eq!(
+            translate_with_flags_in_p4(pml4, 0xFFFF_7FFF_FFFF_F000),
+            None
+        );
+        assert_eq!(translate_with_flags_in_p4(pml4, virt - HUGE_2MB), None);
+    }
+
+    #[test]
+    fn test_split_2mb_keeps_translation() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        let virt = 0xFFFF_9000_0040_0000;
+        let phys = 0x8000_0000;
+        let flags = MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::GLOBAL;
+
+        map_huge_in_p4(pml4, virt, phys, HugePageSize::Page2MB, flags, &mut || {
+            tables.alloc()
+        })
+        .unwrap();
+        let pt = unsafe { split_huge_in_p4(pml4, virt + 0x5000, &mut || tables.alloc()) }
+            .unwrap()
+            .unwrap();
+
+        // 512 PTEs de 4KB, mesmas permissões, sem o PS
+        for i in [0usize, 5, 511] {
+            let pte = unsafe { get_table_entry(pt, i) };
+            assert_eq!(pte & PAGE_MASK, phys + i as u64 * PAGE_SIZE);
+            assert_eq!(
+                pte & !PAGE_MASK,
+                FLAG_PRESENT | FLAG_WRITABLE | FLAG_GLOBAL | FLAG_NO_EXEC
+            );
+        }
+        assert_eq!(
+            read_pte_in_p4(pml4, virt + 0x5000).map(|p| p & PAGE_MASK),
+            Some(phys + 0x5000)
+        );
+        assert_eq!(
+            translate_addr_in_p4(pml4, virt + 0x1F_F123),
+            Some(phys + 0x1F_F123)
+        );
+    }
+
+    /// Mapeia uma página de 4KB, criando as tabelas do caminho
+    fn map_4k(tables: &mut Tables, pml4: u64, virt: u64, phys: u64) {
+        let table_flags = FLAG_PRESENT | FLAG_WRITABLE | FLAG_USER;
+        let mut table = pml4;
+        for (shift, child) in [(39, HUGE_1GB), (30, HUGE_2MB), (21, PAGE_SIZE)] {
+            let index = (virt >> shift) as usize & 0x1FF;
+            let alloc = &mut || tables.alloc();
+            table =
+                unsafe { next_table(pml4, table, index, virt, child, table_flags, alloc) }.unwrap();
+        }
+        unsafe { set_table_entry(table, (virt >> 12) as usize & 0x1FF, phys | table_flags) };
+    }
+
+    /// Desmapeia `[start, end)` guardando as páginas (endereço, frame) e as
+    /// tabelas liberadas
+    fn unmap(
+        tables: &mut Tables,
+        pml4: u64,
+        (start, end): (u64, u64),
+        released: &mut Vec<(u64, u64)>,
+        freed: &mut Vec<u64>,
+    ) -> usize {
+        unsafe {
+            unmap_range_with(
+                pml4,
+                start,
+                end,
+                false,
+                &mut |virt, pte| released.push((virt, pte & PAGE_MASK)),
+                &mut || tables.alloc(),
+                &mut |table| freed.push(table),
+            )
+        }
+        .unwrap()
+    }
+
+    #[test]
+    fn test_unmap_range_frees_tables() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        // Duas PTs (a região cruza um limite de 2MB) e uma huge page de 2MB
+        let start = 0x0040_001F_0000;
+        let huge = 0x0040_0040_0000;
+        for i in 0..0x20 {
+            map_4k(
+                &mut tables,
+                pml4,
+                start + i * PAGE_SIZE,
+                0x10_0000 + i * PAGE_SIZE,
+            );
+        }
+        let flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::HUGE_2M;
+        map_huge_in_p4(
+            pml4,
+            huge,
+            0x20_0000,
+            HugePageSize::Page2MB,
+            flags,
+            &mut || tables.alloc(),
+        )
+        .unwrap();
+        assert_eq!(tables.0.len(), 5);
+
+        // Metade da primeira PT: nada é liberado
+        let mut released = Vec::new();
+        let mut freed = Vec::new();
+        let first = (start, start + 0x8000);
+        assert_eq!(
+            unmap(&mut tables, pml4, first, &mut released, &mut freed),
+            0
+        );
+        assert_eq!(released.len(), 8);
+        assert_eq!(released[7], (start + 0x7000, 0x10_7000));
+        assert_eq!(translate_addr_in_p4(pml4, start + 0x8000), Some(0x10_8000));
+
+        // O resto: as duas PTs, o PD e o PDPT voltam, e a PML4 fica limpa
+        let rest = (start, huge + HUGE_2MB);
+        assert_eq!(unmap(&mut tables, pml4, rest, &mut released, &mut freed), 4);
+        assert_eq!(released.len(), 0x21);
+        assert_eq!(released[0x20], (huge, 0x20_0000));
+        assert_eq!(freed.len(), 4);
+        assert!(!freed.contains(&pml4));
+        assert!(unsafe { table_is_empty(pml4) });
+    }
+
+    #[test]
+    fn test_protect_range() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        let virt = 0x0000_7F00_0000_0000;
+        map_4k(&mut tables, pml4, virt, 0x10_0000);
+        map_4k(&mut tables, pml4, virt + PAGE_SIZE, 0x10_1000);
+        let huge = virt + HUGE_2MB;
+        let flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE | MapFlags::HUGE_2M;
+        map_huge_in_p4(
+            pml4,
+            huge,
+            0x20_0000,
+            HugePageSize::Page2MB,
+            flags,
+            &mut || tables.alloc(),
+        )
+        .unwrap();
+
+        // Primeira página de 4KB e a primeira da huge page, que é dividida
+        let read_only = &mut |pte: u64| pte & !FLAG_WRITABLE;
+        for start in [virt, huge] {
+            unsafe {
+                protect_range_with(pml4, start, start + PAGE_SIZE, read_only, &mut || {
+                    tables.alloc()
+                })
+            }
+            .unwrap();
+        }
+
+        let writable = |virt| {
+            let (_, flags) = translate_with_flags_in_p4(pml4, virt).unwrap();
+            flags.contains(MapFlags::WRITABLE)
+        };
+        assert!(!writable(virt) && writable(virt + PAGE_SIZE));
+        assert!(!writable(huge) && writable(huge + PAGE_SIZE));
+        assert_eq!(page_size_in_p4(pml4, huge + PAGE_SIZE), Some(PAGE_SIZE));
+        assert_eq!(translate_addr_in_p4(pml4, huge + 0x1234), Some(0x20_1234));
+    }
+
+    #[test]
+    fn test_unmap_keeps_kernel_tables() {
+        let mut tables = Tables::default();
+        let pml4 = tables.alloc().unwrap();
+        let virt = 0xFFFF_9000_0000_0000;
+        map_4k(&mut tables, pml4, virt, 0x10_0000);
+
+        let freed = unsafe {
+            unmap_range_with(
+                pml4,
+                virt,
+                virt + PAGE_SIZE,
+                false,
+                &mut |_, _| {},
+                &mut || None,
+                &mut |_| panic!("tabela do kernel liberada"),
+            )
+        };
+        assert_eq!(freed, Ok(0));
+        assert_eq!(translate_addr_in_p4(pml4, virt), None);
+        assert_ne!(unsafe { get_table_entry(pml4, 288) }, 0);
+    }
+
+    #[test]
+    fn test_split_child_entries() {
+        let huge_1g = 0x4000_0000 | FLAG_PRESENT | FLAG_USER | FLAG_HUGE | FLAG_NO_EXEC;
+        // 1GB -> 2MB: continuam huge
+        assert_eq!(split_child(huge_1g, HUGE_2MB, 3), huge_1g + 3 * HUGE_2MB);
+
+        // 2MB -> 4KB: o PAT passa do bit 12 para o 7
+        let huge_2m = 0x20_0000 | FLAG_PRESENT | FLAG_HUGE | FLAG_HUGE_PAT;
+        assert_eq!(
+            split_child(huge_2m, PAGE_SIZE, 2),
+            0x20_2000 | FLAG_PRESENT | FLAG_HUGE
+        );
+        assert_eq!(
+            split_child(huge_2m & !FLAG_HUGE_PAT, PAGE_SIZE, 1),
+            0x20_1000 | FLAG_PRESENT
+        );
+    }
+}
@@ -7,5 +7,8 @@ pub mod mapper;
 pub mod tlb;
 pub mod vmm;
 
-pub use mapper::{map_page, map_page_in_target_p4, map_page_with_pmm, translate_addr, unmap_page};
+pub use mapper::{
+    map_huge_page, map_page, map_page_in_target_p4, map_page_with_pmm, split_huge_page,
+    translate_addr, translate_with_flags, unmap_page,
+};
 pub use vmm::{init, MapFlags, PageTable};
@@ -10,9 +10,10 @@ bitflags! {
         const NO_CACHE = 1 << 4;
         const ACCESSED = 1 << 5;
         const DIRTY = 1 << 6;
-        const HUGE_PAGE = 1 << 7;
+        const HUGE_2M = 1 << 7; // Huge page de 2MB (PS na PDE)
         const GLOBAL = 1 << 8;
         const EXECUTABLE = 1 << 9; // Flag de controle interna
+        const HUGE_1G = 1 << 10; // Huge page de 1GB (PS na PDPTE), interna
         const NO_EXECUTE = 1 << 63;
     }
 }
@@ -0,0 +1,74 @@
+//! # DMA de Módulos
+//!
+//! Frames concedidos a módulos com `ModuleCapType::DmaAccess`.
+//!
+//! ## Isolamento
+//! Cada concessão cria um `IommuDomain` próprio contendo apenas os frames
+//! concedidos, em identidade (IOVA == endereço físico): o driver programa o
+//! dispositivo com o físico que recebeu, e qualquer outro endereço — kernel,
+//! outros módulos — não tem tradução no domínio.
+//!
+//! Os frames ficam `FrameState::Device` no PFM enquanto concedidos.
+//!
+//! Sem IOMMU o domínio continua sendo montado, mas nada o aplica no
+//! hardware; por isso a carga só é permitida com o override explícito do
+//! supervisor.
+
+use super::ModuleError;
+use crate::arch::x86_64::iommu::IommuDomain;
+use crate::mm::config::PAGE_SIZE;
+use crate::mm::pfm::iommu::{alloc_dma_region, free_dma_region, DmaRegion};
+use crate::mm::pfm::PID_KERNEL;
+use crate::mm::PhysAddr;
+
+/// Frames de DMA de um módulo e o domínio que os isola
+pub struct DmaGrant {
+    region: DmaRegion,
+    domain: IommuDomain,
+}
+
+impl DmaGrant {
+    /// Aloca `frames` frames para o módulo `module_id` e monta o domínio.
+    pub fn new(module_id: u64, frames: usize) -> Result<Self, ModuleError> {
+        if frames == 0 {
+            return Err(ModuleError::InternalError);
+        }
+
+        let domain = IommuDomain::new().map_err(|_| ModuleError::InternalError)?;
+        let region = alloc_dma_region(frames * PAGE_SIZE, PID_KERNEL, module_id as u32)
+            .map_err(|_| ModuleError::InternalError)?;
+
+        let mut grant = Self { region, domain };
+        for frame in grant.frames() {
+            if grant.domain.map(frame.as_u64(), frame, true).is_err() {
+                grant.release();
+                return Err(ModuleError::InternalError);
+            }
+        }
+        Ok(grant)
+    }
+
+    /// Frames concedidos (também os únicos IOVAs válidos)
+    pub fn frames(&self) -> impl Iterator<Item = PhysAddr> {
+        let base = self.region.phys_start.as_u64();
+        let count = self.region.size / PAGE_SIZE;
+        (0..count).map(move |i| PhysAddr::new(base + (i * PAGE_SIZE) as u64))
+    }
+
+    /// Domínio IOMMU do módulo
+    pub fn domain(&self) -> &IommuDomain {
+        &self.domain
+    }
+
+    /// Desmonta o domínio e devolve os frames ao PMM.
+    pub fn release(self) {
+        // O domínio sai primeiro: nenhum IOVA aponta para frames já livres
+        drop(self.domain);
+        if free_dma_region(&self.region).is_err() {
+            crate::kerror!(
+                "(Module) Falha ao liberar frames de DMA:",
+                self.region.phys_start.as_u64()
+            );
+        }
+    }
+}
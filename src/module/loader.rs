@@ -5,15 +5,24 @@
 //! ## Responsabilidades
 //! - Ler arquivo do VFS
 //! - Parsear formato ELF
-//! - Alocar páginas separadas para código (RX) e dados (RW)
-//! - Resolver símbolos da Module ABI
+//! - Alocar páginas separadas para código (RX) e dados (RW), com W^X
+//!   garantido nas PTEs finais
+//! - Resolver símbolos da Module ABI (apenas os da allowlist, ver `symbols`)
 
 use super::{LoadedModule, ModuleError};
-use crate::fs::vfs::file::{File, FileOps, OpenFlags};
+use crate::mm::config::{MODULE_SLOTS, MODULE_SLOT_SIZE, MODULE_VIRT_BASE};
+use crate::mm::pmm::{FRAME_ALLOCATOR, FRAME_SIZE};
+use crate::mm::vmm::mapper::{
+    map_page_in_target_p4, read_cr3, read_pte_in_p4, write_pte_in_p4, PTE_ADDR_MASK,
+};
+use crate::mm::vmm::MapFlags;
+use crate::mm::PhysAddr;
+use crate::sched::exec::fmt::elf::reloc;
+use crate::sched::exec::fmt::elf::structs::{Elf64_Ehdr, Elf64_Phdr, PF_W, PF_X, PT_LOAD};
+use crate::sys::KernelError;
 use alloc::vec::Vec;
 
 /// Carregador de módulos ELF
-#[allow(dead_code)]
 pub struct ModuleLoader {
     /// Se deve verificar W^X (código nunca é escrevível)
     enforce_wx: bool,
@@ -27,35 +36,22 @@ impl ModuleLoader {
 
     /// Carrega dados brutos do módulo do VFS
     pub fn load_from_vfs(&self, path: &str) -> Result<Vec<u8>, ModuleError> {
-        use crate::fs::vfs::ROOT_VFS;
-
-        let vfs = ROOT_VFS.lock();
-
-        let node = vfs.lookup(path).map_err(|_| ModuleError::NotFound)?;
-
-        // let handle = node.open().map_err(|_| ModuleError::InternalError)?;
-        // WARNING: Unsafe File creation from reference. Node must live long enough.
-        // Assuming single-threaded bootloader usage where node won't vanish.
-        let handle = File::new(&node, OpenFlags(OpenFlags::READ));
-
-        let size = node.size as usize;
-        if size == 0 {
+        let data = crate::fs::vfs::read_file(path).ok_or(ModuleError::NotFound)?;
+        if data.is_empty() {
             return Err(ModuleError::InvalidFormat);
         }
-
-        let mut buffer = Vec::with_capacity(size);
-        unsafe {
-            buffer.set_len(size);
-        }
-
-        handle
-            .read(&mut buffer)
-            .map_err(|_| ModuleError::InternalError)?;
-
-        Ok(buffer)
+        Ok(data)
     }
 
     /// Parseia ELF e carrega nas páginas do módulo
+    ///
+    /// A imagem é montada no slot virtual do módulo em três fases:
+    /// 1. Cada página dos segmentos é mapeada RW/NX (janela temporária).
+    /// 2. Os bytes do arquivo são copiados e as relocações aplicadas.
+    /// 3. As PTEs recebem as permissões finais: código RX, dados RW/NX e
+    ///    somente leitura R/NX.
+    ///
+    /// Em qualquer falha, as páginas já mapeadas são devolvidas.
     pub fn parse_and_load(
         &self,
         elf_data: &[u8],
@@ -71,51 +67,275 @@ impl ModuleLoader {
             return Err(ModuleError::InvalidFormat);
         }
 
-        // Extrair entry point (offset 0x18 em ELF64)
-        if elf_data.len() >= 0x20 {
-            module.entry_point = u64::from_le_bytes([
-                elf_data[0x18],
-                elf_data[0x19],
-                elf_data[0x1A],
-                elf_data[0x1B],
-                elf_data[0x1C],
-                elf_data[0x1D],
-                elf_data[0x1E],
-                elf_data[0x1F],
-            ]);
+        let ehdr: Elf64_Ehdr = read_at(elf_data, 0).ok_or(ModuleError::InvalidFormat)?;
+        let phdrs = program_headers(elf_data, &ehdr)?;
+
+        // Análise estática: só imports presentes na allowlist do kernel
+        module.imports = super::symbols::resolve_imports(elf_data)?
+            .iter()
+            .map(|import| (import.index, import.addr))
+            .collect();
+
+        // Validar segmentos e calcular a extensão da imagem
+        let mut min_vaddr = u64::MAX;
+        let mut max_vaddr = 0u64;
+        let mut code_pages_needed = 0usize;
+        let mut data_pages_needed = 0usize;
+        for seg in phdrs.iter().filter(|p| p.p_type == PT_LOAD) {
+            let flags = self.segment_flags(seg.p_flags)?;
+            let file_end = seg.p_offset.checked_add(seg.p_filesz);
+            let mem_end = seg.p_vaddr.checked_add(seg.p_memsz);
+            let (start, end) = match (file_end, mem_end) {
+                (Some(file_end), Some(mem_end))
+                    if file_end <= elf_data.len() as u64 && seg.p_filesz <= seg.p_memsz =>
+                {
+                    (seg.p_vaddr & !(FRAME_SIZE - 1), page_align_up(mem_end))
+                }
+                _ => return Err(ModuleError::InvalidFormat),
+            };
+
+            let pages = ((end - start) / FRAME_SIZE) as usize;
+            if flags.contains(MapFlags::EXECUTABLE) {
+                code_pages_needed += pages;
+            } else {
+                data_pages_needed += pages;
+            }
+            min_vaddr = core::cmp::min(min_vaddr, start);
+            max_vaddr = core::cmp::max(max_vaddr, end);
+        }
+        if max_vaddr == 0 {
+            return Err(ModuleError::InvalidFormat);
         }
 
-        // Alocar páginas de código (1 página por 4KB de dados)
-        let code_pages_needed = (elf_data.len() + 4095) / 4096;
-        if code_pages_needed > module.limits.max_code_pages {
+        if code_pages_needed > module.limits.max_code_pages
+            || data_pages_needed > module.limits.max_data_pages
+            || max_vaddr - min_vaddr > MODULE_SLOT_SIZE as u64
+            || module.id.as_u64() >= MODULE_SLOTS as u64
+        {
             return Err(ModuleError::LimitReached);
         }
 
-        // Alocar páginas via PMM
-        for _ in 0..code_pages_needed {
-            match crate::mm::pmm::FRAME_ALLOCATOR.lock().allocate_frame() {
-                Some(frame) => {
-                    module.code_pages.push(frame.addr());
+        let base = (MODULE_VIRT_BASE + module.id.as_u64() as usize * MODULE_SLOT_SIZE) as u64;
+        let bias = base.wrapping_sub(min_vaddr);
+        module.base = base;
+        module.image_size = (max_vaddr - min_vaddr) as usize;
+
+        if let Err(e) = self.layout_image(elf_data, &phdrs, bias, module) {
+            self.free_pages(module);
+            return Err(e);
+        }
+
+        module.entry_point = ehdr.e_entry.wrapping_add(bias);
+        module.exit_fn = super::symbols::find_export(elf_data, "module_exit")
+            .map(|value| value.wrapping_add(bias));
+        module.health_fn = super::symbols::find_export(elf_data, "module_health")
+            .map(|value| value.wrapping_add(bias));
+        Ok(())
+    }
+
+    /// Permissões finais de um segmento `PT_LOAD`.
+    ///
+    /// Com W^X ativo, segmentos que pedem escrita e execução são recusados.
+    pub fn segment_flags(&self, p_flags: u32) -> Result<MapFlags, ModuleError> {
+        let writable = p_flags & PF_W != 0;
+        let executable = p_flags & PF_X != 0;
+
+        if writable && executable && self.enforce_wx {
+            crate::kwarn!("(Module) Segmento W+X recusado (W^X)");
+            return Err(ModuleError::InvalidFormat);
+        }
+
+        let mut flags = MapFlags::PRESENT;
+        if writable {
+            flags |= MapFlags::WRITABLE;
+        }
+        if executable {
+            flags |= MapFlags::EXECUTABLE;
+        } else {
+            flags |= MapFlags::NO_EXECUTE;
+        }
+        Ok(flags)
+    }
+
+    /// Mapeia, copia, reloca e sela os segmentos da imagem
+    fn layout_image(
+        &self,
+        elf_data: &[u8],
+        phdrs: &[Elf64_Phdr],
+        bias: u64,
+        module: &mut LoadedModule,
+    ) -> Result<(), ModuleError> {
+        let cr3 = read_cr3();
+        // Página → permissões finais
+        let mut layout: Vec<(u64, MapFlags)> = Vec::new();
+
+        // 1. Janela temporária: tudo RW e não executável
+        for seg in phdrs.iter().filter(|p| p.p_type == PT_LOAD) {
+            let flags = self.segment_flags(seg.p_flags)?;
+            let start = (seg.p_vaddr + bias) & !(FRAME_SIZE - 1);
+            let end = page_align_up(seg.p_vaddr + bias + seg.p_memsz);
+
+            let mut vaddr = start;
+            while vaddr < end {
+                if let Some(&(_, existing)) = layout.iter().find(|(page, _)| *page == vaddr) {
+                    // Página compartilhada entre segmentos só se as permissões
+                    // finais coincidem; senão W^X seria violado.
+                    if existing != flags {
+                        crate::kwarn!("(Module) Segmentos RX/RW na mesma página:", vaddr);
+                        return Err(ModuleError::InvalidFormat);
+                    }
+                    vaddr += FRAME_SIZE;
+                    continue;
                 }
-                None => {
-                    // Liberar páginas já alocadas
-                    self.free_pages(module);
+
+                let mut pmm = FRAME_ALLOCATOR.lock();
+                let frame = pmm.allocate_frame().ok_or(ModuleError::InternalError)?;
+                if map_page_in_target_p4(cr3, vaddr, frame.as_u64(), WINDOW_FLAGS, &mut pmm)
+                    .is_err()
+                {
+                    pmm.deallocate_frame(frame);
                     return Err(ModuleError::InternalError);
                 }
+                drop(pmm);
+                crate::mm::vmm::tlb::flush(vaddr);
+
+                // SAFETY: página recém-mapeada como RW no slot do módulo
+                unsafe { core::ptr::write_bytes(vaddr as *mut u8, 0, FRAME_SIZE as usize) };
+
+                if flags.contains(MapFlags::EXECUTABLE) {
+                    module.code_pages.push(frame.as_u64());
+                } else {
+                    module.data_pages.push(frame.as_u64());
+                }
+                layout.push((vaddr, flags));
+                vaddr += FRAME_SIZE;
             }
         }
 
-        // TODO: Copiar código para as páginas
-        // TODO: Parsear seções .data e .bss
+        // 2. Copiar dados do arquivo e aplicar relocações
+        for seg in phdrs.iter().filter(|p| p.p_type == PT_LOAD) {
+            let src = &elf_data[seg.p_offset as usize..(seg.p_offset + seg.p_filesz) as usize];
+            // SAFETY: destino inteiro mapeado RW na fase 1
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    src.as_ptr(),
+                    (seg.p_vaddr + bias) as *mut u8,
+                    src.len(),
+                );
+            }
+        }
+
+        let image_start = module.base;
+        let image_end = module.base + module.image_size as u64;
+        let in_image = |vaddr: u64| vaddr >= image_start && vaddr.saturating_add(8) <= image_end;
+        if let Some(table) =
+            reloc::find_rela_table(elf_data, phdrs).map_err(|_| ModuleError::InvalidFormat)?
+        {
+            reloc::apply_relocations(elf_data, table, bias, |vaddr, value| {
+                if !in_image(vaddr) {
+                    return Err(KernelError::InvalidArgument);
+                }
+                // SAFETY: alvo dentro da imagem, ainda RW
+                unsafe { core::ptr::write_unaligned(vaddr as *mut u64, value) };
+                Ok(())
+            })
+            .map_err(|_| ModuleError::InvalidFormat)?;
+        }
+
+        // 3. Selar: permissões finais (código perde WRITABLE)
+        let mut pmm = FRAME_ALLOCATOR.lock();
+        for &(vaddr, flags) in layout.iter() {
+            let phys =
+                read_pte_in_p4(cr3, vaddr).ok_or(ModuleError::InternalError)? & PTE_ADDR_MASK;
+            map_page_in_target_p4(cr3, vaddr, phys, flags, &mut pmm)
+                .map_err(|_| ModuleError::InternalError)?;
+            crate::mm::vmm::tlb::flush(vaddr);
+        }
 
         Ok(())
     }
 
-    /// Libera páginas de um módulo
+    /// Desmapeia a imagem do módulo e devolve os frames ao PMM
     pub fn free_pages(&self, module: &mut LoadedModule) {
-        // TODO: Implementar liberação real quando API estiver estável
-        // Por agora, apenas limpa as listas
+        let cr3 = read_cr3();
+        let end = module.base + module.image_size as u64;
+        let mut vaddr = module.base;
+        while vaddr < end {
+            if let Some(pte) = read_pte_in_p4(cr3, vaddr) {
+                // SAFETY: a PT existe (a PTE estava presente)
+                unsafe {
+                    let _ = write_pte_in_p4(cr3, vaddr, 0);
+                }
+                crate::mm::vmm::tlb::flush(vaddr);
+                FRAME_ALLOCATOR
+                    .lock()
+                    .deallocate_frame(PhysAddr::new(pte & PTE_ADDR_MASK));
+            }
+            vaddr += FRAME_SIZE;
+        }
+
         module.code_pages.clear();
         module.data_pages.clear();
+        module.image_size = 0;
+    }
+}
+
+/// Flags da janela de carga: escrevível, nunca executável
+const WINDOW_FLAGS: MapFlags = MapFlags::from_bits_truncate(
+    MapFlags::PRESENT.bits() | MapFlags::WRITABLE.bits() | MapFlags::NO_EXECUTE.bits(),
+);
+
+fn page_align_up(addr: u64) -> u64 {
+    (addr + FRAME_SIZE - 1) & !(FRAME_SIZE - 1)
+}
+
+/// Lê um `T` (repr(C)) de `data[offset..]` sem exigir alinhamento
+fn read_at<T: Copy>(data: &[u8], offset: usize) -> Option<T> {
+    let end = offset.checked_add(core::mem::size_of::<T>())?;
+    if end > data.len() {
+        return None;
+    }
+    Some(unsafe { core::ptr::read_unaligned(data.as_ptr().add(offset) as *const T) })
+}
+
+/// Tabela de program headers do ELF
+fn program_headers(data: &[u8], ehdr: &Elf64_Ehdr) -> Result<Vec<Elf64_Phdr>, ModuleError> {
+    let size = ehdr.e_phentsize as usize;
+    if size < core::mem::size_of::<Elf64_Phdr>() {
+        return Err(ModuleError::InvalidFormat);
+    }
+    (0..ehdr.e_phnum as usize)
+        .map(|i| {
+            i.checked_mul(size)
+                .and_then(|off| off.checked_add(ehdr.e_phoff as usize))
+                .and_then(|off| read_at(data, off))
+                .ok_or(ModuleError::InvalidFormat)
+        })
+        .collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_flags_wx() {
+        let loader = ModuleLoader::new();
+
+        assert_eq!(
+            loader.segment_flags(PF_W | PF_X),
+            Err(ModuleError::InvalidFormat)
+        );
+
+        let text = loader.segment_flags(PF_X).unwrap();
+        assert!(text.contains(MapFlags::EXECUTABLE));
+        assert!(!text.contains(MapFlags::WRITABLE));
+
+        let data = loader.segment_flags(PF_W).unwrap();
+        assert!(data.contains(MapFlags::WRITABLE | MapFlags::NO_EXECUTE));
     }
 }
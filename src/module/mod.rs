@@ -46,6 +46,9 @@ pub mod abi;
 /// Capabilities específicas de módulos
 pub mod capability;
 
+/// Frames de DMA e domínios IOMMU
+pub mod dma;
+
 /// Carregador ELF
 pub mod loader;
 
@@ -55,6 +58,9 @@ pub mod sandbox;
 /// Supervisor de ciclo de vida
 pub mod supervisor;
 
+/// Allowlist de símbolos do kernel
+pub mod symbols;
+
 /// Verificação de assinatura
 pub mod verifier;
 
@@ -69,7 +75,7 @@ pub use abi::{ModuleAbi, ModuleInfo};
 pub use capability::{ModuleCapType, ModuleCapability};
 pub use loader::ModuleLoader;
 pub use sandbox::ModuleSandbox;
-pub use supervisor::{LoadedModule, ModuleId, ModuleSupervisor, SUPERVISOR};
+pub use supervisor::{LoadedModule, ModuleId, ModuleLimits, ModuleSupervisor, SUPERVISOR};
 pub use verifier::SignatureVerifier;
 pub use watchdog::{HealthStatus, ModuleWatchdog};
 
@@ -106,13 +112,25 @@ pub enum ModuleError {
 // PUBLIC API
 // =============================================================================
 
+/// Intervalo entre rodadas de ping do watchdog (ms)
+const WATCHDOG_PERIOD_MS: u64 = 1000;
+
 /// Inicializa o sistema de módulos
 pub fn init() {
     crate::kinfo!("(Module) Inicializando supervisor...");
     SUPERVISOR.lock().init();
+    crate::sched::core::spawn_kernel_thread("module-watchdog", watchdog_thread);
     crate::kinfo!("(Module) Sistema de módulos inicializado");
 }
 
+/// Thread do watchdog: pinga os módulos periodicamente
+extern "C" fn watchdog_thread() -> ! {
+    loop {
+        crate::sched::core::sleep_current(WATCHDOG_PERIOD_MS);
+        SUPERVISOR.lock().watchdog_tick();
+    }
+}
+
 /// Carrega um módulo
 pub fn load(path: &str) -> Result<ModuleId, ModuleError> {
     SUPERVISOR.lock().load_module(path)
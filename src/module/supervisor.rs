@@ -7,12 +7,19 @@
 //! - Alocar recursos (páginas, capabilities)
 //! - Monitorar saúde via watchdog
 //! - Gerenciar fallbacks
-use super::{ModuleError, ModuleLoader, ModuleSandbox, ModuleWatchdog, SignatureVerifier};
+use super::dma::DmaGrant;
+use super::{
+    HealthStatus, ModuleError, ModuleLoader, ModuleSandbox, ModuleWatchdog, SignatureVerifier,
+};
+use crate::core::time::jiffies::{get_jiffies, millis_to_jiffies};
+use crate::sched::core::{exit_current, kill_ready, kill_sleeping, spawn_kernel_thread, yield_now};
 use crate::security::Capability;
-use crate::sync::Mutex;
+use crate::sync::{Mutex, Spinlock};
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicI64, Ordering};
 
 /// ID único de um módulo carregado
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -71,8 +78,12 @@ pub struct ModuleLimits {
     pub max_irqs: usize,
     /// Timeout de inicialização em ms
     pub init_timeout_ms: u64,
+    /// Timeout de cada ping de saúde em ms
+    pub health_timeout_ms: u64,
     /// Máximo de falhas antes de ban
     pub max_faults: u32,
+    /// Frames de DMA requisitados (`DmaAccess`; 0 = sem DMA)
+    pub dma_frames: usize,
 }
 
 impl Default for ModuleLimits {
@@ -83,7 +94,9 @@ impl Default for ModuleLimits {
             max_capabilities: 64,
             max_irqs: 4,
             init_timeout_ms: 5000, // 5 segundos
+            health_timeout_ms: 1000,
             max_faults: 3,
+            dma_frames: 0,
         }
     }
 }
@@ -108,10 +121,22 @@ pub struct LoadedModule {
     pub fallback: FallbackAction,
     /// Limites de recursos
     pub limits: ModuleLimits,
+    /// Base virtual da imagem (slot do módulo)
+    pub base: u64,
+    /// Tamanho da imagem mapeada em bytes
+    pub image_size: usize,
     /// Entry point do módulo
     pub entry_point: u64,
+    /// Imports resolvidos (índice na `.dynsym`, endereço no kernel)
+    pub imports: Vec<(usize, u64)>,
     /// Função de cleanup
     pub exit_fn: Option<u64>,
+    /// Função de healthcheck (`module_health`), pingada pelo watchdog
+    pub health_fn: Option<u64>,
+    /// Hash do caminho de origem (para banimento)
+    pub path_hash: u64,
+    /// Frames de DMA concedidos e seu domínio IOMMU
+    pub dma: Option<DmaGrant>,
 }
 
 impl LoadedModule {
@@ -127,8 +152,14 @@ impl LoadedModule {
             fault_count: 0,
             fallback: FallbackAction::Disable,
             limits: ModuleLimits::default(),
+            base: 0,
+            image_size: 0,
             entry_point: 0,
+            imports: Vec::new(),
             exit_fn: None,
+            health_fn: None,
+            path_hash: 0,
+            dma: None,
         }
     }
 
@@ -157,6 +188,10 @@ pub struct ModuleSupervisor {
     default_limits: ModuleLimits,
     /// Módulos banidos (hash do nome)
     banned: Vec<u64>,
+    /// Travamentos por módulo (hash do caminho)
+    strikes: BTreeMap<u64, u32>,
+    /// Permite DMA sem IOMMU (ver `allow_dma_without_iommu`)
+    dma_without_iommu: bool,
     /// Sistema inicializado
     initialized: bool,
 }
@@ -177,9 +212,13 @@ impl ModuleSupervisor {
                 max_capabilities: 64,
                 max_irqs: 4,
                 init_timeout_ms: 5000,
+                health_timeout_ms: 1000,
                 max_faults: 3,
+                dma_frames: 0,
             },
             banned: Vec::new(),
+            strikes: BTreeMap::new(),
+            dma_without_iommu: false,
             initialized: false,
         }
     }
@@ -197,8 +236,31 @@ impl ModuleSupervisor {
         self.initialized = true;
     }
 
-    /// Carrega um módulo do caminho especificado
+    /// Permite carregar módulos com DMA mesmo sem IOMMU.
+    ///
+    /// # Safety
+    ///
+    /// Sem IOMMU o domínio do módulo não é aplicado pelo hardware: um driver
+    /// com bug pode fazer DMA sobre qualquer memória, inclusive a do kernel.
+    pub unsafe fn allow_dma_without_iommu(&mut self, allow: bool) {
+        if allow {
+            crate::kwarn!("(Module) DMA sem IOMMU habilitado (inseguro)");
+        }
+        self.dma_without_iommu = allow;
+    }
+
+    /// Carrega um módulo do caminho especificado com os limites padrão
     pub fn load_module(&mut self, path: &str) -> Result<ModuleId, ModuleError> {
+        let limits = self.default_limits.clone();
+        self.load_module_with_limits(path, limits)
+    }
+
+    /// Carrega um módulo com limites próprios (ex.: `init_timeout_ms`)
+    pub fn load_module_with_limits(
+        &mut self,
+        path: &str,
+        limits: ModuleLimits,
+    ) -> Result<ModuleId, ModuleError> {
         if !self.initialized {
             return Err(ModuleError::InternalError);
         }
@@ -206,6 +268,29 @@ impl ModuleSupervisor {
         crate::kdebug!("(Module) Carregando módulo: ");
         // Nota: não podemos concatenar strings facilmente sem alloc
 
+        // 1. Verificar se não está banido (antes de tocar no VFS)
+        if self.banned.contains(&Self::hash_path(path)) {
+            crate::kwarn!("(Module) Módulo banido!");
+            return Err(ModuleError::Banned);
+        }
+
+        // 2. Carregar ELF do VFS
+        let elf_data = self.loader.load_from_vfs(path)?;
+
+        self.load_image(path, &elf_data, limits)
+    }
+
+    /// Carrega um módulo a partir da imagem ELF em memória
+    pub fn load_image(
+        &mut self,
+        path: &str,
+        elf_data: &[u8],
+        limits: ModuleLimits,
+    ) -> Result<ModuleId, ModuleError> {
+        if !self.initialized {
+            return Err(ModuleError::InternalError);
+        }
+
         // 1. Verificar se não está banido
         let path_hash = Self::hash_path(path);
         if self.banned.contains(&path_hash) {
@@ -213,11 +298,14 @@ impl ModuleSupervisor {
             return Err(ModuleError::Banned);
         }
 
-        // 2. Carregar ELF do VFS
-        let elf_data = self.loader.load_from_vfs(path)?;
+        // 2. DMA só com IOMMU (ou override explícito)
+        if limits.dma_frames > 0 && !super::has_iommu() && !self.dma_without_iommu {
+            crate::kerror!("(Module) Módulo requer DMA e não há IOMMU!");
+            return Err(ModuleError::IommuRequired);
+        }
 
         // 3. Verificar assinatura
-        if !self.verifier.verify(&elf_data) {
+        if !self.verifier.verify(elf_data) {
             crate::kerror!("(Module) Assinatura inválida!");
             return Err(ModuleError::InvalidSignature);
         }
@@ -229,19 +317,45 @@ impl ModuleSupervisor {
         // 5. Criar estrutura do módulo
         let name = Self::extract_name(path);
         let mut module = LoadedModule::new(id, name);
-        module.limits = self.default_limits.clone();
+        module.limits = limits;
+        module.path_hash = path_hash;
 
         // 6. Parsear ELF e alocar páginas
-        self.loader.parse_and_load(&elf_data, &mut module)?;
+        self.loader.parse_and_load(elf_data, &mut module)?;
 
         // 7. Configurar sandbox
-        self.sandbox.setup_module(&module)?;
+        if let Err(e) = self.sandbox.setup_module(&module) {
+            self.loader.free_pages(&mut module);
+            return Err(e);
+        }
+
+        // 7.1 Conceder frames de DMA no domínio do módulo
+        if module.limits.dma_frames > 0 {
+            match DmaGrant::new(id.as_u64(), module.limits.dma_frames) {
+                Ok(grant) => module.dma = Some(grant),
+                Err(e) => {
+                    self.sandbox.cleanup_module(&module);
+                    self.loader.free_pages(&mut module);
+                    return Err(e);
+                }
+            }
+        }
 
         // 8. Registrar no watchdog
         self.watchdog.register(id);
 
         // 9. Chamar init do módulo (supervisionado)
-        self.call_module_init(&mut module)?;
+        match self.call_module_init(&mut module) {
+            Ok(()) => {}
+            Err(InitFailure::Error(e)) => {
+                self.discard(module, true, false);
+                return Err(e);
+            }
+            Err(InitFailure::Hung { reclaim }) => {
+                self.discard(module, reclaim, true);
+                return Err(ModuleError::InitTimeout);
+            }
+        }
 
         // 10. Marcar como ativo
         module.state = ModuleState::Active;
@@ -277,6 +391,11 @@ impl ModuleSupervisor {
         // Revogar capabilities (placeholder)
         // TODO: Implementar revogação real
 
+        // Fechar o domínio de DMA e devolver os frames
+        if let Some(grant) = module.dma.take() {
+            grant.release();
+        }
+
         // Desalocar páginas
         self.loader.free_pages(&mut module);
 
@@ -329,6 +448,88 @@ impl ModuleSupervisor {
         }
     }
 
+    /// Pinga os módulos ativos e descarrega os que travaram ou morreram.
+    ///
+    /// Um ping que estoura `health_timeout_ms` derruba o módulo na hora;
+    /// respostas de erro acumulam falhas até o watchdog declará-lo morto.
+    /// Módulos sem `module_health` não são pingados.
+    pub fn watchdog_tick(&mut self) {
+        let ids: Vec<ModuleId> = self.modules.keys().copied().collect();
+        for id in ids {
+            let (health_fn, timeout_ms) = match self.modules.get(&id) {
+                Some(m) if matches!(m.state, ModuleState::Active | ModuleState::Degraded) => {
+                    (m.health_fn, m.limits.health_timeout_ms)
+                }
+                _ => continue,
+            };
+
+            let entry = match health_fn {
+                Some(entry) => entry,
+                None => {
+                    self.watchdog.heartbeat(id);
+                    continue;
+                }
+            };
+
+            match supervised_call(entry, timeout_ms) {
+                CallOutcome::Returned(0) => self.watchdog.heartbeat(id),
+                CallOutcome::Returned(_) => self.watchdog.report_error(id),
+                CallOutcome::TimedOut { killed } => {
+                    self.watchdog.set_status(id, HealthStatus::Unresponsive);
+                    crate::kerror!("(Module) Módulo não respondeu ao ping, ID=", id.as_u64());
+                    self.evict(id, killed);
+                }
+            }
+        }
+
+        for (id, status) in self.watchdog.check_all() {
+            if status == HealthStatus::Dead {
+                crate::kerror!("(Module) Módulo declarado morto, ID=", id.as_u64());
+                self.evict(id, true);
+            }
+        }
+    }
+
+    /// Remove um módulo travado e conta o travamento contra ele
+    fn evict(&mut self, id: ModuleId, reclaim: bool) {
+        if let Some(module) = self.modules.remove(&id) {
+            self.discard(module, reclaim, true);
+        }
+    }
+
+    /// Desfaz a carga de um módulo que falhou.
+    ///
+    /// Com `reclaim`, a imagem é desmapeada e os frames devolvidos; sem ele
+    /// (thread do módulo não encerrada), a imagem fica mapeada para não
+    /// pagefaultar a thread. `hung` conta um travamento e bane ao atingir
+    /// `max_faults`.
+    fn discard(&mut self, mut module: LoadedModule, reclaim: bool, hung: bool) {
+        module.state = ModuleState::Failed;
+        self.watchdog.unregister(module.id);
+        self.sandbox.cleanup_module(&module);
+
+        if reclaim {
+            if let Some(grant) = module.dma.take() {
+                grant.release();
+            }
+            self.loader.free_pages(&mut module);
+        } else {
+            crate::kerror!(
+                "(Module) Thread do módulo não encerrada; imagem mantida, ID=",
+                module.id.as_u64()
+            );
+        }
+
+        if hung {
+            let strikes = self.strikes.entry(module.path_hash).or_insert(0);
+            *strikes += 1;
+            if *strikes >= module.limits.max_faults && !self.banned.contains(&module.path_hash) {
+                self.banned.push(module.path_hash);
+                crate::kerror!("(Module) Módulo banido por travamentos repetidos!");
+            }
+        }
+    }
+
     /// Define ação de fallback para um módulo
     pub fn set_fallback(&mut self, id: ModuleId, action: FallbackAction) {
         if let Some(module) = self.modules.get_mut(&id) {
@@ -343,16 +544,28 @@ impl ModuleSupervisor {
 
     // --- Funções internas ---
 
-    fn call_module_init(&self, module: &mut LoadedModule) -> Result<(), ModuleError> {
+    fn call_module_init(&mut self, module: &mut LoadedModule) -> Result<(), InitFailure> {
         if module.entry_point == 0 {
-            return Err(ModuleError::InvalidFormat);
+            return Err(InitFailure::Error(ModuleError::InvalidFormat));
         }
 
-        // TODO: Implementar chamada supervisionada com timeout
-        // Por agora, apenas marcamos como sucesso
         crate::ktrace!("(Module) Chamando init em ", module.entry_point);
-
-        Ok(())
+        match supervised_call(module.entry_point, module.limits.init_timeout_ms) {
+            CallOutcome::Returned(0) => {
+                self.watchdog.heartbeat(module.id);
+                Ok(())
+            }
+            CallOutcome::Returned(code) => {
+                crate::kerror!("(Module) Init retornou erro:", code);
+                Err(InitFailure::Error(ModuleError::InternalError))
+            }
+            CallOutcome::TimedOut { killed } => {
+                self.watchdog
+                    .set_status(module.id, HealthStatus::Unresponsive);
+                crate::kerror!("(Module) Init excedeu o timeout, ID=", module.id.as_u64());
+                Err(InitFailure::Hung { reclaim: killed })
+            }
+        }
     }
     #[allow(dead_code)]
     fn call_module_exit(&self, module: &LoadedModule) {
@@ -385,5 +598,85 @@ impl ModuleSupervisor {
     }
 }
 
+/// Falha de `call_module_init`
+enum InitFailure {
+    /// Init retornou erro ou o módulo é inválido
+    Error(ModuleError),
+    /// Init não terminou no prazo
+    Hung { reclaim: bool },
+}
+
+// =============================================================================
+// EXECUÇÃO SUPERVISIONADA
+// =============================================================================
+
+/// Valor de `CallSlot::result` enquanto a chamada não retornou
+const CALL_PENDING: i64 = i64::MIN;
+
+/// Chamada de uma função do módulo em uma kernel thread dedicada
+struct CallSlot {
+    entry: u64,
+    result: AtomicI64,
+}
+
+/// Chamada entregue à próxima worker (chamadas são serializadas pelo lock
+/// do `SUPERVISOR`)
+static NEXT_CALL: Spinlock<Option<Arc<CallSlot>>> = Spinlock::new(None);
+
+/// Resultado de `supervised_call`
+enum CallOutcome {
+    /// A função retornou dentro do prazo
+    Returned(i32),
+    /// Prazo estourado; `killed` indica se a worker foi encerrada
+    TimedOut { killed: bool },
+}
+
+extern "C" fn module_call_worker() -> ! {
+    let slot = NEXT_CALL.lock().take();
+    let code = match slot {
+        Some(slot) => {
+            // SAFETY: `entry` aponta para código do módulo mapeado RX pelo loader
+            let func: extern "C" fn() -> i32 = unsafe { core::mem::transmute(slot.entry as usize) };
+            let code = func();
+            slot.result.store(code as i64, Ordering::SeqCst);
+            code
+        }
+        None => -1,
+    };
+    exit_current(code);
+}
+
+/// Executa `entry` (`extern "C" fn() -> i32`) em uma kernel thread e espera
+/// até `timeout_ms`, medido em jiffies.
+///
+/// Estourado o prazo, a worker é encerrada se estiver na RunQueue (caso de
+/// um loop preemptado) ou dormindo; se estiver bloqueada, continua viva.
+fn supervised_call(entry: u64, timeout_ms: u64) -> CallOutcome {
+    let slot = Arc::new(CallSlot {
+        entry,
+        result: AtomicI64::new(CALL_PENDING),
+    });
+    *NEXT_CALL.lock() = Some(slot.clone());
+    let tid = spawn_kernel_thread("module-call", module_call_worker);
+
+    let deadline = get_jiffies() + core::cmp::max(millis_to_jiffies(timeout_ms), 1);
+    loop {
+        let result = slot.result.load(Ordering::SeqCst);
+        if result != CALL_PENDING {
+            return CallOutcome::Returned(result as i32);
+        }
+        if get_jiffies() >= deadline {
+            let killed = kill_ready(tid, -1) || kill_sleeping(tid, -1);
+            // Pode ter retornado entre a leitura e o kill
+            let result = slot.result.load(Ordering::SeqCst);
+            if !killed && result != CALL_PENDING {
+                return CallOutcome::Returned(result as i32);
+            }
+            return CallOutcome::TimedOut { killed };
+        }
+        yield_now();
+    }
+}
+
 /// Instância global do supervisor
 pub static SUPERVISOR: Mutex<ModuleSupervisor> = Mutex::new(ModuleSupervisor::new());
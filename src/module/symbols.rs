@@ -0,0 +1,355 @@
+//! # Module Symbols
+//!
+//! Análise estática dos imports de um módulo (passo 2 do fluxo de carga).
+//!
+//! O loader lê a tabela de símbolos dinâmicos (`.dynsym`) do ELF e aceita
+//! apenas imports presentes em `KERNEL_SYMBOLS`, a allowlist de helpers
+//! exportados pelo kernel. Qualquer outro símbolo indefinido faz a carga
+//! falhar com `ModuleError::CapabilityDenied`.
+//!
+//! ## Regras
+//! - Símbolos definidos pelo próprio módulo (ex.: a `ModuleAbi`) são ignorados.
+//! - Import forte fora da allowlist → rejeitado.
+//! - Import fraco (`STB_WEAK`) fora da allowlist → resolvido para 0, como um
+//!   weak indefinido em qualquer linker; nunca aponta para o kernel.
+
+use super::ModuleError;
+use alloc::vec::Vec;
+
+// =============================================================================
+// ALLOWLIST
+// =============================================================================
+
+/// Símbolo do kernel visível para módulos
+pub struct KernelSymbol {
+    /// Nome exportado
+    pub name: &'static str,
+    /// Endereço resolvido no kernel
+    pub addr: *const (),
+}
+
+/// Símbolos que um módulo pode importar
+pub const KERNEL_SYMBOLS: &[KernelSymbol] = &[
+    KernelSymbol {
+        name: "module_log",
+        addr: module_log as *const (),
+    },
+    KernelSymbol {
+        name: "module_ticks",
+        addr: module_ticks as *const (),
+    },
+    KernelSymbol {
+        name: "module_alloc",
+        addr: module_alloc as *const (),
+    },
+    KernelSymbol {
+        name: "module_free",
+        addr: module_free as *const (),
+    },
+];
+
+/// Procura `name` na allowlist
+pub fn lookup(name: &str) -> Option<&'static KernelSymbol> {
+    KERNEL_SYMBOLS.iter().find(|sym| sym.name == name)
+}
+
+/// Escreve `len` bytes de `msg` no log do kernel
+extern "C" fn module_log(msg: *const u8, len: usize) {
+    if msg.is_null() {
+        return;
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(msg, len) };
+    crate::drivers::serial::write_bytes(bytes);
+}
+
+/// Ticks do timer do sistema
+extern "C" fn module_ticks() -> u64 {
+    crate::drivers::timer::ticks()
+}
+
+/// Aloca `size` bytes alinhados a `align` no heap do kernel (null se falhar)
+extern "C" fn module_alloc(size: usize, align: usize) -> *mut u8 {
+    match core::alloc::Layout::from_size_align(size, align) {
+        Ok(layout) if size > 0 => unsafe { alloc::alloc::alloc(layout) },
+        _ => core::ptr::null_mut(),
+    }
+}
+
+/// Libera memória obtida com `module_alloc`
+extern "C" fn module_free(ptr: *mut u8, size: usize, align: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    if let Ok(layout) = core::alloc::Layout::from_size_align(size, align) {
+        unsafe { alloc::alloc::dealloc(ptr, layout) };
+    }
+}
+
+// =============================================================================
+// ELF
+// =============================================================================
+
+/// Seção com a tabela de símbolos dinâmicos
+const SHT_DYNSYM: u32 = 11;
+/// Índice de seção de símbolo indefinido
+const SHN_UNDEF: u16 = 0;
+/// Binding fraco
+const STB_WEAK: u8 = 2;
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Elf64Shdr {
+    sh_name: u32,
+    sh_type: u32,
+    sh_flags: u64,
+    sh_addr: u64,
+    sh_offset: u64,
+    sh_size: u64,
+    sh_link: u32,
+    sh_info: u32,
+    sh_addralign: u64,
+    sh_entsize: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Elf64Sym {
+    st_name: u32,
+    st_info: u8,
+    st_other: u8,
+    st_shndx: u16,
+    st_value: u64,
+    st_size: u64,
+}
+
+/// Import resolvido de um módulo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedImport<'a> {
+    /// Nome do símbolo
+    pub name: &'a str,
+    /// Índice na `.dynsym` (usado pelas relocações)
+    pub index: usize,
+    /// Endereço no kernel (0 para weak não resolvido)
+    pub addr: u64,
+}
+
+/// Falha da análise de símbolos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolError<'a> {
+    /// Tabelas ELF inconsistentes
+    Malformed,
+    /// Import forte fora da allowlist
+    Forbidden(&'a str),
+}
+
+/// Lê um `T` de `data[offset..]`, se couber
+fn read_at<T: Copy>(data: &[u8], offset: u64) -> Option<T> {
+    let offset = usize::try_from(offset).ok()?;
+    let end = offset.checked_add(core::mem::size_of::<T>())?;
+    if end > data.len() {
+        return None;
+    }
+    Some(unsafe { core::ptr::read_unaligned(data.as_ptr().add(offset) as *const T) })
+}
+
+/// String terminada em null em `strtab[offset..]`
+fn str_at<'a>(data: &'a [u8], strtab: &Elf64Shdr, offset: u32) -> Option<&'a str> {
+    let start = strtab.sh_offset.checked_add(offset as u64)?;
+    let end = strtab.sh_offset.checked_add(strtab.sh_size)?;
+    if offset as u64 >= strtab.sh_size || end > data.len() as u64 {
+        return None;
+    }
+    let bytes = &data[start as usize..end as usize];
+    let len = bytes.iter().position(|&b| b == 0)?;
+    core::str::from_utf8(&bytes[..len]).ok()
+}
+
+/// Símbolos da `.dynsym` de `elf`: (índice, entrada, nome).
+///
+/// Um ELF sem `.dynsym` retorna lista vazia.
+fn dynamic_symbols(elf: &[u8]) -> Result<Vec<(usize, Elf64Sym, &str)>, SymbolError<'_>> {
+    // e_shoff, e_shentsize, e_shnum
+    let shoff: u64 = read_at(elf, 0x28).ok_or(SymbolError::Malformed)?;
+    let shentsize: u16 = read_at(elf, 0x3A).ok_or(SymbolError::Malformed)?;
+    let shnum: u16 = read_at(elf, 0x3C).ok_or(SymbolError::Malformed)?;
+
+    if shnum == 0 {
+        return Ok(Vec::new());
+    }
+    if (shentsize as usize) < core::mem::size_of::<Elf64Shdr>() {
+        return Err(SymbolError::Malformed);
+    }
+
+    let section = |index: u64| -> Option<Elf64Shdr> {
+        if index >= shnum as u64 {
+            return None;
+        }
+        read_at(elf, shoff.checked_add(index * shentsize as u64)?)
+    };
+
+    let mut symbols = Vec::new();
+    for i in 0..shnum as u64 {
+        let symtab = section(i).ok_or(SymbolError::Malformed)?;
+        if symtab.sh_type != SHT_DYNSYM {
+            continue;
+        }
+        let strtab = section(symtab.sh_link as u64).ok_or(SymbolError::Malformed)?;
+
+        let entsize = core::mem::size_of::<Elf64Sym>() as u64;
+        // Entrada 0 é sempre o símbolo nulo
+        for index in 1..symtab.sh_size / entsize {
+            let sym: Elf64Sym = symtab
+                .sh_offset
+                .checked_add(index * entsize)
+                .and_then(|offset| read_at(elf, offset))
+                .ok_or(SymbolError::Malformed)?;
+            let name = str_at(elf, &strtab, sym.st_name).ok_or(SymbolError::Malformed)?;
+            symbols.push((index as usize, sym, name));
+        }
+    }
+
+    Ok(symbols)
+}
+
+/// Verifica os imports de `elf` contra a allowlist.
+///
+/// Um ELF sem `.dynsym` não importa nada e passa.
+pub fn check_imports(elf: &[u8]) -> Result<Vec<ResolvedImport<'_>>, SymbolError<'_>> {
+    let mut imports = Vec::new();
+    for (index, sym, name) in dynamic_symbols(elf)? {
+        if sym.st_shndx != SHN_UNDEF || name.is_empty() {
+            continue;
+        }
+
+        let addr = match lookup(name) {
+            Some(kernel) => kernel.addr as u64,
+            None if sym.st_info >> 4 == STB_WEAK => 0,
+            None => return Err(SymbolError::Forbidden(name)),
+        };
+        imports.push(ResolvedImport { name, index, addr });
+    }
+
+    Ok(imports)
+}
+
+/// Valor (endereço antes do bias) de um símbolo definido pelo módulo
+pub fn find_export(elf: &[u8], name: &str) -> Option<u64> {
+    dynamic_symbols(elf)
+        .ok()?
+        .into_iter()
+        .find(|(_, sym, sym_name)| sym.st_shndx != SHN_UNDEF && *sym_name == name)
+        .map(|(_, sym, _)| sym.st_value)
+}
+
+/// Como `check_imports`, registrando o símbolo recusado no log
+pub fn resolve_imports(elf: &[u8]) -> Result<Vec<ResolvedImport<'_>>, ModuleError> {
+    check_imports(elf).map_err(|err| match err {
+        SymbolError::Malformed => {
+            crate::kerror!("(Module) Tabela de símbolos inválida");
+            ModuleError::InvalidFormat
+        }
+        SymbolError::Forbidden(name) => {
+            crate::kwarn!("(Module) Import fora da allowlist: ", name);
+            ModuleError::CapabilityDenied
+        }
+    })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Monta um ELF64 mínimo com `.dynsym`/`.dynstr` importando `imports`
+    /// (nome, weak) e definindo `module_init`.
+    fn build_module(imports: &[(&str, bool)]) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut syms = vec![0u8; 24];
+        let mut push_sym = |strtab: &mut Vec<u8>, name: &str, info: u8, shndx: u16| {
+            let st_name = strtab.len() as u32;
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+            syms.extend_from_slice(&st_name.to_le_bytes());
+            syms.push(info);
+            syms.push(0);
+            syms.extend_from_slice(&shndx.to_le_bytes());
+            syms.extend_from_slice(&[0u8; 16]);
+        };
+        for &(name, weak) in imports {
+            let bind = if weak { STB_WEAK } else { 1 };
+            push_sym(&mut strtab, name, bind << 4, SHN_UNDEF);
+        }
+        // Símbolo definido: não passa pela allowlist
+        push_sym(&mut strtab, "module_init", (1 << 4) | 2, 1);
+
+        let syms_off = 64u64;
+        let str_off = syms_off + syms.len() as u64;
+        let sh_off = (str_off + strtab.len() as u64 + 7) & !7;
+
+        let mut elf = vec![0u8; sh_off as usize];
+        elf[..4].copy_from_slice(b"\x7FELF");
+        elf[4] = 2;
+        elf[0x28..0x30].copy_from_slice(&sh_off.to_le_bytes());
+        elf[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        elf[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+        elf[syms_off as usize..str_off as usize].copy_from_slice(&syms);
+        elf[str_off as usize..str_off as usize + strtab.len()].copy_from_slice(&strtab);
+
+        let mut shdr = |sh_type: u32, offset: u64, size: u64, link: u32| {
+            let mut raw = [0u8; 64];
+            raw[4..8].copy_from_slice(&sh_type.to_le_bytes());
+            raw[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+            raw[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+            raw[0x28..0x2C].copy_from_slice(&link.to_le_bytes());
+            elf.extend_from_slice(&raw);
+        };
+        shdr(0, 0, 0, 0);
+        shdr(SHT_DYNSYM, syms_off, syms.len() as u64, 2);
+        shdr(3, str_off, strtab.len() as u64, 0);
+        elf
+    }
+
+    #[test]
+    fn test_forbidden_import_rejected() {
+        let elf = build_module(&[("module_log", false), ("kernel_page_tables", false)]);
+        assert_eq!(
+            check_imports(&elf),
+            Err(SymbolError::Forbidden("kernel_page_tables"))
+        );
+    }
+
+    #[test]
+    fn test_allowed_and_weak_imports() {
+        let elf = build_module(&[("module_log", false), ("optional_hook", true)]);
+        let imports = check_imports(&elf).unwrap();
+
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[0].name, "module_log");
+        assert_eq!(imports[0].addr, lookup("module_log").unwrap().addr as u64);
+        assert_eq!(imports[1].name, "optional_hook");
+        assert_eq!(imports[1].addr, 0);
+    }
+
+    #[test]
+    fn test_find_export() {
+        let elf = build_module(&[("module_log", false)]);
+        assert_eq!(find_export(&elf, "module_init"), Some(0));
+        assert_eq!(find_export(&elf, "module_log"), None);
+        assert_eq!(find_export(&elf, "module_health"), None);
+    }
+
+    #[test]
+    fn test_truncated_symbol_table() {
+        let mut elf = build_module(&[("module_log", false)]);
+        // Aponta a tabela de seções para fora do arquivo
+        let len = elf.len() as u64;
+        elf[0x28..0x30].copy_from_slice(&len.to_le_bytes());
+        assert_eq!(check_imports(&elf), Err(SymbolError::Malformed));
+    }
+}
@@ -0,0 +1,231 @@
+//! # Testes do sistema de módulos
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot.
+
+use super::{LoadedModule, ModuleError, ModuleId, ModuleLimits, ModuleLoader, SUPERVISOR};
+use crate::mm::config::{MODULE_SLOTS, PAGE_SIZE};
+use crate::mm::pfm::frame::FrameState;
+use crate::mm::vmm::mapper::{read_cr3, read_pte_in_p4};
+use crate::mm::PhysAddr;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Bits de PTE verificados
+const PTE_WRITABLE: u64 = 1 << 1;
+const PTE_NO_EXEC: u64 = 1 << 63;
+
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+/// `xor eax, eax; ret` (init com sucesso)
+const RET: [u8; 3] = [0x31, 0xC0, 0xC3];
+/// `jmp $` (init que nunca retorna)
+const SPIN: [u8; 2] = [0xEB, 0xFE];
+
+pub fn run_tests() {
+    crate::kinfo!("(Module) Iniciando testes de módulos...");
+    test_wx_final_mappings();
+    test_wx_segment_rejected();
+    test_init_timeout_unloads_and_bans();
+    test_dma_requires_iommu();
+    test_dma_confined_to_domain();
+    crate::kinfo!("(Module) Testes de módulos concluídos com SUCESSO.");
+}
+
+/// ELF64 ET_DYN mínimo: `.text` (R+`text_flags`, começando com `code`) em
+/// 0x0, entry point no início, e `.data` (RW) em 0x1000
+fn build_module(text_flags: u32, code: &[u8]) -> Vec<u8> {
+    let mut elf = vec![0u8; 0x400];
+    elf[..4].copy_from_slice(b"\x7FELF");
+    elf[4] = 2; // ELFCLASS64
+    elf[5] = 1; // little endian
+    elf[6] = 1;
+    elf[0x10..0x12].copy_from_slice(&3u16.to_le_bytes()); // ET_DYN
+    elf[0x12..0x14].copy_from_slice(&62u16.to_le_bytes()); // x86_64
+    elf[0x20..0x28].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+    elf[0x36..0x38].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+    elf[0x38..0x3A].copy_from_slice(&2u16.to_le_bytes()); // e_phnum
+
+    let mut phdr = |index: usize, flags: u32, offset: u64, vaddr: u64, size: u64| {
+        let base = 64 + index * 56;
+        elf[base..base + 4].copy_from_slice(&1u32.to_le_bytes()); // PT_LOAD
+        elf[base + 4..base + 8].copy_from_slice(&flags.to_le_bytes());
+        elf[base + 8..base + 16].copy_from_slice(&offset.to_le_bytes());
+        elf[base + 16..base + 24].copy_from_slice(&vaddr.to_le_bytes());
+        elf[base + 32..base + 40].copy_from_slice(&size.to_le_bytes());
+        elf[base + 40..base + 48].copy_from_slice(&size.to_le_bytes());
+    };
+    phdr(0, PF_R | text_flags, 0x200, 0x0, 16);
+    phdr(1, PF_R | PF_W, 0x300, 0x1000, 16);
+
+    elf[0x200..0x200 + code.len()].copy_from_slice(code);
+    elf[0x300] = 0x5A;
+    elf
+}
+
+fn test_module() -> LoadedModule {
+    LoadedModule::new(
+        ModuleId::new(MODULE_SLOTS as u64 - 1),
+        String::from("wx-test"),
+    )
+}
+
+/// Após a carga, `.text` é RX (sem WRITABLE) e `.data` é RW/NX.
+fn test_wx_final_mappings() {
+    let loader = ModuleLoader::new();
+    let mut module = test_module();
+    loader
+        .parse_and_load(&build_module(PF_X, &RET), &mut module)
+        .expect("(Module) Falha ao carregar módulo de teste");
+
+    let cr3 = read_cr3();
+    let text = read_pte_in_p4(cr3, module.base).expect("(Module) .text não mapeado");
+    assert_eq!(text & PTE_WRITABLE, 0, "(Module) .text continua escrevível");
+    assert_eq!(text & PTE_NO_EXEC, 0, "(Module) .text não é executável");
+
+    let data = read_pte_in_p4(cr3, module.base + 0x1000).expect("(Module) .data não mapeado");
+    assert_ne!(data & PTE_WRITABLE, 0, "(Module) .data não é escrevível");
+    assert_ne!(data & PTE_NO_EXEC, 0, "(Module) .data é executável");
+
+    // Conteúdo copiado durante a janela temporária
+    unsafe {
+        assert_eq!(core::ptr::read_volatile(module.base as *const u8), RET[0]);
+        assert_eq!(
+            core::ptr::read_volatile((module.base + 0x1000) as *const u8),
+            0x5A
+        );
+    }
+    assert_eq!(module.code_pages.len(), 1);
+    assert_eq!(module.data_pages.len(), 1);
+
+    let base = module.base;
+    loader.free_pages(&mut module);
+    assert!(read_pte_in_p4(cr3, base).is_none());
+}
+
+/// Segmento pedindo escrita e execução é recusado sem deixar páginas.
+fn test_wx_segment_rejected() {
+    let loader = ModuleLoader::new();
+    let mut module = test_module();
+
+    assert_eq!(
+        loader.parse_and_load(&build_module(PF_W | PF_X, &RET), &mut module),
+        Err(ModuleError::InvalidFormat)
+    );
+    assert!(module.code_pages.is_empty());
+    assert!(module.data_pages.is_empty());
+}
+
+/// Init que gira além do prazo: `InitTimeout`, nada fica carregado, e a
+/// reincidência bane o módulo.
+fn test_init_timeout_unloads_and_bans() {
+    let mut supervisor = SUPERVISOR.lock();
+    supervisor.init();
+    let loaded = supervisor.list_modules().len();
+
+    let limits = ModuleLimits {
+        init_timeout_ms: 50,
+        max_faults: 2,
+        ..ModuleLimits::default()
+    };
+    let elf = build_module(PF_X, &SPIN);
+
+    for _ in 0..2 {
+        assert_eq!(
+            supervisor.load_image("/test/hang.ko", &elf, limits.clone()),
+            Err(ModuleError::InitTimeout),
+            "(Module) Init travado não estourou o timeout"
+        );
+        assert_eq!(supervisor.list_modules().len(), loaded);
+    }
+
+    assert_eq!(
+        supervisor.load_image("/test/hang.ko", &elf, limits),
+        Err(ModuleError::Banned),
+        "(Module) Reincidente não foi banido"
+    );
+}
+
+/// Módulo pedindo DMA sem IOMMU e sem override é recusado.
+fn test_dma_requires_iommu() {
+    if super::has_iommu() {
+        return;
+    }
+    let mut supervisor = SUPERVISOR.lock();
+    supervisor.init();
+
+    let limits = ModuleLimits {
+        dma_frames: 1,
+        ..ModuleLimits::default()
+    };
+    assert_eq!(
+        supervisor.load_image("/test/dma.ko", &build_module(PF_X, &RET), limits),
+        Err(ModuleError::IommuRequired),
+        "(Module) DMA aceito sem IOMMU"
+    );
+}
+
+/// Com um frame concedido, só esse frame tem tradução no domínio do módulo.
+fn test_dma_confined_to_domain() {
+    let mut supervisor = SUPERVISOR.lock();
+    supervisor.init();
+    // SAFETY: o init do módulo de teste não programa nenhum dispositivo
+    unsafe { supervisor.allow_dma_without_iommu(true) };
+
+    let limits = ModuleLimits {
+        dma_frames: 1,
+        ..ModuleLimits::default()
+    };
+    let id = supervisor
+        .load_image("/test/dma.ko", &build_module(PF_X, &RET), limits)
+        .expect("(Module) Falha ao carregar módulo com DMA");
+
+    let frame = {
+        let grant = supervisor.get_module(id).unwrap().dma.as_ref().unwrap();
+        let frames: Vec<_> = grant.frames().collect();
+        assert_eq!(frames.len(), 1);
+        let frame = frames[0];
+        let domain = grant.domain();
+
+        assert_eq!(domain.translate(frame.as_u64()), Some(frame));
+        assert_eq!(
+            domain.translate(frame.as_u64() + 0x10),
+            Some(PhysAddr::new(frame.as_u64() + 0x10))
+        );
+        // Vizinhos, memória baixa e o próprio root do domínio: bloqueados
+        let outside = [
+            frame.as_u64() + PAGE_SIZE as u64,
+            frame.as_u64().wrapping_sub(PAGE_SIZE as u64),
+            0x10_0000,
+            domain.root().as_u64(),
+        ];
+        for iova in outside {
+            assert!(
+                domain.translate(iova).is_none(),
+                "(Module) DMA fora do domínio tem tradução"
+            );
+        }
+        frame
+    };
+
+    let pfm = crate::mm::pfm::get();
+    if let Ok(state) = pfm.lock().get_state(frame) {
+        assert_eq!(
+            state,
+            FrameState::Device,
+            "(Module) Frame de DMA não é Device"
+        );
+    }
+
+    supervisor.unload_module(id).unwrap();
+    unsafe { supervisor.allow_dma_without_iommu(false) };
+    if let Ok(state) = pfm.lock().get_state(frame) {
+        assert_ne!(
+            state,
+            FrameState::Device,
+            "(Module) Frame de DMA não liberado"
+        );
+    }
+}
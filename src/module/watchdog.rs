@@ -107,6 +107,13 @@ impl ModuleWatchdog {
         }
     }
 
+    /// Força o status de um módulo (ex.: `Unresponsive` após timeout)
+    pub fn set_status(&mut self, id: ModuleId, status: HealthStatus) {
+        if let Some(module) = self.watched.get_mut(&id) {
+            module.status = status;
+        }
+    }
+
     /// Verifica todos os módulos (chamado periodicamente pelo timer)
     pub fn check_all(&mut self) -> alloc::vec::Vec<(ModuleId, HealthStatus)> {
         if !self.active {
@@ -12,8 +12,19 @@ pub const PRIORITY_MAX: u8 = 255;
 /// Tamanho padrão da Stack de Kernel (em bytes)
 pub const KERNEL_STACK_SIZE: usize = 65536; // 64KB
 
+/// Timeslice padrão em milissegundos (independe de `HZ`)
+pub const DEFAULT_TIMESLICE_MS: u64 = 100;
+
 /// Quantum padrão (Timeslice) em ticks do timer
-pub const DEFAULT_QUANTUM: u64 = 10;
+pub const DEFAULT_QUANTUM: u64 =
+    crate::core::time::jiffies::millis_to_jiffies(DEFAULT_TIMESLICE_MS);
+
+/// Tamanho inicial da Stack de Usuário (em bytes) - 128KB
+///
+/// É o que o spawn mapeia; a VMA é `GROWS_DOWN` e cresce sob demanda até
+/// `mm::config::STACK_MAX_SIZE`.
+pub const USER_STACK_SIZE: usize = 128 * 1024;
 
-/// Tamanho padrão da Stack de Usuário (em bytes) - 2MB
-pub const USER_STACK_SIZE: usize = 2 * 1024 * 1024;
+/// Tamanho máximo da imagem inicial da stack de usuário (argv, envp, auxv e
+/// as strings) - 64KB. Tem que caber em `USER_STACK_SIZE`.
+pub const EXEC_ARGS_MAX: usize = 64 * 1024;
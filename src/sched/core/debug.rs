@@ -1,10 +1,36 @@
 //! Ferramentas de Debug para o Scheduler
 
+use super::idle::is_idle_task;
 use super::runqueue::RUNQUEUE;
 use super::scheduler::CURRENT;
 use super::sleep_queue::SLEEP_QUEUE;
 use crate::sched::task::lifecycle::ZOMBIES;
 use crate::sched::task::TaskState;
+use alloc::vec::Vec;
+
+/// TIDs de todas as tasks visíveis (execução, prontas, dormindo e zumbis).
+///
+/// A idle task (TID 0) é omitida: ela está sempre "rodando" quando não há
+/// trabalho e apareceria como um processo descontrolado em listagens.
+pub fn task_ids() -> Vec<u32> {
+    let mut ids = Vec::new();
+
+    if let Some(ref task) = *CURRENT.lock() {
+        ids.push(task.tid.as_u32());
+    }
+    for task in RUNQUEUE.lock().iter() {
+        ids.push(task.tid.as_u32());
+    }
+    for task in SLEEP_QUEUE.lock().values() {
+        ids.push(task.tid.as_u32());
+    }
+    for task in ZOMBIES.lock().iter() {
+        ids.push(task.tid.as_u32());
+    }
+
+    ids.retain(|&tid| tid != 0);
+    ids
+}
 
 /// Imprime o estado de todas as tarefas conhecidas no sistema
 pub fn dump_tasks() {
@@ -16,8 +42,12 @@ pub fn dump_tasks() {
     if let Some(guard) = CURRENT.try_lock() {
         if let Some(ref task) = *guard {
             let tid = task.tid.as_u32();
-            crate::ktrace!("  - Running TID:", tid as u64);
-            total_tasks += 1;
+            if is_idle_task(task) {
+                crate::ktrace!("  - CURRENT: idle");
+            } else {
+                crate::ktrace!("  - Running TID:", tid as u64);
+                total_tasks += 1;
+            }
 
             // Alerta se task em CURRENT não está Running (exceto idle)
             if task.state != TaskState::Running && tid != 0 {
@@ -35,10 +65,8 @@ pub fn dump_tasks() {
 
     // 2. Ready Tasks
     if let Some(rq) = RUNQUEUE.try_lock() {
-        crate::ktrace!("  - READY count:", rq.queue.len() as u64);
-        for _task in &rq.queue {
-            total_tasks += 1;
-        }
+        crate::ktrace!("  - READY count:", rq.len() as u64);
+        total_tasks += rq.len() as u64;
     } else {
         crate::ktrace!("  - RUNQUEUE: [Locked]");
     }
@@ -46,7 +74,7 @@ pub fn dump_tasks() {
     // 3. Sleeping Tasks
     if let Some(sq) = SLEEP_QUEUE.try_lock() {
         crate::ktrace!("  - SLEEPING count:", sq.len() as u64);
-        for _ in sq.iter() {
+        for _ in sq.values() {
             total_tasks += 1;
         }
     } else {
@@ -64,7 +92,7 @@ pub fn dump_tasks() {
     }
 
     // Alerta se perdemos tasks (esperamos 5: idle + 4 processos)
-    // Nota: idle não conta pois está em CURRENT e não é contada separadamente
+    // Nota: idle não é contada
     if total_tasks < 4 && total_tasks > 0 {
         crate::kerror!("(Debug) ALERTA: Tasks desaparecendo! Total:", total_tasks);
     }
@@ -1,7 +1,7 @@
 //!Entry points para novas tasks
 //! Trampolim para novas tarefas de usuário
 //!
-//! Chamado via `jump_to_context` (RIP aponta para cá configurado no loader).
+//! Chamado via `task_bootstrap` na primeira troca (RIP aponta para cá configurado no loader).
 //! Responsável por:
 //! 1. Liberar o lock do scheduler (que foi herdado da task anterior).
 //! 2. Habilitar interrupções (via iretq).
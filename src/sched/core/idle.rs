@@ -1,8 +1,9 @@
 //! Idle Task - Tarefa ociosa dedicada como fallback permanente
 //!
 //! A idle task é mantida em uma variável estática separada (IDLE_TASK) e
-//! NUNCA é removida. Quando não há tasks prontas, o sistema sempre volta
-//! para a idle task de forma segura.
+//! NUNCA vai para a RunQueue: ela só sai de IDLE_TASK enquanto ocupa `CURRENT`.
+//! Quando não há tasks prontas, o sistema sempre volta para a idle task de
+//! forma segura.
 
 use crate::arch::Cpu;
 use crate::mm::VirtAddr;
@@ -17,21 +18,25 @@ use core::sync::atomic::{AtomicBool, Ordering};
 /// Flag indicando se a idle task foi inicializada
 static IDLE_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
-/// A IDLE TASK permanente - NUNCA é removida daqui
-/// Esta é a diferença crucial: a idle task tem sua própria "casa" permanente
+/// A IDLE TASK permanente
+/// Esta é a diferença crucial: a idle task tem sua própria "casa" permanente.
+/// Ela só sai daqui enquanto ocupa `CURRENT` (ver `take`/`park`).
 pub static IDLE_TASK: Spinlock<Option<Pin<Box<Task>>>> = Spinlock::new(None);
 
-/// Retorna um ponteiro mutável para o contexto da idle task
+/// Retira a idle task de `IDLE_TASK` para instalá-la em `CURRENT`.
 ///
-/// # Safety
-/// O chamador deve garantir que a idle task está inicializada
-pub unsafe fn get_idle_context() -> *mut CpuContext {
+/// Retorna `None` se ela não foi inicializada ou já está em execução.
+pub fn take() -> Option<Pin<Box<Task>>> {
+    IDLE_TASK.lock().take()
+}
+
+/// Devolve a idle task para `IDLE_TASK` quando ela perde a CPU.
+pub fn park(task: Pin<Box<Task>>) {
     let mut guard = IDLE_TASK.lock();
-    if let Some(ref mut task) = *guard {
-        &mut Pin::get_unchecked_mut(task.as_mut()).context as *mut CpuContext
-    } else {
-        panic!("(Idle) get_idle_context chamado sem idle task inicializada!");
+    if guard.is_some() {
+        crate::kerror!("(Idle) park: IDLE_TASK já ocupado!");
     }
+    *guard = Some(task);
 }
 
 /// Entry point da idle task - loop infinito de espera
@@ -42,7 +47,11 @@ pub extern "C" fn idle_task_entry() -> ! {
     let mut idle_count: u64 = 0;
 
     loop {
-        // Habilita interrupções e espera próximo evento
+        // Cede a CPU se há tasks prontas; retorna aqui quando a RunQueue esvazia
+        super::scheduler::schedule();
+
+        // Sem trabalho: libera zumbis e espera a próxima interrupção
+        crate::sched::task::lifecycle::cleanup_all();
         Cpu::enable_interrupts();
         Cpu::halt();
         Cpu::disable_interrupts();
@@ -53,10 +62,17 @@ pub extern "C" fn idle_task_entry() -> ! {
         if idle_count % 1000 == 0 {
             crate::kdebug!("(Idle) Ciclos:", idle_count);
         }
+    }
+}
 
-        // Verifica se há tasks prontas e chama schedule
-        super::scheduler::schedule();
-        // Sempre retorna aqui quando não há mais tasks
+/// Loop ocioso dos APs, depois do bringup (`core::smp::bringup`).
+///
+/// Os APs ainda não puxam tasks: `CURRENT` e a idle task são únicos e
+/// pertencem ao BSP. Aqui eles só atendem interrupções e voltam a dormir.
+pub fn ap_idle_loop() -> ! {
+    loop {
+        Cpu::enable_interrupts();
+        Cpu::halt();
     }
 }
 
@@ -101,6 +117,7 @@ pub fn init_idle_task() {
         exit_code: None,
         pending_signals: 0,
         blocked_signals: 0,
+        signal_handlers: crate::sched::signal::handler::SignalHandlers::new(),
         name: name_buf,
         handle_table: crate::syscall::handle::table::HandleTable::new(),
         wake_at: None,
@@ -111,9 +128,10 @@ pub fn init_idle_task() {
     // Configura o contexto para iniciar em idle_task_entry
     unsafe {
         let task_mut = Pin::get_unchecked_mut(idle_task.as_mut());
+        // Slot de retorno + alinhamento: a entrada ocorre com RSP = topo - 8
         task_mut.context.setup(
             VirtAddr::new(idle_task_entry as *const () as u64),
-            VirtAddr::new(stack_top),
+            VirtAddr::new(stack_top - 16),
         );
     }
 
@@ -136,29 +154,6 @@ pub fn is_idle_task(task: &Task) -> bool {
     task.tid.as_u32() == 0
 }
 
-/// Faz o switch para a idle task quando não há mais tasks
-///
-/// # Safety
-/// - Interrupções devem estar desabilitadas
-/// - old_ctx deve ser um ponteiro válido para salvar o contexto atual
-pub unsafe fn switch_to_idle(old_ctx: *mut CpuContext) {
-    let idle_guard = IDLE_TASK.lock();
-    if let Some(ref idle_task) = *idle_guard {
-        let idle_ctx = &idle_task.context as *const CpuContext;
-
-        crate::ktrace!("(Idle) Retornando para idle task");
-
-        // Switch: salva contexto atual em old_ctx, restaura contexto da idle
-        drop(idle_guard); // Libera o lock ANTES do switch
-        crate::sched::task::context::switch(&mut *old_ctx, &*idle_ctx);
-
-        // Retorna aqui quando a task for re-escalonada
-        crate::ktrace!("(Idle) Task retomada do idle");
-    } else {
-        panic!("(Idle) switch_to_idle: idle task não inicializada!");
-    }
-}
-
 /// Obtém o ponteiro para o contexto da idle task (sem lock - para leitura rápida)
 ///
 /// # Safety
@@ -0,0 +1,217 @@
+//! # Herança de Prioridade
+//!
+//! Quando uma task de prioridade alta espera um `sync::Mutex` segurado por
+//! uma de prioridade baixa, o dono "herda" a prioridade de quem espera até
+//! liberar o lock. Sem isso, tasks de prioridade média mantêm o dono fora da
+//! CPU e a de alta fica esperando por tempo indeterminado (inversão de
+//! prioridade).
+//!
+//! ## Modelo
+//! - `Task::priority` continua sendo a prioridade base e nunca é alterada.
+//! - Cada empréstimo é um registro (dono, lock, prioridade) em uma tabela
+//!   fixa, sem heap (o caminho de espera do mutex não pode alocar).
+//! - A prioridade efetiva é a menor entre a base e os empréstimos do dono; é
+//!   ela que decide o nível na RunQueue (`scheduler::enqueue` e preempção).
+//! - Um dono que já está na RunQueue é reposicionado na hora do empréstimo.
+//! - Ao liberar um lock, só os empréstimos daquele lock somem: quem segura
+//!   vários mutexes continua com o maior empréstimo dos restantes.
+//!
+//! A herança não é transitiva: se o dono estiver esperando outro mutex, o
+//! dono desse outro não é promovido.
+//!
+//! ## Locks
+//! `RUNQUEUE` → `BOOSTS`. Nenhuma função daqui toma `CURRENT` com `BOOSTS`
+//! adquirido.
+
+use super::runqueue::RUNQUEUE;
+use super::scheduler::CURRENT;
+use crate::sched::task::Task;
+use crate::sync::Spinlock;
+use crate::sys::types::Tid;
+
+/// Empréstimos simultâneos (dono, lock) suportados
+const MAX_BOOSTS: usize = 64;
+
+static BOOSTS: Spinlock<BoostTable> = Spinlock::new(BoostTable::new());
+
+/// Prioridade emprestada a `owner` por quem espera o lock `lock`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Boost {
+    owner: u32,
+    lock: usize,
+    priority: u8,
+}
+
+/// Tabela de empréstimos ativos
+struct BoostTable {
+    slots: [Option<Boost>; MAX_BOOSTS],
+}
+
+impl BoostTable {
+    const fn new() -> Self {
+        Self {
+            slots: [None; MAX_BOOSTS],
+        }
+    }
+
+    /// Empresta `priority` a `owner` por causa de `lock`.
+    ///
+    /// Um registro por (dono, lock), com a maior prioridade emprestada.
+    /// Retorna se a prioridade efetiva do dono pode ter mudado; `false` se
+    /// não havia ganho ou a tabela está cheia.
+    fn raise(&mut self, owner: u32, lock: usize, priority: u8) -> bool {
+        if let Some(boost) = self
+            .slots
+            .iter_mut()
+            .flatten()
+            .find(|b| b.owner == owner && b.lock == lock)
+        {
+            if priority >= boost.priority {
+                return false;
+            }
+            boost.priority = priority;
+            return true;
+        }
+
+        match self.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(Boost {
+                    owner,
+                    lock,
+                    priority,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove os empréstimos de `lock` a `owner`
+    fn release(&mut self, owner: u32, lock: usize) {
+        for slot in &mut self.slots {
+            if matches!(slot, Some(b) if b.owner == owner && b.lock == lock) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Prioridade efetiva de `owner` com prioridade base `base`
+    fn effective(&self, owner: u32, base: u8) -> u8 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|b| b.owner == owner)
+            .map(|b| b.priority)
+            .fold(base, core::cmp::min)
+    }
+}
+
+/// Prioridade com que `task` deve entrar na RunQueue
+pub fn effective_priority(task: &Task) -> u8 {
+    BOOSTS.lock().effective(task.tid.as_u32(), task.priority)
+}
+
+/// TID e prioridade efetiva da task atual.
+///
+/// `None` no boot ou se `CURRENT` já estiver com o chamador (o mutex pode ser
+/// usado com ele adquirido); nesse caso não há herança.
+pub fn current() -> Option<(Tid, u8)> {
+    let (tid, base) = {
+        let current = CURRENT.try_lock()?;
+        let task = current.as_ref()?;
+        (task.tid, task.priority)
+    };
+    Some((tid, BOOSTS.lock().effective(tid.as_u32(), base)))
+}
+
+/// Empresta `priority` ao dono `owner` do lock `lock`.
+///
+/// `still_owner` é avaliada com a tabela travada, serializada com
+/// [`release`]: se o lock já mudou de dono, nada é registrado (um empréstimo
+/// que ninguém removeria).
+pub fn inherit(owner: Tid, lock: usize, priority: u8, still_owner: impl FnOnce() -> bool) {
+    {
+        let mut table = BOOSTS.lock();
+        if !still_owner() || !table.raise(owner.as_u32(), lock, priority) {
+            return;
+        }
+    }
+    requeue(owner);
+}
+
+/// Desfaz os empréstimos de `lock` a `owner` (chamado ao liberar o lock).
+///
+/// A prioridade volta a valer na próxima entrada do dono na RunQueue.
+pub fn release(owner: Tid, lock: usize) {
+    BOOSTS.lock().release(owner.as_u32(), lock);
+}
+
+/// Reposiciona `tid` na RunQueue conforme a prioridade efetiva, se estiver lá
+fn requeue(tid: Tid) {
+    let mut rq = RUNQUEUE.lock();
+    if let Some(task) = rq.remove(tid) {
+        let priority = effective_priority(&task);
+        rq.enqueue_with_priority(task, priority);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW: u8 = 200;
+    const OWNER: u32 = 7;
+    const LOCK_A: usize = 0x1000;
+    const LOCK_B: usize = 0x2000;
+
+    #[test]
+    fn test_boost_and_restore() {
+        let mut table = BoostTable::new();
+        assert_eq!(table.effective(OWNER, LOW), LOW);
+
+        assert!(table.raise(OWNER, LOCK_A, 100));
+        assert_eq!(table.effective(OWNER, LOW), 100);
+        // Esperador de prioridade menor não muda nada
+        assert!(!table.raise(OWNER, LOCK_A, 150));
+        assert!(table.raise(OWNER, LOCK_A, 10));
+        assert_eq!(table.effective(OWNER, LOW), 10);
+
+        // Outras tasks não são afetadas
+        assert_eq!(table.effective(OWNER + 1, LOW), LOW);
+
+        table.release(OWNER, LOCK_A);
+        assert_eq!(table.effective(OWNER, LOW), LOW);
+    }
+
+    #[test]
+    fn test_nested_locks_restore_remaining_boost() {
+        let mut table = BoostTable::new();
+        table.raise(OWNER, LOCK_A, 50);
+        table.raise(OWNER, LOCK_B, 20);
+        assert_eq!(table.effective(OWNER, LOW), 20);
+
+        // Soltar B volta para o empréstimo de A, não para a base
+        table.release(OWNER, LOCK_B);
+        assert_eq!(table.effective(OWNER, LOW), 50);
+        table.release(OWNER, LOCK_A);
+        assert_eq!(table.effective(OWNER, LOW), LOW);
+
+        // Base já maior que o empréstimo
+        table.raise(OWNER, LOCK_A, 50);
+        assert_eq!(table.effective(OWNER, 5), 5);
+    }
+
+    #[test]
+    fn test_full_table_skips_boost() {
+        let mut table = BoostTable::new();
+        for lock in 0..MAX_BOOSTS {
+            assert!(table.raise(OWNER, lock, 10));
+        }
+        assert!(!table.raise(OWNER + 1, 0, 10));
+        assert_eq!(table.effective(OWNER + 1, LOW), LOW);
+    }
+}
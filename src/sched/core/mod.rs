@@ -22,9 +22,15 @@ pub mod entry;
 /// Lógica de espera e baixo consumo de energia quando não há tarefas prontas.
 pub mod idle;
 
+/// Herança de prioridade para donos de `sync::Mutex` com esperadores.
+pub mod inherit;
+
 /// Definições de políticas de escalonamento (Round Robin, Prioridade, etc).
 pub mod policy;
 
+/// Consultas somente-leitura (snapshots de tasks) para procfs e diagnóstico.
+pub mod query;
+
 /// Implementação da fila de tarefas prontas para execução (Ready).
 pub mod runqueue;
 
@@ -38,11 +44,14 @@ pub mod sleep_queue;
 pub mod switch;
 
 // Re-exportações de tipos e funções essenciais para simplificar o uso pelo resto do kernel.
-pub use debug::dump_tasks;
+pub use debug::{dump_tasks, task_ids};
 pub use idle::{init_idle_task, is_initialized as is_idle_initialized, IDLE_TASK};
 pub use policy::SchedulingPolicy;
+pub use query::{for_each_memory, task_count, task_info, tasks, TaskInfo, TaskMemory};
 pub use scheduler::{
-    current, enqueue, exit_current, init, pick_next, release_scheduler_lock, run, schedule,
-    sleep_current, yield_now, CURRENT,
+    block_current, current, enqueue, enqueue_with_priority, exit_current, init, kill_ready,
+    pick_next, release_scheduler_lock, run, schedule, sleep_current, sleep_until,
+    spawn_kernel_thread, yield_now, yield_or_spin, CURRENT,
 };
+pub use sleep_queue::kill_sleeping;
 pub use switch::prepare_and_switch_to;
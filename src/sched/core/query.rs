@@ -0,0 +1,166 @@
+//! Consultas somente-leitura sobre as tasks
+//!
+//! Fotografias (`TaskInfo`) tiradas com os locks do scheduler adquiridos e
+//! liberados dentro da chamada: o chamador nunca segura uma referência para
+//! uma `Task` viva. Cobre as mesmas filas que `task_ids` (execução, prontas,
+//! dormindo e zumbis); a idle task é omitida.
+
+use super::runqueue::RUNQUEUE;
+use super::scheduler::CURRENT;
+use super::sleep_queue::SLEEP_QUEUE;
+use crate::sched::task::lifecycle::ZOMBIES;
+use crate::sched::task::{Task, TaskState};
+use crate::sys::types::Tid;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Metadados de uma task no momento da consulta
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub tid: Tid,
+    pub parent: Option<Tid>,
+    pub name: String,
+    pub state: TaskState,
+    pub priority: u8,
+    /// Páginas mapeadas no espaço de endereçamento (0 para kernel threads)
+    pub mapped_pages: u64,
+    /// Páginas residentes
+    pub resident_pages: u64,
+    /// Tempo de CPU em nanossegundos
+    pub cpu_time: u64,
+    pub voluntary_switches: u64,
+    pub involuntary_switches: u64,
+}
+
+impl TaskInfo {
+    fn from_task(task: &Task) -> Self {
+        let name_len = task
+            .name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(task.name.len());
+        let (mapped_pages, resident_pages) = match task.aspace {
+            Some(ref aspace) => {
+                let aspace = aspace.lock();
+                let stats = aspace.stats();
+                (stats.mapped_pages, stats.resident_pages)
+            }
+            None => (0, 0),
+        };
+
+        Self {
+            tid: task.tid,
+            parent: crate::sched::task::family::parent_of(task.tid),
+            name: String::from_utf8_lossy(&task.name[..name_len]).into_owned(),
+            state: task.state,
+            priority: task.priority,
+            mapped_pages,
+            resident_pages,
+            cpu_time: task.accounting.total_cpu_time,
+            voluntary_switches: task.accounting.voluntary_switches,
+            involuntary_switches: task.accounting.involuntary_switches,
+        }
+    }
+}
+
+/// Aplica `f` a cada task visível, fila por fila.
+fn for_each_task(mut f: impl FnMut(&Task)) {
+    let mut visit = |task: &Task| {
+        if task.tid.as_u32() != 0 {
+            f(task);
+        }
+    };
+    if let Some(ref task) = *CURRENT.lock() {
+        visit(task);
+    }
+    for task in RUNQUEUE.lock().iter() {
+        visit(task);
+    }
+    for task in SLEEP_QUEUE.lock().values() {
+        visit(task);
+    }
+    for task in ZOMBIES.lock().iter() {
+        visit(task);
+    }
+}
+
+/// Uso de memória de uma task (sem nome: não aloca)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskMemory {
+    pub tid: Tid,
+    pub state: TaskState,
+    pub mapped_pages: u64,
+    pub resident_pages: u64,
+}
+
+/// Aplica `f` ao uso de memória de cada task visível, sem alocar.
+///
+/// Feito para o caminho de OOM: filas (ou espaços de endereçamento) com o
+/// lock ocupado são puladas em vez de esperadas, porque quem falhou ao
+/// alocar pode estar segurando um deles.
+pub fn for_each_memory(mut f: impl FnMut(TaskMemory)) {
+    let mut visit = |task: &Task| {
+        if task.tid.as_u32() == 0 {
+            return;
+        }
+        let (mapped_pages, resident_pages) = match task.aspace {
+            Some(ref aspace) => match aspace.try_lock() {
+                Some(aspace) => (aspace.stats().mapped_pages, aspace.stats().resident_pages),
+                None => return,
+            },
+            None => (0, 0),
+        };
+        f(TaskMemory {
+            tid: task.tid,
+            state: task.state,
+            mapped_pages,
+            resident_pages,
+        });
+    };
+    if let Some(current) = CURRENT.try_lock() {
+        if let Some(ref task) = *current {
+            visit(task);
+        }
+    }
+    if let Some(runqueue) = RUNQUEUE.try_lock() {
+        for task in runqueue.iter() {
+            visit(task);
+        }
+    }
+    if let Some(sleeping) = SLEEP_QUEUE.try_lock() {
+        for task in sleeping.values() {
+            visit(task);
+        }
+    }
+    if let Some(zombies) = ZOMBIES.try_lock() {
+        for task in zombies.iter() {
+            visit(task);
+        }
+    }
+}
+
+/// Todas as tasks visíveis, ordenadas por TID
+pub fn tasks() -> Vec<TaskInfo> {
+    let mut tasks = Vec::new();
+    for_each_task(|task| tasks.push(TaskInfo::from_task(task)));
+    tasks.sort_by_key(|info| info.tid.as_u32());
+    tasks
+}
+
+/// Número de tasks visíveis
+pub fn task_count() -> usize {
+    let mut count = 0;
+    for_each_task(|_| count += 1);
+    count
+}
+
+/// Metadados da task `tid`, se ela ainda existir
+pub fn task_info(tid: Tid) -> Option<TaskInfo> {
+    let mut found = None;
+    for_each_task(|task| {
+        if found.is_none() && task.tid == tid {
+            found = Some(TaskInfo::from_task(task));
+        }
+    });
+    found
+}
@@ -1,44 +1,155 @@
 //! Fila de tasks prontas
+//!
+//! Fila multinível indexada por prioridade (menor valor = maior prioridade,
+//! a idle usa 255). Cada nível é FIFO (round-robin dentro do nível) e um
+//! bitmap de níveis ocupados torna a seleção O(1).
+//!
+//! ## Aging
+//! Cada entrada guarda o "instante" (contador de `pop`s) em que entrou no
+//! nível atual. Tasks esperando há mais de `AGING_THRESHOLD` seleções sobem
+//! um nível, evitando starvation de prioridades baixas.
 
 use super::super::task::Task;
 use crate::sync::Spinlock;
+use crate::sys::types::Tid;
 use alloc::boxed::Box;
 use alloc::collections::VecDeque;
 use core::pin::Pin;
 
+/// Número de níveis da fila
+pub const PRIORITY_LEVELS: usize = 32;
+
+/// Prioridades por nível (256 / PRIORITY_LEVELS)
+const PRIORITIES_PER_LEVEL: usize = 256 / PRIORITY_LEVELS;
+
+/// Seleções que uma task pode esperar antes de subir um nível
+pub const AGING_THRESHOLD: u64 = 64;
+
+/// Task enfileirada
+struct Entry {
+    task: Pin<Box<Task>>,
+    /// Valor de `RunQueue::ticks` quando entrou no nível atual
+    enqueued_at: u64,
+}
+
+const EMPTY_LEVEL: VecDeque<Entry> = VecDeque::new();
+
 /// Fila de execução global (Single Core).
 ///
 /// Armazena as tarefas que estão no estado `Ready` e aguardam tempo de CPU.
-/// Atualmente implementa uma política FIFO (Round Robin simples).
 pub struct RunQueue {
-    pub(crate) queue: VecDeque<Pin<Box<Task>>>,
+    levels: [VecDeque<Entry>; PRIORITY_LEVELS],
+    /// Bit `n` ligado = nível `n` não vazio
+    bitmap: u32,
+    /// Contador de seleções (relógio lógico do aging)
+    ticks: u64,
+    len: usize,
+}
+
+/// Nível correspondente a uma prioridade
+pub fn level_of(priority: u8) -> usize {
+    priority as usize / PRIORITIES_PER_LEVEL
 }
 
 impl RunQueue {
     pub const fn new() -> Self {
         Self {
-            queue: VecDeque::new(),
+            levels: [EMPTY_LEVEL; PRIORITY_LEVELS],
+            bitmap: 0,
+            ticks: 0,
+            len: 0,
         }
     }
 
-    /// Adiciona task à fila (final da fila)
+    fn push_level(&mut self, level: usize, entry: Entry) {
+        self.levels[level].push_back(entry);
+        self.bitmap |= 1 << level;
+    }
+
+    /// Adiciona task à fila, no nível da sua prioridade (`Task::priority`)
     pub fn push(&mut self, task: Pin<Box<Task>>) {
-        self.queue.push_back(task);
+        let priority = task.priority;
+        self.enqueue_with_priority(task, priority);
+    }
+
+    /// Adiciona task à fila no nível de `priority`, ignorando `Task::priority`
+    pub fn enqueue_with_priority(&mut self, task: Pin<Box<Task>>, priority: u8) {
+        let entry = Entry {
+            task,
+            enqueued_at: self.ticks,
+        };
+        self.push_level(level_of(priority), entry);
+        self.len += 1;
     }
 
-    /// Remove próxima task (início da fila)
+    /// Remove a task de maior prioridade (FIFO dentro do nível)
     pub fn pop(&mut self) -> Option<Pin<Box<Task>>> {
-        self.queue.pop_front()
+        if self.bitmap == 0 {
+            return None;
+        }
+        self.ticks += 1;
+        self.age();
+
+        let level = self.bitmap.trailing_zeros() as usize;
+        let entry = self.levels[level].pop_front()?;
+        if self.levels[level].is_empty() {
+            self.bitmap &= !(1 << level);
+        }
+        self.len -= 1;
+        Some(entry.task)
+    }
+
+    /// Remove a task `tid` da fila, se estiver nela
+    pub fn remove(&mut self, tid: Tid) -> Option<Pin<Box<Task>>> {
+        for level in 0..PRIORITY_LEVELS {
+            if let Some(pos) = self.levels[level].iter().position(|e| e.task.tid == tid) {
+                let entry = self.levels[level].remove(pos)?;
+                if self.levels[level].is_empty() {
+                    self.bitmap &= !(1 << level);
+                }
+                self.len -= 1;
+                return Some(entry.task);
+            }
+        }
+        None
+    }
+
+    /// Promove um nível as tasks que esperam há mais de `AGING_THRESHOLD`
+    fn age(&mut self) {
+        for level in 1..PRIORITY_LEVELS {
+            if self.bitmap & (1 << level) == 0 {
+                continue;
+            }
+            // FIFO: as mais antigas estão na frente
+            while let Some(front) = self.levels[level].front() {
+                if self.ticks - front.enqueued_at < AGING_THRESHOLD {
+                    break;
+                }
+                let mut entry = self.levels[level].pop_front().unwrap();
+                entry.enqueued_at = self.ticks;
+                self.push_level(level - 1, entry);
+            }
+            if self.levels[level].is_empty() {
+                self.bitmap &= !(1 << level);
+            }
+        }
     }
 
     /// Número de tasks na fila
     pub fn len(&self) -> usize {
-        self.queue.len()
+        self.len
     }
 
     /// Verifica se está vazia
     pub fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+        self.len == 0
+    }
+
+    /// Itera sobre as tasks, do nível mais prioritário para o menos
+    pub fn iter(&self) -> impl Iterator<Item = &Pin<Box<Task>>> {
+        self.levels
+            .iter()
+            .flat_map(|level| level.iter().map(|entry| &entry.task))
     }
 }
 
@@ -47,3 +158,101 @@ impl RunQueue {
 /// Em implementações futuras SMP, isso pode virar um array `[RunQueue; MAX_CPUS]`
 /// ou ser movido para dentro da struct `Cpu`.
 pub static RUNQUEUE: Spinlock<RunQueue> = Spinlock::new(RunQueue::new());
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(priority: u8) -> Pin<Box<Task>> {
+        let mut task = Task::new("rq-test");
+        task.priority = priority;
+        Box::pin(task)
+    }
+
+    #[test]
+    fn test_high_priority_runs_first() {
+        let mut rq = RunQueue::new();
+        let low = task(200);
+        let high = task(10);
+        let (low_tid, high_tid) = (low.tid, high.tid);
+
+        rq.push(low);
+        rq.push(high);
+        assert_eq!(rq.len(), 2);
+
+        assert_eq!(rq.pop().unwrap().tid, high_tid);
+        assert_eq!(rq.pop().unwrap().tid, low_tid);
+        assert!(rq.pop().is_none());
+    }
+
+    #[test]
+    fn test_fifo_within_level() {
+        let mut rq = RunQueue::new();
+        let tasks: alloc::vec::Vec<_> = (0..3).map(|_| task(128)).collect();
+        let tids: alloc::vec::Vec<_> = tasks.iter().map(|t| t.tid).collect();
+        for t in tasks {
+            rq.push(t);
+        }
+        for tid in tids {
+            assert_eq!(rq.pop().unwrap().tid, tid);
+        }
+    }
+
+    #[test]
+    fn test_remove_by_tid() {
+        let mut rq = RunQueue::new();
+        let a = task(10);
+        let b = task(10);
+        let (a_tid, b_tid) = (a.tid, b.tid);
+        rq.push(a);
+        rq.push(b);
+
+        assert_eq!(rq.remove(a_tid).unwrap().tid, a_tid);
+        assert!(rq.remove(a_tid).is_none());
+        assert_eq!(rq.len(), 1);
+        assert_eq!(rq.pop().unwrap().tid, b_tid);
+        assert!(rq.is_empty());
+    }
+
+    #[test]
+    fn test_enqueue_with_priority_overrides() {
+        let mut rq = RunQueue::new();
+        let a = task(0);
+        let b = task(255);
+        let b_tid = b.tid;
+        rq.push(a);
+        rq.enqueue_with_priority(b, 0);
+        rq.pop();
+        assert_eq!(rq.pop().unwrap().tid, b_tid);
+    }
+
+    #[test]
+    fn test_aging_prevents_starvation() {
+        let mut rq = RunQueue::new();
+        let low = task(255);
+        let low_tid = low.tid;
+        rq.push(low);
+
+        // Uma task de alta prioridade sempre pronta (re-enfileirada a cada seleção)
+        rq.push(task(0));
+        let mut picks = 0u64;
+        loop {
+            let next = rq.pop().unwrap();
+            picks += 1;
+            if next.tid == low_tid {
+                break;
+            }
+            rq.push(next);
+            assert!(
+                picks < 64 * AGING_THRESHOLD,
+                "task de baixa prioridade nunca rodou"
+            );
+        }
+        // Precisa subir PRIORITY_LEVELS - 1 níveis, um a cada AGING_THRESHOLD
+        assert!(picks <= (PRIORITY_LEVELS as u64) * AGING_THRESHOLD + 1);
+    }
+}
@@ -11,13 +11,17 @@
 //!
 //! ## Sincronização:
 //! O agendador utiliza um modelo de "Ownership Global" via o Spinlock `CURRENT`.
-//! Somente o núcleo que detém o lock da tarefa pode realizar a troca de contexto segura.
+//! Após o primeiro `schedule()`, `CURRENT` sempre contém uma task (a idle, na
+//! falta de outra), de modo que toda troca tem um slot válido para salvar o
+//! contexto que sai. O lock é liberado antes de `context_switch`.
 
 use crate::arch::Cpu;
+use crate::mm::VirtAddr;
 use crate::sched::task::context::CpuContext;
 use crate::sched::task::Task;
 use crate::sched::task::TaskState;
 use crate::sync::Spinlock;
+use crate::sys::types::Tid;
 use alloc::boxed::Box;
 use core::pin::Pin;
 
@@ -28,7 +32,11 @@ use super::runqueue::RUNQUEUE;
 pub static CURRENT: Spinlock<Option<Pin<Box<Task>>>> = Spinlock::new(None);
 
 /// Inicializa o subsistema de agendamento.
+///
+/// Cria a idle task: a task de menor prioridade, sempre executável, escolhida
+/// por `pick_next` apenas quando a RunQueue está vazia.
 pub fn init() {
+    super::idle::init_idle_task();
     crate::kinfo!("[SCHED] Sistema de agendamento pronto.");
 }
 
@@ -72,29 +80,98 @@ pub fn current() -> Option<*const Task> {
 
 /// Adiciona task à fila de execução
 pub fn enqueue(task: Pin<Box<Task>>) {
-    if task.tid.as_u32() == 0 {
-        crate::kerror!("(Sched) Tentativa de colocar PID 0 na RunQueue! Ignorando...");
-        // TODO: Tratar melhor isso
+    if super::idle::is_idle_task(&task) {
+        crate::kerror!("(Sched) Tentativa de colocar PID 0 na RunQueue! Estacionando...");
+        super::idle::park(task);
         return;
     }
     crate::ktrace!(
         "(Sched) Nova tarefa na RunQueue PID:",
         task.tid.as_u32() as u64
     );
-    RUNQUEUE.lock().push(task);
+    // Prioridade efetiva: inclui a herdada de quem espera um mutex da task
+    let priority = super::inherit::effective_priority(&task);
+    RUNQUEUE.lock().enqueue_with_priority(task, priority);
+}
+
+/// Adiciona task à fila de execução com prioridade explícita
+///
+/// A prioridade vale apenas para esta entrada na fila; `Task::priority` não muda.
+pub fn enqueue_with_priority(task: Pin<Box<Task>>, priority: u8) {
+    if super::idle::is_idle_task(&task) {
+        crate::kerror!("(Sched) Tentativa de colocar PID 0 na RunQueue! Estacionando...");
+        super::idle::park(task);
+        return;
+    }
+    RUNQUEUE.lock().enqueue_with_priority(task, priority);
+}
+
+/// Tamanho da stack de kernel threads criadas por `spawn_kernel_thread`
+const KERNEL_THREAD_STACK_SIZE: usize = 16 * 1024;
+
+/// Cria uma kernel thread com stack própria e a enfileira. Retorna o TID.
+pub fn spawn_kernel_thread(name: &str, entry: extern "C" fn() -> !) -> Tid {
+    let layout = alloc::alloc::Layout::from_size_align(KERNEL_THREAD_STACK_SIZE, 16).unwrap();
+    let stack = unsafe { alloc::alloc::alloc_zeroed(layout) };
+    assert!(
+        !stack.is_null(),
+        "(Sched) Falha ao alocar stack de kernel thread"
+    );
+    let stack_top = stack as u64 + KERNEL_THREAD_STACK_SIZE as u64;
+
+    let mut task = Task::new(name);
+    task.kernel_stack = VirtAddr::new(stack_top);
+    task.context.setup(
+        VirtAddr::new(entry as *const () as u64),
+        VirtAddr::new(stack_top - 16),
+    );
+    task.set_ready();
+    let tid = task.tid;
+    enqueue(Box::pin(task));
+    tid
+}
+
+/// Encerra uma task que está na RunQueue (pronta ou preemptada).
+///
+/// A task vira zumbi com `code` sem voltar a executar. Retorna `false` se ela
+/// não está na fila (rodando, bloqueada, dormindo ou inexistente).
+pub fn kill_ready(tid: Tid, code: i32) -> bool {
+    let task = RUNQUEUE.lock().remove(tid);
+    match task {
+        Some(mut task) => {
+            let inner = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+            inner.exit_code = Some(code);
+            inner.state = TaskState::Zombie;
+            crate::sched::task::lifecycle::add_zombie(task);
+            crate::sched::task::family::exited(tid, code);
+            true
+        }
+        None => false,
+    }
 }
 
 /// Seleciona próxima task para executar
+///
+/// Retorna a idle task somente quando não há outra task pronta. `None` indica
+/// que a RunQueue está vazia e a idle já está em execução (ou não existe).
 pub fn pick_next() -> Option<Pin<Box<Task>>> {
-    let mut rq = RUNQUEUE.lock();
-    let res = rq.pop();
-    if let Some(ref t) = res {
-        crate::ktrace!(
-            "(Sched) pick_next() selecionado PID:",
-            t.tid.as_u32() as u64
-        );
+    loop {
+        let res = RUNQUEUE.lock().pop();
+        match res {
+            Some(t) if super::idle::is_idle_task(&t) => {
+                crate::kerror!("(Sched) BUG: Idle task encontrada na RunQueue! Removendo.");
+                super::idle::park(t);
+            }
+            Some(t) => {
+                crate::ktrace!(
+                    "(Sched) pick_next() selecionado PID:",
+                    t.tid.as_u32() as u64
+                );
+                return Some(t);
+            }
+            None => return super::idle::take(),
+        }
     }
-    res
 }
 
 /// Yield: cede CPU voluntariamente
@@ -105,23 +182,34 @@ pub fn yield_now() {
     Cpu::enable_interrupts();
 }
 
-/// Sleep: coloca a task atual em estado dormente por N milissegundos
-pub fn sleep_current(ms: u64) {
-    if ms == 0 {
+/// Um passo de espera por um lock ocupado.
+///
+/// Código de kernel não é preemptado: girar não devolve a CPU a quem segura o
+/// lock. Com uma task atual e interrupções ligadas (nenhum spinlock segurado)
+/// cede a CPU; senão apenas gira.
+pub fn yield_or_spin() {
+    let has_task = CURRENT.try_lock().is_some_and(|current| current.is_some());
+    if has_task && Cpu::interrupts_enabled() {
         yield_now();
-        return;
+    } else {
+        core::hint::spin_loop();
     }
+}
+
+/// Sleep: coloca a task atual em estado dormente por N milissegundos
+pub fn sleep_current(ms: u64) {
+    crate::core::time::sleep_ns(ms.saturating_mul(1_000_000));
+}
 
+/// Dorme até `deadline_ns` no relógio monotônico (`core::time::now_ns`)
+pub fn sleep_until(deadline_ns: u64) {
     Cpu::disable_interrupts();
 
     // 1. Marcar a task atual como Sleeping e definir tempo
     {
         let mut current_guard = CURRENT.lock();
         if let Some(ref mut task) = *current_guard {
-            let now = crate::core::time::jiffies::get_jiffies();
-            let ticks = crate::core::time::jiffies::millis_to_jiffies(ms);
-
-            unsafe { Pin::get_unchecked_mut(task.as_mut()) }.wake_at = Some(now + ticks);
+            unsafe { Pin::get_unchecked_mut(task.as_mut()) }.wake_at = Some(deadline_ns);
             unsafe { Pin::get_unchecked_mut(task.as_mut()) }.state = TaskState::Sleeping;
 
             crate::kdebug!("(Sched) Tarefa no estado Sleeping");
@@ -129,7 +217,8 @@ pub fn sleep_current(ms: u64) {
     }
 
     // 2. Chama o schedule.
-    // Como a task está Sleeping, o schedule vai salvar o contexto e movê-la para a SleepQueue.
+    // Como a task está Sleeping, o schedule vai salvar o contexto e movê-la para a SleepQueue,
+    // que arma o prazo na roda de timers.
     schedule();
 
     Cpu::enable_interrupts();
@@ -147,188 +236,187 @@ pub unsafe extern "C" fn release_scheduler_lock() {
 pub fn exit_current(code: i32) -> ! {
     Cpu::disable_interrupts();
 
-    // 1. Remover processo atual do CURRENT
-    {
+    // 1. Marcar a task atual como zumbi. Ela continua em CURRENT até o
+    // schedule() trocar de stack e movê-la para a lista de zumbis.
+    let tid = {
         let mut current_guard = CURRENT.lock();
-        if let Some(mut old_task) = current_guard.take() {
-            // Define o código de saída
-            unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.exit_code = Some(code);
-
-            // Move para lista de zumbis para limpeza posterior
-            crate::sched::task::lifecycle::add_zombie(old_task);
-        }
+        current_guard.as_mut().map(|task| {
+            let task = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+            task.exit_code = Some(code);
+            task.state = TaskState::Zombie;
+            task.tid
+        })
+    };
+
+    // Status para o pai (e acorda quem estiver em wait)
+    if let Some(tid) = tid {
+        crate::sched::task::family::exited(tid, code);
     }
 
-    // 2. Schedule next (ou idle task se não houver mais nada)
-    // schedule() retorna para a idle task se não houver próxima task
+    // 2. Schedule next (ou idle task se não houver mais nada). Não retorna:
+    // o contexto de uma task zumbi é descartado.
     schedule();
 
-    // Se chegarmos aqui após exit, continue no loop do scheduler
+    crate::kerror!("(Sched) BUG: task zumbi foi retomada!");
     loop {
-        schedule();
-        Cpu::enable_interrupts();
         Cpu::halt();
-        Cpu::disable_interrupts();
     }
 }
 
-/// Função principal de escalonamento
+/// Função principal de escalonamento (round-robin)
 ///
-/// Usa a idle task (armazenada em IDLE_TASK) como fallback permanente.
-/// Quando não há tasks prontas, fazemos switch para a idle task.
+/// 1. Se `CURRENT` está vazio (boot), a idle task é instalada em `CURRENT`: o
+///    fluxo de boot passa a ser a idle e seu contexto é salvo nela.
+/// 2. `pick_next` escolhe a próxima task (a idle, se a RunQueue estiver vazia);
+///    se a task atual ainda está `Running`, ela tem preferência sobre a idle.
+/// 3. A task que sai é estacionada conforme seu estado (RunQueue, SleepQueue,
+///    zumbis, `IDLE_TASK` ou a WaitQueue de `block_current`).
+/// 4. `CURRENT` recebe a nova task e só então `context_switch` é chamado.
+///
+/// Deve ser chamada com interrupções desabilitadas.
 #[no_mangle]
 pub extern "C" fn schedule() {
-    let mut next_opt = pick_next();
-
-    // Filtro de segurança: PID 0 (idle) nunca deve estar na RunQueue
-    while let Some(ref task) = next_opt {
-        if task.tid.as_u32() == 0 {
-            crate::kerror!("(Sched) BUG: Idle task encontrada na RunQueue! Removendo.");
-            next_opt = pick_next();
-        } else {
-            break;
-        }
-    }
-
-    let mut current_guard = CURRENT.lock();
+    schedule_inner::<fn(Pin<Box<Task>>)>(None);
+}
 
-    // CASO A: Não há próxima task na RunQueue
-    if next_opt.is_none() {
-        if let Some(ref task) = *current_guard {
-            // Se a task atual está Running, ela continua
-            if task.state == TaskState::Running {
+/// Bloqueia a task atual e troca para a próxima.
+///
+/// A task sai de `CURRENT` como `Blocked` e é entregue a `park` (tipicamente
+/// inserida em uma `WaitQueue`) antes da troca de contexto; `park` deve liberar
+/// quaisquer locks que os acordadores precisem. Retorna quando a task for
+/// re-enfileirada e escalonada novamente.
+///
+/// Deve ser chamada com interrupções desabilitadas.
+pub fn block_current<F: FnOnce(Pin<Box<Task>>)>(park: F) {
+    {
+        let mut current_guard = CURRENT.lock();
+        match *current_guard {
+            Some(ref mut task) if !super::idle::is_idle_task(task) => {
+                unsafe { Pin::get_unchecked_mut(task.as_mut()) }.state = TaskState::Blocked;
+            }
+            _ => {
+                crate::kerror!("(Sched) block_current() sem task bloqueável!");
                 return;
             }
         }
+    }
+    schedule_inner(Some(park));
+}
 
-        // Task atual não está Running (Sleeping/Blocked) ou CURRENT está vazio
-        // Precisamos fazer switch para a idle task
-        if let Some(mut old_task) = current_guard.take() {
-            let old_pid = old_task.tid.as_u32();
-
-            // Se a "task antiga" é a própria idle, algo está errado
-            if old_pid == 0 {
-                crate::kerror!("(Sched) BUG: Idle task em CURRENT com estado não-Running!");
-                // Força Running e coloca de volta
-                unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Running;
-                *current_guard = Some(old_task);
-                return;
-            }
+fn schedule_inner<F: FnOnce(Pin<Box<Task>>)>(park_blocked: Option<F>) {
+    // Passar pelo scheduler é um estado quiescente do RCU
+    crate::sync::rcu::note_context_switch();
 
-            // Salvar task atual no lugar apropriado
-            let old_ctx_ptr = unsafe {
-                &mut Pin::get_unchecked_mut(old_task.as_mut()).context as *mut CpuContext
-            };
+    let mut current_guard = CURRENT.lock();
 
-            if old_task.state == TaskState::Sleeping {
-                super::sleep_queue::add_task(old_task);
-            } else if old_task.state == TaskState::Blocked {
-                // Blocked vai para a WaitQueue (já deve estar lá)
-                // Re-enfileira como fallback
-                crate::kwarn!("(Sched) Task Blocked sem próxima: re-enfileirando");
-                unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Ready;
-                RUNQUEUE.lock().push(old_task);
-            } else {
-                // Estado inesperado - re-enfileira
-                crate::kwarn!("(Sched) Task com estado inesperado:", old_task.state as u64);
-                unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Ready;
-                RUNQUEUE.lock().push(old_task);
+    // Bootstrap: adota o contexto de boot como idle task
+    if current_guard.is_none() {
+        match super::idle::take() {
+            Some(mut idle) => {
+                unsafe { Pin::get_unchecked_mut(idle.as_mut()) }.state = TaskState::Running;
+                *current_guard = Some(idle);
             }
-
-            // Switch para a idle task (fallback permanente)
-            drop(current_guard); // Libera lock antes do switch
-
-            if super::idle::is_initialized() {
-                crate::ktrace!("(Sched) Retornando para idle task");
-                unsafe { super::idle::switch_to_idle(old_ctx_ptr) };
-                // Retorna aqui quando a task for re-escalonada
-                return;
-            } else {
-                crate::kerror!("(Sched) Idle task não inicializada! Sistema pode travar.");
+            None => {
+                crate::kerror!("(Sched) schedule() sem CURRENT e sem idle task!");
                 return;
             }
         }
-
-        // CURRENT vazio e sem próxima - só acontece na inicialização
-        return;
     }
 
-    // CASO B: Há uma próxima task para rodar
-    let next = next_opt.unwrap();
-    let next_pid = next.tid.as_u32();
-
-    if let Some(mut old_task) = current_guard.take() {
-        let old_pid = old_task.tid.as_u32();
-        let state = old_task.state;
-        let is_old_idle = old_pid == 0;
-
-        crate::ktrace!("(Sched) Trocando contexto PID:", old_pid as u64);
-
-        // Obtém ponteiro para contexto da task antiga
-        let old_ctx_ptr =
-            unsafe { &mut Pin::get_unchecked_mut(old_task.as_mut()).context as *mut CpuContext };
-
-        // Gerencia a task antiga baseado em seu estado
-        if state == TaskState::Running {
-            if is_old_idle {
-                // Idle task cedendo CPU - NÃO colocamos na RunQueue
-                // Ela fica apenas na IDLE_TASK esperando
-                // O contexto dela será salvo em old_ctx_ptr mas a task
-                // não sai do IDLE_TASK
-            } else {
-                // Task normal: marca Ready e coloca na RunQueue
+    let (old_pid, old_state) = match *current_guard {
+        Some(ref task) => (task.tid.as_u32(), task.state),
+        None => return,
+    };
+    let old_is_idle = old_pid == 0;
+
+    let next = match pick_next() {
+        Some(task) if super::idle::is_idle_task(&task) && old_state == TaskState::Running => {
+            super::idle::park(task);
+            return;
+        }
+        Some(task) => task,
+        None => {
+            // RunQueue vazia e a idle já é a task atual
+            if old_state != TaskState::Running {
+                crate::kerror!(
+                    "(Sched) BUG: Idle em CURRENT não está Running! PID:",
+                    old_pid as u64
+                );
+                if let Some(ref mut task) = *current_guard {
+                    unsafe { Pin::get_unchecked_mut(task.as_mut()) }.state = TaskState::Running;
+                }
+            }
+            return;
+        }
+    };
+
+    let mut old_task = match current_guard.take() {
+        Some(task) => task,
+        None => return,
+    };
+    crate::ktrace!("(Sched) Trocando contexto PID:", old_pid as u64);
+
+    let old_ctx = {
+        let task = unsafe { Pin::get_unchecked_mut(old_task.as_mut()) };
+        task.accounting.end_exec(crate::core::time::now_ns());
+        &mut task.context as *mut CpuContext
+    };
+
+    // Estaciona a task que sai. Pin<Box<Task>> garante que `old_ctx`
+    // continua válido após o Box mudar de dono.
+    let save_slot = if old_is_idle {
+        super::idle::park(old_task);
+        Some(old_ctx)
+    } else {
+        match old_state {
+            TaskState::Zombie => {
+                crate::sched::task::lifecycle::add_zombie(old_task);
+                None
+            }
+            TaskState::Sleeping => {
+                super::sleep_queue::add_task(old_task);
+                Some(old_ctx)
+            }
+            TaskState::Running => {
                 unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Ready;
-                RUNQUEUE.lock().push(old_task);
+                let priority = super::inherit::effective_priority(&old_task);
+                RUNQUEUE.lock().enqueue_with_priority(old_task, priority);
+                Some(old_ctx)
+            }
+            TaskState::Blocked if park_blocked.is_some() => {
+                (park_blocked.unwrap())(old_task);
+                Some(old_ctx)
             }
-        } else if state == TaskState::Sleeping {
-            super::sleep_queue::add_task(old_task);
-        } else if state == TaskState::Blocked {
-            crate::kerror!("(Sched) Task Blocked em CURRENT! PID:", old_pid as u64);
-            unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Ready;
-            RUNQUEUE.lock().push(old_task);
-        } else {
-            crate::kerror!("(Sched) Estado inesperado! PID:", old_pid as u64);
-            if !is_old_idle {
+            state => {
+                crate::kerror!("(Sched) Estado inesperado em CURRENT:", state as u64);
                 unsafe { Pin::get_unchecked_mut(old_task.as_mut()) }.state = TaskState::Ready;
                 RUNQUEUE.lock().push(old_task);
+                Some(old_ctx)
             }
         }
+    };
 
-        // Faz o switch de contexto
-        if is_old_idle {
-            // Switch DA idle task - precisamos usar o contexto em IDLE_TASK
-            let idle_ctx_ptr = unsafe { super::idle::get_idle_context() };
-            unsafe {
-                super::switch::prepare_and_switch_to(next, Some(idle_ctx_ptr), current_guard)
-            };
-        } else {
-            unsafe { super::switch::prepare_and_switch_to(next, Some(old_ctx_ptr), current_guard) };
-        }
-    } else {
-        // Nenhuma task atual - primeira execução
-        crate::ktrace!("(Sched) Primeira execução de PID:", next_pid as u64);
-        unsafe { super::switch::prepare_and_switch_to(next, None, current_guard) };
-    }
+    unsafe { super::switch::prepare_and_switch_to(next, save_slot, current_guard) };
 }
 
-/// Loop principal do scheduler
+/// Entra no escalonamento a partir do fluxo de boot. Não retorna.
 ///
-/// # TODO (SMP Safety)
-/// Este loop assume que após schedule() retornar, é seguro fazer halt().
-/// Em SMP, outro core pode modificar RUNQUEUE entre a verificação e o halt().
-/// Considerar:
-/// - Usar mecanismos de IPI (Inter-Processor Interrupt) para acordar cores
-/// - Verificar CURRENT antes de halt() para garantir consistência
-/// - Usar spinloop com backoff ao invés de halt() direto
+/// O contexto de boot é descartado e a CPU passa para a idle task, que cede a
+/// CPU às tasks prontas. Se o fluxo de boot já foi adotado como idle (um
+/// `schedule()` antes de `run()`), ele simplesmente executa o loop da idle.
 pub fn run() -> ! {
     Cpu::disable_interrupts();
-    loop {
-        schedule();
-        if RUNQUEUE.lock().is_empty() {
-            crate::sched::task::lifecycle::cleanup_all();
-            Cpu::enable_interrupts();
-            Cpu::halt();
-            Cpu::disable_interrupts();
+
+    let current_guard = CURRENT.lock();
+    if current_guard.is_none() {
+        if let Some(idle) = super::idle::take() {
+            unsafe { super::switch::prepare_and_switch_to(idle, None, current_guard) };
+            unreachable!("(Sched) Contexto de boot retomado");
         }
+        crate::kerror!("(Sched) run() sem idle task! Chame sched::init() antes.");
+    } else {
+        drop(current_guard);
     }
+
+    super::idle::idle_task_entry()
 }
@@ -1,66 +1,71 @@
 //! Sleep Queue - Gerencia tasks que estão dormindo
 //!
 //! Permite que tasks sejam bloqueadas por um tempo determinado e acordadas pelo timer.
+//! As tasks ficam aqui indexadas pelo TID; os prazos ficam na roda de
+//! `core::time::sleep`, que diz quem acordar a cada tick.
 
 use alloc::boxed::Box;
-use alloc::collections::VecDeque;
+use alloc::collections::BTreeMap;
 use core::pin::Pin;
 
-use crate::core::time::jiffies;
+use crate::core::time;
 use crate::sched::task::{Task, TaskState};
 use crate::sync::Spinlock;
+use crate::sys::types::Tid;
 
-/// Fila global de tasks dormindo
-pub static SLEEP_QUEUE: Spinlock<VecDeque<Pin<Box<Task>>>> = Spinlock::new(VecDeque::new());
+/// Tasks dormindo, por TID
+pub static SLEEP_QUEUE: Spinlock<BTreeMap<u32, Pin<Box<Task>>>> = Spinlock::new(BTreeMap::new());
 
-/// Verifica se há tasks que devem acordar e as move para a RunQueue
+/// Acorda as tasks com prazo vencido, na ordem dos prazos, movendo-as para a RunQueue
 pub fn check_sleep_queue() {
-    let now = jiffies::get_jiffies();
-    let mut sleep_queue = SLEEP_QUEUE.lock();
-
-    // Usamos um contador manual para evitar problemas ao remover itens enquanto iteramos
-    let mut i = 0;
-    while i < sleep_queue.len() {
-        let should_wake = if let Some(wake_at) = sleep_queue[i].wake_at {
-            now >= wake_at
-        } else {
-            false
-        };
-
-        if should_wake {
-            // Remove da fila de sleep
-            if let Some(mut task) = sleep_queue.remove(i) {
-                crate::ktrace!("(Sleep) Acordando task PID:", task.tid.as_u32() as u64);
-                // TODO: Remover esse log causa panico no supervisor investigar o motivo
-
-                // Limpa o wake_at e marca como pronta
-                task.wake_at = None;
-                task.state = TaskState::Ready;
+    let expired = time::sleep::expired(time::now_ns());
+    if expired.is_empty() {
+        return;
+    }
 
-                // Devolve para a RunQueue global
-                crate::sched::core::enqueue(task);
+    let mut sleep_queue = SLEEP_QUEUE.lock();
+    for tid in expired {
+        if let Some(mut task) = sleep_queue.remove(&tid) {
+            crate::ktrace!("(Sleep) Acordando task PID:", task.tid.as_u32() as u64);
+            // TODO: Remover esse log causa panico no supervisor investigar o motivo
 
-                // Não incrementa i pois o próximo item agora está nesta posição
-                continue;
-            }
-        }
-        i += 1;
-    }
+            // Limpa o wake_at e marca como pronta
+            let inner = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+            inner.wake_at = None;
+            inner.state = TaskState::Ready;
 
-    // DEBUG: Monitorar estado da fila de sleep
-    if !sleep_queue.is_empty() {
-        for t in sleep_queue.iter() {
-            if t.tid.as_u32() == 1 {
-                crate::ktrace!(
-                    "(Sleep) Task 1 (Supervisor) na fila. Acordando em:",
-                    t.wake_at.unwrap_or(0)
-                );
-                crate::ktrace!("(Sleep) Jiffies atual:", now);
-            }
+            // Devolve para a RunQueue global
+            crate::sched::core::enqueue(task);
         }
     }
 }
 
+/// Estaciona uma task dormindo e arma o prazo dela (`wake_at`)
 pub fn add_task(task: Pin<Box<Task>>) {
-    SLEEP_QUEUE.lock().push_back(task);
+    let tid = task.tid.as_u32();
+    // Sem prazo: acorda no próximo tick
+    let deadline = task.wake_at.unwrap_or(0);
+    SLEEP_QUEUE.lock().insert(tid, task);
+    time::sleep::arm(tid, deadline);
+}
+
+/// Encerra uma task que está dormindo, desarmando o prazo.
+///
+/// A task vira zumbi com `code` sem voltar a executar. Retorna `false` se ela
+/// não está dormindo.
+pub fn kill_sleeping(tid: Tid, code: i32) -> bool {
+    let task = SLEEP_QUEUE.lock().remove(&tid.as_u32());
+    match task {
+        Some(mut task) => {
+            time::sleep::disarm(tid.as_u32());
+            let inner = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+            inner.wake_at = None;
+            inner.exit_code = Some(code);
+            inner.state = TaskState::Zombie;
+            crate::sched::task::lifecycle::add_zombie(task);
+            crate::sched::task::family::exited(tid, code);
+            true
+        }
+        None => false,
+    }
 }
@@ -1,72 +1,81 @@
 //! Lógica de Troca de Contexto (Context Switching)
+//!
+//! Toda troca passa por `context_switch(old_rsp, new_rsp)` (`arch/x86_64/switch.s`):
+//! os registradores callee-saved são empilhados na stack da task que sai e o
+//! `CpuContext` guarda apenas o RSP resultante.
 
-use crate::sched::task::context::{jump_to_context, switch, CpuContext};
+use crate::sched::task::context::CpuContext;
 use crate::sched::task::{Task, TaskState};
 use crate::sync::SpinlockGuard;
 use alloc::boxed::Box;
 use core::pin::Pin;
 
-/// Efetua a troca de contexto de baixo nível.
+/// Slot de descarte para contextos que nunca serão retomados (tasks em saída).
+static mut DISCARD_RSP: u64 = 0;
+
+/// Troca de `old` para `new`.
 ///
-/// Esta função realiza a transição final de ownership e chama o assembly.
+/// Se `new` ainda não executou (`CpuContext::is_fresh`), a stack é preparada
+/// para que o `ret` de `context_switch` caia no entry point. Retorna quando a
+/// task dona de `old` for re-escalonada. Com `old == None` o contexto atual é
+/// descartado.
+///
+/// # Safety
+/// - Interrupções desabilitadas
+/// - `new` válido e com a stack mapeada no CR3 atual
+/// - `old` (se presente) deve permanecer válido até a task ser retomada
+pub unsafe fn switch_context(old: Option<*mut CpuContext>, new: *mut CpuContext) {
+    if (*new).is_fresh() {
+        (*new).prime_for_switch();
+    }
+
+    let old_rsp = match old {
+        Some(ctx) => {
+            (*ctx).mark_saved();
+            &mut (*ctx).rsp as *mut u64
+        }
+        None => core::ptr::addr_of_mut!(DISCARD_RSP),
+    };
+
+    crate::sched::context_switch(old_rsp, (*new).rsp);
+}
+
+/// Instala `next` em `CURRENT` e troca para ele.
+///
+/// `CURRENT` é atualizado (e o lock liberado) ANTES da troca de baixo nível;
+/// o chamador já deve ter estacionado a task que sai (RunQueue, SleepQueue,
+/// WaitQueue, zumbis), mantendo `old_ctx` apontando para o contexto dela.
 ///
-/// **Nota sobre o fluxo:** Se for uma troca entre tarefas existentes (`switch`),
-/// esta função IRÁ RETORNAR quando a tarefa atual for re-escalonada no futuro.
-/// Se for um salto para uma tarefa nova (`jump_to_context`), ela não retorna.
-///  
 /// # Safety
 /// Deve ser chamada com interrupções desabilitadas.
 ///
-/// # TODO (SMP Safety - Memory Lifetime)
-/// O parâmetro `old_ctx` é um ponteiro para o CpuContext dentro de uma Task.
-/// A Task é movida para RunQueue/SleepQueue ANTES de chamar esta função.
-/// Riscos a considerar para SMP:
-/// - Outro core pode acessar/modificar a Task enquanto o switch está em progresso
-/// - O ponteiro old_ctx deve permanecer válido durante toda a operação de switch
-/// - Pin<Box<Task>> ajuda a garantir que a Task não será movida, mas não protege
-///   contra dealocação por outro core
-/// Mitigações possíveis:
-/// - Garantir exclusão mútua via lock durante todo o switch
-/// - Usar Arc<Task> com RwLock interno
-/// - Implementar mecanismo de "handoff" atômico entre cores
+/// # TODO (SMP Safety)
+/// A task que sai já está visível em sua fila antes de `context_switch` salvar
+/// o RSP. Em SMP outro core poderia retomá-la cedo demais; será necessário um
+/// flag `on_cpu` liberado após a troca.
 pub unsafe fn prepare_and_switch_to(
     mut next: Pin<Box<Task>>,
     old_ctx: Option<*mut CpuContext>,
     mut current_guard: SpinlockGuard<Option<Pin<Box<Task>>>>,
 ) {
-    // Extrair dados necessários
-    let is_new = next.state == TaskState::Created;
-    let new_ctx_ptr = &next.context as *const _;
+    let task = Pin::get_unchecked_mut(next.as_mut());
+    task.state = TaskState::Running;
+    task.accounting.start_exec(crate::core::time::now_ns());
+    let new_ctx = &mut task.context as *mut CpuContext;
 
-    // Marcar nova task como Running
-    core::pin::Pin::get_unchecked_mut(next.as_mut()).state = TaskState::Running;
-
-    // Log de troca
     crate::ktrace!("(Sched) Mudando para PID:", next.tid.as_u32() as u64);
 
-    // Aplicar estado de hardware (GDT, CR3)
+    // Aplicar estado de hardware (TSS, CR3) antes de tocar a stack da nova task
     next.apply_hardware_state();
 
-    // Transferir ownership para o global CURRENT
+    // Transferir ownership para o global CURRENT; a área da CPU guarda o
+    // endereço (estável: a task é Pin<Box>)
+    let task_ptr = &*next as *const Task as *mut Task;
     *current_guard = Some(next);
+    crate::core::smp::percpu::this_cpu()
+        .current_task
+        .store(task_ptr, core::sync::atomic::Ordering::Relaxed);
     drop(current_guard);
 
-    // Efetuar o salto/troca final
-    if let Some(old_ctx_ptr) = old_ctx {
-        // Troca completa (salva atual, restaura próxima)
-        // Quando esta tarefa for retomada, ela voltará exatamente aqui.
-        switch(&mut *old_ctx_ptr, &*new_ctx_ptr);
-        // Task retomada - continua execução normal
-    } else {
-        // Apenas restaura próxima (sem contexto anterior para salvar)
-        if is_new {
-            // Task nova - salta para entry point
-            jump_to_context(&*new_ctx_ptr);
-        } else {
-            // Task existente sem old_ctx - isso só deveria acontecer na idle task
-            // ou na primeira execução do scheduler
-            let mut dummy = CpuContext::new();
-            switch(&mut dummy, &*new_ctx_ptr);
-        }
-    }
+    switch_context(old_ctx, new_ctx);
 }
@@ -1,22 +1,30 @@
 //! ELF Loader
 
-use crate::mm::pmm::{FRAME_ALLOCATOR, FRAME_SIZE};
-use crate::mm::vmm::{map_page_with_pmm, MapFlags};
-use crate::mm::VirtAddr;
+use crate::mm::pmm::FRAME_SIZE;
+use crate::mm::vmm::MapFlags;
+use crate::mm::{PhysAddr, VirtAddr};
 use crate::sys::{KernelError, KernelResult};
 
-mod structs;
+pub(crate) mod reloc;
+pub(crate) mod structs;
 use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
-use crate::mm::aspace::{ASpaceError, AddressSpace};
-use crate::sync::Spinlock;
+use crate::mm::aspace::AddressSpace;
+#[cfg(feature = "elf_demand_paging")]
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use structs::*;
 
+/// Fim (exclusivo) da metade canônica de usuário
+const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// Base de carga de executáveis PIE linkados em 0
+const PIE_LOAD_BASE: u64 = 0x0000_0000_0040_0000;
+
 /// Carrega um binário ELF na memória de um AddressSpace
-pub fn load_binary(
-    data: &[u8],
-    aspace_arc: &Arc<Spinlock<AddressSpace>>,
-) -> KernelResult<VirtAddr> {
+///
+/// Os segmentos são mapeados na PML4 de `aspace`; as tabelas de página ativas
+/// não são alteradas. Retorna o entry point.
+pub fn load_binary(data: &[u8], aspace: &mut AddressSpace) -> KernelResult<VirtAddr> {
     // 1. Validar Magic Header (\x7FELF)
     if data.len() < 64 || &data[0..4] != b"\x7fELF" {
         crate::kerror!("(ELF) Invalid Magic");
@@ -42,156 +50,373 @@ pub fn load_binary(
     let ph_num = ehdr.e_phnum as usize;
     let ph_size = ehdr.e_phentsize as usize;
 
+    // Validar tabela de Program Headers
+    let ph_end = ph_num
+        .checked_mul(ph_size)
+        .and_then(|len| len.checked_add(ph_offset));
+    if ph_size < core::mem::size_of::<Elf64_Phdr>() || ph_end.map_or(true, |end| end > data.len()) {
+        crate::kerror!("(ELF) Tabela de Program Headers invalida");
+        return Err(KernelError::InvalidArgument);
+    }
+
+    let phdrs: Vec<Elf64_Phdr> = (0..ph_num)
+        .map(|i| unsafe {
+            core::ptr::read_unaligned(
+                data.as_ptr().add(ph_offset + i * ph_size) as *const Elf64_Phdr
+            )
+        })
+        .collect();
+
+    // Load bias: PIE linkado em 0 é deslocado para PIE_LOAD_BASE
+    let min_vaddr = phdrs
+        .iter()
+        .filter(|p| p.p_type == PT_LOAD)
+        .map(|p| p.p_vaddr & !(FRAME_SIZE - 1))
+        .min()
+        .unwrap_or(0);
+    let bias = if ehdr.e_type == ET_DYN && min_vaddr == 0 {
+        PIE_LOAD_BASE - min_vaddr
+    } else {
+        0
+    };
+
+    // Imagem compartilhada pelas VMAs sob demanda (mantida viva pelo AddressSpace)
+    #[cfg(feature = "elf_demand_paging")]
+    let image: Arc<[u8]> = Arc::from(data);
+
     // Iterar Program Headers
-    for i in 0..ph_num {
-        let offset = ph_offset + i * ph_size;
-        let phdr = unsafe { &*(data.as_ptr().add(offset) as *const Elf64_Phdr) };
-
-        if phdr.p_type == PT_LOAD {
-            crate::ktrace!("(ELF) Segmento LOAD: vaddr=", phdr.p_vaddr);
-            crate::ktrace!("(ELF) memsz=", phdr.p_memsz);
-            // 1. Determinar Proteções e Intenção
-            let mut prot = Protection::READ;
-            if phdr.p_flags & PF_W != 0 {
-                prot = Protection::RW;
-            }
-            if phdr.p_flags & PF_X != 0 {
-                prot = if phdr.p_flags & PF_W != 0 {
-                    Protection::RWX
-                } else {
-                    Protection::RX
-                };
+    for phdr in phdrs.iter().filter(|p| p.p_type == PT_LOAD) {
+        let mut seg = *phdr;
+        seg.p_vaddr = seg
+            .p_vaddr
+            .checked_add(bias)
+            .ok_or(KernelError::InvalidArgument)?;
+
+        crate::ktrace!("(ELF) Segmento LOAD: vaddr=", seg.p_vaddr);
+        crate::ktrace!("(ELF) memsz=", seg.p_memsz);
+        validate_segment(&seg, data.len())?;
+
+        #[cfg(feature = "elf_demand_paging")]
+        load_segment_lazy(&seg, &image, aspace)?;
+        #[cfg(not(feature = "elf_demand_paging"))]
+        load_segment_eager(data, &seg, aspace)?;
+    }
+
+    // Relocações dinâmicas (PIE)
+    if ehdr.e_type == ET_DYN {
+        if let Some(table) = reloc::find_rela_table(data, &phdrs)? {
+            let applied = reloc::apply_relocations(data, table, bias, |vaddr, value| {
+                write_u64_in_aspace(aspace, vaddr, value)
+            })?;
+            crate::ktrace!("(ELF) Relocacoes aplicadas:", applied as u64);
+        }
+    }
+
+    let entry = ehdr.e_entry.wrapping_add(bias);
+    crate::ktrace!("(ELF) Carregado com sucesso. Entrada:", entry);
+    Ok(VirtAddr::new(entry))
+}
+
+/// Grava um u64 em um endereço do AddressSpace alvo via HHDM.
+///
+/// O endereço vem do arquivo: os 8 bytes têm de estar na metade de usuário
+/// e cobertos por VMAs do `aspace` (a metade do kernel está em toda PML4 e
+/// não pode ser alvo de relocação). Páginas ainda não residentes (carga sob
+/// demanda) são populadas antes.
+fn write_u64_in_aspace(aspace: &AddressSpace, vaddr: u64, value: u64) -> KernelResult<()> {
+    use crate::mm::vmm::mapper::translate_addr_in_p4;
+
+    let end = vaddr
+        .checked_add(8)
+        .filter(|&end| end <= USER_SPACE_END)
+        .ok_or(KernelError::InvalidArgument)?;
+    let mut covered = vaddr;
+    while covered < end {
+        let vma = aspace
+            .find_vma(VirtAddr::new(covered))
+            .ok_or(KernelError::InvalidArgument)?;
+        covered = vma.end.as_u64();
+    }
+
+    let cr3 = aspace.cr3();
+    for (addr, byte) in (vaddr..end).zip(value.to_le_bytes()) {
+        let phys = match translate_addr_in_p4(cr3, addr) {
+            Some(phys) => phys,
+            None => {
+                let vma = aspace
+                    .find_vma(VirtAddr::new(addr))
+                    .ok_or(KernelError::InvalidArgument)?;
+                let mut flags = MapFlags::PRESENT | MapFlags::USER;
+                if vma.protection.can_write() {
+                    flags |= MapFlags::WRITABLE;
+                }
+                if vma.protection.can_exec() {
+                    flags |= MapFlags::EXECUTABLE;
+                }
+                let page = VirtAddr::new(addr).align_down(FRAME_SIZE);
+                crate::mm::fault::populate_file_page(cr3, page, &vma.backing, flags)
+                    .map_err(|_| KernelError::OutOfMemory)?;
+                translate_addr_in_p4(cr3, addr).ok_or(KernelError::OutOfMemory)?
             }
+        };
+        unsafe {
+            let dst = crate::mm::physmap::phys_to_virt(PhysAddr::new(phys));
+            core::ptr::write_volatile(dst.as_mut_ptr::<u8>(), byte);
+        }
+    }
+    Ok(())
+}
 
-            let intent = if phdr.p_flags & PF_X != 0 {
-                MemoryIntent::Code
-            } else if phdr.p_flags & PF_W != 0 {
-                MemoryIntent::Data
-            } else {
-                MemoryIntent::FileReadOnly
-            };
+/// Valida os limites de um segmento PT_LOAD
+///
+/// Rejeita overflow em `p_offset + p_filesz` e `p_vaddr + p_memsz`, dados
+/// fora do arquivo, `p_filesz > p_memsz` e segmentos fora da metade de usuário.
+fn validate_segment(phdr: &Elf64_Phdr, file_len: usize) -> KernelResult<()> {
+    let file_end = phdr.p_offset.checked_add(phdr.p_filesz);
+    let mem_end = phdr
+        .p_vaddr
+        .checked_add(phdr.p_memsz)
+        .and_then(|end| end.checked_add(FRAME_SIZE - 1));
+
+    let valid = match (file_end, mem_end) {
+        (Some(file_end), Some(mem_end)) => {
+            file_end <= file_len as u64
+                && phdr.p_filesz <= phdr.p_memsz
+                && mem_end <= USER_SPACE_END
+        }
+        _ => false,
+    };
+
+    if !valid {
+        crate::kerror!("(ELF) Segmento invalido: vaddr=", phdr.p_vaddr);
+        return Err(KernelError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Copia bytes com escritas voláteis.
+///
+/// Evita que o compilador gere `memcpy` vetorizado (SSE) ao copiar para a
+/// janela HHDM.
+unsafe fn copy_volatile(src: *const u8, dst: *mut u8, len: usize) {
+    for i in 0..len {
+        core::ptr::write_volatile(dst.add(i), core::ptr::read(src.add(i)));
+    }
+}
+
+/// Proteção e intenção de um segmento a partir de `p_flags`
+fn segment_protection(phdr: &Elf64_Phdr) -> (Protection, MemoryIntent) {
+    let mut prot = Protection::READ;
+    if phdr.p_flags & PF_W != 0 {
+        prot = Protection::RW;
+    }
+    if phdr.p_flags & PF_X != 0 {
+        prot = if phdr.p_flags & PF_W != 0 {
+            Protection::RWX
+        } else {
+            Protection::RX
+        };
+    }
+
+    let intent = if phdr.p_flags & PF_X != 0 {
+        MemoryIntent::Code
+    } else if phdr.p_flags & PF_W != 0 {
+        MemoryIntent::Data
+    } else {
+        MemoryIntent::FileReadOnly
+    };
+
+    (prot, intent)
+}
 
-            // 2. Registrar VMA no AddressSpace
-            let start_vaddr = VirtAddr::new(phdr.p_vaddr);
-            let mem_size = phdr.p_memsz as usize;
+/// Carga eager: aloca, zera e copia todas as páginas do segmento agora
+///
+/// As páginas são mapeadas na PML4 de `aspace` (não na tabela ativa) e os
+/// dados são escritos pela janela HHDM dos frames recém-alocados.
+#[cfg(not(feature = "elf_demand_paging"))]
+fn load_segment_eager(
+    data: &[u8],
+    phdr: &Elf64_Phdr,
+    aspace: &mut AddressSpace,
+) -> KernelResult<()> {
+    use crate::mm::pmm::FRAME_ALLOCATOR;
+    use crate::mm::vmm::mapper::{map_page_in_target_p4, translate_addr_in_p4};
+
+    // 1. Determinar Proteções e Intenção
+    let (prot, intent) = segment_protection(phdr);
+
+    // 2. Registrar VMA no AddressSpace
+    let mut start_page = phdr.p_vaddr & !(FRAME_SIZE - 1);
+    let end_page = (phdr.p_vaddr + phdr.p_memsz + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
 
-            let map_result = aspace_arc.lock().map_region(
-                Some(start_vaddr),
-                mem_size,
+    if aspace.find_vma(VirtAddr::new(start_page)).is_some() {
+        // Segmentos adjacentes compartilhando página: a primeira página já tem
+        // VMA (e frame); registrar apenas o restante do segmento.
+        crate::kdebug!("(ELF) Pagina compartilhada entre segmentos:", start_page);
+        start_page += FRAME_SIZE;
+    }
+
+    if start_page < end_page {
+        aspace
+            .map_region(
+                Some(VirtAddr::new(start_page)),
+                (end_page - start_page) as usize,
                 prot,
                 VmaFlags::empty(),
                 intent,
-            );
+            )
+            .map_err(|e| {
+                crate::kerror!("(ELF) Falha fatal ao registrar VMA:", e as u64);
+                KernelError::OutOfMemory
+            })?;
+        crate::ktrace!("(ELF) VMA registrada:", start_page);
+    }
 
-            match map_result {
-                Ok(_) => {
-                    crate::ktrace!("(ELF) VMA registrada:", start_vaddr.as_u64());
-                }
-                Err(ASpaceError::RegionOverlap) => {
-                    // Sobreposição detectada (segmentos adjacentes compartilhando página)
-                    // Vamos tentar fazer merge das permissões na VMA existente
-                    crate::kwarn!("(ELF) Sobreposicao detectada. Tentando mesclar...");
-
-                    let mut aspace = aspace_arc.lock();
-                    if let Some(mut existing_vma) = aspace.find_vma(start_vaddr) {
-                        // Atualizar permissões (Union)
-                        // VMA struct é retornada por find_vma (clone).
-                        // Precisamos atualizar a lista de VMAs.
-                        // Mas, por enquanto, assumimos que se sobrepôs, a página anterior já existe.
-                        // Vamos apenas garantir que a página física tenha permissão RWX se necessário no passo 3.
-                        crate::kwarn!(
-                            "(ELF) Mesclagem assumida. VMA existente:",
-                            existing_vma.start.as_u64()
-                        );
-                    } else {
-                        crate::kerror!("(ELF) Erro: Regiao sobreposta mas VMA nao encontrada!");
-                        return Err(KernelError::OutOfMemory);
-                    }
-                }
-                Err(e) => {
-                    crate::kerror!("(ELF) Falha fatal ao registrar VMA:", e as u64);
-                    return Err(KernelError::OutOfMemory);
-                }
-            }
+    // 3. Alocar e mapear páginas físicas na P4 do alvo
+    let first_page = phdr.p_vaddr & !(FRAME_SIZE - 1);
+    let target_cr3 = aspace.cr3();
+    let mut vmm_flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE;
 
-            // 3. Alocar e mapear páginas físicas (Manual Load via HHDM)
-            let start_page = phdr.p_vaddr & !(FRAME_SIZE - 1);
-            let end_page = (phdr.p_vaddr + phdr.p_memsz + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
-            let pages = (end_page - start_page) / FRAME_SIZE;
+    if phdr.p_flags & PF_X != 0 {
+        vmm_flags |= MapFlags::EXECUTABLE;
+    }
 
-            let target_cr3 = aspace_arc.lock().cr3();
-            let mut pmm = FRAME_ALLOCATOR.lock();
-            let mut vmm_flags = MapFlags::PRESENT | MapFlags::USER | MapFlags::WRITABLE;
+    let mut vaddr = first_page;
+    while vaddr < end_page {
+        // Página já mapeada (compartilhada com o segmento anterior): manter
+        if translate_addr_in_p4(target_cr3, vaddr).is_none() {
+            // Pelo PFM: o frame nasce com uma referência (ver `AddressSpace::fork`)
+            let frame = crate::mm::pfm::alloc_user_frame(aspace.owner())
+                .map_err(|_| KernelError::OutOfMemory)?;
 
-            if phdr.p_flags & 0x1 != 0 {
-                vmm_flags |= MapFlags::EXECUTABLE;
+            // Zerar página NOVA pela physmap
+            unsafe {
+                crate::mm::physmap::zero_frame(frame);
             }
 
-            for page_idx in 0..pages {
-                let vaddr = start_page + page_idx * FRAME_SIZE;
-
-                // Verificar se já está mapeado no alvo
-                if crate::mm::vmm::mapper::translate_addr_in_p4(target_cr3, vaddr).is_none() {
-                    if let Some(frame) = pmm.allocate_frame() {
-                        unsafe {
-                            crate::mm::vmm::mapper::map_page_in_target_p4(
-                                target_cr3,
-                                vaddr,
-                                frame.as_u64(),
-                                vmm_flags,
-                                &mut *pmm,
-                            )
-                            .expect("(ELF) Erro ao mapear página");
-
-                            // Zerar página NOVA via HHDM
-                            core::ptr::write_bytes(
-                                crate::mm::addr::phys_to_virt::<u8>(frame.as_u64()),
-                                0,
-                                FRAME_SIZE as usize,
-                            );
-                        }
-                    }
-                }
+            let mapped = {
+                let mut pmm = FRAME_ALLOCATOR.lock();
+                map_page_in_target_p4(target_cr3, vaddr, frame.as_u64(), vmm_flags, &mut *pmm)
+            };
+            if mapped.is_err() {
+                crate::mm::pfm::release_mapped(frame);
+                return Err(KernelError::OutOfMemory);
             }
+        }
+        vaddr += FRAME_SIZE;
+    }
 
-            // 4. Copiar dados via HHDM para os frames do AddressSpace alvo
-            let file_size = phdr.p_filesz as usize;
-            if file_size > 0 {
-                let mut bytes_copied = 0usize;
-                let file_offset = phdr.p_offset as usize;
-                let segment_data = &data[file_offset..file_offset + file_size];
-
-                while bytes_copied < file_size {
-                    let vaddr = phdr.p_vaddr + bytes_copied as u64;
-                    let page_offset = vaddr % FRAME_SIZE;
-                    let bytes_to_copy = core::cmp::min(
-                        file_size - bytes_copied,
-                        (FRAME_SIZE - page_offset) as usize,
-                    );
-
-                    // Achar frame físico correspondente no alvo
-                    if let Some(phys) =
-                        crate::mm::vmm::mapper::translate_addr_in_p4(target_cr3, vaddr)
-                    {
-                        unsafe {
-                            let dst = crate::mm::addr::phys_to_virt::<u8>(phys & !0xFFF)
-                                .add(page_offset as usize);
-                            core::ptr::copy_nonoverlapping(
-                                segment_data.as_ptr().add(bytes_copied),
-                                dst,
-                                bytes_to_copy,
-                            );
-                        }
-                    } else {
-                        panic!("(ELF) Erro fatal: página do segmento não mapeada!");
-                    }
-
-                    bytes_copied += bytes_to_copy;
-                }
+    // 4. Copiar dados via HHDM para os frames do AddressSpace alvo
+    let file_size = phdr.p_filesz as usize;
+    if file_size > 0 {
+        let mut bytes_copied = 0usize;
+        let file_offset = phdr.p_offset as usize;
+        let segment_data = &data[file_offset..file_offset + file_size];
+
+        while bytes_copied < file_size {
+            let vaddr = phdr.p_vaddr + bytes_copied as u64;
+            let page_offset = vaddr % FRAME_SIZE;
+            let bytes_to_copy = core::cmp::min(
+                file_size - bytes_copied,
+                (FRAME_SIZE - page_offset) as usize,
+            );
+
+            // Achar frame físico correspondente no alvo
+            let phys = translate_addr_in_p4(target_cr3, vaddr).ok_or(KernelError::OutOfMemory)?;
+            unsafe {
+                let dst = crate::mm::physmap::phys_to_virt(PhysAddr::new(phys & !0xFFF))
+                    .as_mut_ptr::<u8>()
+                    .add(page_offset as usize);
+                copy_volatile(segment_data.as_ptr().add(bytes_copied), dst, bytes_to_copy);
             }
+
+            bytes_copied += bytes_to_copy;
         }
     }
 
-    crate::ktrace!("(ELF) Carregado com sucesso. Entrada:", ehdr.e_entry);
-    Ok(VirtAddr::new(ehdr.e_entry))
+    Ok(())
+}
+
+/// Carga sob demanda: registra a VMA com backing de arquivo; as páginas são
+/// populadas pelo page-fault handler no primeiro acesso.
+///
+/// Se a primeira página do segmento já pertence ao segmento anterior (ambos
+/// compartilham uma página), essa página é populada agora com os bytes dos
+/// dois segmentos e a VMA nova começa na página seguinte.
+#[cfg(feature = "elf_demand_paging")]
+fn load_segment_lazy(
+    phdr: &Elf64_Phdr,
+    image: &Arc<[u8]>,
+    aspace: &mut AddressSpace,
+) -> KernelResult<()> {
+    use crate::mm::aspace::vma::VmaBacking;
+    use crate::mm::fault::populate_file_page;
+
+    if phdr.p_offset.saturating_add(phdr.p_filesz) > image.len() as u64 {
+        crate::kerror!("(ELF) Segmento fora do arquivo:", phdr.p_offset);
+        return Err(KernelError::InvalidArgument);
+    }
+
+    let (prot, intent) = segment_protection(phdr);
+    let backing = VmaBacking::File {
+        image: image.clone(),
+        offset: phdr.p_offset,
+        file_size: phdr.p_filesz,
+        vaddr: phdr.p_vaddr,
+    };
+
+    let mut vmm_flags = MapFlags::PRESENT | MapFlags::USER;
+    if prot.can_write() {
+        vmm_flags |= MapFlags::WRITABLE;
+    }
+    if prot.can_exec() {
+        vmm_flags |= MapFlags::EXECUTABLE;
+    }
+
+    let mut start_page = phdr.p_vaddr & !(FRAME_SIZE - 1);
+    let end_page = (phdr.p_vaddr + phdr.p_memsz + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
+
+    let target_cr3 = aspace.cr3();
+
+    // Página compartilhada com o segmento anterior
+    if let Some(prev) = aspace.find_vma(VirtAddr::new(start_page)) {
+        crate::kdebug!("(ELF) Pagina compartilhada entre segmentos:", start_page);
+
+        // Mapeada com a união das permissões dos dois segmentos
+        let mut shared_flags = vmm_flags;
+        if prev.protection.can_write() {
+            shared_flags |= MapFlags::WRITABLE;
+        }
+        if prev.protection.can_exec() {
+            shared_flags |= MapFlags::EXECUTABLE;
+        }
+
+        let page = VirtAddr::new(start_page);
+        populate_file_page(target_cr3, page, &prev.backing, shared_flags)
+            .and_then(|_| populate_file_page(target_cr3, page, &backing, shared_flags))
+            .map_err(|_| KernelError::OutOfMemory)?;
+
+        start_page += FRAME_SIZE;
+    }
+
+    if start_page >= end_page {
+        return Ok(());
+    }
+
+    aspace
+        .map_region_backed(
+            Some(VirtAddr::new(start_page)),
+            (end_page - start_page) as usize,
+            prot,
+            VmaFlags::empty(),
+            intent,
+            backing,
+        )
+        .map_err(|e| {
+            crate::kerror!("(ELF) Falha fatal ao registrar VMA:", e as u64);
+            KernelError::OutOfMemory
+        })?;
+
+    crate::ktrace!("(ELF) VMA sob demanda registrada:", start_page);
+    Ok(())
 }
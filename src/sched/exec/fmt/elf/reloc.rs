@@ -0,0 +1,296 @@
+//! # Relocações dinâmicas
+//!
+//! Processa a tabela `DT_RELA` de executáveis PIE (`ET_DYN`).
+//! Apenas `R_X86_64_RELATIVE` (e `R_X86_64_NONE`) são suportadas: o loader não
+//! resolve símbolos.
+
+use super::structs::*;
+use crate::sys::{KernelError, KernelResult};
+
+/// Tabela RELA localizada no arquivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelaTable {
+    /// Offset da tabela dentro do arquivo
+    pub offset: usize,
+    /// Tamanho total em bytes
+    pub size: usize,
+    /// Tamanho de cada entrada
+    pub entsize: usize,
+}
+
+/// Lê um `T` (repr(C)) de `data[offset..]` sem exigir alinhamento
+fn read_at<T: Copy>(data: &[u8], offset: usize) -> KernelResult<T> {
+    let end = offset
+        .checked_add(core::mem::size_of::<T>())
+        .ok_or(KernelError::InvalidArgument)?;
+    if end > data.len() {
+        return Err(KernelError::InvalidArgument);
+    }
+    Ok(unsafe { core::ptr::read_unaligned(data.as_ptr().add(offset) as *const T) })
+}
+
+/// Converte um endereço virtual (sem bias) em offset no arquivo
+fn vaddr_to_offset(phdrs: &[Elf64_Phdr], vaddr: u64) -> Option<usize> {
+    phdrs.iter().filter(|p| p.p_type == PT_LOAD).find_map(|p| {
+        let delta = vaddr.checked_sub(p.p_vaddr).filter(|&d| d < p.p_filesz)?;
+        usize::try_from(p.p_offset.checked_add(delta)?).ok()
+    })
+}
+
+/// Procura `DT_RELA`/`DT_RELASZ`/`DT_RELAENT` no segmento `PT_DYNAMIC`.
+///
+/// Retorna `None` se o binário não tem segmento dinâmico ou tabela RELA.
+pub fn find_rela_table(data: &[u8], phdrs: &[Elf64_Phdr]) -> KernelResult<Option<RelaTable>> {
+    let dynamic = match phdrs.iter().find(|p| p.p_type == PT_DYNAMIC) {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+
+    let mut rela = None;
+    let mut relasz = 0usize;
+    let mut relaent = core::mem::size_of::<Elf64_Rela>();
+
+    // O cabeçalho não passou por validação nenhuma: o segmento tem de caber
+    // no arquivo
+    let dyn_end = dynamic
+        .p_offset
+        .checked_add(dynamic.p_filesz)
+        .ok_or(KernelError::InvalidArgument)?;
+    if dyn_end > data.len() as u64 {
+        return Err(KernelError::InvalidArgument);
+    }
+    let dyn_offset = dynamic.p_offset as usize;
+
+    let entry_size = core::mem::size_of::<Elf64_Dyn>();
+    let count = dynamic.p_filesz as usize / entry_size;
+    for i in 0..count {
+        let offset = i
+            .checked_mul(entry_size)
+            .and_then(|off| off.checked_add(dyn_offset))
+            .ok_or(KernelError::InvalidArgument)?;
+        let dyn_entry: Elf64_Dyn = read_at(data, offset)?;
+        match dyn_entry.d_tag {
+            DT_NULL => break,
+            DT_RELA => rela = Some(dyn_entry.d_val),
+            DT_RELASZ => relasz = dyn_entry.d_val as usize,
+            DT_RELAENT => relaent = dyn_entry.d_val as usize,
+            _ => {}
+        }
+    }
+
+    let rela_vaddr = match rela {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    if relaent < core::mem::size_of::<Elf64_Rela>() {
+        return Err(KernelError::InvalidArgument);
+    }
+    let offset = vaddr_to_offset(phdrs, rela_vaddr).ok_or(KernelError::InvalidArgument)?;
+
+    Ok(Some(RelaTable {
+        offset,
+        size: relasz,
+        entsize: relaent,
+    }))
+}
+
+/// Aplica as relocações da tabela.
+///
+/// `write(vaddr, value)` grava um u64 no endereço virtual já com bias.
+/// Tipos diferentes de `RELATIVE`/`NONE` resultam em `InvalidArgument`.
+pub fn apply_relocations<F>(
+    data: &[u8],
+    table: RelaTable,
+    bias: u64,
+    mut write: F,
+) -> KernelResult<usize>
+where
+    F: FnMut(u64, u64) -> KernelResult<()>,
+{
+    let count = table.size / table.entsize;
+    let mut applied = 0;
+
+    for i in 0..count {
+        let offset = table
+            .offset
+            .checked_add(i * table.entsize)
+            .ok_or(KernelError::InvalidArgument)?;
+        let rela: Elf64_Rela = read_at(data, offset)?;
+        match rela.r_type() {
+            R_X86_64_NONE => {}
+            R_X86_64_RELATIVE => {
+                let target = rela
+                    .r_offset
+                    .checked_add(bias)
+                    .ok_or(KernelError::InvalidArgument)?;
+                let value = bias.wrapping_add(rela.r_addend as u64);
+                write(target, value)?;
+                applied += 1;
+            }
+            other => {
+                crate::kerror!("(ELF) Relocacao nao suportada:", other as u64);
+                return Err(KernelError::InvalidArgument);
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn push_u64(buf: &mut Vec<u8>, v: u64) {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn rela(buf: &mut Vec<u8>, offset: u64, r_type: u32, addend: i64) {
+        push_u64(buf, offset);
+        push_u64(buf, r_type as u64);
+        push_u64(buf, addend as u64);
+    }
+
+    #[test]
+    fn test_relative_relocations() {
+        let mut data = Vec::new();
+        rela(&mut data, 0x2000, R_X86_64_RELATIVE, 0x1234);
+        rela(&mut data, 0x2008, R_X86_64_NONE, 0);
+        rela(&mut data, 0x2010, R_X86_64_RELATIVE, 0x10);
+
+        let table = RelaTable {
+            offset: 0,
+            size: data.len(),
+            entsize: core::mem::size_of::<Elf64_Rela>(),
+        };
+
+        let bias = 0x40_0000;
+        let mut writes = Vec::new();
+        let applied = apply_relocations(&data, table, bias, |addr, value| {
+            writes.push((addr, value));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(applied, 2);
+        assert_eq!(
+            writes,
+            alloc::vec![(0x40_2000, 0x40_1234), (0x40_2010, 0x40_0010)]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_relocation() {
+        let mut data = Vec::new();
+        rela(&mut data, 0x2000, 6 /* R_X86_64_GLOB_DAT */, 0);
+
+        let table = RelaTable {
+            offset: 0,
+            size: data.len(),
+            entsize: core::mem::size_of::<Elf64_Rela>(),
+        };
+        let result = apply_relocations(&data, table, 0x1000, |_, _| Ok(()));
+        assert_eq!(result, Err(KernelError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_relocation_target_overflow() {
+        let mut data = Vec::new();
+        rela(&mut data, u64::MAX - 4, R_X86_64_RELATIVE, 0);
+
+        let table = RelaTable {
+            offset: 0,
+            size: data.len(),
+            entsize: core::mem::size_of::<Elf64_Rela>(),
+        };
+        let result = apply_relocations(&data, table, 0x40_0000, |_, _| Ok(()));
+        assert_eq!(result, Err(KernelError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_find_rela_table() {
+        // [dynamic @0x00][rela @0x40]
+        let mut data = Vec::new();
+        for (tag, val) in [
+            (DT_RELA, 0x1040u64),
+            (DT_RELASZ, 24),
+            (DT_RELAENT, 24),
+            (DT_NULL, 0),
+        ] {
+            push_u64(&mut data, tag as u64);
+            push_u64(&mut data, val);
+        }
+        rela(&mut data, 0x1000, R_X86_64_RELATIVE, 0);
+
+        let load = Elf64_Phdr {
+            p_type: PT_LOAD,
+            p_flags: PF_R,
+            p_offset: 0,
+            p_vaddr: 0x1000,
+            p_paddr: 0,
+            p_filesz: data.len() as u64,
+            p_memsz: data.len() as u64,
+            p_align: 0x1000,
+        };
+        let dynamic = Elf64_Phdr {
+            p_type: PT_DYNAMIC,
+            p_filesz: 0x40,
+            ..load
+        };
+
+        let table = find_rela_table(&data, &[load, dynamic]).unwrap().unwrap();
+        assert_eq!(
+            table,
+            RelaTable {
+                offset: 0x40,
+                size: 24,
+                entsize: 24
+            }
+        );
+    }
+
+    #[test]
+    fn test_dynamic_outside_file() {
+        let data = alloc::vec![0u8; 0x40];
+        let dynamic = |p_offset, p_filesz| Elf64_Phdr {
+            p_type: PT_DYNAMIC,
+            p_flags: PF_R,
+            p_offset,
+            p_vaddr: 0,
+            p_paddr: 0,
+            p_filesz,
+            p_memsz: p_filesz,
+            p_align: 8,
+        };
+
+        // Além do fim do arquivo, e com p_offset + p_filesz estourando
+        for (offset, size) in [(0x30, 0x20), (u64::MAX - 0x8, 0x20), (0x10, u64::MAX)] {
+            assert_eq!(
+                find_rela_table(&data, &[dynamic(offset, size)]),
+                Err(KernelError::InvalidArgument)
+            );
+        }
+    }
+
+    #[test]
+    fn test_vaddr_to_offset_bounds() {
+        let load = Elf64_Phdr {
+            p_type: PT_LOAD,
+            p_flags: PF_R,
+            p_offset: u64::MAX - 0x10,
+            p_vaddr: u64::MAX - 0x100,
+            p_paddr: 0,
+            p_filesz: 0x1000,
+            p_memsz: 0x1000,
+            p_align: 0x1000,
+        };
+        assert_eq!(vaddr_to_offset(&[load], u64::MAX - 0x80), None);
+        assert_eq!(vaddr_to_offset(&[load], 0x1000), None);
+    }
+}
@@ -50,3 +50,44 @@ pub struct Elf64_Phdr {
     pub p_memsz: u64,
     pub p_align: u64,
 }
+
+/// Segmento com informações de linking dinâmico
+pub const PT_DYNAMIC: u32 = 2;
+
+/// Fim da tabela dinâmica
+pub const DT_NULL: i64 = 0;
+/// Endereço da tabela de relocações RELA
+pub const DT_RELA: i64 = 7;
+/// Tamanho total da tabela RELA em bytes
+pub const DT_RELASZ: i64 = 8;
+/// Tamanho de cada entrada RELA
+pub const DT_RELAENT: i64 = 9;
+
+/// Relocação nula
+pub const R_X86_64_NONE: u32 = 0;
+/// Relocação relativa à base de carga: B + A
+pub const R_X86_64_RELATIVE: u32 = 8;
+
+/// Entrada da seção dinâmica
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Elf64_Dyn {
+    pub d_tag: i64,
+    pub d_val: u64,
+}
+
+/// Entrada de relocação com addend
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Elf64_Rela {
+    pub r_offset: u64,
+    pub r_info: u64,
+    pub r_addend: i64,
+}
+
+impl Elf64_Rela {
+    /// Tipo da relocação (32 bits baixos de `r_info`)
+    pub fn r_type(&self) -> u32 {
+        (self.r_info & 0xFFFF_FFFF) as u32
+    }
+}
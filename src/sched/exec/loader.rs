@@ -1,11 +1,14 @@
 //! Criação de processos
 
+use super::stack::{self, StackError, AT_ENTRY, AT_PAGESZ};
 use crate::mm::pmm::{FRAME_ALLOCATOR, FRAME_SIZE};
 use crate::mm::vmm::MapFlags;
-use crate::mm::VirtAddr;
-use crate::sys::types::Pid;
+use crate::mm::{PhysAddr, VirtAddr};
+use crate::sched::task::Task;
+use crate::sys::types::{Pid, Tid};
 use crate::sys::KernelError;
 use alloc::boxed::Box;
+use core::pin::Pin;
 
 /// Erro de execução
 #[derive(Debug, Clone, Copy)]
@@ -14,6 +17,8 @@ pub enum ExecError {
     InvalidFormat,
     OutOfMemory,
     PermissionDenied,
+    /// argv/envp grandes demais ou com NUL no meio
+    InvalidArguments,
 }
 
 impl From<ExecError> for KernelError {
@@ -23,19 +28,53 @@ impl From<ExecError> for KernelError {
             ExecError::InvalidFormat => KernelError::InvalidArgument,
             ExecError::OutOfMemory => KernelError::OutOfMemory,
             ExecError::PermissionDenied => KernelError::PermissionDenied,
+            ExecError::InvalidArguments => KernelError::InvalidArgument,
         }
     }
 }
 
 // Use constantes do config
-use crate::sched::config::USER_STACK_SIZE;
+use crate::sched::config::{EXEC_ARGS_MAX, USER_STACK_SIZE};
 
 /// Topo da stack do userspace (final da metade inferior canônica)
 const USER_STACK_TOP: u64 = 0x7FFF_FFFF_F000;
 
-/// Cria novo processo a partir de executável
-pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pid, ExecError> {
-    crate::kinfo!("(Spawn) Spawning:", path.as_ptr() as u64);
+impl From<StackError> for ExecError {
+    fn from(_: StackError) -> Self {
+        ExecError::InvalidArguments
+    }
+}
+
+/// Cria novo processo a partir de executável e o enfileira
+///
+/// `argv` e `envp` vão para a stack inicial (ver [`stack`]); por convenção
+/// `argv[0]` é o caminho.
+pub fn spawn(
+    path: &str,
+    argv: &[&str],
+    envp: &[&str],
+    parent_id: Option<Tid>,
+) -> Result<Pid, ExecError> {
+    let task = create_process(path, argv, envp, parent_id)?;
+    let pid = Pid::new(task.tid.as_u32());
+
+    crate::sched::core::enqueue(task);
+
+    crate::kinfo!("Process spawned successfully! PID:", pid.as_u32() as u64);
+    Ok(pid)
+}
+
+/// Monta um processo pronto para rodar, sem enfileirá-lo
+///
+/// O processo tem `AddressSpace` próprio: o ELF, a stack de usuário e a
+/// stack de kernel são mapeados na PML4 dele, nunca nas tabelas ativas.
+pub fn create_process(
+    path: &str,
+    argv: &[&str],
+    envp: &[&str],
+    parent_id: Option<Tid>,
+) -> Result<Pin<Box<Task>>, ExecError> {
+    crate::kinfo!("(Spawn) Spawning:", path);
 
     // 1. Carregar arquivo via VFS (roteia para initramfs ou FAT)
     let data = match crate::fs::vfs::read_file(path) {
@@ -47,7 +86,7 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
     };
 
     // 2. Criar task
-    let mut task = crate::sched::task::Task::new(path);
+    let mut task = Task::new(path);
     task.parent_id = parent_id;
     let pid = Pid::new(task.tid.as_u32());
     let pid_u64 = pid.as_u32() as u64;
@@ -86,12 +125,8 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
                     )
                     .expect("(Spawn) Falha ao mapear KStack");
 
-                    // Zerar stack via HHDM (seguro com qualquer CR3)
-                    core::ptr::write_bytes(
-                        crate::mm::hhdm::phys_to_virt::<u8>(frame.as_u64()),
-                        0,
-                        FRAME_SIZE as usize,
-                    );
+                    // Zerar stack pela physmap (seguro com qualquer CR3)
+                    crate::mm::physmap::zero_frame(frame);
                 }
             }
         }
@@ -99,7 +134,7 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
     task.kernel_stack = VirtAddr::new(kstack_top);
 
     // 6. Carregar ELF (agora registra VMAs no aspace e mapeia via HHDM)
-    let entry_point = match crate::sched::exec::fmt::elf::load_binary(&data, &aspace) {
+    let entry_point = match crate::sched::exec::fmt::elf::load_binary(&data, &mut aspace.lock()) {
         Ok(addr) => addr,
         Err(_) => {
             return Err(ExecError::InvalidFormat);
@@ -125,33 +160,34 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
 
     let target_cr3 = aspace.lock().cr3();
 
-    // Alocar frames para a User Stack (via HHDM no alvo)
-    {
-        let mut pmm = FRAME_ALLOCATOR.lock();
-        for i in 0..(ustack_size as u64 / FRAME_SIZE) {
-            let vaddr = ustack_start + i * FRAME_SIZE;
-            if let Some(frame) = pmm.allocate_frame() {
-                unsafe {
-                    crate::mm::vmm::mapper::map_page_in_target_p4(
-                        target_cr3,
-                        vaddr,
-                        frame.as_u64(),
-                        MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::USER,
-                        &mut *pmm,
-                    )
-                    .expect("(Spawn) Falha ao mapear User Stack");
-
-                    // Zerar página no alvo via HHDM
-                    core::ptr::write_bytes(
-                        crate::mm::addr::phys_to_virt::<u8>(frame.as_u64()),
-                        0,
-                        FRAME_SIZE as usize,
-                    );
-                }
+    // Alocar frames para a User Stack (via HHDM no alvo). Frames de usuário
+    // vêm do PFM para nascerem com uma referência (ver `AddressSpace::fork`).
+    for i in 0..(ustack_size as u64 / FRAME_SIZE) {
+        let vaddr = ustack_start + i * FRAME_SIZE;
+        if let Ok(frame) = crate::mm::pfm::alloc_user_frame(pid_u64) {
+            let mut pmm = FRAME_ALLOCATOR.lock();
+            unsafe {
+                crate::mm::vmm::mapper::map_page_in_target_p4(
+                    target_cr3,
+                    vaddr,
+                    frame.as_u64(),
+                    MapFlags::PRESENT | MapFlags::WRITABLE | MapFlags::USER,
+                    &mut *pmm,
+                )
+                .expect("(Spawn) Falha ao mapear User Stack");
+
+                // Zerar página no alvo pela physmap
+                crate::mm::physmap::zero_frame(frame);
             }
         }
     }
     task.user_stack = VirtAddr::new(USER_STACK_TOP);
+
+    // Stack inicial: argc, argv, envp e auxv
+    let auxv = [(AT_PAGESZ, FRAME_SIZE), (AT_ENTRY, entry_point.as_u64())];
+    let image = stack::build(USER_STACK_TOP, argv, envp, &auxv, EXEC_ARGS_MAX)?;
+    write_to_target(target_cr3, image.sp, &image.bytes)?;
+
     // 8. Configurar Trap Frame na stack do kernel do ALVO via HHDM
     unsafe {
         const USER_CODE_SEL: u64 = 0x23; // Index 4, RPL 3
@@ -168,9 +204,10 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
             let phys_page = phys_top & !0xFFF;
             let offset = (kstack_top - 8) % FRAME_SIZE;
 
-            // Frame address via HHDM
-            let stack_top_hhdm =
-                crate::mm::addr::phys_to_virt::<u8>(phys_page).add(offset as usize + 8);
+            // Frame address via physmap
+            let stack_top_hhdm = crate::mm::physmap::phys_to_virt(PhysAddr::new(phys_page))
+                .as_mut_ptr::<u8>()
+                .add(offset as usize + 8);
 
             let frame_ptr = (stack_top_hhdm as u64
                 - core::mem::size_of::<ExceptionStackFrame>() as u64
@@ -179,13 +216,13 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
             (*frame_ptr).instruction_pointer = entry_point.as_u64();
             (*frame_ptr).code_segment = USER_CODE_SEL;
             (*frame_ptr).cpu_flags = RFLAGS_IF;
-            (*frame_ptr).stack_pointer = USER_STACK_TOP;
+            (*frame_ptr).stack_pointer = image.sp;
             (*frame_ptr).stack_segment = USER_DATA_SEL;
 
             let trampoline = crate::sched::core::entry::user_entry_stub as u64;
 
             // task.context.rsp deve ser o valor que o registrador RSP terá ANTES do salto.
-            // Após o salto (via jump_to_context_asm ou ret), o RSP estará em kstack_top.
+            // Após o salto (via task_bootstrap), o RSP estará em kstack_top.
             // O user_entry_stub então descerá 48 bytes para apontar ao TrapFrame.
             task.context.rsp = kstack_top - SWITCH_RESERVE;
             task.context.rip = trampoline;
@@ -194,12 +231,34 @@ pub fn spawn(path: &str, parent_id: Option<crate::sys::types::Tid>) -> Result<Pi
         }
     }
 
-    // 10. Enfileirar Task
+    if let Some(parent) = parent_id {
+        crate::sched::task::family::register(task.tid, parent);
+    }
+
     task.set_ready();
-    crate::sched::core::enqueue(alloc::boxed::Box::pin(task));
+    Ok(Box::pin(task))
+}
 
-    crate::kinfo!("Process spawned successfully! PID:", pid.as_u32() as u64);
-    Ok(pid)
+/// Copia `bytes` para `vaddr` em um address space alvo, pela janela HHDM
+/// dos frames já mapeados na PML4 `cr3`.
+fn write_to_target(cr3: u64, vaddr: u64, bytes: &[u8]) -> Result<(), ExecError> {
+    let mut done = 0usize;
+    while done < bytes.len() {
+        let addr = vaddr + done as u64;
+        let page_offset = addr % FRAME_SIZE;
+        let len = core::cmp::min(bytes.len() - done, (FRAME_SIZE - page_offset) as usize);
+
+        let phys = crate::mm::vmm::mapper::translate_addr_in_p4(cr3, addr)
+            .ok_or(ExecError::OutOfMemory)?;
+        unsafe {
+            let dst = crate::mm::physmap::phys_to_virt(PhysAddr::new(phys)).as_mut_ptr::<u8>();
+            for i in 0..len {
+                core::ptr::write_volatile(dst.add(i), bytes[done + i]);
+            }
+        }
+        done += len;
+    }
+    Ok(())
 }
 
 /// Função de teste para validar troca de contexto
@@ -2,4 +2,8 @@
 
 pub mod fmt;
 pub mod loader;
-pub use loader::{spawn, ExecError};
+pub mod stack;
+pub use loader::{create_process, spawn, ExecError};
+
+#[cfg(feature = "self_test")]
+pub mod test;
@@ -0,0 +1,174 @@
+//! Stack inicial do usuário
+//!
+//! Layout da System V AMD64 ABI (§3.4.1), do RSP inicial para cima:
+//!
+//! ```text
+//! RSP ->  argc
+//!         argv[0] .. argv[argc - 1], NULL
+//!         envp[0] .. envp[n - 1], NULL
+//!         auxv: pares (tipo, valor) .. (AT_NULL, 0)
+//!         padding
+//!         strings de argv e envp (terminadas em NUL), alinhadas a 16
+//! top  -> (fim da stack)
+//! ```
+//!
+//! O RSP fica alinhado a 16, então o `_start` do runtime lê `argc` em
+//! `[rsp]` e `argv` em `rsp + 8`.
+
+use alloc::vec::Vec;
+
+/// Fim do vetor auxiliar
+pub const AT_NULL: u64 = 0;
+/// Endereço dos program headers
+pub const AT_PHDR: u64 = 3;
+/// Tamanho de um program header
+pub const AT_PHENT: u64 = 4;
+/// Número de program headers
+pub const AT_PHNUM: u64 = 5;
+/// Tamanho da página
+pub const AT_PAGESZ: u64 = 6;
+/// Entry point do executável
+pub const AT_ENTRY: u64 = 9;
+
+/// Erro ao montar a stack inicial
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    /// A imagem passa de `max_size`
+    TooLarge,
+    /// String com NUL no meio (seria truncada pelo usuário)
+    InteriorNul,
+}
+
+/// Conteúdo de `[sp, top)` pronto para ser copiado para a stack do usuário
+#[derive(Debug)]
+pub struct StackImage {
+    /// RSP inicial (alinhado a 16)
+    pub sp: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Monta a stack inicial logo abaixo de `top`.
+///
+/// `auxv` não inclui o `AT_NULL` final. Falha se a imagem passar de
+/// `max_size` bytes.
+pub fn build(
+    top: u64,
+    argv: &[&str],
+    envp: &[&str],
+    auxv: &[(u64, u64)],
+    max_size: usize,
+) -> Result<StackImage, StackError> {
+    if argv.iter().chain(envp).any(|s| s.as_bytes().contains(&0)) {
+        return Err(StackError::InteriorNul);
+    }
+
+    let strings_size: usize = argv.iter().chain(envp).map(|s| s.len() + 1).sum();
+    let words = 1 + (argv.len() + 1) + (envp.len() + 1) + 2 * (auxv.len() + 1);
+    let size = strings_size
+        .checked_add(15)
+        .and_then(|s| s.checked_add(words.checked_mul(8)?))
+        .and_then(|s| s.checked_add(15))
+        .ok_or(StackError::TooLarge)?;
+    if size > max_size || size as u64 > top {
+        return Err(StackError::TooLarge);
+    }
+
+    let strings_start = (top - strings_size as u64) & !0xF;
+    let sp = (strings_start - words as u64 * 8) & !0xF;
+
+    let mut bytes = alloc::vec![0u8; (top - sp) as usize];
+    let mut words_out = Vec::with_capacity(words);
+    words_out.push(argv.len() as u64);
+
+    // Strings: argv e depois envp, a partir de strings_start
+    let mut cursor = strings_start;
+    for list in [argv, envp] {
+        for s in list {
+            let offset = (cursor - sp) as usize;
+            bytes[offset..offset + s.len()].copy_from_slice(s.as_bytes());
+            words_out.push(cursor);
+            cursor += s.len() as u64 + 1;
+        }
+        words_out.push(0);
+    }
+
+    for &(kind, value) in auxv {
+        words_out.push(kind);
+        words_out.push(value);
+    }
+    words_out.extend_from_slice(&[AT_NULL, 0]);
+
+    for (i, word) in words_out.iter().enumerate() {
+        bytes[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+
+    Ok(StackImage { sp, bytes })
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP: u64 = 0x7FFF_FFFF_F000;
+
+    fn word(image: &StackImage, index: usize) -> u64 {
+        let at = index * 8;
+        u64::from_le_bytes(image.bytes[at..at + 8].try_into().unwrap())
+    }
+
+    fn c_str(image: &StackImage, addr: u64) -> &str {
+        let start = (addr - image.sp) as usize;
+        let len = image.bytes[start..].iter().position(|&b| b == 0).unwrap();
+        core::str::from_utf8(&image.bytes[start..start + len]).unwrap()
+    }
+
+    #[test]
+    fn test_initial_stack_layout() {
+        let image = build(
+            TOP,
+            &["/bin/init", "-v"],
+            &["HOME=/"],
+            &[(AT_PAGESZ, 4096), (AT_ENTRY, 0x40_1000)],
+            4096,
+        )
+        .unwrap();
+
+        assert_eq!(image.sp % 16, 0);
+        assert_eq!(image.sp + image.bytes.len() as u64, TOP);
+
+        // argc, argv[2] + NULL, envp[1] + NULL
+        assert_eq!(word(&image, 0), 2);
+        assert_eq!(c_str(&image, word(&image, 1)), "/bin/init");
+        assert_eq!(c_str(&image, word(&image, 2)), "-v");
+        assert_eq!(word(&image, 3), 0);
+        assert_eq!(c_str(&image, word(&image, 4)), "HOME=/");
+        assert_eq!(word(&image, 5), 0);
+
+        // auxv
+        assert_eq!((word(&image, 6), word(&image, 7)), (AT_PAGESZ, 4096));
+        assert_eq!((word(&image, 8), word(&image, 9)), (AT_ENTRY, 0x40_1000));
+        assert_eq!((word(&image, 10), word(&image, 11)), (AT_NULL, 0));
+    }
+
+    #[test]
+    fn test_initial_stack_limits() {
+        let long = "x".repeat(200);
+        assert_eq!(
+            build(TOP, &[&long], &[], &[], 128).unwrap_err(),
+            StackError::TooLarge
+        );
+        assert_eq!(
+            build(TOP, &["a\0b"], &[], &[], 4096).unwrap_err(),
+            StackError::InteriorNul
+        );
+
+        // Sem argumentos: só argc e os terminadores
+        let image = build(TOP, &[], &[], &[], 4096).unwrap();
+        assert_eq!(word(&image, 0), 0);
+        assert_eq!(image.bytes.len(), 48);
+    }
+}
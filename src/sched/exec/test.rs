@@ -0,0 +1,38 @@
+//! # Testes de exec
+//!
+//! Executados apenas com a feature `self_test`, depois do InitRAMFS (usam o
+//! binário do init).
+
+use super::create_process;
+
+/// Executável presente em todo InitRAMFS
+const TEST_BINARY: &str = "/system/core/supervisor";
+
+pub fn run_tests() {
+    crate::kinfo!("(Exec) Iniciando testes de exec...");
+    test_spawn_isolated_address_spaces();
+    crate::kinfo!("(Exec) Testes de exec concluídos com SUCESSO.");
+}
+
+/// Dois processos do mesmo binário têm PML4s próprias, diferentes entre si
+/// e da ativa.
+fn test_spawn_isolated_address_spaces() {
+    let first = create_process(TEST_BINARY, &[TEST_BINARY], &[], None)
+        .expect("(Exec) Falha ao criar o primeiro processo");
+    let second = create_process(TEST_BINARY, &[TEST_BINARY, "--test"], &[], None)
+        .expect("(Exec) Falha ao criar o segundo processo");
+
+    let cr3 = |task: &crate::sched::task::Task| {
+        task.aspace
+            .as_ref()
+            .expect("(Exec) Processo sem address space")
+            .lock()
+            .cr3()
+    };
+    let (cr3_a, cr3_b) = (cr3(&first), cr3(&second));
+    let active = crate::arch::x86_64::cpu::Cpu::read_cr3() & crate::mm::config::PAGE_MASK;
+
+    assert_ne!(cr3_a, cr3_b, "(Exec) Processos compartilham a PML4");
+    assert_ne!(cr3_a, active, "(Exec) Processo usa a PML4 ativa");
+    assert_ne!(cr3_b, active, "(Exec) Processo usa a PML4 ativa");
+}
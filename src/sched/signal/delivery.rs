@@ -1,58 +1,265 @@
 //! Entrega de Sinais
+//!
+//! Os sinais pendentes são tratados no retorno para user mode: no fim de
+//! cada syscall (`syscall_dispatcher`) e na saída do timer e das exceções
+//! (`signal_return_to_user`, chamado por interrupts.s). Os dois caminhos têm
+//! um `TrapFrame` completo na stack do kernel, que é o contexto que volta
+//! para o usuário.
+//!
+//! Um handler registrado recebe um [`SignalFrame`] na stack do usuário com
+//! o contexto interrompido e a máscara anterior. O handler é chamado com
+//! `RDI = signum` e retorna para o `restorer` da `SigAction`, que chama
+//! `SYS_SIGRETURN`; [`sigreturn`] restaura o contexto salvo.
 
-use super::{SIGCONT, SIGKILL, SIGSTOP};
-use crate::sched::task::Task;
-use crate::sched::task::TaskState;
+use core::mem::size_of;
+use core::pin::Pin;
 
-/// Verifica e processa sinais pendentes para a tarefa atuaL.
+use super::handler::SignalDisposition;
+use super::{pending, SIGCONT, SIGKILL, SIGSEGV, SIGSTOP};
+use crate::arch::x86_64::syscall::TrapFrame;
+use crate::mm::fault::AccessType;
+use crate::mm::VirtAddr;
+use crate::sched::core::CURRENT;
+use crate::sched::WaitQueue;
+use crate::sys::types::Tid;
+use crate::syscall::uaccess::{validate_user_buffer, UserAccessGuard, USER_SPACE_END};
+
+/// Red zone da System V ABI: abaixo do RSP, ainda em uso pelo código
+/// interrompido
+const RED_ZONE: u64 = 128;
+
+/// Sinais que não podem ser bloqueados
+const UNBLOCKABLE: u64 = (1 << SIGKILL) | (1 << SIGSTOP);
+
+/// Sinais que retomam uma task parada
+const RESUME: u64 = (1 << SIGCONT) | (1 << SIGKILL);
+
+/// RFLAGS que o usuário controla no sigreturn (CF, PF, AF, ZF, SF, TF, DF, OF)
+const USER_RFLAGS: u64 = 0x0DD5;
+const RFLAGS_RESERVED: u64 = 1 << 1;
+const RFLAGS_TF: u64 = 1 << 8;
+const RFLAGS_IF: u64 = 1 << 9;
+const RFLAGS_DF: u64 = 1 << 10;
+
+/// Tasks paradas por SIGSTOP/SIGTSTP, até SIGCONT ou SIGKILL
+static STOPPED: WaitQueue = WaitQueue::new();
+
+/// Frame empilhado na stack do usuário para um handler
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SignalFrame {
+    /// Endereço de retorno do handler (`SigAction::restorer`)
+    pub restorer: u64,
+    pub signum: u64,
+    /// `blocked_signals` antes do handler
+    pub saved_mask: u64,
+    /// Contexto interrompido
+    pub context: TrapFrame,
+}
+
+/// Sinal retirado para entrega
+struct Next {
+    signum: i32,
+    action: SignalDisposition,
+    restorer: u64,
+    saved_mask: u64,
+}
+
+/// Máscara efetiva: SIGKILL e SIGSTOP nunca ficam bloqueados
+pub fn blocked_mask(blocked: u64) -> u64 {
+    blocked & !UNBLOCKABLE
+}
+
+/// Entrega no retorno do timer e das exceções (interrupts.s), com o
+/// `TrapFrame` montado na stack do kernel.
+///
+/// # Safety
+/// `frame` aponta para o `TrapFrame` da interrupção em curso, exclusivo
+/// deste caminho até o `iretq`.
+#[no_mangle]
+pub unsafe extern "C" fn signal_return_to_user(frame: *mut TrapFrame) {
+    deliver_pending(&mut *frame);
+}
+
+/// Trata os sinais pendentes da task atual antes de `frame` voltar para
+/// user mode.
 ///
-/// Deve ser chamado no retorno de interrupções/syscalls (antes de voltar pro user).
-pub fn process_pending_signals(task: &mut Task) {
-    if task.pending_signals == 0 {
+/// Sinais ignorados são descartados; a ação padrão termina ou para a task;
+/// um handler redireciona `frame` para ele (um handler por retorno). Sai
+/// com interrupções desligadas, como os caminhos de retorno esperam.
+pub fn deliver_pending(frame: &mut TrapFrame) {
+    if frame.cs & 3 != 3 {
         return;
     }
 
-    // Iterar sinais (bitmask)
-    for sig in 1..32 {
-        if (task.pending_signals & (1 << sig)) != 0 {
-            // Limpar bit
-            task.pending_signals &= !(1 << sig);
-
-            deliver_signal(task, sig);
+    while let Some(next) = next_signal() {
+        match next.action {
+            SignalDisposition::Ignore | SignalDisposition::Continue => {}
+            SignalDisposition::Stop => stop_current(),
+            SignalDisposition::Terminate => terminate(next.signum, frame, false),
+            SignalDisposition::Core => terminate(next.signum, frame, true),
+            SignalDisposition::Handler(handler) => {
+                if !setup_frame(frame, &next, handler) {
+                    crate::kerror!("(Signal) Sem stack para o handler do sinal:", next.signum);
+                    terminate(SIGSEGV, frame, true);
+                }
+                break;
+            }
         }
     }
+
+    crate::arch::Cpu::disable_interrupts();
 }
 
-/// Entrega um sinal específico
-fn deliver_signal(task: &mut Task, signum: i32) {
-    // TODO: Consultar SignalHandlers da Task (quando implementado na struct)
-    // Por enquanto, implementação hardcoded básica
+/// Retira o próximo sinal entregável da task atual (o de menor número)
+fn next_signal() -> Option<Next> {
+    let mut current = CURRENT.lock();
+    let task = current.as_mut()?;
+    let task = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
 
-    match signum {
-        SIGKILL => {
-            crate::kinfo!("(Signal) Task recebeu SIGKILL. Terminando.");
-            // Força exit (simulado)
-            // Na prática, marcaria flag de exit e schedule() limparia depois.
-            // Impossível chamar exit daqui se não tiver ownership do scheduler.
-            // Apenas marcamos o exit_code e estado por enquanto.
-            task.state = TaskState::Zombie;
-            task.exit_code = Some(128 + SIGKILL);
-        }
-        SIGSTOP => {
-            crate::kinfo!("(Signal) Task recebeu SIGSTOP. Parando.");
-            task.state = TaskState::Stopped;
-            // Scheduler vai precisar ignorar tarefas Stopped
-        }
-        SIGCONT => {
-            if task.state == TaskState::Stopped {
-                task.state = TaskState::Ready;
-            }
-        }
-        _ => {
-            crate::kinfo!(
-                "(Signal) Sinal ignorado (não implementado handler):",
-                signum as u64
-            );
-        }
+    task.pending_signals |= pending::take(task.tid);
+    let deliverable = task.pending_signals & !blocked_mask(task.blocked_signals) & !1;
+    if deliverable == 0 {
+        return None;
+    }
+
+    let signum = deliverable.trailing_zeros() as i32;
+    task.pending_signals &= !(1 << signum);
+
+    let action = task.signal_handlers.get_action(signum);
+    let restorer = task.signal_handlers.get(signum).map_or(0, |a| a.restorer);
+    let saved_mask = task.blocked_signals;
+    if let SignalDisposition::Handler(_) = action {
+        // O sinal fica bloqueado enquanto o handler roda
+        task.blocked_signals |= 1 << signum;
+    }
+
+    Some(Next {
+        signum,
+        action,
+        restorer,
+        saved_mask,
+    })
+}
+
+/// Termina a task atual por `signum` (status `128 + signum`)
+fn terminate(signum: i32, frame: &TrapFrame, core: bool) -> ! {
+    if core {
+        crate::kerror!("(Signal) Task terminada pelo sinal:", signum);
+        crate::kerror!("(Signal) RIP:", frame.rip);
+        crate::kerror!("(Signal) RSP:", frame.rsp);
+    } else {
+        crate::kinfo!("(Signal) Task terminada pelo sinal:", signum);
+    }
+    crate::sched::core::exit_current(128 + signum)
+}
+
+/// Para a task atual até chegar SIGCONT ou SIGKILL
+fn stop_current() {
+    let Some(tid) = current_tid() else {
+        return;
+    };
+    crate::kinfo!("(Signal) Task parada:", tid.as_u32());
+
+    while pending::peek(tid) & RESUME == 0 {
+        STOPPED.wait_if(|| pending::peek(tid) & RESUME == 0);
+    }
+}
+
+/// Acorda `tid` se ela estiver parada
+pub fn resume_stopped(tid: Tid) -> bool {
+    STOPPED.wake_tid(tid)
+}
+
+fn current_tid() -> Option<Tid> {
+    CURRENT.lock().as_ref().map(|task| task.tid)
+}
+
+/// Empilha um [`SignalFrame`] e redireciona `frame` para `handler`.
+///
+/// Retorna `false` sem restorer (o handler não teria para onde voltar) ou
+/// se a stack do usuário não comporta o frame.
+fn setup_frame(frame: &mut TrapFrame, next: &Next, handler: u64) -> bool {
+    if next.restorer == 0 {
+        return false;
+    }
+
+    // Abaixo da red zone, com RSP ≡ 8 (mod 16) na entrada do handler, como
+    // depois de um `call`
+    let size = size_of::<SignalFrame>() as u64;
+    let Some(base) = frame.rsp.checked_sub(RED_ZONE + size) else {
+        return false;
+    };
+    let sp = (base & !0xF).wrapping_sub(8);
+    if !user_stack_writable(sp, size) {
+        return false;
+    }
+
+    let signal_frame = SignalFrame {
+        restorer: next.restorer,
+        signum: next.signum as u64,
+        saved_mask: next.saved_mask,
+        context: *frame,
+    };
+    // SAFETY: intervalo validado contra as VMAs da task; o CR3 ativo é o
+    // dela e faltas em páginas ainda não populadas são resolvidas
+    unsafe {
+        let _uaccess = UserAccessGuard::new();
+        (sp as *mut SignalFrame).write(signal_frame);
+    }
+
+    frame.rip = handler;
+    frame.rsp = sp;
+    frame.rdi = next.signum as u64;
+    frame.rsi = 0;
+    frame.rdx = 0;
+    frame.rflags &= !(RFLAGS_TF | RFLAGS_DF);
+    true
+}
+
+/// `[addr, addr + len)` cabe na stack do usuário? Um frame logo abaixo da
+/// stack a estende, como uma falta estenderia.
+fn user_stack_writable(addr: u64, len: u64) -> bool {
+    if validate_user_buffer(addr as usize, len as usize, AccessType::Write).is_ok() {
+        return true;
+    }
+    let aspace = CURRENT.lock().as_ref().and_then(|task| task.aspace.clone());
+    if let Some(aspace) = aspace {
+        let _ = aspace.lock().grow_stack(VirtAddr::new(addr));
+    }
+    validate_user_buffer(addr as usize, len as usize, AccessType::Write).is_ok()
+}
+
+/// `SYS_SIGRETURN`: restaura o contexto salvo por um handler.
+///
+/// O handler retornou para o restorer (o `ret` consumiu o endereço de
+/// retorno), então o [`SignalFrame`] começa 8 bytes abaixo do RSP. Segmentos
+/// e flags privilegiadas de RFLAGS não vêm do usuário.
+pub fn sigreturn(frame: &mut TrapFrame) {
+    let addr = frame.rsp.wrapping_sub(8);
+    let size = size_of::<SignalFrame>();
+    if validate_user_buffer(addr as usize, size, AccessType::Read).is_err() {
+        crate::kerror!("(Signal) sigreturn com frame inválido:", addr);
+        terminate(SIGSEGV, frame, true);
+    }
+
+    // SAFETY: intervalo validado contra as VMAs da task
+    let saved = unsafe {
+        let _uaccess = UserAccessGuard::new();
+        (addr as *const SignalFrame).read_unaligned()
+    };
+    let mut context = saved.context;
+    if context.rip >= USER_SPACE_END as u64 || context.rsp >= USER_SPACE_END as u64 {
+        crate::kerror!("(Signal) sigreturn com contexto inválido:", context.rip);
+        terminate(SIGSEGV, frame, true);
+    }
+    context.cs = frame.cs;
+    context.ss = frame.ss;
+    context.rflags = (context.rflags & USER_RFLAGS) | RFLAGS_IF | RFLAGS_RESERVED;
+    *frame = context;
+
+    if let Some(task) = CURRENT.lock().as_mut() {
+        let task = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+        task.blocked_signals = blocked_mask(saved.saved_mask);
     }
 }
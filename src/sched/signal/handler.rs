@@ -1,5 +1,7 @@
 //! Registro de Signal Handlers
 
+use super::*;
+
 /// Ação padrão para um sinal
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SignalDisposition {
@@ -11,32 +13,144 @@ pub enum SignalDisposition {
     Handler(u64), // Endereço função user-space
 }
 
+/// `handler` de [`SigAction`]: ação padrão do sinal
+pub const SIG_DFL: u64 = 0;
+/// `handler` de [`SigAction`]: sinal ignorado
+pub const SIG_IGN: u64 = 1;
+
+/// Ação registrada por uma task para um sinal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigAction {
+    /// `SIG_DFL`, `SIG_IGN` ou endereço do handler em user space
+    pub handler: u64,
+    pub flags: u64,
+    /// Código em user space que chama `SYS_SIGRETURN` quando o handler
+    /// retorna (o handler é chamado com ele como endereço de retorno)
+    pub restorer: u64,
+}
+
+impl SigAction {
+    pub const DEFAULT: Self = Self {
+        handler: SIG_DFL,
+        flags: 0,
+        restorer: 0,
+    };
+}
+
+/// Ação padrão de cada sinal (POSIX). `Core` termina como `Terminate`, com
+/// diagnóstico; não há core dump.
+pub const fn default_action(signum: i32) -> SignalDisposition {
+    match signum {
+        SIGCHLD => SignalDisposition::Ignore,
+        SIGCONT => SignalDisposition::Continue,
+        SIGSTOP | SIGTSTP => SignalDisposition::Stop,
+        // SIGTTIN, SIGTTOU
+        21 | 22 => SignalDisposition::Stop,
+        // SIGURG, SIGWINCH
+        23 | 28 => SignalDisposition::Ignore,
+        SIGQUIT | SIGILL | SIGTRAP | SIGABRT | SIGBUS | SIGFPE | SIGSEGV => SignalDisposition::Core,
+        // SIGXCPU, SIGXFSZ, SIGSYS
+        24 | 25 | 31 => SignalDisposition::Core,
+        _ => SignalDisposition::Terminate,
+    }
+}
+
 /// Tabela de ações para sinais
 pub struct SignalHandlers {
-    actions: [SignalDisposition; 32],
+    actions: [SigAction; NSIG],
 }
 
 impl SignalHandlers {
     pub const fn new() -> Self {
         Self {
-            actions: [SignalDisposition::Terminate; 32], // Default é Terminate para maioria (simplificado)
+            actions: [SigAction::DEFAULT; NSIG],
         }
     }
 
+    /// O que fazer com `signum`, já resolvendo `SIG_DFL`/`SIG_IGN`
     pub fn get_action(&self, signum: i32) -> SignalDisposition {
-        if signum > 0 && signum < 32 {
-            self.actions[signum as usize]
+        if signum <= 0 || signum as usize >= NSIG {
+            return SignalDisposition::Ignore;
+        }
+        match self.actions[signum as usize].handler {
+            SIG_DFL => default_action(signum),
+            SIG_IGN => SignalDisposition::Ignore,
+            addr => SignalDisposition::Handler(addr),
+        }
+    }
+
+    /// Ação registrada para `signum`
+    pub fn get(&self, signum: i32) -> Option<SigAction> {
+        if signum > 0 && (signum as usize) < NSIG {
+            Some(self.actions[signum as usize])
         } else {
-            SignalDisposition::Ignore
+            None
         }
     }
 
-    pub fn set_action(&mut self, signum: i32, action: SignalDisposition) {
-        if signum > 0 && signum < 32 {
+    /// Registra `action` para `signum`. Retorna `false` para sinal inválido.
+    pub fn set_action(&mut self, signum: i32, action: SigAction) -> bool {
+        if signum > 0 && (signum as usize) < NSIG {
             // SIGKILL e SIGSTOP não podem ser capturados/ignorados
-            if signum != super::SIGKILL && signum != super::SIGSTOP {
+            if signum != SIGKILL && signum != SIGSTOP {
                 self.actions[signum as usize] = action;
+                return true;
             }
         }
+        false
+    }
+}
+
+impl Default for SignalHandlers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_dispositions() {
+        let handlers = SignalHandlers::new();
+        assert_eq!(handlers.get_action(SIGKILL), SignalDisposition::Terminate);
+        assert_eq!(handlers.get_action(SIGSEGV), SignalDisposition::Core);
+        assert_eq!(handlers.get_action(SIGCHLD), SignalDisposition::Ignore);
+        assert_eq!(handlers.get_action(SIGSTOP), SignalDisposition::Stop);
+        assert_eq!(handlers.get_action(SIGCONT), SignalDisposition::Continue);
+        assert_eq!(handlers.get_action(0), SignalDisposition::Ignore);
+    }
+
+    #[test]
+    fn test_kill_and_stop_stay_default() {
+        let mut handlers = SignalHandlers::new();
+        let action = SigAction {
+            handler: 0x40_1000,
+            flags: 0,
+            restorer: 0x40_2000,
+        };
+
+        assert!(!handlers.set_action(SIGKILL, action));
+        assert!(!handlers.set_action(SIGSTOP, action));
+        assert_eq!(handlers.get_action(SIGKILL), SignalDisposition::Terminate);
+
+        assert!(handlers.set_action(SIGUSR1, action));
+        assert_eq!(
+            handlers.get_action(SIGUSR1),
+            SignalDisposition::Handler(0x40_1000)
+        );
+        handlers.set_action(
+            SIGUSR1,
+            SigAction {
+                handler: SIG_IGN,
+                ..action
+            },
+        );
+        assert_eq!(handlers.get_action(SIGUSR1), SignalDisposition::Ignore);
     }
 }
@@ -1,9 +1,19 @@
 //! Gerenciamento de Sinais POSIX-like
 //!
 //! Submódulos para entrega e manipulação de sinais.
+//!
+//! [`send`] posta o sinal para a task ([`pending`]) e acorda uma espera
+//! interrompível dela; a entrega acontece quando a própria task volta para
+//! user mode ([`delivery`]).
 
 pub mod delivery;
 pub mod handler;
+pub mod pending;
+
+use crate::sys::types::Tid;
+
+/// Número de sinais (1..NSIG)
+pub const NSIG: usize = 32;
 
 /// Standard Signals
 pub const SIGHUP: i32 = 1;
@@ -25,3 +35,78 @@ pub const SIGCHLD: i32 = 17;
 pub const SIGCONT: i32 = 18;
 pub const SIGSTOP: i32 = 19;
 pub const SIGTSTP: i32 = 20;
+
+/// Erros de envio de sinal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalError {
+    InvalidSignal,
+    NoSuchTask,
+}
+
+/// Envia `signum` para a task `tid`.
+///
+/// O sinal fica postado até a task voltar para user mode. Uma espera
+/// interrompível da task é acordada (retorna `Interrupted`); SIGKILL
+/// termina na hora uma task dormindo, e SIGCONT/SIGKILL retomam uma task
+/// parada.
+pub fn send(tid: Tid, signum: i32) -> Result<(), SignalError> {
+    if signum <= 0 || signum as usize >= NSIG {
+        return Err(SignalError::InvalidSignal);
+    }
+    if !crate::sched::task::entity::tid_allocated(tid) {
+        return Err(SignalError::NoSuchTask);
+    }
+    if let Some(info) = crate::sched::core::task_info(tid) {
+        if info.state == crate::sched::task::TaskState::Zombie {
+            return Ok(());
+        }
+    }
+
+    if signum == SIGKILL && crate::sched::core::kill_sleeping(tid, 128 + SIGKILL) {
+        return Ok(());
+    }
+
+    pending::post(tid, signum);
+    if signum == SIGKILL || signum == SIGCONT {
+        delivery::resume_stopped(tid);
+    }
+    crate::sched::sync::waitqueue::interrupt(tid);
+    Ok(())
+}
+
+/// Há sinal entregável (pendente e não bloqueado) para a task atual `tid`?
+///
+/// Usado pelas esperas interrompíveis.
+pub fn has_pending(tid: Tid) -> bool {
+    let posted = pending::peek(tid);
+    let current = crate::sched::core::CURRENT.lock();
+    match current.as_ref() {
+        Some(task) if task.tid == tid => {
+            (posted | task.pending_signals) & !delivery::blocked_mask(task.blocked_signals) != 0
+        }
+        _ => posted != 0,
+    }
+}
+
+/// Marca `signum` como pendente na task atual.
+///
+/// Usado pelos handlers de exceção (SIGSEGV, SIGILL, SIGFPE): o sinal é
+/// síncrono, então se estiver bloqueado ou ignorado volta à ação padrão
+/// (senão a instrução falharia de novo para sempre). Não trava se `CURRENT`
+/// já estiver travado (falta dentro do scheduler): o sinal se perde.
+pub fn raise_current(signum: i32) {
+    if signum <= 0 || signum as usize >= NSIG {
+        return;
+    }
+    if let Some(mut current) = crate::sched::core::CURRENT.try_lock() {
+        if let Some(task) = current.as_mut() {
+            let task = unsafe { core::pin::Pin::get_unchecked_mut(task.as_mut()) };
+            task.pending_signals |= 1 << signum;
+            task.blocked_signals &= !(1 << signum);
+            if task.signal_handlers.get_action(signum) == handler::SignalDisposition::Ignore {
+                task.signal_handlers
+                    .set_action(signum, handler::SigAction::DEFAULT);
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Sinais postados para tasks fora de `CURRENT`
+//!
+//! Uma task na RunQueue, dormindo ou numa WaitQueue pertence à fila onde
+//! está, e quem envia o sinal não tem acesso a ela. O sinal fica aqui, por
+//! TID, até a própria task juntá-lo a `Task::pending_signals` (no retorno
+//! para user mode ou ao checar uma espera interrompível).
+
+use alloc::collections::BTreeMap;
+
+use crate::sync::Spinlock;
+use crate::sys::types::Tid;
+
+static POSTED: Spinlock<BTreeMap<u32, u64>> = Spinlock::new(BTreeMap::new());
+
+/// Marca `signum` como postado para `tid`
+pub fn post(tid: Tid, signum: i32) {
+    *POSTED.lock().entry(tid.as_u32()).or_insert(0) |= 1 << signum;
+}
+
+/// Retira os sinais postados para `tid`
+pub fn take(tid: Tid) -> u64 {
+    POSTED.lock().remove(&tid.as_u32()).unwrap_or(0)
+}
+
+/// Sinais postados para `tid`, sem retirá-los
+pub fn peek(tid: Tid) -> u64 {
+    POSTED.lock().get(&tid.as_u32()).copied().unwrap_or(0)
+}
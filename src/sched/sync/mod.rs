@@ -1,4 +1,6 @@
 //! Wait queues and synchronization
 
+pub mod poll;
 pub mod waitqueue;
-pub use waitqueue::WaitQueue;
+pub use poll::{PollEntry, Pollable};
+pub use waitqueue::{TimedOut, WaitQueue};
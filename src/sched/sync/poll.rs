@@ -0,0 +1,128 @@
+//! Prontidão de objetos para `poll`
+//!
+//! Cada objeto que pode ser esperado (portas, pipes, dispositivos de
+//! caractere) implementa [`Pollable`], informando quais eventos de
+//! `poll_events` (`IN`, `OUT`, `HUP`, ...) estão prontos agora.
+//!
+//! ## Espera
+//! Uma task só fica em uma `WaitQueue` por vez, então `poll` não pode
+//! bloquear nas filas de cada objeto. Todos esperam em uma fila global,
+//! [`POLL_WAIT`], e todo objeto cuja prontidão muda chama [`notify`] depois
+//! de alterar o estado (e soltar o próprio lock). A checagem que decide
+//! bloquear roda sob o lock da fila (`wait_if`), então um `notify` entre a
+//! checagem e o bloqueio não se perde. Quem acorda reavalia tudo; acordar
+//! sem nada pronto só custa uma nova varredura.
+
+use super::waitqueue::{TimedOut, WaitQueue};
+use crate::syscall::abi::types::poll_events;
+use alloc::sync::Arc;
+
+/// Objeto cuja prontidão pode ser consultada por `poll`
+pub trait Pollable: Send + Sync {
+    /// Eventos prontos agora (bits de `poll_events`).
+    ///
+    /// O padrão é sempre pronto para ler e escrever, como um dispositivo
+    /// que nunca bloqueia.
+    fn poll_ready(&self) -> u16 {
+        poll_events::IN | poll_events::OUT
+    }
+}
+
+/// Tasks bloqueadas em `poll`
+static POLL_WAIT: WaitQueue = WaitQueue::new();
+
+/// Acorda as tasks em `poll` para reavaliarem seus objetos.
+///
+/// Chamar depois de mudar um estado que afeta `poll_ready`.
+pub fn notify() {
+    POLL_WAIT.wake_many(usize::MAX);
+}
+
+/// Entrada de `poll`: objeto, eventos pedidos e eventos obtidos
+pub struct PollEntry {
+    /// `None` para um handle inválido (retorna `NVAL`)
+    pub source: Option<Arc<dyn Pollable>>,
+    pub events: u16,
+    pub revents: u16,
+}
+
+impl PollEntry {
+    pub fn new(source: Option<Arc<dyn Pollable>>, events: u16) -> Self {
+        Self {
+            source,
+            events,
+            revents: 0,
+        }
+    }
+}
+
+/// Eventos reportados para uma entrada: os pedidos que estão prontos, mais
+/// `ERR` e `HUP` sempre (não precisam ser pedidos)
+pub fn revents_for(ready: u16, events: u16) -> u16 {
+    ready & (events | poll_events::ERR | poll_events::HUP)
+}
+
+/// Preenche `revents`; retorna quantas entradas têm algum evento
+fn scan(entries: &mut [PollEntry]) -> usize {
+    let mut ready = 0;
+    for entry in entries.iter_mut() {
+        entry.revents = match &entry.source {
+            Some(source) => revents_for(source.poll_ready(), entry.events),
+            None => poll_events::NVAL,
+        };
+        if entry.revents != 0 {
+            ready += 1;
+        }
+    }
+    ready
+}
+
+/// Espera até alguma entrada ficar pronta.
+///
+/// `timeout_ns`: `Some(0)` só consulta, `Some(n)` desiste depois de `n`
+/// nanossegundos, `None` espera sem prazo. Retorna quantas entradas têm
+/// eventos (0 no fim do prazo), com `revents` preenchido em todas.
+pub fn poll(entries: &mut [PollEntry], timeout_ns: Option<u64>) -> usize {
+    let ready = scan(entries);
+    if ready > 0 || timeout_ns == Some(0) {
+        return ready;
+    }
+    let deadline = timeout_ns.map(|ns| crate::core::time::now_ns().saturating_add(ns));
+
+    loop {
+        let mut ready = 0;
+        let idle = || {
+            ready = scan(entries);
+            ready == 0
+        };
+        let blocked = match deadline {
+            Some(deadline) => match POLL_WAIT.wait_if_timeout(idle, deadline) {
+                Ok(blocked) => blocked,
+                Err(TimedOut) => return scan(entries),
+            },
+            None => POLL_WAIT.wait_if(idle),
+        };
+        if !blocked {
+            return ready;
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poll_events::{ERR, HUP, IN, OUT};
+
+    #[test]
+    fn test_revents_masks_unrequested() {
+        assert_eq!(revents_for(IN | OUT, IN), IN);
+        assert_eq!(revents_for(OUT, IN), 0);
+        // HUP e ERR vêm mesmo sem pedido
+        assert_eq!(revents_for(IN | HUP, OUT), HUP);
+        assert_eq!(revents_for(ERR, 0), ERR);
+    }
+}
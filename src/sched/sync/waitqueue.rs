@@ -1,84 +1,263 @@
 //! Wait queues para bloqueio e sincronização
 //!
 //! Permite que threads durmam aguardando eventos e sejam acordadas posteriormente.
+//!
+//! ## Wakeups perdidos
+//! Um `wake_*` que chega entre a decisão de esperar e o bloqueio efetivo não
+//! pode se perder. Toda a decisão é tomada sob o lock da fila:
+//! - `wait_until(cond)` reavalia `cond` com o lock da fila adquirido; quem
+//!   acorda altera o estado ANTES de chamar `wake_*` (que toma o mesmo lock).
+//! - `wait()` consome um wakeup pendente registrado por um `wake_*` que não
+//!   encontrou ninguém na fila, em vez de bloquear.
+//!
+//! ## Esperas interrompíveis
+//! `wait_if_interruptible` registra a task em `INTERRUPTIBLE` enquanto
+//! espera; `signal::send` posta o sinal e depois chama `interrupt`, que a
+//! tira da fila. A decisão de bloquear checa os sinais sob o lock da fila,
+//! então um sinal postado antes do bloqueio também não se perde.
+//!
+//! ## Esperas com prazo
+//! `wait_if_timeout` arma um trabalho com atraso (`core::work::schedule_after`,
+//! roda de timers) que tira a task da fila no prazo. Quem acordar primeiro
+//! (um `wake_*` ou o timer) vence; o timer é cancelado ao voltar. Um timer
+//! que já estava a caminho do worker encontra a espera encerrada no registro
+//! `TIMED` e não faz nada.
 
 use alloc::boxed::Box;
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::sched::task::Task;
+use crate::sync::{Spinlock, SpinlockGuard};
+use crate::sys::types::Tid;
+
+/// Espera interrompida por um sinal (EINTR)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
 
-use crate::sched::core::CURRENT;
-use crate::sched::task::{Task, TaskState};
-use crate::sync::Spinlock;
+/// Tasks em espera interrompível → endereço da `WaitQueue` onde esperam.
+///
+/// O endereço é válido enquanto a entrada existe: a própria task remove a
+/// entrada antes de sair de `wait_if_interruptible`, que empresta a fila.
+static INTERRUPTIBLE: Spinlock<BTreeMap<u32, usize>> = Spinlock::new(BTreeMap::new());
+
+/// Tira `tid` de uma espera interrompível, se ela estiver em uma.
+///
+/// Chamado por `signal::send` depois de postar o sinal.
+pub fn interrupt(tid: Tid) -> bool {
+    let registry = INTERRUPTIBLE.lock();
+    match registry.get(&tid.as_u32()) {
+        // SAFETY: ver INTERRUPTIBLE; o lock do registro impede a remoção
+        Some(&queue) => unsafe { &*(queue as *const WaitQueue) }.wake_tid(tid),
+        None => false,
+    }
+}
+
+/// Prazo de uma espera venceu antes de a task ser acordada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+/// Tasks em espera com prazo → (endereço da `WaitQueue`, identificador da
+/// espera). Mesma regra de validade de `INTERRUPTIBLE`.
+static TIMED: Spinlock<BTreeMap<u32, (usize, u64)>> = Spinlock::new(BTreeMap::new());
+
+/// Identificadores das esperas com prazo
+static NEXT_TIMED: AtomicU64 = AtomicU64::new(0);
+
+/// Prazo da espera `wait` de `tid` venceu: tira a task da fila, se a espera
+/// ainda for a mesma
+fn expire_timed(tid: Tid, wait: u64) {
+    let registry = TIMED.lock();
+    if let Some(&(queue, current)) = registry.get(&tid.as_u32()) {
+        if current == wait {
+            // SAFETY: ver TIMED; o lock do registro impede a remoção
+            unsafe { &*(queue as *const WaitQueue) }.wake_tid(tid);
+        }
+    }
+}
+
+struct Inner {
+    /// Tasks bloqueadas (ownership retirada do agendador)
+    waiters: VecDeque<Pin<Box<Task>>>,
+    /// Wakeups emitidos sem nenhuma task esperando
+    pending_wakeups: usize,
+}
 
 /// Wait queue - fila de tarefas bloqueadas aguardando um evento.
 ///
-/// Diferente da implementação anterior, armazenamos a `Task` inteira (ownership),
-/// retirando-a do agendador. Ao acordar, devolvemos para a `RunQueue`.
+/// Armazenamos a `Task` inteira (ownership), retirando-a do agendador.
+/// Ao acordar, devolvemos para a `RunQueue`.
 pub struct WaitQueue {
-    waiters: Spinlock<VecDeque<Pin<Box<Task>>>>,
+    inner: Spinlock<Inner>,
 }
 
 impl WaitQueue {
     /// Cria nova waitqueue vazia
     pub const fn new() -> Self {
         Self {
-            waiters: Spinlock::new(VecDeque::new()),
+            inner: Spinlock::new(Inner {
+                waiters: VecDeque::new(),
+                pending_wakeups: 0,
+            }),
         }
     }
 
-    /// Bloqueia a thread atual e a coloca nesta fila de espera.
+    /// Bloqueia a thread atual até o próximo `wake_one`/`wake_all`.
     ///
-    /// O scheduler escolherá outra tarefa para rodar.
+    /// Se um wakeup já foi emitido sem ninguém esperando, ele é consumido e a
+    /// função retorna imediatamente. Chamadores devem reverificar sua condição
+    /// (wakeups espúrios são possíveis).
     pub fn wait(&self) {
         crate::arch::Cpu::disable_interrupts();
 
-        // 1. Pegar a task atual para bloquear
-        let (task, old_ctx_ptr) = {
-            let mut current_guard = CURRENT.lock();
-            if let Some(mut task) = current_guard.take() {
-                // Marcar como bloqueada
-                unsafe { Pin::get_unchecked_mut(task.as_mut()) }.state = TaskState::Blocked;
+        let mut inner = self.inner.lock();
+        if inner.pending_wakeups > 0 {
+            inner.pending_wakeups -= 1;
+            drop(inner);
+        } else {
+            self.block(inner);
+        }
 
-                // Obter ponteiro do contexto para o switch
-                let ctx_ptr =
-                    unsafe { &mut Pin::get_unchecked_mut(task.as_mut()).context as *mut _ };
+        crate::arch::Cpu::enable_interrupts();
+    }
 
-                // Devolve a task e o ponteiro
-                (task, ctx_ptr)
-            } else {
-                crate::kerror!("(WaitQueue) wait called without current task!");
+    /// Bloqueia a thread atual até `cond()` ser verdadeira.
+    ///
+    /// `cond` é avaliada com o lock da fila adquirido, portanto um `wake_*`
+    /// feito após tornar a condição verdadeira nunca é perdido. `cond` não deve
+    /// tomar locks que os acordadores seguram enquanto chamam `wake_*`.
+    pub fn wait_until<F: FnMut() -> bool>(&self, mut cond: F) {
+        loop {
+            crate::arch::Cpu::disable_interrupts();
+
+            let inner = self.inner.lock();
+            if cond() {
+                drop(inner);
                 crate::arch::Cpu::enable_interrupts();
                 return;
             }
+            self.block(inner);
+
+            crate::arch::Cpu::enable_interrupts();
+        }
+    }
+
+    /// Bloqueia a thread atual uma única vez se `cond()` for verdadeira.
+    ///
+    /// `cond` é avaliada com o lock da fila adquirido (como em `wait_until`),
+    /// mas wakeups pendentes são ignorados. Retorna true se a task bloqueou.
+    pub fn wait_if<F: FnOnce() -> bool>(&self, cond: F) -> bool {
+        crate::arch::Cpu::disable_interrupts();
+
+        let inner = self.inner.lock();
+        let blocked = cond();
+        if blocked {
+            self.block(inner);
+        } else {
+            drop(inner);
+        }
+
+        crate::arch::Cpu::enable_interrupts();
+        blocked
+    }
+
+    /// Como `wait_if`, mas um sinal para a task interrompe a espera.
+    ///
+    /// Retorna `Err(Interrupted)` se havia sinal entregável antes de
+    /// bloquear ou se a task foi acordada com um. Sem task atual (boot),
+    /// equivale a `wait_if`.
+    pub fn wait_if_interruptible<F: FnOnce() -> bool>(&self, cond: F) -> Result<bool, Interrupted> {
+        let tid = match crate::sched::core::CURRENT.lock().as_ref() {
+            Some(task) => task.tid,
+            None => return Ok(self.wait_if(cond)),
         };
+        let signaled = || crate::sched::signal::has_pending(tid);
 
-        // 2. Adicionar à fila de espera (agora detemos a ownership da task)
-        self.waiters.lock().push_back(task);
+        INTERRUPTIBLE
+            .lock()
+            .insert(tid.as_u32(), self as *const Self as usize);
+        crate::arch::Cpu::disable_interrupts();
 
-        // 3. Escolher a próxima task e trocar de contexto
-        // IMPORTANTE: precisamos pegar o lock do CURRENT de volta para o prepare_and_switch_to
-        let current_guard = CURRENT.lock();
-        if let Some(next) = crate::sched::core::pick_next() {
-            unsafe {
-                crate::sched::core::prepare_and_switch_to(next, Some(old_ctx_ptr), current_guard);
+        let inner = self.inner.lock();
+        let result = if signaled() {
+            drop(inner);
+            Err(Interrupted)
+        } else if cond() {
+            self.block(inner);
+            if signaled() {
+                Err(Interrupted)
+            } else {
+                Ok(true)
             }
         } else {
-            // Se não houver próxima task, retornamos para o scheduler
-            // que vai voltar para a idle task automaticamente
-            drop(current_guard);
-            // A idle task vai continuar rodando e eventualmente re-escalonar
-            // quando esta task for acordada via wake_one()
-        }
+            drop(inner);
+            Ok(false)
+        };
 
         crate::arch::Cpu::enable_interrupts();
+        INTERRUPTIBLE.lock().remove(&tid.as_u32());
+        result
+    }
+
+    /// Como `wait_if`, mas desiste em `deadline_ns` (relógio de
+    /// `core::time::now_ns`).
+    ///
+    /// Retorna `Err(TimedOut)` se o prazo já passou ou se a task voltou
+    /// depois dele sem ter sido acordada antes. Sem task atual (boot),
+    /// equivale a `wait_if`.
+    pub fn wait_if_timeout<F: FnOnce() -> bool>(
+        &self,
+        cond: F,
+        deadline_ns: u64,
+    ) -> Result<bool, TimedOut> {
+        let now = crate::core::time::now_ns();
+        if now >= deadline_ns {
+            return Err(TimedOut);
+        }
+        let tid = match crate::sched::core::CURRENT.lock().as_ref() {
+            Some(task) => task.tid,
+            None => return Ok(self.wait_if(cond)),
+        };
+
+        let wait = NEXT_TIMED.fetch_add(1, Ordering::Relaxed);
+        TIMED
+            .lock()
+            .insert(tid.as_u32(), (self as *const Self as usize, wait));
+        let timer =
+            crate::core::work::schedule_after(deadline_ns - now, move || expire_timed(tid, wait));
+
+        let blocked = self.wait_if(cond);
+
+        // Acordado antes do prazo: o timer não dispara mais
+        let timer_pending = crate::core::work::cancel(timer);
+        TIMED.lock().remove(&tid.as_u32());
+
+        if blocked && !timer_pending && crate::core::time::now_ns() >= deadline_ns {
+            Err(TimedOut)
+        } else {
+            Ok(blocked)
+        }
+    }
+
+    /// Entrega a task atual à fila e chama o scheduler.
+    ///
+    /// O lock da fila é liberado pelo scheduler logo após a task ser inserida,
+    /// antes da troca de contexto.
+    fn block(&self, mut inner: SpinlockGuard<'_, Inner>) {
+        crate::sched::core::block_current(move |task| {
+            inner.waiters.push_back(task);
+            drop(inner);
+        });
     }
 
     /// Acorda uma thread desta fila, movendo-a para a RunQueue.
     ///
-    /// Retorna true se acordou alguém.
+    /// Retorna true se acordou alguém. Sem esperando, registra um wakeup
+    /// pendente para o próximo `wait()`.
     pub fn wake_one(&self) -> bool {
-        let mut waiters = self.waiters.lock();
-        if let Some(mut task) = waiters.pop_front() {
+        let mut inner = self.inner.lock();
+        if let Some(mut task) = inner.waiters.pop_front() {
             // 1. Mudar estado para Ready
             task.set_ready();
 
@@ -86,6 +265,7 @@ impl WaitQueue {
             crate::sched::core::enqueue(task);
             true
         } else {
+            inner.pending_wakeups += 1;
             false
         }
     }
@@ -94,13 +274,61 @@ impl WaitQueue {
     ///
     /// Retorna número de threads acordadas.
     pub fn wake_all(&self) -> usize {
-        let mut waiters = self.waiters.lock();
+        let mut inner = self.inner.lock();
+        if inner.waiters.is_empty() {
+            inner.pending_wakeups = core::cmp::max(inner.pending_wakeups, 1);
+            return 0;
+        }
+
         let mut count = 0;
-        while let Some(mut task) = waiters.pop_front() {
+        while let Some(mut task) = inner.waiters.pop_front() {
             task.set_ready();
             crate::sched::core::enqueue(task);
             count += 1;
         }
         count
     }
+
+    /// Acorda até `count` threads desta fila.
+    ///
+    /// Diferente de `wake_one`/`wake_all`, não registra wakeups pendentes
+    /// quando a fila está vazia. Retorna número de threads acordadas.
+    pub fn wake_many(&self, count: usize) -> usize {
+        let mut inner = self.inner.lock();
+        let mut woken = 0;
+        while woken < count {
+            let mut task = match inner.waiters.pop_front() {
+                Some(task) => task,
+                None => break,
+            };
+            task.set_ready();
+            crate::sched::core::enqueue(task);
+            woken += 1;
+        }
+        woken
+    }
+
+    /// Acorda a task `tid`, se ela estiver nesta fila.
+    pub fn wake_tid(&self, tid: Tid) -> bool {
+        let mut inner = self.inner.lock();
+        match inner.waiters.iter().position(|task| task.tid == tid) {
+            Some(pos) => {
+                let mut task = inner.waiters.remove(pos).unwrap();
+                task.set_ready();
+                crate::sched::core::enqueue(task);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Número de tasks bloqueadas nesta fila
+    pub fn len(&self) -> usize {
+        self.inner.lock().waiters.len()
+    }
+
+    /// Verifica se não há tasks bloqueadas
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
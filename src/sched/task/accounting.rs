@@ -6,7 +6,7 @@
 /// Estatísticas de uso de recursos de uma tarefa
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Accounting {
-    /// Tempo total de CPU consumido (em ticks do sistema ou nanossegundos)
+    /// Tempo total de CPU consumido, em nanossegundos
     pub total_cpu_time: u64,
 
     /// Tempo consumido em modo usuário (se suportado pelo hardware/timer)
@@ -15,7 +15,7 @@ pub struct Accounting {
     /// Tempo consumido em modo kernel
     pub kernel_cpu_time: u64,
 
-    /// Timestamp (em ticks) da última vez que a tarefa começou a executar.
+    /// Timestamp (`core::time::now_ns`) da última vez que a tarefa começou a executar.
     /// Usado para calcular o delta quando ela perde a CPU.
     pub last_start_time: u64,
 
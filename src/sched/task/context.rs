@@ -6,6 +6,12 @@
 
 use crate::mm::VirtAddr;
 
+/// Bytes preservados acima de `rsp` ao preparar a primeira troca.
+///
+/// Tasks de usuário têm o TrapFrame (40 bytes) + o slot de retorno logo abaixo
+/// do `rsp` configurado pelo loader; o frame de bootstrap fica abaixo disso.
+const BOOTSTRAP_RESERVE: u64 = 64;
+
 /// Contexto de CPU (registradores salvos)
 ///
 /// NOTA: FpuState temporariamente removido para debug de SSE
@@ -48,110 +54,55 @@ impl CpuContext {
         self.rsp = stack.as_u64();
         self.rbp = 0;
     }
-}
-
-/// Realiza context switch entre duas tasks
-///
-/// # Safety
-///
-/// - Interrupções devem estar desabilitadas
-/// - old e new devem ser ponteiros válidos
-pub unsafe fn switch(old: &mut CpuContext, new: &CpuContext) {
-    // Chamar assembly de switch
-    context_switch_asm(
-        old as *mut CpuContext as u64,
-        new as *const CpuContext as u64,
-    );
-}
 
-/// Salta diretamente para um contexto sem salvar o atual
-/// Usado para a primeira task quando não há contexto anterior
-///
-/// # Safety
-///
-/// - Interrupções devem estar desabilitadas
-/// - ctx deve ser um ponteiro válido
-/// - Nunca retorna
-pub unsafe fn jump_to_context(ctx: &CpuContext) -> ! {
-    crate::ktrace!("(Switch) jump_to_context ENTRADA");
-    crate::ktrace!("(Switch) ctx.rsp=", ctx.rsp);
-    crate::ktrace!("(Switch) ctx.rip=", ctx.rip);
+    /// Contexto configurado via `setup()` (ou pelo loader) e ainda não executado.
+    ///
+    /// Contextos salvos por `context_switch` vivem inteiramente na stack da task;
+    /// nesse caso apenas `rsp` é significativo e `rip` fica zerado.
+    pub fn is_fresh(&self) -> bool {
+        self.rip != 0
+    }
 
-    // Carregar CR3 do contexto (se necessário, o contexto deve ter CR3, mas aqui usamos o atual ou configurado antes)
-    // Na verdade, o scheduler já trocou o CR3 antes de chamar jump_to_context.
-    // apenas verificamos se ainda estamos com o CR3 correto se quisermos, mas para "clean"
-    // vamos confiar no scheduler.
+    /// Marca o contexto como salvo na stack (ver `is_fresh`).
+    pub fn mark_saved(&mut self) {
+        self.rip = 0;
+    }
 
-    crate::ktrace!("(Switch) Chamando jump_to_context_asm...");
-    jump_to_context_asm(ctx as *const CpuContext as u64);
+    /// Converte um contexto novo (`rip`/`rsp`) no layout esperado por `context_switch`.
+    ///
+    /// Empilha `[r15, r14, r13, r12, rbp, rbx, ret]` com `ret = task_bootstrap`,
+    /// que salta para `rip` com `RSP = rsp + 8` — o mesmo estado que um `ret`
+    /// a partir do slot reservado em `rsp` produziria.
+    ///
+    /// # Safety
+    ///
+    /// - A stack da task deve estar mapeada no CR3 atual
+    /// - Deve ser chamado uma única vez, antes da primeira troca para a task
+    pub unsafe fn prime_for_switch(&mut self) {
+        let frame = (self.rsp - BOOTSTRAP_RESERVE - 7 * 8) as *mut u64;
+        frame.add(0).write(0); // r15
+        frame.add(1).write(0); // r14
+        frame.add(2).write(0); // r13
+        frame.add(3).write(self.rip); // r12 = entry
+        frame.add(4).write(0); // rbp
+        frame.add(5).write(self.rsp); // rbx = rsp de entrada
+        frame.add(6).write(task_bootstrap as *const () as u64);
+
+        self.rsp = frame as u64;
+        self.mark_saved();
+    }
 }
 
-// Assembly implementation of context_switch_asm
-// RDI = old (mut ptr), RSI = new (ptr)
-// Struct offsets (CpuContext):
-// 0:rbx, 8:rbp, 16:r12, 24:r13, 32:r14, 40:r15, 48:rsp, 56:rip
+// Primeira execução de uma task: chamado pelo `ret` de `context_switch`.
+// RBX = rsp configurado, R12 = entry point (ver `prime_for_switch`).
 core::arch::global_asm!(
     r#"
-.global context_switch_asm
-context_switch_asm:
-    // Save Generic Registers (Callee-saved)
-    mov [rdi + 0x00], rbx
-    mov [rdi + 0x08], rbp
-    mov [rdi + 0x10], r12
-    mov [rdi + 0x18], r13
-    mov [rdi + 0x20], r14
-    mov [rdi + 0x28], r15
-
-    // Save Stack Pointer
-    mov [rdi + 0x30], rsp
-
-    // Save Instruction Pointer (Return Address)
-    mov rax, [rsp]
-    mov [rdi + 0x38], rax
-
-    // --- Switch Point ---
-
-    // Load New Context
-    mov rbx, [rsi + 0x00]
-    mov rbp, [rsi + 0x08]
-    mov r12, [rsi + 0x10]
-    mov r13, [rsi + 0x18]
-    mov r14, [rsi + 0x20]
-    mov r15, [rsi + 0x28]
-    
-    // Switch Stack
-    mov rsp, [rsi + 0x30]
-
-    // Push RIP and ret to it
-    // FIX: Usar 'mov [rsp], rax; ret' em vez de 'push rax; ret'
-    // 'push rax' decrementa RSP, 'ret' incrementa de volta para o valor original (apontando para old ret addr).
-    // Isso deixa o endereço de retorno original na stack (vazamento de 8 bytes).
-    // Ao sobrescrever [rsp] e dar ret, consumimos o slot corretamente.
-    mov rax, [rsi + 0x38]
-    mov [rsp], rax
-    ret
-
-.global jump_to_context_asm
-jump_to_context_asm:
-    // RDI = ptr to CpuContext
-    // Load all registers from context
-    mov rbx, [rdi + 0x00]
-    mov rbp, [rdi + 0x08]
-    mov r12, [rdi + 0x10]
-    mov r13, [rdi + 0x18]
-    mov r14, [rdi + 0x20]
-    mov r15, [rdi + 0x28]
-    
-    // Switch Stack
-    mov rsp, [rdi + 0x30]
-
-    // Simular o comportamento do 'ret' do context_switch (consumir slot de retorno)
-    // O context_switch faz 'mov [rsp], rax; ret' que incrementa RSP em 8.
-    // O jump_to_context_asm não faz ret, então precisamos ajustar manualmente.
-    add rsp, 8
-
-    // Jump to RIP directly (avoid push which writes to memory)
-    mov rax, [rdi + 0x38]
+.global task_bootstrap
+task_bootstrap:
+    mov rax, r12
+    lea rsp, [rbx + 8]
+    xor ebx, ebx
+    xor r12d, r12d
     jmp rax
 
 .global iretq_restore
@@ -168,7 +119,6 @@ iretq_restore:
 );
 
 extern "C" {
-    fn context_switch_asm(old: u64, new: u64);
-    fn jump_to_context_asm(new: u64) -> !;
+    fn task_bootstrap();
     pub fn iretq_restore() -> !;
 }
@@ -5,6 +5,7 @@ use super::context::CpuContext;
 use super::state::TaskState;
 use crate::mm::aspace::{AddressSpace, Pid};
 use crate::mm::VirtAddr;
+use crate::sched::signal::handler::SignalHandlers;
 use crate::sync::Spinlock;
 use crate::sys::types::Tid;
 use crate::syscall::handle::table::HandleTable;
@@ -13,6 +14,11 @@ use alloc::sync::Arc;
 /// Task ID counter
 static NEXT_TID: crate::sync::AtomicCounter = crate::sync::AtomicCounter::new(1);
 
+/// `tid` já foi atribuído a alguma task (não diz se ela ainda existe)
+pub fn tid_allocated(tid: Tid) -> bool {
+    tid.as_u32() != 0 && (tid.as_u32() as u64) < NEXT_TID.get()
+}
+
 /// Thread Control Block
 pub struct Task {
     /// ID único
@@ -33,7 +39,8 @@ pub struct Task {
     pub accounting: Accounting,
 
     // --- Hierarquia ---
-    /// ID da tarefa pai (quem criou esta)
+    /// ID da tarefa que criou esta (o pai atual, depois de uma adoção pelo
+    /// init, fica em `family`)
     pub parent_id: Option<Tid>,
     /// Código de saída (para waitpid)
     pub exit_code: Option<i32>,
@@ -43,12 +50,14 @@ pub struct Task {
     pub pending_signals: u64,
     /// Sinais bloqueados (máscara)
     pub blocked_signals: u64,
+    /// Ação registrada para cada sinal
+    pub signal_handlers: SignalHandlers,
 
     /// Nome (debug)
     pub name: [u8; 32],
     /// Tabela de handles
     pub handle_table: HandleTable,
-    /// Momento de acordar (jiffies) se estiver dormindo
+    /// Momento de acordar (`core::time::now_ns`) se estiver dormindo
     pub wake_at: Option<u64>,
     /// Base da heap do usuário
     pub heap_start: u64,
@@ -81,6 +90,7 @@ impl Task {
             exit_code: None,
             pending_signals: 0,
             blocked_signals: 0,
+            signal_handlers: SignalHandlers::new(),
             name: name_buf,
             handle_table: HandleTable::new(),
             wake_at: None,
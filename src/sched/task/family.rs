@@ -0,0 +1,283 @@
+//! Hierarquia de processos e `wait`
+//!
+//! Cada processo criado com pai (`exec::spawn`) é registrado aqui com o pai
+//! atual. Ao terminar, o status fica guardado até o pai coletá-lo com
+//! [`wait`], mesmo que o filho tenha saído antes da chamada; a `Task` zumbi
+//! também é mantida até a coleta (`lifecycle::cleanup_all` só descarta
+//! zumbis sem pai esperando). Os filhos de quem termina passam para o init.
+//!
+//! `Task::parent_id` guarda quem criou a task; o pai atual (depois de uma
+//! adoção pelo init) é o desta tabela.
+
+use crate::sched::sync::waitqueue::Interrupted;
+use crate::sched::WaitQueue;
+use crate::sync::Spinlock;
+use crate::sys::types::Tid;
+use alloc::collections::BTreeMap;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Intervalo de verificação de um `wait` com timeout
+const WAIT_POLL_NS: u64 = 10_000_000;
+
+static FAMILY: Spinlock<Family> = Spinlock::new(Family::new());
+
+/// Pais esperando algum filho terminar
+static CHILD_EXITED: WaitQueue = WaitQueue::new();
+
+/// TID do init (0 = nenhum): adota os órfãos
+static INIT: AtomicU32 = AtomicU32::new(0);
+
+/// Erros de [`wait`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitError {
+    /// O alvo não é filho do chamador (ou não há filhos)
+    NoChild,
+    /// Sinal durante a espera
+    Interrupted,
+    /// Prazo esgotado com o filho ainda vivo
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Member {
+    parent: u32,
+    /// Código de saída, quando já terminou
+    status: Option<i32>,
+}
+
+/// Resultado de uma tentativa de coleta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reap {
+    Exited(u32, i32),
+    Running,
+    NoChild,
+}
+
+/// Relação filho → pai, com o status dos filhos ainda não coletados
+struct Family {
+    members: BTreeMap<u32, Member>,
+}
+
+impl Family {
+    const fn new() -> Self {
+        Self {
+            members: BTreeMap::new(),
+        }
+    }
+
+    fn register(&mut self, child: u32, parent: u32) {
+        self.members.insert(
+            child,
+            Member {
+                parent,
+                status: None,
+            },
+        );
+    }
+
+    /// Registra a saída de `tid` e entrega os filhos dele a `init`.
+    ///
+    /// Sem init (ou se é o próprio init saindo) os filhos ficam sem pai:
+    /// ninguém vai coletá-los. Retorna se havia um pai para avisar.
+    fn exited(&mut self, tid: u32, code: i32, init: Option<u32>) -> bool {
+        let adopter = init.filter(|&init| init != tid);
+        self.members.retain(|_, member| {
+            if member.parent != tid {
+                return true;
+            }
+            match adopter {
+                Some(init) => {
+                    member.parent = init;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        match self.members.get_mut(&tid) {
+            Some(member) if member.status.is_none() => {
+                member.status = Some(code);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Filho terminado de `parent` (`target` ou qualquer um), sem coletar
+    fn peek(&self, parent: u32, target: Option<u32>) -> Reap {
+        let mut found = Reap::NoChild;
+        for (&tid, member) in &self.members {
+            if member.parent != parent || target.map_or(false, |t| t != tid) {
+                continue;
+            }
+            match member.status {
+                Some(code) => return Reap::Exited(tid, code),
+                None => found = Reap::Running,
+            }
+        }
+        found
+    }
+
+    /// Como `peek`, removendo o filho coletado
+    fn try_reap(&mut self, parent: u32, target: Option<u32>) -> Reap {
+        let reap = self.peek(parent, target);
+        if let Reap::Exited(tid, _) = reap {
+            self.members.remove(&tid);
+        }
+        reap
+    }
+
+    fn parent_of(&self, tid: u32) -> Option<u32> {
+        self.members.get(&tid).map(|member| member.parent)
+    }
+}
+
+/// Define o init, que adota os processos órfãos
+pub fn set_init(tid: Tid) {
+    INIT.store(tid.as_u32(), Ordering::Release);
+}
+
+fn init_tid() -> Option<u32> {
+    match INIT.load(Ordering::Acquire) {
+        0 => None,
+        tid => Some(tid),
+    }
+}
+
+/// `tid` é o init?
+pub fn is_init(tid: Tid) -> bool {
+    init_tid() == Some(tid.as_u32())
+}
+
+/// Registra `child` como filho de `parent`
+pub fn register(child: Tid, parent: Tid) {
+    FAMILY.lock().register(child.as_u32(), parent.as_u32());
+}
+
+/// Pai atual de `tid`
+pub fn parent_of(tid: Tid) -> Option<Tid> {
+    FAMILY.lock().parent_of(tid.as_u32()).map(Tid::new)
+}
+
+/// O status de `tid` ainda vai ser coletado por um pai? (a `Task` zumbi
+/// tem que ficar até lá)
+pub fn is_awaited(tid: Tid) -> bool {
+    FAMILY.lock().members.contains_key(&tid.as_u32())
+}
+
+/// Registra o término de `tid` com `code` e acorda os pais em `wait`.
+///
+/// Chamado por quem transforma a task em zumbi (`exit_current`,
+/// `kill_ready`, `kill_sleeping`), sem locks do scheduler.
+pub fn exited(tid: Tid, code: i32) {
+    let notify = FAMILY.lock().exited(tid.as_u32(), code, init_tid());
+    if notify {
+        CHILD_EXITED.wake_all();
+    }
+}
+
+/// Espera um filho da task atual terminar e o coleta.
+///
+/// `target` escolhe o filho (`None` = qualquer um). `timeout_ns` 0 espera
+/// sem prazo. Retorna o TID e o código de saída; a `Task` zumbi é liberada.
+pub fn wait(target: Option<Tid>, timeout_ns: u64) -> Result<(Tid, i32), WaitError> {
+    let parent = crate::sched::core::CURRENT
+        .lock()
+        .as_ref()
+        .map(|task| task.tid.as_u32())
+        .ok_or(WaitError::NoChild)?;
+    let target = target.map(Tid::as_u32);
+    let deadline = crate::core::time::now_ns().saturating_add(timeout_ns);
+
+    loop {
+        match FAMILY.lock().try_reap(parent, target) {
+            Reap::Exited(tid, code) => {
+                let tid = Tid::new(tid);
+                super::lifecycle::cleanup(tid);
+                return Ok((tid, code));
+            }
+            Reap::NoChild => return Err(WaitError::NoChild),
+            Reap::Running => {}
+        }
+
+        if timeout_ns == 0 {
+            // A condição é checada sob o lock da fila: um `exited` entre a
+            // tentativa acima e o bloqueio não se perde
+            CHILD_EXITED
+                .wait_if_interruptible(|| FAMILY.lock().peek(parent, target) == Reap::Running)
+                .map_err(|Interrupted| WaitError::Interrupted)?;
+        } else {
+            let now = crate::core::time::now_ns();
+            if now >= deadline {
+                return Err(WaitError::Timeout);
+            }
+            crate::core::time::sleep_ns(core::cmp::min(deadline - now, WAIT_POLL_NS));
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INIT_TID: u32 = 1;
+    const PARENT: u32 = 5;
+    const CHILD: u32 = 6;
+
+    #[test]
+    fn test_wait_collects_exit_code() {
+        let mut family = Family::new();
+        family.register(CHILD, PARENT);
+
+        assert_eq!(family.try_reap(PARENT, Some(CHILD)), Reap::Running);
+        assert!(family.exited(CHILD, 42, Some(INIT_TID)));
+        assert_eq!(
+            family.try_reap(PARENT, Some(CHILD)),
+            Reap::Exited(CHILD, 42)
+        );
+
+        // Coletado uma vez só
+        assert_eq!(family.try_reap(PARENT, Some(CHILD)), Reap::NoChild);
+    }
+
+    #[test]
+    fn test_status_kept_until_wait() {
+        let mut family = Family::new();
+        family.register(CHILD, PARENT);
+        family.register(CHILD + 1, PARENT);
+
+        // Filho sai antes de o pai chamar wait
+        family.exited(CHILD + 1, 7, Some(INIT_TID));
+        assert_eq!(family.peek(PARENT, None), Reap::Exited(CHILD + 1, 7));
+        assert_eq!(family.try_reap(PARENT, None), Reap::Exited(CHILD + 1, 7));
+        assert_eq!(family.try_reap(PARENT, None), Reap::Running);
+
+        // Não é filho de quem pergunta
+        assert_eq!(family.try_reap(INIT_TID, Some(CHILD)), Reap::NoChild);
+    }
+
+    #[test]
+    fn test_orphans_go_to_init() {
+        let mut family = Family::new();
+        family.register(PARENT, INIT_TID);
+        family.register(CHILD, PARENT);
+
+        assert!(family.exited(PARENT, 0, Some(INIT_TID)));
+        assert_eq!(family.parent_of(CHILD), Some(INIT_TID));
+
+        family.exited(CHILD, 3, Some(INIT_TID));
+        assert_eq!(
+            family.try_reap(INIT_TID, Some(CHILD)),
+            Reap::Exited(CHILD, 3)
+        );
+
+        // Sem init, os órfãos saem da tabela
+        family.register(CHILD, PARENT);
+        family.exited(PARENT, 0, None);
+        assert_eq!(family.parent_of(CHILD), None);
+    }
+}
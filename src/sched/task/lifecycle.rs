@@ -13,9 +13,30 @@ pub(crate) static ZOMBIES: Spinlock<VecDeque<Pin<Box<Task>>>> = Spinlock::new(Ve
 
 /// Adiciona tarefa à lista de zombies
 pub fn add_zombie(task: Pin<Box<Task>>) {
+    // Sinais postados para uma task morta não serão entregues
+    crate::sched::signal::pending::take(task.tid);
     ZOMBIES.lock().push_back(task);
 }
 
+/// Solta o espaço de endereçamento de um zumbi antes da coleta.
+///
+/// Usado pelo OOM killer: a memória da vítima volta na hora, sem esperar
+/// o pai chamar `wait`. Retorna `false` se `tid` não é zumbi.
+pub fn release_memory(tid: Tid) -> bool {
+    let aspace = {
+        let mut zombies = ZOMBIES.lock();
+        let Some(task) = zombies.iter_mut().find(|t| t.tid == tid) else {
+            return false;
+        };
+        // SAFETY: só o campo `aspace` é movido; a `Task` continua no lugar
+        let task = unsafe { Pin::get_unchecked_mut(task.as_mut()) };
+        task.aspace.take()
+    };
+    // Drop fora do lock dos zumbis (libera frames)
+    drop(aspace);
+    true
+}
+
 /// Finaliza a task atual
 pub fn exit(code: i32) -> ! {
     crate::kinfo!("(Task) exit() chamado. Code=", code as u64);
@@ -55,28 +76,16 @@ pub fn cleanup(_tid: Tid) {
     }
 }
 
-/// Procura um zumbi pelo TID, remove-o da lista e retorna seu código de saída.
-pub fn find_and_collect_zombie(_tid: Tid) -> Option<i32> {
-    let mut zombies = ZOMBIES.lock();
-    if let Some(pos) = zombies.iter().position(|t| t.tid == _tid) {
-        let task = zombies.remove(pos).unwrap();
-        let code = task.exit_code.unwrap_or(-1);
-        crate::kinfo!(
-            "(Lifecycle) Collected zombie PID:",
-            task.tid.as_u32() as u64
-        );
-        Some(code)
-    } else {
-        None
-    }
-}
-
-/// Limpa todos os zumbis pendentes (útil para idle task chamar)
+/// Limpa os zumbis pendentes (útil para idle task chamar)
+///
+/// Zumbis cujo status ainda vai ser coletado por um pai (`family::wait`)
+/// ficam; o `wait` os libera.
 pub fn cleanup_all() {
     let mut zombies = ZOMBIES.lock();
-    let count = zombies.len();
+    let before = zombies.len();
+    zombies.retain(|task| super::family::is_awaited(task.tid));
+    let count = before - zombies.len();
     if count > 0 {
         crate::kinfo!("(Lifecycle) Cleaning up all zombies. Count:", count as u64);
-        zombies.clear(); // Dropa todos
     }
 }
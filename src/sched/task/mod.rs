@@ -3,6 +3,7 @@
 pub mod accounting;
 pub mod context;
 pub mod entity;
+pub mod family;
 pub mod lifecycle;
 pub mod state;
 pub use crate::sys::Tid;
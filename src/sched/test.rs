@@ -0,0 +1,401 @@
+//! # Testes do Scheduler
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot
+//! (que o `schedule()` adota como idle task).
+
+use crate::arch::Cpu;
+use crate::sched::core::runqueue::RUNQUEUE;
+use crate::sched::core::{exit_current, inherit, kill_sleeping, spawn_kernel_thread, yield_now};
+use crate::sched::task::family;
+use crate::sched::WaitQueue;
+use crate::sync::{Mutex, RwLock, Spinlock};
+use crate::sys::types::Tid;
+use alloc::vec::Vec;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+/// Rodadas de cada task no teste cooperativo
+const ROUNDS: usize = 4;
+
+/// Ordem em que as tasks executaram (b'A' / b'B')
+static TRACE: Spinlock<Vec<u8>> = Spinlock::new(Vec::new());
+static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn run_tests() {
+    crate::kinfo!("(Sched) Iniciando testes do scheduler...");
+    test_cooperative_yield();
+    test_waitqueue_wake();
+    test_sleep_wake_order();
+    test_signal_interrupts_wait();
+    test_parent_waits_child();
+    test_mutex_priority_inheritance();
+    test_rwlock_fair_writer();
+    crate::kinfo!("(Sched) Testes do scheduler concluídos com SUCESSO.");
+}
+
+fn cooperative_body(tag: u8) -> ! {
+    for _ in 0..ROUNDS {
+        TRACE.lock().push(tag);
+        yield_now();
+    }
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn task_a() -> ! {
+    cooperative_body(b'A')
+}
+
+extern "C" fn task_b() -> ! {
+    cooperative_body(b'B')
+}
+
+/// Cria uma kernel task de teste com stack própria e a enfileira
+pub(crate) fn spawn_kernel_task(name: &str, entry: extern "C" fn() -> !) {
+    spawn_kernel_thread(name, entry);
+}
+
+/// Duas tasks cedem a CPU uma para a outra; ambas devem rodar, alternadamente.
+fn test_cooperative_yield() {
+    TRACE.lock().clear();
+    FINISHED.store(0, Ordering::SeqCst);
+
+    spawn_kernel_task("sched-test-a", task_a);
+    spawn_kernel_task("sched-test-b", task_b);
+
+    while FINISHED.load(Ordering::SeqCst) < 2 {
+        yield_now();
+    }
+
+    let trace = TRACE.lock();
+    let a_runs = trace.iter().filter(|&&t| t == b'A').count();
+    let b_runs = trace.iter().filter(|&&t| t == b'B').count();
+    assert_eq!(a_runs, ROUNDS, "(Sched) Task A não rodou todas as rodadas");
+    assert_eq!(b_runs, ROUNDS, "(Sched) Task B não rodou todas as rodadas");
+    assert!(
+        trace.windows(2).all(|w| w[0] != w[1]),
+        "(Sched) As tasks não alternaram a CPU"
+    );
+}
+
+static EVENT_QUEUE: WaitQueue = WaitQueue::new();
+static EVENT_READY: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn waiter_task() -> ! {
+    EVENT_QUEUE.wait_until(|| EVENT_READY.load(Ordering::SeqCst));
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Uma task bloqueia na WaitQueue e só volta a rodar após o `wake_all`.
+fn test_waitqueue_wake() {
+    FINISHED.store(0, Ordering::SeqCst);
+    EVENT_READY.store(false, Ordering::SeqCst);
+
+    spawn_kernel_task("sched-test-wait", waiter_task);
+
+    // Deixa a task rodar até bloquear
+    yield_now();
+    assert_eq!(
+        EVENT_QUEUE.len(),
+        1,
+        "(Sched) Task não bloqueou na WaitQueue"
+    );
+    assert_eq!(FINISHED.load(Ordering::SeqCst), 0);
+
+    EVENT_READY.store(true, Ordering::SeqCst);
+    assert_eq!(EVENT_QUEUE.wake_all(), 1);
+
+    while FINISHED.load(Ordering::SeqCst) < 1 {
+        yield_now();
+    }
+    assert!(EVENT_QUEUE.is_empty());
+}
+
+/// Duração dos sleeps do teste de ordem (a longa dorme primeiro)
+const SHORT_SLEEP_NS: u64 = 20_000_000;
+const LONG_SLEEP_NS: u64 = 60_000_000;
+
+extern "C" fn long_sleeper() -> ! {
+    crate::core::time::sleep_ns(LONG_SLEEP_NS);
+    TRACE.lock().push(b'L');
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn short_sleeper() -> ! {
+    crate::core::time::sleep_ns(SHORT_SLEEP_NS);
+    TRACE.lock().push(b'S');
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn killed_sleeper() -> ! {
+    crate::core::time::sleep_ns(LONG_SLEEP_NS);
+    TRACE.lock().push(b'K');
+    exit_current(0);
+}
+
+/// Duas tasks dormem prazos diferentes e acordam na ordem dos prazos, não na
+/// de chegada; uma terceira é morta dormindo e nunca acorda.
+fn test_sleep_wake_order() {
+    TRACE.lock().clear();
+    FINISHED.store(0, Ordering::SeqCst);
+
+    let start = crate::core::time::now_ns();
+    spawn_kernel_task("sched-test-long", long_sleeper);
+    spawn_kernel_task("sched-test-short", short_sleeper);
+    let victim = spawn_kernel_thread("sched-test-killed", killed_sleeper);
+
+    // Deixa as três dormirem
+    yield_now();
+    assert!(
+        kill_sleeping(victim, -1),
+        "(Sched) Task não estava dormindo"
+    );
+
+    while FINISHED.load(Ordering::SeqCst) < 2 {
+        yield_now();
+    }
+    let elapsed = crate::core::time::now_ns() - start;
+
+    assert_eq!(
+        TRACE.lock().as_slice(),
+        b"SL",
+        "(Sched) Tasks acordaram fora da ordem dos prazos"
+    );
+    assert!(elapsed >= LONG_SLEEP_NS, "(Sched) Sleep acordou cedo");
+}
+
+static SIGNAL_QUEUE: WaitQueue = WaitQueue::new();
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interruptible_waiter() -> ! {
+    let result = SIGNAL_QUEUE.wait_if_interruptible(|| true);
+    INTERRUPTED.store(result.is_err(), Ordering::SeqCst);
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Um sinal acorda uma espera interrompível, que retorna `Interrupted` sem
+/// nenhum wake na fila.
+fn test_signal_interrupts_wait() {
+    FINISHED.store(0, Ordering::SeqCst);
+    INTERRUPTED.store(false, Ordering::SeqCst);
+
+    let tid = spawn_kernel_thread("sched-test-signal", interruptible_waiter);
+    yield_now();
+    assert_eq!(SIGNAL_QUEUE.len(), 1, "(Sched) Task não bloqueou");
+
+    assert!(crate::sched::signal::send(tid, crate::sched::signal::SIGUSR1).is_ok());
+    while FINISHED.load(Ordering::SeqCst) < 1 {
+        yield_now();
+    }
+    assert!(
+        INTERRUPTED.load(Ordering::SeqCst),
+        "(Sched) Espera não foi interrompida pelo sinal"
+    );
+    assert!(SIGNAL_QUEUE.is_empty());
+}
+
+/// Código com que o filho do teste de `wait` termina
+const CHILD_EXIT_CODE: i32 = 42;
+static WAIT_RESULT: Spinlock<Option<i32>> = Spinlock::new(None);
+
+extern "C" fn exiting_child() -> ! {
+    exit_current(CHILD_EXIT_CODE);
+}
+
+extern "C" fn waiting_parent() -> ! {
+    let parent = crate::sched::core::CURRENT
+        .lock()
+        .as_ref()
+        .map(|task| task.tid)
+        .expect("(Sched) Sem task atual");
+    let child = spawn_kernel_thread("sched-test-child", exiting_child);
+    family::register(child, parent);
+
+    let result = family::wait(Some(child), 0).map(|(tid, code)| {
+        assert_eq!(tid, child);
+        code
+    });
+    *WAIT_RESULT.lock() = result.ok();
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Um pai bloqueia em `wait` até o filho terminar e recebe o código dele.
+fn test_parent_waits_child() {
+    FINISHED.store(0, Ordering::SeqCst);
+    *WAIT_RESULT.lock() = None;
+
+    spawn_kernel_task("sched-test-parent", waiting_parent);
+    while FINISHED.load(Ordering::SeqCst) < 1 {
+        yield_now();
+    }
+    assert_eq!(
+        *WAIT_RESULT.lock(),
+        Some(CHILD_EXIT_CODE),
+        "(Sched) wait não devolveu o código do filho"
+    );
+}
+
+/// Prioridades das tasks do teste de herança (menor = mais prioritária)
+const LOW_PRIORITY: u8 = 200;
+const MID_PRIORITY: u8 = 100;
+const HIGH_PRIORITY: u8 = 10;
+
+static PI_LOCK: Mutex<()> = Mutex::new(());
+static LOW_HOLDING: AtomicBool = AtomicBool::new(false);
+static HIGH_WAITING: AtomicBool = AtomicBool::new(false);
+static HIGH_DONE: AtomicBool = AtomicBool::new(false);
+/// Prioridade efetiva da task baixa com o lock disputado / depois de soltar
+static LOW_BOOSTED: AtomicU8 = AtomicU8::new(0);
+static LOW_RESTORED: AtomicU8 = AtomicU8::new(0);
+
+/// Cria uma kernel task já com `priority` (antes de ela rodar)
+fn spawn_with_priority(name: &str, entry: extern "C" fn() -> !, priority: u8) -> Tid {
+    Cpu::disable_interrupts();
+    let tid = spawn_kernel_thread(name, entry);
+    {
+        let mut rq = RUNQUEUE.lock();
+        if let Some(mut task) = rq.remove(tid) {
+            unsafe { Pin::get_unchecked_mut(task.as_mut()) }.priority = priority;
+            rq.push(task);
+        }
+    }
+    Cpu::enable_interrupts();
+    tid
+}
+
+fn current_priority() -> u8 {
+    inherit::current()
+        .map(|(_, priority)| priority)
+        .expect("(Sched) Sem task atual")
+}
+
+extern "C" fn pi_low() -> ! {
+    let guard = PI_LOCK.lock();
+    LOW_HOLDING.store(true, Ordering::SeqCst);
+    // Só volta a rodar antes da média se herdar a prioridade da alta
+    while !HIGH_WAITING.load(Ordering::SeqCst) || current_priority() != HIGH_PRIORITY {
+        yield_now();
+    }
+    LOW_BOOSTED.store(current_priority(), Ordering::SeqCst);
+    drop(guard);
+    LOW_RESTORED.store(current_priority(), Ordering::SeqCst);
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn pi_mid() -> ! {
+    while !HIGH_DONE.load(Ordering::SeqCst) {
+        yield_now();
+    }
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn pi_high() -> ! {
+    HIGH_WAITING.store(true, Ordering::SeqCst);
+    drop(PI_LOCK.lock());
+    HIGH_DONE.store(true, Ordering::SeqCst);
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Inversão de prioridade: a baixa segura o mutex, a média ocupa a CPU e a
+/// alta espera. A baixa deve herdar a prioridade da alta enquanto ela espera
+/// e voltar à própria ao liberar o lock.
+fn test_mutex_priority_inheritance() {
+    FINISHED.store(0, Ordering::SeqCst);
+    for flag in [&LOW_HOLDING, &HIGH_WAITING, &HIGH_DONE] {
+        flag.store(false, Ordering::SeqCst);
+    }
+
+    spawn_with_priority("sched-test-pi-low", pi_low, LOW_PRIORITY);
+    while !LOW_HOLDING.load(Ordering::SeqCst) {
+        yield_now();
+    }
+    spawn_with_priority("sched-test-pi-mid", pi_mid, MID_PRIORITY);
+    spawn_with_priority("sched-test-pi-high", pi_high, HIGH_PRIORITY);
+
+    while FINISHED.load(Ordering::SeqCst) < 3 {
+        yield_now();
+    }
+    assert_eq!(
+        LOW_BOOSTED.load(Ordering::SeqCst),
+        HIGH_PRIORITY,
+        "(Sched) O dono do mutex não herdou a prioridade de quem espera"
+    );
+    assert_eq!(
+        LOW_RESTORED.load(Ordering::SeqCst),
+        LOW_PRIORITY,
+        "(Sched) A prioridade herdada não foi desfeita ao liberar o mutex"
+    );
+}
+
+/// Ticks que um escritor pode esperar por um `RwLock::new_fair` sob carga
+/// contínua de leitores
+const WRITER_MAX_TICKS: u64 = 50;
+/// Leitores que se revezam segurando o lock
+const RW_READERS: usize = 2;
+
+static RW_LOCK: RwLock<u64> = RwLock::new_fair(0);
+static RW_STOP: AtomicBool = AtomicBool::new(false);
+/// Tick em que o escritor começou a esperar (0 = ainda não começou)
+static RW_WRITER_START: AtomicU64 = AtomicU64::new(0);
+static RW_WRITER_WAITED: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Os leitores param sozinhos se o escritor passar muito do limite, para o
+/// teste falhar no assert em vez de travar
+fn readers_should_stop() -> bool {
+    let start = RW_WRITER_START.load(Ordering::SeqCst);
+    RW_STOP.load(Ordering::SeqCst)
+        || (start != 0 && crate::drivers::timer::ticks() - start > 10 * WRITER_MAX_TICKS)
+}
+
+extern "C" fn rw_reader() -> ! {
+    while !readers_should_stop() {
+        // Cede a CPU com a leitura aberta: os leitores se sobrepõem e o lock
+        // nunca fica livre sem a política justa
+        let guard = RW_LOCK.read();
+        yield_now();
+        drop(guard);
+    }
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn rw_writer() -> ! {
+    let start = crate::drivers::timer::ticks().max(1);
+    RW_WRITER_START.store(start, Ordering::SeqCst);
+    *RW_LOCK.write() += 1;
+    RW_WRITER_WAITED.store(crate::drivers::timer::ticks() - start, Ordering::SeqCst);
+    RW_STOP.store(true, Ordering::SeqCst);
+    FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Um escritor de um `RwLock::new_fair` entra em tempo limitado mesmo com
+/// leitores segurando o lock sem parar.
+fn test_rwlock_fair_writer() {
+    FINISHED.store(0, Ordering::SeqCst);
+    RW_STOP.store(false, Ordering::SeqCst);
+
+    for _ in 0..RW_READERS {
+        spawn_kernel_task("sched-test-rw-reader", rw_reader);
+    }
+    // Nível FIFO: os dois leitores abrem leitura antes de o escritor rodar
+    spawn_kernel_task("sched-test-rw-writer", rw_writer);
+
+    while FINISHED.load(Ordering::SeqCst) < RW_READERS + 1 {
+        yield_now();
+    }
+    assert_eq!(*RW_LOCK.read(), 1, "(Sched) O escritor não escreveu");
+    let waited = RW_WRITER_WAITED.load(Ordering::SeqCst);
+    assert!(
+        waited <= WRITER_MAX_TICKS,
+        "(Sched) Escritor esperou demais pelo RwLock justo"
+    );
+}
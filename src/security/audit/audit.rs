@@ -16,6 +16,7 @@ pub enum AuditEvent {
 
 /// Entrada de log de auditoria
 pub struct AuditEntry {
+    /// Nanossegundos desde o boot (`core::time::now_ns`)
     pub timestamp: u64,
     pub event: AuditEvent,
     pub pid: Pid,
@@ -23,11 +24,23 @@ pub struct AuditEntry {
     pub details: [u8; 64],
 }
 
+impl AuditEntry {
+    /// Entrada com o timestamp atual e sem detalhes
+    pub fn new(event: AuditEvent, pid: Pid, uid: Uid) -> Self {
+        Self {
+            timestamp: crate::core::time::now_ns(),
+            event,
+            pid,
+            uid,
+            details: [0; 64],
+        }
+    }
+}
+
 /// Loga evento de auditoria
 pub fn log_event(event: AuditEvent, pid: Pid, uid: Uid) {
-    // Evitar warning unused
-    let _ = pid;
-    let _ = uid;
+    let entry = AuditEntry::new(event, pid, uid);
     // TODO: adicionar ao buffer de log
-    crate::kdebug!("Audit:", event as u64);
+    crate::kdebug!("Audit:", entry.event as u64);
+    crate::kdebug!("Audit: timestamp (ns)=", entry.timestamp);
 }
@@ -1,5 +1,6 @@
 //! Capability - token de acesso
 
+use super::derivation::CAP_ID_NONE;
 use super::rights::CapRights;
 
 /// Tipo de objeto que a capability referencia
@@ -43,6 +44,10 @@ pub struct Capability {
     pub badge: u64,
     /// Generation counter (para revocação)
     pub generation: u32,
+    /// ID na árvore de derivação (atribuído ao inserir em um CSpace)
+    pub id: u64,
+    /// ID da capability de origem (`CAP_ID_NONE` se for raiz)
+    pub parent: u64,
 }
 
 impl Capability {
@@ -54,9 +59,11 @@ impl Capability {
             object_ref: 0,
             badge: 0,
             generation: 0,
+            id: CAP_ID_NONE,
+            parent: CAP_ID_NONE,
         }
     }
-    
+
     /// Cria nova capability
     pub const fn new(cap_type: CapType, rights: CapRights, object_ref: u64) -> Self {
         Self {
@@ -65,32 +72,49 @@ impl Capability {
             object_ref,
             badge: 0,
             generation: 0,
+            id: CAP_ID_NONE,
+            parent: CAP_ID_NONE,
         }
     }
-    
+
     /// Verifica se é válida
     pub const fn is_valid(&self) -> bool {
         !matches!(self.cap_type, CapType::Null)
     }
-    
+
     /// Cria capability derivada com menos direitos
+    ///
+    /// `GRANT` só é repassado se pedido em `new_rights`, permitindo cadeias
+    /// de delegação. A derivada fica ligada a esta na árvore de derivação
+    /// quando for inserida em um CSpace.
     pub fn derive(&self, new_rights: CapRights) -> Option<Self> {
         // Só pode reduzir direitos
         if !self.rights.has(CapRights::GRANT) {
             return None;
         }
-        
+
         // Nova capability tem apenas direitos que origem tinha
         let reduced_rights = new_rights.intersect(self.rights);
-        
+
         Some(Self {
             cap_type: self.cap_type,
-            rights: reduced_rights.without(CapRights::GRANT),
+            rights: reduced_rights,
             object_ref: self.object_ref,
             badge: self.badge,
             generation: self.generation,
+            id: CAP_ID_NONE,
+            parent: self.id,
         })
     }
+
+    /// Cópia com os mesmos direitos, filha desta na árvore de derivação
+    pub fn child(&self) -> Self {
+        Self {
+            id: CAP_ID_NONE,
+            parent: self.id,
+            ..self.clone()
+        }
+    }
 }
 
 /// Handle opaco para userspace
@@ -100,15 +124,15 @@ pub struct CapHandle(u32);
 
 impl CapHandle {
     pub const INVALID: Self = Self(0);
-    
+
     pub const fn new(index: u32) -> Self {
         Self(index)
     }
-    
+
     pub const fn as_u32(self) -> u32 {
         self.0
     }
-    
+
     pub const fn is_valid(self) -> bool {
         self.0 != 0
     }
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 //! Capability Space - tabela por processo
 
+use super::derivation::{self, CAP_ID_NONE};
 use super::{CapHandle, CapRights, Capability};
 // Note: Guide imported Sync Spinlock but didn't use it in struct definition?
 // Maybe intended for internal locking or external usage. Struct CSpace provided in guide doesn't have Spinlock field.
@@ -31,43 +32,54 @@ impl CSpace {
     }
 
     /// Insere capability e retorna handle
-    pub fn insert(&mut self, cap: Capability) -> Option<CapHandle> {
+    ///
+    /// Capabilities ainda sem ID são registradas na árvore de derivação,
+    /// como filhas de `cap.parent`.
+    pub fn insert(&mut self, mut cap: Capability) -> Option<CapHandle> {
+        let index = self.free_slot()?;
+        if cap.id == CAP_ID_NONE {
+            cap.id = derivation::register(cap.parent);
+        }
+        self.slots[index] = Some(cap);
+        Some(CapHandle::new(index as u32))
+    }
+
+    /// Procura slot livre, a partir de `next_free`
+    fn free_slot(&mut self) -> Option<usize> {
         // Procurar slot livre
         for i in self.next_free..CSPACE_SIZE {
             if self.slots[i].is_none() {
-                self.slots[i] = Some(cap);
                 self.next_free = i + 1;
-                return Some(CapHandle::new(i as u32));
-            }
-        }
-
-        // Tentar desde o início
-        for i in 1..self.next_free {
-            if self.slots[i].is_none() {
-                self.slots[i] = Some(cap);
-                return Some(CapHandle::new(i as u32));
+                return Some(i);
             }
         }
 
-        None // CSpace cheio
+        // Tentar desde o início; `None` se o CSpace está cheio
+        (1..self.next_free).find(|&i| self.slots[i].is_none())
     }
 
-    /// Busca capability por handle
-    pub fn lookup(&self, handle: CapHandle) -> Option<&Capability> {
+    /// Busca capability por handle, distinguindo slot vazio de revogada
+    pub fn get(&self, handle: CapHandle) -> Result<&Capability, CapError> {
         let index = handle.as_u32() as usize;
         if index >= CSPACE_SIZE {
-            return None;
+            return Err(CapError::InvalidHandle);
+        }
+        let cap = self.slots[index].as_ref().ok_or(CapError::InvalidHandle)?;
+        if !derivation::is_live(cap.id) {
+            return Err(CapError::Revoked);
         }
-        self.slots[index].as_ref()
+        Ok(cap)
+    }
+
+    /// Busca capability por handle (`None` se vazia ou revogada)
+    pub fn lookup(&self, handle: CapHandle) -> Option<&Capability> {
+        self.get(handle).ok()
     }
 
     /// Busca capability mutável
     pub fn lookup_mut(&mut self, handle: CapHandle) -> Option<&mut Capability> {
-        let index = handle.as_u32() as usize;
-        if index >= CSPACE_SIZE {
-            return None;
-        }
-        self.slots[index].as_mut()
+        self.lookup(handle)?;
+        self.slots[handle.as_u32() as usize].as_mut()
     }
 
     /// Remove capability
@@ -84,9 +96,9 @@ impl CSpace {
         cap
     }
 
-    /// Duplica capability
+    /// Duplica capability (a cópia é derivada da original)
     pub fn duplicate(&mut self, handle: CapHandle) -> Option<CapHandle> {
-        let cap = self.lookup(handle)?.clone();
+        let cap = self.lookup(handle)?.child();
 
         if !cap.rights.has(CapRights::DUPLICATE) {
             return None;
@@ -95,21 +107,129 @@ impl CSpace {
         self.insert(cap)
     }
 
+    /// Concede a `target` uma capability derivada de `handle` com `rights`
+    /// (limitados aos direitos da origem).
+    pub fn grant(
+        &self,
+        handle: CapHandle,
+        rights: CapRights,
+        target: &mut CSpace,
+    ) -> Result<CapHandle, CapError> {
+        let derived = self
+            .check(handle, CapRights::GRANT)?
+            .derive(rights)
+            .ok_or(CapError::InsufficientRights)?;
+        target.insert(derived).ok_or(CapError::CSpaceFull)
+    }
+
+    /// Revoga `handle` e, transitivamente, todas as capabilities derivadas
+    /// dela, em qualquer CSpace. Exige `REVOKE`.
+    ///
+    /// O slot continua ocupado; acessos posteriores retornam `Revoked` até
+    /// ele ser removido. Retorna quantas capabilities foram invalidadas.
+    pub fn revoke(&mut self, handle: CapHandle) -> Result<usize, CapError> {
+        let id = self.check(handle, CapRights::REVOKE)?.id;
+        Ok(derivation::revoke(id))
+    }
+
+    /// Verifica direitos, retornando a capability ou o motivo da falha
+    pub fn check(&self, handle: CapHandle, required: CapRights) -> Result<&Capability, CapError> {
+        let cap = self.get(handle)?;
+        if !cap.rights.has(required) {
+            return Err(CapError::InsufficientRights);
+        }
+        Ok(cap)
+    }
+
     /// Verifica se handle tem direito específico
     pub fn check_rights(&self, handle: CapHandle, required: CapRights) -> bool {
-        match self.lookup(handle) {
-            Some(cap) => cap.rights.has(required),
-            None => false,
-        }
+        self.check(handle, required).is_ok()
     }
 }
 
 /// Erro de capability
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CapError {
     InvalidHandle,
     InsufficientRights,
     TypeMismatch,
     CSpaceFull,
     NotTransferable,
+    /// A capability (ou uma de suas origens) foi revogada
+    Revoked,
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::super::CapType;
+    use super::*;
+    use alloc::boxed::Box;
+
+    #[test]
+    fn test_revoke_propagates_to_derived() {
+        let mut a = Box::new(CSpace::new());
+        let mut b = Box::new(CSpace::new());
+        let mut c = Box::new(CSpace::new());
+
+        let root = a
+            .insert(Capability::new(CapType::Port, CapRights::ALL, 7))
+            .unwrap();
+        let in_b = a.grant(root, CapRights::ALL, &mut b).unwrap();
+        let in_c = b
+            .grant(in_b, CapRights::READ.union(CapRights::GRANT), &mut c)
+            .unwrap();
+        assert!(c.check_rights(in_c, CapRights::READ));
+        assert_eq!(
+            c.check(in_c, CapRights::WRITE).err(),
+            Some(CapError::InsufficientRights)
+        );
+
+        assert_eq!(a.revoke(root), Ok(3));
+
+        assert_eq!(
+            a.check(root, CapRights::READ).err(),
+            Some(CapError::Revoked)
+        );
+        assert_eq!(
+            b.check(in_b, CapRights::READ).err(),
+            Some(CapError::Revoked)
+        );
+        assert_eq!(
+            c.check(in_c, CapRights::READ).err(),
+            Some(CapError::Revoked)
+        );
+        assert!(!c.check_rights(in_c, CapRights::READ));
+
+        // Derivar de uma capability morta também falha
+        let mut d = Box::new(CSpace::new());
+        assert_eq!(
+            c.grant(in_c, CapRights::READ, &mut d).err(),
+            Some(CapError::Revoked)
+        );
+    }
+
+    #[test]
+    fn test_revoke_spares_siblings_and_ancestors() {
+        let mut a = Box::new(CSpace::new());
+        let mut b = Box::new(CSpace::new());
+
+        let root = a
+            .insert(Capability::new(CapType::Memory, CapRights::ALL, 1))
+            .unwrap();
+        let left = a.grant(root, CapRights::ALL, &mut b).unwrap();
+        let right = a.grant(root, CapRights::READ, &mut b).unwrap();
+        let dup = b.duplicate(left).unwrap();
+
+        assert_eq!(b.revoke(left), Ok(2));
+        assert_eq!(b.get(dup).err(), Some(CapError::Revoked));
+        assert!(b.check_rights(right, CapRights::READ));
+        assert!(a.check_rights(root, CapRights::ALL));
+
+        // Sem REVOKE não é possível revogar
+        assert_eq!(b.revoke(right).err(), Some(CapError::InsufficientRights));
+    }
 }
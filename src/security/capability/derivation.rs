@@ -0,0 +1,83 @@
+//! Árvore de derivação de capabilities
+//!
+//! Toda capability inserida em um `CSpace` recebe um ID global e um nó nesta
+//! árvore, ligado ao nó da capability de onde foi derivada (`grant` ou
+//! `duplicate`). Revogar remove o nó e toda a subárvore; uma capability cujo
+//! ID não está mais na árvore é considerada revogada, em qualquer `CSpace`.
+//!
+//! ## Invariante: sem ciclos
+//! IDs são monotônicos e um nó só é criado com um pai ainda vivo, então o pai
+//! sempre tem ID menor que o filho. Nenhum caminho filho → pai pode voltar a
+//! um nó mais novo, e a revogação percorre a subárvore sem recursão.
+
+use crate::sync::Mutex;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// ID de capability fora da árvore (nunca inserida em um CSpace)
+pub const CAP_ID_NONE: u64 = 0;
+
+static NEXT_CAP_ID: AtomicU64 = AtomicU64::new(1);
+
+struct Node {
+    parent: u64,
+    children: Vec<u64>,
+}
+
+/// Nós vivos por ID
+static TREE: Mutex<BTreeMap<u64, Node>> = Mutex::new(BTreeMap::new());
+
+/// Registra uma capability nova derivada de `parent` (`CAP_ID_NONE` = raiz).
+///
+/// Se o pai já foi revogado, o ID é alocado mas não entra na árvore: a
+/// capability nasce revogada.
+pub fn register(parent: u64) -> u64 {
+    let id = NEXT_CAP_ID.fetch_add(1, Ordering::SeqCst);
+    let mut tree = TREE.lock();
+
+    if parent != CAP_ID_NONE {
+        debug_assert!(parent < id, "derivação fora de ordem");
+        match tree.get_mut(&parent) {
+            Some(node) => node.children.push(id),
+            None => return id,
+        }
+    }
+
+    tree.insert(
+        id,
+        Node {
+            parent,
+            children: Vec::new(),
+        },
+    );
+    id
+}
+
+/// A capability `id` ainda está viva?
+pub fn is_live(id: u64) -> bool {
+    TREE.lock().contains_key(&id)
+}
+
+/// Revoga `id` e todos os descendentes. Retorna quantos nós foram removidos.
+pub fn revoke(id: u64) -> usize {
+    let mut tree = TREE.lock();
+
+    let parent = match tree.get(&id) {
+        Some(node) => node.parent,
+        None => return 0,
+    };
+    if let Some(node) = tree.get_mut(&parent) {
+        node.children.retain(|&child| child != id);
+    }
+
+    let mut removed = 0;
+    let mut pending = alloc::vec![id];
+    while let Some(current) = pending.pop() {
+        if let Some(node) = tree.remove(&current) {
+            pending.extend(node.children);
+            removed += 1;
+        }
+    }
+    removed
+}
@@ -4,6 +4,7 @@
 
 pub mod cap;
 pub mod cspace;
+pub mod derivation;
 pub mod rights; // It was in the directory listing
 
 pub use cap::{CapHandle, CapType, Capability};
@@ -85,10 +85,3 @@ pub fn init() {
     // Inicializar CSpace global do kernel
     crate::kinfo!("(Security) Segurança inicializada");
 }
-
-// =============================================================================
-// TESTS
-// =============================================================================
-
-#[cfg(feature = "self_test")]
-pub mod test;
@@ -1,55 +1,67 @@
 //! Condition Variable
+//!
+//! ## Wakeups perdidos
+//! `wait` lê o contador de notificações com o mutex ainda adquirido e só
+//! bloqueia se ele não mudou, decidindo sob o lock da `WaitQueue`. Um
+//! `notify_*` incrementa o contador antes de tomar o lock da fila: ou a
+//! espera vê o contador novo e não bloqueia, ou já está na fila e é acordada.
 
+use crate::sched::sync::WaitQueue;
 use crate::sync::mutex::MutexGuard;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// Condition Variable
 /// Permite que threads esperem por uma condição específica.
 pub struct CondVar {
-    // TODO: Usar WaitQueue do scheduler
-    // Por enquanto, usamos um contador de notificações para spin-waiting (ineficiente, mas funcional para boot)
+    /// Notificações emitidas
     signal_counter: AtomicUsize,
+    waiters: WaitQueue,
 }
 
 impl CondVar {
     pub const fn new() -> Self {
         Self {
             signal_counter: AtomicUsize::new(0),
+            waiters: WaitQueue::new(),
         }
     }
 
     /// Espera pela condição.
-    /// Libera o lock atômica e dorme (simulado com spin) até ser notificado.
-    pub fn wait<T>(&self, _guard: &mut MutexGuard<'_, T>) {
-        let current_signal = self.signal_counter.load(Ordering::Relaxed);
-
-        // 1. Liberar Mutex (Drop manual simulado ou suporte no Mutex)
-        // Como MutexGuard não tem unlock_and_sleep, vamos apenas...
-        // Essa implementação REQUER suporte do Scheduler para ser correta e eficiente.
-        // Implementação dummy apenas para compilação e interfaces.
-
-        // TODO: guard.lock.unlock();
-        // TODO: scheduler.wait(self);
-        // TODO: guard.lock.lock();
+    /// Libera o mutex, dorme até ser notificado e o adquire de novo antes de
+    /// retornar. Wakeups espúrios são possíveis: prefira [`CondVar::wait_while`].
+    pub fn wait<T>(&self, guard: &mut MutexGuard<'_, T>) {
+        let current_signal = self.signal_counter.load(Ordering::SeqCst);
+        guard.unlocked(|| {
+            self.waiters
+                .wait_if(|| self.signal_counter.load(Ordering::SeqCst) == current_signal);
+        });
+    }
 
-        // Placeholder busy-wait:
-        loop {
-            if self.signal_counter.load(Ordering::Relaxed) != current_signal {
-                break;
-            }
-            core::hint::spin_loop();
+    /// Espera enquanto `predicate` for verdadeiro, reavaliando a cada
+    /// notificação com o mutex adquirido (como `std::sync::Condvar::wait_while`).
+    pub fn wait_while<'a, T, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut predicate: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while predicate(&mut *guard) {
+            self.wait(&mut guard);
         }
+        guard
     }
 
     /// Acorda uma thread esperando.
     pub fn notify_one(&self) {
-        self.signal_counter.fetch_add(1, Ordering::Relaxed);
-        // TODO: scheduler.wake_one(self);
+        self.signal_counter.fetch_add(1, Ordering::SeqCst);
+        self.waiters.wake_many(1);
     }
 
     /// Acorda todas as threads esperando.
     pub fn notify_all(&self) {
-        self.signal_counter.fetch_add(1, Ordering::Relaxed);
-        // TODO: scheduler.wake_all(self);
+        self.signal_counter.fetch_add(1, Ordering::SeqCst);
+        self.waiters.wake_many(usize::MAX);
     }
 }
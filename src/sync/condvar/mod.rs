@@ -1,3 +1,4 @@
 //! Condition Variable implementation
 
 pub mod condvar;
+pub use condvar::CondVar;
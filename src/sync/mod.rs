@@ -5,13 +5,22 @@
 pub mod atomic;
 pub mod condvar;
 pub mod mutex;
+pub mod order;
 pub mod rcu;
 pub mod rwlock;
 pub mod semaphore;
 pub mod spinlock;
 
 pub use atomic::{AtomicCell, AtomicCounter, AtomicFlag};
+pub use condvar::CondVar;
 pub use mutex::Mutex;
 pub use rwlock::RwLock;
 pub use semaphore::Semaphore;
 pub use spinlock::{Spinlock, SpinlockGuard};
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(feature = "self_test")]
+pub mod test;
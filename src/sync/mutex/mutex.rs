@@ -1,22 +1,54 @@
 //! Mutex - pode bloquear thread
+//!
+//! ## Herança de prioridade
+//! O mutex guarda o TID do dono. Quem encontra o lock ocupado empresta a sua
+//! prioridade efetiva ao dono (`sched::core::inherit`) uma vez por dono, e o
+//! empréstimo é desfeito quando o dono libera o lock. Assim um dono de
+//! prioridade baixa não fica fora da CPU enquanto uma task de prioridade alta
+//! espera por ele.
+//!
+//! Sem task atual identificável (boot, ou `CURRENT` adquirido pelo próprio
+//! chamador) o mutex funciona sem herança.
+//!
+//! ## Espera
+//! Quem encontra o lock ocupado dorme na `WaitQueue` do mutex; `unlock`
+//! acorda um esperador, que disputa o lock de novo. A decisão de dormir é
+//! tomada com o lock da fila (como em `Semaphore`): `unlock` libera o lock
+//! antes de acordar, então o esperador ou vê o lock livre ou já está na fila.
+//! Sem task que possa bloquear (boot, idle ou interrupções desligadas) a
+//! espera gira.
 
-use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use core::cell::UnsafeCell;
 use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::sched::core::inherit;
+use crate::sched::sync::WaitQueue;
+use crate::sync::order::{LockLevel, UNORDERED};
+use crate::sys::types::Tid;
 
 /// Mutex - bloqueia thread se não conseguir lock
-/// 
+///
 /// # Diferença do Spinlock
-/// 
+///
 /// - Mutex PODE dormir (chama scheduler)
 /// - Spinlock NÃO pode dormir (busy-wait)
-/// 
-/// Use Mutex para seções mais longas.
+///
+/// Use Mutex para seções mais longas. A espera e o `unlock` passam pelo
+/// scheduler, então o mutex não serve para proteger o próprio heap nem
+/// pode ser liberado com a `RunQueue` adquirida.
 pub struct Mutex<T> {
     /// Estado do lock
     locked: AtomicBool,
-    /// ID do owner (para debug)
+    /// TID do dono (0 = livre ou dono desconhecido)
     owner: AtomicU32,
+    /// Algum esperador emprestou prioridade ao dono atual
+    boosted: AtomicBool,
+    /// Tasks dormindo até o `unlock`
+    waiters: WaitQueue,
+    /// Nível na ordem de aquisição (ver `sync::order`)
+    #[cfg_attr(not(feature = "lock_order"), allow(dead_code))]
+    level: LockLevel,
     /// Dados protegidos
     data: UnsafeCell<T>,
 }
@@ -27,52 +59,119 @@ unsafe impl<T: Send> Sync for Mutex<T> {}
 
 impl<T> Mutex<T> {
     pub const fn new(data: T) -> Self {
+        Self::with_level(data, UNORDERED)
+    }
+
+    /// Cria mutex com nível na ordem de aquisição (ver `sync::order`)
+    pub const fn with_level(data: T, level: LockLevel) -> Self {
         Self {
             locked: AtomicBool::new(false),
             owner: AtomicU32::new(0),
+            boosted: AtomicBool::new(false),
+            waiters: WaitQueue::new(),
+            level,
             data: UnsafeCell::new(data),
         }
     }
-    
+
+    /// Identifica este mutex na tabela de herança
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
     /// Adquire o lock (pode bloquear)
     pub fn lock(&self) -> MutexGuard<'_, T> {
-        // Tentar adquirir
-        while self.locked.compare_exchange_weak(
-            false,
-            true,
-            Ordering::Acquire,
-            Ordering::Relaxed
-        ).is_err() {
-            // TODO: Integrar com scheduler para dormir
-            // Por enquanto, spin
-            core::hint::spin_loop();
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::before_lock(self.id(), self.level);
+
+        let me = inherit::current();
+        // Dono que já recebeu a prioridade desta task
+        let mut lent_to = 0;
+
+        loop {
+            if let Some(guard) = self.acquire(me.map(|(tid, _)| tid)) {
+                return guard;
+            }
+
+            let owner = self.owner.load(Ordering::SeqCst);
+            if let Some((_, priority)) = me {
+                if owner != 0 && owner != lent_to {
+                    // Publicado antes de conferir o dono: pareia com a ordem
+                    // inversa em `unlock` (ver lá)
+                    self.boosted.store(true, Ordering::SeqCst);
+                    inherit::inherit(Tid::new(owner), self.id(), priority, || {
+                        self.owner.load(Ordering::SeqCst) == owner
+                    });
+                    lent_to = owner;
+                }
+            }
+
+            if can_block(me) {
+                // O dono, promovido, roda antes de nós
+                self.waiters.wait_if(|| self.locked.load(Ordering::Acquire));
+            } else {
+                core::hint::spin_loop();
+            }
         }
-        
-        MutexGuard { lock: self }
     }
-    
+
     /// Tenta adquirir sem bloquear
     pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
-        if self.locked.compare_exchange(
-            false,
-            true,
-            Ordering::Acquire,
-            Ordering::Relaxed
-        ).is_ok() {
-            Some(MutexGuard { lock: self })
-        } else {
-            None
+        self.acquire(inherit::current().map(|(tid, _)| tid))
+    }
+
+    fn acquire(&self, me: Option<Tid>) -> Option<MutexGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        self.owner
+            .store(me.map_or(0, Tid::as_u32), Ordering::SeqCst);
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::locked(self.id(), self.level);
+        Some(MutexGuard { lock: self })
+    }
+
+    /// Libera o lock e desfaz empréstimos de prioridade ao dono.
+    ///
+    /// O dono é limpo ANTES de ler `boosted`, e o esperador marca `boosted`
+    /// ANTES de conferir o dono: ou vemos a marca e removemos o empréstimo,
+    /// ou o esperador vê o dono já trocado e não empresta nada.
+    fn unlock(&self) {
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::unlocked(self.id(), self.level);
+
+        let owner = self.owner.swap(0, Ordering::SeqCst);
+        if self.boosted.swap(false, Ordering::SeqCst) && owner != 0 {
+            inherit::release(Tid::new(owner), self.id());
         }
+        self.locked.store(false, Ordering::Release);
+        self.waiters.wake_many(1);
     }
 }
 
+/// A task atual pode dormir na fila do mutex: existe, não é a idle e está
+/// com interrupções ligadas
+fn can_block(me: Option<(Tid, u8)>) -> bool {
+    me.is_some_and(|(tid, _)| tid.as_u32() != 0) && crate::arch::Cpu::interrupts_enabled()
+}
+
 pub struct MutexGuard<'a, T> {
     lock: &'a Mutex<T>,
 }
 
+impl<T> MutexGuard<'_, T> {
+    /// Executa `f` com o mutex liberado e o adquire de novo antes de retornar
+    pub(crate) fn unlocked<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.lock.unlock();
+        let result = f();
+        core::mem::forget(self.lock.lock());
+        result
+    }
+}
+
 impl<T> Deref for MutexGuard<'_, T> {
     type Target = T;
-    
+
     fn deref(&self) -> &T {
         // SAFETY: Lock está adquirido
         unsafe { &*self.lock.data.get() }
@@ -88,8 +187,6 @@ impl<T> DerefMut for MutexGuard<'_, T> {
 
 impl<T> Drop for MutexGuard<'_, T> {
     fn drop(&mut self) {
-        self.lock.owner.store(0, Ordering::Release);
-        self.lock.locked.store(false, Ordering::Release);
-        // TODO: Acordar threads esperando
+        self.lock.unlock();
     }
 }
@@ -0,0 +1,248 @@
+//! # Ordem de Locks
+//!
+//! Verificação da ordem de aquisição de `Spinlock`/`Mutex`, ativa só com a
+//! feature `lock_order` (sem ela os locks não chamam nada daqui).
+//!
+//! ## Níveis
+//! Cada lock criado com `with_level` tem um [`LockLevel`]; os criados com
+//! `new` ficam em [`UNORDERED`] e não são verificados. Locks devem ser
+//! adquiridos em nível crescente: esperar por um lock de nível menor que o
+//! maior nível já segurado na CPU é uma violação. Ela sai em `kerror!` com os
+//! dois locks (endereço e nível) e conta em [`violations`], mas o lock segue
+//! normalmente.
+//!
+//! `try_lock` não espera, então não é verificado; o lock obtido entra na
+//! pilha como qualquer outro.
+//!
+//! ## Pilha por CPU
+//! Os locks segurados ficam em `PerCpu::held_locks`. Um `Mutex` segurado por
+//! uma task preemptada continua na pilha da CPU em que foi adquirido, e
+//! pode acusar uma violação falsa para a task que rodar em seguida.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Nível de um lock na ordem de aquisição (menor = adquirido antes)
+pub type LockLevel = u8;
+
+/// Nível dos locks fora da verificação
+pub const UNORDERED: LockLevel = 0;
+
+/// Locks com nível segurados ao mesmo tempo numa CPU
+const MAX_HELD: usize = 16;
+
+static VIOLATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Lock com nível, identificado pelo endereço
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeldLock {
+    pub id: usize,
+    pub level: LockLevel,
+}
+
+/// `acquiring` pedido com `held`, de nível maior, segurado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub held: HeldLock,
+    pub acquiring: HeldLock,
+}
+
+/// Locks segurados por uma CPU, na ordem de aquisição
+struct LockStack {
+    locks: [HeldLock; MAX_HELD],
+    depth: usize,
+    /// Aquisições que não couberam em `locks`
+    overflow: usize,
+}
+
+impl LockStack {
+    const fn new() -> Self {
+        Self {
+            locks: [HeldLock { id: 0, level: 0 }; MAX_HELD],
+            depth: 0,
+            overflow: 0,
+        }
+    }
+
+    /// Violação que esperar por `lock` agora causaria (o lock segurado de
+    /// maior nível acima do de `lock`)
+    fn check(&self, lock: HeldLock) -> Option<Violation> {
+        self.locks[..self.depth]
+            .iter()
+            .filter(|held| held.level > lock.level)
+            .max_by_key(|held| held.level)
+            .map(|&held| Violation {
+                held,
+                acquiring: lock,
+            })
+    }
+
+    fn push(&mut self, lock: HeldLock) {
+        match self.locks.get_mut(self.depth) {
+            Some(slot) => {
+                *slot = lock;
+                self.depth += 1;
+            }
+            None => self.overflow += 1,
+        }
+    }
+
+    /// Remove `id` (a liberação não precisa seguir a ordem inversa)
+    fn remove(&mut self, id: usize) {
+        match self.locks[..self.depth]
+            .iter()
+            .rposition(|held| held.id == id)
+        {
+            Some(pos) => {
+                self.locks.copy_within(pos + 1..self.depth, pos);
+                self.depth -= 1;
+            }
+            None => self.overflow = self.overflow.saturating_sub(1),
+        }
+    }
+}
+
+/// Pilha de locks de uma CPU, guardada na área por CPU
+pub struct HeldLocks(UnsafeCell<LockStack>);
+
+// SAFETY: só a própria CPU acessa a sua pilha, com interrupções desligadas
+unsafe impl Sync for HeldLocks {}
+
+impl HeldLocks {
+    pub const fn new() -> Self {
+        Self(UnsafeCell::new(LockStack::new()))
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut LockStack) -> R) -> R {
+        let interrupts_enabled = crate::arch::Cpu::interrupts_enabled();
+        crate::arch::Cpu::disable_interrupts();
+        // SAFETY: CPU atual, sem interrupções; `f` não adquire locks
+        let result = f(unsafe { &mut *self.0.get() });
+        if interrupts_enabled {
+            crate::arch::Cpu::enable_interrupts();
+        }
+        result
+    }
+}
+
+impl Default for HeldLocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pilha da CPU atual (`None` antes do `GS` base da área ser instalado)
+fn held_locks() -> Option<&'static HeldLocks> {
+    crate::core::smp::percpu::try_this_cpu().map(|cpu| &cpu.held_locks)
+}
+
+/// Chamado antes de esperar pelo lock `id`: reporta violação de ordem
+pub fn before_lock(id: usize, level: LockLevel) {
+    if level == UNORDERED {
+        return;
+    }
+    let Some(held) = held_locks() else {
+        return;
+    };
+    // O log adquire locks: sai depois de devolver a pilha
+    if let Some(violation) = held.with(|stack| stack.check(HeldLock { id, level })) {
+        report(violation);
+    }
+}
+
+/// Chamado com o lock `id` adquirido
+pub fn locked(id: usize, level: LockLevel) {
+    if level == UNORDERED {
+        return;
+    }
+    if let Some(held) = held_locks() {
+        held.with(|stack| stack.push(HeldLock { id, level }));
+    }
+}
+
+/// Chamado ao liberar o lock `id`
+pub fn unlocked(id: usize, level: LockLevel) {
+    if level == UNORDERED {
+        return;
+    }
+    if let Some(held) = held_locks() {
+        held.with(|stack| stack.remove(id));
+    }
+}
+
+/// Violações reportadas desde o boot
+pub fn violations() -> usize {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+fn report(violation: Violation) {
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    crate::kerror!(
+        "(Sync) Ordem de locks violada! Adquirindo lock:",
+        violation.acquiring.id
+    );
+    crate::kerror!("(Sync)   nível:", violation.acquiring.level);
+    crate::kerror!(
+        "(Sync)   com lock de nível maior segurado:",
+        violation.held.id
+    );
+    crate::kerror!("(Sync)   nível:", violation.held.level);
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(id: usize, level: LockLevel) -> HeldLock {
+        HeldLock { id, level }
+    }
+
+    #[test]
+    fn test_increasing_order_is_accepted() {
+        let mut stack = LockStack::new();
+        for (id, level) in [(0x10, 1), (0x20, 2), (0x30, 2), (0x40, 5)] {
+            assert_eq!(stack.check(lock(id, level)), None);
+            stack.push(lock(id, level));
+        }
+    }
+
+    #[test]
+    fn test_lower_level_reports_highest_held() {
+        let mut stack = LockStack::new();
+        stack.push(lock(0x10, 3));
+        stack.push(lock(0x20, 7));
+        stack.push(lock(0x30, 5));
+
+        assert_eq!(
+            stack.check(lock(0x40, 4)),
+            Some(Violation {
+                held: lock(0x20, 7),
+                acquiring: lock(0x40, 4),
+            })
+        );
+
+        // Liberado fora de ordem: sobra só o nível 3
+        stack.remove(0x20);
+        stack.remove(0x30);
+        assert_eq!(stack.check(lock(0x40, 4)), None);
+        stack.remove(0x10);
+        assert_eq!(stack.depth, 0);
+    }
+
+    #[test]
+    fn test_overflow_keeps_stack_balanced() {
+        let mut stack = LockStack::new();
+        for id in 0..MAX_HELD + 2 {
+            stack.push(lock(id, 1));
+        }
+        assert_eq!(stack.overflow, 2);
+        for id in (0..MAX_HELD + 2).rev() {
+            stack.remove(id);
+        }
+        assert_eq!((stack.depth, stack.overflow), (0, 0));
+    }
+}
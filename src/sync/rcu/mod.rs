@@ -1,3 +1,7 @@
 //! RCU implementation
 
 pub mod rcu;
+pub use rcu::{
+    call_rcu, note_context_switch, poll_grace_period, rcu_read_lock, rcu_read_unlock,
+    start_grace_period, synchronize_rcu, Rcu, RcuReadGuard,
+};
@@ -1,75 +1,201 @@
 //! Read-Copy-Update (RCU)
 //! Mecanismo de sincronização otimizado para cenários com muitas leituras e poucas escritas.
+//!
+//! ## Leitura
+//! [`rcu_read_lock`]/[`rcu_read_unlock`] marcam uma seção de leitura na CPU
+//! atual (contador de aninhamento na área por CPU). Dentro dela o leitor
+//! segue ponteiros publicados sem lock, e não pode trocar de contexto
+//! (`yield_now`, dormir, bloquear). Como o kernel não preempta código de
+//! kernel, nada mais é preciso.
+//!
+//! ## Grace period
+//! Uma troca de contexto é um estado quiescente: a CPU não está em nenhuma
+//! seção de leitura. `schedule()` registra na área por CPU o número do
+//! último grace period iniciado ([`note_context_switch`]). Um grace period
+//! termina quando toda CPU online passou por um estado quiescente depois do
+//! seu início, ou foi vista fora de seção de leitura (CPU ociosa, que não
+//! troca de contexto).
+//!
+//! - [`synchronize_rcu`]: espera um grace period (cedendo a CPU).
+//! - [`call_rcu`]: adia um callback (tipicamente liberar a versão antiga)
+//!   para depois do próximo grace period; roda no worker do sistema.
+//!
+//! A área por CPU precisa estar instalada (`init_basics` no BSP, bringup nos
+//! APs) antes de qualquer uso.
 
-use alloc::sync::Arc;
-use core::sync::atomic::{AtomicPtr, Ordering};
+use crate::core::smp::percpu::{self, MAX_CPUS};
+use crate::sync::Spinlock;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+
+/// Último grace period iniciado
+static GP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Callbacks de [`call_rcu`], com o grace period que esperam (em ordem)
+type Callback = Box<dyn FnOnce() + Send>;
+static CALLBACKS: Spinlock<VecDeque<(u64, Callback)>> = Spinlock::new(VecDeque::new());
+
+/// Entra numa seção de leitura (aninhável)
+pub fn rcu_read_lock() {
+    percpu::this_cpu()
+        .rcu_nesting
+        .fetch_add(1, Ordering::SeqCst);
+}
+
+/// Sai da seção de leitura aberta por [`rcu_read_lock`]
+pub fn rcu_read_unlock() {
+    let previous = percpu::this_cpu()
+        .rcu_nesting
+        .fetch_sub(1, Ordering::SeqCst);
+    debug_assert!(previous > 0, "(RCU) rcu_read_unlock sem rcu_read_lock");
+}
+
+/// Registra um estado quiescente na CPU atual (troca de contexto).
+///
+/// Chamado pelo scheduler; ignorado dentro de uma seção de leitura, que não
+/// pode trocar de contexto.
+pub fn note_context_switch() {
+    let cpu = percpu::this_cpu();
+    if cpu.rcu_nesting.load(Ordering::SeqCst) != 0 {
+        crate::kerror!(
+            "(RCU) Troca de contexto dentro de seção de leitura! CPU:",
+            cpu.cpu_id
+        );
+        return;
+    }
+    cpu.rcu_quiescent
+        .store(GP_SEQ.load(Ordering::SeqCst), Ordering::SeqCst);
+}
+
+/// Inicia um grace period e retorna o cookie para [`poll_grace_period`]
+pub fn start_grace_period() -> u64 {
+    GP_SEQ.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// O grace period `cookie` terminou: nenhuma seção de leitura anterior a
+/// ele continua aberta
+pub fn poll_grace_period(cookie: u64) -> bool {
+    (0..MAX_CPUS)
+        .filter_map(|id| percpu::area(id as _))
+        .all(|cpu| {
+            cpu.rcu_quiescent.load(Ordering::SeqCst) >= cookie
+                || cpu.rcu_nesting.load(Ordering::SeqCst) == 0
+        })
+}
+
+/// Espera até todas as seções de leitura abertas agora terminarem.
+///
+/// Cede a CPU enquanto espera: não pode ser chamada dentro de uma seção de
+/// leitura nem com spinlocks adquiridos.
+pub fn synchronize_rcu() {
+    let cookie = start_grace_period();
+    while !poll_grace_period(cookie) {
+        crate::sched::core::yield_or_spin();
+    }
+}
+
+/// Executa `callback` depois do próximo grace period, no worker do sistema.
+///
+/// Seguro em seção de leitura e em contexto de interrupção.
+pub fn call_rcu<F>(callback: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let cookie = start_grace_period();
+    CALLBACKS.lock().push_back((cookie, Box::new(callback)));
+    crate::core::work::schedule(run_callbacks);
+}
+
+/// Roda, em ordem, os callbacks pendentes cujo grace period terminou
+fn run_callbacks() {
+    while let Some(cookie) = next_cookie() {
+        while !poll_grace_period(cookie) {
+            crate::sched::core::yield_or_spin();
+        }
+        let callback = {
+            let mut callbacks = CALLBACKS.lock();
+            match callbacks.front() {
+                Some((front, _)) if *front == cookie => callbacks.pop_front(),
+                _ => None,
+            }
+        };
+        if let Some((_, callback)) = callback {
+            callback();
+        }
+    }
+}
+
+fn next_cookie() -> Option<u64> {
+    CALLBACKS.lock().front().map(|(cookie, _)| *cookie)
+}
 
 /// Container RCU para dados compartilhados
-pub struct Rcu<T> {
-    // Ponteiro atômico para os dados (gerenciado via Arc internamente, mas mantido como raw ptr)
+///
+/// Leitores ([`Rcu::read`]) não travam; [`Rcu::update`] publica uma versão
+/// nova e libera a antiga só depois de um grace period.
+pub struct Rcu<T: Send + 'static> {
     inner: AtomicPtr<T>,
 }
 
-impl<T> Rcu<T> {
+// SAFETY: leitores de várias CPUs veem `&T`; a versão antiga é liberada em
+// outra thread
+unsafe impl<T: Send + Sync> Send for Rcu<T> {}
+unsafe impl<T: Send + Sync> Sync for Rcu<T> {}
+
+impl<T: Send + 'static> Rcu<T> {
     pub fn new(data: T) -> Self {
-        let ptr = Arc::into_raw(Arc::new(data)) as *mut T;
         Self {
-            inner: AtomicPtr::new(ptr),
+            inner: AtomicPtr::new(Box::into_raw(Box::new(data))),
         }
     }
 
-    /// Leitura RCU (block-free)
-    pub fn read(&self) -> RcuReadGuard<T> {
+    /// Leitura RCU (sem lock); a versão lida vale até o guard sair de escopo
+    pub fn read(&self) -> RcuReadGuard<'_, T> {
+        rcu_read_lock();
         let ptr = self.inner.load(Ordering::Acquire);
-        // Em um sistema RCU real, precisaríamos de barreiras de memória e rastreamento de "epoch"
-        // para garantir que o ponteiro não seja deletado enquanto lemos.
-        // Aqui, assumimos que Arc + vazamento controlado (ou epoch-based reclamation futura) segura as pontas.
-
-        // Incrementamos refcount do Arc para segurança simples (incur custo de atomicidade, mas é seguro)
-        unsafe {
-            Arc::increment_strong_count(ptr);
-        }
-
         RcuReadGuard {
-            ptr: unsafe { &*ptr },
-            raw: ptr,
+            // SAFETY: versões publicadas só são liberadas depois de um grace
+            // period, e a seção de leitura está aberta
+            data: unsafe { &*ptr },
+            _not_send: PhantomData,
         }
     }
 
     /// Atualização RCU (writer)
-    /// Cria uma nova versão e troca o ponteiro.
+    /// Publica a nova versão; a antiga é liberada via [`call_rcu`].
     pub fn update(&self, new_data: T) {
-        let new_ptr = Arc::into_raw(Arc::new(new_data)) as *mut T;
-
-        // Troca atômica do ponteiro
-        let old_ptr = self.inner.swap(new_ptr, Ordering::AcqRel);
+        let new_ptr = Box::into_raw(Box::new(new_data));
+        let old = self.inner.swap(new_ptr, Ordering::AcqRel) as usize;
+        // SAFETY: `old` saiu de `Box::into_raw` e não é mais alcançável por
+        // leitores novos; os antigos terminam antes do grace period
+        call_rcu(move || drop(unsafe { Box::from_raw(old as *mut T) }));
+    }
+}
 
-        // O dado antigo (old_ptr) só pode ser liberado quando todos os leitores terminarem (Grace Period).
-        // TODO: Implementar Grace Period tracking (call_rcu).
-        // Por enquanto, soltamos o Arc, mas se houver leitores eles seguram via increment_strong_count.
-        unsafe {
-            Arc::decrement_strong_count(old_ptr);
-        }
+impl<T: Send + 'static> Drop for Rcu<T> {
+    fn drop(&mut self) {
+        // SAFETY: `&mut self`: não há leitores
+        drop(unsafe { Box::from_raw(*self.inner.get_mut()) });
     }
 }
 
+/// Seção de leitura de um [`Rcu`]; fica na CPU em que foi aberta
 pub struct RcuReadGuard<'a, T> {
-    ptr: &'a T,
-    raw: *const T,
+    data: &'a T,
+    _not_send: PhantomData<*const ()>,
 }
 
 impl<T> core::ops::Deref for RcuReadGuard<'_, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        self.ptr
+        self.data
     }
 }
 
 impl<T> Drop for RcuReadGuard<'_, T> {
     fn drop(&mut self) {
-        // Solta referência
-        unsafe {
-            Arc::decrement_strong_count(self.raw);
-        }
+        rcu_read_unlock();
     }
 }
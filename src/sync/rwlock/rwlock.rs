@@ -1,17 +1,30 @@
 //! Reader-Writer Lock
+//!
+//! ## Políticas
+//! - [`RwLock::new`]: prefere leitores. Um leitor entra sempre que não há
+//!   escritor ativo, então uma carga contínua de leitores pode deixar um
+//!   escritor esperando indefinidamente.
+//! - [`RwLock::new_fair`]: escritor esperando bloqueia leitores novos. Os
+//!   leitores que já estão dentro terminam, o escritor entra e só então os
+//!   leitores voltam. Consequência: ler de novo o mesmo lock com uma leitura
+//!   já aberta pode travar se um escritor chegar no meio.
 
-use core::sync::atomic::{AtomicI32, Ordering};
 use core::cell::UnsafeCell;
 use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 
 /// RwLock - múltiplos leitores OU um escritor
-/// 
+///
 /// Contador:
 /// - 0 = Livre
 /// - N>0 = N leitores ativos
 /// - -1 = Escritor ativo
 pub struct RwLock<T> {
     state: AtomicI32,
+    /// Escritores esperando (só consultado na política justa)
+    writers_waiting: AtomicU32,
+    /// Leitores novos esperam escritores pendentes
+    fair: bool,
     data: UnsafeCell<T>,
 }
 
@@ -19,49 +32,66 @@ unsafe impl<T: Send> Send for RwLock<T> {}
 unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
 
 impl<T> RwLock<T> {
+    /// RwLock que prefere leitores
     pub const fn new(data: T) -> Self {
+        Self::with_policy(data, false)
+    }
+
+    /// RwLock em que escritores esperando têm a vez antes de leitores novos
+    pub const fn new_fair(data: T) -> Self {
+        Self::with_policy(data, true)
+    }
+
+    const fn with_policy(data: T, fair: bool) -> Self {
         Self {
             state: AtomicI32::new(0),
+            writers_waiting: AtomicU32::new(0),
+            fair,
             data: UnsafeCell::new(data),
         }
     }
-    
+
+    /// Leitores novos devem esperar (política justa com escritor pendente)
+    fn readers_blocked(&self) -> bool {
+        self.fair && self.writers_waiting.load(Ordering::Acquire) > 0
+    }
+
     /// Adquire lock de leitura
     pub fn read(&self) -> RwLockReadGuard<'_, T> {
         loop {
             let state = self.state.load(Ordering::Acquire);
-            
-            // Se escritor ativo, esperar
-            if state < 0 {
-                core::hint::spin_loop();
+
+            // Se escritor ativo (ou esperando, na política justa), esperar
+            if state < 0 || self.readers_blocked() {
+                crate::sched::core::yield_or_spin();
                 continue;
             }
-            
+
             // Tentar incrementar leitores
-            if self.state.compare_exchange_weak(
-                state,
-                state + 1,
-                Ordering::Acquire,
-                Ordering::Relaxed
-            ).is_ok() {
+            if self
+                .state
+                .compare_exchange_weak(state, state + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
                 return RwLockReadGuard { lock: self };
             }
         }
     }
-    
+
     /// Adquire lock de escrita
     pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.writers_waiting.fetch_add(1, Ordering::AcqRel);
         loop {
             // Tentar adquirir se livre
-            if self.state.compare_exchange_weak(
-                0,
-                -1,
-                Ordering::Acquire,
-                Ordering::Relaxed
-            ).is_ok() {
+            if self
+                .state
+                .compare_exchange_weak(0, -1, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                self.writers_waiting.fetch_sub(1, Ordering::AcqRel);
                 return RwLockWriteGuard { lock: self };
             }
-            core::hint::spin_loop();
+            crate::sched::core::yield_or_spin();
         }
     }
 }
@@ -1,57 +1,97 @@
 //! Semáforo para controle de recursos
+//!
+//! Quem não encontra permissões bloqueia na `WaitQueue` do semáforo; `release`
+//! devolve a permissão ao contador e acorda os bloqueados, que disputam de
+//! novo. Permissões nunca são entregues diretamente a uma task: uma espera
+//! que vence o prazo junto com um `release` não consome nem perde permissão.
 
 use core::sync::atomic::{AtomicI32, Ordering};
 
+use crate::sched::sync::{TimedOut, WaitQueue};
+
 /// Semáforo de contagem
 pub struct Semaphore {
     count: AtomicI32,
+    waiters: WaitQueue,
 }
 
 impl Semaphore {
     pub const fn new(initial: i32) -> Self {
         Self {
             count: AtomicI32::new(initial),
+            waiters: WaitQueue::new(),
         }
     }
-    
+
     /// Decrementa (P/wait/acquire)
     pub fn acquire(&self) {
+        while !self.try_acquire() {
+            // Decisão de bloquear sob o lock da fila: `release` incrementa
+            // antes de acordar
+            self.waiters
+                .wait_if(|| self.count.load(Ordering::Acquire) <= 0);
+        }
+    }
+
+    /// Tenta decrementar sem bloquear
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_many(1)
+    }
+
+    /// Tenta tirar `count` permissões de uma vez, sem bloquear
+    ///
+    /// `count` acima de `i32::MAX` nunca cabe no contador e falha.
+    pub fn try_acquire_many(&self, count: u32) -> bool {
+        let Ok(count) = i32::try_from(count) else {
+            return false;
+        };
+        let mut current = self.count.load(Ordering::Acquire);
         loop {
-            let count = self.count.load(Ordering::Acquire);
-            if count <= 0 {
-                // Esperar
-                core::hint::spin_loop();
-                continue;
+            if current < count {
+                return false;
             }
-            
-            if self.count.compare_exchange_weak(
-                count,
-                count - 1,
+            match self.count.compare_exchange_weak(
+                current,
+                current - count,
                 Ordering::AcqRel,
-                Ordering::Relaxed
-            ).is_ok() {
-                return;
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
             }
         }
     }
-    
-    /// Tenta decrementar sem bloquear
-    pub fn try_acquire(&self) -> bool {
-        let count = self.count.load(Ordering::Acquire);
-        if count <= 0 {
-            return false;
+
+    /// Tira `count` permissões, bloqueando até `deadline_ns` (relógio de
+    /// `core::time::now_ns`).
+    ///
+    /// Retorna `Err(TimedOut)` se o prazo passou sem as permissões
+    /// disponíveis; nesse caso nenhuma permissão é consumida. `count` acima
+    /// de `i32::MAX` nunca pode ser satisfeito e falha sem esperar.
+    pub fn try_acquire_timeout(&self, count: u32, deadline_ns: u64) -> Result<(), TimedOut> {
+        let needed = i32::try_from(count).map_err(|_| TimedOut)?;
+        loop {
+            if self.try_acquire_many(count) {
+                return Ok(());
+            }
+            let wait = self
+                .waiters
+                .wait_if_timeout(|| self.count.load(Ordering::Acquire) < needed, deadline_ns);
+            if wait.is_err() {
+                // Um `release` pode ter chegado junto com o prazo
+                return if self.try_acquire_many(count) {
+                    Ok(())
+                } else {
+                    Err(TimedOut)
+                };
+            }
         }
-        
-        self.count.compare_exchange(
-            count,
-            count - 1,
-            Ordering::AcqRel,
-            Ordering::Relaxed
-        ).is_ok()
     }
-    
+
     /// Incrementa (V/signal/release)
     pub fn release(&self) {
         self.count.fetch_add(1, Ordering::Release);
+        // Todos reavaliam: cada um pode esperar um número diferente
+        self.waiters.wake_many(usize::MAX);
     }
 }
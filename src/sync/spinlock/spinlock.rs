@@ -4,6 +4,8 @@ use core::cell::UnsafeCell;
 use core::ops::{Deref, DerefMut};
 use core::sync::atomic::{AtomicBool, Ordering};
 
+use crate::sync::order::{LockLevel, UNORDERED};
+
 /// Spinlock - usa busy-wait, NÃO pode dormir
 ///
 /// # Quando usar
@@ -19,6 +21,9 @@ use core::sync::atomic::{AtomicBool, Ordering};
 /// - Para proteger I/O lento
 pub struct Spinlock<T> {
     locked: AtomicBool,
+    /// Nível na ordem de aquisição (ver `sync::order`)
+    #[cfg_attr(not(feature = "lock_order"), allow(dead_code))]
+    level: LockLevel,
     data: UnsafeCell<T>,
 }
 
@@ -29,18 +34,33 @@ unsafe impl<T: Send> Sync for Spinlock<T> {}
 impl<T> Spinlock<T> {
     /// Cria novo spinlock
     pub const fn new(data: T) -> Self {
+        Self::with_level(data, UNORDERED)
+    }
+
+    /// Cria spinlock com nível na ordem de aquisição (ver `sync::order`)
+    pub const fn with_level(data: T, level: LockLevel) -> Self {
         Self {
             locked: AtomicBool::new(false),
+            level,
             data: UnsafeCell::new(data),
         }
     }
 
+    /// Identifica este lock na verificação de ordem
+    #[cfg(feature = "lock_order")]
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
     /// Adquire o lock
     pub fn lock(&self) -> SpinlockGuard<'_, T> {
         // Desabilitar interrupções antes de adquirir
         let interrupts_enabled = crate::arch::Cpu::interrupts_enabled();
         crate::arch::Cpu::disable_interrupts();
 
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::before_lock(self.id(), self.level);
+
         // Spin até conseguir o lock
         while self
             .locked
@@ -51,6 +71,9 @@ impl<T> Spinlock<T> {
             core::hint::spin_loop();
         }
 
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::locked(self.id(), self.level);
+
         SpinlockGuard {
             lock: self,
             interrupts_were_enabled: interrupts_enabled,
@@ -67,6 +90,9 @@ impl<T> Spinlock<T> {
             .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
         {
+            #[cfg(feature = "lock_order")]
+            crate::sync::order::locked(self.id(), self.level);
+
             Some(SpinlockGuard {
                 lock: self,
                 interrupts_were_enabled: interrupts_enabled,
@@ -87,6 +113,9 @@ impl<T> Spinlock<T> {
     /// Extremamente inseguro. Só deve ser usado pelo scheduler ao iniciar
     /// uma nova task que "herdou" o lock da task anterior mas não tem o Guard.
     pub unsafe fn force_unlock(&self) {
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::unlocked(self.id(), self.level);
+
         self.locked.store(false, Ordering::Release);
     }
 }
@@ -115,6 +144,9 @@ impl<T> DerefMut for SpinlockGuard<'_, T> {
 
 impl<T> Drop for SpinlockGuard<'_, T> {
     fn drop(&mut self) {
+        #[cfg(feature = "lock_order")]
+        crate::sync::order::unlocked(self.lock.id(), self.lock.level);
+
         // Liberar lock
         self.lock.locked.store(false, Ordering::Release);
 
@@ -0,0 +1,258 @@
+//! # Testes de Sincronização
+//!
+//! Executados apenas com a feature `self_test`, a partir do fluxo de boot,
+//! com o scheduler e o worker do sistema (`core::work::init`) prontos. O teste
+//! de ordem de locks exige também a feature `lock_order`.
+
+use crate::sched::core::{exit_current, yield_now};
+use crate::sched::sync::TimedOut;
+use crate::sched::test::spawn_kernel_task;
+use crate::sync::condvar::condvar::CondVar;
+use crate::sync::rcu::{self, Rcu};
+use crate::sync::{Mutex, Semaphore, Spinlock};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+pub fn run_tests() {
+    crate::kinfo!("(Sync) Iniciando testes de sincronização...");
+    #[cfg(feature = "lock_order")]
+    lock_order::test_lock_order_violation_reported();
+    test_rcu_reader_delays_reclaim();
+    test_semaphore_timeout_expires();
+    test_semaphore_release_beats_timeout();
+    test_semaphore_rejects_oversized_count();
+    test_condvar_wait_while();
+    crate::kinfo!("(Sync) Testes de sincronização concluídos com SUCESSO.");
+}
+
+/// Exige a feature `lock_order`
+#[cfg(feature = "lock_order")]
+mod lock_order {
+    use crate::sync::{order, Mutex, Spinlock};
+
+    const LOW_LEVEL: order::LockLevel = 1;
+    const HIGH_LEVEL: order::LockLevel = 2;
+
+    static LOW: Spinlock<()> = Spinlock::with_level((), LOW_LEVEL);
+    static HIGH: Mutex<()> = Mutex::with_level((), HIGH_LEVEL);
+
+    /// Nível crescente passa calado; nível menor com um maior segurado é
+    /// reportado uma vez, e a pilha volta a ficar limpa depois.
+    pub fn test_lock_order_violation_reported() {
+        let before = order::violations();
+
+        {
+            let _low = LOW.lock();
+            let _high = HIGH.lock();
+        }
+        assert_eq!(
+            order::violations(),
+            before,
+            "(Sync) Ordem crescente reportada como violação"
+        );
+
+        {
+            let _high = HIGH.lock();
+            let _low = LOW.lock();
+        }
+        assert_eq!(
+            order::violations(),
+            before + 1,
+            "(Sync) Lock fora de ordem não foi reportado"
+        );
+
+        // try_lock fora de ordem não espera: não é violação
+        {
+            let _high = HIGH.lock();
+            assert!(LOW.try_lock().is_some());
+        }
+        {
+            let _low = LOW.lock();
+        }
+        assert_eq!(
+            order::violations(),
+            before + 1,
+            "(Sync) Locks liberados continuaram na pilha"
+        );
+    }
+}
+
+/// Trocas de contexto que o worker tem para liberar a versão antiga
+const RECLAIM_MAX_YIELDS: usize = 1000;
+
+static RECLAIMED: AtomicUsize = AtomicUsize::new(0);
+
+/// Versão de um `Rcu` que conta quando é liberada
+struct Version(u64);
+
+impl Drop for Version {
+    fn drop(&mut self) {
+        RECLAIMED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Um leitor com a versão antiga em mãos impede a liberação dela; depois de
+/// soltá-la, o grace period termina e o `call_rcu` da atualização roda.
+fn test_rcu_reader_delays_reclaim() {
+    RECLAIMED.store(0, Ordering::SeqCst);
+    let data = Rcu::new(Version(1));
+
+    {
+        let old = data.read();
+        let cookie = rcu::start_grace_period();
+        data.update(Version(2));
+
+        assert!(
+            !rcu::poll_grace_period(cookie),
+            "(Sync) Grace period terminou com leitor ativo"
+        );
+        assert_eq!(
+            RECLAIMED.load(Ordering::SeqCst),
+            0,
+            "(Sync) Versão liberada com leitor ativo"
+        );
+        assert_eq!(old.0, 1, "(Sync) Leitor perdeu a versão antiga");
+        assert_eq!(data.read().0, 2, "(Sync) Leitor novo não vê a versão nova");
+    }
+
+    let mut yields = 0;
+    while RECLAIMED.load(Ordering::SeqCst) == 0 && yields < RECLAIM_MAX_YIELDS {
+        yield_now();
+        yields += 1;
+    }
+    assert_eq!(
+        RECLAIMED.load(Ordering::SeqCst),
+        1,
+        "(Sync) Versão antiga não foi liberada depois do grace period"
+    );
+
+    // Sem leitores: retorna sem esperar nada
+    rcu::synchronize_rcu();
+}
+
+/// Prazo da espera que deve vencer
+const SHORT_TIMEOUT_NS: u64 = 20_000_000;
+/// Prazo da espera que um `release` deve interromper
+const LONG_TIMEOUT_NS: u64 = 5_000_000_000;
+
+static SEM: Semaphore = Semaphore::new(0);
+static SEM_TIMEOUT_NS: AtomicU64 = AtomicU64::new(0);
+static SEM_RESULT: Spinlock<Option<(Result<(), TimedOut>, u64)>> = Spinlock::new(None);
+static SEM_DONE: AtomicBool = AtomicBool::new(false);
+
+/// Espera uma permissão de `SEM` e registra o resultado e a hora da volta
+extern "C" fn semaphore_waiter() -> ! {
+    let deadline = crate::core::time::now_ns() + SEM_TIMEOUT_NS.load(Ordering::SeqCst);
+    let result = SEM.try_acquire_timeout(1, deadline);
+    *SEM_RESULT.lock() = Some((result, crate::core::time::now_ns()));
+    SEM_DONE.store(true, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Dispara `semaphore_waiter` com `timeout_ns` e cede a CPU até ele bloquear
+fn start_semaphore_waiter(timeout_ns: u64) -> u64 {
+    *SEM_RESULT.lock() = None;
+    SEM_DONE.store(false, Ordering::SeqCst);
+    SEM_TIMEOUT_NS.store(timeout_ns, Ordering::SeqCst);
+    let start = crate::core::time::now_ns();
+    spawn_kernel_task("sync-test-sem", semaphore_waiter);
+    // O boot só volta a rodar com o waiter bloqueado
+    yield_now();
+    start
+}
+
+fn finish_semaphore_waiter() -> (Result<(), TimedOut>, u64) {
+    while !SEM_DONE.load(Ordering::SeqCst) {
+        yield_now();
+    }
+    SEM_RESULT
+        .lock()
+        .take()
+        .expect("(Sync) Waiter sem resultado")
+}
+
+/// Ninguém libera: a espera volta com `TimedOut` depois do prazo.
+fn test_semaphore_timeout_expires() {
+    let start = start_semaphore_waiter(SHORT_TIMEOUT_NS);
+    let (result, end) = finish_semaphore_waiter();
+    assert_eq!(result, Err(TimedOut), "(Sync) Semáforo não expirou");
+    assert!(
+        end >= start + SHORT_TIMEOUT_NS,
+        "(Sync) Semáforo expirou antes do prazo"
+    );
+    assert!(!SEM.try_acquire(), "(Sync) Espera expirada criou permissão");
+}
+
+/// Um `release` antes do prazo acorda a espera com a permissão, e o timer
+/// cancelado não devolve nem consome outra.
+fn test_semaphore_release_beats_timeout() {
+    let start = start_semaphore_waiter(LONG_TIMEOUT_NS);
+    SEM.release();
+    let (result, end) = finish_semaphore_waiter();
+    assert_eq!(result, Ok(()), "(Sync) release não acordou a espera");
+    assert!(
+        end < start + LONG_TIMEOUT_NS,
+        "(Sync) Espera só voltou no prazo"
+    );
+    assert!(!SEM.try_acquire(), "(Sync) Permissão contada duas vezes");
+}
+
+/// Um pedido acima de `i32::MAX` permissões falha sem mexer no contador
+/// (antes virava um número negativo e *criava* permissões).
+fn test_semaphore_rejects_oversized_count() {
+    let sem = Semaphore::new(1);
+    assert!(
+        !sem.try_acquire_many(u32::MAX),
+        "(Sync) Pedido gigante foi aceito"
+    );
+    assert_eq!(
+        sem.try_acquire_timeout(u32::MAX, crate::core::time::now_ns() + LONG_TIMEOUT_NS),
+        Err(TimedOut),
+        "(Sync) Pedido gigante esperou ou foi aceito"
+    );
+    assert!(sem.try_acquire(), "(Sync) Permissão perdida");
+    assert!(!sem.try_acquire(), "(Sync) Pedido gigante criou permissões");
+}
+
+/// Vezes que o produtor cede a CPU antes de levantar a flag
+const PRODUCER_DELAY_YIELDS: usize = 8;
+
+static READY: Mutex<bool> = Mutex::new(false);
+static READY_CV: CondVar = CondVar::new();
+static CONSUMER_SAW: AtomicBool = AtomicBool::new(false);
+static CV_FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn condvar_consumer() -> ! {
+    let ready = READY_CV.wait_while(READY.lock(), |ready| !*ready);
+    CONSUMER_SAW.store(*ready, Ordering::SeqCst);
+    drop(ready);
+    CV_FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+extern "C" fn condvar_producer() -> ! {
+    for _ in 0..PRODUCER_DELAY_YIELDS {
+        yield_now();
+    }
+    *READY.lock() = true;
+    READY_CV.notify_all();
+    CV_FINISHED.fetch_add(1, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// O consumidor bloqueia em `wait_while` até o produtor levantar a flag e
+/// notificar, e volta com o mutex adquirido vendo a flag.
+fn test_condvar_wait_while() {
+    *READY.lock() = false;
+    CONSUMER_SAW.store(false, Ordering::SeqCst);
+    CV_FINISHED.store(0, Ordering::SeqCst);
+
+    spawn_kernel_task("sync-test-cv-consumer", condvar_consumer);
+    spawn_kernel_task("sync-test-cv-producer", condvar_producer);
+    while CV_FINISHED.load(Ordering::SeqCst) < 2 {
+        yield_now();
+    }
+    assert!(
+        CONSUMER_SAW.load(Ordering::SeqCst),
+        "(Sync) wait_while voltou sem a flag"
+    );
+}
@@ -32,6 +32,30 @@ pub mod msg {
     pub const URGENT: u32 = 1 << 1;
 }
 
+/// Operações de `SYS_FUTEX`
+pub mod futex {
+    /// Dorme se `*addr == val`
+    pub const WAIT: u32 = 0;
+    /// Acorda até `val` threads
+    pub const WAKE: u32 = 1;
+}
+
+/// Flags de eventfd
+pub mod eventfd {
+    /// `read` devolve 1 e decrementa, em vez de zerar (na criação)
+    pub const SEMAPHORE: u32 = 1 << 0;
+    /// Retornar `Busy` em vez de bloquear (em read/write)
+    pub const NONBLOCK: u32 = 1 << 1;
+}
+
+/// Argumentos especiais de `SYS_KLOG_SET_LEVEL`
+pub mod klog {
+    /// Subsistema: muda o limite global
+    pub const ALL_SUBSYSTEMS: u32 = u32::MAX;
+    /// Nível: remove o override do subsistema (volta a seguir o global)
+    pub const INHERIT: u32 = u32::MAX;
+}
+
 /// Flags para open
 pub mod open {
     pub const RDONLY: u32 = 0;
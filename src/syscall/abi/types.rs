@@ -145,6 +145,48 @@ pub mod poll_events {
     pub const NVAL: u16 = 1 << 4; // Handle inválido
 }
 
+/// Informações do sistema (`sys_sysinfo`)
+///
+/// Layout fixo de 64 bytes:
+///
+/// | Offset | Campo         | Tipo       |
+/// |--------|---------------|------------|
+/// | 0      | `uptime_ns`   | `u64`      |
+/// | 8      | `total_ram`   | `u64`      |
+/// | 16     | `free_ram`    | `u64`      |
+/// | 24     | `processes`   | `u32`      |
+/// | 28     | `abi_version` | `u32`      |
+/// | 32     | `version`     | `[u8; 32]` |
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SysInfo {
+    /// Nanossegundos desde o boot
+    pub uptime_ns: u64,
+    /// RAM física gerenciada, em bytes
+    pub total_ram: u64,
+    /// RAM física livre (frames livres no PFM), em bytes
+    pub free_ram: u64,
+    /// Tasks existentes (sem a idle)
+    pub processes: u32,
+    /// Versão da ABI de syscalls
+    pub abi_version: u32,
+    /// Versão do kernel em UTF-8, completada com zeros
+    pub version: [u8; 32],
+}
+
+const _: () = assert!(core::mem::size_of::<SysInfo>() == 64);
+
+impl SysInfo {
+    pub fn version_str(&self) -> &str {
+        let len = self
+            .version
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.version.len());
+        core::str::from_utf8(&self.version[..len]).unwrap_or("")
+    }
+}
+
 /// Tipos de clock
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -2,8 +2,9 @@
 //! # Syscall Dispatcher
 //!
 //! Despachante baseado em tabela para despacho O(1).
-//! Os logs de tracer estão desativados por padrão para evitar poluição.
-//! Ative-os para depuração, desative quando puder.
+//! Os traces daqui usam o subsistema `Syscall` e ficam mudos até o limite
+//! dele chegar a `Trace` (`klog::set_subsystem_level` ou
+//! `SYS_KLOG_SET_LEVEL`).
 
 pub mod table;
 
@@ -16,17 +17,28 @@ pub use table::SYSCALL_TABLE;
 /// Handler de syscall (entry point do assembly)
 ///
 /// Usa acesso volatile para evitar que o compilador gere código SSE
+///
+/// # Safety
+/// Chamado só por syscall.s, com `ctx` apontando para o `ContextFrame`
+/// salvo na stack do kernel da task atual.
 #[no_mangle]
 #[inline(never)]
-pub extern "C" fn syscall_dispatcher(ctx: *mut ContextFrame) {
+pub unsafe extern "C" fn syscall_dispatcher(ctx: *mut ContextFrame) {
     // Acesso via ponteiro bruto com volatile para evitar SSE
     unsafe {
-        // crate::ktrace!("(Syscall) ENTRADA no dispatcher");
-        // crate::ktrace!("(Syscall) ctx ptr=", ctx as u64);
-        // NOTA: ktrace desativado para evitar overhead em loops rápidos
+        crate::ktrace!(Syscall: "(Syscall) ENTRADA no dispatcher");
+        crate::ktrace!(Syscall: "(Syscall) ctx ptr=", ctx as u64);
 
         // Ler argumentos da syscall
         let num = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).rax)) as usize;
+
+        // sigreturn substitui o contexto inteiro (inclusive RAX): não há
+        // resultado a escrever
+        if num == super::numbers::SYS_SIGRETURN {
+            crate::sched::signal::delivery::sigreturn(&mut *ctx);
+            crate::sched::signal::delivery::deliver_pending(&mut *ctx);
+            return;
+        }
         let arg1 = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).rdi)) as usize;
         let arg2 = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).rsi)) as usize;
         let arg3 = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).rdx)) as usize;
@@ -34,10 +46,9 @@ pub extern "C" fn syscall_dispatcher(ctx: *mut ContextFrame) {
         let arg5 = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).r8)) as usize;
         let arg6 = core::ptr::read_volatile(core::ptr::addr_of!((*ctx).r9)) as usize;
 
-        // crate::ktrace!("(Syscall) num=", num as u64);
-        // crate::ktrace!("(Syscall) arg1=", arg1 as u64);
-        // crate::ktrace!("(Syscall) arg2=", arg2 as u64);
-        // NOTA: ktrace desativado para evitar overhead em loops rápidos
+        crate::ktrace!(Syscall: "(Syscall) num=", num as u64);
+        crate::ktrace!(Syscall: "(Syscall) arg1=", arg1 as u64);
+        crate::ktrace!(Syscall: "(Syscall) arg2=", arg2 as u64);
 
         // Construir struct de argumentos
         let args = SyscallArgs {
@@ -53,75 +64,44 @@ pub extern "C" fn syscall_dispatcher(ctx: *mut ContextFrame) {
         // Dispatch via tabela
         let result: u64 = if num < table::TABLE_SIZE {
             if let Some(handler) = SYSCALL_TABLE[num] {
-                // crate::ktrace!("(Syscall) Handler encontrado");
-                // NOTA: ktrace desativado para evitar overhead
+                crate::ktrace!(Syscall: "(Syscall) Handler encontrado");
                 match handler(&args) {
                     Ok(val) => val as u64,
                     Err(e) => {
                         if e == SysError::NotFound || e == SysError::InvalidHandle {
-                            crate::kdebug!("(Syscall) Op falhou (esperado): num=", num as u64);
-                            crate::kdebug!("(Syscall) Codigo do erro=", e.as_isize() as u64);
+                            crate::kdebug!(Syscall: "(Syscall) Op falhou (esperado): num=", num as u64);
+                            crate::kdebug!(Syscall: "(Syscall) Codigo do erro=", e.as_isize() as u64);
                         } else {
-                            crate::kerror!("(Syscall) Handler retornou erro! num=", num as u64);
-                            crate::kerror!("(Syscall) Codigo do erro=", e.as_isize() as u64);
+                            crate::kerror!(Syscall: "(Syscall) Handler retornou erro! num=", num as u64);
+                            crate::kerror!(Syscall: "(Syscall) Codigo do erro=", e.as_isize() as u64);
                         }
                         e.as_isize() as u64
                     }
                 }
             } else {
-                // Fallback para syscalls hardcoded (compatibilidade)
-                dispatch_hardcoded(num, arg1, arg2)
+                crate::ktrace!(Syscall: "(Syscall) syscall nao registrada:", num as u64);
+                SysError::NotImplemented.as_isize() as u64 // ENOSYS
             }
         } else {
-            crate::ktrace!("(Syscall) num fora do range");
-            (-1i64) as u64 // ENOSYS
+            crate::ktrace!(Syscall: "(Syscall) num fora do range");
+            SysError::NotImplemented.as_isize() as u64 // ENOSYS
         };
 
-        // crate::ktrace!("(Syscall) Resultado=", result);
-        // NOTA: ktrace desativado para evitar overhead
+        crate::ktrace!(Syscall: "(Syscall) Resultado=", result);
 
         // Escrever resultado em RAX via volatile
         core::ptr::write_volatile(core::ptr::addr_of_mut!((*ctx).rax), result);
 
+        // Sinais pendentes antes de voltar para user mode (um handler
+        // redireciona o contexto; o resultado fica salvo no SignalFrame)
+        crate::sched::signal::delivery::deliver_pending(&mut *ctx);
+
         // NOTA: NÃO chamar maybe_reschedule() aqui!
         // Context switch no meio do dispatcher corrompe o estado da task.
-        // Preempção deve acontecer apenas em pontos seguros (yield explícito).
-
-        // crate::ktrace!("(Syscall) SAINDO do dispatcher");
-        // NOTA: ktrace desativado para evitar overhead
-    }
-}
+        // Preempção acontece só em pontos seguros: yield explícito ou o tick
+        // do timer ao retornar para user mode (ver `timer_handler`).
 
-/// Fallback para syscalls hardcoded (console, yield)
-#[inline(always)]
-unsafe fn dispatch_hardcoded(num: usize, arg1: usize, arg2: usize) -> u64 {
-    match num {
-        0xF3 => {
-            crate::ktrace!("(Syscall) SYS_CONSOLE_WRITE (hardcoded)");
-            // SYS_CONSOLE_WRITE - escrever na serial diretamente
-            if arg1 != 0 && arg2 != 0 {
-                for i in 0..arg2 {
-                    let byte = core::ptr::read_volatile((arg1 + i) as *const u8);
-                    core::arch::asm!(
-                        "out dx, al",
-                        in("dx") 0x3F8u16,
-                        in("al") byte,
-                        options(nostack, preserves_flags),
-                    );
-                }
-                arg2 as u64
-            } else {
-                0
-            }
-        }
-        0x04 => {
-            // SYS_YIELD
-            0
-        }
-        _ => {
-            crate::ktrace!("(Syscall) syscall desconhecida!", num as u64);
-            (-1i64) as u64 // ENOSYS
-        }
+        crate::ktrace!(Syscall: "(Syscall) SAINDO do dispatcher");
     }
 }
 
@@ -136,3 +116,46 @@ fn dispatch(args: &SyscallArgs) -> SysResult<usize> {
         None => Err(SysError::NotImplemented),
     }
 }
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscall::numbers::SYS_CONSOLE_WRITE;
+
+    fn args(num: usize, arg1: usize, arg2: usize) -> SyscallArgs {
+        SyscallArgs {
+            num,
+            arg1,
+            arg2,
+            arg3: 0,
+            arg4: 0,
+            arg5: 0,
+            arg6: 0,
+        }
+    }
+
+    #[test]
+    fn test_console_write_rejects_bad_pointer() {
+        // Endereço de kernel
+        let kernel_ptr = args(SYS_CONSOLE_WRITE, 0xFFFF_8000_0000_1000, 8);
+        assert_eq!(dispatch(&kernel_ptr), Err(SysError::InvalidArgument));
+        // Nulo
+        assert_eq!(
+            dispatch(&args(SYS_CONSOLE_WRITE, 0, 8)),
+            Err(SysError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_unregistered_syscall_is_enosys() {
+        assert_eq!(dispatch(&args(0xEE, 0, 0)), Err(SysError::NotImplemented));
+        assert_eq!(
+            dispatch(&args(table::TABLE_SIZE, 0, 0)),
+            Err(SysError::InvalidSyscall)
+        );
+    }
+}
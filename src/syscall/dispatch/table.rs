@@ -28,6 +28,9 @@ pub static SYSCALL_TABLE: [Option<SyscallHandler>; TABLE_SIZE] = {
     table[SYS_GETTID] = Some(super::super::process::sys_gettid_wrapper);
     table[SYS_THREAD_CREATE] = Some(super::super::process::sys_thread_create_wrapper);
     table[SYS_THREAD_EXIT] = Some(super::super::process::sys_thread_exit_wrapper);
+    table[SYS_KILL] = Some(super::super::process::sys_kill_wrapper);
+    table[SYS_SIGACTION] = Some(super::super::process::sys_sigaction_wrapper);
+    table[SYS_SIGPROCMASK] = Some(super::super::process::sys_sigprocmask_wrapper);
 
     // === MEMÓRIA (0x10-0x1F) ===
     table[SYS_ALLOC] = Some(super::super::memory::sys_alloc_wrapper);
@@ -45,12 +48,16 @@ pub static SYSCALL_TABLE: [Option<SyscallHandler>; TABLE_SIZE] = {
     table[SYS_CREATE_PORT] = Some(super::super::ipc::port::sys_create_port_wrapper);
     table[SYS_SEND_MSG] = Some(super::super::ipc::port::sys_send_msg_wrapper);
     table[SYS_RECV_MSG] = Some(super::super::ipc::port::sys_recv_msg_wrapper);
-    table[SYS_FUTEX_WAIT] = Some(super::super::ipc::port::sys_futex_wait_wrapper);
-    table[SYS_FUTEX_WAKE] = Some(super::super::ipc::port::sys_futex_wake_wrapper);
+    table[SYS_FUTEX_WAIT] = Some(super::super::ipc::futex::sys_futex_wait_wrapper);
+    table[SYS_FUTEX_WAKE] = Some(super::super::ipc::futex::sys_futex_wake_wrapper);
     table[SYS_SHM_CREATE] = Some(super::super::ipc::shm::sys_shm_create_wrapper);
     table[SYS_SHM_MAP] = Some(super::super::ipc::shm::sys_shm_map_wrapper);
     table[SYS_PORT_CONNECT] = Some(super::super::ipc::port::sys_port_connect_wrapper);
     table[SYS_SHM_GET_SIZE] = Some(super::super::ipc::shm::sys_shm_get_size_wrapper);
+    table[SYS_FUTEX] = Some(super::super::ipc::futex::sys_futex_wrapper);
+    table[SYS_EVENTFD_CREATE] = Some(super::super::ipc::eventfd::sys_eventfd_create_wrapper);
+    table[SYS_EVENTFD_READ] = Some(super::super::ipc::eventfd::sys_eventfd_read_wrapper);
+    table[SYS_EVENTFD_WRITE] = Some(super::super::ipc::eventfd::sys_eventfd_write_wrapper);
 
     // === DISPLAY (0x40-0x4F) ===
     table[SYS_FB_INFO] = Some(super::super::display::sys_display_info_wrapper);
@@ -125,6 +132,8 @@ pub static SYSCALL_TABLE: [Option<SyscallHandler>; TABLE_SIZE] = {
     table[SYS_POWEROFF] = Some(super::super::system::sys_poweroff_wrapper);
     table[SYS_CONSOLE_WRITE] = Some(super::super::system::sys_console_write_wrapper);
     table[SYS_CONSOLE_READ] = Some(super::super::system::sys_console_read_wrapper);
+    table[SYS_KLOG_READ] = Some(super::super::system::sys_klog_read_wrapper);
+    table[SYS_KLOG_SET_LEVEL] = Some(super::super::system::sys_klog_set_level_wrapper);
     table[SYS_DEBUG] = Some(super::super::system::sys_debug_wrapper);
 
     table
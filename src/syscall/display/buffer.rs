@@ -5,6 +5,7 @@
 use crate::drivers::display::BUFFER_MANAGER;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 use gfx_types::{BufferDescriptor, BufferHandle, PixelFormat};
 
 // ============================================================================
@@ -129,6 +130,7 @@ pub fn sys_buffer_info(handle: u64, out_ptr: *mut BufferDescriptor) -> SysResult
     let buffer = mgr.get(buffer_handle).ok_or(SysError::InvalidArgument)?;
 
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         core::ptr::write_volatile(out_ptr, buffer.desc);
     }
 
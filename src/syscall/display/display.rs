@@ -6,6 +6,7 @@
 use crate::drivers::display::DISPLAY_CRTC;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // ============================================================================
 // Estrutura de resposta legada (compatibilidade com SDK)
@@ -52,6 +53,7 @@ pub fn sys_display_info_wrapper(args: &SyscallArgs) -> SysResult<usize> {
     };
 
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         core::ptr::write_volatile(out_ptr, legacy_info);
     }
 
@@ -121,6 +123,7 @@ pub fn sys_display_commit_wrapper(args: &SyscallArgs) -> SysResult<usize> {
 
         let src_u64 = data_ptr as *const u64;
         let dst_u64 = dst as *mut u64;
+        let _uaccess = UserAccessGuard::new();
 
         for i in 0..chunks {
             let val = core::ptr::read_volatile(src_u64.add(i));
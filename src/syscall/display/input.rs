@@ -5,6 +5,7 @@
 use crate::drivers::input::{keyboard, mouse};
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // ============================================================================
 // SYS_MOUSE_READ (0x48)
@@ -42,6 +43,7 @@ pub fn sys_mouse_read_wrapper(args: &SyscallArgs) -> SysResult<usize> {
     };
 
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         core::ptr::write_volatile(out_ptr, user_state);
     }
 
@@ -84,23 +86,20 @@ pub fn sys_keyboard_read_wrapper(args: &SyscallArgs) -> SysResult<usize> {
     }
 
     for i in 0..max {
-        if let Some(scancode) = keyboard::pop_scancode() {
-            // Bit 7 = 1 significa key released (0x80)
-            let pressed = (scancode & 0x80) == 0;
-            let actual_scancode = scancode & 0x7F;
-
-            unsafe {
-                let user_event = UserKeyEvent {
-                    scancode: actual_scancode,
-                    pressed,
-                    _pad: [0; 6],
-                };
-                core::ptr::write_volatile(out_ptr.add(i), user_event);
-            }
-            count += 1;
-        } else {
+        let Some(event) = keyboard::read_key() else {
             break;
+        };
+        // Teclas estendidas (E0) chegam com o scancode de 7 bits
+        unsafe {
+            let user_event = UserKeyEvent {
+                scancode: event.scancode(),
+                pressed: event.pressed,
+                _pad: [0; 6],
+            };
+            let _uaccess = UserAccessGuard::new();
+            core::ptr::write_volatile(out_ptr.add(i), user_event);
         }
+        count += 1;
     }
 
     Ok(count)
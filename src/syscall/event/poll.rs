@@ -2,8 +2,17 @@
 //!
 //! Multiplexação de I/O.
 
+use crate::sched::sync::poll::{self, PollEntry, Pollable};
+use crate::syscall::abi::types::PollFd;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::{copy_from_user, copy_to_user};
+use crate::syscall::{Handle, HandleType};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Entradas aceitas em uma chamada
+pub const MAX_POLL_FDS: usize = 256;
 
 // === WRAPPERS ===
 
@@ -18,18 +27,63 @@ pub fn sys_poll_wrapper(args: &SyscallArgs) -> SysResult<usize> {
 /// # Args
 /// - fds_ptr: ponteiro para array de PollFd
 /// - nfds: número de entradas no array
-/// - timeout_ms: timeout em ms (-1 = infinito, 0 = não bloqueia)
+/// - timeout_ns: timeout em ns (negativo = infinito, 0 = não bloqueia)
 ///
 /// # Returns
-/// Número de handles com eventos ou erro
-pub fn sys_poll(fds_ptr: usize, nfds: usize, timeout_ms: i64) -> SysResult<usize> {
-    // TODO: Validar ponteiro (array de PollFd)
-    // TODO: Para cada fd, verificar se eventos solicitados ocorreram
-    // TODO: Se nenhum evento e timeout != 0, bloquear
-    // TODO: Preencher revents em cada PollFd
-    // TODO: Retornar número de handles com eventos
-
-    let _ = (fds_ptr, nfds, timeout_ms);
-    crate::kwarn!("(Syscall) sys_poll não implementado");
-    Err(SysError::NotImplemented)
+/// Número de handles com eventos (0 se o prazo venceu) ou erro. O `revents`
+/// de cada `PollFd` é preenchido; handle inválido ou de tipo sem suporte a
+/// poll recebe `NVAL`.
+pub fn sys_poll(fds_ptr: usize, nfds: usize, timeout_ns: i64) -> SysResult<usize> {
+    if nfds > MAX_POLL_FDS {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let mut fds = alloc::vec![PollFd { handle: 0, events: 0, revents: 0 }; nfds];
+    if nfds > 0 {
+        copy_from_user(fd_bytes(&mut fds), fds_ptr)?;
+    }
+
+    let mut entries: Vec<PollEntry> = {
+        let task_guard = crate::sched::core::CURRENT.lock();
+        let task = task_guard.as_ref().ok_or(SysError::Interrupted)?;
+        fds.iter()
+            .map(|fd| {
+                let handle = Handle::new((fd.handle & 0xFFFF) as u16, (fd.handle >> 16) as u16);
+                let source = task
+                    .handle_table
+                    .get(handle)
+                    .and_then(|entry| pollable(entry.htype, entry.object));
+                PollEntry::new(source, fd.events)
+            })
+            .collect()
+    };
+
+    let timeout = u64::try_from(timeout_ns).ok();
+    let ready = poll::poll(&mut entries, timeout);
+
+    if nfds > 0 {
+        for (fd, entry) in fds.iter_mut().zip(&entries) {
+            fd.revents = entry.revents;
+        }
+        copy_to_user(fds_ptr, fd_bytes(&mut fds))?;
+    }
+    Ok(ready)
+}
+
+/// Objeto por trás de um handle, se o tipo suportar poll
+fn pollable(htype: HandleType, object: usize) -> Option<Arc<dyn Pollable>> {
+    match htype {
+        HandleType::Port => crate::ipc::manager::pollable(object),
+        HandleType::Event => crate::ipc::eventfd::lookup(object)
+            .map(|eventfd| Arc::new(eventfd) as Arc<dyn Pollable>),
+        _ => None,
+    }
+}
+
+/// Bytes de um array de `PollFd`
+fn fd_bytes(fds: &mut [PollFd]) -> &mut [u8] {
+    // SAFETY: `PollFd` é `repr(C)`, só inteiros e sem padding (4 + 2 + 2)
+    unsafe {
+        core::slice::from_raw_parts_mut(fds.as_mut_ptr() as *mut u8, core::mem::size_of_val(fds))
+    }
 }
@@ -7,6 +7,7 @@ use super::types::{DirEntryBuilder, FileType};
 use crate::sync::Spinlock;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -78,7 +79,8 @@ pub fn sys_getdents(handle: u32, buf_ptr: usize, buf_len: usize) -> SysResult<us
         return Ok(0);
     }
 
-    // Buffer de saída
+    // Buffer de saída (o laço só formata entradas: não bloqueia)
+    let _uaccess = UserAccessGuard::new();
     let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, buf_len) };
     let mut written = 0;
     let mut current_index = start_index;
@@ -143,6 +145,7 @@ pub fn sys_getcwd(buf_ptr: usize, buf_len: usize) -> SysResult<usize> {
     }
 
     // Copiar para userspace
+    let _uaccess = UserAccessGuard::new();
     let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, buf_len) };
     buf[..cwd_bytes.len()].copy_from_slice(cwd_bytes);
     buf[cwd_bytes.len()] = 0; // null terminator
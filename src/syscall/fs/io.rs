@@ -4,8 +4,10 @@
 
 use super::handle::{alloc_handle, get_handle, update_offset, FileHandle};
 use super::types::{path_from_user, FileType, OpenFlags, SeekWhence};
+use crate::fs::vfs::file::{seek_target, SeekFrom};
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // =============================================================================
 // WRAPPERS
@@ -158,43 +160,27 @@ pub fn sys_write(handle: u32, _buf_ptr: usize, _len: usize) -> SysResult<usize>
     Err(SysError::NotImplemented)
 }
 
-/// Move posição de leitura/escrita
+/// Move posição de leitura/escrita (`lseek`)
 ///
 /// # Args
 /// - handle: handle do arquivo
 /// - offset: deslocamento
-/// - whence: referência (SET=0, CUR=1, END=2)
+/// - whence: referência (SET=0, CUR=1, END=2; END parte do tamanho do arquivo)
 ///
 /// # Returns
-/// Nova posição ou erro
+/// Nova posição ou erro. Posição final negativa é `InvalidArgument`; passar
+/// do fim é permitido (leituras lá retornam 0).
 pub fn sys_seek(handle: u32, offset: i64, whence: u32) -> SysResult<usize> {
     let h = get_handle(handle).ok_or(SysError::InvalidHandle)?;
     let whence = SeekWhence::from_u32(whence).ok_or(SysError::InvalidArgument)?;
 
-    let new_offset = match whence {
-        SeekWhence::Set => {
-            if offset < 0 {
-                return Err(SysError::InvalidArgument);
-            }
-            offset as u64
-        }
-        SeekWhence::Cur => {
-            let cur = h.offset as i64;
-            let new = cur + offset;
-            if new < 0 {
-                return Err(SysError::InvalidArgument);
-            }
-            new as u64
-        }
-        SeekWhence::End => {
-            let end = h.size as i64;
-            let new = end + offset;
-            if new < 0 {
-                return Err(SysError::InvalidArgument);
-            }
-            new as u64
-        }
+    let from = match whence {
+        SeekWhence::Set if offset < 0 => return Err(SysError::InvalidArgument),
+        SeekWhence::Set => SeekFrom::Start(offset as u64),
+        SeekWhence::Cur => SeekFrom::Current(offset),
+        SeekWhence::End => SeekFrom::End(offset),
     };
+    let new_offset = seek_target(h.offset, h.size, from).ok_or(SysError::InvalidArgument)?;
 
     update_offset(handle, new_offset);
     Ok(new_offset as usize)
@@ -351,6 +337,7 @@ fn read_file_data(
 
     // Copiar para userspace
     // TODO: Proper copy_to_user
+    let _uaccess = UserAccessGuard::new();
     let dest = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, to_copy) };
     dest.copy_from_slice(&data[start..start + to_copy]);
 
@@ -5,6 +5,7 @@
 use super::types::path_from_user;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // =============================================================================
 // WRAPPERS
@@ -98,6 +99,7 @@ pub fn sys_realpath(
     }
 
     // Copiar para userspace
+    let _uaccess = UserAccessGuard::new();
     let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, buf_len) };
     buf[..bytes.len()].copy_from_slice(bytes);
     buf[bytes.len()] = 0;
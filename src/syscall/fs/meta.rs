@@ -6,6 +6,7 @@ use super::handle::get_handle;
 use super::types::{path_from_user, FileStat, FileType};
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // =============================================================================
 // WRAPPERS
@@ -103,6 +104,7 @@ pub fn sys_stat(path_ptr: usize, path_len: usize, stat_ptr: usize) -> SysResult<
     };
 
     // Copiar para userspace
+    let _uaccess = UserAccessGuard::new();
     let dest = unsafe { &mut *(stat_ptr as *mut FileStat) };
     *dest = stat;
 
@@ -139,6 +141,7 @@ pub fn sys_fstat(handle: u32, stat_ptr: usize) -> SysResult<usize> {
     };
 
     // Copiar para userspace
+    let _uaccess = UserAccessGuard::new();
     let dest = unsafe { &mut *(stat_ptr as *mut FileStat) };
     *dest = stat;
 
@@ -5,6 +5,7 @@
 use super::types::{path_from_user, FsStat};
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // =============================================================================
 // WRAPPERS
@@ -103,6 +104,7 @@ pub fn sys_statfs(path_ptr: usize, path_len: usize, statfs_ptr: usize) -> SysRes
     };
 
     // Copiar para userspace
+    let _uaccess = UserAccessGuard::new();
     let dest = unsafe { &mut *(statfs_ptr as *mut FsStat) };
     *dest = stat;
 
@@ -114,8 +116,6 @@ pub fn sys_statfs(path_ptr: usize, path_len: usize, statfs_ptr: usize) -> SysRes
 /// # Returns
 /// 0 ou erro
 pub fn sys_sync() -> SysResult<usize> {
-    // Por enquanto, não temos buffering de escrita
-    // Quando implementarmos, este syscall forçará flush
-    crate::ktrace!("(FS) sys_sync: não implementado");
+    crate::fs::vfs::sync().map_err(|_| SysError::IoError)?;
     Ok(0)
 }
@@ -259,13 +259,9 @@ pub fn path_from_user(ptr: usize, len: usize) -> Result<String, crate::syscall::
         return Err(SysError::BadAddress);
     }
 
-    // TODO: Proper copy_from_user with page table validation
-    // Por agora, assumimos que o ponteiro é válido
-    let slice = unsafe { core::slice::from_raw_parts(ptr as *const u8, len) };
+    let mut bytes = alloc::vec![0u8; len];
+    crate::syscall::uaccess::copy_from_user(&mut bytes, ptr)?;
 
     // Converter para String
-    match core::str::from_utf8(slice) {
-        Ok(s) => Ok(String::from(s)),
-        Err(_) => Err(SysError::InvalidArgument),
-    }
+    String::from_utf8(bytes).map_err(|_| SysError::InvalidArgument)
 }
@@ -159,6 +159,25 @@ impl HandleTable {
         false
     }
 
+    /// Retira o handle da tabela para transferência a outro processo.
+    ///
+    /// O slot fica livre (a generation muda na próxima alocação, então o
+    /// valor antigo do handle deixa de ser válido aqui).
+    pub fn take(&mut self, handle: Handle) -> Option<(HandleType, usize, HandleRights)> {
+        let entry = self.get_mut(handle)?;
+        let taken = (entry.htype, entry.object, entry.rights);
+        entry.in_use = false;
+        entry.object = 0;
+        entry.rights = HandleRights::empty();
+        entry.refcount = AtomicU32::new(0);
+        Some(taken)
+    }
+
+    /// Número de slots livres
+    pub fn free_slots(&self) -> usize {
+        self.entries.iter().filter(|entry| !entry.in_use).count()
+    }
+
     /// Duplica handle com rights reduzidos
     pub fn dup(&mut self, handle: Handle, new_rights: HandleRights) -> Option<Handle> {
         let (htype, object, current_rights) = {
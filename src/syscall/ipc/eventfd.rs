@@ -0,0 +1,127 @@
+//! # EventFd Syscalls
+//!
+//! Contadores de eventos acessados por handle (`HandleType::Event`).
+
+use crate::ipc::eventfd::{self, EventFd, EventFdError};
+use crate::syscall::abi::flags::eventfd as efd;
+use crate::syscall::abi::SyscallArgs;
+use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::copy_to_user;
+use crate::syscall::{Handle, HandleRights, HandleType};
+
+// === WRAPPERS ===
+
+pub fn sys_eventfd_create_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_eventfd_create(args.arg1 as u64, args.arg2 as u32)
+}
+
+pub fn sys_eventfd_read_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_eventfd_read(args.arg1 as u32, args.arg2, args.arg3 as u32)
+}
+
+pub fn sys_eventfd_write_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_eventfd_write(args.arg1 as u32, args.arg2 as u64, args.arg3 as u32)
+}
+
+// === IMPLEMENTAÇÕES ===
+
+/// Cria um contador de eventos
+///
+/// # Args
+/// - initial: valor inicial
+/// - flags: `eventfd::SEMAPHORE` para leitura de 1 em 1
+///
+/// # Returns
+/// Handle do contador ou erro
+pub fn sys_eventfd_create(initial: u64, flags: u32) -> SysResult<usize> {
+    if flags & !efd::SEMAPHORE != 0 || initial > crate::ipc::eventfd::EVENTFD_MAX {
+        return Err(SysError::InvalidArgument);
+    }
+    let id = eventfd::register(EventFd::new(initial, flags & efd::SEMAPHORE != 0));
+
+    let mut task_guard = crate::sched::core::CURRENT.lock();
+    let task = task_guard.as_mut().ok_or(SysError::Interrupted)?;
+    let handle = task
+        .handle_table
+        .alloc(
+            HandleType::Event,
+            id,
+            HandleRights::READ
+                .union(HandleRights::WRITE)
+                .union(HandleRights::CLOSE)
+                .union(HandleRights::DUP)
+                .union(HandleRights::TRANSFER),
+        )
+        .ok_or(SysError::LimitReached)?;
+
+    Ok(handle.as_u32() as usize)
+}
+
+/// Lê o contador (zerando, ou decrementando 1 no modo semáforo)
+///
+/// # Args
+/// - handle: handle do contador
+/// - out_ptr: onde gravar o valor lido (`u64`)
+/// - flags: `eventfd::NONBLOCK` para não bloquear com o contador em 0
+///
+/// # Returns
+/// 0 ou erro (`Busy` se bloquearia)
+pub fn sys_eventfd_read(handle: u32, out_ptr: usize, flags: u32) -> SysResult<usize> {
+    let eventfd = get_eventfd(handle, HandleRights::READ)?;
+    let value = if flags & efd::NONBLOCK != 0 {
+        eventfd.try_read().map_err(map_error)?
+    } else {
+        eventfd.read()
+    };
+    copy_to_user(out_ptr, &value.to_le_bytes())?;
+    Ok(0)
+}
+
+/// Soma `value` ao contador
+///
+/// # Args
+/// - handle: handle do contador
+/// - value: quanto somar (`u64::MAX` é inválido)
+/// - flags: `eventfd::NONBLOCK` para não bloquear se a soma estourar
+///
+/// # Returns
+/// 0 ou erro (`Busy` se bloquearia)
+pub fn sys_eventfd_write(handle: u32, value: u64, flags: u32) -> SysResult<usize> {
+    let eventfd = get_eventfd(handle, HandleRights::WRITE)?;
+    if flags & efd::NONBLOCK != 0 {
+        eventfd.try_write(value)
+    } else {
+        eventfd.write(value)
+    }
+    .map_err(map_error)?;
+    Ok(0)
+}
+
+/// Contador por trás de `handle`, exigindo `rights`
+fn get_eventfd(handle: u32, rights: HandleRights) -> SysResult<EventFd> {
+    let id = {
+        let task_guard = crate::sched::core::CURRENT.lock();
+        let task = task_guard.as_ref().ok_or(SysError::Interrupted)?;
+        let handle = Handle::new((handle & 0xFFFF) as u16, (handle >> 16) as u16);
+        let entry = task
+            .handle_table
+            .get(handle)
+            .ok_or(SysError::InvalidHandle)?;
+
+        if entry.htype != HandleType::Event {
+            return Err(SysError::InvalidArgument);
+        }
+        if !entry.rights.contains(rights) {
+            return Err(SysError::PermissionDenied);
+        }
+        entry.object
+    };
+    eventfd::lookup(id).ok_or(SysError::InvalidHandle)
+}
+
+fn map_error(error: EventFdError) -> SysError {
+    match error {
+        EventFdError::WouldBlock => SysError::Busy,
+        EventFdError::InvalidValue => SysError::InvalidArgument,
+    }
+}
@@ -0,0 +1,85 @@
+//! # Futex Syscalls
+//!
+//! futex_wait, futex_wake e a forma multiplexada `sys_futex(addr, op, val)`.
+
+use crate::ipc::futex::FutexError;
+use crate::ipc::Futex;
+use crate::mm::VirtAddr;
+use crate::syscall::abi::{futex as futex_op, SyscallArgs};
+use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::copy_from_user;
+
+/// Fim do espaço de usuário (x86_64 canonical)
+const USER_SPACE_END: usize = 0x0000_8000_0000_0000;
+
+// === WRAPPERS ===
+
+pub fn sys_futex_wait_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_futex_wait(args.arg1, args.arg2, args.arg3 as u64)
+}
+
+pub fn sys_futex_wake_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_futex_wake(args.arg1, args.arg2)
+}
+
+pub fn sys_futex_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_futex(args.arg1, args.arg2 as u32, args.arg3, args.arg4 as u64)
+}
+
+// === IMPLEMENTAÇÕES ===
+
+/// Valida o endereço da palavra do futex (usuário, alinhado a 4)
+fn futex_addr(addr: usize) -> SysResult<VirtAddr> {
+    if addr == 0 || addr % 4 != 0 || addr > USER_SPACE_END - 4 {
+        return Err(SysError::BadAddress);
+    }
+    Ok(VirtAddr::new(addr as u64))
+}
+
+/// Operação de futex multiplexada
+///
+/// # Args
+/// - addr: endereço da palavra (u32 alinhado)
+/// - op: `futex::WAIT` ou `futex::WAKE`
+/// - val: valor esperado (WAIT) ou máximo de threads a acordar (WAKE)
+/// - timeout_ms: apenas para WAIT
+pub fn sys_futex(addr: usize, op: u32, val: usize, timeout_ms: u64) -> SysResult<usize> {
+    match op {
+        futex_op::WAIT => sys_futex_wait(addr, val, timeout_ms),
+        futex_op::WAKE => sys_futex_wake(addr, val),
+        _ => Err(SysError::InvalidArgument),
+    }
+}
+
+/// Suspende a thread enquanto `*addr == expected`
+///
+/// Retorna 0 ao ser acordada (possivelmente de forma espúria), `Busy` se o
+/// valor já era diferente, `BadAddress` se a palavra não estiver numa VMA
+/// legível e `Interrupted` se um sinal chegou. Timeouts ainda não são suportados: `timeout_ms`
+/// é ignorado e a espera dura até um wake.
+pub fn sys_futex_wait(addr: usize, expected: usize, timeout_ms: u64) -> SysResult<usize> {
+    let _ = timeout_ms;
+    let word_addr = futex_addr(addr)?;
+
+    // Valida contra as VMAs e traz a página agora, pela falta normal: dentro
+    // de `Futex::wait` a leitura roda com lock e sem interrupções
+    let mut word = [0u8; 4];
+    copy_from_user(&mut word, addr).map_err(|_| SysError::BadAddress)?;
+    if u32::from_ne_bytes(word) != expected as u32 {
+        return Err(SysError::Busy);
+    }
+
+    match Futex::wait(word_addr, expected as u32) {
+        Ok(()) => Ok(0),
+        Err(FutexError::WouldBlock) => Err(SysError::Busy),
+        Err(FutexError::InvalidAddress) => Err(SysError::BadAddress),
+        Err(FutexError::Interrupted) => Err(SysError::Interrupted),
+    }
+}
+
+/// Acorda até `count` threads esperando em um futex
+pub fn sys_futex_wake(addr: usize, count: usize) -> SysResult<usize> {
+    let addr = futex_addr(addr)?;
+    let count = core::cmp::min(count, u32::MAX as usize) as u32;
+    Ok(Futex::wake(addr, count) as usize)
+}
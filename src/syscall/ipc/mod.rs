@@ -1,9 +1,13 @@
 //! # IPC Syscalls
 //!
-//! Comunicação entre processos via portas.
+//! Comunicação entre processos via portas, SHM, futex e eventfd.
 
+pub mod eventfd;
+pub mod futex;
 pub mod port;
 pub mod shm;
 
+pub use eventfd::*;
+pub use futex::*;
 pub use port::*;
 pub use shm::*;
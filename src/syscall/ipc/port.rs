@@ -4,6 +4,7 @@
 
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // === WRAPPERS ===
 
@@ -19,14 +20,6 @@ pub fn sys_recv_msg_wrapper(args: &SyscallArgs) -> SysResult<usize> {
     sys_recv_msg(args.arg1 as u32, args.arg2, args.arg3, args.arg4 as u64)
 }
 
-pub fn sys_futex_wait_wrapper(args: &SyscallArgs) -> SysResult<usize> {
-    sys_futex_wait(args.arg1, args.arg2, args.arg3 as u64)
-}
-
-pub fn sys_futex_wake_wrapper(args: &SyscallArgs) -> SysResult<usize> {
-    sys_futex_wake(args.arg1, args.arg2)
-}
-
 pub fn sys_port_connect_wrapper(args: &SyscallArgs) -> SysResult<usize> {
     sys_port_connect(args.arg1, args.arg2)
 }
@@ -54,6 +47,7 @@ pub fn sys_create_port(name_ptr: usize, name_len: usize, capacity: usize) -> Sys
     // Copiar nome do user stack/heap
     let mut name_bytes = Vec::with_capacity(name_len);
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         let ptr = name_ptr as *const u8;
         for i in 0..name_len {
             name_bytes.push(*ptr.add(i));
@@ -97,6 +91,7 @@ pub fn sys_port_connect(name_ptr: usize, name_len: usize) -> SysResult<usize> {
 
     let mut name_bytes = Vec::with_capacity(name_len);
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         let ptr = name_ptr as *const u8;
         for i in 0..name_len {
             name_bytes.push(*ptr.add(i));
@@ -151,6 +146,7 @@ pub fn sys_send_msg(
     use alloc::vec::Vec;
     let mut data = Vec::with_capacity(msg_len);
     unsafe {
+        let _uaccess = UserAccessGuard::new();
         let ptr = msg_ptr as *const u8;
         for i in 0..msg_len {
             data.push(*ptr.add(i));
@@ -221,6 +217,7 @@ pub fn sys_recv_msg(
         Ok(len) => {
             // Copiar para user
             unsafe {
+                let _uaccess = UserAccessGuard::new();
                 let ptr = buf_ptr as *mut u8;
                 for i in 0..len {
                     *ptr.add(i) = kbuf[i];
@@ -231,17 +228,3 @@ pub fn sys_recv_msg(
         Err(_) => Err(SysError::InvalidHandle),
     }
 }
-
-/// Suspende a thread até que o valor mude (futex)
-pub fn sys_futex_wait(addr: usize, expected: usize, timeout_ms: u64) -> SysResult<usize> {
-    let _ = (addr, expected, timeout_ms);
-    crate::kwarn!("(Syscall) sys_futex_wait não implementado");
-    Err(SysError::NotImplemented)
-}
-
-/// Acorda threads esperando em um futex
-pub fn sys_futex_wake(addr: usize, count: usize) -> SysResult<usize> {
-    let _ = (addr, count);
-    crate::kwarn!("(Syscall) sys_futex_wake não implementado");
-    Err(SysError::NotImplemented)
-}
@@ -3,6 +3,7 @@
 //! Criação e mapeamento de memória compartilhada.
 
 use crate::ipc::shm::{ShmId, SHM_REGISTRY};
+use crate::mm::aspace::vma::Protection;
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
 
@@ -69,57 +70,32 @@ pub fn sys_shm_map(shm_id: u64, suggested_addr: usize) -> SysResult<usize> {
         0x6_0000_0000 + (shm_id * 0x1000000)
     };
 
-    let registry = SHM_REGISTRY.lock();
-    if let Some(shm) = registry.get(id) {
-        // crate::kdebug!("(Syscall) sys_shm_map: vaddr=", base_addr);
+    let shm = SHM_REGISTRY.lock().get(id).ok_or(SysError::InvalidHandle)?;
 
-        // 0. FIX DO BURACO NEGRO (Bunker Buster)
-        // Verifica se existe uma Huge Page (2MB) bloqueando este endereço.
-        // Se existir, ZERA a entrada do PDE para forçar o mapper a criar uma Page Table nova.
-        unsafe {
-            nuke_huge_page_if_exists(base_addr);
-        }
-        crate::ktrace!("(Syscall) sys_shm_map: addr=", base_addr);
-
-        // 1. Mapear
-        match shm.map(base_addr) {
-            Ok(_) => {
-                crate::ktrace!("(Syscall) sys_shm_map: mapping OK. Registering VMA...");
-                // 2. Registrar VMA para que o Page Fault handler saiba que esta região é legítima
-                let aspace_arc = {
-                    let guard = crate::sched::core::CURRENT.lock();
-                    if let Some(task) = guard.as_ref() {
-                        task.aspace.clone()
-                    } else {
-                        None
-                    }
-                };
-
-                if let Some(aspace) = aspace_arc {
-                    use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
-                    let mut as_lock = aspace.lock();
-                    let _ = as_lock.map_region(
-                        Some(crate::mm::VirtAddr::new(base_addr)),
-                        shm.size,
-                        Protection::RW,
-                        VmaFlags::SHARED,
-                        MemoryIntent::SharedMemory,
-                    );
-                }
-                crate::ktrace!("(Syscall) sys_shm_map: VMA registered.");
-
-                // Flush TLB
-                unsafe {
-                    let cr3 = crate::mm::vmm::mapper::read_cr3();
-                    crate::mm::vmm::mapper::write_cr3(cr3);
-                }
-
-                Ok(base_addr as usize)
-            }
-            Err(_) => Err(SysError::BadAddress),
-        }
-    } else {
-        Err(SysError::InvalidHandle)
+    let aspace = {
+        let guard = crate::sched::core::CURRENT.lock();
+        guard.as_ref().and_then(|task| task.aspace.clone())
+    }
+    .ok_or(SysError::BadAddress)?;
+
+    // 0. FIX DO BURACO NEGRO (Bunker Buster)
+    // Verifica se existe uma Huge Page (2MB) bloqueando este endereço.
+    // Se existir, ZERA a entrada do PDE para forçar o mapper a criar uma Page Table nova.
+    unsafe {
+        nuke_huge_page_if_exists(base_addr);
+    }
+    crate::ktrace!("(Syscall) sys_shm_map: addr=", base_addr);
+
+    // Mapeia os frames da região (mesmos frames em todos os processos) e
+    // registra a VMA. Um segundo map no mesmo processo devolve o endereço existente.
+    let mut as_lock = aspace.lock();
+    match shm.map_at(
+        &mut as_lock,
+        Some(crate::mm::VirtAddr::new(base_addr)),
+        Protection::RW,
+    ) {
+        Ok(vaddr) => Ok(vaddr.as_u64() as usize),
+        Err(_) => Err(SysError::BadAddress),
     }
 }
 
@@ -135,7 +111,7 @@ pub fn sys_shm_get_size(shm_id: u64) -> SysResult<usize> {
     let registry = SHM_REGISTRY.lock();
 
     if let Some(shm) = registry.get(id) {
-        Ok(shm.size)
+        Ok(shm.size())
     } else {
         Err(SysError::InvalidHandle)
     }
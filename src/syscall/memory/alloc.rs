@@ -31,6 +31,10 @@ pub fn sys_mprotect_wrapper(args: &SyscallArgs) -> SysResult<usize> {
 
 /// Aloca memória virtual
 ///
+/// As páginas começam todas na página zero compartilhada (somente leitura,
+/// COW): ler devolve zeros sem gastar frames, e a primeira escrita em cada
+/// página aloca um frame próprio pelo page-fault handler.
+///
 /// # Args
 /// - size: tamanho em bytes
 /// - flags: flags de alocação (ignorado por enquanto)
@@ -47,72 +51,50 @@ pub fn sys_alloc(size: usize, _flags: u32) -> SysResult<usize> {
     // Alinhar tamanho a 4KB (página)
     let aligned_size = (size + 0xFFF) & !0xFFF;
 
-    // Obter task atual para acessar seu contador de heap
-    let mut current_task = crate::sched::core::CURRENT.lock();
-    let task = current_task.as_mut().ok_or(SysError::Interrupted)?;
-
-    let alloc_addr = task.heap_next;
-
-    if alloc_addr + aligned_size as u64 > USER_HEAP_MAX {
-        crate::kerror!("(Syscall) sys_alloc: OOM (Virtual)! addr=", alloc_addr);
-        return Err(SysError::OutOfMemory);
-    }
-
-    // Atualizar contador da task
-    task.heap_next += aligned_size as u64;
-
-    // Obter lock do PMM para alocar frames
-    let mut pmm = crate::mm::pmm::FRAME_ALLOCATOR.lock();
-
-    // Mapear as páginas necessárias
-    let pages = aligned_size / 4096;
-    let flags = crate::mm::vmm::MapFlags::PRESENT
-        | crate::mm::vmm::MapFlags::WRITABLE
-        | crate::mm::vmm::MapFlags::USER;
+    // Reservar o intervalo no contador de heap da task. O CURRENT é solto
+    // antes de mexer nas páginas: o page-fault handler precisa dele.
+    let (alloc_addr, aspace) = {
+        let mut current_task = crate::sched::core::CURRENT.lock();
+        let task = current_task.as_mut().ok_or(SysError::Interrupted)?;
+        let aspace = task.aspace.clone().ok_or(SysError::OutOfMemory)?;
 
-    for i in 0..pages {
-        let vaddr = alloc_addr + (i * 4096) as u64;
-
-        // Alocar frame físico
-        let frame = match pmm.allocate_frame() {
-            Some(f) => f,
-            None => {
-                crate::kerror!("(Syscall) sys_alloc: PMM OOM at page", i as u64);
-                return Err(SysError::OutOfMemory);
-            }
-        };
-
-        // Mapear no espaço de usuário atual
-        if let Err(_) = crate::mm::vmm::map_page_with_pmm(vaddr, frame.addr(), flags, &mut *pmm) {
-            crate::kerror!("(Syscall) sys_alloc: map failed at", vaddr);
+        let alloc_addr = task.heap_next;
+        if alloc_addr + aligned_size as u64 > USER_HEAP_MAX {
+            crate::kerror!("(Syscall) sys_alloc: OOM (Virtual)! addr=", alloc_addr);
             return Err(SysError::OutOfMemory);
         }
-
-        // Registrar VMA (apenas na primeira página da alocação ou em cada página?
-        // Idealmente, registrar uma única VMA para o bloco todo após o loop)
-    }
+        task.heap_next += aligned_size as u64;
+        (alloc_addr, aspace)
+    };
 
     // Registrar VMA para o bloco inteiro
-    if let Some(aspace) = &task.aspace {
+    let pml4 = {
         use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
         let mut as_lock = aspace.lock();
-        let _ = as_lock.map_region(
-            Some(crate::mm::VirtAddr::new(alloc_addr)),
-            aligned_size,
-            Protection::RW,
-            VmaFlags::empty(),
-            MemoryIntent::Heap,
-        );
-    }
-
-    // Zerar o bloco (seguro agora que está mapeado e no contexto da task)
-    unsafe {
-        core::ptr::write_bytes(alloc_addr as *mut u8, 0, aligned_size);
+        as_lock
+            .map_region(
+                Some(crate::mm::VirtAddr::new(alloc_addr)),
+                aligned_size,
+                Protection::RW,
+                VmaFlags::empty(),
+                MemoryIntent::Heap,
+            )
+            .map_err(|_| SysError::OutOfMemory)?;
+        as_lock.cr3()
+    };
+
+    // Apontar cada página para a página zero. Sem ela (ou sem memória para
+    // as page tables), a página fica ausente e o fault handler a popula.
+    let flags = crate::mm::vmm::MapFlags::PRESENT
+        | crate::mm::vmm::MapFlags::WRITABLE
+        | crate::mm::vmm::MapFlags::USER;
+    for i in 0..aligned_size / 4096 {
+        let vaddr = crate::mm::VirtAddr::new(alloc_addr + (i * 4096) as u64);
+        if crate::mm::fault::map_zero_page(pml4, vaddr, flags).is_err() {
+            break;
+        }
     }
 
-    // Importante: liberar o lock do CURRENT antes de retornar,
-    // embora no Rust o Scoped Lock faça isso automaticamente.
-
     Ok(alloc_addr as usize)
 }
 
@@ -139,46 +121,63 @@ pub fn sys_free(addr: usize, size: usize) -> SysResult<usize> {
 
 /// Mapeia memória ou handle
 ///
+/// Traduz as flags da ABI (`abi::flags::map`) para [`sys_mmap`]: sem
+/// `SHARED`, o mapeamento é privado.
+///
 /// # Args
-/// - addr: endereço desejado (0 = kernel escolhe)
+/// - addr: endereço desejado (0 = kernel escolhe), alinhado a página
 /// - size: tamanho da região
-/// - flags: permissões (READ/WRITE/EXEC)
+/// - flags: permissões (READ/WRITE/EXEC) e SHARED/PRIVATE/FIXED
 /// - handle: handle do objeto (0 = memória anônima)
 ///
 /// # Returns
 /// Endereço mapeado ou erro
 pub fn sys_map(addr: usize, size: usize, flags: u32, handle: u32) -> SysResult<usize> {
-    // TODO: Validar parâmetros
+    use super::mmap::{sys_mmap, MAP_ANONYMOUS, MAP_FIXED, MAP_PRIVATE, MAP_SHARED};
+    use crate::syscall::abi::flags::map;
+
     // TODO: Se handle != 0, validar que é mapeável
-    // TODO: Criar mapeamento no VMM do processo
-    // TODO: Retornar endereço
+    if handle != 0 {
+        return Err(SysError::NotSupported);
+    }
 
-    let _ = (addr, size, flags, handle);
-    crate::kwarn!("(Syscall) sys_map não implementado");
-    Err(SysError::NotImplemented)
+    // READ/WRITE/EXEC têm os mesmos bits de PROT_*
+    let prot = flags & (map::READ | map::WRITE | map::EXEC);
+    let mut mmap_flags = MAP_ANONYMOUS;
+    mmap_flags |= if flags & map::SHARED != 0 {
+        MAP_SHARED
+    } else {
+        MAP_PRIVATE
+    };
+    if flags & map::FIXED != 0 {
+        mmap_flags |= MAP_FIXED;
+    }
+    sys_mmap(addr, size, prot, mmap_flags)
 }
 
 /// Remove mapeamento de memória
 ///
 /// # Args
-/// - addr: endereço da região
+/// - addr: endereço da região, alinhado a página
 /// - size: tamanho da região
 ///
 /// # Returns
 /// 0 ou erro
 pub fn sys_unmap(addr: usize, size: usize) -> SysResult<usize> {
-    // TODO: Validar parâmetros
-    // TODO: Remover mapeamento do VMM
-    // TODO: Flush TLB
-
-    let _ = (addr, size);
-    crate::kwarn!("(Syscall) sys_unmap não implementado");
-    Err(SysError::NotImplemented)
+    super::mmap::sys_munmap(addr, size)
 }
 
 /// Altera as proteções de uma região de memória
+///
+/// # Args
+/// - addr: endereço da região, alinhado a página
+/// - size: tamanho da região
+/// - flags: permissões novas (READ/WRITE/EXEC)
+///
+/// # Returns
+/// 0 ou erro (`InvalidArgument` se parte da região não estiver mapeada)
 pub fn sys_mprotect(addr: usize, size: usize, flags: u32) -> SysResult<usize> {
-    let _ = (addr, size, flags);
-    crate::kwarn!("(Syscall) sys_mprotect não implementado");
-    Err(SysError::NotImplemented)
+    use crate::syscall::abi::flags::map;
+
+    super::mmap::sys_mprotect(addr, size, flags & (map::READ | map::WRITE | map::EXEC))
 }
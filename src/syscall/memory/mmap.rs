@@ -1,7 +1,13 @@
 //! # Memory Mapping Syscalls
 
 use crate::mm::aspace::vma::{MemoryIntent, Protection, VmaFlags};
-use crate::syscall::SysResult;
+use crate::mm::aspace::{ASpaceError, AddressSpace};
+use crate::mm::config::PAGE_SIZE;
+use crate::mm::VirtAddr;
+use crate::sync::Spinlock;
+use crate::syscall::uaccess::USER_SPACE_END;
+use crate::syscall::{SysError, SysResult};
+use alloc::sync::Arc;
 
 pub const PROT_NONE: u32 = 0;
 pub const PROT_READ: u32 = 1;
@@ -13,37 +19,126 @@ pub const MAP_SHARED: u32 = 0x01;
 pub const MAP_ANONYMOUS: u32 = 0x20;
 pub const MAP_FIXED: u32 = 0x10;
 
-pub fn sys_mmap(
-    _hint: usize,
-    size: usize,
-    _prot: u32,
-    flags: u32,
-    fd: i32,
-    _offset: u64,
-) -> SysResult<usize> {
-    if size == 0 || size > 0x0000_7FFF_FFFF_0000 {
-        return Err(crate::syscall::SysError::InvalidArgument);
+/// Mapeia uma região anônima no address space da task atual
+///
+/// As páginas são populadas sob demanda pelo page-fault handler (página zero
+/// na leitura, frame próprio na escrita). `hint` é só uma sugestão: ocupado,
+/// o kernel escolhe outro lugar, a menos que `MAP_FIXED` peça exatamente
+/// ele, desmapeando o que houver lá.
+///
+/// # Returns
+/// Endereço da região ou erro (`InvalidArgument` para `hint` desalinhado)
+pub fn sys_mmap(hint: usize, size: usize, prot: u32, flags: u32) -> SysResult<usize> {
+    let len = check_range(hint, size)?;
+    if prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+        return Err(SysError::InvalidArgument);
     }
+    // Exatamente um de SHARED e PRIVATE
+    if (flags & MAP_SHARED != 0) == (flags & MAP_PRIVATE != 0) {
+        return Err(SysError::InvalidArgument);
+    }
+    if flags & MAP_FIXED != 0 && hint == 0 {
+        return Err(SysError::InvalidArgument);
+    }
+    // TODO: mapeamento de arquivo
+    if flags & MAP_ANONYMOUS == 0 {
+        return Err(SysError::NotSupported);
+    }
+
+    let aspace = current_aspace()?;
+    let mut aspace = aspace.lock();
+    let hint = (hint != 0).then(|| VirtAddr::new(hint as u64));
+    let protection = convert_prot(prot);
+    let vma_flags = convert_flags(flags);
+    let intent = infer_intent(prot, flags);
 
-    if flags & MAP_ANONYMOUS == 0 && fd >= 0 {
-        return Err(crate::syscall::SysError::NotSupported);
+    if let Some(addr) = hint.filter(|_| flags & MAP_FIXED != 0) {
+        match aspace.unmap_region(addr, len) {
+            Ok(()) | Err(ASpaceError::RegionNotFound) => {}
+            Err(_) => return Err(SysError::OutOfMemory),
+        }
     }
+    let mapped = match aspace.map_region(hint, len, protection, vma_flags, intent) {
+        Err(ASpaceError::RegionOverlap) if flags & MAP_FIXED == 0 => {
+            aspace.map_region(None, len, protection, vma_flags, intent)
+        }
+        result => result,
+    };
+    mapped
+        .map(|addr| addr.as_u64() as usize)
+        .map_err(|_| SysError::OutOfMemory)
+}
 
-    Err(crate::syscall::SysError::NotSupported)
+/// Remove as páginas de `[addr, addr + size)` do address space da task
+/// atual
+///
+/// Os frames perdem a referência e as tabelas de página vazias voltam ao
+/// PMM. Um intervalo sem nada mapeado não é erro.
+pub fn sys_munmap(addr: usize, size: usize) -> SysResult<usize> {
+    if addr == 0 {
+        return Err(SysError::InvalidArgument);
+    }
+    let len = check_range(addr, size)?;
+
+    let aspace = current_aspace()?;
+    let result = aspace.lock().unmap_region(VirtAddr::new(addr as u64), len);
+    match result {
+        Ok(()) | Err(ASpaceError::RegionNotFound) => Ok(0),
+        Err(_) => Err(SysError::OutOfMemory),
+    }
 }
 
-pub fn sys_munmap(_addr: usize, size: usize) -> SysResult<usize> {
-    if size == 0 {
-        return Err(crate::syscall::SysError::InvalidArgument);
+/// Muda para `prot` a proteção de `[addr, addr + size)` no address space
+/// da task atual
+///
+/// O intervalo pode atravessar várias VMAs (divididas nas bordas) e vale
+/// também para as páginas já mapeadas: tirar `PROT_WRITE` faz a próxima
+/// escrita falhar, e o caso RW → RX de um JIT funciona sem remapear.
+///
+/// # Returns
+/// 0, ou `InvalidArgument` se `addr` estiver desalinhado ou se algum trecho
+/// do intervalo não estiver mapeado
+pub fn sys_mprotect(addr: usize, size: usize, prot: u32) -> SysResult<usize> {
+    if addr == 0 || prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+    let len = check_range(addr, size)?;
+
+    let aspace = current_aspace()?;
+    let result = aspace
+        .lock()
+        .protect_region(VirtAddr::new(addr as u64), len, convert_prot(prot));
+    match result {
+        Ok(()) => Ok(0),
+        Err(ASpaceError::NotMapped) => Err(SysError::InvalidArgument),
+        Err(_) => Err(SysError::OutOfMemory),
     }
-    Err(crate::syscall::SysError::NotSupported)
 }
 
-pub fn sys_mprotect(_addr: usize, size: usize, _prot: u32) -> SysResult<usize> {
-    if size == 0 {
-        return Err(crate::syscall::SysError::InvalidArgument);
+/// Valida `addr` (alinhado a página, 0 = sem endereço) e `size` (não nulo,
+/// sem sair do espaço de usuário) e retorna o tamanho alinhado
+fn check_range(addr: usize, size: usize) -> SysResult<usize> {
+    if size == 0 || addr % PAGE_SIZE != 0 {
+        return Err(SysError::InvalidArgument);
     }
-    Err(crate::syscall::SysError::NotSupported)
+    let len = size
+        .checked_add(PAGE_SIZE - 1)
+        .ok_or(SysError::InvalidArgument)?
+        & !(PAGE_SIZE - 1);
+    match addr.checked_add(len) {
+        Some(end) if end <= USER_SPACE_END => Ok(len),
+        _ => Err(SysError::InvalidArgument),
+    }
+}
+
+/// Address space da task atual (o CURRENT é solto antes de travá-lo: o
+/// page-fault handler precisa dele)
+fn current_aspace() -> SysResult<Arc<Spinlock<AddressSpace>>> {
+    let guard = crate::sched::core::CURRENT.lock();
+    guard
+        .as_ref()
+        .and_then(|task| task.aspace.clone())
+        .ok_or(SysError::BadAddress)
 }
 
 fn convert_prot(prot: u32) -> Protection {
@@ -58,6 +58,9 @@ pub mod handle;
 /// Números de syscall
 pub mod numbers;
 
+/// Validação de ponteiros de usuário
+pub mod uaccess;
+
 // =============================================================================
 // IMPLEMENTATIONS
 // =============================================================================
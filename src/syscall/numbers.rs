@@ -30,12 +30,13 @@
 pub const SYS_EXIT: usize = 0x01;
 
 /// Cria um novo processo.
-/// Args: (path_ptr, path_len, args_ptr, args_len)
+/// Args: (path_ptr, path_len, argv_ptr, envp_ptr) — arrays de `char*`
+/// terminados em NULL, 0 = padrão (`argv = [path]`, `envp` vazio)
 /// Retorno: pid ou erro
 pub const SYS_SPAWN: usize = 0x02;
 
-/// Espera um processo terminar.
-/// Args: (pid, timeout_ms)
+/// Espera um processo filho terminar e o coleta.
+/// Args: (pid, timeout_ms) — pid 0 = qualquer filho, timeout 0 = sem prazo
 /// Retorno: exit_code ou erro
 pub const SYS_WAIT: usize = 0x03;
 
@@ -69,6 +70,26 @@ pub const SYS_THREAD_CREATE: usize = 0x08;
 /// Retorno: Nunca retorna
 pub const SYS_THREAD_EXIT: usize = 0x09;
 
+/// Envia um sinal a uma thread.
+/// Args: (tid, signum)
+/// Retorno: 0 ou erro
+pub const SYS_KILL: usize = 0x0A;
+
+/// Retorna de um signal handler, restaurando o contexto interrompido.
+/// Args: nenhum (o frame está na stack do usuário)
+/// Retorno: Nunca retorna para o chamador (tratada pelo dispatcher)
+pub const SYS_SIGRETURN: usize = 0x0B;
+
+/// Registra a ação de um sinal.
+/// Args: (signum, handler, flags, restorer) — handler 0 = SIG_DFL, 1 = SIG_IGN
+/// Retorno: handler anterior ou erro
+pub const SYS_SIGACTION: usize = 0x0C;
+
+/// Altera a máscara de sinais bloqueados da thread.
+/// Args: (how, set) — how: 0 = BLOCK, 1 = UNBLOCK, 2 = SETMASK
+/// Retorno: máscara anterior
+pub const SYS_SIGPROCMASK: usize = 0x0D;
+
 // ============================================================================
 // MEMÓRIA (0x10 - 0x1F)
 // ============================================================================
@@ -166,6 +187,26 @@ pub const SYS_PORT_CONNECT: usize = 0x37;
 /// Retorno: tamanho em bytes ou erro
 pub const SYS_SHM_GET_SIZE: usize = 0x38;
 
+/// Operação de futex multiplexada (`abi::futex::WAIT` / `abi::futex::WAKE`).
+/// Args: (addr, op, val, timeout_ms)
+/// Retorno: WAIT → 0 ou erro; WAKE → número de threads acordadas
+pub const SYS_FUTEX: usize = 0x39;
+
+/// Cria um contador de eventos (eventfd).
+/// Args: (initial, flags)
+/// Retorno: handle do contador ou erro
+pub const SYS_EVENTFD_CREATE: usize = 0x3A;
+
+/// Lê um contador de eventos (zera, ou decrementa 1 no modo semáforo).
+/// Args: (handle, out_ptr, flags)
+/// Retorno: 0 (valor em *out_ptr) ou erro
+pub const SYS_EVENTFD_READ: usize = 0x3B;
+
+/// Soma a um contador de eventos.
+/// Args: (handle, value, flags)
+/// Retorno: 0 ou erro
+pub const SYS_EVENTFD_WRITE: usize = 0x3C;
+
 // ============================================================================
 // GRÁFICOS / INPUT (0x40 - 0x4F)
 // ============================================================================
@@ -422,7 +463,7 @@ pub const SYS_CHDIR: usize = 0x7F;
 // ============================================================================
 
 /// Espera eventos em múltiplos handles.
-/// Args: (fds_ptr, nfds, timeout_ms)
+/// Args: (fds_ptr, nfds, timeout_ns) (timeout negativo = infinito, 0 = não bloqueia)
 /// Retorno: número de handles com eventos ou erro
 pub const SYS_POLL: usize = 0x80;
 
@@ -431,7 +472,7 @@ pub const SYS_POLL: usize = 0x80;
 // ============================================================================
 
 /// Obtém informações do sistema.
-/// Args: (out_ptr, out_len)
+/// Args: (buf_ptr) (destino de um `SysInfo`, 64 bytes)
 /// Retorno: bytes escritos ou erro
 pub const SYS_SYSINFO: usize = 0xF0;
 
@@ -455,6 +496,16 @@ pub const SYS_CONSOLE_WRITE: usize = 0xF3;
 /// Retorno: bytes lidos
 pub const SYS_CONSOLE_READ: usize = 0xF4;
 
+/// Lê o buffer circular do log do kernel.
+/// Args: (buf_ptr, len, min_level) (nível mínimo: 0 = trace, ..., 4 = error)
+/// Retorno: bytes escritos (só linhas inteiras, as mais recentes)
+pub const SYS_KLOG_READ: usize = 0xF5;
+
+/// Muda o nível mínimo de log (global ou de um subsistema).
+/// Args: (subsystem, level) (`ALL_SUBSYSTEMS` = global, `INHERIT` = tira o override)
+/// Retorno: 0 ou erro
+pub const SYS_KLOG_SET_LEVEL: usize = 0xF6;
+
 /// Comandos de debug (apenas em builds debug).
 /// Args: (cmd, arg_ptr, arg_len)
 /// Retorno: depende do comando
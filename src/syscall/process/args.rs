@@ -0,0 +1,227 @@
+//! # argv/envp do spawn
+//!
+//! Cópia dos arrays de strings do usuário (ponteiros terminados em NULL,
+//! strings terminadas em NUL) para o kernel. Cada ponteiro e cada string é
+//! validado na hora da leitura; o total copiado (ponteiros + strings) é
+//! limitado por `EXEC_ARGS_MAX`, o mesmo limite da stack inicial.
+
+use crate::mm::config::PAGE_SIZE;
+use crate::sched::config::EXEC_ARGS_MAX;
+use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::copy_from_user;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Argumentos de um novo processo, já no kernel
+#[derive(Debug, Default)]
+pub struct ExecArgs {
+    pub argv: Vec<String>,
+    pub envp: Vec<String>,
+}
+
+impl ExecArgs {
+    pub fn argv(&self) -> Vec<&str> {
+        self.argv.iter().map(String::as_str).collect()
+    }
+
+    pub fn envp(&self) -> Vec<&str> {
+        self.envp.iter().map(String::as_str).collect()
+    }
+}
+
+/// Copia argv e envp do usuário (ponteiro 0 = array vazio)
+///
+/// Retorna `InvalidArgument` para qualquer ponteiro inválido ou string que
+/// não seja UTF-8, e `LimitReached` se o total passar de `EXEC_ARGS_MAX`.
+pub fn copy_exec_args(argv_ptr: usize, envp_ptr: usize) -> SysResult<ExecArgs> {
+    let mut budget = EXEC_ARGS_MAX;
+    let mut read = |addr: usize, dst: &mut [u8]| copy_from_user(dst, addr);
+    Ok(ExecArgs {
+        argv: read_string_array(argv_ptr, &mut budget, &mut read)?,
+        envp: read_string_array(envp_ptr, &mut budget, &mut read)?,
+    })
+}
+
+/// Lê um array de ponteiros terminado em NULL e as strings apontadas.
+///
+/// `read(addr, dst)` copia `dst.len()` bytes de `addr`.
+fn read_string_array<R>(
+    array_ptr: usize,
+    budget: &mut usize,
+    read: &mut R,
+) -> SysResult<Vec<String>>
+where
+    R: FnMut(usize, &mut [u8]) -> SysResult<()>,
+{
+    let mut strings = Vec::new();
+    if array_ptr == 0 {
+        return Ok(strings);
+    }
+
+    for index in 0.. {
+        let slot = index * 8;
+        let entry = array_ptr
+            .checked_add(slot)
+            .ok_or(SysError::InvalidArgument)?;
+        let mut word = [0u8; 8];
+        read(entry, &mut word).map_err(|_| SysError::InvalidArgument)?;
+        let ptr = u64::from_le_bytes(word) as usize;
+        if ptr == 0 {
+            break;
+        }
+
+        *budget = budget.checked_sub(8).ok_or(SysError::LimitReached)?;
+        strings.push(read_c_string(ptr, budget, read)?);
+    }
+    Ok(strings)
+}
+
+/// Lê uma string terminada em NUL, página a página (sem passar do fim da
+/// página do último byte validado)
+fn read_c_string<R>(ptr: usize, budget: &mut usize, read: &mut R) -> SysResult<String>
+where
+    R: FnMut(usize, &mut [u8]) -> SysResult<()>,
+{
+    let mut bytes = Vec::new();
+    let mut addr = ptr;
+    loop {
+        if *budget == 0 {
+            return Err(SysError::LimitReached);
+        }
+        let to_page_end = PAGE_SIZE - addr % PAGE_SIZE;
+        let mut chunk = alloc::vec![0u8; core::cmp::min(to_page_end, *budget)];
+        read(addr, &mut chunk).map_err(|_| SysError::InvalidArgument)?;
+
+        match chunk.iter().position(|&b| b == 0) {
+            Some(nul) => {
+                bytes.extend_from_slice(&chunk[..nul]);
+                *budget -= nul + 1;
+                break;
+            }
+            None => {
+                bytes.extend_from_slice(&chunk);
+                *budget -= chunk.len();
+                addr = addr
+                    .checked_add(chunk.len())
+                    .ok_or(SysError::InvalidArgument)?;
+            }
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| SysError::InvalidArgument)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::exec::stack;
+
+    /// Memória de usuário simulada a partir de `BASE`
+    const BASE: usize = 0x40_0000;
+
+    struct FakeUser {
+        mem: Vec<u8>,
+    }
+
+    impl FakeUser {
+        fn new() -> Self {
+            Self {
+                mem: alloc::vec![0u8; 0x2000],
+            }
+        }
+
+        fn put(&mut self, addr: usize, bytes: &[u8]) {
+            let at = addr - BASE;
+            self.mem[at..at + bytes.len()].copy_from_slice(bytes);
+        }
+
+        fn reader(&self) -> impl FnMut(usize, &mut [u8]) -> SysResult<()> + '_ {
+            move |addr, dst| {
+                let at = addr.checked_sub(BASE).ok_or(SysError::InvalidArgument)?;
+                let src = self
+                    .mem
+                    .get(at..at + dst.len())
+                    .ok_or(SysError::InvalidArgument)?;
+                dst.copy_from_slice(src);
+                Ok(())
+            }
+        }
+    }
+
+    fn word(image: &stack::StackImage, index: usize) -> u64 {
+        let at = index * 8;
+        u64::from_le_bytes(image.bytes[at..at + 8].try_into().unwrap())
+    }
+
+    fn c_str(image: &stack::StackImage, addr: u64) -> String {
+        let start = (addr - image.sp) as usize;
+        let len = image.bytes[start..].iter().position(|&b| b == 0).unwrap();
+        String::from_utf8(image.bytes[start..start + len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_args_round_trip_into_stack_image() {
+        let mut user = FakeUser::new();
+        let argv = BASE;
+        user.put(argv, &((BASE + 0x100) as u64).to_le_bytes());
+        user.put(argv + 8, &((BASE + 0x200) as u64).to_le_bytes());
+        user.put(argv + 16, &0u64.to_le_bytes());
+        user.put(BASE + 0x100, b"/bin/app\0");
+        user.put(BASE + 0x200, b"--verbose\0");
+
+        let mut budget = EXEC_ARGS_MAX;
+        let args = read_string_array(argv, &mut budget, &mut user.reader()).unwrap();
+        assert_eq!(args, ["/bin/app", "--verbose"]);
+        assert_eq!(budget, EXEC_ARGS_MAX - 16 - 9 - 10);
+
+        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+        let image = stack::build(0x7FFF_FFFF_F000, &refs, &[], &[], EXEC_ARGS_MAX).unwrap();
+        assert_eq!(word(&image, 0), 2);
+        assert_eq!(c_str(&image, word(&image, 1)), "/bin/app");
+        assert_eq!(c_str(&image, word(&image, 2)), "--verbose");
+        assert_eq!(word(&image, 3), 0);
+    }
+
+    #[test]
+    fn test_bad_string_pointer_is_rejected() {
+        let mut user = FakeUser::new();
+        user.put(BASE, &((BASE + 0x100) as u64).to_le_bytes());
+        user.put(BASE + 8, &0xDEAD_0000u64.to_le_bytes());
+        user.put(BASE + 16, &0u64.to_le_bytes());
+        user.put(BASE + 0x100, b"ok\0");
+
+        let mut budget = EXEC_ARGS_MAX;
+        assert_eq!(
+            read_string_array(BASE, &mut budget, &mut user.reader()),
+            Err(SysError::InvalidArgument)
+        );
+        // Array em endereço inválido
+        assert_eq!(
+            read_string_array(0x10, &mut budget, &mut user.reader()),
+            Err(SysError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_args_budget() {
+        let mut user = FakeUser::new();
+        user.put(BASE, &((BASE + 0x100) as u64).to_le_bytes());
+        user.put(BASE + 8, &0u64.to_le_bytes());
+        user.put(BASE + 0x100, &[b'x'; 64]);
+        user.put(BASE + 0x140, b"\0");
+
+        let mut budget = 40;
+        assert_eq!(
+            read_string_array(BASE, &mut budget, &mut user.reader()),
+            Err(SysError::LimitReached)
+        );
+
+        let mut budget = 8 + 65;
+        let args = read_string_array(BASE, &mut budget, &mut user.reader()).unwrap();
+        assert_eq!(args[0].len(), 64);
+        assert_eq!(budget, 0);
+    }
+}
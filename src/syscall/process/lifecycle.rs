@@ -59,16 +59,16 @@ pub fn sys_exit(code: i32) -> ! {
 /// # Args
 /// - path_ptr: caminho do executável (userspace)
 /// - path_len: tamanho do caminho
-/// - args_ptr: argumentos (ignorado por enquanto)
-/// - args_len: número de argumentos (ignorado por enquanto)
+/// - argv_ptr: array de `char*` terminado em NULL (0 = `[path]`)
+/// - envp_ptr: array de `char*` terminado em NULL (0 = vazio)
 ///
 /// # Returns
 /// PID do novo processo ou erro
 pub fn sys_spawn(
     path_ptr: usize,
     path_len: usize,
-    _args_ptr: usize,
-    _args_len: usize,
+    argv_ptr: usize,
+    envp_ptr: usize,
 ) -> SysResult<usize> {
     // Validar ponteiros básicos
     if path_ptr == 0 || path_len == 0 || path_len > 256 {
@@ -85,6 +85,15 @@ pub fn sys_spawn(
         }
     };
 
+    // Argumentos e ambiente, copiados para o kernel
+    let mut args = super::args::copy_exec_args(argv_ptr, envp_ptr).map_err(|e| {
+        crate::kerror!("(Syscall) sys_spawn: argv/envp inválidos");
+        e
+    })?;
+    if argv_ptr == 0 {
+        args.argv.push(path.clone());
+    }
+
     // Obter PID do chamador para definir como pai
     let current_tid = {
         let guard = crate::sched::core::CURRENT.lock();
@@ -92,7 +101,7 @@ pub fn sys_spawn(
     };
 
     // Chamar função de spawn existente
-    match crate::sched::exec::spawn(&path, current_tid) {
+    match crate::sched::exec::spawn(&path, &args.argv(), &args.envp(), current_tid) {
         Ok(pid) => {
             crate::kinfo!("(Syscall) spawn OK, PID=", pid.as_u32() as u64);
             Ok(pid.as_u32() as usize)
@@ -105,6 +114,7 @@ pub fn sys_spawn(
                 crate::sched::ExecError::InvalidFormat => Err(SysError::InvalidArgument),
                 crate::sched::ExecError::OutOfMemory => Err(SysError::OutOfMemory),
                 crate::sched::ExecError::PermissionDenied => Err(SysError::PermissionDenied),
+                crate::sched::ExecError::InvalidArguments => Err(SysError::InvalidArgument),
             }
         }
     }
@@ -113,27 +123,30 @@ pub fn sys_spawn(
 /// Espera processo filho terminar
 ///
 /// # Args
-/// - pid: PID do processo (0 = qualquer filho - NOT YET SUPPORTED)
-/// - timeout_ms: timeout em ms (0 = bloqueante infinito - NOT YET SUPPORTED)
+/// - pid: PID do filho (0 = qualquer filho)
+/// - timeout_ms: timeout em ms (0 = bloqueante infinito)
 ///
 /// # Returns
-/// Exit code do processo ou erro
-pub fn sys_wait(pid: usize, _timeout_ms: u64) -> SysResult<usize> {
-    if pid == 0 {
-        crate::kwarn!("(Syscall) sys_wait(0) não implementado (any child)");
-        return Err(SysError::NotImplemented);
-    }
+/// Exit code do filho, que é coletado (o status fica guardado mesmo se o
+/// filho terminou antes da chamada). `NotFound` se `pid` não é filho do
+/// chamador, `Timeout` se o prazo esgotou, `Interrupted` com um sinal.
+pub fn sys_wait(pid: usize, timeout_ms: u64) -> SysResult<usize> {
+    use crate::sched::task::family::{self, WaitError};
 
-    let tid = crate::sys::types::Tid::new(pid as u32);
+    if pid > u32::MAX as usize {
+        return Err(SysError::InvalidArgument);
+    }
+    let target = match pid {
+        0 => None,
+        pid => Some(crate::sys::types::Tid::new(pid as u32)),
+    };
+    let timeout_ns = timeout_ms.saturating_mul(1_000_000);
 
-    // Tenta coletar o zumbi
-    if let Some(code) = crate::sched::task::lifecycle::find_and_collect_zombie(tid) {
-        Ok(code as usize)
-    } else {
-        // Se não for zumbi, pode estar rodando ou não existir
-        // TODO: Verificar se processo existe. Se existir, deveria bloquear.
-        // Por enquanto, Supervisor usa timeout e poll, então retornamos NotFound se não for zumbi.
-        Err(SysError::NotFound)
+    match family::wait(target, timeout_ns) {
+        Ok((_, code)) => Ok(code as usize),
+        Err(WaitError::NoChild) => Err(SysError::NotFound),
+        Err(WaitError::Timeout) => Err(SysError::Timeout),
+        Err(WaitError::Interrupted) => Err(SysError::Interrupted),
     }
 }
 
@@ -2,8 +2,11 @@
 //!
 //! Controle do ciclo de vida de processos.
 
+pub mod args;
 pub mod info;
 pub mod lifecycle;
+pub mod signal;
 
 pub use info::*;
 pub use lifecycle::*;
+pub use signal::*;
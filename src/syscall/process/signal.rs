@@ -0,0 +1,242 @@
+//! # Signal Syscalls
+//!
+//! kill, sigaction, sigprocmask. O retorno de handlers (`SYS_SIGRETURN`) é
+//! tratado direto pelo dispatcher, que precisa do contexto completo.
+
+use core::pin::Pin;
+
+use crate::mm::fault::AccessType;
+use crate::sched::signal::delivery::blocked_mask;
+use crate::sched::signal::handler::{SigAction, SignalHandlers, SIG_DFL, SIG_IGN};
+use crate::sched::signal::{SignalError, NSIG, SIGKILL, SIGSTOP};
+use crate::sys::types::Tid;
+use crate::syscall::abi::SyscallArgs;
+use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::{check_user_range, validate_user_buffer};
+
+/// `how` de `SYS_SIGPROCMASK`
+pub mod sigmask_how {
+    /// Adiciona `set` aos bloqueados
+    pub const BLOCK: usize = 0;
+    /// Remove `set` dos bloqueados
+    pub const UNBLOCK: usize = 1;
+    /// Substitui os bloqueados por `set`
+    pub const SETMASK: usize = 2;
+}
+
+// === WRAPPERS ===
+
+pub fn sys_kill_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_kill(args.arg1, args.arg2 as i32)
+}
+
+pub fn sys_sigaction_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_sigaction(args.arg1 as i32, args.arg2, args.arg3, args.arg4)
+}
+
+pub fn sys_sigprocmask_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_sigprocmask(args.arg1, args.arg2 as u64)
+}
+
+// === IMPLEMENTAÇÕES ===
+
+/// Envia `signum` para a thread `tid`
+///
+/// O sinal é entregue quando a thread voltar para user mode; uma espera
+/// interrompível em andamento é acordada.
+pub fn sys_kill(tid: usize, signum: i32) -> SysResult<usize> {
+    if tid == 0 || tid > u32::MAX as usize {
+        return Err(SysError::InvalidArgument);
+    }
+
+    match crate::sched::signal::send(Tid::new(tid as u32), signum) {
+        Ok(()) => Ok(0),
+        Err(SignalError::InvalidSignal) => Err(SysError::InvalidArgument),
+        Err(SignalError::NoSuchTask) => Err(SysError::NotFound),
+    }
+}
+
+/// Registra a ação de `signum` para a thread atual
+///
+/// # Args
+/// - handler: `SIG_DFL`, `SIG_IGN` ou endereço do handler (executável)
+/// - flags: guardadas na `SigAction`
+/// - restorer: código que chama `SYS_SIGRETURN`; obrigatório com handler
+///
+/// # Returns
+/// Handler anterior
+pub fn sys_sigaction(
+    signum: i32,
+    handler: usize,
+    flags: usize,
+    restorer: usize,
+) -> SysResult<usize> {
+    let action = SigAction {
+        handler: handler as u64,
+        flags: flags as u64,
+        restorer: restorer as u64,
+    };
+    check_action(signum, &action)?;
+    if is_user_function(&action) {
+        validate_user_buffer(handler, 1, AccessType::Execute)?;
+        validate_user_buffer(restorer, 1, AccessType::Execute)?;
+    }
+
+    with_current(|task| install_action(&mut task.signal_handlers, signum, action))?
+        .map(|old| old.handler as usize)
+}
+
+/// Altera os sinais bloqueados da thread atual
+///
+/// SIGKILL e SIGSTOP nunca ficam bloqueados. Retorna a máscara anterior.
+pub fn sys_sigprocmask(how: usize, set: u64) -> SysResult<usize> {
+    with_current(|task| {
+        let old = task.blocked_signals;
+        task.blocked_signals = apply_mask(old, how, set)?;
+        Ok(old as usize)
+    })?
+}
+
+/// Roda `f` com a task atual
+fn with_current<R>(f: impl FnOnce(&mut crate::sched::task::Task) -> R) -> SysResult<R> {
+    let mut current = crate::sched::core::CURRENT.lock();
+    let task = current.as_mut().ok_or(SysError::NotFound)?;
+    Ok(f(unsafe { Pin::get_unchecked_mut(task.as_mut()) }))
+}
+
+/// A ação aponta para uma função do usuário (não `SIG_DFL`/`SIG_IGN`)?
+fn is_user_function(action: &SigAction) -> bool {
+    action.handler != SIG_DFL && action.handler != SIG_IGN
+}
+
+/// Validação que não depende do address space: sinal capturável e
+/// handler/restorer no espaço de usuário
+fn check_action(signum: i32, action: &SigAction) -> SysResult<()> {
+    if signum <= 0 || signum as usize >= NSIG {
+        return Err(SysError::InvalidArgument);
+    }
+    // SIGKILL e SIGSTOP ficam sempre na ação padrão
+    if (signum == SIGKILL || signum == SIGSTOP) && action.handler != SIG_DFL {
+        return Err(SysError::InvalidArgument);
+    }
+    if is_user_function(action) {
+        check_user_range(action.handler as usize, 1)?;
+        check_user_range(action.restorer as usize, 1)?;
+    }
+    Ok(())
+}
+
+/// Registra `action` em `handlers`, retornando a anterior
+fn install_action(
+    handlers: &mut SignalHandlers,
+    signum: i32,
+    action: SigAction,
+) -> SysResult<SigAction> {
+    let old = handlers.get(signum).ok_or(SysError::InvalidArgument)?;
+    // SIG_DFL em SIGKILL/SIGSTOP: nada muda
+    if (signum == SIGKILL || signum == SIGSTOP) && action.handler == SIG_DFL {
+        return Ok(old);
+    }
+    if !handlers.set_action(signum, action) {
+        return Err(SysError::InvalidArgument);
+    }
+    Ok(old)
+}
+
+/// Nova máscara de bloqueados a partir de `old`
+fn apply_mask(old: u64, how: usize, set: u64) -> SysResult<u64> {
+    let mask = match how {
+        sigmask_how::BLOCK => old | set,
+        sigmask_how::UNBLOCK => old & !set,
+        sigmask_how::SETMASK => set,
+        _ => return Err(SysError::InvalidArgument),
+    };
+    Ok(blocked_mask(mask))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::signal::handler::SignalDisposition;
+    use crate::sched::signal::{SIGINT, SIGUSR1};
+
+    const HANDLER: u64 = 0x40_1000;
+    const RESTORER: u64 = 0x40_2000;
+
+    fn action(handler: u64) -> SigAction {
+        SigAction {
+            handler,
+            flags: 0,
+            restorer: RESTORER,
+        }
+    }
+
+    #[test]
+    fn test_registered_handler_is_retrievable() {
+        let mut handlers = SignalHandlers::new();
+        assert_eq!(check_action(SIGUSR1, &action(HANDLER)), Ok(()));
+
+        let old = install_action(&mut handlers, SIGUSR1, action(HANDLER)).unwrap();
+        assert_eq!(old, SigAction::DEFAULT);
+        assert_eq!(handlers.get(SIGUSR1), Some(action(HANDLER)));
+        assert_eq!(
+            handlers.get_action(SIGUSR1),
+            SignalDisposition::Handler(HANDLER)
+        );
+
+        // Trocar devolve a anterior
+        let old = install_action(&mut handlers, SIGUSR1, action(SIG_IGN)).unwrap();
+        assert_eq!(old.handler, HANDLER);
+        assert_eq!(handlers.get_action(SIGUSR1), SignalDisposition::Ignore);
+    }
+
+    #[test]
+    fn test_sigaction_rejects_kill_stop_and_bad_pointers() {
+        assert_eq!(
+            check_action(SIGKILL, &action(HANDLER)),
+            Err(SysError::InvalidArgument)
+        );
+        assert_eq!(
+            check_action(SIGSTOP, &action(SIG_IGN)),
+            Err(SysError::InvalidArgument)
+        );
+        assert_eq!(check_action(SIGKILL, &SigAction::DEFAULT), Ok(()));
+        assert_eq!(
+            check_action(0, &action(HANDLER)),
+            Err(SysError::InvalidArgument)
+        );
+
+        // Handler no kernel, sem restorer
+        assert_eq!(
+            check_action(SIGINT, &action(0xFFFF_8000_0000_1000)),
+            Err(SysError::InvalidArgument)
+        );
+        let no_restorer = SigAction {
+            restorer: 0,
+            ..action(HANDLER)
+        };
+        assert_eq!(
+            check_action(SIGINT, &no_restorer),
+            Err(SysError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_sigprocmask_never_blocks_kill_or_stop() {
+        let set = (1 << SIGUSR1) | (1 << SIGKILL) | (1 << SIGSTOP);
+        assert_eq!(apply_mask(0, sigmask_how::BLOCK, set), Ok(1 << SIGUSR1));
+        assert_eq!(
+            apply_mask(1 << SIGUSR1, sigmask_how::UNBLOCK, 1 << SIGUSR1),
+            Ok(0)
+        );
+        assert_eq!(
+            apply_mask(1 << SIGINT, sigmask_how::SETMASK, set),
+            Ok(1 << SIGUSR1)
+        );
+        assert_eq!(apply_mask(0, 7, set), Err(SysError::InvalidArgument));
+    }
+}
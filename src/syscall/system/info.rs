@@ -2,13 +2,15 @@
 //!
 //! sysinfo, debug, reboot, poweroff, console I/O
 
-use crate::syscall::abi::SyscallArgs;
+use crate::mm::fault::AccessType;
+use crate::syscall::abi::{SysInfo, SyscallArgs};
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::{copy_to_user, validate_user_buffer, UserAccessGuard};
 
 // === WRAPPERS ===
 
 pub fn sys_sysinfo_wrapper(args: &SyscallArgs) -> SysResult<usize> {
-    sys_sysinfo(args.arg1, args.arg2)
+    sys_sysinfo(args.arg1)
 }
 
 pub fn sys_debug_wrapper(args: &SyscallArgs) -> SysResult<usize> {
@@ -34,9 +36,45 @@ pub fn sys_console_read_wrapper(args: &SyscallArgs) -> SysResult<usize> {
 // === IMPLEMENTAÇÕES ===
 
 /// Obtém informações do sistema
-pub fn sys_sysinfo(out_ptr: usize, out_len: usize) -> SysResult<usize> {
-    let _ = (out_ptr, out_len);
-    Err(SysError::NotImplemented)
+///
+/// # Args
+/// - buf_ptr: destino de um `SysInfo` (64 bytes)
+///
+/// # Returns
+/// Bytes escritos ou erro
+pub fn sys_sysinfo(buf_ptr: usize) -> SysResult<usize> {
+    let info = collect_sysinfo();
+    // SAFETY: `SysInfo` é `repr(C)`, só inteiros e sem padding
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            &info as *const SysInfo as *const u8,
+            core::mem::size_of::<SysInfo>(),
+        )
+    };
+    copy_to_user(buf_ptr, bytes)?;
+    Ok(bytes.len())
+}
+
+/// Versão do kernel reportada em `SysInfo::version`
+pub const KERNEL_VERSION: &str = concat!("Forge ", env!("CARGO_PKG_VERSION"));
+
+/// Fotografia atual de `SysInfo`
+pub fn collect_sysinfo() -> SysInfo {
+    let page = crate::mm::config::PAGE_SIZE as u64;
+    let pfm = *crate::mm::pfm::get().lock().stats();
+
+    let mut version = [0u8; 32];
+    let len = KERNEL_VERSION.len().min(version.len());
+    version[..len].copy_from_slice(&KERNEL_VERSION.as_bytes()[..len]);
+
+    SysInfo {
+        uptime_ns: crate::core::time::now_ns(),
+        total_ram: pfm.total_frames * page,
+        free_ram: pfm.free_frames * page,
+        processes: crate::sched::core::task_count() as u32,
+        abi_version: crate::syscall::abi::ABI_VERSION,
+        version,
+    }
 }
 
 /// Comandos de debug
@@ -168,42 +206,24 @@ pub fn sys_poweroff() -> SysResult<usize> {
     }
 }
 
-/// Escreve na console (framebuffer + serial)
+/// Escreve na console (serial)
 ///
-/// VERSÃO MÍNIMA: Usa inline assembly direto para evitar qualquer
-/// código Rust que possa gerar instruções SSE/AVX
+/// O buffer é validado contra o address space da task atual antes de ser
+/// lido; ponteiros fora do espaço de usuário ou não mapeados resultam em
+/// `InvalidArgument`. Escritas maiores que 4KB são truncadas.
 pub fn sys_console_write(buf_ptr: usize, len: usize) -> SysResult<usize> {
-    if buf_ptr == 0 || len == 0 {
+    if len == 0 {
         return Ok(0);
     }
 
     // Limitar tamanho
-    let safe_len = if len > 4096 { 4096 } else { len };
-
-    // Escrever diretamente na porta serial COM1 (0x3F8) usando inline assembly
-    // Isso evita chamar qualquer função Rust que possa usar SSE
-    for i in 0..safe_len {
-        let byte: u8 = unsafe { core::ptr::read_volatile((buf_ptr + i) as *const u8) };
+    let safe_len = core::cmp::min(len, 4096);
+    validate_user_buffer(buf_ptr, safe_len, AccessType::Read)?;
 
-        // Esperar que TX esteja vazio (bit 5 de Line Status Register)
-        // Port 0x3FD = 0x3F8 + 5
-        unsafe {
-            core::arch::asm!(
-                "mov dx, 0x3FD", // Line Status Register
-                "2:",
-                "in al, dx",
-                "test al, 0x20", // Empty Transmitter Holding Register
-                "jz 2b",
-                "mov dx, 0x3F8", // Data Register
-                "mov al, {byte}",
-                "out dx, al",
-                byte = in(reg_byte) byte,
-                out("al") _,
-                out("dx") _,
-                options(nostack, preserves_flags),
-            );
-        }
-    }
+    let _uaccess = UserAccessGuard::new();
+    // SAFETY: intervalo validado acima
+    let bytes = unsafe { core::slice::from_raw_parts(buf_ptr as *const u8, safe_len) };
+    crate::drivers::serial::write_bytes(bytes);
 
     Ok(safe_len)
 }
@@ -227,16 +247,3 @@ pub mod debug_cmd {
     pub const DUMP_MEM: u32 = 0x03;
     pub const BREAKPOINT: u32 = 0x04;
 }
-
-/// Informações do sistema
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-pub struct SysInfo {
-    pub kernel_version: u32,
-    pub abi_version: u32,
-    pub total_memory: u64,
-    pub free_memory: u64,
-    pub uptime_ms: u64,
-    pub num_cpus: u32,
-    pub num_processes: u32,
-}
@@ -0,0 +1,82 @@
+//! # Kernel Log Syscall
+//!
+//! Leitura do buffer circular do log do kernel (estilo `dmesg`) e ajuste
+//! do nível de log em runtime.
+
+use crate::core::debug::klog::{self, Level, Subsystem};
+use crate::core::debug::kmsg;
+use crate::syscall::abi::flags::klog as kf;
+use crate::syscall::abi::SyscallArgs;
+use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::copy_to_user;
+
+// === WRAPPERS ===
+
+pub fn sys_klog_read_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_klog_read(args.arg1, args.arg2, args.arg3 as u32)
+}
+
+pub fn sys_klog_set_level_wrapper(args: &SyscallArgs) -> SysResult<usize> {
+    sys_klog_set_level(args.arg1 as u32, args.arg2 as u32)
+}
+
+// === IMPLEMENTAÇÕES ===
+
+/// Lê as linhas retidas do log do kernel
+///
+/// # Args
+/// - buf_ptr: buffer de destino
+/// - len: tamanho do buffer
+/// - min_level: nível mínimo (0 = trace, ..., 4 = error)
+///
+/// # Returns
+/// Bytes escritos ou erro. Só entram linhas inteiras; se não couberem
+/// todas, ficam as mais recentes.
+pub fn sys_klog_read(buf_ptr: usize, len: usize, min_level: u32) -> SysResult<usize> {
+    let min = parse_level(min_level)?;
+    if len == 0 {
+        return Ok(0);
+    }
+
+    // Formata em memória do kernel: a cópia para o usuário pode faltar
+    // página, e o tratamento da falta loga
+    let mut text = alloc::vec![0u8; len.min(kmsg::capacity())];
+    let written = kmsg::read(&mut text, min);
+    copy_to_user(buf_ptr, &text[..written])?;
+    Ok(written)
+}
+
+/// Muda o nível mínimo de log
+///
+/// # Args
+/// - subsystem: `klog::Subsystem` como número, ou `ALL_SUBSYSTEMS` para o
+///   limite global
+/// - level: nível mínimo (0 = trace, ..., 4 = error), ou `INHERIT` para o
+///   subsistema voltar a seguir o global
+///
+/// # Returns
+/// 0 ou erro
+pub fn sys_klog_set_level(subsystem: u32, level: u32) -> SysResult<usize> {
+    if subsystem == kf::ALL_SUBSYSTEMS {
+        klog::set_level(parse_level(level)?);
+        return Ok(0);
+    }
+
+    let subsystem = u8::try_from(subsystem)
+        .ok()
+        .and_then(Subsystem::from_u8)
+        .ok_or(SysError::InvalidArgument)?;
+    let level = match level {
+        kf::INHERIT => None,
+        level => Some(parse_level(level)?),
+    };
+    klog::set_subsystem_level(subsystem, level);
+    Ok(0)
+}
+
+fn parse_level(level: u32) -> SysResult<Level> {
+    u8::try_from(level)
+        .ok()
+        .and_then(Level::from_u8)
+        .ok_or(SysError::InvalidArgument)
+}
@@ -3,5 +3,7 @@
 //! Informações do sistema e debug.
 
 pub mod info;
+pub mod klog;
 
 pub use info::*;
+pub use klog::*;
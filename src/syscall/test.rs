@@ -0,0 +1,245 @@
+//! # Testes de syscalls
+//!
+//! Executados apenas com a feature `self_test`, com o tick ligado.
+
+use crate::core::debug::klog::{self, Level, Subsystem};
+use crate::core::debug::kmsg;
+use crate::core::time::jiffies::get_jiffies;
+use crate::mm::aspace::AddressSpace;
+use crate::mm::config::PAGE_SIZE;
+use crate::sched::core::{exit_current, yield_now, CURRENT};
+use crate::sched::test::spawn_kernel_task;
+use crate::sync::Spinlock;
+use crate::syscall::memory::mmap::{
+    sys_mmap, sys_mprotect, sys_munmap, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+};
+use crate::syscall::system::{collect_sysinfo, KERNEL_VERSION};
+use crate::syscall::uaccess::{copy_from_user, copy_to_user};
+use crate::syscall::SysError;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub fn run_tests() {
+    crate::kinfo!("(Syscall) Iniciando testes de syscalls...");
+    test_sysinfo_counters();
+    test_klog_read();
+    test_klog_level_filter();
+    run_in_aspace("syscall-test-mmap", test_mmap_write_read_back);
+    run_in_aspace("syscall-test-mprotect", test_mprotect_read_only);
+    crate::kinfo!("(Syscall) Testes de syscalls concluídos com SUCESSO.");
+}
+
+/// `sysinfo` reporta uptime crescente, a RAM livre do PFM e a task atual.
+fn test_sysinfo_counters() {
+    let first = collect_sysinfo();
+    let start = get_jiffies();
+    while get_jiffies() < start + 2 {
+        yield_now();
+    }
+
+    let frame = crate::mm::config::PAGE_SIZE as u64;
+    let free_before = crate::mm::pfm::get().lock().stats().free_frames * frame;
+    let info = collect_sysinfo();
+    let free_after = crate::mm::pfm::get().lock().stats().free_frames * frame;
+
+    assert!(info.uptime_ns > 0, "(Syscall) Uptime zerado");
+    assert!(info.uptime_ns > first.uptime_ns, "(Syscall) Uptime parado");
+    // Outras tasks podem alocar entre as leituras
+    let (low, high) = (free_before.min(free_after), free_before.max(free_after));
+    assert!(
+        (low..=high).contains(&info.free_ram),
+        "(Syscall) RAM livre diverge do PFM"
+    );
+    assert!(info.free_ram <= info.total_ram);
+    assert!(info.processes >= 1);
+    assert_eq!(info.version_str(), KERNEL_VERSION);
+}
+
+/// Linhas emitidas pelas macros aparecem na leitura do log, com nível e
+/// respeitando o filtro.
+fn test_klog_read() {
+    crate::kinfo!("(Syscall) marcador klog", 0x42u64);
+    crate::kwarn!("(Syscall) marcador klog aviso");
+
+    let all = klog_text();
+    assert!(
+        all.contains("[INFO]  (Syscall) marcador klog 0x0000000000000042\n"),
+        "(Syscall) Linha de info ausente no log"
+    );
+    assert!(all.contains("[WARN]  (Syscall) marcador klog aviso\n"));
+
+    let mut text = alloc::vec![0u8; kmsg::capacity()];
+    let len = kmsg::read(&mut text, Level::Warn);
+    let warn = core::str::from_utf8(&text[..len]).unwrap_or("");
+    assert!(warn.contains("marcador klog aviso"));
+    assert!(
+        !warn.contains("[INFO]"),
+        "(Syscall) Filtro de nível não aplicado"
+    );
+
+    // Buffer curto: só a linha mais recente inteira
+    let mut short = [0u8; 64];
+    let len = kmsg::read(&mut short, Level::Trace);
+    assert!(short[..len].ends_with(b"\n"));
+}
+
+/// Log do kernel retido, como texto
+fn klog_text() -> alloc::string::String {
+    let mut text = alloc::vec![0u8; kmsg::capacity()];
+    let len = kmsg::read(&mut text, Level::Trace);
+    alloc::string::String::from_utf8_lossy(&text[..len]).into_owned()
+}
+
+/// `kdebug!` abaixo do limite não chega ao log; o override de um
+/// subsistema libera só as linhas dele.
+fn test_klog_level_filter() {
+    let previous = klog::level();
+    klog::set_level(Level::Info);
+
+    crate::kdebug!("(Syscall) marcador nivel global");
+    crate::kdebug!(Syscall: "(Syscall) marcador nivel antes");
+    klog::set_subsystem_level(Subsystem::Syscall, Some(Level::Debug));
+    crate::kdebug!(Syscall: "(Syscall) marcador nivel override");
+    crate::kdebug!("(Syscall) marcador nivel ainda global");
+    assert!(!klog::enabled(Subsystem::Kernel, Level::Debug));
+    assert!(klog::enabled(Subsystem::Syscall, Level::Debug));
+    assert!(!klog::enabled(Subsystem::Syscall, Level::Trace));
+
+    klog::set_subsystem_level(Subsystem::Syscall, None);
+    klog::set_level(previous);
+
+    let text = klog_text();
+    assert!(
+        !text.contains("marcador nivel global") && !text.contains("marcador nivel antes"),
+        "(Syscall) kdebug abaixo do limite chegou ao log"
+    );
+    assert!(!text.contains("marcador nivel ainda global"));
+    // `kdebug!` só é compilado em builds debug
+    if cfg!(debug_assertions) {
+        assert!(
+            text.contains("[DEBUG] (Syscall) marcador nivel override"),
+            "(Syscall) Override de subsistema ignorado"
+        );
+    }
+}
+
+/// Corpo a rodar na task de teste com address space próprio
+static ASPACE_BODY: Spinlock<Option<fn()>> = Spinlock::new(None);
+static ASPACE_DONE: AtomicBool = AtomicBool::new(false);
+
+fn set_current_aspace(aspace: Option<Arc<Spinlock<AddressSpace>>>) {
+    if let Some(task) = CURRENT.lock().as_mut() {
+        task.aspace = aspace;
+    }
+}
+
+extern "C" fn aspace_task() -> ! {
+    let body = ASPACE_BODY
+        .lock()
+        .take()
+        .expect("(Syscall) Task de teste sem corpo");
+    let aspace = Arc::new(Spinlock::new(
+        AddressSpace::new(0).expect("(Syscall) Falha ao criar address space"),
+    ));
+    set_current_aspace(Some(aspace.clone()));
+    // SAFETY: PML4 nova, com a metade do kernel copiada
+    unsafe { aspace.lock().activate() };
+
+    body();
+
+    // De volta ao CR3 do kernel antes de largar o address space
+    set_current_aspace(None);
+    let kernel_cr3 = crate::mm::vmm::vmm::KERNEL_CR3.load(Ordering::SeqCst);
+    // SAFETY: CR3 do boot, com a metade do kernel
+    unsafe { crate::arch::Cpu::write_cr3(kernel_cr3) };
+    drop(aspace);
+
+    ASPACE_DONE.store(true, Ordering::SeqCst);
+    exit_current(0);
+}
+
+/// Roda `body` numa task do kernel com um address space próprio ativo, como
+/// um processo: as syscalls de memória e as cópias de/para usuário atuam
+/// nele, e as faltas são resolvidas pelas VMAs dele.
+fn run_in_aspace(name: &str, body: fn()) {
+    *ASPACE_BODY.lock() = Some(body);
+    ASPACE_DONE.store(false, Ordering::SeqCst);
+    spawn_kernel_task(name, aspace_task);
+    while !ASPACE_DONE.load(Ordering::SeqCst) {
+        yield_now();
+    }
+}
+
+/// Uma região do `mmap` anônimo guarda o que se escreve nela; depois do
+/// `munmap`, some.
+fn test_mmap_write_read_back() {
+    const SIZE: usize = 4 * PAGE_SIZE;
+    let rw = PROT_READ | PROT_WRITE;
+    let anon = MAP_PRIVATE | MAP_ANONYMOUS;
+
+    let addr = sys_mmap(0, SIZE, rw, anon).expect("(Syscall) mmap falhou");
+    assert_eq!(addr % PAGE_SIZE, 0);
+
+    // Escrita e leitura como userspace: as páginas vêm sob demanda
+    let pattern: alloc::vec::Vec<u8> = (0..SIZE).map(|i| (i % 251) as u8).collect();
+    copy_to_user(addr, &pattern).expect("(Syscall) Escrita na região do mmap falhou");
+    let mut back = alloc::vec![0u8; SIZE];
+    copy_from_user(&mut back, addr).expect("(Syscall) Leitura da região do mmap falhou");
+    assert!(back == pattern, "(Syscall) mmap não guardou os dados");
+
+    // Endereço desalinhado
+    assert_eq!(
+        sys_mmap(addr + 1, SIZE, rw, anon),
+        Err(SysError::InvalidArgument)
+    );
+    assert_eq!(sys_munmap(addr + 1, SIZE), Err(SysError::InvalidArgument));
+
+    assert_eq!(sys_munmap(addr, SIZE), Ok(0));
+    assert!(copy_from_user(&mut back, addr).is_err());
+    assert_eq!(crate::mm::translate_addr(addr as u64), None);
+}
+
+/// Uma página passada de RW a somente-leitura pelo `mprotect` faz a escrita
+/// seguinte falhar, sem perder os dados; de volta a RW, a escrita volta.
+fn test_mprotect_read_only() {
+    use crate::mm::fault::{handle_page_fault, FaultResult, PageFaultInfo};
+    use crate::mm::fault::{PF_PRESENT, PF_USER, PF_WRITE};
+    use crate::mm::{translate_with_flags, MapFlags};
+
+    let rw = PROT_READ | PROT_WRITE;
+    let addr =
+        sys_mmap(0, 2 * PAGE_SIZE, rw, MAP_PRIVATE | MAP_ANONYMOUS).expect("(Syscall) mmap falhou");
+    let data = [0xA5u8; 64];
+    copy_to_user(addr, &data).expect("(Syscall) Escrita antes do mprotect falhou");
+    copy_to_user(addr + PAGE_SIZE, &data).expect("(Syscall) Escrita antes do mprotect falhou");
+
+    assert_eq!(sys_mprotect(addr, PAGE_SIZE, PROT_READ), Ok(0));
+
+    // A PTE perdeu o W; a página vizinha, fora do intervalo, não
+    let writable = |addr: usize| {
+        let (_, flags) = translate_with_flags(addr as u64).expect("(Syscall) Página sumiu");
+        flags.contains(MapFlags::WRITABLE)
+    };
+    assert!(!writable(addr), "(Syscall) mprotect não tirou o W da PTE");
+    assert!(writable(addr + PAGE_SIZE));
+
+    // Escrita de usuário na página: a falta não tem conserto
+    let fault = PageFaultInfo::from_error_code(addr as u64, 0, PF_PRESENT | PF_WRITE | PF_USER);
+    assert_eq!(handle_page_fault(fault), FaultResult::ProtectionViolation);
+    assert!(copy_to_user(addr, &data).is_err());
+    let mut back = [0u8; 64];
+    copy_from_user(&mut back, addr).expect("(Syscall) Leitura depois do mprotect falhou");
+    assert!(back == data, "(Syscall) mprotect perdeu os dados");
+
+    // Intervalo que passa do fim da região
+    assert_eq!(
+        sys_mprotect(addr, 3 * PAGE_SIZE, PROT_READ),
+        Err(SysError::InvalidArgument)
+    );
+
+    assert_eq!(sys_mprotect(addr, PAGE_SIZE, rw), Ok(0));
+    assert!(writable(addr));
+    copy_to_user(addr, &[0x5Au8; 64]).expect("(Syscall) Escrita depois de voltar a RW falhou");
+
+    assert_eq!(sys_munmap(addr, 2 * PAGE_SIZE), Ok(0));
+}
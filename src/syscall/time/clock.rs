@@ -5,6 +5,7 @@
 use crate::syscall::abi::types::{ClockId, TimeSpec};
 use crate::syscall::abi::SyscallArgs;
 use crate::syscall::error::{SysError, SysResult};
+use crate::syscall::uaccess::UserAccessGuard;
 
 // === WRAPPERS ===
 
@@ -47,19 +48,11 @@ pub fn sys_clock_get(clock_id: u32, out_ptr: usize) -> SysResult<usize> {
     // Obter tempo
     let time = match clock {
         ClockId::Monotonic => {
-            let ticks = crate::drivers::timer::ticks();
-            let freq = crate::drivers::timer::frequency() as u64;
-            if freq == 0 {
-                TimeSpec::zero()
-            } else {
-                let seconds = ticks / freq;
-                let remaining_ticks = ticks % freq;
-                let nanoseconds = (remaining_ticks * 1_000_000_000) / freq;
-                TimeSpec {
-                    seconds,
-                    nanoseconds: nanoseconds as u32,
-                    _pad: 0,
-                }
+            let ns = crate::core::time::now_ns();
+            TimeSpec {
+                seconds: ns / 1_000_000_000,
+                nanoseconds: (ns % 1_000_000_000) as u32,
+                _pad: 0,
             }
         }
         _ => {
@@ -72,6 +65,7 @@ pub fn sys_clock_get(clock_id: u32, out_ptr: usize) -> SysResult<usize> {
     // TODO: Validar ponteiro e usar copy_to_user
     if out_ptr != 0 {
         unsafe {
+            let _uaccess = UserAccessGuard::new();
             let out = out_ptr as *mut TimeSpec;
             *out = time;
         }
@@ -91,11 +85,12 @@ pub fn sys_sleep(ms: u64) -> SysResult<usize> {
     if ms == 0 {
         return Ok(0);
     }
+    let ns = ms.checked_mul(1_000_000).ok_or(SysError::InvalidArgument)?;
 
-    // Usar o scheduler para colocar a task em estado dormente
-    crate::sched::core::sleep_current(ms);
+    // Prazo na roda de timers; a task fica bloqueada no scheduler até lá
+    let remaining_ns = crate::core::time::sleep_ns(ns);
 
-    Ok(0)
+    Ok(remaining_ns.div_ceil(1_000_000) as usize)
 }
 
 /// Cria um timer do sistema
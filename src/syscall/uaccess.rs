@@ -0,0 +1,122 @@
+//! # Acesso a memória de usuário
+//!
+//! Validação de buffers recebidos de userspace antes de o kernel tocá-los.
+//! Um buffer só é aceito se estiver inteiro na metade baixa canônica e
+//! coberto por VMAs do address space da task atual que permitam o acesso.
+//!
+//! Com SMAP ligado, o kernel só toca memória de usuário dentro de um
+//! [`UserAccessGuard`]: as cópias daqui já o usam; acesso direto a um
+//! ponteiro de usuário precisa de um guard em volta (e só da cópia).
+
+pub use crate::arch::platform::smap::UserAccessGuard;
+
+use crate::mm::fault::AccessType;
+use crate::mm::VirtAddr;
+use crate::syscall::error::{SysError, SysResult};
+
+/// Fim (exclusivo) do espaço de usuário em x86_64
+pub const USER_SPACE_END: usize = 0x0000_8000_0000_0000;
+
+/// Verifica apenas os limites de `[ptr, ptr + len)` (não nulo, sem overflow,
+/// abaixo de `USER_SPACE_END`).
+pub fn check_user_range(ptr: usize, len: usize) -> SysResult<()> {
+    if ptr == 0 {
+        return Err(SysError::InvalidArgument);
+    }
+    let end = ptr.checked_add(len).ok_or(SysError::InvalidArgument)?;
+    if end > USER_SPACE_END {
+        return Err(SysError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Valida `[ptr, ptr + len)` contra o address space da task atual.
+///
+/// Retorna `InvalidArgument` se o intervalo sair do espaço de usuário, se a
+/// task não tiver address space ou se alguma parte não estiver coberta por
+/// uma VMA que permita `access`.
+pub fn validate_user_buffer(ptr: usize, len: usize, access: AccessType) -> SysResult<()> {
+    check_user_range(ptr, len)?;
+    if len == 0 {
+        return Ok(());
+    }
+
+    let aspace = {
+        let guard = crate::sched::core::CURRENT.lock();
+        guard.as_ref().and_then(|task| task.aspace.clone())
+    }
+    .ok_or(SysError::InvalidArgument)?;
+    let aspace = aspace.lock();
+
+    let end = (ptr + len) as u64;
+    let mut addr = ptr as u64;
+    while addr < end {
+        let vma = aspace
+            .find_vma(VirtAddr::new(addr))
+            .ok_or(SysError::InvalidArgument)?;
+        if !vma.protection.permits(access) {
+            return Err(SysError::InvalidArgument);
+        }
+        addr = vma.end.as_u64();
+    }
+    Ok(())
+}
+
+/// Copia `dst.len()` bytes de `[src, src + dst.len())` do usuário.
+///
+/// O intervalo é validado contra as VMAs da task atual antes da cópia;
+/// páginas ainda não populadas são resolvidas pelo page-fault handler.
+pub fn copy_from_user(dst: &mut [u8], src: usize) -> SysResult<()> {
+    validate_user_buffer(src, dst.len(), AccessType::Read)?;
+    let _uaccess = UserAccessGuard::new();
+    // SAFETY: intervalo validado; o CR3 ativo é o da task atual
+    unsafe {
+        core::ptr::copy_nonoverlapping(src as *const u8, dst.as_mut_ptr(), dst.len());
+    }
+    Ok(())
+}
+
+/// Copia `src` para `[dst, dst + src.len())` do usuário.
+///
+/// Mesma validação de [`copy_from_user`], exigindo VMAs graváveis.
+pub fn copy_to_user(dst: usize, src: &[u8]) -> SysResult<()> {
+    validate_user_buffer(dst, src.len(), AccessType::Write)?;
+    let _uaccess = UserAccessGuard::new();
+    // SAFETY: intervalo validado; o CR3 ativo é o da task atual
+    unsafe {
+        core::ptr::copy_nonoverlapping(src.as_ptr(), dst as *mut u8, src.len());
+    }
+    Ok(())
+}
+
+/// Lê um `u64` do usuário (sem exigir alinhamento)
+pub fn read_user_u64(src: usize) -> SysResult<u64> {
+    let mut bytes = [0u8; 8];
+    copy_from_user(&mut bytes, src)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_user_range() {
+        assert_eq!(check_user_range(0x1000, 16), Ok(()));
+        assert_eq!(check_user_range(0, 16), Err(SysError::InvalidArgument));
+        assert_eq!(
+            check_user_range(usize::MAX - 4, 16),
+            Err(SysError::InvalidArgument)
+        );
+        assert_eq!(
+            check_user_range(0xFFFF_8000_0000_0000, 1),
+            Err(SysError::InvalidArgument)
+        );
+        // Termina exatamente no limite
+        assert_eq!(check_user_range(USER_SPACE_END - 8, 8), Ok(()));
+    }
+}